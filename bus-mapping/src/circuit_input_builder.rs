@@ -13,6 +13,7 @@ mod transaction;
 use self::access::gen_state_access_trace;
 use crate::error::Error;
 use crate::evm::opcodes::{gen_associated_ops, gen_begin_tx_ops, gen_end_tx_ops};
+use crate::mpt;
 use crate::operation::{CallContextField, RW};
 use crate::rpc::GethClient;
 use crate::state_db::{self, CodeDB, StateDB};
@@ -20,10 +21,19 @@ pub use access::{Access, AccessSet, AccessValue, CodeSource};
 pub use block::{Block, BlockContext};
 pub use call::{Call, CallContext, CallKind};
 use core::fmt::Debug;
-use eth_types::{self, Address, GethExecStep, GethExecTrace, Word};
+use eth_types::{
+    self, evm_types::NUM_PREV_BLOCK_ALLOWED, Address, GethExecStep, GethExecTrace, ToWord, Word,
+    H256,
+};
+use ethers_core::utils::keccak256;
 use ethers_providers::JsonRpcClient;
-pub use execution::{CopyDataType, CopyEvent, CopyStep, ExecState, ExecStep, NumberOrHash};
+pub use execution::{
+    CopyDataType, CopyEvent, CopyStep, ExecState, ExecStep, ExpEvent, ExpStep, NumberOrHash,
+};
+use futures::stream::{self, StreamExt, TryStreamExt};
 pub use input_state_ref::CircuitInputStateRef;
+use keccak256::EMPTY_HASH;
+use rlp::RlpStream;
 use std::collections::HashMap;
 pub use transaction::{Transaction, TransactionContext};
 
@@ -193,10 +203,17 @@ impl<'a> CircuitInputBuilder {
 }
 
 /// Retrieve the init_code from memory for {CREATE, CREATE2}
-pub fn get_create_init_code(step: &GethExecStep) -> Result<&[u8], Error> {
+pub fn get_create_init_code(step: &GethExecStep) -> Result<Vec<u8>, Error> {
     let offset = step.stack.nth_last(1)?;
     let length = step.stack.nth_last(2)?;
-    Ok(&step.memory.0[offset.low_u64() as usize..(offset.low_u64() + length.low_u64()) as usize])
+    if length.is_zero() {
+        return Ok(Vec::new());
+    }
+    // Memory is not yet expanded in the CREATE step, so the init code is
+    // padded with zeros when it reads beyond the current memory size.
+    Ok(step
+        .memory
+        .read_chunk(offset.low_u64().into(), length.low_u64().into()))
 }
 
 /// Retrieve the memory offset and length of call.
@@ -212,13 +229,71 @@ pub fn get_call_memory_offset_length(step: &GethExecStep, nth: usize) -> Result<
 
 type EthBlock = eth_types::Block<eth_types::Transaction>;
 
+/// Maximum number of concurrent JSON-RPC requests sent by [`BuilderClient`].
+const MAX_CONCURRENT_REQUESTS: usize = 16;
+
+/// Return the RLP encoding of the header of `block`, whose keccak hash is the
+/// block hash.
+pub fn header_rlp<TX>(block: &eth_types::Block<TX>) -> Result<Vec<u8>, Error> {
+    let incomplete = || Error::EthTypeError(eth_types::Error::IncompleteBlock);
+    let number = block.number.ok_or_else(incomplete)?;
+    let logs_bloom = block.logs_bloom.ok_or_else(incomplete)?;
+    let mix_hash = block.mix_hash.ok_or_else(incomplete)?;
+    let nonce = block.nonce.ok_or_else(incomplete)?;
+
+    let mut stream = RlpStream::new_list(15 + block.base_fee_per_gas.is_some() as usize);
+    stream.append(&block.parent_hash.as_bytes());
+    stream.append(&block.uncles_hash.as_bytes());
+    stream.append(&block.author.as_bytes());
+    stream.append(&block.state_root.as_bytes());
+    stream.append(&block.transactions_root.as_bytes());
+    stream.append(&block.receipts_root.as_bytes());
+    stream.append(&logs_bloom.as_bytes());
+    mpt::append_word(&mut stream, block.difficulty);
+    mpt::append_word(&mut stream, number.as_u64().into());
+    mpt::append_word(&mut stream, block.gas_limit);
+    mpt::append_word(&mut stream, block.gas_used);
+    mpt::append_word(&mut stream, block.timestamp);
+    stream.append(&block.extra_data.as_ref());
+    stream.append(&mix_hash.as_bytes());
+    // The nonce is encoded as a fixed length 8 bytes string.
+    stream.append(&&nonce.as_u64().to_be_bytes()[..]);
+    if let Some(base_fee_per_gas) = block.base_fee_per_gas {
+        mpt::append_word(&mut stream, base_fee_per_gas);
+    }
+    Ok(stream.out().to_vec())
+}
+
+/// Return the block hash of `block`, which is the keccak hash of the RLP
+/// encoding of its header.
+fn header_hash(block: &EthBlock) -> Result<H256, Error> {
+    Ok(H256(keccak256(&header_rlp(block)?)))
+}
+
+/// Check that `headers` link to each other and to `eth_block` by parent
+/// hash, and return their block hashes computed from the headers.
+fn verify_history_headers(eth_block: &EthBlock, headers: &[EthBlock]) -> Result<Vec<Word>, Error> {
+    let mut history_hashes = Vec::with_capacity(headers.len());
+    let children = headers.iter().skip(1).chain(std::iter::once(eth_block));
+    for (header, child) in headers.iter().zip(children) {
+        let hash = header_hash(header)?;
+        if child.parent_hash != hash {
+            let number = child
+                .number
+                .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?;
+            return Err(Error::ParentHashMismatch(number.as_u64()));
+        }
+        history_hashes.push(hash.to_word());
+    }
+    Ok(history_hashes)
+}
+
 /// Struct that wraps a GethClient and contains methods to perform all the steps
 /// necessary to generate the circuit inputs for a block by querying geth for
 /// the necessary information and using the CircuitInputBuilder.
 pub struct BuilderClient<P: JsonRpcClient> {
     cli: GethClient<P>,
     chain_id: Word,
-    history_hashes: Vec<Word>,
 }
 
 impl<P: JsonRpcClient> BuilderClient<P> {
@@ -229,8 +304,6 @@ impl<P: JsonRpcClient> BuilderClient<P> {
         Ok(Self {
             cli: client,
             chain_id: chain_id.into(),
-            // TODO: Get history hashes
-            history_hashes: Vec::new(),
         })
     }
 
@@ -244,6 +317,40 @@ impl<P: JsonRpcClient> BuilderClient<P> {
         Ok((eth_block, geth_traces))
     }
 
+    /// Query geth for the headers of the most recent 256 blocks before
+    /// `eth_block`, where the latest one is at the end.  The headers are
+    /// requested concurrently and are not verified.
+    pub async fn get_history_headers(&self, eth_block: &EthBlock) -> Result<Vec<EthBlock>, Error> {
+        let block_num = eth_block
+            .number
+            .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?
+            .as_u64();
+        stream::iter(block_num.saturating_sub(NUM_PREV_BLOCK_ALLOWED)..block_num)
+            .map(|number| self.cli.get_block_by_number(number.into()))
+            .buffered(MAX_CONCURRENT_REQUESTS)
+            .try_collect()
+            .await
+    }
+
+    /// Query geth for the hashes of the most recent 256 blocks before
+    /// `eth_block`, where the latest one is at the end, and the state root of
+    /// the previous block.  The hashes are computed from the headers, which
+    /// are checked to link to each other and to `eth_block` by parent hash.
+    pub async fn get_history_hashes(
+        &self,
+        eth_block: &EthBlock,
+    ) -> Result<(Vec<Word>, H256), Error> {
+        let headers = self.get_history_headers(eth_block).await?;
+        let history_hashes = verify_history_headers(eth_block, &headers)?;
+        let prev_state_root = headers
+            .last()
+            .ok_or_else(|| {
+                Error::ParentBlockNotFound(eth_block.number.unwrap_or_default().as_u64())
+            })?
+            .state_root;
+        Ok((history_hashes, prev_state_root))
+    }
+
     /// Step 2. Get State Accesses from TxExecTraces
     pub fn get_state_accesses(
         &self,
@@ -267,10 +374,13 @@ impl<P: JsonRpcClient> BuilderClient<P> {
     }
 
     /// Step 3. Query geth for all accounts, storage keys, and codes from
-    /// Accesses
+    /// Accesses.  The account and storage proofs are verified against
+    /// `prev_state_root`, the state root of the previous block, and the codes
+    /// against the code hashes of the verified accounts.
     pub async fn get_state(
         &self,
         block_num: u64,
+        prev_state_root: H256,
         access_set: AccessSet,
     ) -> Result<
         (
@@ -280,23 +390,39 @@ impl<P: JsonRpcClient> BuilderClient<P> {
         Error,
     > {
         let mut proofs = Vec::new();
+        let mut code_hashes = HashMap::new();
         for (address, key_set) in access_set.state {
             let mut keys: Vec<Word> = key_set.iter().cloned().collect();
             keys.sort();
             let proof = self
                 .cli
                 .get_proof(address, keys, (block_num - 1).into())
-                .await
-                .unwrap();
+                .await?;
+            mpt::verify_proof(prev_state_root, &proof)?;
+            code_hashes.insert(address, proof.code_hash);
             proofs.push(proof);
         }
         let mut codes: HashMap<Address, Vec<u8>> = HashMap::new();
         for address in access_set.code {
-            let code = self
-                .cli
-                .get_code(address, (block_num - 1).into())
-                .await
-                .unwrap();
+            let code_hash = match code_hashes.get(&address) {
+                Some(code_hash) => *code_hash,
+                None => {
+                    let proof = self
+                        .cli
+                        .get_proof(address, Vec::new(), (block_num - 1).into())
+                        .await?;
+                    mpt::verify_proof(prev_state_root, &proof)?;
+                    let code_hash = proof.code_hash;
+                    proofs.push(proof);
+                    code_hash
+                }
+            };
+            let code = self.cli.get_code(address, (block_num - 1).into()).await?;
+            // Geth returns either zero or the empty hash as the code hash of
+            // an absent account.
+            if H256(keccak256(&code)) != code_hash && !(code.is_empty() && code_hash.is_zero()) {
+                return Err(Error::CodeHashMismatch(address));
+            }
             codes.insert(address, code);
         }
         Ok((proofs, codes))
@@ -320,7 +446,13 @@ impl<P: JsonRpcClient> BuilderClient<P> {
                     nonce: proof.nonce,
                     balance: proof.balance,
                     storage,
-                    code_hash: proof.code_hash,
+                    // Geth returns either zero or the empty hash as the code
+                    // hash of an absent account.
+                    code_hash: if proof.code_hash.is_zero() {
+                        H256(*EMPTY_HASH)
+                    } else {
+                        proof.code_hash
+                    },
                 },
             )
         }
@@ -338,10 +470,11 @@ impl<P: JsonRpcClient> BuilderClient<P> {
         &self,
         sdb: StateDB,
         code_db: CodeDB,
+        history_hashes: Vec<Word>,
         eth_block: &EthBlock,
         geth_traces: &[eth_types::GethExecTrace],
     ) -> Result<CircuitInputBuilder, Error> {
-        let block = Block::new(self.chain_id, self.history_hashes.clone(), eth_block)?;
+        let block = Block::new(self.chain_id, history_hashes, eth_block)?;
         let mut builder = CircuitInputBuilder::new(sdb, code_db, block);
         builder.handle_block(eth_block, geth_traces)?;
         Ok(builder)
@@ -350,10 +483,133 @@ impl<P: JsonRpcClient> BuilderClient<P> {
     /// Perform all the steps to generate the circuit inputs
     pub async fn gen_inputs(&self, block_num: u64) -> Result<CircuitInputBuilder, Error> {
         let (eth_block, geth_traces) = self.get_block(block_num).await?;
+        let (history_hashes, prev_state_root) = self.get_history_hashes(&eth_block).await?;
         let access_set = self.get_state_accesses(&eth_block, &geth_traces)?;
-        let (proofs, codes) = self.get_state(block_num, access_set).await?;
+        let (proofs, codes) = self
+            .get_state(block_num, prev_state_root, access_set)
+            .await?;
+        let trie_nodes = proofs
+            .iter()
+            .flat_map(|proof| {
+                proof.account_proof.iter().chain(
+                    proof
+                        .storage_proof
+                        .iter()
+                        .flat_map(|storage_proof| storage_proof.proof.iter()),
+                )
+            })
+            .map(|node| node.to_vec())
+            .collect();
         let (state_db, code_db) = self.build_state_code_db(proofs, codes);
-        let builder = self.gen_inputs_from_state(state_db, code_db, &eth_block, &geth_traces)?;
+        let mut builder = self.gen_inputs_from_state(
+            state_db,
+            code_db,
+            history_hashes,
+            &eth_block,
+            &geth_traces,
+        )?;
+        builder.block.prev_state_root = prev_state_root.to_word();
+        builder.block.trie_nodes = trie_nodes;
         Ok(builder)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth_types::{address, U64};
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    fn h256(hex: &str) -> H256 {
+        H256::from_str(hex).expect("invalid hex H256")
+    }
+
+    /// Header of the mainnet block 1.
+    fn mainnet_block_1() -> EthBlock {
+        EthBlock {
+            parent_hash: h256("d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"),
+            uncles_hash: h256("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347"),
+            author: address!("0x05a56e2d52c817161883f50c441c3228cfe54d9f"),
+            state_root: h256("d67e4d450343046425ae4271474353857ab860dbc0a1dde64b41b5cd3a532bf3"),
+            transactions_root: h256(
+                "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+            ),
+            receipts_root: h256("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"),
+            logs_bloom: Some(Default::default()),
+            difficulty: Word::from(0x3ff800000u64),
+            number: Some(U64::from(1)),
+            gas_limit: Word::from(5000),
+            gas_used: Word::zero(),
+            timestamp: Word::from(0x55ba4224),
+            extra_data: hex::decode("476574682f76312e302e302f6c696e75782f676f312e342e32")
+                .unwrap()
+                .into(),
+            mix_hash: Some(h256(
+                "969b900de27b6ac6a67742365dd65f55a0526c41fd18e1b16f1a1215c2e66f59",
+            )),
+            nonce: Some(U64::from(0x539bd4979fef1ec4u64)),
+            ..Default::default()
+        }
+    }
+
+    /// Return a chain of `n` headers starting at `first`, linked by parent
+    /// hash.
+    fn header_chain(first: EthBlock, n: usize) -> Vec<EthBlock> {
+        let mut headers = vec![first];
+        while headers.len() < n {
+            let parent = headers.last().unwrap();
+            let header = EthBlock {
+                parent_hash: header_hash(parent).unwrap(),
+                number: parent.number.map(|number| number + 1),
+                base_fee_per_gas: Some(Word::from(7)),
+                ..parent.clone()
+            };
+            headers.push(header);
+        }
+        headers
+    }
+
+    #[test]
+    fn header_hash_mainnet() {
+        assert_eq!(
+            header_hash(&mainnet_block_1()).unwrap(),
+            h256("88e96d4537bea4d9c05d12549907b32561d3bf31f45aae734cdc119f13406cb6")
+        );
+    }
+
+    #[test]
+    fn history_headers_linked() {
+        let mut headers = header_chain(mainnet_block_1(), 4);
+        let eth_block = headers.pop().unwrap();
+        let history_hashes = verify_history_headers(&eth_block, &headers).unwrap();
+        assert_eq!(
+            history_hashes,
+            headers
+                .iter()
+                .map(|header| header_hash(header).unwrap().to_word())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(verify_history_headers(&eth_block, &[]).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn history_headers_parent_hash_mismatch() {
+        let mut headers = header_chain(mainnet_block_1(), 4);
+        let eth_block = headers.pop().unwrap();
+
+        // A header whose reported hash is correct but whose content is not
+        let mut tampered = headers.clone();
+        tampered[1].state_root = H256::zero();
+        assert!(matches!(
+            verify_history_headers(&eth_block, &tampered),
+            Err(Error::ParentHashMismatch(3))
+        ));
+
+        // The last header doesn't link to the block
+        assert!(matches!(
+            verify_history_headers(&eth_block, &headers[..2]),
+            Err(Error::ParentHashMismatch(4))
+        ));
+    }
+}
@@ -1,11 +1,12 @@
 //! Block-related utility module
 
-use super::{transaction::Transaction, CopyEvent};
+use super::{header_rlp, transaction::Transaction, CopyEvent, ExpEvent};
 use crate::{
     operation::{OperationContainer, RWCounter},
     Error,
 };
-use eth_types::{Address, Hash, Word};
+use eth_types::{Address, Hash, ToWord, Word};
+use ethers_core::utils::keccak256;
 use std::collections::HashMap;
 
 /// Context of a [`Block`] which can mutate in a [`Transaction`].
@@ -59,12 +60,31 @@ pub struct Block {
     pub difficulty: Word,
     /// base fee
     pub base_fee: Word,
+    /// hash of the block, which is the keccak hash of `header_rlp`
+    pub hash: Word,
+    /// RLP encoding of the block header
+    pub header_rlp: Vec<u8>,
+    /// hash of the parent block
+    pub parent_hash: Word,
+    /// state root after the block
+    pub state_root: Word,
+    /// state root before the block, which is the state root of the parent
+    /// block
+    pub prev_state_root: Word,
+    /// RLP encoded nodes of the state and storage tries at
+    /// `prev_state_root` which are known, which include the nodes of the
+    /// paths of all the accounts and storage slots accessed in the block.
+    pub trie_nodes: Vec<Vec<u8>>,
     /// Container of operations done in this block.
     pub container: OperationContainer,
     /// Transactions contained in the block
     pub txs: Vec<Transaction>,
     /// Copy events in this block.
     pub copy_events: Vec<CopyEvent>,
+    /// Exponentiation events in this block.
+    pub exp_events: Vec<ExpEvent>,
+    /// Inputs to the SHA3 opcode in this block.
+    pub sha3_inputs: Vec<Vec<u8>>,
     code: HashMap<Hash, Vec<u8>>,
 }
 
@@ -82,6 +102,7 @@ impl Block {
             );
         }
 
+        let header_rlp = header_rlp(eth_block)?;
         Ok(Self {
             chain_id,
             history_hashes,
@@ -95,9 +116,17 @@ impl Block {
             timestamp: eth_block.timestamp,
             difficulty: eth_block.difficulty,
             base_fee: eth_block.base_fee_per_gas.unwrap_or_default(),
+            hash: Word::from_big_endian(&keccak256(&header_rlp)),
+            header_rlp,
+            parent_hash: eth_block.parent_hash.to_word(),
+            state_root: eth_block.state_root.to_word(),
+            prev_state_root: Word::zero(),
+            trie_nodes: Vec::new(),
             container: OperationContainer::new(),
             txs: Vec::new(),
             copy_events: Vec::new(),
+            exp_events: Vec::new(),
+            sha3_inputs: Vec::new(),
             code: HashMap::new(),
         })
     }
@@ -118,4 +147,14 @@ impl Block {
     pub fn add_copy_event(&mut self, copy: CopyEvent) {
         self.copy_events.push(copy);
     }
+
+    /// Push an exponentiation event to the block.
+    pub fn add_exp_event(&mut self, event: ExpEvent) {
+        self.exp_events.push(event);
+    }
+
+    /// Push a SHA3 input to the block.
+    pub fn add_sha3_input(&mut self, input: Vec<u8>) {
+        self.sha3_inputs.push(input);
+    }
 }
@@ -86,6 +86,13 @@ pub struct Call {
     pub return_data_offset: u64,
     /// Return data length
     pub return_data_length: u64,
+    /// Last callee's id, which is `0` if no callee has been executed yet or
+    /// the last callee has no code.
+    pub last_callee_id: usize,
+    /// Last callee's return data offset
+    pub last_callee_return_data_offset: u64,
+    /// Last callee's return data length
+    pub last_callee_return_data_length: u64,
 }
 
 impl Call {
@@ -108,6 +115,10 @@ pub struct CallContext {
     /// Call data (copy of tx input or caller's
     /// memory[call_data_offset..call_data_offset + call_data_length])
     pub call_data: Vec<u8>,
+    /// Return data of the last callee (copy of callee's
+    /// memory[last_callee_return_data_offset..last_callee_return_data_offset +
+    /// last_callee_return_data_length])
+    pub return_data: Vec<u8>,
 }
 
 /// A reversion group is the collection of calls and the operations which are
@@ -3,7 +3,7 @@
 use crate::{error::ExecError, exec_trace::OperationRef, operation::RWCounter, operation::RW};
 use eth_types::{
     evm_types::{Gas, GasCost, OpcodeId, ProgramCounter},
-    GethExecStep, H256,
+    GethExecStep, Word, H256,
 };
 use gadgets::impl_expr;
 use halo2_proofs::{arithmetic::FieldExt, plonk::Expression};
@@ -150,6 +150,9 @@ pub enum CopyDataType {
     TxCalldata,
     /// When the destination for the copy event is tx's log.
     TxLog,
+    /// When the destination for the copy event is the random linear
+    /// combination of the copied bytes, used as the input to keccak.
+    RlcAcc,
 }
 
 impl From<CopyDataType> for usize {
@@ -232,3 +235,43 @@ pub struct CopyEvent {
     /// Helper field for witness generation.
     pub pc: ProgramCounter,
 }
+
+/// Intermediary multiplication step, representing `a * b == d (mod 2^256)`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpStep {
+    /// First multiplicand.
+    pub a: Word,
+    /// Second multiplicand.
+    pub b: Word,
+    /// Multiplication result.
+    pub d: Word,
+}
+
+impl From<(Word, Word, Word)> for ExpStep {
+    fn from(values: (Word, Word, Word)) -> Self {
+        Self {
+            a: values.0,
+            b: values.1,
+            d: values.2,
+        }
+    }
+}
+
+/// Event representing an exponentiation `a ^ b == d (mod 2^256)` performed by
+/// the EXP opcode, computed through exponentiation by squaring.
+#[derive(Clone, Debug)]
+pub struct ExpEvent {
+    /// Identifier for the exponentiation trace, which is the RW counter of the
+    /// EXP step.
+    pub identifier: usize,
+    /// Base `a` for the exponentiation.
+    pub base: Word,
+    /// Exponent `b` for the exponentiation.
+    pub exponent: Word,
+    /// Exponentiation result.
+    pub exponentiation: Word,
+    /// Intermediate multiplication results, starting from the multiplication
+    /// that produces the exponentiation result down to the first squaring of
+    /// the base.
+    pub steps: Vec<ExpStep>,
+}
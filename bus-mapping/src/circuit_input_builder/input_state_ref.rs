@@ -2,7 +2,8 @@
 
 use super::{
     get_call_memory_offset_length, get_create_init_code, Block, BlockContext, Call, CallContext,
-    CallKind, CodeSource, CopyEvent, ExecState, ExecStep, Transaction, TransactionContext,
+    CallKind, CodeSource, CopyEvent, ExecState, ExecStep, ExpEvent, Transaction,
+    TransactionContext,
 };
 use crate::{
     error::{get_step_reported_error, ExecError},
@@ -16,8 +17,11 @@ use crate::{
     Error,
 };
 use eth_types::{
-    evm_types::{Gas, MemoryAddress, OpcodeId, StackAddress},
-    Address, GethExecStep, ToAddress, ToBigEndian, Word, H256,
+    evm_types::{
+        gas_utils::memory_expansion_gas_cost, Gas, GasCost, MemoryAddress, OpcodeId, StackAddress,
+        INVALID_INIT_CODE_FIRST_BYTE, MAX_CALL_DEPTH, MAX_CODE_SIZE,
+    },
+    Address, Bytecode, GethExecStep, ToAddress, ToBigEndian, ToWord, Word, H256,
 };
 use ethers_core::utils::{get_contract_address, get_create2_address};
 
@@ -71,7 +75,12 @@ impl<'a> CircuitInputStateRef<'a> {
             .expect("steps should have at least one BeginTx step");
         ExecStep {
             exec_state: ExecState::EndTx,
-            gas_left: Gas(prev_step.gas_left.0 - prev_step.gas_cost.0),
+            // All the gas left is consumed when the transaction halts in exception
+            gas_left: if prev_step.error.is_some() {
+                Gas(0)
+            } else {
+                Gas(prev_step.gas_left.0 - prev_step.gas_cost.0)
+            },
             rwc: self.block_ctx.rwc,
             // For tx without code execution
             reversible_write_counter: if let Some(call_ctx) = self.tx_ctx.calls().last() {
@@ -541,7 +550,7 @@ impl<'a> CircuitInputStateRef<'a> {
         Ok(get_create2_address(
             self.call()?.address,
             salt.to_be_bytes().to_vec(),
-            init_code.to_vec(),
+            init_code,
         ))
     }
 
@@ -568,7 +577,7 @@ impl<'a> CircuitInputStateRef<'a> {
                 step.stack.nth_last(2)?,
             ),
             CallKind::CallCode => (caller.address, caller.address, step.stack.nth_last(2)?),
-            CallKind::DelegateCall => (caller.caller_address, caller.address, Word::zero()),
+            CallKind::DelegateCall => (caller.caller_address, caller.address, caller.value),
             CallKind::StaticCall => (
                 caller.address,
                 step.stack.nth_last(1)?.to_address(),
@@ -585,7 +594,7 @@ impl<'a> CircuitInputStateRef<'a> {
         let (code_source, code_hash) = match kind {
             CallKind::Create | CallKind::Create2 => {
                 let init_code = get_create_init_code(step)?;
-                let code_hash = self.code_db.insert(init_code.to_vec());
+                let code_hash = self.code_db.insert(init_code);
                 (CodeSource::Memory, code_hash)
             }
             _ => {
@@ -638,6 +647,9 @@ impl<'a> CircuitInputStateRef<'a> {
             call_data_length,
             return_data_offset,
             return_data_length,
+            last_callee_id: 0,
+            last_callee_return_data_offset: 0,
+            last_callee_return_data_length: 0,
         };
 
         Ok(call)
@@ -736,7 +748,14 @@ impl<'a> CircuitInputStateRef<'a> {
             OpEnum::TxRefund(op) => {
                 self.sdb.set_refund(op.value);
             }
-            OpEnum::AccountDestructed(_) => unimplemented!(),
+            OpEnum::AccountDestructed(op) => {
+                if !op.is_destructed_prev && op.is_destructed {
+                    self.sdb.destruct_account(op.address);
+                }
+                if op.is_destructed_prev && !op.is_destructed {
+                    self.sdb.remove_destructed_account(&op.address);
+                }
+            }
             _ => unreachable!(),
         };
     }
@@ -776,58 +795,241 @@ impl<'a> CircuitInputStateRef<'a> {
     /// Handle a return step caused by any opcode that causes a return to the
     /// previous call context.
     pub fn handle_return(&mut self, step: &GethExecStep) -> Result<(), Error> {
-        let call = self.call()?.clone();
-
-        // Store deployed code if it's a successful create
-        if call.is_create() && call.is_success {
-            let offset = step.stack.nth_last(0)?;
-            let length = step.stack.nth_last(1)?;
-            let code = step
-                .memory
-                .read_chunk(offset.low_u64().into(), length.low_u64().into());
-            let code_hash = self.code_db.insert(code);
-            let (found, callee_account) = self.sdb.get_account_mut(&call.address);
-            if !found {
-                return Err(Error::AccountNotFound(call.address));
-            }
-            callee_account.code_hash = code_hash;
-        }
-
         // Handle reversion if this call doens't end successfully
         if !self.call()?.is_success {
             self.handle_reversion();
         }
 
+        let call = self.call()?.clone();
         self.tx_ctx.pop_call_ctx();
 
+        // Record the return data region of this call in its caller, so it can
+        // be accessed by RETURNDATASIZE and RETURNDATACOPY.
+        if !call.is_root {
+            let (last_callee_id, offset, length) = if call.depth > step.depth as usize {
+                // The callee has no code, so the step is the CALL or CREATE
+                // in the caller.
+                (0, 0, 0)
+            } else if step.error.is_none()
+                && (step.op == OpcodeId::REVERT
+                    || (step.op == OpcodeId::RETURN && !call.is_create()))
+            {
+                let length = step.stack.nth_last(1)?.low_u64();
+                let offset = if length == 0 {
+                    0
+                } else {
+                    step.stack.nth_last(0)?.low_u64()
+                };
+                (call.call_id, offset, length)
+            } else {
+                (call.call_id, 0, 0)
+            };
+            let return_data = step.memory.read_chunk(offset.into(), length.into());
+
+            let caller = self.call_mut()?;
+            caller.last_callee_id = last_callee_id;
+            caller.last_callee_return_data_offset = offset;
+            caller.last_callee_return_data_length = length;
+            self.call_ctx_mut()?.return_data = return_data;
+        }
+
         Ok(())
     }
 
+    /// Generate the associated operations that restore the caller's context
+    /// when the current call ends without return data, which corresponds to
+    /// `Instruction.step_state_transition_to_restored_context` in python spec.
+    /// When the step halts in exception, all the gas left of the call is
+    /// consumed, so nothing is returned to the caller.
+    pub fn gen_restore_context_ops(
+        &mut self,
+        exec_step: &mut ExecStep,
+        geth_steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let geth_step = &geth_steps[0];
+        let call = self.call()?.clone();
+        let caller = self.caller()?.clone();
+        self.call_context_read(
+            exec_step,
+            call.call_id,
+            CallContextField::CallerId,
+            caller.call_id.into(),
+        );
+
+        let geth_step_next = &geth_steps[1];
+        let caller_gas_left = if exec_step.error.is_some() {
+            geth_step_next.gas.0
+        } else {
+            geth_step_next.gas.0 - geth_step.gas.0 + geth_step.gas_cost.0
+        };
+        for (field, value) in [
+            (CallContextField::IsRoot, (caller.is_root as u64).into()),
+            (
+                CallContextField::IsCreate,
+                (caller.is_create() as u64).into(),
+            ),
+            (CallContextField::CodeHash, caller.code_hash.to_word()),
+            (CallContextField::ProgramCounter, geth_step_next.pc.0.into()),
+            (
+                CallContextField::StackPointer,
+                geth_step_next.stack.stack_pointer().0.into(),
+            ),
+            (CallContextField::GasLeft, caller_gas_left.into()),
+            (
+                CallContextField::MemorySize,
+                geth_step_next.memory.word_size().into(),
+            ),
+            (
+                CallContextField::ReversibleWriteCounter,
+                self.caller_ctx()?.reversible_write_counter.into(),
+            ),
+        ] {
+            self.call_context_read(exec_step, caller.call_id, field, value);
+        }
+
+        for (field, value) in [
+            (CallContextField::LastCalleeId, call.call_id.into()),
+            (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+            (CallContextField::LastCalleeReturnDataLength, 0.into()),
+        ] {
+            self.call_context_write(exec_step, caller.call_id, field, value);
+        }
+
+        Ok(())
+    }
+
+    /// Generate the associated operations of a step that halts in exception,
+    /// which ends the call as failed, restores the caller's context if any,
+    /// and reverts all the reversible writes done in the call.
+    pub fn handle_exception(
+        &mut self,
+        exec_step: &mut ExecStep,
+        geth_steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let call = self.call()?.clone();
+
+        // NOTE: For `RwCounterEndOfReversion` we use the `0` value as a placeholder,
+        // and later set the proper value in
+        // `CircuitInputBuilder::set_value_ops_call_context_rwc_eor`
+        for (field, value) in [
+            (CallContextField::IsSuccess, 0.into()),
+            (CallContextField::RwCounterEndOfReversion, 0.into()),
+            (CallContextField::IsPersistent, 0.into()),
+        ] {
+            self.call_context_read(exec_step, call.call_id, field, value);
+        }
+
+        if !call.is_root {
+            self.gen_restore_context_ops(exec_step, geth_steps)?;
+        }
+
+        self.handle_return(&geth_steps[0])
+    }
+
     /// Push a copy event to the state.
     pub fn push_copy(&mut self, copy: CopyEvent) {
         self.block.add_copy_event(copy);
     }
 
+    /// Push an exponentiation event to the state.
+    pub fn push_exponentiation(&mut self, event: ExpEvent) {
+        self.block.add_exp_event(event);
+    }
+
+    /// Push a SHA3 input to the state.
+    pub fn push_sha3_input(&mut self, input: Vec<u8>) {
+        self.block.add_sha3_input(input);
+    }
+
+    /// Return the error of the code deposit done by a RETURN step of a
+    /// creation call, which is not reported by geth in the step itself.  The
+    /// checks are done in the same order as geth: code size, then the first
+    /// byte of the code, then the gas to store the code.
+    pub(crate) fn get_code_deposit_err(
+        &self,
+        step: &GethExecStep,
+    ) -> Result<Option<ExecError>, Error> {
+        let offset = step.stack.nth_last(0)?;
+        let length = step.stack.nth_last(1)?;
+        if length > Word::from(MAX_CODE_SIZE) {
+            return Ok(Some(ExecError::MaxCodeSizeExceeded));
+        }
+        if length.is_zero() {
+            return Ok(None);
+        }
+        if step.memory.0.get(offset.low_u64() as usize) == Some(&INVALID_INIT_CODE_FIRST_BYTE) {
+            return Ok(Some(ExecError::InvalidCreationCode));
+        }
+        let next_memory_word_size = std::cmp::max(
+            step.memory.word_size() as u64,
+            (offset.low_u64() + length.low_u64() + 31) / 32,
+        );
+        let gas_left = step.gas.0.saturating_sub(memory_expansion_gas_cost(
+            step.memory.word_size() as u64,
+            next_memory_word_size,
+        ));
+        if GasCost::CODE_DEPOSIT_BYTE_COST.as_u64() * length.low_u64() > gas_left {
+            return Ok(Some(ExecError::CodeStoreOutOfGas));
+        }
+        Ok(None)
+    }
+
+    /// Returns whether the JUMP or JUMPI in the step jumps to an invalid
+    /// destination, which is out of the code range, not a JUMPDEST, or inside
+    /// the data of a PUSH.
+    fn is_invalid_jump(&self, step: &GethExecStep) -> Result<bool, Error> {
+        if step.op == OpcodeId::JUMPI && step.stack.nth_last(1)?.is_zero() {
+            return Ok(false);
+        }
+
+        let dest = step.stack.last()?;
+        let code = self.code(self.call()?.code_hash)?;
+        if dest >= code.len().into() {
+            return Ok(true);
+        }
+        Ok(Bytecode::from(code).get(dest.as_usize()).map_or(true, |e| {
+            !e.is_code || e.value != OpcodeId::JUMPDEST.as_u8()
+        }))
+    }
+
     pub(crate) fn get_step_err(
         &self,
         step: &GethExecStep,
         next_step: Option<&GethExecStep>,
     ) -> Result<Option<ExecError>, Error> {
         if let Some(error) = &step.error {
-            return Ok(Some(get_step_reported_error(&step.op, error)));
+            return match get_step_reported_error(&step.op, error) {
+                Some(error) => Ok(Some(error)),
+                None => Err(Error::UnexpectedExecStepError(
+                    "unknown error reported by geth",
+                    step.clone(),
+                )),
+            };
         }
 
         if matches!(step.op, OpcodeId::INVALID(_)) {
             return Ok(Some(ExecError::InvalidOpcode));
         }
 
-        // When last step has opcodes that halt, there's no error.
+        // Geth doesn't report the invalid jump in the step, so it's inferred
+        // from the destination.
+        if matches!(step.op, OpcodeId::JUMP | OpcodeId::JUMPI) && self.is_invalid_jump(step)? {
+            return Ok(Some(ExecError::InvalidJump));
+        }
+
+        let call = self.call()?;
+
+        // When last step has opcodes that halt, there's no error, except for
+        // the code deposit of a failed creation transaction.
         if matches!(next_step, None)
             && matches!(
                 step.op,
                 OpcodeId::STOP | OpcodeId::RETURN | OpcodeId::REVERT | OpcodeId::SELFDESTRUCT
             )
         {
+            if step.op == OpcodeId::RETURN && call.is_create() && !call.is_success {
+                return self.get_code_deposit_err(step);
+            }
             return Ok(None);
         }
 
@@ -836,16 +1038,16 @@ impl<'a> CircuitInputStateRef<'a> {
             .map(|s| s.stack.last().unwrap_or_else(|_| Word::zero()))
             .unwrap_or_else(Word::zero);
 
-        let call = self.call()?;
-
         // Return from a call with a failure
-        if step.depth != next_depth && next_result.is_zero() {
+        if step.depth == next_depth + 1 && next_result.is_zero() {
             if !matches!(step.op, OpcodeId::RETURN) {
                 // Without calling RETURN
                 return Ok(match step.op {
-                    OpcodeId::JUMP | OpcodeId::JUMPI => Some(ExecError::InvalidJump),
                     OpcodeId::RETURNDATACOPY => Some(ExecError::ReturnDataOutOfBounds),
-                    // Break write protection (CALL with value will be handled below)
+                    // Break write protection
+                    OpcodeId::CALL if call.is_static && !step.stack.nth_last(2)?.is_zero() => {
+                        Some(ExecError::WriteProtection)
+                    }
                     OpcodeId::SSTORE
                     | OpcodeId::CREATE
                     | OpcodeId::CREATE2
@@ -870,23 +1072,13 @@ impl<'a> CircuitInputStateRef<'a> {
             } else {
                 // Return from a {CREATE, CREATE2} with a failure, via RETURN
                 if !call.is_root && call.is_create() {
-                    let offset = step.stack.nth_last(0)?;
-                    let length = step.stack.nth_last(1)?;
-                    if length > Word::from(0x6000u64) {
-                        return Ok(Some(ExecError::MaxCodeSizeExceeded));
-                    } else if length > Word::zero()
-                        && !step.memory.0.is_empty()
-                        && step.memory.0.get(offset.low_u64() as usize) == Some(&0xef)
-                    {
-                        return Ok(Some(ExecError::InvalidCreationCode));
-                    } else if Word::from(200u64) * length > Word::from(step.gas.0) {
-                        return Ok(Some(ExecError::CodeStoreOutOfGas));
-                    } else {
-                        return Err(Error::UnexpectedExecStepError(
+                    return match self.get_code_deposit_err(step)? {
+                        Some(error) => Ok(Some(error)),
+                        None => Err(Error::UnexpectedExecStepError(
                             "failure in RETURN from {CREATE, CREATE2}",
                             step.clone(),
-                        ));
-                    }
+                        )),
+                    };
                 } else {
                     return Err(Error::UnexpectedExecStepError(
                         "failure in RETURN",
@@ -901,9 +1093,12 @@ impl<'a> CircuitInputStateRef<'a> {
 
         // Return from a call without calling RETURN or STOP and having success
         // is unexpected.
-        if step.depth != next_depth
+        if step.depth == next_depth + 1
             && next_result != Word::zero()
-            && !matches!(step.op, OpcodeId::RETURN | OpcodeId::STOP)
+            && !matches!(
+                step.op,
+                OpcodeId::RETURN | OpcodeId::STOP | OpcodeId::SELFDESTRUCT
+            )
         {
             return Err(Error::UnexpectedExecStepError(
                 "success result without {RETURN, STOP}",
@@ -924,7 +1119,7 @@ impl<'a> CircuitInputStateRef<'a> {
         ) && next_result.is_zero()
             && next_pc != 0
         {
-            if step.depth == 1025 {
+            if step.depth as u64 == MAX_CALL_DEPTH + 1 {
                 return Ok(Some(ExecError::Depth));
             }
 
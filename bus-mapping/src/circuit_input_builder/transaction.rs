@@ -2,7 +2,7 @@
 
 use std::collections::BTreeMap;
 
-use eth_types::{Address, GethExecTrace, Word};
+use eth_types::{Address, GethExecTrace, ToWord, Word};
 use ethers_core::utils::get_contract_address;
 
 use crate::{
@@ -159,6 +159,7 @@ impl TransactionContext {
             index: call_idx,
             reversible_write_counter: 0,
             call_data,
+            return_data: Vec::new(),
         });
     }
 
@@ -177,6 +178,8 @@ impl TransactionContext {
 #[derive(Debug, Clone)]
 /// Result of the parsing of an Ethereum Transaction.
 pub struct Transaction {
+    /// Hash
+    pub hash: Word,
     /// Nonce
     pub nonce: u64,
     /// Gas
@@ -191,6 +194,12 @@ pub struct Transaction {
     pub value: Word,
     /// Input / Call Data
     pub input: Vec<u8>,
+    /// Signature v
+    pub v: u64,
+    /// Signature r
+    pub r: Word,
+    /// Signature s
+    pub s: Word,
     /// Calls made in the transaction
     calls: Vec<Call>,
     /// Execution steps
@@ -253,6 +262,7 @@ impl Transaction {
         };
 
         Ok(Self {
+            hash: eth_tx.hash.to_word(),
             nonce: eth_tx.nonce.as_u64(),
             gas: eth_tx.gas.as_u64(),
             gas_price: eth_tx.gas_price.unwrap_or_default(),
@@ -260,6 +270,9 @@ impl Transaction {
             to: eth_tx.to.unwrap_or_default(),
             value: eth_tx.value,
             input: eth_tx.input.to_vec(),
+            v: eth_tx.v.as_u64(),
+            r: eth_tx.r,
+            s: eth_tx.s,
             calls: vec![call],
             steps: Vec::new(),
         })
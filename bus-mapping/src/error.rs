@@ -6,8 +6,8 @@ use ethers_providers::ProviderError;
 use std::error::Error as StdError;
 
 use crate::geth_errors::{
-    GETH_ERR_GAS_UINT_OVERFLOW, GETH_ERR_OUT_OF_GAS, GETH_ERR_STACK_OVERFLOW,
-    GETH_ERR_STACK_UNDERFLOW,
+    GETH_ERR_GAS_UINT_OVERFLOW, GETH_ERR_INVALID_JUMP, GETH_ERR_INVALID_OPCODE,
+    GETH_ERR_OUT_OF_GAS, GETH_ERR_SSTORE_SENTRY, GETH_ERR_STACK_OVERFLOW, GETH_ERR_STACK_UNDERFLOW,
 };
 
 /// Error type for any BusMapping related failure.
@@ -36,6 +36,24 @@ pub enum Error {
     EthTypeError(eth_types::Error),
     /// EVM Execution error
     ExecutionError(ExecError),
+    /// The block with the given number doesn't link to its previous block by
+    /// parent hash.
+    ParentHashMismatch(u64),
+    /// The block with the given number has no previous block to take the
+    /// pre-state from.
+    ParentBlockNotFound(u64),
+    /// Invalid account proof of the address returned by `eth_getProof`.
+    InvalidAccountProof(Address, &'static str),
+    /// Invalid storage proof of the address and key returned by
+    /// `eth_getProof`.
+    InvalidStorageProof(Address, Word, &'static str),
+    /// The code of the address returned by `eth_getCode` doesn't match the
+    /// code hash in its account proof.
+    CodeHashMismatch(Address),
+    /// The node of the hash is needed to update a trie but is unknown.
+    MissingTrieNode(H256),
+    /// Invalid trie node, or an update of a trie which is not supported.
+    InvalidTrieUpdate(&'static str),
 }
 
 impl From<eth_types::Error> for Error {
@@ -138,7 +156,7 @@ pub enum ExecError {
 }
 
 // TODO: Move to impl block.
-pub(crate) fn get_step_reported_error(op: &OpcodeId, error: &str) -> ExecError {
+pub(crate) fn get_step_reported_error(op: &OpcodeId, error: &str) -> Option<ExecError> {
     if error == GETH_ERR_OUT_OF_GAS || error == GETH_ERR_GAS_UINT_OVERFLOW {
         // NOTE: We report a GasUintOverflow error as an OutOfGas error
         let oog_err = match op {
@@ -170,12 +188,18 @@ pub(crate) fn get_step_reported_error(op: &OpcodeId, error: &str) -> ExecError {
             OpcodeId::SELFDESTRUCT => OogError::SelfDestruct,
             _ => OogError::Constant,
         };
-        ExecError::OutOfGas(oog_err)
+        Some(ExecError::OutOfGas(oog_err))
+    } else if error == GETH_ERR_SSTORE_SENTRY {
+        Some(ExecError::OutOfGas(OogError::Sstore))
     } else if error.starts_with(GETH_ERR_STACK_OVERFLOW) {
-        ExecError::StackOverflow
+        Some(ExecError::StackOverflow)
     } else if error.starts_with(GETH_ERR_STACK_UNDERFLOW) {
-        ExecError::StackUnderflow
+        Some(ExecError::StackUnderflow)
+    } else if error.starts_with(GETH_ERR_INVALID_OPCODE) {
+        Some(ExecError::InvalidOpcode)
+    } else if error == GETH_ERR_INVALID_JUMP {
+        Some(ExecError::InvalidJump)
     } else {
-        panic!("Unknown GethExecStep.error: {}", error);
+        None
     }
 }
@@ -1,21 +1,21 @@
 //! Definition of each opcode of the EVM.
 use crate::{
     circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
     evm::OpcodeId,
-    operation::{
-        AccountField, AccountOp, CallContextField, TxAccessListAccountOp, TxReceiptField,
-        TxRefundOp, RW,
-    },
+    operation::{AccountField, AccountOp, CallContextField, TxReceiptField, TxRefundOp, RW},
     Error,
 };
 use core::fmt::Debug;
 use eth_types::{
     evm_types::{GasCost, MAX_REFUND_QUOTIENT_OF_GAS_USED},
-    GethExecStep, ToAddress, ToWord, Word,
+    GethExecStep, ToWord, Word,
 };
 use keccak256::EMPTY_HASH;
 use log::warn;
 
+mod balance;
+mod blockhash;
 mod call;
 mod calldatacopy;
 mod calldataload;
@@ -25,8 +25,31 @@ mod callvalue;
 mod chainid;
 mod codecopy;
 mod codesize;
+mod create;
 mod dup;
+mod error_code_deposit;
+mod error_invalid_jump;
+mod error_oog_account_access;
+mod error_oog_call;
+mod error_oog_create2;
+mod error_oog_dynamic_memory;
+mod error_oog_exp;
+mod error_oog_extcodecopy;
+mod error_oog_log;
+mod error_oog_memory_copy;
+mod error_oog_selfdestruct;
+mod error_oog_sha3;
+mod error_oog_sload;
+mod error_oog_sstore;
+mod error_oog_static_memory;
+mod error_precheck;
+mod error_return_data_out_of_bound;
+mod error_simple;
+mod error_write_protection;
+mod exp;
+mod extcodecopy;
 mod extcodehash;
+mod extcodesize;
 mod gasprice;
 mod logs;
 mod mload;
@@ -34,13 +57,20 @@ mod mstore;
 mod number;
 mod origin;
 mod r#return;
+mod returndatacopy;
+mod returndatasize;
+mod revert;
 mod selfbalance;
+mod selfdestruct;
+mod sha3;
 mod sload;
 mod sstore;
 mod stackonlyop;
 mod stop;
 mod swap;
 
+use balance::Balance;
+use blockhash::Blockhash;
 use call::Call;
 use calldatacopy::Calldatacopy;
 use calldataload::Calldataload;
@@ -49,15 +79,43 @@ use caller::Caller;
 use callvalue::Callvalue;
 use codecopy::Codecopy;
 use codesize::Codesize;
+use create::{create_address_preimage, gen_tx_calldata_to_rlc_acc_copy_event, Create};
 use dup::Dup;
+use error_code_deposit::ErrorCodeDeposit;
+use error_invalid_jump::ErrorInvalidJump;
+use error_oog_account_access::ErrorOOGAccountAccess;
+use error_oog_call::ErrorOOGCall;
+use error_oog_create2::ErrorOOGCreate2;
+use error_oog_dynamic_memory::ErrorOOGDynamicMemory;
+use error_oog_exp::ErrorOOGExp;
+use error_oog_extcodecopy::ErrorOOGExtcodecopy;
+use error_oog_log::ErrorOOGLog;
+use error_oog_memory_copy::ErrorOOGMemoryCopy;
+use error_oog_selfdestruct::ErrorOOGSelfdestruct;
+use error_oog_sha3::ErrorOOGSha3;
+use error_oog_sload::ErrorOOGSload;
+use error_oog_sstore::ErrorOOGSstore;
+use error_oog_static_memory::ErrorOOGStaticMemory;
+use error_precheck::ErrorPrecheck;
+use error_return_data_out_of_bound::ErrorReturnDataOutOfBound;
+use error_simple::ErrorSimple;
+use error_write_protection::ErrorWriteProtection;
+use exp::Exponentiation;
+use extcodecopy::Extcodecopy;
 use extcodehash::Extcodehash;
+use extcodesize::Extcodesize;
 use gasprice::GasPrice;
 use logs::Log;
 use mload::Mload;
 use mstore::Mstore;
 use origin::Origin;
 use r#return::Return;
+use returndatacopy::Returndatacopy;
+use returndatasize::Returndatasize;
+use revert::Revert;
 use selfbalance::Selfbalance;
+use selfdestruct::Selfdestruct;
+use sha3::Sha3;
 use sload::Sload;
 use sstore::Sstore;
 use stackonlyop::StackOnlyOpcode;
@@ -107,7 +165,7 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::SMOD => StackOnlyOpcode::<2, 1>::gen_associated_ops,
         OpcodeId::ADDMOD => StackOnlyOpcode::<3, 1>::gen_associated_ops,
         OpcodeId::MULMOD => StackOnlyOpcode::<3, 1>::gen_associated_ops,
-        OpcodeId::EXP => StackOnlyOpcode::<2, 1>::gen_associated_ops,
+        OpcodeId::EXP => Exponentiation::gen_associated_ops,
         OpcodeId::SIGNEXTEND => StackOnlyOpcode::<2, 1>::gen_associated_ops,
         OpcodeId::LT => StackOnlyOpcode::<2, 1>::gen_associated_ops,
         OpcodeId::GT => StackOnlyOpcode::<2, 1>::gen_associated_ops,
@@ -123,9 +181,9 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::SHL => StackOnlyOpcode::<2, 1>::gen_associated_ops,
         OpcodeId::SHR => StackOnlyOpcode::<2, 1>::gen_associated_ops,
         OpcodeId::SAR => StackOnlyOpcode::<2, 1>::gen_associated_ops,
-        OpcodeId::SHA3 => StackOnlyOpcode::<2, 1>::gen_associated_ops,
+        OpcodeId::SHA3 => Sha3::gen_associated_ops,
         OpcodeId::ADDRESS => StackOnlyOpcode::<0, 1>::gen_associated_ops,
-        OpcodeId::BALANCE => StackOnlyOpcode::<1, 1>::gen_associated_ops,
+        OpcodeId::BALANCE => Balance::gen_associated_ops,
         OpcodeId::ORIGIN => Origin::gen_associated_ops,
         OpcodeId::CALLER => Caller::gen_associated_ops,
         OpcodeId::CALLVALUE => Callvalue::gen_associated_ops,
@@ -135,12 +193,12 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::GASPRICE => GasPrice::gen_associated_ops,
         OpcodeId::CODECOPY => Codecopy::gen_associated_ops,
         OpcodeId::CODESIZE => Codesize::gen_associated_ops,
-        OpcodeId::EXTCODESIZE => StackOnlyOpcode::<1, 1>::gen_associated_ops,
-        OpcodeId::EXTCODECOPY => StackOnlyOpcode::<4, 0>::gen_associated_ops,
-        OpcodeId::RETURNDATASIZE => StackOnlyOpcode::<0, 1>::gen_associated_ops,
-        OpcodeId::RETURNDATACOPY => StackOnlyOpcode::<3, 0>::gen_associated_ops,
+        OpcodeId::EXTCODESIZE => Extcodesize::gen_associated_ops,
+        OpcodeId::EXTCODECOPY => Extcodecopy::gen_associated_ops,
+        OpcodeId::RETURNDATASIZE => Returndatasize::gen_associated_ops,
+        OpcodeId::RETURNDATACOPY => Returndatacopy::gen_associated_ops,
         OpcodeId::EXTCODEHASH => Extcodehash::gen_associated_ops,
-        OpcodeId::BLOCKHASH => StackOnlyOpcode::<1, 1>::gen_associated_ops,
+        OpcodeId::BLOCKHASH => Blockhash::gen_associated_ops,
         OpcodeId::COINBASE => StackOnlyOpcode::<0, 1>::gen_associated_ops,
         OpcodeId::TIMESTAMP => StackOnlyOpcode::<0, 1>::gen_associated_ops,
         OpcodeId::NUMBER => StackOnlyOpcode::<0, 1>::gen_associated_ops,
@@ -198,30 +256,19 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::LOG2 => Log::gen_associated_ops,
         OpcodeId::LOG3 => Log::gen_associated_ops,
         OpcodeId::LOG4 => Log::gen_associated_ops,
-        // OpcodeId::CREATE => {},
+        OpcodeId::CREATE => Create::<false>::gen_associated_ops,
         OpcodeId::CALL => Call::gen_associated_ops,
-        // OpcodeId::CALLCODE => {},
+        OpcodeId::CALLCODE => Call::gen_associated_ops,
         // OpcodeId::RETURN => {},
-        // OpcodeId::DELEGATECALL => {},
-        // OpcodeId::CREATE2 => {},
-        // OpcodeId::STATICCALL => {},
-        // OpcodeId::REVERT => {},
-        OpcodeId::REVERT | OpcodeId::RETURN => {
+        OpcodeId::DELEGATECALL => Call::gen_associated_ops,
+        OpcodeId::CREATE2 => Create::<true>::gen_associated_ops,
+        OpcodeId::STATICCALL => Call::gen_associated_ops,
+        OpcodeId::REVERT => Revert::gen_associated_ops,
+        OpcodeId::RETURN => {
             warn!("Using dummy gen_associated_ops for opcode {:?}", opcode_id);
             Return::gen_associated_ops
         }
-        OpcodeId::SELFDESTRUCT => {
-            warn!("Using dummy gen_selfdestruct_ops for opcode SELFDESTRUCT");
-            dummy_gen_selfdestruct_ops
-        }
-        OpcodeId::CALLCODE | OpcodeId::DELEGATECALL | OpcodeId::STATICCALL => {
-            warn!("Using dummy gen_call_ops for opcode {:?}", opcode_id);
-            dummy_gen_call_ops
-        }
-        OpcodeId::CREATE | OpcodeId::CREATE2 => {
-            warn!("Using dummy gen_create_ops for opcode {:?}", opcode_id);
-            dummy_gen_create_ops
-        }
+        OpcodeId::SELFDESTRUCT => Selfdestruct::gen_associated_ops,
         _ => {
             warn!("Using dummy gen_associated_ops for opcode {:?}", opcode_id);
             dummy_gen_associated_ops
@@ -229,6 +276,72 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
     }
 }
 
+fn fn_gen_error_state_associated_ops(error: &ExecError) -> Option<FnGenAssociatedOps> {
+    match error {
+        ExecError::OutOfGas(OogError::StaticMemoryExpansion) => {
+            Some(ErrorOOGStaticMemory::gen_associated_ops)
+        }
+        ExecError::OutOfGas(OogError::DynamicMemoryExpansion) => {
+            Some(ErrorOOGDynamicMemory::gen_associated_ops)
+        }
+        ExecError::OutOfGas(OogError::MemoryCopy) => Some(ErrorOOGMemoryCopy::gen_associated_ops),
+        ExecError::OutOfGas(OogError::AccountAccess) => {
+            Some(ErrorOOGAccountAccess::gen_associated_ops)
+        }
+        ExecError::OutOfGas(OogError::Log) => Some(ErrorOOGLog::gen_associated_ops),
+        ExecError::OutOfGas(OogError::Exp) => Some(ErrorOOGExp::gen_associated_ops),
+        ExecError::OutOfGas(OogError::Sha3) => Some(ErrorOOGSha3::gen_associated_ops),
+        ExecError::OutOfGas(OogError::ExtCodeCopy) => Some(ErrorOOGExtcodecopy::gen_associated_ops),
+        ExecError::OutOfGas(OogError::Sload) => Some(ErrorOOGSload::gen_associated_ops),
+        ExecError::OutOfGas(OogError::Sstore) => Some(ErrorOOGSstore::gen_associated_ops),
+        ExecError::OutOfGas(
+            OogError::Call | OogError::CallCode | OogError::DelegateCall | OogError::StaticCall,
+        ) => Some(ErrorOOGCall::gen_associated_ops),
+        ExecError::OutOfGas(OogError::Create2) => Some(ErrorOOGCreate2::gen_associated_ops),
+        ExecError::OutOfGas(OogError::SelfDestruct) => {
+            Some(ErrorOOGSelfdestruct::gen_associated_ops)
+        }
+        ExecError::InvalidOpcode
+        | ExecError::StackOverflow
+        | ExecError::StackUnderflow
+        | ExecError::OutOfGas(OogError::Constant) => Some(ErrorSimple::gen_associated_ops),
+        ExecError::InvalidJump => Some(ErrorInvalidJump::gen_associated_ops),
+        ExecError::WriteProtection => Some(ErrorWriteProtection::gen_associated_ops),
+        ExecError::ReturnDataOutOfBounds => Some(ErrorReturnDataOutOfBound::gen_associated_ops),
+        ExecError::Depth | ExecError::InsufficientBalance => {
+            Some(ErrorPrecheck::gen_associated_ops)
+        }
+        ExecError::MaxCodeSizeExceeded
+        | ExecError::InvalidCreationCode
+        | ExecError::CodeStoreOutOfGas => Some(ErrorCodeDeposit::gen_associated_ops),
+        _ => None,
+    }
+}
+
+/// Maximum memory size in bytes geth could expand to, beyond which the access
+/// is reported as gas uint64 overflow.
+const MAX_MEMORY_SIZE: u64 = 0x1FFFFFFFE0;
+
+/// Return the length of a memory access, capped at [`MAX_MEMORY_SIZE`] so
+/// that the gas cost of an overflowing access is still computable.
+fn capped_memory_length(length: Word) -> u64 {
+    length.min(MAX_MEMORY_SIZE.into()).as_u64()
+}
+
+/// Return the memory word size after accessing `length` bytes at `offset`,
+/// which is capped at [`MAX_MEMORY_SIZE`] so that the gas cost of an
+/// overflowing access is still computable (and more than any gas left).
+fn next_memory_word_size(curr_memory_word_size: u64, offset: Word, length: Word) -> u64 {
+    if length.is_zero() {
+        return curr_memory_word_size;
+    }
+    let end = offset
+        .saturating_add(length)
+        .min(MAX_MEMORY_SIZE.into())
+        .as_u64();
+    curr_memory_word_size.max((end + 31) / 32)
+}
+
 /// Generate the associated operations according to the particular
 /// [`OpcodeId`].
 pub fn gen_associated_ops(
@@ -236,6 +349,18 @@ pub fn gen_associated_ops(
     state: &mut CircuitInputStateRef,
     geth_steps: &[GethExecStep],
 ) -> Result<Vec<ExecStep>, Error> {
+    // Errors which have a dedicated handler take precedence over the opcode's
+    // regular associated operations.  Some errors like the code deposit ones or
+    // `ExecError::WriteProtection` are not reported by geth in the step, so
+    // they are inferred from the next step.
+    let exec_error = state.get_step_err(&geth_steps[0], geth_steps.get(1))?;
+    if let Some(fn_gen_error_ops) = exec_error
+        .as_ref()
+        .and_then(fn_gen_error_state_associated_ops)
+    {
+        return fn_gen_error_ops(state, geth_steps);
+    }
+
     let fn_gen_associated_ops = fn_gen_associated_ops(opcode_id);
     fn_gen_associated_ops(state, geth_steps)
 }
@@ -294,6 +419,11 @@ pub fn gen_begin_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Er
     } + call_data_gas_cost;
     exec_step.gas_cost = GasCost(intrinsic_gas_cost);
 
+    // The account of the contract to be created might not be in the state yet.
+    if call.is_create() {
+        state.sdb.get_account_mut(&call.address);
+    }
+
     // Transfer with fee
     state.transfer_with_fee(
         &mut exec_step,
@@ -315,7 +445,59 @@ pub fn gen_begin_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Er
     ) {
         // 1. Creation transaction.
         (true, _, _) => {
-            warn!("Creation transaction is left unimplemented");
+            // Increase callee's nonce, which is reverted if the creation
+            // fails
+            let callee_nonce = state.sdb.get_nonce(&call.address);
+            debug_assert!(callee_nonce == 0);
+            state.push_op_reversible(
+                &mut exec_step,
+                RW::WRITE,
+                AccountOp {
+                    address: call.address,
+                    field: AccountField::Nonce,
+                    value: 1.into(),
+                    value_prev: 0.into(),
+                },
+            )?;
+
+            for (field, value) in [
+                (CallContextField::Depth, call.depth.into()),
+                (
+                    CallContextField::CallerAddress,
+                    call.caller_address.to_word(),
+                ),
+                (CallContextField::CalleeAddress, call.address.to_word()),
+                (
+                    CallContextField::CallDataOffset,
+                    call.call_data_offset.into(),
+                ),
+                (
+                    CallContextField::CallDataLength,
+                    call.call_data_length.into(),
+                ),
+                (CallContextField::Value, call.value),
+                (CallContextField::IsStatic, (call.is_static as usize).into()),
+                (CallContextField::LastCalleeId, 0.into()),
+                (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+                (CallContextField::LastCalleeReturnDataLength, 0.into()),
+                (CallContextField::IsRoot, 1.into()),
+                (CallContextField::IsCreate, 1.into()),
+                (CallContextField::CodeHash, call.code_hash.to_word()),
+            ] {
+                state.call_context_read(&mut exec_step, call.call_id, field, value);
+            }
+
+            // Copy of the init code from the tx calldata, to be hashed.
+            let init_code = state.tx.input.clone();
+            if !init_code.is_empty() {
+                let copy_event = gen_tx_calldata_to_rlc_acc_copy_event(state, &init_code);
+                state.push_copy(copy_event);
+            }
+
+            // Keccak inputs of the init code hash and of the contract address.
+            state.push_sha3_input(init_code);
+            state.push_sha3_input(create_address_preimage(caller_address, nonce_prev));
+
             Ok(exec_step)
         }
         // 2. Call to precompiled.
@@ -478,150 +660,3 @@ pub fn gen_end_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Erro
 
     Ok(exec_step)
 }
-
-fn dummy_gen_call_ops(
-    state: &mut CircuitInputStateRef,
-    geth_steps: &[GethExecStep],
-) -> Result<Vec<ExecStep>, Error> {
-    let geth_step = &geth_steps[0];
-    let mut exec_step = state.new_step(geth_step)?;
-
-    let tx_id = state.tx_ctx.id();
-    let call = state.parse_call(geth_step)?;
-
-    let (_, account) = state.sdb.get_account(&call.address);
-    let callee_code_hash = account.code_hash;
-
-    let is_warm = state.sdb.check_account_in_access_list(&call.address);
-    state.push_op_reversible(
-        &mut exec_step,
-        RW::WRITE,
-        TxAccessListAccountOp {
-            tx_id,
-            address: call.address,
-            is_warm: true,
-            is_warm_prev: is_warm,
-        },
-    )?;
-
-    state.push_call(call.clone(), geth_step);
-
-    match (
-        state.is_precompiled(&call.address),
-        callee_code_hash.to_fixed_bytes() == *EMPTY_HASH,
-    ) {
-        // 1. Call to precompiled.
-        (true, _) => Ok(vec![exec_step]),
-        // 2. Call to account with empty code.
-        (_, true) => {
-            state.handle_return(geth_step)?;
-            Ok(vec![exec_step])
-        }
-        // 3. Call to account with non-empty code.
-        (_, false) => Ok(vec![exec_step]),
-    }
-}
-
-fn dummy_gen_create_ops(
-    state: &mut CircuitInputStateRef,
-    geth_steps: &[GethExecStep],
-) -> Result<Vec<ExecStep>, Error> {
-    let geth_step = &geth_steps[0];
-    let mut exec_step = state.new_step(geth_step)?;
-
-    let tx_id = state.tx_ctx.id();
-    let call = state.parse_call(geth_step)?;
-
-    // Increase caller's nonce
-    let nonce_prev = state.sdb.get_nonce(&call.caller_address);
-    state.push_op_reversible(
-        &mut exec_step,
-        RW::WRITE,
-        AccountOp {
-            address: call.caller_address,
-            field: AccountField::Nonce,
-            value: (nonce_prev + 1).into(),
-            value_prev: nonce_prev.into(),
-        },
-    )?;
-
-    // Add callee into access list
-    let is_warm = state.sdb.check_account_in_access_list(&call.address);
-    state.push_op_reversible(
-        &mut exec_step,
-        RW::WRITE,
-        TxAccessListAccountOp {
-            tx_id,
-            address: call.address,
-            is_warm: true,
-            is_warm_prev: is_warm,
-        },
-    )?;
-
-    state.push_call(call.clone(), geth_step);
-
-    // Increase callee's nonce
-    let nonce_prev = state.sdb.get_nonce(&call.address);
-    debug_assert!(nonce_prev == 0);
-    state.push_op_reversible(
-        &mut exec_step,
-        RW::WRITE,
-        AccountOp {
-            address: call.address,
-            field: AccountField::Nonce,
-            value: 1.into(),
-            value_prev: 0.into(),
-        },
-    )?;
-
-    state.transfer(
-        &mut exec_step,
-        call.caller_address,
-        call.address,
-        call.value,
-    )?;
-
-    if call.code_hash.to_fixed_bytes() == *EMPTY_HASH {
-        // 1. Create with empty initcode.
-        state.handle_return(geth_step)?;
-        Ok(vec![exec_step])
-    } else {
-        // 2. Create with non-empty initcode.
-        Ok(vec![exec_step])
-    }
-}
-
-fn dummy_gen_selfdestruct_ops(
-    state: &mut CircuitInputStateRef,
-    geth_steps: &[GethExecStep],
-) -> Result<Vec<ExecStep>, Error> {
-    let geth_step = &geth_steps[0];
-    let mut exec_step = state.new_step(geth_step)?;
-    let sender = state.call()?.address;
-    let receiver = geth_step.stack.last()?.to_address();
-
-    let is_warm = state.sdb.check_account_in_access_list(&receiver);
-    state.push_op_reversible(
-        &mut exec_step,
-        RW::WRITE,
-        TxAccessListAccountOp {
-            tx_id: state.tx_ctx.id(),
-            address: receiver,
-            is_warm: true,
-            is_warm_prev: is_warm,
-        },
-    )?;
-
-    let (found, receiver_account) = state.sdb.get_account(&receiver);
-    if !found {
-        return Err(Error::AccountNotFound(receiver));
-    }
-    let value = receiver_account.balance;
-    state.transfer(&mut exec_step, sender, receiver, value)?;
-
-    if state.call()?.is_persistent {
-        state.sdb.destruct_account(sender);
-    }
-
-    Ok(vec![exec_step])
-}
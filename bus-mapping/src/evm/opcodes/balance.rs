@@ -0,0 +1,254 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::CircuitInputStateRef,
+    evm::opcodes::ExecStep,
+    operation::{AccountField, CallContextField, TxAccessListAccountOp, RW},
+    Error,
+};
+use eth_types::{GethExecStep, ToAddress, ToWord, U256};
+
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Balance;
+
+impl Opcode for Balance {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        let stack_address = geth_step.stack.last_filled();
+
+        // Pop external address off stack
+        let address = geth_step.stack.last()?.to_address();
+        state.stack_read(&mut exec_step, stack_address, address.to_word())?;
+
+        // Read transaction id, rw_counter_end_of_reversion, and is_persistent from call
+        // context
+        for (field, value) in [
+            (CallContextField::TxId, U256::from(state.tx_ctx.id())),
+            (
+                CallContextField::RwCounterEndOfReversion,
+                U256::from(state.call()?.rw_counter_end_of_reversion as u64),
+            ),
+            (
+                CallContextField::IsPersistent,
+                U256::from(state.call()?.is_persistent as u64),
+            ),
+        ] {
+            state.call_context_read(&mut exec_step, state.call()?.call_id, field, value);
+        }
+
+        // Update transaction access list for address
+        let is_warm = state.sdb.check_account_in_access_list(&address);
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            TxAccessListAccountOp {
+                tx_id: state.tx_ctx.id(),
+                address,
+                is_warm: true,
+                is_warm_prev: is_warm,
+            },
+        )?;
+
+        // Read the balance of the account, which is zero if it doesn't exist
+        let balance = state.sdb.get_account(&address).1.balance;
+        state.account_read(
+            &mut exec_step,
+            address,
+            AccountField::Balance,
+            balance,
+            balance,
+        )?;
+
+        // Stack write of the balance
+        debug_assert_eq!(balance, geth_steps[1].stack.last()?);
+        state.stack_write(&mut exec_step, stack_address, balance)?;
+
+        Ok(vec![exec_step])
+    }
+}
+
+#[cfg(test)]
+mod balance_tests {
+    use super::*;
+    use crate::circuit_input_builder::ExecState;
+    use crate::mock::BlockData;
+    use crate::operation::{AccountOp, CallContextOp, StackOp};
+    use eth_types::{
+        address, bytecode,
+        evm_types::{OpcodeId, StackAddress},
+        geth_types::GethData,
+        Bytecode, Word,
+    };
+    use mock::TestContext;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn cold_empty_account() -> Result<(), Error> {
+        test_ok(false, false)
+    }
+
+    #[test]
+    fn warm_empty_account() -> Result<(), Error> {
+        test_ok(false, true)
+    }
+
+    #[test]
+    fn cold_existing_account() -> Result<(), Error> {
+        test_ok(true, false)
+    }
+
+    #[test]
+    fn warm_existing_account() -> Result<(), Error> {
+        test_ok(true, true)
+    }
+
+    fn test_ok(exists: bool, is_warm: bool) -> Result<(), Error> {
+        let address = address!("0xaabbccddee000000000000000000000000000000");
+
+        // Make the account warm, if needed, by first getting its balance.
+        let mut code = Bytecode::default();
+        if is_warm {
+            code.append(&bytecode! {
+                PUSH20(address.to_word())
+                BALANCE
+                POP
+            });
+        }
+        code.append(&bytecode! {
+            PUSH20(address.to_word())
+            BALANCE
+            STOP
+        });
+        let balance = if exists {
+            Word::from(800u64)
+        } else {
+            Word::zero()
+        };
+
+        // Get the execution steps from the external tracer
+        let block: GethData = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(address!("0x0000000000000000000000000000000000000010"))
+                    .balance(Word::from(1u64 << 20))
+                    .code(code.clone());
+                if exists {
+                    accs[1].address(address).balance(balance);
+                } else {
+                    accs[1]
+                        .address(address!("0x0000000000000000000000000000000000000020"))
+                        .balance(Word::from(1u64 << 20));
+                }
+                accs[2]
+                    .address(address!("0x0000000000000000000000000000000000cafe01"))
+                    .balance(Word::from(1u64 << 20));
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[0].address).from(accs[2].address);
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        // Check that `address` is in access list as a result of bus mapping.
+        assert!(builder.sdb.add_account_to_access_list(address));
+
+        let tx_id = 1;
+        let transaction = &builder.block.txs()[tx_id - 1];
+        let call_id = transaction.calls()[0].call_id;
+
+        let indices = transaction
+            .steps()
+            .iter()
+            .filter(|step| step.exec_state == ExecState::Op(OpcodeId::BALANCE))
+            .last()
+            .unwrap()
+            .bus_mapping_instance
+            .clone();
+        let container = builder.block.container;
+        assert_eq!(
+            {
+                let operation = &container.stack[indices[0].as_usize()];
+                (operation.rw(), operation.op())
+            },
+            (
+                RW::READ,
+                &StackOp {
+                    call_id,
+                    address: StackAddress::from(1023u32),
+                    value: address.to_word()
+                }
+            )
+        );
+        assert_eq!(
+            {
+                let operation = &container.call_context[indices[1].as_usize()];
+                (operation.rw(), operation.op())
+            },
+            (
+                RW::READ,
+                &CallContextOp {
+                    call_id,
+                    field: CallContextField::TxId,
+                    value: tx_id.into()
+                }
+            )
+        );
+        assert_eq!(
+            {
+                let operation = &container.tx_access_list_account[indices[4].as_usize()];
+                (operation.rw(), operation.op())
+            },
+            (
+                RW::WRITE,
+                &TxAccessListAccountOp {
+                    tx_id,
+                    address,
+                    is_warm: true,
+                    is_warm_prev: is_warm
+                }
+            )
+        );
+        assert_eq!(
+            {
+                let operation = &container.account[indices[5].as_usize()];
+                (operation.rw(), operation.op())
+            },
+            (
+                RW::READ,
+                &AccountOp {
+                    address,
+                    field: AccountField::Balance,
+                    value: balance,
+                    value_prev: balance,
+                }
+            )
+        );
+        assert_eq!(
+            {
+                let operation = &container.stack[indices[6].as_usize()];
+                (operation.rw(), operation.op())
+            },
+            (
+                RW::WRITE,
+                &StackOp {
+                    call_id,
+                    address: 1023u32.into(),
+                    value: balance,
+                }
+            )
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    Error,
+};
+use eth_types::{evm_types::NUM_PREV_BLOCK_ALLOWED, GethExecStep, Word};
+
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Blockhash;
+
+impl Opcode for Blockhash {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        let stack_address = geth_step.stack.last_filled();
+
+        // Pop the block number off stack
+        let block_number = geth_step.stack.last()?;
+        state.stack_read(&mut exec_step, stack_address, block_number)?;
+
+        // Only the most recent 256 blocks excluding the current one are
+        // accessible, otherwise the hash is zero.
+        let current_block_number = state.block.number;
+        let block_hash = if block_number < current_block_number
+            && current_block_number - block_number <= NUM_PREV_BLOCK_ALLOWED.into()
+        {
+            let history_hashes = &state.block.history_hashes;
+            history_hashes
+                .len()
+                .checked_sub((current_block_number - block_number).as_usize())
+                .and_then(|idx| history_hashes.get(idx))
+                .cloned()
+                .unwrap_or_default()
+        } else {
+            Word::zero()
+        };
+
+        // Stack write of the block hash
+        debug_assert_eq!(block_hash, geth_steps[1].stack.last()?);
+        state.stack_write(&mut exec_step, stack_address, block_hash)?;
+
+        Ok(vec![exec_step])
+    }
+}
+
+#[cfg(test)]
+mod blockhash_tests {
+    use super::*;
+    use crate::{
+        circuit_input_builder::ExecState,
+        mock::BlockData,
+        operation::{StackOp, RW},
+    };
+    use eth_types::{
+        bytecode,
+        evm_types::{OpcodeId, StackAddress},
+        geth_types::GethData,
+    };
+    use mock::test_ctx::{helpers::*, TestContext};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn blockhash_opcode_impl() -> Result<(), Error> {
+        test_ok(0xcafe, 0xcafe - 1)
+    }
+
+    #[test]
+    fn blockhash_opcode_oldest_block() -> Result<(), Error> {
+        test_ok(0xcafe, 0xcafe - NUM_PREV_BLOCK_ALLOWED)
+    }
+
+    #[test]
+    fn blockhash_opcode_out_of_range() -> Result<(), Error> {
+        test_ok(0xcafe, 0xcafe - NUM_PREV_BLOCK_ALLOWED - 1)?;
+        test_ok(0xcafe, 0xcafe)
+    }
+
+    fn test_ok(current_block_number: u64, block_number: u64) -> Result<(), Error> {
+        let code = bytecode! {
+            PUSH8(block_number)
+            #[start]
+            BLOCKHASH
+            STOP
+        };
+        let history_hashes = (0..NUM_PREV_BLOCK_ALLOWED)
+            .map(|idx| Word::from(0x1234_5678u64 * (idx + 1)))
+            .collect::<Vec<_>>();
+        let block_hash = if block_number < current_block_number
+            && current_block_number - block_number <= NUM_PREV_BLOCK_ALLOWED
+        {
+            history_hashes
+                [(NUM_PREV_BLOCK_ALLOWED - (current_block_number - block_number)) as usize]
+        } else {
+            Word::zero()
+        };
+
+        // Get the execution steps from the external tracer
+        let block: GethData = TestContext::<2, 1>::new(
+            Some(history_hashes),
+            account_0_code_account_1_no_code(code),
+            tx_from_1_to_0,
+            |block, _tx| block.number(current_block_number),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::BLOCKHASH))
+            .unwrap();
+
+        assert_eq!(
+            [0, 1]
+                .map(|idx| &builder.block.container.stack[step.bus_mapping_instance[idx].as_usize()])
+                .map(|operation| (operation.rw(), operation.op())),
+            [
+                (
+                    RW::READ,
+                    &StackOp::new(1, StackAddress(1023usize), block_number.into())
+                ),
+                (
+                    RW::WRITE,
+                    &StackOp::new(1, StackAddress(1023usize), block_hash)
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+}
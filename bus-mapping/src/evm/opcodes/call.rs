@@ -1,21 +1,19 @@
 use super::Opcode;
 use crate::{
-    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    circuit_input_builder::{CallKind, CircuitInputStateRef, ExecStep},
     operation::{AccountField, CallContextField, TxAccessListAccountOp, RW},
     Error,
 };
 use eth_types::{
-    evm_types::{
-        gas_utils::{eip150_gas, memory_expansion_gas_cost},
-        GasCost,
-    },
-    GethExecStep, ToWord,
+    evm_types::gas_utils::{call_gas_cost, eip150_gas},
+    GethExecStep, ToAddress, ToWord, Word,
 };
 use keccak256::EMPTY_HASH;
 use log::warn;
 
 /// Placeholder structure used to implement [`Opcode`] trait over it
-/// corresponding to the `OpcodeId::CALL` `OpcodeId`.
+/// corresponding to the `OpcodeId::CALL`, `OpcodeId::CALLCODE`,
+/// `OpcodeId::DELEGATECALL` and `OpcodeId::STATICCALL` `OpcodeId`s.
 #[derive(Debug, Copy, Clone)]
 pub(crate) struct Call;
 
@@ -54,7 +52,25 @@ impl Opcode for Call {
             state.call_context_read(&mut exec_step, current_call.call_id, field, value);
         }
 
-        for i in 0..7 {
+        // `DELEGATECALL` keeps the caller and the value of the current call.
+        if call.kind == CallKind::DelegateCall {
+            for (field, value) in [
+                (
+                    CallContextField::CallerAddress,
+                    current_call.caller_address.to_word(),
+                ),
+                (CallContextField::Value, current_call.value),
+            ] {
+                state.call_context_read(&mut exec_step, current_call.call_id, field, value);
+            }
+        }
+
+        // `CALL` and `CALLCODE` take an extra value argument.
+        let n_pop = match call.kind {
+            CallKind::Call | CallKind::CallCode => 7,
+            _ => 6,
+        };
+        for i in 0..n_pop {
             state.stack_read(
                 &mut exec_step,
                 geth_step.stack.nth_last_filled(i),
@@ -64,17 +80,20 @@ impl Opcode for Call {
 
         state.stack_write(
             &mut exec_step,
-            geth_step.stack.nth_last_filled(6),
+            geth_step.stack.nth_last_filled(n_pop - 1),
             (call.is_success as u64).into(),
         )?;
 
-        let is_warm = state.sdb.check_account_in_access_list(&call.address);
+        // The account whose code is executed, which differs from the callee
+        // for `CALLCODE` and `DELEGATECALL`.
+        let code_address = geth_step.stack.nth_last(1)?.to_address();
+        let is_warm = state.sdb.check_account_in_access_list(&code_address);
         state.push_op_reversible(
             &mut exec_step,
             RW::WRITE,
             TxAccessListAccountOp {
                 tx_id,
-                address: call.address,
+                address: code_address,
                 is_warm: true,
                 is_warm_prev: is_warm,
             },
@@ -93,14 +112,20 @@ impl Opcode for Call {
             state.call_context_read(&mut exec_step, call.call_id, field, value);
         }
 
+        // Only `CALL` moves value to another account, `CALLCODE` transfers it
+        // to the current account itself and the others transfer nothing.
+        let transfer_value = match call.kind {
+            CallKind::Call | CallKind::CallCode => call.value,
+            _ => Word::zero(),
+        };
         state.transfer(
             &mut exec_step,
-            call.caller_address,
+            current_call.address,
             call.address,
-            call.value,
+            transfer_value,
         )?;
 
-        let (_, callee_account) = state.sdb.get_account(&call.address);
+        let (_, callee_account) = state.sdb.get_account(&code_address);
         let is_account_empty = callee_account.is_empty();
         let callee_nonce = callee_account.nonce;
         let callee_code_hash = callee_account.code_hash;
@@ -108,7 +133,7 @@ impl Opcode for Call {
             (AccountField::Nonce, callee_nonce),
             (AccountField::CodeHash, callee_code_hash.to_word()),
         ] {
-            state.account_read(&mut exec_step, call.address, field, value, value)?;
+            state.account_read(&mut exec_step, code_address, field, value, value)?;
         }
 
         // Calculate next_memory_word_size and callee_gas_left manually in case
@@ -121,29 +146,19 @@ impl Opcode for Call {
         .into_iter()
         .max()
         .unwrap();
-        let has_value = !call.value.is_zero();
-        let gas_cost = if is_warm {
-            GasCost::WARM_ACCESS.as_u64()
-        } else {
-            GasCost::COLD_ACCOUNT_ACCESS.as_u64()
-        } + if has_value {
-            GasCost::CALL_WITH_VALUE.as_u64()
-                + if is_account_empty {
-                    GasCost::NEW_ACCOUNT.as_u64()
-                } else {
-                    0
-                }
-        } else {
-            0
-        } + memory_expansion_gas_cost(
+        let has_value = !transfer_value.is_zero();
+        let gas_cost = call_gas_cost(
             geth_step.memory.word_size() as u64,
             next_memory_word_size,
+            is_warm,
+            has_value,
+            call.kind == CallKind::Call && is_account_empty,
         );
         let callee_gas_left = eip150_gas(geth_step.gas.0 - gas_cost, geth_step.stack.last()?);
 
         // There are 3 branches from here.
         match (
-            state.is_precompiled(&call.address),
+            state.is_precompiled(&code_address),
             callee_code_hash.to_fixed_bytes() == *EMPTY_HASH,
         ) {
             // 1. Call to precompiled.
@@ -172,7 +187,7 @@ impl Opcode for Call {
                     ),
                     (
                         CallContextField::StackPointer,
-                        (geth_step.stack.stack_pointer().0 + 6).into(),
+                        (geth_step.stack.stack_pointer().0 + n_pop - 1).into(),
                     ),
                     (
                         CallContextField::GasLeft,
@@ -0,0 +1,323 @@
+use super::{sha3::gen_memory_to_rlc_acc_copy_event, Opcode};
+use crate::{
+    circuit_input_builder::{
+        get_create_init_code, CircuitInputStateRef, CopyDataType, CopyEvent, CopyStep, ExecStep,
+        NumberOrHash,
+    },
+    operation::{AccountField, AccountOp, CallContextField, TxAccessListAccountOp, RW},
+    Error,
+};
+use eth_types::{
+    evm_types::gas_utils::create_gas_cost, Address, GethExecStep, ToBigEndian, ToWord, Word,
+};
+use ethers_core::utils::keccak256;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OpcodeId::CREATE`](crate::evm::OpcodeId::CREATE)
+/// and [`OpcodeId::CREATE2`](crate::evm::OpcodeId::CREATE2) `OpcodeId`s.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Create<const IS_CREATE2: bool>;
+
+impl<const IS_CREATE2: bool> Opcode for Create<IS_CREATE2> {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+
+        let tx_id = state.tx_ctx.id();
+        let current_call = state.call()?.clone();
+        // The contract address is derived from the caller's nonce before
+        // it's increased.
+        let call = state.parse_call(geth_step)?;
+        let caller_nonce = state.sdb.get_nonce(&call.caller_address);
+
+        // NOTE: For `RwCounterEndOfReversion` we use the `0` value as a placeholder,
+        // and later set the proper value in
+        // `CircuitInputBuilder::set_value_ops_call_context_rwc_eor`
+        for (field, value) in [
+            (CallContextField::TxId, tx_id.into()),
+            (CallContextField::RwCounterEndOfReversion, 0.into()),
+            (
+                CallContextField::IsPersistent,
+                (current_call.is_persistent as u64).into(),
+            ),
+            (
+                CallContextField::CalleeAddress,
+                current_call.address.to_word(),
+            ),
+            (
+                CallContextField::IsStatic,
+                (current_call.is_static as u64).into(),
+            ),
+            (CallContextField::Depth, current_call.depth.into()),
+        ] {
+            state.call_context_read(&mut exec_step, current_call.call_id, field, value);
+        }
+
+        let n_pop = if IS_CREATE2 { 4 } else { 3 };
+        for i in 0..n_pop {
+            state.stack_read(
+                &mut exec_step,
+                geth_step.stack.nth_last_filled(i),
+                geth_step.stack.nth_last(i)?,
+            )?;
+        }
+        let address = if call.is_success {
+            call.address.to_word()
+        } else {
+            Word::zero()
+        };
+        state.stack_write(
+            &mut exec_step,
+            geth_step.stack.nth_last_filled(n_pop - 1),
+            address,
+        )?;
+
+        // Increase caller's nonce
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            AccountOp {
+                address: call.caller_address,
+                field: AccountField::Nonce,
+                value: (caller_nonce + 1).into(),
+                value_prev: caller_nonce.into(),
+            },
+        )?;
+
+        // Add callee into access list
+        let is_warm = state.sdb.check_account_in_access_list(&call.address);
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            TxAccessListAccountOp {
+                tx_id,
+                address: call.address,
+                is_warm: true,
+                is_warm_prev: is_warm,
+            },
+        )?;
+
+        // Switch to callee's call context
+        state.push_call(call.clone(), geth_step);
+
+        for (field, value) in [
+            (CallContextField::RwCounterEndOfReversion, 0.into()),
+            (
+                CallContextField::IsPersistent,
+                (call.is_persistent as u64).into(),
+            ),
+        ] {
+            state.call_context_read(&mut exec_step, call.call_id, field, value);
+        }
+
+        // Increase callee's nonce
+        let callee_nonce = state.sdb.get_nonce(&call.address);
+        debug_assert!(callee_nonce == 0);
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            AccountOp {
+                address: call.address,
+                field: AccountField::Nonce,
+                value: 1.into(),
+                value_prev: 0.into(),
+            },
+        )?;
+
+        state.transfer(
+            &mut exec_step,
+            call.caller_address,
+            call.address,
+            call.value,
+        )?;
+
+        let offset = geth_step.stack.nth_last(1)?;
+        let length = geth_step.stack.nth_last(2)?;
+        let init_code = get_create_init_code(geth_step)?;
+        let next_memory_word_size = if length.is_zero() {
+            geth_step.memory.word_size() as u64
+        } else {
+            std::cmp::max(
+                geth_step.memory.word_size() as u64,
+                (offset.low_u64() + length.low_u64() + 31) / 32,
+            )
+        };
+        let gas_cost = create_gas_cost(
+            geth_step.memory.word_size() as u64,
+            next_memory_word_size,
+            length.low_u64(),
+            IS_CREATE2,
+        );
+        let gas_available = geth_step.gas.0 - gas_cost;
+        let callee_gas_left = gas_available - gas_available / 64;
+
+        // There are 2 branches from here.
+        if init_code.is_empty() {
+            // 1. Create with empty init code.
+            for (field, value) in [
+                (CallContextField::LastCalleeId, 0.into()),
+                (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+                (CallContextField::LastCalleeReturnDataLength, 0.into()),
+            ] {
+                state.call_context_write(&mut exec_step, current_call.call_id, field, value);
+            }
+            state.handle_return(geth_step)?;
+        } else {
+            // 2. Create with non-empty init code.
+            for (field, value) in [
+                (
+                    CallContextField::ProgramCounter,
+                    (geth_step.pc.0 + 1).into(),
+                ),
+                (
+                    CallContextField::StackPointer,
+                    (geth_step.stack.stack_pointer().0 + n_pop - 1).into(),
+                ),
+                (
+                    CallContextField::GasLeft,
+                    (geth_step.gas.0 - gas_cost - callee_gas_left).into(),
+                ),
+                (CallContextField::MemorySize, next_memory_word_size.into()),
+                (
+                    CallContextField::ReversibleWriteCounter,
+                    (exec_step.reversible_write_counter + 2).into(),
+                ),
+            ] {
+                state.call_context_write(&mut exec_step, current_call.call_id, field, value);
+            }
+
+            for (field, value) in [
+                (CallContextField::CallerId, current_call.call_id.into()),
+                (CallContextField::TxId, tx_id.into()),
+                (CallContextField::Depth, call.depth.into()),
+                (
+                    CallContextField::CallerAddress,
+                    call.caller_address.to_word(),
+                ),
+                (CallContextField::CalleeAddress, call.address.to_word()),
+                (CallContextField::CallDataOffset, 0.into()),
+                (CallContextField::CallDataLength, 0.into()),
+                (CallContextField::ReturnDataOffset, 0.into()),
+                (CallContextField::ReturnDataLength, 0.into()),
+                (CallContextField::Value, call.value),
+                (CallContextField::IsSuccess, (call.is_success as u64).into()),
+                (CallContextField::IsStatic, 0.into()),
+                (CallContextField::LastCalleeId, 0.into()),
+                (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+                (CallContextField::LastCalleeReturnDataLength, 0.into()),
+                (CallContextField::IsRoot, 0.into()),
+                (CallContextField::IsCreate, 1.into()),
+                (CallContextField::CodeHash, call.code_hash.to_word()),
+            ] {
+                state.call_context_read(&mut exec_step, call.call_id, field, value);
+            }
+
+            // Memory reads of the init code, which is still in the caller's
+            // memory.
+            let copy_event = gen_memory_to_rlc_acc_copy_event(
+                state,
+                &mut exec_step,
+                current_call.call_id,
+                offset.low_u64(),
+                &init_code,
+            )?;
+            state.push_copy(copy_event);
+        }
+
+        // Keccak inputs of the init code hash and of the contract address.
+        let address_preimage = if IS_CREATE2 {
+            let salt = geth_step.stack.nth_last(3)?;
+            std::iter::once(0xff)
+                .chain(call.caller_address.to_fixed_bytes())
+                .chain(salt.to_be_bytes())
+                .chain(keccak256(&init_code))
+                .collect()
+        } else {
+            create_address_preimage(call.caller_address, caller_nonce)
+        };
+        state.push_sha3_input(init_code);
+        state.push_sha3_input(address_preimage);
+
+        Ok(vec![exec_step])
+    }
+}
+
+/// Returns the RLP encoding of `[sender, nonce]`, which is hashed to derive the
+/// address of a contract created by `CREATE`.
+pub(crate) fn create_address_preimage(sender: Address, nonce: u64) -> Vec<u8> {
+    let nonce_bytes: Vec<u8> = nonce
+        .to_be_bytes()
+        .into_iter()
+        .skip_while(|byte| *byte == 0)
+        .collect();
+    let nonce_rlp = match nonce_bytes.as_slice() {
+        [] => vec![0x80],
+        [byte] if *byte < 0x80 => vec![*byte],
+        bytes => std::iter::once(0x80 + bytes.len() as u8)
+            .chain(bytes.iter().copied())
+            .collect(),
+    };
+    std::iter::once(0xc0 + 21 + nonce_rlp.len() as u8)
+        .chain(std::iter::once(0x80 + 20))
+        .chain(sender.to_fixed_bytes())
+        .chain(nonce_rlp)
+        .collect()
+}
+
+/// Generate the copy event of the init code of a creation transaction from the
+/// tx calldata into the RLC accumulator, which involves no rw operations.
+pub(crate) fn gen_tx_calldata_to_rlc_acc_copy_event(
+    state: &CircuitInputStateRef,
+    init_code: &[u8],
+) -> CopyEvent {
+    let tx_id = state.tx_ctx.id();
+    let call_id = state.tx.calls()[0].call_id;
+    let rwc = state.block_ctx.rwc;
+    let steps = init_code
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, byte)| {
+            [
+                CopyStep {
+                    addr: idx as u64,
+                    tag: CopyDataType::TxCalldata,
+                    rw: RW::READ,
+                    value: *byte,
+                    is_code: None,
+                    is_pad: false,
+                    rwc,
+                    rwc_inc_left: 0,
+                },
+                CopyStep {
+                    addr: idx as u64,
+                    tag: CopyDataType::RlcAcc,
+                    rw: RW::WRITE,
+                    value: *byte,
+                    is_code: None,
+                    is_pad: false,
+                    rwc,
+                    rwc_inc_left: 0,
+                },
+            ]
+        })
+        .collect();
+
+    CopyEvent {
+        src_addr: 0,
+        src_addr_end: init_code.len() as u64,
+        src_type: CopyDataType::TxCalldata,
+        src_id: NumberOrHash::Number(tx_id),
+        dst_addr: 0,
+        dst_type: CopyDataType::RlcAcc,
+        dst_id: NumberOrHash::Number(call_id),
+        log_id: None,
+        length: init_code.len() as u64,
+        steps,
+        tx_id,
+        call_id,
+        pc: 0.into(),
+    }
+}
@@ -0,0 +1,45 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::ExecError,
+    Error,
+};
+use eth_types::GethExecStep;
+
+use super::Opcode;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the errors raised by the code deposit of
+/// [`OpcodeId::RETURN`](crate::evm::OpcodeId::RETURN) in a creation call:
+/// [`ExecError::MaxCodeSizeExceeded`], [`ExecError::InvalidCreationCode`] and
+/// [`ExecError::CodeStoreOutOfGas`].
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorCodeDeposit;
+
+impl Opcode for ErrorCodeDeposit {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        let error = state.get_code_deposit_err(geth_step)?.ok_or_else(|| {
+            Error::UnexpectedExecStepError("code deposit without error", geth_step.clone())
+        })?;
+
+        let offset = geth_step.stack.nth_last(0)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(0), offset)?;
+        let length = geth_step.stack.nth_last(1)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(1), length)?;
+
+        // The first byte of the code is read to show it's invalid.
+        if error == ExecError::InvalidCreationCode {
+            let byte = geth_step.memory.0[offset.as_usize()];
+            state.memory_read(&mut exec_step, offset.as_usize().into(), byte)?;
+        }
+
+        exec_step.error = Some(error);
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::ExecError,
+    evm::OpcodeId,
+    Error,
+};
+use eth_types::GethExecStep;
+
+use super::Opcode;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`ExecError::InvalidJump`] error raised by
+/// [`OpcodeId::JUMP`] and [`OpcodeId::JUMPI`].
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorInvalidJump;
+
+impl Opcode for ErrorInvalidJump {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::InvalidJump);
+
+        let destination = geth_step.stack.nth_last(0)?;
+        state.stack_read(
+            &mut exec_step,
+            geth_step.stack.nth_last_filled(0),
+            destination,
+        )?;
+        if geth_step.op == OpcodeId::JUMPI {
+            let condition = geth_step.stack.nth_last(1)?;
+            state.stack_read(
+                &mut exec_step,
+                geth_step.stack.nth_last_filled(1),
+                condition,
+            )?;
+        }
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
+
+#[cfg(test)]
+mod error_invalid_jump_tests {
+    use super::*;
+    use crate::{
+        mock::BlockData,
+        operation::{CallContextField, CallContextOp, StackOp, RW},
+    };
+    use eth_types::{bytecode, evm_types::StackAddress, geth_types::GethData, Bytecode, Word};
+    use mock::test_ctx::{helpers::*, TestContext};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn invalid_jump_out_of_range() {
+        let code = bytecode! {
+            PUSH1(0x10)
+            JUMP
+            STOP
+        };
+        test_ok(code, vec![(1023, 0x10.into())]);
+    }
+
+    #[test]
+    fn invalid_jumpi_not_jumpdest() {
+        let code = bytecode! {
+            PUSH1(0x1)
+            PUSH1(0x5)
+            JUMPI
+            STOP
+        };
+        test_ok(code, vec![(1022, 0x5.into()), (1023, 0x1.into())]);
+    }
+
+    #[test]
+    fn invalid_jump_into_push_data() {
+        let code = bytecode! {
+            PUSH1(0x4)
+            JUMP
+            PUSH1(0x5b)
+            STOP
+        };
+        test_ok(code, vec![(1023, 0x4.into())]);
+    }
+
+    fn test_ok(code: Bytecode, stack_reads: Vec<(usize, Word)>) {
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            account_0_code_account_1_no_code(code),
+            tx_from_1_to_0,
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.error == Some(ExecError::InvalidJump))
+            .unwrap();
+
+        for (idx, (address, value)) in stack_reads.iter().enumerate() {
+            let operation =
+                &builder.block.container.stack[step.bus_mapping_instance[idx].as_usize()];
+            assert_eq!(
+                (operation.rw(), operation.op()),
+                (RW::READ, &StackOp::new(1, StackAddress(*address), *value))
+            );
+        }
+
+        let operation = &builder.block.container.call_context
+            [step.bus_mapping_instance[stack_reads.len()].as_usize()];
+        assert_eq!(
+            (operation.rw(), operation.op()),
+            (
+                RW::READ,
+                &CallContextOp {
+                    call_id: 1,
+                    field: CallContextField::IsSuccess,
+                    value: Word::zero(),
+                }
+            )
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    operation::{CallContextField, TxAccessListAccountOp, RW},
+    Error,
+};
+use eth_types::{evm_types::GasCost, GethExecStep, ToAddress, ToWord};
+
+use super::Opcode;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OogError::AccountAccess`] error raised by
+/// [`OpcodeId::BALANCE`](crate::evm::OpcodeId::BALANCE),
+/// [`OpcodeId::EXTCODESIZE`](crate::evm::OpcodeId::EXTCODESIZE) and
+/// [`OpcodeId::EXTCODEHASH`](crate::evm::OpcodeId::EXTCODEHASH).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorOOGAccountAccess;
+
+impl Opcode for ErrorOOGAccountAccess {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::OutOfGas(OogError::AccountAccess));
+
+        let address = geth_step.stack.last()?.to_address();
+        state.stack_read(
+            &mut exec_step,
+            geth_step.stack.last_filled(),
+            address.to_word(),
+        )?;
+
+        let tx_id = state.tx_ctx.id();
+        state.call_context_read(
+            &mut exec_step,
+            state.call()?.call_id,
+            CallContextField::TxId,
+            tx_id.into(),
+        );
+
+        // The access list is only read, since the account isn't accessed when
+        // the gas is insufficient.
+        let is_warm = state.sdb.check_account_in_access_list(&address);
+        state.push_op(
+            &mut exec_step,
+            RW::READ,
+            TxAccessListAccountOp {
+                tx_id,
+                address,
+                is_warm,
+                is_warm_prev: is_warm,
+            },
+        );
+
+        exec_step.gas_cost = if is_warm {
+            GasCost::WARM_ACCESS
+        } else {
+            GasCost::COLD_ACCOUNT_ACCESS
+        };
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    evm::OpcodeId,
+    operation::{AccountField, CallContextField, TxAccessListAccountOp, RW},
+    Error,
+};
+use eth_types::{
+    evm_types::{gas_utils::call_gas_cost, GasCost},
+    GethExecStep, ToAddress, ToWord, Word,
+};
+
+use super::{next_memory_word_size, Opcode};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OogError::Call`], [`OogError::CallCode`],
+/// [`OogError::DelegateCall`] and [`OogError::StaticCall`] errors raised by
+/// the respective call opcodes.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorOOGCall;
+
+impl Opcode for ErrorOOGCall {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        let oog_error = match geth_step.op {
+            OpcodeId::CALL => OogError::Call,
+            OpcodeId::CALLCODE => OogError::CallCode,
+            OpcodeId::DELEGATECALL => OogError::DelegateCall,
+            OpcodeId::STATICCALL => OogError::StaticCall,
+            op => unreachable!("ErrorOOGCall is raised by {:?}", op),
+        };
+        exec_step.error = Some(ExecError::OutOfGas(oog_error));
+
+        // `CALL` and `CALLCODE` take an extra value argument.
+        let has_value_arg = matches!(geth_step.op, OpcodeId::CALL | OpcodeId::CALLCODE);
+        let n_pop = if has_value_arg { 7 } else { 6 };
+        let mut values = Vec::with_capacity(n_pop);
+        for i in 0..n_pop {
+            let value = geth_step.stack.nth_last(i)?;
+            state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(i), value)?;
+            values.push(value);
+        }
+        let callee_address = values[1].to_address();
+        let value = if has_value_arg {
+            values[2]
+        } else {
+            Word::zero()
+        };
+        let memory_args = &values[n_pop - 4..];
+
+        let tx_id = state.tx_ctx.id();
+        state.call_context_read(
+            &mut exec_step,
+            state.call()?.call_id,
+            CallContextField::TxId,
+            tx_id.into(),
+        );
+
+        // The access list is only read, since the callee isn't accessed when
+        // the gas is insufficient.
+        let is_warm = state.sdb.check_account_in_access_list(&callee_address);
+        state.push_op(
+            &mut exec_step,
+            RW::READ,
+            TxAccessListAccountOp {
+                tx_id,
+                address: callee_address,
+                is_warm,
+                is_warm_prev: is_warm,
+            },
+        );
+
+        let (_, callee_account) = state.sdb.get_account(&callee_address);
+        let is_account_empty = callee_account.is_empty();
+        for (field, value) in [
+            (AccountField::Nonce, callee_account.nonce),
+            (AccountField::Balance, callee_account.balance),
+            (AccountField::CodeHash, callee_account.code_hash.to_word()),
+        ] {
+            state.account_read(&mut exec_step, callee_address, field, value, value)?;
+        }
+
+        // Gas cost of the call without the gas passed to the callee
+        let curr_memory_word_size = geth_step.memory.word_size() as u64;
+        let next_memory_word_size =
+            next_memory_word_size(curr_memory_word_size, memory_args[0], memory_args[1]).max(
+                next_memory_word_size(curr_memory_word_size, memory_args[2], memory_args[3]),
+            );
+        exec_step.gas_cost = GasCost(call_gas_cost(
+            curr_memory_word_size,
+            next_memory_word_size,
+            is_warm,
+            !value.is_zero(),
+            geth_step.op == OpcodeId::CALL && is_account_empty,
+        ));
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    Error,
+};
+use eth_types::{
+    evm_types::{gas_utils::create_gas_cost, GasCost},
+    GethExecStep, Word,
+};
+
+use super::{capped_memory_length, next_memory_word_size, Opcode};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OogError::Create2`] error raised by
+/// [`OpcodeId::CREATE2`](crate::evm::OpcodeId::CREATE2).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorOOGCreate2;
+
+impl Opcode for ErrorOOGCreate2 {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::OutOfGas(OogError::Create2));
+
+        // The salt isn't read, since it doesn't affect the gas cost.
+        let mut values = [Word::zero(); 3];
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = geth_step.stack.nth_last(i)?;
+            state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(i), *value)?;
+        }
+        let [_, memory_offset, length] = values;
+
+        // Gas cost of CREATE2 including the init code hashing and memory
+        // expansion
+        let curr_memory_word_size = geth_step.memory.word_size() as u64;
+        let next_memory_word_size =
+            next_memory_word_size(curr_memory_word_size, memory_offset, length);
+        exec_step.gas_cost = GasCost(create_gas_cost(
+            curr_memory_word_size,
+            next_memory_word_size,
+            capped_memory_length(length),
+            true,
+        ));
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    evm::OpcodeId,
+    Error,
+};
+use eth_types::{
+    evm_types::{gas_utils::memory_expansion_gas_cost, GasCost},
+    GethExecStep,
+};
+
+use super::{next_memory_word_size, Opcode};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OogError::DynamicMemoryExpansion`] error raised by
+/// [`OpcodeId::CREATE`], [`OpcodeId::RETURN`] and [`OpcodeId::REVERT`].
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorOOGDynamicMemory;
+
+impl Opcode for ErrorOOGDynamicMemory {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::OutOfGas(OogError::DynamicMemoryExpansion));
+
+        // `CREATE` has the value on top of the memory offset and length, which
+        // is skipped.
+        let is_create = geth_step.op == OpcodeId::CREATE;
+        let stack_offset = is_create as usize;
+        let memory_offset = geth_step.stack.nth_last(stack_offset)?;
+        state.stack_read(
+            &mut exec_step,
+            geth_step.stack.nth_last_filled(stack_offset),
+            memory_offset,
+        )?;
+        let length = geth_step.stack.nth_last(stack_offset + 1)?;
+        state.stack_read(
+            &mut exec_step,
+            geth_step.stack.nth_last_filled(stack_offset + 1),
+            length,
+        )?;
+
+        let curr_memory_word_size = geth_step.memory.word_size() as u64;
+        let next_memory_word_size =
+            next_memory_word_size(curr_memory_word_size, memory_offset, length);
+        let constant_gas_cost = if is_create {
+            OpcodeId::CREATE.constant_gas_cost().as_u64()
+        } else {
+            0
+        };
+        exec_step.gas_cost = GasCost(
+            constant_gas_cost
+                + memory_expansion_gas_cost(curr_memory_word_size, next_memory_word_size),
+        );
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
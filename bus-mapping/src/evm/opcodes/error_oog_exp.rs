@@ -0,0 +1,39 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    Error,
+};
+use eth_types::{
+    evm_types::{gas_utils::exp_gas_cost, GasCost},
+    GethExecStep,
+};
+
+use super::Opcode;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OogError::Exp`] error raised by
+/// [`OpcodeId::EXP`](crate::evm::OpcodeId::EXP).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorOOGExp;
+
+impl Opcode for ErrorOOGExp {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::OutOfGas(OogError::Exp));
+
+        let base = geth_step.stack.nth_last(0)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(0), base)?;
+        let exponent = geth_step.stack.nth_last(1)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(1), exponent)?;
+
+        // Gas cost of EXP including the dynamic part for the exponent byte size
+        exec_step.gas_cost = GasCost(exp_gas_cost(exponent));
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
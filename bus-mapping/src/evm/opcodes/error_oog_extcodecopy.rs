@@ -0,0 +1,86 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    operation::{CallContextField, TxAccessListAccountOp, RW},
+    Error,
+};
+use eth_types::{
+    evm_types::{gas_utils::memory_copier_gas_cost, GasCost},
+    GethExecStep, ToAddress, ToWord,
+};
+
+use super::Opcode;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OogError::ExtCodeCopy`] error raised by
+/// [`OpcodeId::EXTCODECOPY`](crate::evm::OpcodeId::EXTCODECOPY).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorOOGExtcodecopy;
+
+impl Opcode for ErrorOOGExtcodecopy {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::OutOfGas(OogError::ExtCodeCopy));
+
+        let address = geth_step.stack.nth_last(0)?.to_address();
+        state.stack_read(
+            &mut exec_step,
+            geth_step.stack.nth_last_filled(0),
+            address.to_word(),
+        )?;
+        let mut values = [0u64; 3];
+        for (i, value) in values.iter_mut().enumerate() {
+            let word = geth_step.stack.nth_last(i + 1)?;
+            state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(i + 1), word)?;
+            *value = word.low_u64();
+        }
+        let [dst_offset, _, length] = values;
+
+        let tx_id = state.tx_ctx.id();
+        state.call_context_read(
+            &mut exec_step,
+            state.call()?.call_id,
+            CallContextField::TxId,
+            tx_id.into(),
+        );
+
+        // The access list is only read, since the account isn't accessed when
+        // the gas is insufficient.
+        let is_warm = state.sdb.check_account_in_access_list(&address);
+        state.push_op(
+            &mut exec_step,
+            RW::READ,
+            TxAccessListAccountOp {
+                tx_id,
+                address,
+                is_warm,
+                is_warm_prev: is_warm,
+            },
+        );
+
+        // Gas cost of EXTCODECOPY including the account access, copy and
+        // memory expansion
+        let curr_memory_word_size = geth_step.memory.word_size() as u64;
+        let next_memory_word_size = if length == 0 {
+            curr_memory_word_size
+        } else {
+            curr_memory_word_size.max((dst_offset + length + 31) / 32)
+        };
+        let access_gas_cost = if is_warm {
+            GasCost::WARM_ACCESS
+        } else {
+            GasCost::COLD_ACCOUNT_ACCESS
+        };
+        exec_step.gas_cost = GasCost(
+            access_gas_cost.as_u64()
+                + memory_copier_gas_cost(curr_memory_word_size, next_memory_word_size, length),
+        );
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
@@ -0,0 +1,54 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    evm::OpcodeId,
+    Error,
+};
+use eth_types::{
+    evm_types::{gas_utils::log_gas_cost, GasCost},
+    GethExecStep,
+};
+
+use super::{capped_memory_length, next_memory_word_size, Opcode};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OogError::Log`] error raised by [`OpcodeId::LOG0`]
+/// to [`OpcodeId::LOG4`].
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorOOGLog;
+
+impl Opcode for ErrorOOGLog {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::OutOfGas(OogError::Log));
+
+        // Only the memory offset and length are read, since the topics don't
+        // affect the gas cost.
+        let memory_offset = geth_step.stack.nth_last(0)?;
+        state.stack_read(
+            &mut exec_step,
+            geth_step.stack.nth_last_filled(0),
+            memory_offset,
+        )?;
+        let length = geth_step.stack.nth_last(1)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(1), length)?;
+
+        let num_topics = geth_step.op.as_u64() - OpcodeId::LOG0.as_u64();
+        let curr_memory_word_size = geth_step.memory.word_size() as u64;
+        let next_memory_word_size =
+            next_memory_word_size(curr_memory_word_size, memory_offset, length);
+        exec_step.gas_cost = GasCost(log_gas_cost(
+            curr_memory_word_size,
+            next_memory_word_size,
+            num_topics,
+            capped_memory_length(length),
+        ));
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
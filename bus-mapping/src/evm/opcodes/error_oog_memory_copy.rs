@@ -0,0 +1,53 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    Error,
+};
+use eth_types::{
+    evm_types::{gas_utils::memory_copier_gas_cost, GasCost},
+    GethExecStep, Word,
+};
+
+use super::{capped_memory_length, next_memory_word_size, Opcode};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OogError::MemoryCopy`] error raised by
+/// [`OpcodeId::CALLDATACOPY`](crate::evm::OpcodeId::CALLDATACOPY),
+/// [`OpcodeId::CODECOPY`](crate::evm::OpcodeId::CODECOPY) and
+/// [`OpcodeId::RETURNDATACOPY`](crate::evm::OpcodeId::RETURNDATACOPY).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorOOGMemoryCopy;
+
+impl Opcode for ErrorOOGMemoryCopy {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::OutOfGas(OogError::MemoryCopy));
+
+        let mut values = [Word::zero(); 3];
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = geth_step.stack.nth_last(i)?;
+            state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(i), *value)?;
+        }
+        let [dst_offset, _, length] = values;
+
+        // Gas cost of the copy including the memory expansion
+        let curr_memory_word_size = geth_step.memory.word_size() as u64;
+        let next_memory_word_size =
+            next_memory_word_size(curr_memory_word_size, dst_offset, length);
+        exec_step.gas_cost = GasCost(
+            geth_step.op.constant_gas_cost().as_u64()
+                + memory_copier_gas_cost(
+                    curr_memory_word_size,
+                    next_memory_word_size,
+                    capped_memory_length(length),
+                ),
+        );
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
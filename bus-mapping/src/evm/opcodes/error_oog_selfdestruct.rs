@@ -0,0 +1,91 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    operation::{AccountField, CallContextField, TxAccessListAccountOp, RW},
+    Error,
+};
+use eth_types::{
+    evm_types::{gas_utils::selfdestruct_gas_cost, GasCost},
+    GethExecStep, ToAddress, ToWord,
+};
+
+use super::Opcode;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OogError::SelfDestruct`] error raised by
+/// [`OpcodeId::SELFDESTRUCT`](crate::evm::OpcodeId::SELFDESTRUCT).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorOOGSelfdestruct;
+
+impl Opcode for ErrorOOGSelfdestruct {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::OutOfGas(OogError::SelfDestruct));
+
+        let beneficiary = geth_step.stack.last()?;
+        state.stack_read(&mut exec_step, geth_step.stack.last_filled(), beneficiary)?;
+        let beneficiary = beneficiary.to_address();
+
+        let tx_id = state.tx_ctx.id();
+        let call = state.call()?.clone();
+        for (field, value) in [
+            (CallContextField::TxId, tx_id.into()),
+            (CallContextField::CalleeAddress, call.address.to_word()),
+        ] {
+            state.call_context_read(&mut exec_step, call.call_id, field, value);
+        }
+
+        // The access list is only read, since the beneficiary isn't accessed
+        // when the gas is insufficient.
+        let is_warm = state.sdb.check_account_in_access_list(&beneficiary);
+        state.push_op(
+            &mut exec_step,
+            RW::READ,
+            TxAccessListAccountOp {
+                tx_id,
+                address: beneficiary,
+                is_warm,
+                is_warm_prev: is_warm,
+            },
+        );
+
+        // The balance to send and the beneficiary account are read to check if
+        // a new account would be created.
+        let (found, account) = state.sdb.get_account(&call.address);
+        if !found {
+            return Err(Error::AccountNotFound(call.address));
+        }
+        let value = account.balance;
+        state.account_read(
+            &mut exec_step,
+            call.address,
+            AccountField::Balance,
+            value,
+            value,
+        )?;
+        let (_, beneficiary_account) = state.sdb.get_account(&beneficiary);
+        let is_account_empty = beneficiary_account.is_empty();
+        for (field, value) in [
+            (AccountField::Nonce, beneficiary_account.nonce),
+            (AccountField::Balance, beneficiary_account.balance),
+            (
+                AccountField::CodeHash,
+                beneficiary_account.code_hash.to_word(),
+            ),
+        ] {
+            state.account_read(&mut exec_step, beneficiary, field, value, value)?;
+        }
+
+        exec_step.gas_cost = GasCost(selfdestruct_gas_cost(
+            is_warm,
+            !value.is_zero() && is_account_empty,
+        ));
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    Error,
+};
+use eth_types::{
+    evm_types::{gas_utils::sha3_gas_cost, GasCost},
+    GethExecStep,
+};
+
+use super::{capped_memory_length, next_memory_word_size, Opcode};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OogError::Sha3`] error raised by
+/// [`OpcodeId::SHA3`](crate::evm::OpcodeId::SHA3).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorOOGSha3;
+
+impl Opcode for ErrorOOGSha3 {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::OutOfGas(OogError::Sha3));
+
+        let memory_offset = geth_step.stack.nth_last(0)?;
+        state.stack_read(
+            &mut exec_step,
+            geth_step.stack.nth_last_filled(0),
+            memory_offset,
+        )?;
+        let length = geth_step.stack.nth_last(1)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(1), length)?;
+
+        let curr_memory_word_size = geth_step.memory.word_size() as u64;
+        let next_memory_word_size =
+            next_memory_word_size(curr_memory_word_size, memory_offset, length);
+        exec_step.gas_cost = GasCost(sha3_gas_cost(
+            curr_memory_word_size,
+            next_memory_word_size,
+            capped_memory_length(length),
+        ));
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
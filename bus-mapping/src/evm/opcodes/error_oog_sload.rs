@@ -0,0 +1,70 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    operation::{CallContextField, TxAccessListAccountStorageOp, RW},
+    Error,
+};
+use eth_types::{
+    evm_types::{gas_utils::sload_gas_cost, GasCost},
+    GethExecStep, ToWord,
+};
+
+use super::Opcode;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OogError::Sload`] error raised by
+/// [`OpcodeId::SLOAD`](crate::evm::OpcodeId::SLOAD).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorOOGSload;
+
+impl Opcode for ErrorOOGSload {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::OutOfGas(OogError::Sload));
+
+        let call_id = state.call()?.call_id;
+        let contract_addr = state.call()?.address;
+        let tx_id = state.tx_ctx.id();
+        state.call_context_read(
+            &mut exec_step,
+            call_id,
+            CallContextField::TxId,
+            tx_id.into(),
+        );
+        state.call_context_read(
+            &mut exec_step,
+            call_id,
+            CallContextField::CalleeAddress,
+            contract_addr.to_word(),
+        );
+
+        let key = geth_step.stack.last()?;
+        state.stack_read(&mut exec_step, geth_step.stack.last_filled(), key)?;
+
+        // The access list is only read, since the storage slot isn't accessed
+        // when the gas is insufficient.
+        let is_warm = state
+            .sdb
+            .check_account_storage_in_access_list(&(contract_addr, key));
+        state.push_op(
+            &mut exec_step,
+            RW::READ,
+            TxAccessListAccountStorageOp {
+                tx_id,
+                address: contract_addr,
+                key,
+                is_warm,
+                is_warm_prev: is_warm,
+            },
+        );
+
+        exec_step.gas_cost = GasCost(sload_gas_cost(is_warm));
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    operation::{CallContextField, StorageOp, TxAccessListAccountStorageOp, RW},
+    Error,
+};
+use eth_types::{
+    evm_types::{gas_utils::sstore_gas_cost, GasCost},
+    GethExecStep, ToWord,
+};
+
+use super::Opcode;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OogError::Sstore`] error raised by
+/// [`OpcodeId::SSTORE`](crate::evm::OpcodeId::SSTORE), either when the gas left
+/// is less than the gas cost or not more than the reentrancy sentry
+/// [`SSTORE_SENTRY_GAS`](eth_types::evm_types::SSTORE_SENTRY_GAS).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorOOGSstore;
+
+impl Opcode for ErrorOOGSstore {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::OutOfGas(OogError::Sstore));
+
+        let call_id = state.call()?.call_id;
+        let contract_addr = state.call()?.address;
+        let tx_id = state.tx_ctx.id();
+        state.call_context_read(
+            &mut exec_step,
+            call_id,
+            CallContextField::TxId,
+            tx_id.into(),
+        );
+        state.call_context_read(
+            &mut exec_step,
+            call_id,
+            CallContextField::CalleeAddress,
+            contract_addr.to_word(),
+        );
+
+        let key = geth_step.stack.nth_last(0)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(0), key)?;
+        let value = geth_step.stack.nth_last(1)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(1), value)?;
+
+        // The storage slot and the access list are only read, since neither
+        // of them is written when the gas is insufficient.
+        let (_, value_prev) = state.sdb.get_storage(&contract_addr, &key);
+        let value_prev = *value_prev;
+        let (_, committed_value) = state.sdb.get_committed_storage(&contract_addr, &key);
+        let committed_value = *committed_value;
+        state.push_op(
+            &mut exec_step,
+            RW::READ,
+            StorageOp::new(
+                contract_addr,
+                key,
+                value_prev,
+                value_prev,
+                tx_id,
+                committed_value,
+            ),
+        );
+
+        let is_warm = state
+            .sdb
+            .check_account_storage_in_access_list(&(contract_addr, key));
+        state.push_op(
+            &mut exec_step,
+            RW::READ,
+            TxAccessListAccountStorageOp {
+                tx_id,
+                address: contract_addr,
+                key,
+                is_warm,
+                is_warm_prev: is_warm,
+            },
+        );
+
+        exec_step.gas_cost = GasCost(sstore_gas_cost(value, value_prev, committed_value, is_warm));
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
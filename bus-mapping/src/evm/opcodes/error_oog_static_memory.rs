@@ -0,0 +1,50 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    evm::OpcodeId,
+    Error,
+};
+use eth_types::{
+    evm_types::{gas_utils::memory_expansion_gas_cost, GasCost},
+    GethExecStep,
+};
+
+use super::{next_memory_word_size, Opcode};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OogError::StaticMemoryExpansion`] error raised by
+/// [`OpcodeId::MLOAD`], [`OpcodeId::MSTORE`] and [`OpcodeId::MSTORE8`].
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorOOGStaticMemory;
+
+impl Opcode for ErrorOOGStaticMemory {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::OutOfGas(OogError::StaticMemoryExpansion));
+
+        // Only the memory offset is read, since the value to store doesn't
+        // affect the gas cost.
+        let memory_offset = geth_step.stack.last()?;
+        state.stack_read(&mut exec_step, geth_step.stack.last_filled(), memory_offset)?;
+
+        let length = if geth_step.op == OpcodeId::MSTORE8 {
+            1
+        } else {
+            32
+        };
+        let curr_memory_word_size = geth_step.memory.word_size() as u64;
+        let next_memory_word_size =
+            next_memory_word_size(curr_memory_word_size, memory_offset, length.into());
+        exec_step.gas_cost = GasCost(
+            geth_step.op.constant_gas_cost().as_u64()
+                + memory_expansion_gas_cost(curr_memory_word_size, next_memory_word_size),
+        );
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{
+    circuit_input_builder::{CallKind, CircuitInputStateRef, ExecStep},
+    error::ExecError,
+    operation::{AccountField, CallContextField, TxAccessListAccountOp, RW},
+    Error,
+};
+use eth_types::{GethExecStep, ToAddress, ToWord};
+
+use super::Opcode;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`ExecError::Depth`] and
+/// [`ExecError::InsufficientBalance`] errors raised by `*CALL*` and `CREATE*`
+/// in the precheck before the callee runs. The current call isn't halted but
+/// continues with `0` pushed on the stack.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorPrecheck;
+
+impl Opcode for ErrorPrecheck {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = state.get_step_err(geth_step, geth_steps.get(1))?;
+
+        let tx_id = state.tx_ctx.id();
+        let current_call = state.call()?.clone();
+
+        // NOTE: For `RwCounterEndOfReversion` we use the `0` value as a placeholder,
+        // and later set the proper value in
+        // `CircuitInputBuilder::set_value_ops_call_context_rwc_eor`
+        for (field, value) in [
+            (CallContextField::TxId, tx_id.into()),
+            (CallContextField::RwCounterEndOfReversion, 0.into()),
+            (
+                CallContextField::IsPersistent,
+                (current_call.is_persistent as u64).into(),
+            ),
+            (
+                CallContextField::CalleeAddress,
+                current_call.address.to_word(),
+            ),
+            (CallContextField::Depth, current_call.depth.into()),
+        ] {
+            state.call_context_read(&mut exec_step, current_call.call_id, field, value);
+        }
+
+        // The value is read first, since it's on top of stack for `CREATE*`
+        // but third for `CALL` and `CALLCODE`.
+        let kind = CallKind::try_from(geth_step.op)?;
+        let (stack_indices, n_pop) = match kind {
+            CallKind::Call | CallKind::CallCode => (vec![2, 0, 1, 3, 4, 5, 6], 7),
+            CallKind::DelegateCall | CallKind::StaticCall => (vec![0, 1, 2, 3, 4, 5], 6),
+            CallKind::Create => (vec![0, 1, 2], 3),
+            CallKind::Create2 => (vec![0, 1, 2], 4),
+        };
+        for i in stack_indices {
+            state.stack_read(
+                &mut exec_step,
+                geth_step.stack.nth_last_filled(i),
+                geth_step.stack.nth_last(i)?,
+            )?;
+        }
+        state.stack_write(
+            &mut exec_step,
+            geth_step.stack.nth_last_filled(n_pop - 1),
+            0.into(),
+        )?;
+
+        // `*CALL*` adds the callee to access list before the precheck, while
+        // `CREATE*` doesn't. To keep the layout the same, `CREATE*` writes the
+        // current address, which is always warm, with its value unchanged.
+        let is_call_op = !matches!(kind, CallKind::Create | CallKind::Create2);
+        let address = if is_call_op {
+            geth_step.stack.nth_last(1)?.to_address()
+        } else {
+            current_call.address
+        };
+        let is_warm = state.sdb.check_account_in_access_list(&address);
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            TxAccessListAccountOp {
+                tx_id,
+                address,
+                is_warm: is_call_op || is_warm,
+                is_warm_prev: is_warm,
+            },
+        )?;
+
+        // The callee account is read to tell if a `CALL` with value creates a
+        // new account.
+        if is_call_op {
+            let (_, callee_account) = state.sdb.get_account(&address);
+            for (field, value) in [
+                (AccountField::Nonce, callee_account.nonce),
+                (AccountField::Balance, callee_account.balance),
+                (AccountField::CodeHash, callee_account.code_hash.to_word()),
+            ] {
+                state.account_read(&mut exec_step, address, field, value, value)?;
+            }
+        }
+
+        if exec_step.error == Some(ExecError::InsufficientBalance) {
+            let (found, caller_account) = state.sdb.get_account(&current_call.address);
+            if !found {
+                return Err(Error::AccountNotFound(current_call.address));
+            }
+            let caller_balance = caller_account.balance;
+            state.account_read(
+                &mut exec_step,
+                current_call.address,
+                AccountField::Balance,
+                caller_balance,
+                caller_balance,
+            )?;
+        }
+
+        // The return data of last callee is cleared.
+        for (field, value) in [
+            (CallContextField::LastCalleeId, 0.into()),
+            (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+            (CallContextField::LastCalleeReturnDataLength, 0.into()),
+        ] {
+            state.call_context_write(&mut exec_step, current_call.call_id, field, value);
+        }
+
+        // The callee is still recorded as a failed call without any step, in
+        // the same way as a call to an account with empty code.
+        let call = state.parse_call(geth_step)?;
+        state.push_call(call, geth_step);
+        state.handle_return(geth_step)?;
+
+        Ok(vec![exec_step])
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::ExecError,
+    operation::CallContextField,
+    Error,
+};
+use eth_types::GethExecStep;
+
+use super::Opcode;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`ExecError::ReturnDataOutOfBounds`] error raised by
+/// [`OpcodeId::RETURNDATACOPY`](crate::evm::OpcodeId::RETURNDATACOPY) when the
+/// copied range exceeds the return data of the last callee.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorReturnDataOutOfBound;
+
+impl Opcode for ErrorReturnDataOutOfBound {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::ReturnDataOutOfBounds);
+
+        for i in 0..3 {
+            state.stack_read(
+                &mut exec_step,
+                geth_step.stack.nth_last_filled(i),
+                geth_step.stack.nth_last(i)?,
+            )?;
+        }
+
+        let call = state.call()?.clone();
+        state.call_context_read(
+            &mut exec_step,
+            call.call_id,
+            CallContextField::LastCalleeReturnDataLength,
+            call.last_callee_return_data_length.into(),
+        );
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
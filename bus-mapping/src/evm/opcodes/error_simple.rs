@@ -0,0 +1,30 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    Error,
+};
+use eth_types::GethExecStep;
+
+use super::Opcode;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the errors which don't involve any operation other than
+/// ending the call in exception, which are
+/// [`ExecError::InvalidOpcode`](crate::error::ExecError::InvalidOpcode),
+/// [`ExecError::StackOverflow`](crate::error::ExecError::StackOverflow) and
+/// [`ExecError::StackUnderflow`](crate::error::ExecError::StackUnderflow).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorSimple;
+
+impl Opcode for ErrorSimple {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = state.get_step_err(geth_step, geth_steps.get(1))?;
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::ExecError,
+    evm::OpcodeId,
+    operation::CallContextField,
+    Error,
+};
+use eth_types::GethExecStep;
+
+use super::Opcode;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`ExecError::WriteProtection`] error raised by a state
+/// modifying opcode, or by [`OpcodeId::CALL`] with value, in a static call.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ErrorWriteProtection;
+
+impl Opcode for ErrorWriteProtection {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        exec_step.error = Some(ExecError::WriteProtection);
+
+        // The value of CALL is read to show it's not zero.
+        if geth_step.op == OpcodeId::CALL {
+            for i in 0..3 {
+                state.stack_read(
+                    &mut exec_step,
+                    geth_step.stack.nth_last_filled(i),
+                    geth_step.stack.nth_last(i)?,
+                )?;
+            }
+        }
+
+        let call_id = state.call()?.call_id;
+        state.call_context_read(
+            &mut exec_step,
+            call_id,
+            CallContextField::IsStatic,
+            1.into(),
+        );
+
+        state.handle_exception(&mut exec_step, geth_steps)?;
+        Ok(vec![exec_step])
+    }
+}
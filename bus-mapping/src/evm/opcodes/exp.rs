@@ -0,0 +1,173 @@
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep, ExpEvent, ExpStep},
+    Error,
+};
+use eth_types::{evm_types::gas_utils::exp_gas_cost, GethExecStep, U256};
+
+use super::Opcode;
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Exponentiation;
+
+/// Compute `base ^ exponent (mod 2^256)` by squaring, and collect the
+/// intermediate multiplication steps `a * b == d (mod 2^256)`.
+///
+/// Steps are collected from the first squaring of the base up to the
+/// multiplication that produces the final result.
+fn exp_by_squaring(base: U256, exponent: U256, steps: &mut Vec<(U256, U256, U256)>) -> U256 {
+    if exponent.is_zero() {
+        return U256::one();
+    }
+    if exponent == U256::one() {
+        return base;
+    }
+
+    let (exponent_div2, odd) = exponent.div_mod(U256::from(2));
+    let exp1 = exp_by_squaring(base, exponent_div2, steps);
+    let (exp2, _) = exp1.overflowing_mul(exp1);
+    steps.push((exp1, exp1, exp2));
+
+    if odd.is_zero() {
+        // exponent is even
+        exp2
+    } else {
+        // exponent is odd
+        let (exp, _) = exp2.overflowing_mul(base);
+        steps.push((exp2, base, exp));
+        exp
+    }
+}
+
+impl Opcode for Exponentiation {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+
+        let base = geth_step.stack.nth_last(0)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(0), base)?;
+        let exponent = geth_step.stack.nth_last(1)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(1), exponent)?;
+
+        let (exponentiation, _) = base.overflowing_pow(exponent);
+        state.stack_write(
+            &mut exec_step,
+            geth_steps[1].stack.last_filled(),
+            exponentiation,
+        )?;
+
+        debug_assert_eq!(exec_step.gas_cost.as_u64(), exp_gas_cost(exponent));
+
+        let mut steps = Vec::new();
+        let exponentiation_calc = exp_by_squaring(base, exponent, &mut steps);
+        debug_assert_eq!(exponentiation, exponentiation_calc);
+        state.push_exponentiation(ExpEvent {
+            identifier: exec_step.rwc.0,
+            base,
+            exponent,
+            exponentiation,
+            steps: steps.into_iter().rev().map(ExpStep::from).collect(),
+        });
+
+        Ok(vec![exec_step])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{circuit_input_builder::ExecState, mock::BlockData, operation::RW};
+    use eth_types::{bytecode, evm_types::OpcodeId, geth_types::GethData, Word};
+    use mock::test_ctx::{helpers::*, TestContext};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_exp_by_squaring() {
+        let mut steps = Vec::new();
+        let exponentiation = exp_by_squaring(23.into(), 123.into(), &mut steps);
+        let (expected, _) = U256::from(23).overflowing_pow(123.into());
+        assert_eq!(exponentiation, expected);
+        // 123 = 0b1111011, so 6 squarings and 5 multiplications by the base.
+        assert_eq!(steps.len(), 11);
+        for (a, b, d) in steps.iter() {
+            assert_eq!(a.overflowing_mul(*b).0, *d);
+        }
+        assert_eq!(steps.last().unwrap().2, exponentiation);
+
+        let mut steps = Vec::new();
+        assert_eq!(exp_by_squaring(23.into(), 1.into(), &mut steps), 23.into());
+        assert_eq!(exp_by_squaring(23.into(), 0.into(), &mut steps), 1.into());
+        assert!(steps.is_empty());
+    }
+
+    fn test_ok(base: Word, exponent: Word) {
+        let code = bytecode! {
+            PUSH32(exponent)
+            PUSH32(base)
+            EXP
+            STOP
+        };
+
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            account_0_code_account_1_no_code(code),
+            tx_from_1_to_0,
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::EXP))
+            .unwrap();
+
+        let (exponentiation, _) = base.overflowing_pow(exponent);
+        assert_eq!(step.gas_cost.as_u64(), exp_gas_cost(exponent));
+        assert_eq!(
+            step.bus_mapping_instance
+                .iter()
+                .map(|op_ref| {
+                    let operation = &builder.block.container.stack[op_ref.as_usize()];
+                    (operation.rw(), operation.op().value)
+                })
+                .collect::<Vec<_>>(),
+            vec![
+                (RW::READ, base),
+                (RW::READ, exponent),
+                (RW::WRITE, exponentiation),
+            ]
+        );
+
+        assert_eq!(builder.block.exp_events.len(), 1);
+        let exp_event = &builder.block.exp_events[0];
+        assert_eq!(exp_event.identifier, step.rwc.0);
+        assert_eq!(exp_event.base, base);
+        assert_eq!(exp_event.exponent, exponent);
+        assert_eq!(exp_event.exponentiation, exponentiation);
+        if exponent > Word::one() {
+            assert_eq!(exp_event.steps[0].d, exponentiation);
+            let last_step = exp_event.steps.last().unwrap();
+            assert_eq!((last_step.a, last_step.b), (base, base));
+        } else {
+            assert!(exp_event.steps.is_empty());
+        }
+    }
+
+    #[test]
+    fn exp_opcode_impl() {
+        test_ok(0.into(), 0.into());
+        test_ok(3.into(), 1.into());
+        test_ok(2.into(), 255.into());
+        test_ok(23.into(), 123.into());
+        test_ok(Word::MAX, Word::MAX);
+    }
+}
@@ -0,0 +1,299 @@
+use super::{codecopy::gen_copy_steps, Opcode};
+use crate::{
+    circuit_input_builder::{
+        CircuitInputStateRef, CopyDataType, CopyEvent, ExecStep, NumberOrHash,
+    },
+    operation::{AccountField, CallContextField, TxAccessListAccountOp, RW},
+    Error,
+};
+use eth_types::{Bytecode, GethExecStep, ToAddress, ToWord, H256, U256};
+use keccak256::EMPTY_HASH;
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Extcodecopy;
+
+impl Opcode for Extcodecopy {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let exec_steps = vec![gen_extcodecopy_step(state, geth_step)?];
+        let copy_event = gen_copy_event(state, geth_step)?;
+        state.push_copy(copy_event);
+        Ok(exec_steps)
+    }
+}
+
+fn gen_extcodecopy_step(
+    state: &mut CircuitInputStateRef,
+    geth_step: &GethExecStep,
+) -> Result<ExecStep, Error> {
+    let mut exec_step = state.new_step(geth_step)?;
+
+    let external_address = geth_step.stack.nth_last(0)?.to_address();
+    let dest_offset = geth_step.stack.nth_last(1)?;
+    let code_offset = geth_step.stack.nth_last(2)?;
+    let length = geth_step.stack.nth_last(3)?;
+
+    // stack reads
+    state.stack_read(
+        &mut exec_step,
+        geth_step.stack.nth_last_filled(0),
+        external_address.to_word(),
+    )?;
+    state.stack_read(
+        &mut exec_step,
+        geth_step.stack.nth_last_filled(1),
+        dest_offset,
+    )?;
+    state.stack_read(
+        &mut exec_step,
+        geth_step.stack.nth_last_filled(2),
+        code_offset,
+    )?;
+    state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(3), length)?;
+
+    for (field, value) in [
+        (CallContextField::TxId, U256::from(state.tx_ctx.id())),
+        (
+            CallContextField::RwCounterEndOfReversion,
+            U256::from(state.call()?.rw_counter_end_of_reversion as u64),
+        ),
+        (
+            CallContextField::IsPersistent,
+            U256::from(state.call()?.is_persistent as u64),
+        ),
+    ] {
+        state.call_context_read(&mut exec_step, state.call()?.call_id, field, value);
+    }
+
+    let is_warm = state.sdb.check_account_in_access_list(&external_address);
+    state.push_op_reversible(
+        &mut exec_step,
+        RW::WRITE,
+        TxAccessListAccountOp {
+            tx_id: state.tx_ctx.id(),
+            address: external_address,
+            is_warm: true,
+            is_warm_prev: is_warm,
+        },
+    )?;
+
+    let code_hash = state.sdb.get_account(&external_address).1.code_hash;
+    state.account_read(
+        &mut exec_step,
+        external_address,
+        AccountField::CodeHash,
+        code_hash.to_word(),
+        code_hash.to_word(),
+    )?;
+
+    Ok(exec_step)
+}
+
+fn gen_copy_event(
+    state: &mut CircuitInputStateRef,
+    geth_step: &GethExecStep,
+) -> Result<CopyEvent, Error> {
+    let external_address = geth_step.stack.nth_last(0)?.to_address();
+    let dst_offset = geth_step.stack.nth_last(1)?.as_u64();
+    let code_offset = geth_step.stack.nth_last(2)?.as_u64();
+    let length = geth_step.stack.nth_last(3)?.as_u64();
+
+    let code_hash = state.sdb.get_account(&external_address).1.code_hash;
+    // Accounts without code aren't necessarily in the code db
+    let bytecode: Bytecode = if code_hash == H256(*EMPTY_HASH) {
+        Bytecode::default()
+    } else {
+        state.code(code_hash)?.into()
+    };
+    let src_addr_end = bytecode.to_vec().len() as u64;
+
+    let mut exec_step = state.new_step(geth_step)?;
+    let copy_steps = gen_copy_steps(
+        state,
+        &mut exec_step,
+        code_offset,
+        dst_offset,
+        length,
+        src_addr_end,
+        &bytecode,
+    )?;
+
+    Ok(CopyEvent {
+        src_type: CopyDataType::Bytecode,
+        src_id: NumberOrHash::Hash(code_hash),
+        src_addr: code_offset,
+        src_addr_end,
+        dst_type: CopyDataType::Memory,
+        dst_id: NumberOrHash::Number(state.call()?.call_id),
+        dst_addr: dst_offset,
+        log_id: None,
+        length,
+        steps: copy_steps,
+        tx_id: state.tx_ctx.id(),
+        call_id: state.call()?.call_id,
+        pc: exec_step.pc,
+    })
+}
+
+#[cfg(test)]
+mod extcodecopy_tests {
+    use eth_types::{
+        address, bytecode,
+        evm_types::{MemoryAddress, OpcodeId, StackAddress},
+        geth_types::GethData,
+        ToWord, Word, H256,
+    };
+    use ethers_core::utils::keccak256;
+    use mock::TestContext;
+
+    use crate::{
+        circuit_input_builder::{CopyDataType, ExecState, NumberOrHash},
+        mock::BlockData,
+        operation::{MemoryOp, StackOp, TxAccessListAccountOp, RW},
+    };
+
+    #[test]
+    fn extcodecopy_opcode_impl() {
+        test_ok(0x00, 0x00, 0x40);
+        test_ok(0x20, 0x02, 0x10);
+    }
+
+    fn test_ok(dst_offset: usize, code_offset: usize, size: usize) {
+        let external_address = address!("0xaabbccddee000000000000000000000000000000");
+        let code_ext = bytecode! {
+            PUSH1(0x01)
+            PUSH1(0x02)
+            ADD
+            STOP
+        };
+        let code = bytecode! {
+            PUSH32(size)
+            PUSH32(code_offset)
+            PUSH32(dst_offset)
+            PUSH20(external_address.to_word())
+            EXTCODECOPY
+            STOP
+        };
+
+        let block: GethData = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(address!("0x0000000000000000000000000000000000000010"))
+                    .balance(Word::from(1u64 << 20))
+                    .code(code.clone());
+                accs[1].address(external_address).code(code_ext.clone());
+                accs[2]
+                    .address(address!("0x0000000000000000000000000000000000cafe01"))
+                    .balance(Word::from(1u64 << 20));
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[0].address).from(accs[2].address);
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::EXTCODECOPY))
+            .unwrap();
+
+        let expected_call_id = builder.block.txs()[0].calls()[step.call_index].call_id;
+
+        assert_eq!(
+            [0, 1, 2, 3]
+                .map(|idx| &builder.block.container.stack[step.bus_mapping_instance[idx].as_usize()])
+                .map(|op| (op.rw(), op.op())),
+            [
+                (
+                    RW::READ,
+                    &StackOp::new(1, StackAddress::from(1020), external_address.to_word()),
+                ),
+                (
+                    RW::READ,
+                    &StackOp::new(1, StackAddress::from(1021), Word::from(dst_offset)),
+                ),
+                (
+                    RW::READ,
+                    &StackOp::new(1, StackAddress::from(1022), Word::from(code_offset)),
+                ),
+                (
+                    RW::READ,
+                    &StackOp::new(1, StackAddress::from(1023), Word::from(size)),
+                ),
+            ]
+        );
+        assert_eq!(
+            {
+                let operation = &builder.block.container.tx_access_list_account
+                    [step.bus_mapping_instance[7].as_usize()];
+                (operation.rw(), operation.op())
+            },
+            (
+                RW::WRITE,
+                &TxAccessListAccountOp {
+                    tx_id: 1,
+                    address: external_address,
+                    is_warm: true,
+                    is_warm_prev: false
+                }
+            )
+        );
+
+        // RW table memory writes.
+        assert_eq!(
+            (0..size)
+                .map(|idx| &builder.block.container.memory[idx])
+                .map(|op| (op.rw(), op.op().clone()))
+                .collect::<Vec<(RW, MemoryOp)>>(),
+            (0..size)
+                .map(|idx| {
+                    (
+                        RW::WRITE,
+                        MemoryOp::new(
+                            1,
+                            MemoryAddress::from(dst_offset + idx),
+                            if code_offset + idx < code_ext.to_vec().len() {
+                                code_ext.to_vec()[code_offset + idx]
+                            } else {
+                                0
+                            },
+                        ),
+                    )
+                })
+                .collect::<Vec<(RW, MemoryOp)>>(),
+        );
+
+        let copy_events = builder.block.copy_events.clone();
+        assert_eq!(copy_events.len(), 1);
+        assert_eq!(copy_events[0].steps.len(), 2 * size);
+        assert_eq!(
+            copy_events[0].src_id,
+            NumberOrHash::Hash(H256(keccak256(&code_ext.to_vec())))
+        );
+        assert_eq!(copy_events[0].src_addr as usize, code_offset);
+        assert_eq!(
+            copy_events[0].src_addr_end as usize,
+            code_ext.to_vec().len()
+        );
+        assert_eq!(copy_events[0].src_type, CopyDataType::Bytecode);
+        assert_eq!(
+            copy_events[0].dst_id,
+            NumberOrHash::Number(expected_call_id)
+        );
+        assert_eq!(copy_events[0].dst_addr as usize, dst_offset);
+        assert_eq!(copy_events[0].dst_type, CopyDataType::Memory);
+        assert_eq!(copy_events[0].length as usize, size);
+    }
+}
@@ -1,7 +1,11 @@
-use super::Opcode;
-use crate::circuit_input_builder::{CircuitInputStateRef, ExecStep};
-use crate::Error;
-use eth_types::GethExecStep;
+use super::{sha3::gen_memory_to_rlc_acc_copy_event, Opcode};
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    evm::OpcodeId,
+    operation::{AccountField, AccountOp, CallContextField, RW},
+    Error,
+};
+use eth_types::{GethExecStep, ToWord, Word};
 
 /// Placeholder structure used to implement [`Opcode`] trait over it
 /// corresponding to the [`OpcodeId::RETURN`](crate::evm::OpcodeId::RETURN).
@@ -14,9 +18,75 @@ impl Opcode for Return {
         geth_steps: &[GethExecStep],
     ) -> Result<Vec<ExecStep>, Error> {
         let geth_step = &geth_steps[0];
-        let exec_step = state.new_step(geth_step)?;
+        let mut exec_step = state.new_step(geth_step)?;
 
-        // TODO: Generate associated operations of RETURN
+        let offset = geth_step.stack.nth_last(0)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(0), offset)?;
+        let length = geth_step.stack.nth_last(1)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(1), length)?;
+
+        // Deposit the returned code if it's a successful creation.
+        let call = state.call()?.clone();
+        if call.is_create() && call.is_success && geth_step.op == OpcodeId::RETURN {
+            let code = if length.is_zero() {
+                Vec::new()
+            } else {
+                geth_step
+                    .memory
+                    .read_chunk(offset.low_u64().into(), length.low_u64().into())
+            };
+
+            let copy_event = gen_memory_to_rlc_acc_copy_event(
+                state,
+                &mut exec_step,
+                call.call_id,
+                offset.low_u64(),
+                &code,
+            )?;
+            state.push_copy(copy_event);
+
+            let (_, callee_account) = state.sdb.get_account(&call.address);
+            let code_hash_prev = callee_account.code_hash;
+            let code_hash = state.code_db.insert(code.clone());
+            state.push_sha3_input(code);
+            state.push_op_reversible(
+                &mut exec_step,
+                RW::WRITE,
+                AccountOp {
+                    address: call.address,
+                    field: AccountField::CodeHash,
+                    value: code_hash.to_word(),
+                    value_prev: code_hash_prev.to_word(),
+                },
+            )?;
+        }
+
+        // Record the return data region in the caller's context. The return
+        // data of a creation is empty, since the returned bytes are deposited
+        // as the code of the new contract.
+        if !call.is_root {
+            let (return_data_offset, return_data_length) = if call.is_create() || length.is_zero() {
+                (Word::zero(), Word::zero())
+            } else {
+                (offset, length)
+            };
+            let caller_id = state.caller()?.call_id;
+            for (field, value) in [
+                (CallContextField::LastCalleeId, call.call_id.into()),
+                (
+                    CallContextField::LastCalleeReturnDataOffset,
+                    return_data_offset,
+                ),
+                (
+                    CallContextField::LastCalleeReturnDataLength,
+                    return_data_length,
+                ),
+            ] {
+                state.call_context_write(&mut exec_step, caller_id, field, value);
+            }
+        }
+
+        // TODO: Generate the remaining associated operations of RETURN
 
         state.handle_return(geth_step)?;
         Ok(vec![exec_step])
@@ -0,0 +1,219 @@
+use super::{revert::gen_memory_copy_event, Opcode};
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    operation::CallContextField,
+    Error,
+};
+use eth_types::GethExecStep;
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Returndatacopy;
+
+impl Opcode for Returndatacopy {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+
+        let memory_offset = geth_step.stack.nth_last(0)?;
+        let data_offset = geth_step.stack.nth_last(1)?;
+        let length = geth_step.stack.nth_last(2)?;
+        for (i, value) in [memory_offset, data_offset, length].into_iter().enumerate() {
+            state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(i), value)?;
+        }
+
+        let call = state.call()?.clone();
+        for (field, value) in [
+            (CallContextField::LastCalleeId, call.last_callee_id.into()),
+            (
+                CallContextField::LastCalleeReturnDataOffset,
+                call.last_callee_return_data_offset.into(),
+            ),
+            (
+                CallContextField::LastCalleeReturnDataLength,
+                call.last_callee_return_data_length.into(),
+            ),
+        ] {
+            state.call_context_read(&mut exec_step, call.call_id, field, value);
+        }
+
+        // The return data is still in the memory of the last callee, and the
+        // range is checked to be in bound, so no padding is needed.
+        let (data_offset, length) = (data_offset.low_u64(), length.low_u64());
+        if length > 0 {
+            let bytes = state.call_ctx()?.return_data
+                [data_offset as usize..(data_offset + length) as usize]
+                .to_vec();
+            let copy_event = gen_memory_copy_event(
+                state,
+                &mut exec_step,
+                call.last_callee_id,
+                call.call_id,
+                call.last_callee_return_data_offset + data_offset,
+                memory_offset.low_u64(),
+                &bytes,
+            )?;
+            state.push_copy(copy_event);
+        }
+
+        Ok(vec![exec_step])
+    }
+}
+
+#[cfg(test)]
+mod returndatacopy_tests {
+    use crate::{
+        circuit_input_builder::{ExecState, NumberOrHash},
+        mock::BlockData,
+        operation::{CallContextField, CallContextOp, MemoryOp, RW},
+    };
+    use eth_types::{bytecode, evm_types::OpcodeId, geth_types::GethData, ToWord, Word};
+
+    use mock::test_ctx::TestContext;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn returndatacopy_opcode_impl() {
+        let (addr_a, addr_b) = (mock::MOCK_ACCOUNTS[0], mock::MOCK_ACCOUNTS[1]);
+
+        // code B returns 32 bytes from its memory.
+        let return_data = (1..=32u8).collect::<Vec<u8>>();
+        let return_data_length = 0x20usize;
+        let code_b = bytecode! {
+            PUSH32(Word::from_big_endian(&return_data))
+            PUSH1(0x00) // offset
+            MSTORE
+            PUSH1(return_data_length) // length
+            PUSH1(0x00) // offset
+            RETURN
+        };
+
+        // code A calls code B, and copies part of the return data.
+        let dst_offset = 0x00usize;
+        let offset = 0x08usize;
+        let copy_size = 0x10usize;
+        let code_a = bytecode! {
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x00) // value
+            PUSH32(addr_b.to_word()) // addr
+            PUSH32(0x1_0000) // gas
+            CALL
+            PUSH1(copy_size)  // size
+            PUSH1(offset)     // offset
+            PUSH1(dst_offset) // dst_offset
+            RETURNDATACOPY
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block: GethData = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0].address(addr_b).code(code_b);
+                accs[1].address(addr_a).code(code_a);
+                accs[2]
+                    .address(mock::MOCK_ACCOUNTS[2])
+                    .balance(Word::from(1u64 << 30));
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[1].address).from(accs[2].address);
+            },
+            |block, _tx| block,
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::RETURNDATACOPY))
+            .unwrap();
+
+        let call_id = builder.block.txs()[0].calls()[0].call_id;
+        let callee_id = builder.block.txs()[0].calls()[1].call_id;
+
+        // 3 stack reads + 3 call context reads + `copy_size` memory reads and
+        // writes.
+        assert_eq!(step.bus_mapping_instance.len(), 6 + 2 * copy_size);
+
+        assert_eq!(
+            [3, 4, 5]
+                .map(|idx| &builder.block.container.call_context
+                    [step.bus_mapping_instance[idx].as_usize()])
+                .map(|operation| (operation.rw(), operation.op())),
+            [
+                (
+                    RW::READ,
+                    &CallContextOp {
+                        call_id,
+                        field: CallContextField::LastCalleeId,
+                        value: Word::from(callee_id),
+                    }
+                ),
+                (
+                    RW::READ,
+                    &CallContextOp {
+                        call_id,
+                        field: CallContextField::LastCalleeReturnDataOffset,
+                        value: Word::zero(),
+                    }
+                ),
+                (
+                    RW::READ,
+                    &CallContextOp {
+                        call_id,
+                        field: CallContextField::LastCalleeReturnDataLength,
+                        value: Word::from(return_data_length),
+                    }
+                ),
+            ]
+        );
+
+        // Tuples of (RW::READ and RW::WRITE) where the callee memory is read
+        // and the current call's memory is written to.
+        assert_eq!(
+            (6..6 + 2 * copy_size)
+                .map(|idx| &builder.block.container.memory
+                    [step.bus_mapping_instance[idx].as_usize()])
+                .map(|op| (op.rw(), op.op().clone()))
+                .collect::<Vec<(RW, MemoryOp)>>(),
+            (0..copy_size)
+                .flat_map(|idx| {
+                    let value = return_data[offset + idx];
+                    [
+                        (
+                            RW::READ,
+                            MemoryOp::new(callee_id, (offset + idx).into(), value),
+                        ),
+                        (
+                            RW::WRITE,
+                            MemoryOp::new(call_id, (dst_offset + idx).into(), value),
+                        ),
+                    ]
+                })
+                .collect::<Vec<(RW, MemoryOp)>>(),
+        );
+
+        let copy_event = builder
+            .block
+            .copy_events
+            .iter()
+            .find(|event| event.dst_id == NumberOrHash::Number(call_id))
+            .unwrap();
+        assert_eq!(copy_event.src_id, NumberOrHash::Number(callee_id));
+        assert_eq!(copy_event.src_addr as usize, offset);
+        assert_eq!(copy_event.dst_addr as usize, dst_offset);
+        assert_eq!(copy_event.length, copy_size as u64);
+        assert_eq!(copy_event.steps.len(), 2 * copy_size);
+    }
+}
@@ -0,0 +1,209 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{
+        CircuitInputStateRef, CopyDataType, CopyEvent, CopyStep, ExecStep, NumberOrHash,
+    },
+    operation::{CallContextField, MemoryOp, RW},
+    Error,
+};
+use eth_types::{GethExecStep, ToWord, Word};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OpcodeId::REVERT`](crate::evm::OpcodeId::REVERT)
+/// `OpcodeId`. Besides restoring the caller's context, it copies the revert
+/// data into the return data buffer of the caller, and reverts all the
+/// reversible writes done in the call via `handle_return`.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Revert;
+
+impl Opcode for Revert {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+
+        let offset = geth_step.stack.nth_last(0)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(0), offset)?;
+        let length = geth_step.stack.nth_last(1)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(1), length)?;
+
+        let call = state.call()?.clone();
+
+        // NOTE: For `RwCounterEndOfReversion` we use the `0` value as a placeholder,
+        // and later set the proper value in
+        // `CircuitInputBuilder::set_value_ops_call_context_rwc_eor`
+        for (field, value) in [
+            (CallContextField::IsSuccess, 0.into()),
+            (CallContextField::RwCounterEndOfReversion, 0.into()),
+            (CallContextField::IsPersistent, 0.into()),
+        ] {
+            state.call_context_read(&mut exec_step, call.call_id, field, value);
+        }
+
+        if !call.is_root {
+            for (field, value) in [
+                (
+                    CallContextField::ReturnDataOffset,
+                    call.return_data_offset.into(),
+                ),
+                (
+                    CallContextField::ReturnDataLength,
+                    call.return_data_length.into(),
+                ),
+            ] {
+                state.call_context_read(&mut exec_step, call.call_id, field, value);
+            }
+
+            // The following part corresponds to
+            // Instruction.step_state_transition_to_restored_context
+            // in python spec.
+            let caller = state.caller()?.clone();
+            state.call_context_read(
+                &mut exec_step,
+                call.call_id,
+                CallContextField::CallerId,
+                caller.call_id.into(),
+            );
+
+            // The memory expansion cost of REVERT is not returned to the
+            // caller.
+            let geth_step_next = &geth_steps[1];
+            let caller_gas_left = geth_step_next.gas.0 - geth_step.gas.0 + geth_step.gas_cost.0;
+            for (field, value) in [
+                (CallContextField::IsRoot, (caller.is_root as u64).into()),
+                (
+                    CallContextField::IsCreate,
+                    (caller.is_create() as u64).into(),
+                ),
+                (CallContextField::CodeHash, caller.code_hash.to_word()),
+                (CallContextField::ProgramCounter, geth_step_next.pc.0.into()),
+                (
+                    CallContextField::StackPointer,
+                    geth_step_next.stack.stack_pointer().0.into(),
+                ),
+                (CallContextField::GasLeft, caller_gas_left.into()),
+                (
+                    CallContextField::MemorySize,
+                    geth_step_next.memory.word_size().into(),
+                ),
+                (
+                    CallContextField::ReversibleWriteCounter,
+                    state.caller_ctx()?.reversible_write_counter.into(),
+                ),
+            ] {
+                state.call_context_read(&mut exec_step, caller.call_id, field, value);
+            }
+
+            let return_data_offset = if length.is_zero() {
+                Word::zero()
+            } else {
+                offset
+            };
+            for (field, value) in [
+                (CallContextField::LastCalleeId, call.call_id.into()),
+                (
+                    CallContextField::LastCalleeReturnDataOffset,
+                    return_data_offset,
+                ),
+                (CallContextField::LastCalleeReturnDataLength, length),
+            ] {
+                state.call_context_write(&mut exec_step, caller.call_id, field, value);
+            }
+
+            // Copy the revert data into the return data buffer of the caller.
+            let copy_length = std::cmp::min(length.low_u64(), call.return_data_length);
+            if copy_length > 0 {
+                let bytes = geth_step
+                    .memory
+                    .read_chunk(offset.low_u64().into(), copy_length.into());
+                let copy_event = gen_memory_copy_event(
+                    state,
+                    &mut exec_step,
+                    call.call_id,
+                    caller.call_id,
+                    offset.low_u64(),
+                    call.return_data_offset,
+                    &bytes,
+                )?;
+                state.push_copy(copy_event);
+            }
+        }
+
+        state.handle_return(geth_step)?;
+        Ok(vec![exec_step])
+    }
+}
+
+/// Generate the copy event of `bytes` from the memory of the call `src_id` to
+/// the memory of the call `dst_id`, which is used to copy the return data
+/// between the callee and the caller.
+pub(crate) fn gen_memory_copy_event(
+    state: &mut CircuitInputStateRef,
+    exec_step: &mut ExecStep,
+    src_id: usize,
+    dst_id: usize,
+    src_addr: u64,
+    dst_addr: u64,
+    bytes: &[u8],
+) -> Result<CopyEvent, Error> {
+    let mut steps = Vec::with_capacity(2 * bytes.len());
+    for (idx, byte) in bytes.iter().enumerate() {
+        let idx = idx as u64;
+        // Read
+        let rwc = state.block_ctx.rwc;
+        state.push_op(
+            exec_step,
+            RW::READ,
+            MemoryOp::new(src_id, ((src_addr + idx) as usize).into(), *byte),
+        );
+        steps.push(CopyStep {
+            addr: src_addr + idx,
+            tag: CopyDataType::Memory,
+            rw: RW::READ,
+            value: *byte,
+            is_code: None,
+            is_pad: false,
+            rwc,
+            rwc_inc_left: 0,
+        });
+        // Write
+        let rwc = state.block_ctx.rwc;
+        state.push_op(
+            exec_step,
+            RW::WRITE,
+            MemoryOp::new(dst_id, ((dst_addr + idx) as usize).into(), *byte),
+        );
+        steps.push(CopyStep {
+            addr: dst_addr + idx,
+            tag: CopyDataType::Memory,
+            rw: RW::WRITE,
+            value: *byte,
+            is_code: None,
+            is_pad: false,
+            rwc,
+            rwc_inc_left: 0,
+        });
+    }
+
+    for cs in steps.iter_mut() {
+        cs.rwc_inc_left = state.block_ctx.rwc.0 as u64 - cs.rwc.0 as u64;
+    }
+
+    Ok(CopyEvent {
+        src_type: CopyDataType::Memory,
+        src_id: NumberOrHash::Number(src_id),
+        src_addr,
+        src_addr_end: src_addr + bytes.len() as u64,
+        dst_type: CopyDataType::Memory,
+        dst_id: NumberOrHash::Number(dst_id),
+        dst_addr,
+        log_id: None,
+        length: bytes.len() as u64,
+        steps,
+        tx_id: state.tx_ctx.id(),
+        call_id: state.call()?.call_id,
+        pc: exec_step.pc,
+    })
+}
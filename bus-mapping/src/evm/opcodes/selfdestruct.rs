@@ -0,0 +1,148 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    operation::{AccountDestructedOp, AccountField, CallContextField, TxAccessListAccountOp, RW},
+    Error,
+};
+use eth_types::{GethExecStep, ToAddress, ToWord};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the
+/// [`OpcodeId::SELFDESTRUCT`](crate::evm::OpcodeId::SELFDESTRUCT) `OpcodeId`.
+/// It transfers all the balance of the current account to the beneficiary and
+/// marks the account as destructed, so it's deleted at the end of the
+/// transaction in [`StateDB::commit_tx`](crate::state_db::StateDB::commit_tx).
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Selfdestruct;
+
+impl Opcode for Selfdestruct {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+
+        let beneficiary = geth_step.stack.last()?;
+        state.stack_read(&mut exec_step, geth_step.stack.last_filled(), beneficiary)?;
+        let beneficiary = beneficiary.to_address();
+
+        let tx_id = state.tx_ctx.id();
+        let call = state.call()?.clone();
+
+        // NOTE: For `RwCounterEndOfReversion` we use the `0` value as a placeholder,
+        // and later set the proper value in
+        // `CircuitInputBuilder::set_value_ops_call_context_rwc_eor`
+        for (field, value) in [
+            (CallContextField::TxId, tx_id.into()),
+            (CallContextField::IsStatic, 0.into()),
+            (CallContextField::CalleeAddress, call.address.to_word()),
+            (CallContextField::RwCounterEndOfReversion, 0.into()),
+            (
+                CallContextField::IsPersistent,
+                (call.is_persistent as u64).into(),
+            ),
+            (CallContextField::IsSuccess, 1.into()),
+        ] {
+            state.call_context_read(&mut exec_step, call.call_id, field, value);
+        }
+
+        // Add beneficiary into access list
+        let is_warm = state.sdb.check_account_in_access_list(&beneficiary);
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            TxAccessListAccountOp {
+                tx_id,
+                address: beneficiary,
+                is_warm: true,
+                is_warm_prev: is_warm,
+            },
+        )?;
+
+        // Transfer all the balance to the beneficiary
+        let (found, account) = state.sdb.get_account(&call.address);
+        if !found {
+            return Err(Error::AccountNotFound(call.address));
+        }
+        let value = account.balance;
+        state.transfer(&mut exec_step, call.address, beneficiary, value)?;
+
+        // The beneficiary's nonce and code hash are read to check if it's an
+        // empty account for the gas cost.
+        let (_, beneficiary_account) = state.sdb.get_account(&beneficiary);
+        let beneficiary_nonce = beneficiary_account.nonce;
+        let beneficiary_code_hash = beneficiary_account.code_hash;
+        for (field, value) in [
+            (AccountField::Nonce, beneficiary_nonce),
+            (AccountField::CodeHash, beneficiary_code_hash.to_word()),
+        ] {
+            state.account_read(&mut exec_step, beneficiary, field, value, value)?;
+        }
+
+        // Since EIP-3529 there is no refund for SELFDESTRUCT, so the refund
+        // counter of the tx is left as is.
+        let is_destructed = state.sdb.check_account_destructed(&call.address);
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            AccountDestructedOp {
+                tx_id,
+                address: call.address,
+                is_destructed: true,
+                is_destructed_prev: is_destructed,
+            },
+        )?;
+
+        if !call.is_root {
+            // The following part corresponds to
+            // Instruction.step_state_transition_to_restored_context
+            // in python spec.
+            let caller = state.caller()?.clone();
+            state.call_context_read(
+                &mut exec_step,
+                call.call_id,
+                CallContextField::CallerId,
+                caller.call_id.into(),
+            );
+
+            let geth_step_next = &geth_steps[1];
+            let caller_gas_left = geth_step_next.gas.0 - geth_step.gas.0 + geth_step.gas_cost.0;
+            for (field, value) in [
+                (CallContextField::IsRoot, (caller.is_root as u64).into()),
+                (
+                    CallContextField::IsCreate,
+                    (caller.is_create() as u64).into(),
+                ),
+                (CallContextField::CodeHash, caller.code_hash.to_word()),
+                (CallContextField::ProgramCounter, geth_step_next.pc.0.into()),
+                (
+                    CallContextField::StackPointer,
+                    geth_step_next.stack.stack_pointer().0.into(),
+                ),
+                (CallContextField::GasLeft, caller_gas_left.into()),
+                (
+                    CallContextField::MemorySize,
+                    geth_step_next.memory.word_size().into(),
+                ),
+                (
+                    CallContextField::ReversibleWriteCounter,
+                    state.caller_ctx()?.reversible_write_counter.into(),
+                ),
+            ] {
+                state.call_context_read(&mut exec_step, caller.call_id, field, value);
+            }
+
+            for (field, value) in [
+                (CallContextField::LastCalleeId, call.call_id.into()),
+                (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+                (CallContextField::LastCalleeReturnDataLength, 0.into()),
+            ] {
+                state.call_context_write(&mut exec_step, caller.call_id, field, value);
+            }
+        }
+
+        state.handle_return(geth_step)?;
+        Ok(vec![exec_step])
+    }
+}
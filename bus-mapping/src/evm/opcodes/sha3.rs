@@ -0,0 +1,252 @@
+use crate::{
+    circuit_input_builder::{
+        CircuitInputStateRef, CopyDataType, CopyEvent, CopyStep, ExecStep, NumberOrHash,
+    },
+    operation::{MemoryOp, RW},
+    Error,
+};
+use eth_types::{GethExecStep, Word};
+use ethers_core::utils::keccak256;
+
+use super::{Opcode, MAX_MEMORY_SIZE};
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Sha3;
+
+impl Opcode for Sha3 {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+
+        let expected_sha3 = geth_steps[1].stack.last()?;
+
+        // byte offset in the memory.
+        let offset = geth_step.stack.nth_last(0)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(0), offset)?;
+
+        // byte size to read in the memory.
+        let size = geth_step.stack.nth_last(1)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(1), size)?;
+
+        // read the bytes to be hashed, which are zero beyond the current
+        // memory size. A successful SHA3 has paid for the memory expansion,
+        // so a range beyond `MAX_MEMORY_SIZE` means the trace is invalid (the
+        // out of gas case is handled by `ErrorOOGSha3`).
+        let sha3_input = if size.is_zero() {
+            Vec::new()
+        } else {
+            let (end, overflow) = offset.overflowing_add(size);
+            if overflow || end > MAX_MEMORY_SIZE.into() {
+                return Err(Error::InvalidGethExecStep(
+                    "SHA3: memory range out of bounds",
+                    geth_step.clone(),
+                ));
+            }
+            geth_step.memory.read_chunk(
+                (offset.as_u64() as usize).into(),
+                (size.as_u64() as usize).into(),
+            )
+        };
+        let sha3 = keccak256(&sha3_input);
+        debug_assert_eq!(Word::from_big_endian(&sha3), expected_sha3);
+
+        state.stack_write(
+            &mut exec_step,
+            geth_steps[1].stack.last_filled(),
+            expected_sha3,
+        )?;
+
+        // memory reads and copy event of the bytes to be hashed.
+        let call_id = state.call()?.call_id;
+        let copy_event = gen_memory_to_rlc_acc_copy_event(
+            state,
+            &mut exec_step,
+            call_id,
+            offset.low_u64(),
+            &sha3_input,
+        )?;
+        state.push_copy(copy_event);
+        state.push_sha3_input(sha3_input);
+
+        Ok(vec![exec_step])
+    }
+}
+
+/// Generate the memory reads and the copy event of `bytes` read from memory at
+/// `offset` in the call `call_id`, which are accumulated into a random linear
+/// combination to be used as a keccak input.
+pub(super) fn gen_memory_to_rlc_acc_copy_event(
+    state: &mut CircuitInputStateRef,
+    exec_step: &mut ExecStep,
+    call_id: usize,
+    offset: u64,
+    bytes: &[u8],
+) -> Result<CopyEvent, Error> {
+    let src_addr = if bytes.is_empty() { 0 } else { offset };
+    let src_addr_end = src_addr + bytes.len() as u64;
+
+    let mut steps = Vec::with_capacity(2 * bytes.len());
+    for (idx, byte) in bytes.iter().enumerate() {
+        let addr = src_addr + idx as u64;
+        // Read
+        let rwc = state.block_ctx.rwc;
+        state.push_op(
+            exec_step,
+            RW::READ,
+            MemoryOp::new(call_id, (addr as usize).into(), *byte),
+        );
+        steps.push(CopyStep {
+            addr,
+            tag: CopyDataType::Memory,
+            rw: RW::READ,
+            value: *byte,
+            is_code: None,
+            is_pad: false,
+            rwc,
+            rwc_inc_left: 0,
+        });
+        // Write
+        steps.push(CopyStep {
+            addr: idx as u64,
+            tag: CopyDataType::RlcAcc,
+            rw: RW::WRITE,
+            value: *byte,
+            is_code: None,
+            is_pad: false,
+            rwc: state.block_ctx.rwc,
+            rwc_inc_left: 0,
+        });
+    }
+
+    for cs in steps.iter_mut() {
+        cs.rwc_inc_left = state.block_ctx.rwc.0 as u64 - cs.rwc.0 as u64;
+    }
+
+    Ok(CopyEvent {
+        src_addr,
+        src_addr_end,
+        src_type: CopyDataType::Memory,
+        src_id: NumberOrHash::Number(call_id),
+        dst_addr: 0,
+        dst_type: CopyDataType::RlcAcc,
+        dst_id: NumberOrHash::Number(call_id),
+        log_id: None,
+        length: bytes.len() as u64,
+        steps,
+        tx_id: state.tx_ctx.id(),
+        call_id,
+        pc: exec_step.pc,
+    })
+}
+
+#[cfg(test)]
+mod sha3_tests {
+    use eth_types::{bytecode, evm_types::OpcodeId, geth_types::GethData, Word};
+    use ethers_core::utils::keccak256;
+    use mock::test_ctx::{helpers::*, TestContext};
+    use pretty_assertions::assert_eq;
+
+    use crate::{
+        circuit_input_builder::{CopyDataType, ExecState, NumberOrHash},
+        mock::BlockData,
+        operation::{MemoryOp, StackOp, RW},
+    };
+
+    fn test_ok(offset: usize, size: usize, memory: &[u8]) {
+        let mut code = bytecode! {};
+        for (idx, byte) in memory.iter().enumerate() {
+            code.push(32, Word::from(*byte));
+            code.push(32, Word::from(idx));
+            code.write_op(OpcodeId::MSTORE8);
+        }
+        code.push(32, Word::from(size));
+        code.push(32, Word::from(offset));
+        code.write_op(OpcodeId::SHA3);
+        code.write_op(OpcodeId::STOP);
+
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            account_0_code_account_1_no_code(code),
+            tx_from_1_to_0,
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::SHA3))
+            .unwrap();
+
+        let call_id = builder.block.txs()[0].calls()[0].call_id;
+        let mut memory_view = memory.to_vec();
+        memory_view.resize(offset + size, 0);
+        let sha3_input = memory_view[offset..offset + size].to_vec();
+        let expected_sha3 = Word::from_big_endian(&keccak256(&sha3_input));
+
+        assert_eq!(
+            [0, 1, 2]
+                .map(|idx| &builder.block.container.stack[step.bus_mapping_instance[idx].as_usize()])
+                .map(|operation| (operation.rw(), operation.op())),
+            [
+                (
+                    RW::READ,
+                    &StackOp::new(call_id, 1022.into(), Word::from(offset))
+                ),
+                (
+                    RW::READ,
+                    &StackOp::new(call_id, 1023.into(), Word::from(size))
+                ),
+                (RW::WRITE, &StackOp::new(call_id, 1023.into(), expected_sha3)),
+            ]
+        );
+
+        assert_eq!(
+            (0..size)
+                .map(|idx| &builder.block.container.memory
+                    [step.bus_mapping_instance[3 + idx].as_usize()])
+                .map(|operation| (operation.rw(), operation.op().clone()))
+                .collect::<Vec<_>>(),
+            (0..size)
+                .map(|idx| (
+                    RW::READ,
+                    MemoryOp::new(call_id, (offset + idx).into(), sha3_input[idx])
+                ))
+                .collect::<Vec<_>>(),
+        );
+
+        let copy_events = builder.block.copy_events.clone();
+        assert_eq!(copy_events.len(), 1);
+        assert_eq!(copy_events[0].src_type, CopyDataType::Memory);
+        assert_eq!(copy_events[0].src_id, NumberOrHash::Number(call_id));
+        assert_eq!(copy_events[0].dst_type, CopyDataType::RlcAcc);
+        assert_eq!(copy_events[0].length, size as u64);
+        assert_eq!(copy_events[0].steps.len(), 2 * size);
+        for (idx, copy_rw_pair) in copy_events[0].steps.chunks(2).enumerate() {
+            let (read_step, write_step) = (&copy_rw_pair[0], &copy_rw_pair[1]);
+            assert_eq!(read_step.tag, CopyDataType::Memory);
+            assert_eq!(read_step.addr, (offset + idx) as u64);
+            assert_eq!(read_step.value, sha3_input[idx]);
+            assert_eq!(write_step.tag, CopyDataType::RlcAcc);
+            assert_eq!(write_step.value, sha3_input[idx]);
+        }
+
+        assert_eq!(builder.block.sha3_inputs, vec![sha3_input]);
+    }
+
+    #[test]
+    fn sha3_opcode_ok() {
+        test_ok(0x10, 0x32, &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06]);
+        test_ok(0x00, 0x04, &[0xff, 0xee, 0xdd, 0xcc, 0xbb, 0xaa]);
+        test_ok(0x40, 0x00, &[0x42]);
+    }
+}
@@ -6,3 +6,10 @@ pub const GETH_ERR_STACK_UNDERFLOW: &str = "stack underflow";
 pub const GETH_ERR_OUT_OF_GAS: &str = "out of gas";
 /// Geth error message for gas uint64 overflow
 pub const GETH_ERR_GAS_UINT_OVERFLOW: &str = "gas uint64 overflow";
+/// Geth error message for invalid opcode
+pub const GETH_ERR_INVALID_OPCODE: &str = "invalid opcode";
+/// Geth error message for jump to an invalid destination
+pub const GETH_ERR_INVALID_JUMP: &str = "invalid jump destination";
+/// Geth error message for SSTORE with gas left not more than the reentrancy
+/// sentry
+pub const GETH_ERR_SSTORE_SENTRY: &str = "not enough gas for reentrancy sentry";
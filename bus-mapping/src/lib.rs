@@ -231,6 +231,7 @@ pub mod evm;
 pub mod exec_trace;
 pub(crate) mod geth_errors;
 pub mod mock;
+pub mod mpt;
 pub mod operation;
 pub mod rpc;
 pub mod state_db;
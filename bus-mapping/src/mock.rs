@@ -2,9 +2,11 @@
 
 use crate::{
     circuit_input_builder::{Block, CircuitInputBuilder},
+    mpt::{encode_account, encode_storage_value, trie::Trie, EMPTY_ROOT},
     state_db::{self, CodeDB, StateDB},
 };
-use eth_types::{geth_types::GethData, Word};
+use eth_types::{geth_types::GethData, ToBigEndian, ToWord, Word, H256};
+use ethers_core::utils::keccak256;
 
 /// BlockData is a type that contains all the information from a block required
 /// to build the circuit inputs.
@@ -23,17 +25,22 @@ pub struct BlockData {
     pub eth_block: eth_types::Block<eth_types::Transaction>,
     /// Execution Trace from geth
     pub geth_traces: Vec<eth_types::GethExecTrace>,
+    /// State root of the accounts before the block
+    pub prev_state_root: Word,
+    /// RLP encoded nodes of the state and storage tries of the accounts
+    /// before the block
+    pub trie_nodes: Vec<Vec<u8>>,
 }
 
 impl BlockData {
     /// Generate a new CircuitInputBuilder initialized with the context of the
     /// BlockData.
     pub fn new_circuit_input_builder(&self) -> CircuitInputBuilder {
-        CircuitInputBuilder::new(
-            self.sdb.clone(),
-            self.code_db.clone(),
-            Block::new(self.chain_id, self.history_hashes.clone(), &self.eth_block).unwrap(),
-        )
+        let mut block =
+            Block::new(self.chain_id, self.history_hashes.clone(), &self.eth_block).unwrap();
+        block.prev_state_root = self.prev_state_root;
+        block.trie_nodes = self.trie_nodes.clone();
+        CircuitInputBuilder::new(self.sdb.clone(), self.code_db.clone(), block)
     }
 
     /// Create a new block from the given Geth data.
@@ -49,6 +56,38 @@ impl BlockData {
             }
         }
 
+        // Build the state trie of the accounts, where the accounts which are
+        // empty are absent.
+        let mut trie = Trie::default();
+        let mut state_root = *EMPTY_ROOT;
+        for account in geth_data.accounts.iter() {
+            let mut storage_root = *EMPTY_ROOT;
+            for (key, value) in account.storage.iter().filter(|(_, value)| !value.is_zero()) {
+                storage_root = trie
+                    .insert(
+                        storage_root,
+                        &H256(keccak256(key.to_be_bytes())),
+                        encode_storage_value(*value),
+                    )
+                    .unwrap();
+            }
+            let code_hash = H256(keccak256(&account.code));
+            if account.nonce.is_zero()
+                && account.balance.is_zero()
+                && account.code.is_empty()
+                && storage_root == *EMPTY_ROOT
+            {
+                continue;
+            }
+            state_root = trie
+                .insert(
+                    state_root,
+                    &H256(keccak256(account.address.as_bytes())),
+                    encode_account(account.nonce, account.balance, storage_root, code_hash),
+                )
+                .unwrap();
+        }
+
         for account in geth_data.accounts {
             let code_hash = code_db.insert(account.code.to_vec());
             sdb.set_account(
@@ -69,6 +108,8 @@ impl BlockData {
             history_hashes: geth_data.history_hashes,
             eth_block: geth_data.eth_block,
             geth_traces: geth_data.geth_traces,
+            prev_state_root: state_root.to_word(),
+            trie_nodes: trie.nodes().map(<[u8]>::to_vec).collect(),
         }
     }
 }
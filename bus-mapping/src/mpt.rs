@@ -0,0 +1,378 @@
+//! Verification of the Merkle Patricia Trie proofs returned by
+//! `eth_getProof` ([EIP-1186](https://eips.ethereum.org/EIPS/eip-1186)), so
+//! that the account and storage values used as the pre-state of a block can
+//! be trusted even when they come from an untrusted node.
+
+use crate::Error;
+use eth_types::{Bytes, EIP1186ProofResponse, ToBigEndian, Word, H256};
+use ethers_core::utils::keccak256;
+use keccak256::EMPTY_HASH;
+use lazy_static::lazy_static;
+use rlp::{Rlp, RlpStream};
+
+pub mod trie;
+
+lazy_static! {
+    /// Root of an empty trie, which is the hash of the RLP encoding of an
+    /// empty string.
+    pub static ref EMPTY_ROOT: H256 = H256(keccak256(&rlp::NULL_RLP));
+}
+
+/// Verify the account proof in `proof` against `state_root`, and then all
+/// the storage proofs in `proof` against the verified storage root of the
+/// account.
+pub fn verify_proof(state_root: H256, proof: &EIP1186ProofResponse) -> Result<(), Error> {
+    let account = verify_trie_proof(
+        state_root,
+        &keccak256(proof.address.as_bytes()),
+        &proof.account_proof,
+    )
+    .map_err(|reason| Error::InvalidAccountProof(proof.address, reason))?;
+
+    let storage_root = match account {
+        Some(account) => {
+            if account != account_rlp(proof) {
+                return Err(Error::InvalidAccountProof(
+                    proof.address,
+                    "account value mismatch",
+                ));
+            }
+            proof.storage_hash
+        }
+        None => {
+            // Geth returns either zero or the empty hash as the code hash of
+            // an absent account.
+            if !proof.nonce.is_zero()
+                || !proof.balance.is_zero()
+                || !(proof.code_hash.is_zero() || proof.code_hash.0 == *EMPTY_HASH)
+            {
+                return Err(Error::InvalidAccountProof(
+                    proof.address,
+                    "non-empty value of absent account",
+                ));
+            }
+            *EMPTY_ROOT
+        }
+    };
+
+    for storage_proof in &proof.storage_proof {
+        let value = verify_trie_proof(
+            storage_root,
+            &keccak256(storage_proof.key.to_be_bytes()),
+            &storage_proof.proof,
+        )
+        .map_err(|reason| Error::InvalidStorageProof(proof.address, storage_proof.key, reason))?;
+        // Slots with zero value are removed from the trie.
+        let expected_value =
+            (!storage_proof.value.is_zero()).then(|| encode_storage_value(storage_proof.value));
+        if value != expected_value {
+            return Err(Error::InvalidStorageProof(
+                proof.address,
+                storage_proof.key,
+                "storage value mismatch",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Return the RLP encoding of the account in `proof` as stored in the state
+/// trie.
+fn account_rlp(proof: &EIP1186ProofResponse) -> Vec<u8> {
+    encode_account(
+        proof.nonce,
+        proof.balance,
+        proof.storage_hash,
+        proof.code_hash,
+    )
+}
+
+/// Return the RLP encoding of an account as stored in the leaves of the state
+/// trie.
+pub fn encode_account(nonce: Word, balance: Word, storage_root: H256, code_hash: H256) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(4);
+    append_word(&mut stream, nonce);
+    append_word(&mut stream, balance);
+    stream.append(&storage_root.as_bytes());
+    stream.append(&code_hash.as_bytes());
+    stream.out().to_vec()
+}
+
+/// Return the RLP encoding of a non-zero storage value as stored in the
+/// leaves of the storage tries.
+pub fn encode_storage_value(value: Word) -> Vec<u8> {
+    let mut stream = RlpStream::new();
+    append_word(&mut stream, value);
+    stream.out().to_vec()
+}
+
+/// Append `word` to `stream` as a big endian integer without leading zeros.
+pub(crate) fn append_word(stream: &mut RlpStream, word: Word) {
+    let bytes = word.to_be_bytes();
+    let leading_zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+    stream.append(&&bytes[leading_zeros..]);
+}
+
+/// Encode `path` in hex prefix encoding, with the flag of leaf nodes if
+/// `is_leaf`.
+fn encode_path(path: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 2 } else { 0 };
+    let (first, rest) = if path.len() % 2 == 1 {
+        (((flag | 1) << 4) | path[0], &path[1..])
+    } else {
+        (flag << 4, path)
+    };
+    std::iter::once(first)
+        .chain(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]))
+        .collect()
+}
+
+/// Decode the hex prefix encoded path of a leaf or extension node into
+/// nibbles, and return whether the node is a leaf.
+fn decode_path(encoded: &[u8]) -> Result<(Vec<u8>, bool), &'static str> {
+    let (first, rest) = encoded.split_first().ok_or("empty node path")?;
+    let flag = first >> 4;
+    if flag > 3 {
+        return Err("invalid node path flag");
+    }
+    let mut path = Vec::with_capacity(rest.len() * 2 + 1);
+    if flag & 1 == 1 {
+        path.push(first & 0xf);
+    }
+    path.extend(rest.iter().flat_map(|byte| [byte >> 4, byte & 0xf]));
+    Ok((path, flag & 2 == 2))
+}
+
+/// Walk down the trie from `root` along `key` with the RLP encoded nodes in
+/// `proof`, and return the value at `key`, or `None` if the proof shows that
+/// `key` is absent from the trie.  Nodes shorter than 32 bytes are embedded
+/// in their parent instead of being in `proof`.
+fn verify_trie_proof(
+    root: H256,
+    key: &[u8],
+    proof: &[Bytes],
+) -> Result<Option<Vec<u8>>, &'static str> {
+    let key: Vec<u8> = key
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0xf])
+        .collect();
+    let mut nodes = proof.iter();
+    let mut node = match nodes.next() {
+        Some(node) => node.to_vec(),
+        None if root == *EMPTY_ROOT => return Ok(None),
+        None => return Err("missing root node"),
+    };
+    if H256(keccak256(&node)) != root {
+        return Err("root hash mismatch");
+    }
+
+    let mut key_index = 0;
+    let value = loop {
+        let rlp = Rlp::new(&node);
+        let child = match rlp.item_count().map_err(|_| "invalid node")? {
+            // Branch node, whose value is always empty since all keys have
+            // the same length.
+            17 => {
+                let nibble = *key.get(key_index).ok_or("key ends at branch node")?;
+                key_index += 1;
+                rlp.at(nibble as usize).map_err(|_| "invalid branch node")?
+            }
+            // Leaf or extension node
+            2 => {
+                let encoded_path = rlp
+                    .at(0)
+                    .and_then(|path| path.data().map(<[u8]>::to_vec))
+                    .map_err(|_| "invalid node path")?;
+                let (path, is_leaf) = decode_path(&encoded_path)?;
+                if !key[key_index..].starts_with(&path) {
+                    break None;
+                }
+                key_index += path.len();
+                let child = rlp.at(1).map_err(|_| "invalid node")?;
+                if is_leaf {
+                    if key_index != key.len() {
+                        return Err("key doesn't end at leaf node");
+                    }
+                    break Some(child.data().map_err(|_| "invalid leaf value")?.to_vec());
+                }
+                child
+            }
+            _ => return Err("invalid node"),
+        };
+
+        node = if child.is_list() {
+            child.as_raw().to_vec()
+        } else {
+            let hash = child.data().map_err(|_| "invalid child hash")?;
+            if hash.is_empty() {
+                break None;
+            }
+            if hash.len() != 32 {
+                return Err("invalid child hash");
+            }
+            let next_node = nodes.next().ok_or("missing node")?.to_vec();
+            if keccak256(&next_node)[..] != *hash {
+                return Err("node hash mismatch");
+            }
+            next_node
+        };
+    };
+
+    if nodes.next().is_some() {
+        return Err("unexpected node after the end of path");
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth_types::{Address, StorageProof, U256};
+    use pretty_assertions::assert_eq;
+
+    fn leaf_node(path: &[u8], value: &[u8]) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&encode_path(path, true));
+        stream.append(&value);
+        stream.out().to_vec()
+    }
+
+    fn nibbles(key: &[u8]) -> Vec<u8> {
+        key.iter()
+            .flat_map(|byte| [byte >> 4, byte & 0xf])
+            .collect()
+    }
+
+    /// Build a trie of a branch node as root with 2 leaves, and return the
+    /// root and the proofs of both keys.
+    fn two_leaves_trie(
+        key_a: &[u8],
+        value_a: &[u8],
+        key_b: &[u8],
+        value_b: &[u8],
+    ) -> (H256, Vec<Bytes>, Vec<Bytes>) {
+        let (key_a, key_b) = (nibbles(key_a), nibbles(key_b));
+        assert_ne!(key_a[0], key_b[0]);
+        let leaf_a = leaf_node(&key_a[1..], value_a);
+        let leaf_b = leaf_node(&key_b[1..], value_b);
+        let mut stream = RlpStream::new_list(17);
+        for nibble in 0..16 {
+            if nibble == key_a[0] {
+                stream.append(&&keccak256(&leaf_a)[..]);
+            } else if nibble == key_b[0] {
+                stream.append(&&keccak256(&leaf_b)[..]);
+            } else {
+                stream.append_empty_data();
+            }
+        }
+        stream.append_empty_data();
+        let branch = stream.out().to_vec();
+        (
+            H256(keccak256(&branch)),
+            vec![branch.clone().into(), leaf_a.into()],
+            vec![branch.into(), leaf_b.into()],
+        )
+    }
+
+    #[test]
+    fn trie_proof_inclusion_and_exclusion() {
+        let (key_a, key_b) = ([0x11; 32], [0x22; 32]);
+        let (root, proof_a, proof_b) = two_leaves_trie(&key_a, b"a", &key_b, b"b");
+
+        assert_eq!(
+            verify_trie_proof(root, &key_a, &proof_a),
+            Ok(Some(b"a".to_vec()))
+        );
+        assert_eq!(
+            verify_trie_proof(root, &key_b, &proof_b),
+            Ok(Some(b"b".to_vec()))
+        );
+        // Same first nibble as `key_a` but diverges in the leaf path
+        let mut key_c = key_a;
+        key_c[31] = 0;
+        assert_eq!(verify_trie_proof(root, &key_c, &proof_a), Ok(None));
+        // Empty slot in the branch node
+        assert_eq!(
+            verify_trie_proof(root, &[0x33; 32], &proof_a[..1]),
+            Ok(None)
+        );
+        assert_eq!(verify_trie_proof(*EMPTY_ROOT, &key_a, &[]), Ok(None));
+    }
+
+    #[test]
+    fn trie_proof_tampered() {
+        let (key_a, key_b) = ([0x11; 32], [0x22; 32]);
+        let (root, proof_a, proof_b) = two_leaves_trie(&key_a, b"a", &key_b, b"b");
+
+        // Leaf of another key
+        let wrong_proof = vec![proof_a[0].clone(), proof_b[1].clone()];
+        assert_eq!(
+            verify_trie_proof(root, &key_a, &wrong_proof),
+            Err("node hash mismatch")
+        );
+        assert_eq!(
+            verify_trie_proof(H256::zero(), &key_a, &proof_a),
+            Err("root hash mismatch")
+        );
+        assert_eq!(
+            verify_trie_proof(root, &key_a, &proof_a[..1]),
+            Err("missing node")
+        );
+        let mut extra_proof = proof_a.clone();
+        extra_proof.push(proof_b[1].clone());
+        assert_eq!(
+            verify_trie_proof(root, &key_a, &extra_proof),
+            Err("unexpected node after the end of path")
+        );
+    }
+
+    #[test]
+    fn account_and_storage_proof() {
+        let address = Address::repeat_byte(0xaa);
+        let slot = U256::from(1);
+        let value = U256::from(0x1234);
+
+        // Storage trie with a single leaf as root
+        let storage_key = nibbles(&keccak256(slot.to_be_bytes()));
+        let storage_leaf = leaf_node(&storage_key, &encode_storage_value(value));
+        let storage_hash = H256(keccak256(&storage_leaf));
+
+        let mut proof = EIP1186ProofResponse {
+            address,
+            balance: U256::from(100),
+            code_hash: H256(*EMPTY_HASH),
+            nonce: U256::from(1),
+            storage_hash,
+            account_proof: vec![],
+            storage_proof: vec![StorageProof {
+                key: slot,
+                value,
+                proof: vec![storage_leaf.into()],
+            }],
+        };
+
+        // State trie with a single leaf as root
+        let account_leaf = leaf_node(
+            &nibbles(&keccak256(address.as_bytes())),
+            &account_rlp(&proof),
+        );
+        let state_root = H256(keccak256(&account_leaf));
+        proof.account_proof = vec![account_leaf.into()];
+        assert!(verify_proof(state_root, &proof).is_ok());
+
+        let mut wrong_balance = proof.clone();
+        wrong_balance.balance = U256::from(101);
+        assert!(matches!(
+            verify_proof(state_root, &wrong_balance),
+            Err(Error::InvalidAccountProof(_, "account value mismatch"))
+        ));
+
+        let mut wrong_value = proof;
+        wrong_value.storage_proof[0].value = U256::from(0x4321);
+        assert!(matches!(
+            verify_proof(state_root, &wrong_value),
+            Err(Error::InvalidStorageProof(_, _, "storage value mismatch"))
+        ));
+    }
+}
@@ -0,0 +1,880 @@
+//! A partial Merkle Patricia Trie made of the nodes which are known, such as
+//! the nodes of the proofs returned by `eth_getProof`, which can compute the
+//! nodes on the path of an updated key before and after the update, so that
+//! the update can be verified in a circuit.
+
+use super::{decode_path, encode_path, EMPTY_ROOT};
+use crate::Error;
+use eth_types::H256;
+use ethers_core::utils::keccak256;
+use rlp::{Rlp, RlpStream};
+use std::collections::HashMap;
+
+/// Return the 64 nibbles of the (hashed) `key` of a trie.
+pub fn key_nibbles(key: &H256) -> Vec<u8> {
+    key.as_bytes()
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0xf])
+        .collect()
+}
+
+/// A node of a trie.  Nodes whose RLP encoding is shorter than 32 bytes are
+/// embedded in their parent instead of being referenced by their hash, which
+/// only happens to the leaves of short storage values deeper than 8 nibbles,
+/// that is in tries of billions of keys, and is not supported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Node {
+    /// Branch node with the hashes of its 16 children.  Its value is always
+    /// empty since all the keys have the same length.
+    Branch([Option<H256>; 16]),
+    /// Extension node with its path in nibbles and the hash of its child.
+    Extension(Vec<u8>, H256),
+    /// Leaf node with the rest of the path of its key in nibbles and its
+    /// value.
+    Leaf(Vec<u8>, Vec<u8>),
+}
+
+impl Node {
+    /// Return the RLP encoding of the node.
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Node::Branch(children) => {
+                let mut stream = RlpStream::new_list(17);
+                for child in children {
+                    match child {
+                        Some(hash) => stream.append(&hash.as_bytes()),
+                        None => stream.append_empty_data(),
+                    };
+                }
+                stream.append_empty_data();
+                stream.out().to_vec()
+            }
+            Node::Extension(path, child) => {
+                let mut stream = RlpStream::new_list(2);
+                stream.append(&encode_path(path, false));
+                stream.append(&child.as_bytes());
+                stream.out().to_vec()
+            }
+            Node::Leaf(path, value) => {
+                let mut stream = RlpStream::new_list(2);
+                stream.append(&encode_path(path, true));
+                stream.append(value);
+                stream.out().to_vec()
+            }
+        }
+    }
+
+    /// Decode a node from its RLP encoding.
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let invalid = |_| Error::InvalidTrieUpdate("invalid node");
+        let rlp = Rlp::new(bytes);
+        match rlp.item_count().map_err(invalid)? {
+            17 => {
+                let mut children = [None; 16];
+                for (idx, child) in children.iter_mut().enumerate() {
+                    *child = child_hash(&rlp.at(idx).map_err(invalid)?)?;
+                }
+                let value = rlp.at(16).map_err(invalid)?;
+                if value.is_list() || !value.data().map_err(invalid)?.is_empty() {
+                    return Err(Error::InvalidTrieUpdate("branch node with a value"));
+                }
+                Ok(Node::Branch(children))
+            }
+            2 => {
+                let encoded_path = rlp
+                    .at(0)
+                    .and_then(|path| path.data().map(<[u8]>::to_vec))
+                    .map_err(invalid)?;
+                let (path, is_leaf) =
+                    decode_path(&encoded_path).map_err(Error::InvalidTrieUpdate)?;
+                let child = rlp.at(1).map_err(invalid)?;
+                if is_leaf {
+                    Ok(Node::Leaf(path, child.data().map_err(invalid)?.to_vec()))
+                } else {
+                    let child = child_hash(&child)?
+                        .ok_or(Error::InvalidTrieUpdate("extension node without child"))?;
+                    Ok(Node::Extension(path, child))
+                }
+            }
+            _ => Err(Error::InvalidTrieUpdate("invalid node")),
+        }
+    }
+
+    /// Return the hash of the RLP encoding of the node.
+    pub fn hash(&self) -> H256 {
+        H256(keccak256(&self.encode()))
+    }
+}
+
+/// Return the hash of the child referenced by `rlp`, or `None` if the child is
+/// empty.
+fn child_hash(rlp: &Rlp) -> Result<Option<H256>, Error> {
+    if rlp.is_list() {
+        return Err(Error::InvalidTrieUpdate("embedded node"));
+    }
+    let hash = rlp
+        .data()
+        .map_err(|_| Error::InvalidTrieUpdate("invalid child hash"))?;
+    match hash.len() {
+        0 => Ok(None),
+        32 => Ok(Some(H256::from_slice(hash))),
+        _ => Err(Error::InvalidTrieUpdate("invalid child hash")),
+    }
+}
+
+/// Branch or extension node on the path of an updated key, which is in the
+/// trie both before and after the update with a different child.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathNode {
+    /// Depth of the node in nibbles
+    pub depth: usize,
+    /// Node before the update
+    pub old: Node,
+    /// Node after the update
+    pub new: Node,
+}
+
+/// End of the path of an updated key, after the nodes which are in the trie
+/// both before and after the update.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathEnd {
+    /// Leaf of the key before and after the update, which is absent before
+    /// an insertion into an empty slot and after a deletion which leaves its
+    /// parent branch node with at least 2 children.
+    Leaf {
+        /// Depth of the leaf in nibbles
+        depth: usize,
+        /// Leaf before the update
+        old: Option<Node>,
+        /// Leaf after the update
+        new: Option<Node>,
+    },
+    /// Empty slot of a branch node or empty trie, which shows that the key is
+    /// absent both before and after the update.
+    Empty {
+        /// Depth of the empty slot in nibbles
+        depth: usize,
+    },
+    /// Leaf of another key, which shows that the key is absent both before and
+    /// after the update.
+    Other {
+        /// Depth of the leaf in nibbles
+        depth: usize,
+        /// Leaf of the other key
+        leaf: Node,
+    },
+    /// Leaf of another key which is split into a branch node with the leaf of
+    /// the key and the leaf of the other key as children, with an extension
+    /// node above the branch node if both keys share nibbles after the depth
+    /// of the leaf, to insert the key.  Deleting the key merges them back.
+    Split {
+        /// Depth of the leaf of the other key before the split in nibbles
+        depth: usize,
+        /// Leaf of the other key before the split
+        other: Node,
+        /// Extension node above the branch node
+        extension: Option<Node>,
+        /// Branch node of the split
+        branch: Node,
+        /// Leaf of the key, as a child of the branch node
+        leaf: Node,
+        /// Leaf of the other key, as a child of the branch node
+        sibling: Node,
+        /// Whether the key is inserted, or deleted otherwise
+        is_insert: bool,
+    },
+}
+
+/// Nodes of the path of a key before and after its update.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrieUpdate {
+    /// Root of the trie before the update
+    pub old_root: H256,
+    /// Root of the trie after the update
+    pub new_root: H256,
+    /// Nodes from the root to the end of the path
+    pub path: Vec<PathNode>,
+    /// End of the path
+    pub end: PathEnd,
+}
+
+impl TrieUpdate {
+    /// Return the value of the key before the update.
+    pub fn old_value(&self) -> Option<&[u8]> {
+        match &self.end {
+            PathEnd::Leaf {
+                old: Some(Node::Leaf(_, value)),
+                ..
+            } => Some(value),
+            PathEnd::Split {
+                leaf: Node::Leaf(_, value),
+                is_insert: false,
+                ..
+            } => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Return the value of the key after the update.
+    pub fn new_value(&self) -> Option<&[u8]> {
+        match &self.end {
+            PathEnd::Leaf {
+                new: Some(Node::Leaf(_, value)),
+                ..
+            } => Some(value),
+            PathEnd::Split {
+                leaf: Node::Leaf(_, value),
+                is_insert: true,
+                ..
+            } => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// A partial trie with the nodes which are known, indexed by their hash,
+/// which can hold several tries at once since each of them is identified by
+/// its root.
+#[derive(Clone, Debug, Default)]
+pub struct Trie {
+    nodes: HashMap<H256, Vec<u8>>,
+}
+
+impl Trie {
+    /// Create a trie with the RLP encoded `nodes`.
+    pub fn new<'a>(nodes: impl IntoIterator<Item = &'a [u8]>) -> Self {
+        Self {
+            nodes: nodes
+                .into_iter()
+                .map(|node| (H256(keccak256(node)), node.to_vec()))
+                .collect(),
+        }
+    }
+
+    /// Return the RLP encodings of all the nodes of the trie.
+    pub fn nodes(&self) -> impl Iterator<Item = &[u8]> {
+        self.nodes.values().map(Vec::as_slice)
+    }
+
+    fn node(&self, hash: H256) -> Result<Node, Error> {
+        Node::decode(self.nodes.get(&hash).ok_or(Error::MissingTrieNode(hash))?)
+    }
+
+    fn insert_node(&mut self, node: &Node) -> H256 {
+        let encoded = node.encode();
+        let hash = H256(keccak256(&encoded));
+        self.nodes.insert(hash, encoded);
+        hash
+    }
+
+    /// Walk down the trie from `root` along `nibbles`, and return the branch
+    /// and extension nodes of the path with their depth, and the last node
+    /// of the path, which is either a leaf or an extension node that the path
+    /// diverges from, or `None` if the path ends at an empty slot.
+    fn walk(
+        &self,
+        root: H256,
+        nibbles: &[u8],
+    ) -> Result<(Vec<(usize, Node)>, usize, Option<Node>), Error> {
+        let mut path = Vec::new();
+        let mut depth = 0;
+        let mut hash = (root != *EMPTY_ROOT).then(|| root);
+        while let Some(node_hash) = hash {
+            let node = self.node(node_hash)?;
+            match &node {
+                Node::Branch(children) => {
+                    let nibble = *nibbles
+                        .get(depth)
+                        .ok_or(Error::InvalidTrieUpdate("key ends at branch node"))?;
+                    hash = children[nibble as usize];
+                    path.push((depth, node));
+                    depth += 1;
+                }
+                Node::Extension(ext_path, child) => {
+                    if !nibbles[depth..].starts_with(ext_path) {
+                        return Ok((path, depth, Some(node)));
+                    }
+                    hash = Some(*child);
+                    let ext_len = ext_path.len();
+                    path.push((depth, node));
+                    depth += ext_len;
+                }
+                Node::Leaf(..) => return Ok((path, depth, Some(node))),
+            }
+        }
+        Ok((path, depth, None))
+    }
+
+    /// Return the value of `key` in the trie of `root`.
+    pub fn get(&self, root: H256, key: &H256) -> Result<Option<Vec<u8>>, Error> {
+        let nibbles = key_nibbles(key);
+        Ok(match self.walk(root, &nibbles)? {
+            (_, depth, Some(Node::Leaf(path, value))) if nibbles[depth..] == path[..] => {
+                Some(value)
+            }
+            _ => None,
+        })
+    }
+
+    /// Insert `value` at `key` into the trie of `root`, and return the new
+    /// root.
+    pub fn insert(&mut self, root: H256, key: &H256, value: Vec<u8>) -> Result<H256, Error> {
+        let root = (root != *EMPTY_ROOT).then(|| root);
+        self.insert_at(root, &key_nibbles(key), value)
+    }
+
+    fn insert_at(
+        &mut self,
+        hash: Option<H256>,
+        nibbles: &[u8],
+        value: Vec<u8>,
+    ) -> Result<H256, Error> {
+        let hash = match hash {
+            Some(hash) => hash,
+            None => return Ok(self.insert_node(&Node::Leaf(nibbles.to_vec(), value))),
+        };
+        let node = match self.node(hash)? {
+            Node::Branch(mut children) => {
+                let nibble = *nibbles
+                    .first()
+                    .ok_or(Error::InvalidTrieUpdate("key ends at branch node"))?;
+                let child = children[nibble as usize];
+                children[nibble as usize] = Some(self.insert_at(child, &nibbles[1..], value)?);
+                Node::Branch(children)
+            }
+            Node::Extension(path, child) if nibbles.starts_with(&path) => {
+                let child = self.insert_at(Some(child), &nibbles[path.len()..], value)?;
+                Node::Extension(path, child)
+            }
+            Node::Leaf(path, _) if nibbles == &path[..] => Node::Leaf(path, value),
+            node => {
+                // Split the leaf or extension node at the first nibble where
+                // the key diverges from its path.
+                let (shared, path, sibling) = match node {
+                    Node::Extension(path, child) => {
+                        let shared = shared_len(&path, nibbles);
+                        let sibling = if shared + 1 < path.len() {
+                            self.insert_node(&Node::Extension(path[shared + 1..].to_vec(), child))
+                        } else {
+                            child
+                        };
+                        (shared, path, sibling)
+                    }
+                    Node::Leaf(path, leaf_value) => {
+                        let shared = shared_len(&path, nibbles);
+                        let sibling =
+                            self.insert_node(&Node::Leaf(path[shared + 1..].to_vec(), leaf_value));
+                        (shared, path, sibling)
+                    }
+                    Node::Branch(_) => unreachable!(),
+                };
+                let mut children = [None; 16];
+                children[path[shared] as usize] = Some(sibling);
+                children[nibbles[shared] as usize] =
+                    Some(self.insert_node(&Node::Leaf(nibbles[shared + 1..].to_vec(), value)));
+                let branch = Node::Branch(children);
+                if shared == 0 {
+                    branch
+                } else {
+                    let branch = self.insert_node(&branch);
+                    Node::Extension(path[..shared].to_vec(), branch)
+                }
+            }
+        };
+        Ok(self.insert_node(&node))
+    }
+
+    /// Update `key` in the trie of `root` to `value`, or delete it if `value`
+    /// is `None`, and return the nodes of its path before and after the
+    /// update.
+    ///
+    /// Only the updates which don't change the structure of the trie above
+    /// the end of the path of the key are supported, as listed in
+    /// [`PathEnd`].
+    // TODO: Support the insertion of a key which diverges from the path of
+    // an extension node, and the deletion of a key which leaves its parent
+    // branch node with a single child which is not a leaf.
+    pub fn update(
+        &mut self,
+        root: H256,
+        key: &H256,
+        value: Option<Vec<u8>>,
+    ) -> Result<TrieUpdate, Error> {
+        let nibbles = key_nibbles(key);
+        let (mut path, depth, last) = self.walk(root, &nibbles)?;
+        let (end, child) = match (last, value) {
+            (Some(Node::Leaf(leaf_path, old_value)), value)
+                if nibbles[depth..] == leaf_path[..] =>
+            {
+                let old = Node::Leaf(leaf_path.clone(), old_value);
+                match value {
+                    Some(value) => {
+                        let new = Node::Leaf(leaf_path, value);
+                        let hash = self.insert_node(&new);
+                        let end = PathEnd::Leaf {
+                            depth,
+                            old: Some(old),
+                            new: Some(new),
+                        };
+                        (end, Some(hash))
+                    }
+                    None => self.delete(&mut path, &nibbles, depth, old)?,
+                }
+            }
+            (Some(Node::Extension(..)), _) => {
+                return Err(Error::InvalidTrieUpdate(
+                    "key diverges from an extension node",
+                ))
+            }
+            (Some(Node::Branch(_)), _) => unreachable!(),
+            (Some(leaf), None) => {
+                let hash = leaf.hash();
+                (PathEnd::Other { depth, leaf }, Some(hash))
+            }
+            (Some(other), Some(value)) => self.split(&nibbles, depth, other, value),
+            (None, None) => (PathEnd::Empty { depth }, None),
+            (None, Some(value)) => {
+                let new = Node::Leaf(nibbles[depth..].to_vec(), value);
+                let hash = self.insert_node(&new);
+                let end = PathEnd::Leaf {
+                    depth,
+                    old: None,
+                    new: Some(new),
+                };
+                (end, Some(hash))
+            }
+        };
+
+        let mut child = child;
+        let mut path_nodes = Vec::with_capacity(path.len());
+        for (depth, old) in path.into_iter().rev() {
+            let new = match &old {
+                Node::Branch(children) => {
+                    let mut children = *children;
+                    children[nibbles[depth] as usize] = child;
+                    Node::Branch(children)
+                }
+                Node::Extension(ext_path, _) => Node::Extension(
+                    ext_path.clone(),
+                    child.ok_or(Error::InvalidTrieUpdate("extension node without child"))?,
+                ),
+                Node::Leaf(..) => unreachable!(),
+            };
+            child = Some(self.insert_node(&new));
+            path_nodes.push(PathNode { depth, old, new });
+        }
+        path_nodes.reverse();
+
+        Ok(TrieUpdate {
+            old_root: root,
+            new_root: child.unwrap_or(*EMPTY_ROOT),
+            path: path_nodes,
+            end,
+        })
+    }
+
+    /// Delete the leaf `old` at `depth` whose parent is the last node of
+    /// `path`, and return the end of the path with the hash of the node which
+    /// replaces the leaf.
+    fn delete(
+        &mut self,
+        path: &mut Vec<(usize, Node)>,
+        nibbles: &[u8],
+        depth: usize,
+        old: Node,
+    ) -> Result<(PathEnd, Option<H256>), Error> {
+        let children = match path.last() {
+            Some((_, Node::Branch(children))) => *children,
+            Some(_) => return Err(Error::InvalidTrieUpdate("leaf below extension node")),
+            None => {
+                // The leaf is the root
+                let end = PathEnd::Leaf {
+                    depth,
+                    old: Some(old),
+                    new: None,
+                };
+                return Ok((end, None));
+            }
+        };
+        let mut siblings = children
+            .iter()
+            .enumerate()
+            .filter(|(nibble, child)| *nibble != nibbles[depth - 1] as usize && child.is_some());
+        let sibling = match (siblings.next(), siblings.next()) {
+            (Some((nibble, Some(hash))), None) => (nibble, *hash),
+            (Some(_), Some(_)) => {
+                let end = PathEnd::Leaf {
+                    depth,
+                    old: Some(old),
+                    new: None,
+                };
+                return Ok((end, None));
+            }
+            _ => return Err(Error::InvalidTrieUpdate("branch node with a single child")),
+        };
+
+        // The branch node is left with a single child, so it's merged with
+        // its child and with its parent extension node if any.
+        let (sibling_nibble, sibling_hash) = sibling;
+        let sibling = self.node(sibling_hash)?;
+        let sibling_path = match &sibling {
+            Node::Leaf(sibling_path, _) => sibling_path.clone(),
+            _ => {
+                return Err(Error::InvalidTrieUpdate(
+                    "deletion merges a branch node with a non-leaf node",
+                ))
+            }
+        };
+        let (branch_depth, branch) = path.pop().unwrap();
+        let extension = match path.last() {
+            Some((_, Node::Extension(..))) => path.pop(),
+            _ => None,
+        };
+        let other_depth = extension.as_ref().map_or(branch_depth, |(depth, _)| *depth);
+        let other_path = nibbles[other_depth..branch_depth]
+            .iter()
+            .copied()
+            .chain(std::iter::once(sibling_nibble as u8))
+            .chain(sibling_path)
+            .collect();
+        let other_value = match &sibling {
+            Node::Leaf(_, value) => value.clone(),
+            _ => unreachable!(),
+        };
+        let other = Node::Leaf(other_path, other_value);
+        let hash = self.insert_node(&other);
+        let end = PathEnd::Split {
+            depth: other_depth,
+            other,
+            extension: extension.map(|(_, node)| node),
+            branch,
+            leaf: old,
+            sibling,
+            is_insert: false,
+        };
+        Ok((end, Some(hash)))
+    }
+
+    /// Split the leaf `other` of another key at `depth` to insert `value` at
+    /// the key of `nibbles`, and return the end of the path with the hash of
+    /// the node which replaces `other`.
+    fn split(
+        &mut self,
+        nibbles: &[u8],
+        depth: usize,
+        other: Node,
+        value: Vec<u8>,
+    ) -> (PathEnd, Option<H256>) {
+        let (other_path, other_value) = match &other {
+            Node::Leaf(path, value) => (path, value),
+            _ => unreachable!(),
+        };
+        let shared = shared_len(other_path, &nibbles[depth..]);
+        let branch_depth = depth + shared;
+        let sibling = Node::Leaf(other_path[shared + 1..].to_vec(), other_value.clone());
+        let leaf = Node::Leaf(nibbles[branch_depth + 1..].to_vec(), value);
+        let mut children = [None; 16];
+        children[other_path[shared] as usize] = Some(self.insert_node(&sibling));
+        children[nibbles[branch_depth] as usize] = Some(self.insert_node(&leaf));
+        let branch = Node::Branch(children);
+        let mut hash = self.insert_node(&branch);
+        let extension =
+            (shared > 0).then(|| Node::Extension(nibbles[depth..branch_depth].to_vec(), hash));
+        if let Some(extension) = &extension {
+            hash = self.insert_node(extension);
+        }
+        let end = PathEnd::Split {
+            depth,
+            other,
+            extension,
+            branch,
+            leaf,
+            sibling,
+            is_insert: true,
+        };
+        (end, Some(hash))
+    }
+}
+
+/// Return the number of leading nibbles shared by `a` and `b`.
+fn shared_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mpt::verify_trie_proof;
+    use eth_types::Bytes;
+    use pretty_assertions::assert_eq;
+
+    /// Return a key starting with the nibbles of `prefix`, followed by the
+    /// nibble 0xf.
+    fn key(prefix: &[u8]) -> H256 {
+        let mut nibbles = prefix.to_vec();
+        nibbles.resize(64, 0xf);
+        H256::from_slice(
+            &nibbles
+                .chunks(2)
+                .map(|pair| (pair[0] << 4) | pair[1])
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Return a value long enough for its leaf not to be embedded in its
+    /// parent whatever its depth.
+    fn value(byte: u8) -> Vec<u8> {
+        vec![byte; 32]
+    }
+
+    /// Build the trie of `entries` by inserting them in order.
+    fn build(entries: &[(H256, Vec<u8>)]) -> (Trie, H256) {
+        let mut trie = Trie::default();
+        let mut root = *EMPTY_ROOT;
+        for (key, value) in entries {
+            root = trie.insert(root, key, value.clone()).unwrap();
+        }
+        (trie, root)
+    }
+
+    /// Return the proof of `key` in the trie of `root`.
+    fn proof(trie: &Trie, root: H256, key: &H256) -> Vec<Bytes> {
+        let nibbles = key_nibbles(key);
+        let (path, _, last) = trie.walk(root, &nibbles).unwrap();
+        path.into_iter()
+            .map(|(_, node)| node)
+            .chain(last)
+            .map(|node| node.encode().into())
+            .collect()
+    }
+
+    #[test]
+    fn trie_insert_any_order() {
+        let entries: Vec<_> = (0..64u8)
+            .map(|i| (H256(keccak256(&[i])), value(i)))
+            .collect();
+        let (trie, root) = build(&entries);
+        let (_, reversed_root) = build(&entries.iter().rev().cloned().collect::<Vec<_>>());
+        assert_eq!(root, reversed_root);
+
+        for (key, value) in &entries {
+            assert_eq!(trie.get(root, key).unwrap().as_ref(), Some(value));
+            assert_eq!(
+                verify_trie_proof(root, key.as_bytes(), &proof(&trie, root, key)),
+                Ok(Some(value.clone()))
+            );
+        }
+        let absent = H256(keccak256(&[0xff]));
+        assert_eq!(trie.get(root, &absent).unwrap(), None);
+        assert_eq!(
+            verify_trie_proof(root, absent.as_bytes(), &proof(&trie, root, &absent)),
+            Ok(None)
+        );
+    }
+
+    /// Update the trie of `entries` with `update`, check that the new root is
+    /// the root of the trie of `expected`, and return the update.
+    fn check_update(
+        entries: &[(H256, Vec<u8>)],
+        update: (H256, Option<Vec<u8>>),
+        expected: &[(H256, Vec<u8>)],
+    ) -> TrieUpdate {
+        let (mut trie, root) = build(entries);
+        let (key, value) = update;
+        let trie_update = trie.update(root, &key, value.clone()).unwrap();
+        assert_eq!(trie_update.old_root, root);
+        assert_eq!(trie_update.new_root, build(expected).1);
+        assert_eq!(
+            trie_update.old_value(),
+            trie.get(root, &key).unwrap().as_deref()
+        );
+        assert_eq!(trie_update.new_value(), value.as_deref());
+        assert_eq!(trie.get(trie_update.new_root, &key).unwrap(), value);
+        trie_update
+    }
+
+    #[test]
+    fn trie_update_leaf() {
+        let (a, c, d) = (key(&[1]), key(&[2]), key(&[3]));
+        let entries = [(a, value(1)), (c, value(2))];
+
+        // Modification
+        let update = check_update(
+            &entries,
+            (a, Some(value(3))),
+            &[(a, value(3)), (c, value(2))],
+        );
+        assert!(matches!(
+            update.end,
+            PathEnd::Leaf {
+                depth: 1,
+                old: Some(_),
+                new: Some(_)
+            }
+        ));
+        assert_eq!(update.path.len(), 1);
+
+        // Insertion into an empty slot
+        let update = check_update(
+            &entries,
+            (d, Some(value(3))),
+            &[(a, value(1)), (c, value(2)), (d, value(3))],
+        );
+        assert!(matches!(
+            update.end,
+            PathEnd::Leaf {
+                depth: 1,
+                old: None,
+                new: Some(_)
+            }
+        ));
+
+        // Deletion from a branch node with 3 children
+        let update = check_update(
+            &[(a, value(1)), (c, value(2)), (d, value(3))],
+            (d, None),
+            &entries,
+        );
+        assert!(matches!(
+            update.end,
+            PathEnd::Leaf {
+                depth: 1,
+                old: Some(_),
+                new: None
+            }
+        ));
+
+        // Insertion into and deletion from an empty trie
+        let update = check_update(&[], (a, Some(value(1))), &[(a, value(1))]);
+        assert!(matches!(
+            update.end,
+            PathEnd::Leaf {
+                depth: 0,
+                old: None,
+                new: Some(_)
+            }
+        ));
+        assert_eq!(update.old_root, *EMPTY_ROOT);
+        let update = check_update(&[(a, value(1))], (a, None), &[]);
+        assert!(matches!(
+            update.end,
+            PathEnd::Leaf {
+                depth: 0,
+                old: Some(_),
+                new: None
+            }
+        ));
+        assert_eq!(update.new_root, *EMPTY_ROOT);
+    }
+
+    #[test]
+    fn trie_update_absent() {
+        let (a, b, c, d) = (key(&[1, 0]), key(&[1, 1]), key(&[2]), key(&[3]));
+        let entries = [(a, value(1)), (c, value(2))];
+
+        let update = check_update(&entries, (d, None), &entries);
+        assert_eq!(update.end, PathEnd::Empty { depth: 1 });
+        assert_eq!(update.new_root, update.old_root);
+        let update = check_update(&entries, (b, None), &entries);
+        assert!(matches!(update.end, PathEnd::Other { depth: 1, .. }));
+        assert_eq!(update.new_root, update.old_root);
+        let update = check_update(&[], (a, None), &[]);
+        assert_eq!(update.end, PathEnd::Empty { depth: 0 });
+    }
+
+    #[test]
+    fn trie_update_split_and_merge() {
+        let (a, b, b2, c) = (key(&[1, 0, 0]), key(&[1, 1]), key(&[1, 0, 1]), key(&[2]));
+        let entries = [(a, value(1)), (c, value(2))];
+
+        // Split without extension node, and the merge back
+        let split = [(a, value(1)), (b, value(3)), (c, value(2))];
+        let update = check_update(&entries, (b, Some(value(3))), &split);
+        assert!(matches!(
+            update.end,
+            PathEnd::Split {
+                depth: 1,
+                extension: None,
+                is_insert: true,
+                ..
+            }
+        ));
+        let update = check_update(&split, (b, None), &entries);
+        assert!(matches!(
+            update.end,
+            PathEnd::Split {
+                depth: 1,
+                extension: None,
+                is_insert: false,
+                ..
+            }
+        ));
+
+        // Split with an extension node of 1 nibble, and the merge back
+        let split = [(a, value(1)), (b2, value(3)), (c, value(2))];
+        let update = check_update(&entries, (b2, Some(value(3))), &split);
+        assert!(matches!(
+            update.end,
+            PathEnd::Split {
+                depth: 1,
+                extension: Some(Node::Extension(_, _)),
+                is_insert: true,
+                ..
+            }
+        ));
+        let update = check_update(&split, (b2, None), &entries);
+        assert!(matches!(
+            update.end,
+            PathEnd::Split {
+                depth: 1,
+                extension: Some(_),
+                is_insert: false,
+                ..
+            }
+        ));
+
+        // Split of the root leaf
+        let update = check_update(
+            &[(a, value(1))],
+            (b2, Some(value(3))),
+            &[(a, value(1)), (b2, value(3))],
+        );
+        assert!(matches!(
+            update.end,
+            PathEnd::Split {
+                depth: 0,
+                extension: Some(_),
+                ..
+            }
+        ));
+        assert!(update.path.is_empty());
+    }
+
+    #[test]
+    fn trie_update_unsupported() {
+        // The root is an extension node of 2 nibbles
+        let (a, b, c) = (key(&[1, 0, 0]), key(&[1, 0, 1]), key(&[2]));
+        let (mut trie, root) = build(&[(a, value(1)), (b, value(2))]);
+        assert!(matches!(
+            trie.update(root, &c, Some(value(3))),
+            Err(Error::InvalidTrieUpdate(
+                "key diverges from an extension node"
+            ))
+        ));
+
+        // The deletion of `c` leaves the root branch node with the extension
+        // node above `a` and `b` as single child.
+        let (mut trie, root) = build(&[(a, value(1)), (b, value(2)), (c, value(3))]);
+        assert!(matches!(
+            trie.update(root, &c, None),
+            Err(Error::InvalidTrieUpdate(
+                "deletion merges a branch node with a non-leaf node"
+            ))
+        ));
+
+        assert!(matches!(
+            Trie::default().update(H256::zero(), &a, None),
+            Err(Error::MissingTrieNode(hash)) if hash.is_zero()
+        ));
+    }
+}
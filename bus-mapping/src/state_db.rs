@@ -225,11 +225,24 @@ impl StateDB {
         debug_assert!(exist);
     }
 
+    /// Check whether `addr` has been self destructed in the current
+    /// transaction.
+    pub fn check_account_destructed(&self, addr: &Address) -> bool {
+        self.destructed_account.contains(addr)
+    }
+
     /// Set account as self destructed.
     pub fn destruct_account(&mut self, addr: Address) {
         self.destructed_account.insert(addr);
     }
 
+    /// Unset account as self destructed, used when the self destruction is
+    /// reverted.
+    pub fn remove_destructed_account(&mut self, addr: &Address) {
+        let exist = self.destructed_account.remove(addr);
+        debug_assert!(exist);
+    }
+
     /// Retrieve refund.
     pub fn refund(&self) -> u64 {
         self.refund
@@ -240,7 +253,8 @@ impl StateDB {
         self.refund = value;
     }
 
-    /// Clear access list and refund, and commit dirty storage.
+    /// Clear access list and refund, commit dirty storage, and delete the
+    /// self destructed accounts.
     /// It should be invoked before processing
     /// with new transaction with the same [`StateDB`].
     pub fn commit_tx(&mut self) {
@@ -255,6 +269,7 @@ impl StateDB {
             let (_, account) = self.get_account_mut(&addr);
             *account = ACCOUNT_ZERO.clone();
         }
+        self.destructed_account = HashSet::new();
         self.refund = 0;
     }
 }
@@ -318,4 +333,35 @@ mod statedb_tests {
         assert!(found);
         assert_eq!(value, &Word::from(102));
     }
+
+    #[test]
+    fn statedb_destruct_account() {
+        let addr_a = address!("0x0000000000000000000000000000000000000001");
+        let mut statedb = StateDB::new();
+
+        let (_, acc) = statedb.get_account_mut(&addr_a);
+        acc.nonce = Word::from(1);
+        acc.balance = Word::from(100);
+        let (_, value) = statedb.get_storage_mut(&addr_a, &Word::from(2));
+        *value = Word::from(101);
+
+        // Destruct and revert the destruction
+        statedb.destruct_account(addr_a);
+        assert!(statedb.check_account_destructed(&addr_a));
+        statedb.remove_destructed_account(&addr_a);
+        assert!(!statedb.check_account_destructed(&addr_a));
+
+        // The account still exists until the end of the tx
+        statedb.destruct_account(addr_a);
+        let (_, acc) = statedb.get_account(&addr_a);
+        assert_eq!(acc.balance, Word::from(100));
+
+        // The account is deleted when committing the tx
+        statedb.commit_tx();
+        assert!(!statedb.check_account_destructed(&addr_a));
+        let (_, acc) = statedb.get_account(&addr_a);
+        assert_eq!(acc, &Account::zero());
+        let (_, value) = statedb.get_storage(&addr_a, &Word::from(2));
+        assert_eq!(value, &Word::zero());
+    }
 }
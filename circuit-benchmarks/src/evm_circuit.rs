@@ -5,7 +5,7 @@ use halo2_proofs::{
     circuit::{Layouter, SimpleFloorPlanner},
     plonk::{Circuit, ConstraintSystem, Error, Expression},
 };
-use zkevm_circuits::evm_circuit::{witness::Block, EvmCircuit};
+use zkevm_circuits::evm_circuit::{witness::Block, EvmCircuitConfig};
 
 #[derive(Debug, Default)]
 pub struct TestCircuit<F> {
@@ -13,7 +13,7 @@ pub struct TestCircuit<F> {
 }
 
 impl<F: Field> Circuit<F> for TestCircuit<F> {
-    type Config = EvmCircuit<F>;
+    type Config = EvmCircuitConfig<F>;
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
@@ -25,12 +25,14 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
         let rw_table = [(); 11].map(|_| meta.advice_column());
         let bytecode_table = [(); 5].map(|_| meta.advice_column());
         let block_table = [(); 3].map(|_| meta.advice_column());
-        let copy_table = [(); 11].map(|_| meta.advice_column());
+        let copy_table = [(); 12].map(|_| meta.advice_column());
+        let exp_table = [(); 10].map(|_| meta.advice_column());
+        let keccak_table = [(); 4].map(|_| meta.advice_column());
         // Use constant expression to mock constant instance column for a more
         // reasonable benchmark.
         let power_of_randomness = [(); 31].map(|_| Expression::Constant(F::one()));
 
-        EvmCircuit::configure(
+        EvmCircuitConfig::configure(
             meta,
             power_of_randomness,
             &tx_table,
@@ -38,6 +40,8 @@ impl<F: Field> Circuit<F> for TestCircuit<F> {
             &bytecode_table,
             &block_table,
             &copy_table,
+            &exp_table,
+            &keccak_table,
         )
     }
 
@@ -18,10 +18,7 @@ mod tests {
     use rand_xorshift::XorShiftRng;
     use secp256k1::Secp256k1Affine;
     use std::marker::PhantomData;
-    use zkevm_circuits::tx_circuit::{
-        sign_verify::{SignVerifyChip, POW_RAND_SIZE, VERIF_HEIGHT},
-        TxCircuit,
-    };
+    use zkevm_circuits::tx_circuit::{sign_verify::SignVerifyChip, TxCircuit};
 
     #[cfg_attr(not(feature = "benches"), ignore)]
     #[test]
@@ -60,11 +57,7 @@ mod tests {
         }];
 
         let randomness = Fr::random(&mut rng);
-        let mut instance: Vec<Vec<Fr>> = (1..POW_RAND_SIZE + 1)
-            .map(|exp| vec![randomness.pow(&[exp as u64, 0, 0, 0]); MAX_TXS * VERIF_HEIGHT])
-            .collect();
-        // SignVerifyChip -> ECDSAChip -> MainGate instance column
-        instance.push(vec![]);
+        let instance = TxCircuit::<Fr, MAX_TXS, MAX_CALLDATA>::instance(DEGREE as u32, randomness);
         let circuit = TxCircuit::<Fr, MAX_TXS, MAX_CALLDATA> {
             sign_verify: SignVerifyChip {
                 aux_generator,
@@ -74,6 +67,7 @@ mod tests {
             randomness,
             txs,
             chain_id,
+            size: 1 << DEGREE,
         };
 
         // Bench setup generation
@@ -85,7 +79,7 @@ mod tests {
         let general_params: Params<G1Affine> =
             Params::<G1Affine>::unsafe_setup::<Bn256>(DEGREE.try_into().unwrap());
         let verifier_params: ParamsVerifier<Bn256> =
-            general_params.verifier(MAX_TXS * VERIF_HEIGHT).unwrap();
+            general_params.verifier((1 << DEGREE) - 64).unwrap();
         end_timer!(start1);
 
         // Initialize the proving key
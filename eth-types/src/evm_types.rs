@@ -66,6 +66,19 @@ impl fmt::Debug for Gas {
 pub const MAX_REFUND_QUOTIENT_OF_GAS_USED: usize = 5;
 /// Gas stipend when CALL or CALLCODE is attached with value.
 pub const GAS_STIPEND_CALL_WITH_VALUE: u64 = 2300;
+/// SSTORE fails when the gas left is not more than this reentrancy sentry
+/// (EIP-2200).
+pub const SSTORE_SENTRY_GAS: u64 = 2300;
+/// Maximum size in bytes of the code deployed by a contract creation
+/// (EIP-170).
+pub const MAX_CODE_SIZE: u64 = 0x6000;
+/// First byte which the deployed code is not allowed to start with (EIP-3541).
+pub const INVALID_INIT_CODE_FIRST_BYTE: u8 = 0xef;
+/// Number of most recent block hashes that BLOCKHASH has access to.
+pub const NUM_PREV_BLOCK_ALLOWED: u64 = 256;
+/// Maximum depth of the call stack, beyond which *CALL* and CREATE* fail
+/// without running the callee.
+pub const MAX_CALL_DEPTH: u64 = 1024;
 
 /// Defines the gas consumption.
 #[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -100,8 +113,12 @@ impl GasCost {
     pub const SELFDESTRUCT: Self = Self(5000);
     /// Constant cost for CREATE
     pub const CREATE: Self = Self(32000);
+    /// Cost per byte of the code deposited by a contract creation
+    pub const CODE_DEPOSIT_BYTE_COST: Self = Self(200);
     /// Constant cost for copying every word
     pub const COPY: Self = Self(3);
+    /// Constant cost for copying every word for SHA3
+    pub const COPY_SHA3: Self = Self(6);
     /// Constant cost for accessing account or storage key
     pub const WARM_ACCESS: Self = Self(100);
     /// Constant cost for a cold SLOAD
@@ -129,6 +146,11 @@ impl GasCost {
     pub const MEMORY_EXPANSION_LINEAR_COEFF: Self = Self(3);
     /// constant gas for logs op codes
     pub const LOG: Self = Self(375);
+    /// Gas per byte of data for logs op codes
+    pub const LOG_DATA_GAS: Self = Self(8);
+    /// Times ceil exponent byte size for the EXP instruction, EIP-158 changed
+    /// it from 10 to 50.
+    pub const EXP_BYTE_TIMES: Self = Self(50);
 }
 
 impl GasCost {
@@ -1,6 +1,6 @@
 //! Utility functions to help calculate gas
 
-use super::GasCost;
+use super::{GasCost, OpcodeId};
 use crate::Word;
 
 /// Calculate memory expansion gas cost by current and next memory word size.
@@ -27,6 +27,24 @@ pub fn memory_copier_gas_cost(
         + memory_expansion_gas_cost(curr_memory_word_size, next_memory_word_size)
 }
 
+/// Calculate the gas cost of CREATE and CREATE2 (without the gas passed to
+/// the callee), by current and next memory word size, and init code length.
+pub fn create_gas_cost(
+    curr_memory_word_size: u64,
+    next_memory_word_size: u64,
+    init_code_length: u64,
+    is_create2: bool,
+) -> u64 {
+    let hash_gas_cost = if is_create2 {
+        (init_code_length + 31) / 32 * GasCost::COPY_SHA3.as_u64()
+    } else {
+        0
+    };
+    GasCost::CREATE.as_u64()
+        + hash_gas_cost
+        + memory_expansion_gas_cost(curr_memory_word_size, next_memory_word_size)
+}
+
 /// Calculate EIP 150 gas passed to callee.
 pub fn eip150_gas(gas_left: u64, gas_specified: Word) -> u64 {
     let capped_gas = gas_left - gas_left / 64;
@@ -40,3 +58,117 @@ pub fn eip150_gas(gas_left: u64, gas_specified: Word) -> u64 {
 
     capped_gas
 }
+
+/// Calculate the gas cost of EXP, which consists of the constant gas cost and
+/// the dynamic gas cost proportional to the byte size of the exponent.
+pub fn exp_gas_cost(exponent: Word) -> u64 {
+    let exponent_byte_size = (exponent.bits() as u64 + 7) / 8;
+    OpcodeId::EXP.constant_gas_cost().as_u64()
+        + GasCost::EXP_BYTE_TIMES.as_u64() * exponent_byte_size
+}
+
+/// Calculate the gas cost of LOG0 to LOG4 by current and next memory word
+/// size, number of topics and data length.
+pub fn log_gas_cost(
+    curr_memory_word_size: u64,
+    next_memory_word_size: u64,
+    num_topics: u64,
+    data_length: u64,
+) -> u64 {
+    GasCost::LOG.as_u64()
+        + GasCost::LOG.as_u64() * num_topics
+        + GasCost::LOG_DATA_GAS.as_u64() * data_length
+        + memory_expansion_gas_cost(curr_memory_word_size, next_memory_word_size)
+}
+
+/// Calculate the gas cost of SHA3 by current and next memory word size, and
+/// number of bytes to hash.
+pub fn sha3_gas_cost(
+    curr_memory_word_size: u64,
+    next_memory_word_size: u64,
+    num_hash_bytes: u64,
+) -> u64 {
+    let num_words = (num_hash_bytes + 31) / 32;
+    OpcodeId::SHA3.constant_gas_cost().as_u64()
+        + num_words * GasCost::COPY_SHA3.as_u64()
+        + memory_expansion_gas_cost(curr_memory_word_size, next_memory_word_size)
+}
+
+/// Calculate the gas cost of SLOAD by whether the storage slot is warm.
+pub fn sload_gas_cost(is_warm: bool) -> u64 {
+    if is_warm {
+        GasCost::WARM_ACCESS.as_u64()
+    } else {
+        GasCost::COLD_SLOAD.as_u64()
+    }
+}
+
+/// Calculate the gas cost of SSTORE according to EIP-2200 and EIP-2929, by
+/// the new, current and original value of the storage slot, and whether the
+/// storage slot is warm.
+pub fn sstore_gas_cost(value: Word, value_prev: Word, original_value: Word, is_warm: bool) -> u64 {
+    let warm_case_gas = if value_prev == value {
+        GasCost::WARM_ACCESS
+    } else if original_value == value_prev {
+        if original_value.is_zero() {
+            GasCost::SSTORE_SET
+        } else {
+            GasCost::SSTORE_RESET
+        }
+    } else {
+        GasCost::WARM_ACCESS
+    };
+    if is_warm {
+        warm_case_gas.as_u64()
+    } else {
+        warm_case_gas.as_u64() + GasCost::COLD_SLOAD.as_u64()
+    }
+}
+
+/// Calculate the gas cost of CALL, CALLCODE, DELEGATECALL and STATICCALL
+/// (without the gas passed to the callee), by current and next memory word
+/// size, whether the callee account is warm, whether value is transferred, and
+/// whether a new account is created by a CALL to an empty account.
+pub fn call_gas_cost(
+    curr_memory_word_size: u64,
+    next_memory_word_size: u64,
+    is_warm: bool,
+    has_value: bool,
+    is_call_to_empty_account: bool,
+) -> u64 {
+    let access_gas_cost = if is_warm {
+        GasCost::WARM_ACCESS.as_u64()
+    } else {
+        GasCost::COLD_ACCOUNT_ACCESS.as_u64()
+    };
+    let value_gas_cost = if has_value {
+        GasCost::CALL_WITH_VALUE.as_u64()
+            + if is_call_to_empty_account {
+                GasCost::NEW_ACCOUNT.as_u64()
+            } else {
+                0
+            }
+    } else {
+        0
+    };
+    access_gas_cost
+        + value_gas_cost
+        + memory_expansion_gas_cost(curr_memory_word_size, next_memory_word_size)
+}
+
+/// Calculate the gas cost of SELFDESTRUCT by whether the beneficiary is warm,
+/// and whether a new account is created by sending non-zero balance to an
+/// empty beneficiary.
+pub fn selfdestruct_gas_cost(is_warm: bool, is_new_account: bool) -> u64 {
+    OpcodeId::SELFDESTRUCT.constant_gas_cost().as_u64()
+        + if is_warm {
+            0
+        } else {
+            GasCost::COLD_ACCOUNT_ACCESS.as_u64()
+        }
+        + if is_new_account {
+            GasCost::NEW_ACCOUNT.as_u64()
+        } else {
+            0
+        }
+}
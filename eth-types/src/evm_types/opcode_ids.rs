@@ -333,6 +333,121 @@ impl OpcodeId {
     pub fn is_log(&self) -> bool {
         self.as_u8() >= Self::LOG0.as_u8() && self.as_u8() <= Self::LOG4.as_u8()
     }
+
+    /// Returns all the defined `OpcodeId`s.
+    pub fn valid_opcodes() -> Vec<Self> {
+        (0..=u8::MAX)
+            .filter_map(|byte| match Self::try_from(byte) {
+                Ok(OpcodeId::INVALID(_)) | Err(_) => None,
+                Ok(opcode) => Some(opcode),
+            })
+            .collect()
+    }
+
+    /// Returns an `OpcodeId::INVALID` for each byte which is not a defined
+    /// opcode.
+    pub fn invalid_opcodes() -> Vec<Self> {
+        (0..=u8::MAX)
+            .filter_map(|byte| match Self::try_from(byte) {
+                Ok(OpcodeId::INVALID(_)) | Err(_) => Some(OpcodeId::INVALID(byte)),
+                Ok(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns the number of stack items popped and pushed by the `OpcodeId`,
+    /// where the items that `DUPn` and `SWAPn` peek are regarded as popped.
+    fn stack_pops_pushes(&self) -> (u64, u64) {
+        match self {
+            _ if self.is_push() => (0, 1),
+            _ if self.is_dup() => {
+                let n = (self.as_u8() - Self::DUP1.as_u8()) as u64 + 1;
+                (n, n + 1)
+            }
+            _ if self.is_swap() => {
+                let n = (self.as_u8() - Self::SWAP1.as_u8()) as u64 + 1;
+                (n + 1, n + 1)
+            }
+            _ if self.is_log() => {
+                let n = (self.as_u8() - Self::LOG0.as_u8()) as u64;
+                (n + 2, 0)
+            }
+            OpcodeId::STOP | OpcodeId::JUMPDEST | OpcodeId::INVALID(_) => (0, 0),
+            OpcodeId::ADDRESS
+            | OpcodeId::ORIGIN
+            | OpcodeId::CALLER
+            | OpcodeId::CALLVALUE
+            | OpcodeId::CALLDATASIZE
+            | OpcodeId::CODESIZE
+            | OpcodeId::GASPRICE
+            | OpcodeId::RETURNDATASIZE
+            | OpcodeId::COINBASE
+            | OpcodeId::TIMESTAMP
+            | OpcodeId::NUMBER
+            | OpcodeId::DIFFICULTY
+            | OpcodeId::GASLIMIT
+            | OpcodeId::CHAINID
+            | OpcodeId::SELFBALANCE
+            | OpcodeId::BASEFEE
+            | OpcodeId::PC
+            | OpcodeId::MSIZE
+            | OpcodeId::GAS => (0, 1),
+            OpcodeId::POP | OpcodeId::JUMP | OpcodeId::SELFDESTRUCT => (1, 0),
+            OpcodeId::ISZERO
+            | OpcodeId::NOT
+            | OpcodeId::BALANCE
+            | OpcodeId::CALLDATALOAD
+            | OpcodeId::EXTCODESIZE
+            | OpcodeId::EXTCODEHASH
+            | OpcodeId::BLOCKHASH
+            | OpcodeId::MLOAD
+            | OpcodeId::SLOAD => (1, 1),
+            OpcodeId::MSTORE
+            | OpcodeId::MSTORE8
+            | OpcodeId::SSTORE
+            | OpcodeId::JUMPI
+            | OpcodeId::RETURN
+            | OpcodeId::REVERT => (2, 0),
+            OpcodeId::ADD
+            | OpcodeId::MUL
+            | OpcodeId::SUB
+            | OpcodeId::DIV
+            | OpcodeId::SDIV
+            | OpcodeId::MOD
+            | OpcodeId::SMOD
+            | OpcodeId::EXP
+            | OpcodeId::SIGNEXTEND
+            | OpcodeId::LT
+            | OpcodeId::GT
+            | OpcodeId::SLT
+            | OpcodeId::SGT
+            | OpcodeId::EQ
+            | OpcodeId::AND
+            | OpcodeId::OR
+            | OpcodeId::XOR
+            | OpcodeId::BYTE
+            | OpcodeId::SHL
+            | OpcodeId::SHR
+            | OpcodeId::SAR
+            | OpcodeId::SHA3 => (2, 1),
+            OpcodeId::CALLDATACOPY | OpcodeId::CODECOPY | OpcodeId::RETURNDATACOPY => (3, 0),
+            OpcodeId::ADDMOD | OpcodeId::MULMOD | OpcodeId::CREATE => (3, 1),
+            OpcodeId::EXTCODECOPY => (4, 0),
+            OpcodeId::CREATE2 => (4, 1),
+            OpcodeId::DELEGATECALL | OpcodeId::STATICCALL => (6, 1),
+            OpcodeId::CALL | OpcodeId::CALLCODE => (7, 1),
+            _ => unreachable!("PUSHn, DUPn, SWAPn and LOGn are matched by the guards"),
+        }
+    }
+
+    /// Returns the range `(min, max)` of the stack pointer, both inclusive,
+    /// within which the `OpcodeId` neither underflows nor overflows the
+    /// stack. The stack pointer is `1024` when the stack is empty and
+    /// decreases as items are pushed.
+    pub fn valid_stack_ptr_range(&self) -> (u64, u64) {
+        let (pops, pushes) = self.stack_pops_pushes();
+        (pushes.saturating_sub(pops), 1024 - pops)
+    }
 }
 
 impl OpcodeId {
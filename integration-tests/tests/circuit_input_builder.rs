@@ -20,17 +20,23 @@ async fn test_circuit_input_builder_block(block_num: u64) {
     let access_set = cli.get_state_accesses(&eth_block, &geth_trace).unwrap();
     trace!("AccessSet: {:#?}", access_set);
 
-    // 3. Query geth for all accounts, storage keys, and codes from Accesses
-    let (proofs, codes) = cli.get_state(block_num, access_set).await.unwrap();
+    // 3. Query geth for the hashes and the state root of the previous blocks
+    let (history_hashes, prev_state_root) = cli.get_history_hashes(&eth_block).await.unwrap();
 
-    // 4. Build a partial StateDB from step 3
+    // 4. Query geth for all accounts, storage keys, and codes from Accesses
+    let (proofs, codes) = cli
+        .get_state(block_num, prev_state_root, access_set)
+        .await
+        .unwrap();
+
+    // 5. Build a partial StateDB from step 4
     let (state_db, code_db) = cli.build_state_code_db(proofs, codes);
     trace!("StateDB: {:#?}", state_db);
 
-    // 5. For each step in TxExecTraces, gen the associated ops and state
+    // 6. For each step in TxExecTraces, gen the associated ops and state
     // circuit inputs
     let builder = cli
-        .gen_inputs_from_state(state_db, code_db, &eth_block, &geth_trace)
+        .gen_inputs_from_state(state_db, code_db, history_hashes, &eth_block, &geth_trace)
         .unwrap();
 
     trace!("CircuitInputBuilder: {:#?}", builder);
@@ -52,7 +52,7 @@ impl Default for MockBlock {
             gas_limit: Word::from(0x2386f26fc10000u64),
             base_fee_per_gas: Word::zero(),
             extra_data: Bytes::default(),
-            logs_bloom: None,
+            logs_bloom: Some(Bloom::default()),
             timestamp: Word::from(123456789u64),
             difficulty: Word::from(0x200000u64),
             total_difficulty: Word::zero(),
@@ -1,21 +1,81 @@
 use env_logger::Env;
 use halo2_proofs::pairing::bn256::G1Affine;
 use halo2_proofs::poly::commitment::Params;
-use std::env::var;
+use std::collections::HashMap;
+use std::env::{args, var};
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
 
-use prover::compute_proof::compute_proof;
+use prover::compute_proof::{compute_proof, proof_rng};
+use prover::key_cache::KeyCache;
+use prover::structs::{ProofRequestOptions, Proofs};
+use prover::verify_proof::verify_proofs;
 
 /// This command generates and prints the proofs to stdout.
 /// Required environment variables:
 /// - BLOCK_NUM - the block number to generate the proof for
 /// - RPC_URL - a geth http rpc that supports the debug namespace
 /// - PARAMS_PATH - a path to a file generated with the gen_params tool
+/// Optional environment variables:
+/// - KEY_CACHE_DIR - a directory to serialize the verifying keys to, so that
+///   they are only generated once across invocations
+/// - TRANSCRIPT - the transcript to create the proofs with, `blake2b` (default)
+///   or `keccak`
+/// - TX_PROOF, BYTECODE_PROOF, COPY_PROOF - if `1` or `true`, the proof of the
+///   tx, bytecode or copy circuit is generated as well
+/// - TX_CIRCUIT_MAX_TXS - the largest tx circuit to create the proof with, in
+///   txs, defaults to the largest supported one
+/// - DETERMINISTIC_PROOFS - if `1` or `true`, the proofs are blinded with a
+///   fixed seed so that they are reproducible. Only meant for testing, such
+///   proofs are not zero-knowledge.
+///
+/// Invoked as `prover_cmd verify`, it reads the proofs generated by this
+/// command from stdin instead and prints for each circuit whether its proof
+/// is valid, exiting with an error if any of them is not.
+/// Required environment variables:
+/// - PARAMS_PATH - a path to the file the proofs were generated with
+/// Optional environment variables:
+/// - KEY_CACHE_DIR - as above
+///
+/// Invoked as `prover_cmd serve`, it reads one `ProofRequestOptions` per line
+/// from stdin instead and prints the result of each as a line, until stdin is
+/// closed.  The params and the keys are kept in memory across the requests.
+/// This is how `prover_rpcd` runs its workers.
+/// Optional environment variables:
+/// - KEY_CACHE_DIR, DETERMINISTIC_PROOFS - as above
 #[tokio::main]
 async fn main() {
     env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
 
+    if args().nth(1).as_deref() == Some("serve") {
+        serve().await;
+        return;
+    }
+
+    let params_path: String = var("PARAMS_PATH")
+        .expect("PARAMS_PATH env var")
+        .parse()
+        .expect("Cannot parse PARAMS_PATH env var");
+
+    // load polynomial commitment parameters
+    let params = read_params(&params_path).expect("load params");
+
+    let key_cache = KeyCache::new(var("KEY_CACHE_DIR").ok().map(PathBuf::from));
+
+    if args().nth(1).as_deref() == Some("verify") {
+        let proofs: Proofs =
+            serde_json::from_reader(std::io::stdin()).expect("deserialize proofs from stdin");
+        let result =
+            verify_proofs(&params, &params_path, &key_cache, &proofs).expect("verify_proofs");
+
+        serde_json::to_writer(std::io::stdout(), &result).expect("serialize and write");
+        if !result.is_valid() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let block_num: u64 = var("BLOCK_NUM")
         .expect("BLOCK_NUM env var")
         .parse()
@@ -24,19 +84,88 @@ async fn main() {
         .expect("RPC_URL env var")
         .parse()
         .expect("Cannot parse RPC_URL env var");
-    let params_path: String = var("PARAMS_PATH")
-        .expect("PARAMS_PATH env var")
-        .parse()
-        .expect("Cannot parse PARAMS_PATH env var");
 
-    // load polynomial commitment parameters
-    let params_fs = File::open(&params_path).expect("couldn't open params");
-    let params: Params<G1Affine> =
-        Params::read::<_>(&mut BufReader::new(params_fs)).expect("Failed to read params");
+    let rng = proof_rng(env_flag("DETERMINISTIC_PROOFS"));
 
-    let result = compute_proof(&params, &block_num, &rpc_url)
+    let options = ProofRequestOptions {
+        block: block_num,
+        rpc: rpc_url,
+        param: params_path,
+        tx_proof: env_flag("TX_PROOF"),
+        bytecode_proof: env_flag("BYTECODE_PROOF"),
+        copy_proof: env_flag("COPY_PROOF"),
+        tx_circuit_max_txs: var("TX_CIRCUIT_MAX_TXS").ok().map(|max_txs| {
+            max_txs
+                .parse()
+                .expect("Cannot parse TX_CIRCUIT_MAX_TXS env var")
+        }),
+        transcript: var("TRANSCRIPT")
+            .map(|transcript| transcript.parse().expect("Cannot parse TRANSCRIPT env var"))
+            .unwrap_or_default(),
+        ..Default::default()
+    };
+
+    let result = compute_proof(&params, &key_cache, &options, rng)
         .await
         .expect("compute_proof");
 
     serde_json::to_writer(std::io::stdout(), &result).expect("serialize and write");
 }
+
+/// Computes the proofs of the requests read from stdin, see `main`.
+async fn serve() {
+    let key_cache = KeyCache::new(var("KEY_CACHE_DIR").ok().map(PathBuf::from));
+    let mut params_cache: HashMap<String, Params<G1Affine>> = HashMap::new();
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+
+    for request in stdin.lock().lines() {
+        let request = request.expect("read request from stdin");
+        let options: ProofRequestOptions =
+            serde_json::from_str(&request).expect("deserialize request");
+
+        if !params_cache.contains_key(&options.param) {
+            match read_params(&options.param) {
+                Ok(params) => {
+                    params_cache.insert(options.param.clone(), params);
+                }
+                Err(err) => {
+                    write_result(&mut stdout, &Err::<Proofs, _>(err));
+                    continue;
+                }
+            }
+        }
+        let params = &params_cache[&options.param];
+
+        let rng = proof_rng(env_flag("DETERMINISTIC_PROOFS"));
+        let result = compute_proof(params, &key_cache, &options, rng)
+            .await
+            .map_err(|err| err.to_string());
+        write_result(&mut stdout, &result);
+    }
+}
+
+/// Writes `result` as a line to `stdout`
+fn write_result(stdout: &mut std::io::Stdout, result: &Result<Proofs, String>) {
+    serde_json::to_writer(&mut *stdout, result).expect("serialize and write");
+    writeln!(stdout)
+        .and_then(|_| stdout.flush())
+        .expect("write result");
+}
+
+/// Reads the polynomial commitment parameters from `params_path`
+fn read_params(params_path: &str) -> Result<Params<G1Affine>, String> {
+    let params_fs =
+        File::open(params_path).map_err(|err| format!("couldn't open params: {}", err))?;
+    Params::read::<_>(&mut BufReader::new(params_fs))
+        .map_err(|err| format!("Failed to read params: {}", err))
+}
+
+/// Returns whether the env var `name` is set to `1` or `true`
+fn env_flag(name: &str) -> bool {
+    match var(name).as_deref() {
+        Err(_) | Ok("") | Ok("0") | Ok("false") => false,
+        Ok("1") | Ok("true") => true,
+        Ok(value) => panic!("Cannot parse {} env var: {}", name, value),
+    }
+}
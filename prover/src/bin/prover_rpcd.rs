@@ -5,9 +5,12 @@ use hyper::header::HeaderValue;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Method, Request, Response, Server, StatusCode};
 use std::env::var;
+use std::path::PathBuf;
+use std::time::Duration;
 
-use prover::shared_state::SharedState;
+use prover::shared_state::{SharedState, WorkerConfig};
 use prover::structs::*;
+use prover::verify_proof::verify_proofs;
 
 /// sets default headers for CORS requests
 fn set_headers(headers: &mut hyper::HeaderMap, extended: bool) {
@@ -162,6 +165,27 @@ async fn handle_method(
                 }
             }
         }
+        // verifies the proofs returned by `proof` and reports for each circuit
+        // whether its proof is valid
+        "verify" => {
+            let options = params
+                .get(0)
+                .ok_or("expected struct VerifyRequestOptions")?;
+            let options: VerifyRequestOptions =
+                serde_json::from_value(options.to_owned()).map_err(|e| e.to_string())?;
+
+            let param = shared_state.load_param(&options.param).await;
+            let key_cache = shared_state.key_cache.clone();
+            // verification is blocking compute
+            let result = tokio::task::spawn_blocking(move || {
+                verify_proofs(param.as_ref(), &options.param, &key_cache, &options.proofs)
+                    .map_err(|e| e.to_string())
+            })
+            .await
+            .map_err(|e| e.to_string())??;
+
+            Ok(serde_json::to_value(result).unwrap())
+        }
         // TODO/TBD: add method to only return the witnesses for a block.
         //  block table, tx table, etc...
         //
@@ -200,15 +224,47 @@ async fn handle_method(
 /// - BIND - the interface address + port combination to accept connections on
 ///   `[::]:1234`
 /// - PARAMS_PATH - a path to a file generated with the gen_params tool
+/// Optional environment variables:
+/// - KEY_CACHE_DIR - a directory to serialize the verifying keys to, so that
+///   they are only generated once across restarts
+/// - WORKERS - the number of proofs computed in parallel, defaults to 1
+/// - PROVER_CMD - a path to the prover_cmd binary. If set, the proofs are
+///   computed in long-lived child processes, one per worker, so that panics,
+///   OOMs etc. only fail the task while the proving keys are kept in memory
+/// - PROOF_TIMEOUT - the seconds after which a child process computing a proof
+///   is killed, requires PROVER_CMD
+/// DETERMINISTIC_PROOFS of prover_cmd is rejected, because the proofs served
+/// by this command have to be zero-knowledge.
 #[tokio::main]
 async fn main() {
     env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
 
+    if !matches!(
+        var("DETERMINISTIC_PROOFS").as_deref(),
+        Err(_) | Ok("") | Ok("0") | Ok("false")
+    ) {
+        panic!("DETERMINISTIC_PROOFS is only meant for prover_cmd in tests, unset it");
+    }
+
     let addr = var("BIND")
         .expect("BIND env var")
         .parse::<std::net::SocketAddr>()
         .expect("valid socket address");
-    let shared_state = SharedState::new();
+    let worker_config = WorkerConfig {
+        workers: var("WORKERS")
+            .map(|workers| workers.parse().expect("Cannot parse WORKERS env var"))
+            .unwrap_or(1),
+        prover_cmd: var("PROVER_CMD").ok().map(PathBuf::from),
+        timeout: var("PROOF_TIMEOUT").ok().map(|timeout| {
+            Duration::from_secs(timeout.parse().expect("Cannot parse PROOF_TIMEOUT env var"))
+        }),
+    };
+    // e.g. a PROOF_TIMEOUT without PROVER_CMD would be ignored
+    worker_config
+        .validate()
+        .expect("Invalid WORKERS, PROVER_CMD or PROOF_TIMEOUT env var");
+    let shared_state =
+        SharedState::new(var("KEY_CACHE_DIR").ok().map(PathBuf::from), worker_config);
 
     {
         // start the http server
@@ -1,5 +1,6 @@
 use bus_mapping::circuit_input_builder::BuilderClient;
 use bus_mapping::rpc::GethClient;
+use eth_types::Field;
 use ethers_providers::Http;
 use halo2_proofs::{
     pairing::bn256::{Fr, G1Affine},
@@ -7,88 +8,481 @@ use halo2_proofs::{
     poly::commitment::Params,
     transcript::{Blake2bWrite, Challenge255},
 };
-use rand::SeedableRng;
-use rand_xorshift::XorShiftRng;
+use rand::{CryptoRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 
 use std::str::FromStr;
 use std::time::Instant;
 
 use strum::IntoEnumIterator;
+use zkevm_circuits::bytecode_circuit::BytecodeCircuit;
+use zkevm_circuits::copy_circuit::CopyCircuit;
 use zkevm_circuits::evm_circuit::{
-    table::FixedTableTag, test::TestCircuit, witness::block_convert,
+    param::MAX_STEP_HEIGHT,
+    table::FixedTableTag,
+    witness::{block_convert, witness_commitment, Block, RwMap},
+    EvmCircuit,
 };
+use zkevm_circuits::keccak_circuit::KeccakConfig;
+use zkevm_circuits::pi_circuit::{PiCircuit, PublicData};
 use zkevm_circuits::state_circuit::StateCircuit;
+use zkevm_circuits::tx_circuit::TxCircuit;
 
-use crate::structs::Proofs;
+use crate::key_cache::KeyCache;
+use crate::structs::{CircuitProof, ProofRequestOptions, Proofs, TranscriptKind};
+use crate::transcript::KeccakWrite;
 
-/// Gathers debug trace(s) from `rpc_url` for block `block_num` with `params`
-/// created via the `gen_params` tool.
-/// Expects a go-ethereum node with debug & archive capabilities on `rpc_url`.
+/// Number of rows of the state circuit
+pub(crate) const STATE_CIRCUIT_ROWS: usize = 1 << 16;
+
+/// Largest capacity for txs of the tx circuit, to which the bound of
+/// `ProofRequestOptions::tx_circuit_max_txs` defaults.  The txs of a block are
+/// rounded up to a power of two, so that there are only a few tx circuit
+/// keys.  The signature verification takes about 175k rows per tx, so that
+/// the largest circuit fits in `2^26` rows.
+pub const TX_CIRCUIT_MAX_TXS: usize = 256;
+
+/// Capacities for calldata bytes of the tx circuit, see `TX_CIRCUIT_MAX_TXS`
+pub(crate) const TX_CIRCUIT_MAX_CALLDATA: [usize; 6] =
+    [1 << 10, 1 << 12, 1 << 14, 1 << 16, 1 << 18, 1 << 20];
+
+/// Evaluates `$body` with the const `$name` set to `$value`, which has to be
+/// one of the literals `$option`.  Evaluates to an error for any other value.
+macro_rules! with_const {
+    ($value:expr, $name:ident, [$($option:literal),*], $body:expr) => {
+        match $value {
+            $(
+                $option => {
+                    const $name: usize = $option;
+                    $body
+                }
+            )*
+            value => Err(format!("unsupported {}: {}", stringify!($name), value).into()),
+        }
+    };
+}
+pub(crate) use with_const;
+
+/// Evaluates `$body` with the consts `$max_txs` and `$max_calldata` set to the
+/// capacities of the tx circuit given by the slice `$shape`, see
+/// `tx_circuit_shape`.  Evaluates to an error for any other shape.
+macro_rules! with_tx_circuit_shape {
+    ($shape:expr, |$max_txs:ident, $max_calldata:ident| $body:expr) => {
+        match $shape {
+            &[max_txs, max_calldata] => $crate::compute_proof::with_const!(
+                max_txs,
+                $max_txs,
+                [1, 2, 4, 8, 16, 32, 64, 128, 256],
+                $crate::compute_proof::with_const!(
+                    max_calldata,
+                    $max_calldata,
+                    [1024, 4096, 16384, 65536, 262144, 1048576],
+                    $body
+                )
+            ),
+            shape => Err(format!("unsupported tx circuit shape: {:?}", shape).into()),
+        }
+    };
+}
+pub(crate) use with_tx_circuit_shape;
+
+/// Maximum number of txs of the public-input circuit, which takes one row per
+/// tx
+pub(crate) const PI_CIRCUIT_MAX_TXS: usize = 1 << 12;
+
+/// Returns the instance columns of the evm circuit with a domain of `2^k`
+/// rows, which are the powers of `randomness`.
+pub(crate) fn evm_circuit_instance<F: Field>(k: u32, randomness: F) -> Vec<Vec<F>> {
+    (1..32)
+        .map(|exp| vec![randomness.pow(&[exp, 0, 0, 0]); (1 << k) - 64])
+        .collect()
+}
+
+/// Returns the instance columns of the state circuit, which are the powers of
+/// `randomness`.
+pub(crate) fn state_circuit_instance(randomness: Fr) -> Vec<Vec<Fr>> {
+    StateCircuit::<Fr, STATE_CIRCUIT_ROWS>::new(randomness, RwMap::default()).instance()
+}
+
+/// Returns the instance columns of the public-input circuit with a domain of
+/// `2^k` rows, which are the powers of `randomness` followed by
+/// `public_inputs`.
+pub(crate) fn pi_circuit_instance(k: u32, randomness: Fr, public_inputs: Vec<Fr>) -> Vec<Vec<Fr>> {
+    let mut instance = evm_circuit_instance(k, randomness);
+    instance.push(public_inputs);
+    instance
+}
+
+/// Returns the number of steps of the copy events of `block`
+fn num_copy_steps(block: &Block<Fr>) -> usize {
+    block
+        .copy_events
+        .values()
+        .map(|copy_event| copy_event.steps.len())
+        .sum()
+}
+
+/// Returns the number of steps of the exponentiation events of `block`
+fn num_exp_steps(block: &Block<Fr>) -> usize {
+    block
+        .exp_events
+        .iter()
+        .map(|exp_event| exp_event.steps.len())
+        .sum()
+}
+
+/// Returns the capacities of the smallest tx circuit for the txs and calldata
+/// of `block`, which are `[MAX_TXS, MAX_CALLDATA]`.  `MAX_TXS` is the number
+/// of txs rounded up to a power of two, which may not exceed `max_txs`.
+fn tx_circuit_shape(
+    block: &Block<Fr>,
+    max_txs: usize,
+) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+    if max_txs > TX_CIRCUIT_MAX_TXS {
+        return Err(format!(
+            "tx circuit bound of {} txs exceeds the supported {} txs",
+            max_txs, TX_CIRCUIT_MAX_TXS
+        )
+        .into());
+    }
+    let num_txs = block.txs.len();
+    if num_txs > max_txs {
+        return Err(format!(
+            "block exceeds the tx circuit: {} txs (max {})",
+            num_txs, max_txs
+        )
+        .into());
+    }
+    let num_calldata: usize = block.txs.iter().map(|tx| tx.call_data.len()).sum();
+    let max_calldata = TX_CIRCUIT_MAX_CALLDATA
+        .into_iter()
+        .find(|max| num_calldata <= *max)
+        .ok_or_else(|| {
+            format!(
+                "block exceeds the tx circuit: {} calldata bytes (max {})",
+                num_calldata,
+                TX_CIRCUIT_MAX_CALLDATA[TX_CIRCUIT_MAX_CALLDATA.len() - 1]
+            )
+        })?;
+
+    Ok(vec![num_txs.max(1).next_power_of_two(), max_calldata])
+}
+
+/// Pads the circuits of `block` to a domain of `2^k` rows, so that their fixed
+/// columns, and hence their keys, only depend on `k`.
+pub(crate) fn pad_block(block: &mut Block<Fr>, k: u32) {
+    // The powers of randomness are only given in the first `2^k - 64` rows
+    let rows = (1 << k) - 64;
+    // The padding steps end at most `2 * MAX_STEP_HEIGHT` rows after the
+    // padding
+    block.evm_circuit_pad_to = rows - 2 * MAX_STEP_HEIGHT;
+    // The copy events take an even number of rows and are followed by 2
+    // rows, the exponentiation events are followed by 1 row.
+    block.copy_circuit_pad_to = (rows - 2) & !1;
+    block.exp_circuit_pad_to = rows - 1;
+}
+
+/// Returns the values that determine the fixed columns of the evm circuit for
+/// `block`, which are the paddings of the evm, copy and exponentiation
+/// circuits.
+pub(crate) fn evm_circuit_shape(block: &Block<Fr>) -> Vec<usize> {
+    vec![
+        block.evm_circuit_pad_to,
+        block.copy_circuit_pad_to,
+        block.exp_circuit_pad_to,
+    ]
+}
+
+/// Returns the rng for the blinding factors of the proofs, seeded from the OS
+/// randomness.  `deterministic` uses a fixed seed instead, to create
+/// reproducible proofs in tests: it must not be used otherwise, because
+/// predictable blinding factors break the zero-knowledge of the proofs.
+pub fn proof_rng(deterministic: bool) -> ChaCha20Rng {
+    if deterministic {
+        log::warn!("proofs: blinded with a fixed seed, they are not zero-knowledge");
+        ChaCha20Rng::seed_from_u64(0)
+    } else {
+        ChaCha20Rng::from_entropy()
+    }
+}
+
+/// Creates a proof of `circuit` with `pk` and `transcript`.  `instance`
+/// returns the instance columns for the degree of the circuit domain.
+pub(crate) fn create_circuit_proof<C: Circuit<Fr>, R: RngCore + CryptoRng>(
+    params: &Params<G1Affine>,
+    pk: &ProvingKey<G1Affine>,
+    circuit: C,
+    instance: impl FnOnce(u32) -> Vec<Vec<Fr>>,
+    transcript: TranscriptKind,
+    rng: &mut R,
+) -> Result<CircuitProof, Box<dyn std::error::Error>> {
+    let instance = instance(pk.get_vk().get_domain().k());
+    let instance: Vec<&[Fr]> = instance.iter().map(|column| column.as_slice()).collect();
+
+    // create a proof
+    let proof = match transcript {
+        TranscriptKind::Blake2b => {
+            let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+            create_proof(params, pk, &[circuit], &[&instance], rng, &mut transcript)?;
+            transcript.finalize()
+        }
+        TranscriptKind::Keccak => {
+            let mut transcript = KeccakWrite::<_, _, Challenge255<_>>::init(vec![]);
+            create_proof(params, pk, &[circuit], &[&instance], rng, &mut transcript)?;
+            transcript.finalize()
+        }
+    };
+
+    Ok(CircuitProof {
+        proof: proof.into(),
+        shape: vec![],
+    })
+}
+
+/// Gathers debug trace(s) from `options.rpc` for block `options.block` with
+/// `params` created via the `gen_params` tool and read from `options.param`.
+/// The proofs of the tx, bytecode and copy circuits are only created if
+/// enabled in `options`.
+/// The proving keys are taken from `key_cache`, the proofs are created with
+/// `options.transcript` and blinded with `rng`, see `proof_rng`.
+/// Expects a go-ethereum node with debug & archive capabilities on
+/// `options.rpc`.
 pub async fn compute_proof(
     params: &Params<G1Affine>,
-    block_num: &u64,
-    rpc_url: &str,
+    key_cache: &KeyCache,
+    options: &ProofRequestOptions,
+    mut rng: impl RngCore + CryptoRng,
 ) -> Result<Proofs, Box<dyn std::error::Error>> {
+    let params_path = options.param.as_str();
+    let transcript = options.transcript;
+
     // request & build the inputs for the circuits
     let time_started = Instant::now();
-    let url = Http::from_str(rpc_url)?;
+    let url = Http::from_str(&options.rpc)?;
     let geth_client = GethClient::new(url);
     let builder = BuilderClient::new(geth_client).await?;
-    let builder = builder.gen_inputs(*block_num).await?;
-
-    // TODO: only {evm,state}_proof are implemented right now
-    let evm_proof;
-    let state_proof;
-    let block = block_convert(&builder.block, &builder.code_db);
-    {
-        // generate evm_circuit proof
-        let circuit = TestCircuit::<Fr>::new(block.clone(), FixedTableTag::iter().collect());
-
-        // TODO: can this be pre-generated to a file?
-        // related
-        // https://github.com/zcash/halo2/issues/443
-        // https://github.com/zcash/halo2/issues/449
-        let vk = keygen_vk(params, &circuit)?;
-        let pk = keygen_pk(params, vk, &circuit)?;
-
-        // Create randomness
-        let rng = XorShiftRng::from_seed([
-            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
-            0xbc, 0xe5,
-        ]);
-
-        // create a proof
-        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
-        create_proof(params, &pk, &[circuit], &[], rng, &mut transcript)?;
-        evm_proof = transcript.finalize();
+    let builder = builder.gen_inputs(options.block).await?;
+
+    let mut block = block_convert(&builder.block, &builder.code_db);
+    let randomness = block.randomness;
+    let witness_commitment = witness_commitment(&block);
+
+    // generate state_circuit proof
+    let circuit = StateCircuit::<Fr, STATE_CIRCUIT_ROWS>::new(randomness, block.rws.clone());
+    let pk = key_cache.get_or_create("state", params_path, params, vec![], &circuit)?;
+    // All the circuits are in the domain of `params`
+    let k = pk.get_vk().get_domain().k();
+    let state_proof = create_circuit_proof(
+        params,
+        &pk,
+        circuit,
+        |_| state_circuit_instance(randomness),
+        transcript,
+        &mut rng,
+    )?;
+
+    // pad the circuits to the domain
+    let num_rows = [
+        ("evm", EvmCircuit::get_num_rows_required(&block)),
+        ("copy", num_copy_steps(&block)),
+        ("exponentiation", num_exp_steps(&block)),
+    ];
+    pad_block(&mut block, k);
+    for ((name, num_rows), max_rows) in num_rows.into_iter().zip(evm_circuit_shape(&block)) {
+        if num_rows > max_rows {
+            return Err(format!(
+                "block exceeds the {} circuit: {} rows (max {})",
+                name, num_rows, max_rows
+            )
+            .into());
+        }
+    }
+    let num_rows = KeccakConfig::<Fr>::get_num_rows_required(&block.sha3_inputs);
+    if num_rows > 1 << k {
+        return Err(format!(
+            "block exceeds the keccak circuit: {} rows (max {})",
+            num_rows,
+            1 << k
+        )
+        .into());
     }
 
-    {
-        // generate state_circuit proof
-        const N_ROWS: usize = 1 << 16;
-        let circuit = StateCircuit::<Fr, N_ROWS>::new(block.randomness, block.rws);
-
-        // TODO: same quest like in the first scope
-        let vk = keygen_vk(params, &circuit)?;
-        let pk = keygen_pk(params, vk, &circuit)?;
-
-        // Create randomness
-        let rng = XorShiftRng::from_seed([
-            0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06,
-            0xbc, 0xe5,
-        ]);
-
-        // create a proof
-        let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
-        create_proof(params, &pk, &[circuit], &[], rng, &mut transcript)?;
-        state_proof = transcript.finalize();
+    // generate evm_circuit proof
+    let circuit = EvmCircuit::<Fr>::new(block.clone(), FixedTableTag::iter().collect(), 1 << k);
+    let pk = key_cache.get_or_create(
+        "evm",
+        params_path,
+        params,
+        evm_circuit_shape(&block),
+        &circuit,
+    )?;
+    let evm_proof = create_circuit_proof(
+        params,
+        &pk,
+        circuit,
+        |k| evm_circuit_instance(k, randomness),
+        transcript,
+        &mut rng,
+    )?;
+
+    // generate pi_circuit proof
+    if block.txs.len() > PI_CIRCUIT_MAX_TXS {
+        return Err(format!(
+            "block exceeds the public-input circuit: {} txs (max {})",
+            block.txs.len(),
+            PI_CIRCUIT_MAX_TXS
+        )
+        .into());
     }
+    let circuit = PiCircuit::<Fr, PI_CIRCUIT_MAX_TXS>::new(&block, k);
+    let public_inputs = circuit.public_inputs();
+    let pk = key_cache.get_or_create("pi", params_path, params, vec![], &circuit)?;
+    let pi_proof = create_circuit_proof(
+        params,
+        &pk,
+        circuit,
+        |k| pi_circuit_instance(k, randomness, public_inputs),
+        transcript,
+        &mut rng,
+    )?;
+
+    // generate tx_circuit proof
+    let tx_proof = if options.tx_proof {
+        let shape = tx_circuit_shape(
+            &block,
+            options.tx_circuit_max_txs.unwrap_or(TX_CIRCUIT_MAX_TXS),
+        )?;
+        let mut proof = with_tx_circuit_shape!(shape.as_slice(), |MAX_TXS, MAX_CALLDATA| {
+            // The auxiliary generator of the signature verification has to be
+            // unpredictable, so it's sampled from the rng of the blinding
+            // factors.
+            let circuit =
+                TxCircuit::<Fr, MAX_TXS, MAX_CALLDATA>::new_from_block(&block, k, &mut rng);
+            let pk = key_cache.get_or_create("tx", params_path, params, shape.clone(), &circuit)?;
+            create_circuit_proof(
+                params,
+                &pk,
+                circuit,
+                |k| TxCircuit::<Fr, MAX_TXS, MAX_CALLDATA>::instance(k, randomness),
+                transcript,
+                &mut rng,
+            )
+        })?;
+        proof.shape = shape;
+        Some(proof)
+    } else {
+        None
+    };
+
+    // generate bytecode_circuit proof
+    let bytecode_proof = if options.bytecode_proof {
+        // The circuit fills the domain, so that its keys don't depend on the
+        // block
+        let size = 1 << k;
+        let num_rows = BytecodeCircuit::get_num_rows_required(&block);
+        if num_rows > size {
+            return Err(format!(
+                "block exceeds the bytecode circuit: {} rows (max {})",
+                num_rows, size
+            )
+            .into());
+        }
+        let circuit = BytecodeCircuit::new_from_block(&block, size);
+        let pk = key_cache.get_or_create("bytecode", params_path, params, vec![size], &circuit)?;
+        Some(create_circuit_proof(
+            params,
+            &pk,
+            circuit,
+            |k| BytecodeCircuit::instance(k, randomness),
+            transcript,
+            &mut rng,
+        )?)
+    } else {
+        None
+    };
+
+    // generate copy_circuit proof
+    let copy_proof = if options.copy_proof {
+        let circuit = CopyCircuit::new(block.clone());
+        let pk = key_cache.get_or_create(
+            "copy",
+            params_path,
+            params,
+            vec![block.copy_circuit_pad_to],
+            &circuit,
+        )?;
+        Some(create_circuit_proof(
+            params,
+            &pk,
+            circuit,
+            |k| CopyCircuit::instance(k, randomness),
+            transcript,
+            &mut rng,
+        )?)
+    } else {
+        None
+    };
 
     let ret = Proofs {
-        evm_proof: evm_proof.into(),
-        state_proof: state_proof.into(),
+        evm_proof,
+        state_proof,
+        pi_proof,
+        public_data: PublicData::new(&block),
+        witness_commitment,
+        tx_proof,
+        bytecode_proof,
+        copy_proof,
+        transcript,
         duration: Instant::now().duration_since(time_started).as_millis() as u64,
     };
 
     Ok(ret)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tx_circuit_shapes() {
+        let max_txs = (0..)
+            .map(|exp| 1 << exp)
+            .take_while(|max_txs| *max_txs <= TX_CIRCUIT_MAX_TXS);
+        for max_txs in max_txs {
+            for max_calldata in TX_CIRCUIT_MAX_CALLDATA {
+                let shape = [max_txs, max_calldata];
+                let result: Result<_, Box<dyn std::error::Error>> =
+                    with_tx_circuit_shape!(shape.as_slice(), |MAX_TXS, MAX_CALLDATA| {
+                        Ok([MAX_TXS, MAX_CALLDATA])
+                    });
+                assert_eq!(result.unwrap(), shape);
+            }
+        }
+
+        for shape in [[3, 1024], [512, 1024], [1, 1000]] {
+            let result: Result<[usize; 2], Box<dyn std::error::Error>> =
+                with_tx_circuit_shape!(shape.as_slice(), |MAX_TXS, MAX_CALLDATA| {
+                    Ok([MAX_TXS, MAX_CALLDATA])
+                });
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn tx_circuit_shape_of_block() {
+        let block = |num_txs: usize| Block::<Fr> {
+            txs: vec![Default::default(); num_txs],
+            ..Default::default()
+        };
+
+        for (num_txs, max_txs) in [(0, 1), (1, 1), (3, 4), (5, 8), (256, 256)] {
+            assert_eq!(
+                tx_circuit_shape(&block(num_txs), TX_CIRCUIT_MAX_TXS).unwrap(),
+                vec![max_txs, 1 << 10]
+            );
+        }
+        assert!(tx_circuit_shape(&block(5), 4).is_err());
+        assert!(tx_circuit_shape(&block(257), TX_CIRCUIT_MAX_TXS).is_err());
+        assert!(tx_circuit_shape(&block(1), 2 * TX_CIRCUIT_MAX_TXS).is_err());
+    }
+}
@@ -0,0 +1,491 @@
+use halo2_proofs::{
+    pairing::bn256::{Fr, G1Affine},
+    plonk::{keygen_pk, keygen_vk, Circuit, ProvingKey, VerifyingKey},
+    poly::commitment::Params,
+};
+
+use sha3::{Digest, Keccak256};
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Identifies the keys of a circuit: the keys depend on the params and on the
+/// fixed columns of the circuit, which for some circuits are determined by the
+/// `shape` of the block.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct KeyId {
+    circuit: &'static str,
+    /// keccak256 of the serialized params
+    params: [u8; 32],
+    shape: Vec<usize>,
+}
+
+impl KeyId {
+    /// Returns a file name that is stable across builds and machines, so
+    /// that a cached key is only ever read for the same params and shape.
+    fn vk_file_name(&self) -> String {
+        let mut hasher = Keccak256::new();
+        hasher.update(self.circuit.as_bytes());
+        hasher.update(self.params);
+        for value in self.shape.iter() {
+            hasher.update((*value as u64).to_le_bytes());
+        }
+        let hash: String = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        format!("{}_{}.vk", self.circuit, hash)
+    }
+}
+
+/// The keys of a circuit, params and shape.  The verifying key is set once
+/// requested by a prover or a verifier, the proving key once a proof has been
+/// requested.
+#[derive(Default)]
+struct Keys {
+    vk: Option<VerifyingKey<G1Affine>>,
+    pk: Option<Arc<ProvingKey<G1Affine>>>,
+}
+
+/// Identifies the content of a params file without reading it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ParamsFile {
+    path: String,
+    modified: SystemTime,
+    len: u64,
+}
+
+impl ParamsFile {
+    fn new(path: &str) -> Option<ParamsFile> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            path: path.to_string(),
+            modified: metadata.modified().ok()?,
+            len: metadata.len(),
+        })
+    }
+}
+
+/// Caches the keys of the circuits, so that the keys are only generated once
+/// per circuit, params and circuit shape.  The circuits are padded to the
+/// capacity of the params, so that there are few shapes per params.
+/// Only the proving key of the latest shape of each circuit and params is
+/// kept in memory, because a proving key can take up several GB, while the
+/// verifying keys of all the shapes are kept.  Concurrent requests for the
+/// keys of a circuit, params and shape wait for a single generation.
+/// The verifying keys are also serialized to `dir` if set, to skip their
+/// generation across restarts.
+// TODO: Serialize the proving keys as well once halo2 supports it.
+pub struct KeyCache {
+    dir: Option<PathBuf>,
+    /// keccak256 of the params of a file, valid as long as the file isn't
+    /// modified
+    params_hashes: Mutex<HashMap<ParamsFile, [u8; 32]>>,
+    keys: Mutex<HashMap<KeyId, Arc<Mutex<Keys>>>>,
+}
+
+impl KeyCache {
+    pub fn new(dir: Option<PathBuf>) -> KeyCache {
+        Self {
+            dir,
+            params_hashes: Mutex::new(HashMap::new()),
+            keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the id of the keys of the circuit `name` with `shape` and the
+    /// `params` read from `params_path`.  The params are hashed on the first
+    /// call for the current content of `params_path`, or on every call if
+    /// `params_path` isn't a file.
+    fn key_id(
+        &self,
+        name: &'static str,
+        params_path: &str,
+        params: &Params<G1Affine>,
+        shape: Vec<usize>,
+    ) -> Result<KeyId, Box<dyn std::error::Error>> {
+        let params_file = ParamsFile::new(params_path);
+        let cached = params_file
+            .as_ref()
+            .and_then(|params_file| self.params_hashes.lock().unwrap().get(params_file).copied());
+        let params_hash = match cached {
+            Some(params_hash) => params_hash,
+            None => {
+                // potentially long running, so without holding the lock;
+                // concurrent callers may hash the same params
+                let mut hasher = Keccak256::new();
+                params.write(&mut hasher)?;
+                let params_hash = hasher.finalize().into();
+                if let Some(params_file) = params_file {
+                    self.params_hashes
+                        .lock()
+                        .unwrap()
+                        .insert(params_file, params_hash);
+                }
+                params_hash
+            }
+        };
+
+        Ok(KeyId {
+            circuit: name,
+            params: params_hash,
+            shape,
+        })
+    }
+
+    /// Returns the slot of the keys of `id`
+    fn keys(&self, id: &KeyId) -> Arc<Mutex<Keys>> {
+        self.keys
+            .lock()
+            .unwrap()
+            .entry(id.clone())
+            .or_default()
+            .clone()
+    }
+
+    /// Drops the proving keys of the other shapes of the circuit and params
+    /// of `id`.  Slots locked by a concurrent generation are skipped.
+    fn evict_other_pks(&self, id: &KeyId) {
+        let keys = self.keys.lock().unwrap();
+        for (other_id, slot) in keys.iter() {
+            if other_id.circuit != id.circuit || other_id.params != id.params || other_id == id {
+                continue;
+            }
+            if let Ok(mut other_keys) = slot.try_lock() {
+                if other_keys.pk.take().is_some() {
+                    log::info!("keys: evicted proving key {:?}", other_id);
+                }
+            }
+        }
+    }
+
+    /// Returns the proving key of `circuit` named `name`, with the params
+    /// read from `params_path`.  `shape` has to contain all the values the
+    /// fixed columns of the circuit depend on.
+    /// The keys are generated from the circuit without witnesses, so that a
+    /// verifier derives the same verifying key, see `get_or_create_vk`.
+    pub fn get_or_create<C: Circuit<Fr>>(
+        &self,
+        name: &'static str,
+        params_path: &str,
+        params: &Params<G1Affine>,
+        shape: Vec<usize>,
+        circuit: &C,
+    ) -> Result<Arc<ProvingKey<G1Affine>>, Box<dyn std::error::Error>> {
+        let id = self.key_id(name, params_path, params, shape)?;
+        let circuit = circuit.without_witnesses();
+        let slot = self.keys(&id);
+        // potentially long running, only holding the lock of these keys, so
+        // that concurrent callers wait for this generation
+        let mut keys = slot.lock().unwrap();
+
+        if let Some(pk) = keys.pk.as_ref() {
+            return Ok(pk.clone());
+        }
+        let vk = match keys.vk.as_ref() {
+            Some(vk) => vk.clone(),
+            None => self.read_or_create_vk(&id, params, &circuit)?,
+        };
+        let pk = Arc::new(keygen_pk(params, vk.clone(), &circuit)?);
+        log::info!("keys: initialized {:?}", id);
+
+        keys.vk = Some(vk);
+        keys.pk = Some(pk.clone());
+        self.evict_other_pks(&id);
+
+        Ok(pk)
+    }
+
+    /// Returns the verifying key of `circuit`, generated from the circuit
+    /// without witnesses, so that it doesn't depend on the prover.  See
+    /// `get_or_create` for the arguments.
+    pub fn get_or_create_vk<C: Circuit<Fr>>(
+        &self,
+        name: &'static str,
+        params_path: &str,
+        params: &Params<G1Affine>,
+        shape: Vec<usize>,
+        circuit: &C,
+    ) -> Result<VerifyingKey<G1Affine>, Box<dyn std::error::Error>> {
+        let id = self.key_id(name, params_path, params, shape)?;
+        let slot = self.keys(&id);
+        let mut keys = slot.lock().unwrap();
+
+        if let Some(vk) = keys.vk.as_ref() {
+            return Ok(vk.clone());
+        }
+        let vk = self.read_or_create_vk(&id, params, &circuit.without_witnesses())?;
+        keys.vk = Some(vk.clone());
+
+        Ok(vk)
+    }
+
+    /// Reads the verifying key of `circuit` from the cache directory, or
+    /// generates and serializes it.
+    fn read_or_create_vk<C: Circuit<Fr>>(
+        &self,
+        id: &KeyId,
+        params: &Params<G1Affine>,
+        circuit: &C,
+    ) -> Result<VerifyingKey<G1Affine>, Box<dyn std::error::Error>> {
+        if let Some(vk) = self.read_vk::<C>(id, params) {
+            return Ok(vk);
+        }
+        let vk = keygen_vk(params, circuit)?;
+        self.write_vk(id, &vk);
+        Ok(vk)
+    }
+
+    fn read_vk<C: Circuit<Fr>>(
+        &self,
+        id: &KeyId,
+        params: &Params<G1Affine>,
+    ) -> Option<VerifyingKey<G1Affine>> {
+        let path = self.dir.as_ref()?.join(id.vk_file_name());
+        let file = File::open(&path).ok()?;
+
+        match VerifyingKey::read::<_, C>(&mut BufReader::new(file), params) {
+            Ok(vk) => Some(vk),
+            Err(err) => {
+                log::warn!("keys: can't read {:?}: {}", path, err);
+                None
+            }
+        }
+    }
+
+    /// Serializes `vk` to the cache directory.  Failures are only logged
+    /// because the key can always be generated again.
+    fn write_vk(&self, id: &KeyId, vk: &VerifyingKey<G1Affine>) {
+        let dir = match self.dir.as_ref() {
+            Some(dir) => dir,
+            None => return,
+        };
+        let path = dir.join(id.vk_file_name());
+
+        let res = std::fs::create_dir_all(dir)
+            .and_then(|_| File::create(&path))
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                vk.write(&mut writer)?;
+                writer.flush()
+            });
+        if let Err(err) = res {
+            log::warn!("keys: can't write {:?}: {}", path, err);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        pairing::bn256::Bn256,
+        plonk::{Advice, Column, ConstraintSystem, Error, Fixed, Selector},
+        poly::Rotation,
+    };
+
+    /// Circuit whose advice cell has to equal the fixed cell `value`, which is
+    /// part of its keys.
+    #[derive(Clone, Default)]
+    pub(crate) struct FixedValueCircuit {
+        pub(crate) value: u64,
+    }
+
+    impl Circuit<Fr> for FixedValueCircuit {
+        type Config = (Selector, Column<Fixed>, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let q_enable = meta.selector();
+            let fixed = meta.fixed_column();
+            let advice = meta.advice_column();
+            meta.create_gate("advice == fixed", |meta| {
+                let q_enable = meta.query_selector(q_enable);
+                let fixed = meta.query_fixed(fixed, Rotation::cur());
+                let advice = meta.query_advice(advice, Rotation::cur());
+                vec![q_enable * (advice - fixed)]
+            });
+            (q_enable, fixed, advice)
+        }
+
+        fn synthesize(
+            &self,
+            (q_enable, fixed, advice): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "value",
+                |mut region| {
+                    q_enable.enable(&mut region, 0)?;
+                    region.assign_fixed(|| "fixed", fixed, 0, || Ok(Fr::from(self.value)))?;
+                    region.assign_advice(|| "advice", advice, 0, || Ok(Fr::from(self.value)))?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    fn vk_bytes(vk: &VerifyingKey<G1Affine>) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        vk.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn key_cache_hit_and_miss() {
+        let params = Params::<G1Affine>::unsafe_setup::<Bn256>(4);
+        let key_cache = KeyCache::new(None);
+        let get = |value: u64| {
+            key_cache
+                .get_or_create(
+                    "test",
+                    "params",
+                    &params,
+                    vec![value as usize],
+                    &FixedValueCircuit { value },
+                )
+                .unwrap()
+        };
+
+        let pk = get(1);
+        assert!(Arc::ptr_eq(&pk, &get(1)));
+        let other_pk = get(2);
+        assert!(!Arc::ptr_eq(&pk, &other_pk));
+        assert_ne!(vk_bytes(pk.get_vk()), vk_bytes(other_pk.get_vk()));
+        // only the proving key of the latest shape is kept
+        let pk_again = get(1);
+        assert!(!Arc::ptr_eq(&pk, &pk_again));
+        assert_eq!(vk_bytes(pk.get_vk()), vk_bytes(pk_again.get_vk()));
+    }
+
+    #[test]
+    fn key_cache_vk_keeps_pk() {
+        let params = Params::<G1Affine>::unsafe_setup::<Bn256>(4);
+        let key_cache = KeyCache::new(None);
+        let circuit = FixedValueCircuit { value: 1 };
+        let get = || {
+            key_cache
+                .get_or_create("test", "params", &params, vec![1], &circuit)
+                .unwrap()
+        };
+
+        let pk = get();
+        for value in [1, 2] {
+            let vk = key_cache
+                .get_or_create_vk(
+                    "test",
+                    "params",
+                    &params,
+                    vec![value as usize],
+                    &FixedValueCircuit { value },
+                )
+                .unwrap();
+            assert_eq!(vk_bytes(&vk) == vk_bytes(pk.get_vk()), value == 1);
+        }
+        assert!(Arc::ptr_eq(&pk, &get()));
+    }
+
+    #[test]
+    fn key_cache_single_flight() {
+        let params = Arc::new(Params::<G1Affine>::unsafe_setup::<Bn256>(4));
+        let key_cache = Arc::new(KeyCache::new(None));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let params = params.clone();
+                let key_cache = key_cache.clone();
+                std::thread::spawn(move || {
+                    key_cache
+                        .get_or_create(
+                            "test",
+                            "params",
+                            &params,
+                            vec![1],
+                            &FixedValueCircuit { value: 1 },
+                        )
+                        .unwrap()
+                })
+            })
+            .collect();
+        let pks: Vec<_> = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+
+        assert!(pks.iter().all(|pk| Arc::ptr_eq(pk, &pks[0])));
+    }
+
+    #[test]
+    fn key_cache_vk_round_trip() {
+        let params = Params::<G1Affine>::unsafe_setup::<Bn256>(4);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path().join("keys");
+        let circuit = FixedValueCircuit { value: 1 };
+
+        let vk = KeyCache::new(Some(dir.clone()))
+            .get_or_create_vk("test", "params", &params, vec![1], &circuit)
+            .unwrap();
+        let files: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(files.len(), 1);
+
+        // read back by another cache, whose params path doesn't matter
+        let key_cache = KeyCache::new(Some(dir.clone()));
+        let id = key_cache
+            .key_id("test", "other_params", &params, vec![1])
+            .unwrap();
+        let read_vk = key_cache
+            .read_vk::<FixedValueCircuit>(&id, &params)
+            .unwrap();
+        assert_eq!(vk_bytes(&vk), vk_bytes(&read_vk));
+        let pk = key_cache
+            .get_or_create("test", "other_params", &params, vec![1], &circuit)
+            .unwrap();
+        assert_eq!(vk_bytes(&vk), vk_bytes(pk.get_vk()));
+    }
+
+    #[test]
+    fn key_id_depends_on_params() {
+        let key_cache = KeyCache::new(None);
+        let id = |params_path: &str, k: u32| {
+            let params = Params::<G1Affine>::unsafe_setup::<Bn256>(k);
+            key_cache
+                .key_id("test", params_path, &params, vec![1])
+                .unwrap()
+                .vk_file_name()
+        };
+
+        assert_eq!(id("a", 4), id("b", 4));
+        assert_ne!(id("c", 4), id("d", 5));
+        // not a file, so the params are hashed on every call
+        assert_ne!(id("e", 4), id("e", 5));
+    }
+
+    #[test]
+    fn key_id_depends_on_params_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("params");
+        let path = path.to_str().unwrap();
+        let key_cache = KeyCache::new(None);
+        let id = |k: u32| {
+            let params = Params::<G1Affine>::unsafe_setup::<Bn256>(k);
+            let mut file = File::create(path).unwrap();
+            params.write(&mut file).unwrap();
+            key_cache
+                .key_id("test", path, &params, vec![1])
+                .unwrap()
+                .vk_file_name()
+        };
+
+        assert_ne!(id(4), id(5));
+        assert_eq!(key_cache.params_hashes.lock().unwrap().len(), 2);
+    }
+}
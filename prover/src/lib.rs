@@ -1,3 +1,6 @@
 pub mod compute_proof;
+pub mod key_cache;
 pub mod shared_state;
 pub mod structs;
+pub mod transcript;
+pub mod verify_proof;
@@ -3,17 +3,63 @@ use halo2_proofs::poly::commitment::Params;
 
 use std::collections::HashMap;
 use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
 use std::sync::Arc;
+use std::time::Duration;
 
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
 use tokio::sync::Mutex;
 
-use crate::compute_proof::compute_proof;
+use crate::compute_proof::{compute_proof, proof_rng};
+use crate::key_cache::KeyCache;
 use crate::structs::{ProofRequestOptions, Proofs};
 
 #[derive(Debug, Clone)]
 pub struct ProofRequest {
     pub options: ProofRequestOptions,
     pub result: Option<Result<Proofs, String>>,
+    /// whether a worker is computing the proof
+    pub running: bool,
+}
+
+/// Configures how the proofs are computed
+#[derive(Debug, Clone)]
+pub struct WorkerConfig {
+    /// the number of proofs computed in parallel
+    pub workers: u32,
+    /// the path of the `prover_cmd` binary.  If set, the proofs are computed
+    /// in long-lived child processes running `prover_cmd serve`, so that
+    /// panics, OOMs etc. are reported as task errors instead of taking down
+    /// the server.  A failed child process is replaced by a new one.
+    pub prover_cmd: Option<PathBuf>,
+    /// kills the child process computing a proof after this time.  Requires
+    /// `prover_cmd`, as a proof computed in-process can't be aborted.
+    pub timeout: Option<Duration>,
+}
+
+impl WorkerConfig {
+    /// Returns an error if the config can't be applied as given.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.workers == 0 {
+            return Err("workers must be at least 1".to_string());
+        }
+        if self.timeout.is_some() && self.prover_cmd.is_none() {
+            return Err("timeout requires prover_cmd".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        Self {
+            workers: 1,
+            prover_cmd: None,
+            timeout: None,
+        }
+    }
 }
 
 pub struct RwState {
@@ -25,16 +71,25 @@ pub struct RwState {
 #[derive(Clone)]
 pub struct SharedState {
     pub rw: Arc<Mutex<RwState>>,
+    pub key_cache: Arc<KeyCache>,
+    pub worker_config: Arc<WorkerConfig>,
+    /// the idle child processes if `worker_config.prover_cmd` is set
+    prover_processes: Arc<Mutex<Vec<ProverProcess>>>,
 }
 
 impl SharedState {
-    pub fn new() -> SharedState {
+    /// `key_cache_dir` is the directory the verifying keys are serialized to,
+    /// if any.
+    pub fn new(key_cache_dir: Option<PathBuf>, worker_config: WorkerConfig) -> SharedState {
         Self {
             rw: Arc::new(Mutex::new(RwState {
                 tasks: Vec::new(),
                 pending_tasks: 0,
                 params_cache: HashMap::new(),
             })),
+            key_cache: Arc::new(KeyCache::new(key_cache_dir)),
+            worker_config: Arc::new(worker_config),
+            prover_processes: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
@@ -72,6 +127,7 @@ impl SharedState {
             let task = ProofRequest {
                 options: options.clone(),
                 result: None,
+                running: false,
             };
             log::debug!("enqueue: {:#?}", task);
             rw.tasks.push(task);
@@ -81,63 +137,102 @@ impl SharedState {
     }
 
     /// Checks if there is anything to do like:
-    /// - records if a task completed
-    /// - starting a new task
-    /// Blocks until completion but releases the lock of `self.rw` in between.
+    /// - starting new tasks, up to the number of workers
+    /// Returns without waiting for the tasks, which record their result
+    /// once completed.
     pub async fn duty_cycle(&self) {
         let mut rw = self.rw.lock().await;
 
-        if rw.pending_tasks > 0 {
-            // already computing
-            // TODO: can spawn multiple tasks if ever required
-            return;
-        }
-
-        // find a pending task
-        let pending_task = rw.tasks.iter().find(|&e| e.result.is_none());
-        if pending_task.is_none() {
-            // nothing to do
-            return;
-        }
+        while rw.pending_tasks < self.worker_config.workers {
+            // find a task that is neither completed nor running
+            let pending_task = rw
+                .tasks
+                .iter_mut()
+                .find(|e| e.result.is_none() && !e.running);
+            let pending_task = match pending_task {
+                Some(task) => task,
+                // nothing to do
+                None => return,
+            };
 
-        // needs to be cloned because of long running tasks and
-        // the possibility that the task gets removed in the meantime
-        let mut pending_task = pending_task.unwrap().clone();
-        {
+            // needs to be cloned because of long running tasks and
+            // the possibility that the task gets removed in the meantime
+            pending_task.running = true;
+            let pending_task = pending_task.clone();
             rw.pending_tasks += 1;
             log::info!("compute_proof: {:#?}", pending_task);
-            drop(rw);
+
+            let self_copy = self.clone();
+            tokio::spawn(async move { self_copy.run_task(pending_task).await });
         }
+    }
+
+    /// Computes the proofs of `pending_task` and records the result.
+    async fn run_task(&self, mut pending_task: ProofRequest) {
+        let task_result = match self.worker_config.prover_cmd.as_ref() {
+            Some(prover_cmd) => {
+                self.compute_proof_subprocess(prover_cmd, &pending_task.options)
+                    .await
+            }
+            None => self.compute_proof_in_process(&pending_task.options).await,
+        };
+
+        {
+            // done, update the queue
+            log::info!("task_result: {:#?}", task_result);
 
-        // Note: this catches any panics for the task itself but will not help in the
-        // situation when the process get itself OOM killed, stack overflows etc.
-        // This could be avoided by spawning a subprocess for the proof computation
-        // instead.
+            let mut rw = self.rw.lock().await;
+            rw.pending_tasks -= 1;
 
-        let pending_task_copy = pending_task.clone();
+            let task = rw
+                .tasks
+                .iter_mut()
+                .find(|e| e.options == pending_task.options);
+            if let Some(task) = task {
+                // found our task, update result
+                task.result = Some(task_result);
+                task.running = false;
+            } else {
+                // task was already removed in the meantime, insert it again
+                pending_task.result = Some(task_result);
+                pending_task.running = false;
+                rw.tasks.push(pending_task);
+            }
+        }
+    }
+
+    /// Computes the proofs in a blocking thread of this process, so that the
+    /// proving doesn't stall the workers of the runtime.
+    // Note: this catches any panics for the task itself but will not help in the
+    // situation when the process get itself OOM killed, stack overflows etc.
+    // Use `WorkerConfig::prover_cmd` to avoid that.
+    async fn compute_proof_in_process(
+        &self,
+        options: &ProofRequestOptions,
+    ) -> Result<Proofs, String> {
+        let options = options.clone();
         let self_copy = self.clone();
+        let handle = tokio::runtime::Handle::current();
         let task_result: Result<Result<Proofs, String>, tokio::task::JoinError> =
-            tokio::spawn(async move {
-                // lazily load the file and cache it
-                let param = self_copy.load_param(&pending_task_copy.options.param).await;
-                let res = compute_proof(
-                    param.as_ref(),
-                    &pending_task_copy.options.block,
-                    &pending_task_copy.options.rpc,
-                )
-                .await;
-
-                if let Err(err) = res {
+            tokio::task::spawn_blocking(move || {
+                handle.block_on(async move {
+                    // lazily load the file and cache it
+                    let param = self_copy.load_param(&options.param).await;
+                    compute_proof(
+                        param.as_ref(),
+                        &self_copy.key_cache,
+                        &options,
+                        proof_rng(false),
+                    )
+                    .await
                     // cast Error to string
-                    return Err(err.to_string());
-                }
-
-                Ok(res.unwrap())
+                    .map_err(|err| err.to_string())
+                })
             })
             .await;
 
         // convert the JoinError to string - if applicable
-        let task_result: Result<Proofs, String> = match task_result {
+        match task_result {
             Err(err) => match err.is_panic() {
                 true => {
                     let panic = err.into_panic();
@@ -153,31 +248,40 @@ impl SharedState {
                 false => Err(err.to_string()),
             },
             Ok(val) => val,
-        };
-
-        {
-            // done, update the queue
-            log::info!("task_result: {:#?}", task_result);
+        }
+    }
 
-            let mut rw = self.rw.lock().await;
-            rw.pending_tasks -= 1;
+    /// Computes the proofs in an idle child process running `prover_cmd`, or
+    /// in a new one if there is none.  The child process is only reused if it
+    /// returned a result in time.
+    async fn compute_proof_subprocess(
+        &self,
+        prover_cmd: &Path,
+        options: &ProofRequestOptions,
+    ) -> Result<Proofs, String> {
+        let process = self.prover_processes.lock().await.pop();
+        let mut process = match process {
+            Some(process) => process,
+            None => ProverProcess::spawn(prover_cmd)?,
+        };
 
-            let task = rw
-                .tasks
-                .iter_mut()
-                .find(|e| e.options == pending_task.options);
-            if let Some(task) = task {
-                // found our task, update result
-                task.result = Some(task_result);
-            } else {
-                // task was already removed in the meantime, insert it again
-                pending_task.result = Some(task_result);
-                rw.tasks.push(pending_task);
+        let result = match self.worker_config.timeout {
+            // dropping the process on timeout kills it
+            Some(timeout) => {
+                match tokio::time::timeout(timeout, process.compute_proof(options)).await {
+                    Ok(result) => result,
+                    Err(_) => Err(format!("prover_cmd timed out after {:?}", timeout)),
+                }
             }
-        }
+            None => process.compute_proof(options).await,
+        }?;
+        self.prover_processes.lock().await.push(process);
+
+        result
     }
 
-    async fn load_param(&self, params_path: &str) -> Arc<Params<G1Affine>> {
+    /// Returns the params of `params_path`, loading them on the first call.
+    pub async fn load_param(&self, params_path: &str) -> Arc<Params<G1Affine>> {
         let mut rw = self.rw.lock().await;
 
         if !rw.params_cache.contains_key(params_path) {
@@ -204,6 +308,203 @@ impl SharedState {
 
 impl Default for SharedState {
     fn default() -> Self {
-        Self::new()
+        Self::new(None, WorkerConfig::default())
+    }
+}
+
+/// A child process running `prover_cmd serve`, which computes the proofs of
+/// the requests written to its stdin.  It's long-lived, because the proving
+/// keys can only be kept in memory, as only the verifying keys are
+/// serialized.  The child is killed on drop.
+struct ProverProcess {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+}
+
+impl ProverProcess {
+    fn spawn(prover_cmd: &Path) -> Result<Self, String> {
+        let mut child = Command::new(prover_cmd)
+            .arg("serve")
+            // never inherit the test mode
+            .env_remove("DETERMINISTIC_PROOFS")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("can't run {:?}: {}", prover_cmd, e))?;
+        let stdin = child.stdin.take().expect("piped stdin");
+        let stdout = BufReader::new(child.stdout.take().expect("piped stdout")).lines();
+
+        Ok(Self {
+            child,
+            stdin,
+            stdout,
+        })
+    }
+
+    /// Returns the result of the child computing the proofs of `options`, or
+    /// an error if the child can't be used anymore.
+    async fn compute_proof(
+        &mut self,
+        options: &ProofRequestOptions,
+    ) -> Result<Result<Proofs, String>, String> {
+        let mut request = serde_json::to_vec(options).map_err(|e| e.to_string())?;
+        request.push(b'\n');
+
+        let response = match self.stdin.write_all(&request).await {
+            Ok(()) => self.stdout.next_line().await.ok().flatten(),
+            Err(_) => None,
+        };
+        match response {
+            Some(response) => serde_json::from_str(&response)
+                .map_err(|e| format!("invalid prover_cmd output: {}", e)),
+            None => {
+                // e.g. `signal: 9` if the child got OOM killed
+                let status = self.child.wait().await.map_err(|e| e.to_string())?;
+                Err(format!("prover_cmd failed: {}", status))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempPath;
+
+    /// Writes a shell script that runs `script` to use as `prover_cmd`, which
+    /// is removed once the returned path is dropped.
+    fn prover_cmd(script: &str) -> TempPath {
+        let mut file = tempfile::Builder::new()
+            .prefix("prover_cmd_")
+            .tempfile()
+            .unwrap();
+        write!(file, "#!/bin/sh\n{}\n", script).unwrap();
+        file.as_file()
+            .set_permissions(std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+        file.into_temp_path()
+    }
+
+    fn state(prover_cmd: &Path, workers: u32, timeout: Option<Duration>) -> SharedState {
+        SharedState::new(
+            None,
+            WorkerConfig {
+                workers,
+                prover_cmd: Some(prover_cmd.to_path_buf()),
+                timeout,
+            },
+        )
+    }
+
+    fn options(block: u64) -> ProofRequestOptions {
+        ProofRequestOptions {
+            block,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn worker_config_timeout_requires_prover_cmd() {
+        let mut config = WorkerConfig {
+            timeout: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+        config.prover_cmd = Some(PathBuf::from("prover_cmd"));
+        assert!(config.validate().is_ok());
+        config.workers = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn duty_cycle_worker_limit() {
+        let prover_cmd = prover_cmd("sleep 10");
+        let state = state(&prover_cmd, 2, None);
+        for block in 0..3 {
+            assert!(state.get_or_enqueue(&options(block)).await.is_none());
+        }
+
+        // starting the tasks again doesn't exceed the workers
+        for _ in 0..2 {
+            state.duty_cycle().await;
+            let rw = state.rw.lock().await;
+            assert_eq!(rw.pending_tasks, 2);
+            assert_eq!(rw.tasks.iter().filter(|task| task.running).count(), 2);
+        }
+        // dropping the runtime kills the children
+    }
+
+    #[tokio::test]
+    async fn duty_cycle_records_failure() {
+        let prover_cmd = prover_cmd("exit 3");
+        let state = state(&prover_cmd, 1, None);
+        assert!(state.get_or_enqueue(&options(1)).await.is_none());
+        state.duty_cycle().await;
+
+        let result = loop {
+            if let Some(result) = state.get_or_enqueue(&options(1)).await {
+                break result;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert!(result.unwrap_err().contains("prover_cmd failed"));
+        assert_eq!(state.rw.lock().await.pending_tasks, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn in_process_panic() {
+        let state = SharedState::default();
+        let result = state
+            .compute_proof_in_process(&ProofRequestOptions {
+                param: "/nonexistent".to_string(),
+                ..options(1)
+            })
+            .await;
+        assert!(result.unwrap_err().contains("couldn't open params"));
+    }
+
+    #[tokio::test]
+    async fn subprocess_timeout() {
+        let prover_cmd = prover_cmd("sleep 10");
+        let state = state(&prover_cmd, 1, Some(Duration::from_millis(100)));
+        let result = state
+            .compute_proof_subprocess(&prover_cmd, &options(1))
+            .await;
+        assert!(result.unwrap_err().contains("timed out"));
+        // the child is killed instead of being reused
+        assert!(state.prover_processes.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn subprocess_invalid_output() {
+        let prover_cmd = prover_cmd("echo proofs");
+        let state = state(&prover_cmd, 1, None);
+        let result = state
+            .compute_proof_subprocess(&prover_cmd, &options(1))
+            .await;
+        assert!(result.unwrap_err().contains("invalid prover_cmd output"));
+        assert!(state.prover_processes.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn subprocess_reused() {
+        // answers each request with an error containing its pid
+        let prover_cmd = prover_cmd(r#"while read request; do echo "{\"Err\": \"$$\"}"; done"#);
+        let state = state(&prover_cmd, 1, None);
+        let mut pids = Vec::new();
+        for block in 0..2 {
+            let result = state
+                .compute_proof_subprocess(&prover_cmd, &options(block))
+                .await;
+            pids.push(result.unwrap_err());
+        }
+
+        assert_eq!(pids[0], pids[1]);
+        assert_eq!(state.prover_processes.lock().await.len(), 1);
     }
 }
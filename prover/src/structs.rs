@@ -1,10 +1,110 @@
-#[derive(Debug, Clone, serde::Serialize)]
+use zkevm_circuits::pi_circuit::PublicData;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CircuitProof {
+    /// the proof, whose verifying key the verifier derives from the params
+    pub proof: eth_types::Bytes,
+    /// the capacities of the circuit, if they depend on the block, see
+    /// `TxCircuit`.  The verifier derives the verifying key for them.
+    #[serde(default)]
+    pub shape: Vec<usize>,
+}
+
+/// The transcript of the Fiat-Shamir transform of the proofs
+// TODO: Add a Poseidon transcript for recursive verification once halo2
+// provides one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptKind {
+    Blake2b,
+    /// for on-chain verification, see `transcript::KeccakWrite`
+    Keccak,
+}
+
+impl TranscriptKind {
+    /// Returns the name of the transcript, as in the serialized requests
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Blake2b => "blake2b",
+            Self::Keccak => "keccak",
+        }
+    }
+}
+
+impl std::str::FromStr for TranscriptKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blake2b" => Ok(Self::Blake2b),
+            "keccak" => Ok(Self::Keccak),
+            _ => Err(format!("unknown transcript: {}", s)),
+        }
+    }
+}
+
+impl Default for TranscriptKind {
+    fn default() -> Self {
+        Self::Blake2b
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Proofs {
-    pub state_proof: eth_types::Bytes,
-    pub evm_proof: eth_types::Bytes,
+    pub state_proof: CircuitProof,
+    pub evm_proof: CircuitProof,
+    pub pi_proof: CircuitProof,
+    /// the public data of the block, from which the verifier computes the
+    /// public inputs of the proofs.  Callers need to
+    /// compare it with the block they expect.
+    pub public_data: PublicData,
+    /// the commitment of the prover to the witnesses of the block, from which
+    /// the verifier derives the randomness together with the block hash
+    pub witness_commitment: eth_types::Word,
+    /// only set if requested via `ProofRequestOptions`
+    #[serde(default)]
+    pub tx_proof: Option<CircuitProof>,
+    /// only set if requested via `ProofRequestOptions`
+    #[serde(default)]
+    pub bytecode_proof: Option<CircuitProof>,
+    /// only set if requested via `ProofRequestOptions`
+    #[serde(default)]
+    pub copy_proof: Option<CircuitProof>,
+    /// the transcript the proofs were created with
+    #[serde(default)]
+    pub transcript: TranscriptKind,
     pub duration: u64,
 }
 
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CircuitVerification {
+    /// whether the proof is valid
+    pub valid: bool,
+    /// the reason why the proof is not valid
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ProofsVerification {
+    pub state_proof: CircuitVerification,
+    pub evm_proof: CircuitVerification,
+    pub pi_proof: CircuitVerification,
+    pub tx_proof: Option<CircuitVerification>,
+    pub bytecode_proof: Option<CircuitVerification>,
+    pub copy_proof: Option<CircuitVerification>,
+}
+
+impl ProofsVerification {
+    /// Returns true if all the proofs are valid
+    pub fn is_valid(&self) -> bool {
+        [&self.tx_proof, &self.bytecode_proof, &self.copy_proof]
+            .iter()
+            .filter_map(|verification| verification.as_ref())
+            .chain([&self.state_proof, &self.evm_proof, &self.pi_proof])
+            .all(|verification| verification.valid)
+    }
+}
+
 #[derive(Debug, serde::Serialize)]
 pub struct JsonRpcError {
     pub code: i32,
@@ -32,7 +132,7 @@ pub struct JsonRpcRequest<T: serde::Serialize> {
     pub params: T,
 }
 
-#[derive(Debug, Default, Clone, serde::Deserialize)]
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ProofRequestOptions {
     /// the block number
     pub block: u64,
@@ -42,10 +142,41 @@ pub struct ProofRequestOptions {
     pub retry: bool,
     /// parameter file to use
     pub param: String,
+    /// the transcript to create the proofs with
+    #[serde(default)]
+    pub transcript: TranscriptKind,
+    /// also create the proof of the tx circuit
+    #[serde(default)]
+    pub tx_proof: bool,
+    /// also create the proof of the bytecode circuit
+    #[serde(default)]
+    pub bytecode_proof: bool,
+    /// also create the proof of the copy circuit
+    #[serde(default)]
+    pub copy_proof: bool,
+    /// the largest tx circuit to create the proof with, in txs.  Defaults to
+    /// `compute_proof::TX_CIRCUIT_MAX_TXS`, which it may not exceed.
+    #[serde(default)]
+    pub tx_circuit_max_txs: Option<usize>,
 }
 
 impl PartialEq for ProofRequestOptions {
     fn eq(&self, other: &Self) -> bool {
-        self.block == other.block && self.rpc == other.rpc && self.param == other.param
+        self.block == other.block
+            && self.rpc == other.rpc
+            && self.param == other.param
+            && self.transcript == other.transcript
+            && self.tx_proof == other.tx_proof
+            && self.bytecode_proof == other.bytecode_proof
+            && self.copy_proof == other.copy_proof
+            && self.tx_circuit_max_txs == other.tx_circuit_max_txs
     }
 }
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct VerifyRequestOptions {
+    /// the proofs to verify
+    pub proofs: Proofs,
+    /// parameter file the proofs were created with
+    pub param: String,
+}
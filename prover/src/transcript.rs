@@ -0,0 +1,226 @@
+//! Keccak256 transcript for the Fiat-Shamir transform, whose challenges an
+//! on-chain verifier can compute with the keccak256 precompile.
+
+use halo2_proofs::{
+    arithmetic::{Coordinates, CurveAffine},
+    pairing::group::{ff::PrimeField, GroupEncoding},
+    transcript::{EncodedChallenge, Transcript, TranscriptRead, TranscriptWrite},
+};
+use sha3::{Digest, Keccak256};
+
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+/// Prefix to a prover's message soliciting a challenge
+const KECCAK256_PREFIX_CHALLENGE: u8 = 0;
+
+/// First prefix to a prover's message soliciting a challenge
+/// Not included in the growing state!
+const KECCAK256_PREFIX_CHALLENGE_LO: u8 = 10;
+
+/// Second prefix to a prover's message soliciting a challenge
+/// Not included in the growing state!
+const KECCAK256_PREFIX_CHALLENGE_HI: u8 = 11;
+
+/// Prefix to a prover's message containing a curve point
+const KECCAK256_PREFIX_POINT: u8 = 1;
+
+/// Prefix to a prover's message containing a scalar
+const KECCAK256_PREFIX_SCALAR: u8 = 2;
+
+/// Returns the initial state of the transcript
+fn init_state() -> Keccak256 {
+    let mut state = Keccak256::new();
+    state.update(b"Halo2-Transcript");
+    state
+}
+
+/// Squeezes a challenge of 64 bytes out of `state`, from two hashes so that
+/// the challenge is uniform in the scalar field.
+fn squeeze_challenge<C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>>(
+    state: &mut Keccak256,
+) -> E {
+    state.update(&[KECCAK256_PREFIX_CHALLENGE]);
+
+    let mut result = [0; 64];
+    for (prefix, bytes) in [KECCAK256_PREFIX_CHALLENGE_LO, KECCAK256_PREFIX_CHALLENGE_HI]
+        .into_iter()
+        .zip(result.chunks_mut(32))
+    {
+        let mut state = state.clone();
+        state.update(&[prefix]);
+        bytes.copy_from_slice(&state.finalize());
+    }
+    E::new(&result)
+}
+
+fn common_point<C: CurveAffine>(state: &mut Keccak256, point: C) -> io::Result<()> {
+    state.update(&[KECCAK256_PREFIX_POINT]);
+    let coords: Coordinates<C> = Option::from(point.coordinates()).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Other,
+            "cannot write points at infinity to the transcript",
+        )
+    })?;
+    state.update(coords.x().to_repr().as_ref());
+    state.update(coords.y().to_repr().as_ref());
+    Ok(())
+}
+
+fn common_scalar<C: CurveAffine>(state: &mut Keccak256, scalar: C::Scalar) {
+    state.update(&[KECCAK256_PREFIX_SCALAR]);
+    state.update(scalar.to_repr().as_ref());
+}
+
+/// Keccak256 transcript reader
+pub struct KeccakRead<R: Read, C: CurveAffine, E: EncodedChallenge<C>> {
+    state: Keccak256,
+    reader: R,
+    _marker: PhantomData<(C, E)>,
+}
+
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C>> KeccakRead<R, C, E> {
+    /// Initialize a transcript given an input buffer.
+    pub fn init(reader: R) -> Self {
+        KeccakRead {
+            state: init_state(),
+            reader,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>> Transcript<C, E>
+    for KeccakRead<R, C, E>
+{
+    fn squeeze_challenge(&mut self) -> E {
+        squeeze_challenge::<C, E>(&mut self.state)
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        common_point(&mut self.state, point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        common_scalar::<C>(&mut self.state, scalar);
+        Ok(())
+    }
+}
+
+impl<R: Read, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>> TranscriptRead<C, E>
+    for KeccakRead<R, C, E>
+{
+    fn read_point(&mut self) -> io::Result<C> {
+        let mut compressed = C::Repr::default();
+        self.reader.read_exact(compressed.as_mut())?;
+        let point: C = Option::from(C::from_bytes(&compressed)).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, "invalid point encoding in proof")
+        })?;
+        self.common_point(point)?;
+
+        Ok(point)
+    }
+
+    fn read_scalar(&mut self) -> io::Result<C::Scalar> {
+        let mut data = <C::Scalar as PrimeField>::Repr::default();
+        self.reader.read_exact(data.as_mut())?;
+        let scalar: C::Scalar = Option::from(C::Scalar::from_repr(data)).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                "invalid field element encoding in proof",
+            )
+        })?;
+        self.common_scalar(scalar)?;
+
+        Ok(scalar)
+    }
+}
+
+/// Keccak256 transcript writer
+pub struct KeccakWrite<W: Write, C: CurveAffine, E: EncodedChallenge<C>> {
+    state: Keccak256,
+    writer: W,
+    _marker: PhantomData<(C, E)>,
+}
+
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C>> KeccakWrite<W, C, E> {
+    /// Initialize a transcript given an output buffer.
+    pub fn init(writer: W) -> Self {
+        KeccakWrite {
+            state: init_state(),
+            writer,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Conclude the interaction and return the output buffer (writer).
+    pub fn finalize(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>> Transcript<C, E>
+    for KeccakWrite<W, C, E>
+{
+    fn squeeze_challenge(&mut self) -> E {
+        squeeze_challenge::<C, E>(&mut self.state)
+    }
+
+    fn common_point(&mut self, point: C) -> io::Result<()> {
+        common_point(&mut self.state, point)
+    }
+
+    fn common_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        common_scalar::<C>(&mut self.state, scalar);
+        Ok(())
+    }
+}
+
+impl<W: Write, C: CurveAffine, E: EncodedChallenge<C, Input = [u8; 64]>> TranscriptWrite<C, E>
+    for KeccakWrite<W, C, E>
+{
+    fn write_point(&mut self, point: C) -> io::Result<()> {
+        self.common_point(point)?;
+        let compressed = point.to_bytes();
+        self.writer.write_all(compressed.as_ref())
+    }
+
+    fn write_scalar(&mut self, scalar: C::Scalar) -> io::Result<()> {
+        self.common_scalar(scalar)?;
+        let data = scalar.to_repr();
+        self.writer.write_all(data.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        pairing::bn256::{Fr, G1Affine},
+        pairing::group::prime::PrimeCurveAffine,
+        transcript::Challenge255,
+    };
+
+    #[test]
+    fn keccak_transcript_round_trip() {
+        let point = G1Affine::generator();
+        let scalar = Fr::from(42);
+
+        let mut transcript = KeccakWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        transcript.write_point(point).unwrap();
+        transcript.write_scalar(scalar).unwrap();
+        let challenge = transcript.squeeze_challenge_scalar::<()>();
+        let proof = transcript.finalize();
+
+        let mut transcript = KeccakRead::<_, G1Affine, Challenge255<_>>::init(proof.as_slice());
+        assert_eq!(transcript.read_point().unwrap(), point);
+        assert_eq!(transcript.read_scalar().unwrap(), scalar);
+        assert_eq!(*transcript.squeeze_challenge_scalar::<()>(), *challenge);
+
+        // the challenges depend on the messages
+        let mut transcript = KeccakWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        transcript.write_point(point).unwrap();
+        transcript.write_scalar(Fr::from(43)).unwrap();
+        assert_ne!(*transcript.squeeze_challenge_scalar::<()>(), *challenge);
+    }
+}
@@ -0,0 +1,312 @@
+use halo2_proofs::{
+    pairing::bn256::{Bn256, Fr, G1Affine},
+    plonk::{verify_proof, SingleVerifier, VerifyingKey},
+    poly::commitment::{Params, ParamsVerifier},
+    transcript::{Blake2bRead, Challenge255},
+};
+
+use rand::rngs::OsRng;
+use strum::IntoEnumIterator;
+use zkevm_circuits::bytecode_circuit::BytecodeCircuit;
+use zkevm_circuits::copy_circuit::CopyCircuit;
+use zkevm_circuits::evm_circuit::{
+    table::FixedTableTag,
+    witness::{block_randomness, Block},
+    EvmCircuit,
+};
+use zkevm_circuits::pi_circuit::PiCircuit;
+use zkevm_circuits::state_circuit::StateCircuit;
+use zkevm_circuits::tx_circuit::TxCircuit;
+
+use crate::compute_proof::{
+    evm_circuit_instance, evm_circuit_shape, pad_block, pi_circuit_instance,
+    state_circuit_instance, with_tx_circuit_shape, PI_CIRCUIT_MAX_TXS, STATE_CIRCUIT_ROWS,
+};
+use crate::key_cache::KeyCache;
+use crate::structs::{
+    CircuitProof, CircuitVerification, Proofs, ProofsVerification, TranscriptKind,
+};
+use crate::transcript::KeccakRead;
+
+/// Verifies a proof created with `transcript` with the verifying key `vk`.
+/// `instance` returns the instance columns for the degree of the circuit
+/// domain.
+fn verify_circuit_proof(
+    params: &Params<G1Affine>,
+    vk: &VerifyingKey<G1Affine>,
+    proof: &CircuitProof,
+    instance: impl FnOnce(u32) -> Vec<Vec<Fr>>,
+    transcript: TranscriptKind,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let instance = instance(vk.get_domain().k());
+    let instance: Vec<&[Fr]> = instance.iter().map(|column| column.as_slice()).collect();
+    let public_inputs_size = instance
+        .iter()
+        .map(|column| column.len())
+        .max()
+        .unwrap_or(0);
+
+    let verifier_params: ParamsVerifier<Bn256> = params.verifier(public_inputs_size)?;
+    let strategy = SingleVerifier::new(&verifier_params);
+    match transcript {
+        TranscriptKind::Blake2b => {
+            let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof.proof.as_ref());
+            verify_proof(
+                &verifier_params,
+                vk,
+                strategy,
+                &[&instance],
+                &mut transcript,
+            )?;
+        }
+        TranscriptKind::Keccak => {
+            let mut transcript = KeccakRead::<_, _, Challenge255<_>>::init(proof.proof.as_ref());
+            verify_proof(
+                &verifier_params,
+                vk,
+                strategy,
+                &[&instance],
+                &mut transcript,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn circuit_verification(result: Result<(), Box<dyn std::error::Error>>) -> CircuitVerification {
+    match result {
+        Ok(()) => CircuitVerification {
+            valid: true,
+            error: None,
+        },
+        Err(err) => CircuitVerification {
+            valid: false,
+            error: Some(err.to_string()),
+        },
+    }
+}
+
+/// Verifies `proofs` created via `compute_proof` with `params` read from
+/// `params_path`, and reports for each circuit whether its proof is valid.
+/// The verifying keys are generated from the circuits without witnesses and
+/// taken from `key_cache`, and the randomness is derived from the block hash
+/// and the witness commitment of the prover, so that the prover can't choose
+/// them.
+/// Callers need to compare `proofs.public_data` with the block they expect.
+pub fn verify_proofs(
+    params: &Params<G1Affine>,
+    params_path: &str,
+    key_cache: &KeyCache,
+    proofs: &Proofs,
+) -> Result<ProofsVerification, Box<dyn std::error::Error>> {
+    let transcript = proofs.transcript;
+    let randomness = block_randomness(proofs.public_data.hash, proofs.witness_commitment);
+
+    // The state circuit doesn't depend on the block, its key gives the
+    // degree of the domain of all the circuits.
+    let state_vk = key_cache.get_or_create_vk(
+        "state",
+        params_path,
+        params,
+        vec![],
+        &StateCircuit::<Fr, STATE_CIRCUIT_ROWS>::default(),
+    )?;
+    let k = state_vk.get_domain().k();
+    let state_proof = verify_circuit_proof(
+        params,
+        &state_vk,
+        &proofs.state_proof,
+        |_| state_circuit_instance(randomness),
+        transcript,
+    );
+
+    let mut block = Block::<Fr>::default();
+    pad_block(&mut block, k);
+
+    let evm_proof = key_cache
+        .get_or_create_vk(
+            "evm",
+            params_path,
+            params,
+            evm_circuit_shape(&block),
+            &EvmCircuit::<Fr>::new(block.clone(), FixedTableTag::iter().collect(), 1 << k),
+        )
+        .and_then(|vk| {
+            verify_circuit_proof(
+                params,
+                &vk,
+                &proofs.evm_proof,
+                |k| evm_circuit_instance(k, randomness),
+                transcript,
+            )
+        });
+
+    let public_inputs = proofs
+        .public_data
+        .public_inputs::<Fr, PI_CIRCUIT_MAX_TXS>(randomness);
+    let pi_proof = key_cache
+        .get_or_create_vk(
+            "pi",
+            params_path,
+            params,
+            vec![],
+            &PiCircuit::<Fr, PI_CIRCUIT_MAX_TXS>::new(&block, k),
+        )
+        .and_then(|vk| {
+            verify_circuit_proof(
+                params,
+                &vk,
+                &proofs.pi_proof,
+                |k| pi_circuit_instance(k, randomness, public_inputs),
+                transcript,
+            )
+        });
+
+    // The capacities of the tx circuit are chosen by the prover, the
+    // verifying key is derived for them.
+    let tx_proof = proofs.tx_proof.as_ref().map(|proof| {
+        with_tx_circuit_shape!(proof.shape.as_slice(), |MAX_TXS, MAX_CALLDATA| {
+            // The circuit without witnesses keeps the window size of the
+            // signature verification, which the keys depend on.
+            let circuit = TxCircuit::<Fr, MAX_TXS, MAX_CALLDATA>::new_from_block(&block, k, OsRng);
+            key_cache
+                .get_or_create_vk("tx", params_path, params, proof.shape.clone(), &circuit)
+                .and_then(|vk| {
+                    verify_circuit_proof(
+                        params,
+                        &vk,
+                        proof,
+                        |k| TxCircuit::<Fr, MAX_TXS, MAX_CALLDATA>::instance(k, randomness),
+                        transcript,
+                    )
+                })
+        })
+    });
+
+    let bytecode_proof = proofs.bytecode_proof.as_ref().map(|proof| {
+        let size = 1 << k;
+        key_cache
+            .get_or_create_vk(
+                "bytecode",
+                params_path,
+                params,
+                vec![size],
+                &BytecodeCircuit::<Fr>::new_from_block(&block, size),
+            )
+            .and_then(|vk| {
+                verify_circuit_proof(
+                    params,
+                    &vk,
+                    proof,
+                    |k| BytecodeCircuit::instance(k, randomness),
+                    transcript,
+                )
+            })
+    });
+
+    let copy_proof = proofs.copy_proof.as_ref().map(|proof| {
+        key_cache
+            .get_or_create_vk(
+                "copy",
+                params_path,
+                params,
+                vec![block.copy_circuit_pad_to],
+                &CopyCircuit::<Fr>::new(block.clone()),
+            )
+            .and_then(|vk| {
+                verify_circuit_proof(
+                    params,
+                    &vk,
+                    proof,
+                    |k| CopyCircuit::instance(k, randomness),
+                    transcript,
+                )
+            })
+    });
+
+    Ok(ProofsVerification {
+        state_proof: circuit_verification(state_proof),
+        evm_proof: circuit_verification(evm_proof),
+        pi_proof: circuit_verification(pi_proof),
+        tx_proof: tx_proof.map(circuit_verification),
+        bytecode_proof: bytecode_proof.map(circuit_verification),
+        copy_proof: copy_proof.map(circuit_verification),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute_proof::{create_circuit_proof, proof_rng};
+    use crate::key_cache::tests::FixedValueCircuit;
+
+    fn prove(
+        params: &Params<G1Affine>,
+        key_cache: &KeyCache,
+        value: u64,
+        transcript: TranscriptKind,
+    ) -> CircuitProof {
+        let circuit = FixedValueCircuit { value };
+        let pk = key_cache
+            .get_or_create("test", "params", params, vec![value as usize], &circuit)
+            .unwrap();
+        create_circuit_proof(
+            params,
+            &pk,
+            circuit,
+            |_| vec![],
+            transcript,
+            &mut proof_rng(true),
+        )
+        .unwrap()
+    }
+
+    fn verify(
+        params: &Params<G1Affine>,
+        proof: &CircuitProof,
+        transcript: TranscriptKind,
+    ) -> CircuitVerification {
+        // The verifier generates the key on its own
+        let vk = KeyCache::new(None)
+            .get_or_create_vk(
+                "test",
+                "params",
+                params,
+                vec![1],
+                &FixedValueCircuit { value: 1 },
+            )
+            .unwrap();
+        circuit_verification(verify_circuit_proof(
+            params,
+            &vk,
+            proof,
+            |_| vec![],
+            transcript,
+        ))
+    }
+
+    #[test]
+    fn verify_with_derived_vk() {
+        let params = Params::<G1Affine>::unsafe_setup::<Bn256>(4);
+        let proof = prove(&params, &KeyCache::new(None), 1, TranscriptKind::Blake2b);
+        assert!(verify(&params, &proof, TranscriptKind::Blake2b).valid);
+    }
+
+    #[test]
+    fn verify_keccak_transcript() {
+        let params = Params::<G1Affine>::unsafe_setup::<Bn256>(4);
+        let proof = prove(&params, &KeyCache::new(None), 1, TranscriptKind::Keccak);
+        assert!(verify(&params, &proof, TranscriptKind::Keccak).valid);
+        assert!(!verify(&params, &proof, TranscriptKind::Blake2b).valid);
+    }
+
+    #[test]
+    fn verify_rejects_tampered_vk() {
+        // A prover with a key of another fixed column can prove another
+        // statement, but the proof doesn't verify with the verifier's key.
+        let params = Params::<G1Affine>::unsafe_setup::<Bn256>(4);
+        let proof = prove(&params, &KeyCache::new(None), 2, TranscriptKind::Blake2b);
+        assert!(!verify(&params, &proof, TranscriptKind::Blake2b).valid);
+    }
+}
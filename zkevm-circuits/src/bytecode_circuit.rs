@@ -2,3 +2,222 @@
 
 pub(crate) mod bytecode_unroller;
 pub(crate) mod param;
+
+use crate::{
+    evm_circuit::witness::Block,
+    keccak_circuit::{KeccakConfig, KeccakTable},
+    util::Expr,
+};
+use bytecode_unroller::{unroll, Config, UnrolledBytecode};
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression},
+    poly::Rotation,
+};
+
+/// Construct the bytecode and keccak tables, and the powers of randomness of
+/// the instance columns.
+fn configure_tables<F: Field>(
+    meta: &mut ConstraintSystem<F>,
+) -> ([Column<Advice>; 5], KeccakTable, [Expression<F>; 31]) {
+    let bytecode_table = [(); 5].map(|_| meta.advice_column());
+    let keccak_table = KeccakTable::construct(meta);
+    let power_of_randomness = {
+        let columns = [(); 31].map(|_| meta.instance_column());
+        let mut power_of_randomness = None;
+
+        meta.create_gate("", |meta| {
+            power_of_randomness =
+                Some(columns.map(|column| meta.query_instance(column, Rotation::cur())));
+
+            [0.expr()]
+        });
+
+        power_of_randomness.unwrap()
+    };
+    (bytecode_table, keccak_table, power_of_randomness)
+}
+
+/// Returns the instance columns of a circuit with `minimum_rows` in a domain
+/// of `2^k` rows, which fill all the usable rows with the powers of
+/// `randomness`.
+fn power_of_randomness_instance<F: Field>(
+    minimum_rows: usize,
+    k: u32,
+    randomness: F,
+) -> Vec<Vec<F>> {
+    (1..32)
+        .map(|exp| vec![randomness.pow(&[exp, 0, 0, 0]); (1 << k) - minimum_rows + 2])
+        .collect()
+}
+
+/// Bytecode circuit with its own bytecode and keccak tables, whose keccak
+/// table the keccak circuit proves, to prove the circuit on its own.
+#[derive(Default)]
+pub struct BytecodeCircuit<F: Field> {
+    bytecodes: Vec<UnrolledBytecode<F>>,
+    size: usize,
+    randomness: F,
+}
+
+impl<F: Field> BytecodeCircuit<F> {
+    /// Build the circuit for the bytecodes of `block`, filling `size`
+    /// rows.
+    pub fn new_from_block(block: &Block<F>, size: usize) -> Self {
+        let bytecodes = block
+            .bytecodes
+            .values()
+            .map(|bytecode| unroll(bytecode.bytes.clone(), block.randomness))
+            .collect();
+        Self {
+            bytecodes,
+            size,
+            randomness: block.randomness,
+        }
+    }
+
+    /// Returns the number of rows required by the bytecodes of `block`,
+    /// including the unusable rows, and by their keccak hashes.
+    pub fn get_num_rows_required(block: &Block<F>) -> usize {
+        let mut cs = ConstraintSystem::default();
+        Self::configure(&mut cs);
+        // The all-zero row, and a length row plus a row per byte for each
+        // bytecode
+        let num_rows = 1
+            + block
+                .bytecodes
+                .values()
+                .map(|bytecode| bytecode.bytes.len() + 1)
+                .sum::<usize>()
+            + cs.minimum_rows();
+        num_rows.max(KeccakConfig::<F>::get_num_rows_required(
+            block.bytecodes.values().map(|bytecode| &bytecode.bytes),
+        ))
+    }
+
+    /// Returns the instance columns for a domain of `2^k` rows, which
+    /// fill all the usable rows with the powers of `randomness`.
+    pub fn instance(k: u32, randomness: F) -> Vec<Vec<F>> {
+        let mut cs = ConstraintSystem::default();
+        Self::configure(&mut cs);
+        power_of_randomness_instance(cs.minimum_rows(), k, randomness)
+    }
+}
+
+impl<F: Field> Circuit<F> for BytecodeCircuit<F> {
+    type Config = (Config<F>, KeccakConfig<F>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            size: self.size,
+            ..Default::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let (bytecode_table, keccak_table, power_of_randomness) = configure_tables(meta);
+        // The keccak circuit is configured before the bytecode circuit, see
+        // `KeccakConfig::configure`.
+        let keccak_circuit =
+            KeccakConfig::configure(meta, power_of_randomness.clone(), keccak_table);
+        (
+            Config::configure(meta, power_of_randomness, bytecode_table, keccak_table),
+            keccak_circuit,
+        )
+    }
+
+    fn synthesize(
+        &self,
+        (config, keccak_circuit): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load(&mut layouter)?;
+        keccak_circuit.assign(
+            &mut layouter,
+            self.size,
+            self.bytecodes.iter().map(|bytecode| &bytecode.bytes),
+            self.randomness,
+        )?;
+        config.assign(&mut layouter, self.size, &self.bytecodes, self.randomness)?;
+        Ok(())
+    }
+}
+
+/// Bytecode circuit that loads the keccak table from the witness, which
+/// avoids the columns of the keccak circuit in the unit tests.
+#[cfg(any(feature = "test", test))]
+pub mod test {
+    use super::{configure_tables, power_of_randomness_instance};
+    use crate::{
+        bytecode_circuit::bytecode_unroller::{Config, UnrolledBytecode},
+        keccak_circuit::KeccakTable,
+    };
+    use eth_types::Field;
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+
+    /// Bytecode circuit that loads the keccak table from the witness
+    #[derive(Default)]
+    pub struct TestCircuit<F: Field> {
+        bytecodes: Vec<UnrolledBytecode<F>>,
+        size: usize,
+        randomness: F,
+    }
+
+    impl<F: Field> TestCircuit<F> {
+        pub(crate) fn new(bytecodes: Vec<UnrolledBytecode<F>>, size: usize, randomness: F) -> Self {
+            Self {
+                bytecodes,
+                size,
+                randomness,
+            }
+        }
+
+        /// Returns the instance columns for a domain of `2^k` rows, which
+        /// fill all the usable rows with the powers of `randomness`.
+        pub fn instance(k: u32, randomness: F) -> Vec<Vec<F>> {
+            let mut cs = ConstraintSystem::default();
+            Self::configure(&mut cs);
+            power_of_randomness_instance(cs.minimum_rows(), k, randomness)
+        }
+    }
+
+    impl<F: Field> Circuit<F> for TestCircuit<F> {
+        type Config = (Config<F>, KeccakTable);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                size: self.size,
+                ..Default::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let (bytecode_table, keccak_table, power_of_randomness) = configure_tables(meta);
+            (
+                Config::configure(meta, power_of_randomness, bytecode_table, keccak_table),
+                keccak_table,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, keccak_table): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.load(&mut layouter)?;
+            keccak_table.dev_load(
+                &mut layouter,
+                self.bytecodes.iter().map(|bytecode| &bytecode.bytes),
+                self.randomness,
+            )?;
+            config.assign(&mut layouter, self.size, &self.bytecodes, self.randomness)?;
+            Ok(())
+        }
+    }
+}
@@ -1,28 +1,26 @@
 use crate::{
     evm_circuit::{
-        table::BytecodeFieldTag,
+        table::{BytecodeFieldTag, LookupTable},
         util::{
-            and, constraint_builder::BaseConstraintBuilder, not, or, select,
+            and, constraint_builder::BaseConstraintBuilder, not, or, rlc, select,
             RandomLinearCombination,
         },
     },
+    keccak_circuit::KeccakTable,
     util::Expr,
 };
 use bus_mapping::evm::OpcodeId;
-use eth_types::Field;
-use gadgets::{
-    evm_word::encode,
-    is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction},
-};
+use eth_types::{Field, ToLittleEndian, Word};
+use gadgets::is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction};
 use halo2_proofs::{
     circuit::{Layouter, Region},
     plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector, VirtualCells},
     poly::Rotation,
 };
 use keccak256::plain::Keccak;
-use std::{convert::TryInto, vec};
+use std::vec;
 
-use super::param::{KECCAK_WIDTH, PUSH_TABLE_WIDTH};
+use super::param::PUSH_TABLE_WIDTH;
 
 /// Public data for the bytecode
 #[derive(Clone, Debug, PartialEq)]
@@ -43,7 +41,6 @@ pub(crate) struct UnrolledBytecode<F: Field> {
 
 #[derive(Clone, Debug)]
 pub struct Config<F> {
-    r: F,
     minimum_rows: usize,
     q_enable: Column<Fixed>,
     q_first: Column<Fixed>,
@@ -64,19 +61,25 @@ pub struct Config<F> {
     length_inv: Column<Advice>,
     length_is_zero: IsZeroConfig<F>,
     push_table: [Column<Fixed>; PUSH_TABLE_WIDTH],
-    keccak_table: [Column<Advice>; KECCAK_WIDTH],
+    keccak_table: KeccakTable,
 }
 
 impl<F: Field> Config<F> {
-    pub(crate) fn configure(meta: &mut ConstraintSystem<F>, r: F) -> Self {
+    /// Configure the bytecode circuit so that it assigns its rows into
+    /// `bytecode_table`, laid out as `[hash, tag, index, is_code, value]` so
+    /// that it can be shared with the EVM circuit, and looks up the code
+    /// hashes in the shared `keccak_table`.
+    pub(crate) fn configure(
+        meta: &mut ConstraintSystem<F>,
+        power_of_randomness: [Expression<F>; 31],
+        bytecode_table: [Column<Advice>; 5],
+        keccak_table: KeccakTable,
+    ) -> Self {
+        let r = power_of_randomness[0].clone();
         let q_enable = meta.fixed_column();
         let q_first = meta.fixed_column();
         let q_last = meta.selector();
-        let hash = meta.advice_column();
-        let tag = meta.advice_column();
-        let index = meta.advice_column();
-        let is_code = meta.advice_column();
-        let value = meta.advice_column();
+        let [hash, tag, index, is_code, value] = bytecode_table;
         let push_rindex = meta.advice_column();
         let hash_rlc = meta.advice_column();
         let hash_length = meta.advice_column();
@@ -86,7 +89,6 @@ impl<F: Field> Config<F> {
         let push_rindex_inv = meta.advice_column();
         let length_inv = meta.advice_column();
         let push_table = array_init::array_init(|_| meta.fixed_column());
-        let keccak_table = array_init::array_init(|_| meta.advice_column());
 
         // A byte is an opcode when `push_rindex == 0` on the previous row,
         // else it's push data.
@@ -166,11 +168,19 @@ impl<F: Field> Config<F> {
                     push_rindex_is_zero.clone().is_zero_expression,
                 ),
             );
+            // `hash_rlc` accumulates the bytes from the last one to the first
+            // one, so that it ends up on the Length row as the RLC of the
+            // bytecode with the first byte multiplied by the lowest power of
+            // randomness, as it's encoded in the keccak table.
             cb.require_equal(
-                "hash_rlc := hash_rlc_prev * r + byte",
-                meta.query_advice(hash_rlc, Rotation::cur()),
-                meta.query_advice(hash_rlc, Rotation::prev()) * r
-                    + meta.query_advice(value, Rotation::cur()),
+                "hash_rlc_prev := prev_row.tag == Length ? hash_rlc : byte_prev + r * hash_rlc",
+                meta.query_advice(hash_rlc, Rotation::prev()),
+                select::expr(
+                    is_prev_row_tag_length(meta),
+                    meta.query_advice(hash_rlc, Rotation::cur()),
+                    meta.query_advice(value, Rotation::prev())
+                        + r.clone() * meta.query_advice(hash_rlc, Rotation::cur()),
+                ),
             );
             cb.require_equal(
                 "hash needs to remain the same",
@@ -220,7 +230,11 @@ impl<F: Field> Config<F> {
                 cb.require_equal(
                     "if length == 0: hash == RLC(EMPTY_HASH, randomness)",
                     meta.query_advice(hash, Rotation::cur()),
-                    Expression::Constant(keccak(&[], r)),
+                    rlc::expr(
+                        &keccak_le_bytes(&[])
+                            .map(|byte| Expression::Constant(F::from(byte as u64))),
+                        &power_of_randomness,
+                    ),
                 );
             });
             // Conditions:
@@ -259,9 +273,15 @@ impl<F: Field> Config<F> {
                     meta.query_advice(hash_length, Rotation::cur()),
                 );
             });
+            // For bytecode with length == 0 this is the Length row, with
+            // value == 0, so the RLC of the empty bytecode is 0.
+            cb.require_equal(
+                "hash_rlc := byte on the last byte",
+                meta.query_advice(hash_rlc, Rotation::cur()),
+                meta.query_advice(value, Rotation::cur()),
+            );
             // Conditions:
             // - On the row with the last byte (`is_final == 1`)
-            // - Of bytecode with length > 0
             // - Not padding
             cb.gate(and::expr(vec![
                 meta.query_fixed(q_enable, Rotation::cur()),
@@ -348,25 +368,20 @@ impl<F: Field> Config<F> {
         // keccak lookup
         meta.lookup_any("keccak", |meta| {
             // Conditions:
-            // - On the row with the last byte (`is_final == 1`)
-            // - Not padding
-            let enable = and::expr(vec![
-                meta.query_advice(is_final, Rotation::cur()),
-                not::expr(meta.query_advice(padding, Rotation::cur())),
-            ]);
+            // - On the Length row, where `hash_rlc` is the RLC of all the bytes
+            let enable = meta.query_fixed(q_enable, Rotation::cur()) * is_row_tag_length(meta);
             let lookup_columns = vec![hash_rlc, hash_length, hash];
-            let mut constraints = vec![];
-            for i in 0..KECCAK_WIDTH {
-                constraints.push((
-                    enable.clone() * meta.query_advice(lookup_columns[i], Rotation::cur()),
-                    meta.query_advice(keccak_table[i], Rotation::cur()),
-                ))
+            let mut constraints = vec![enable.clone()];
+            for column in lookup_columns {
+                constraints.push(enable.clone() * meta.query_advice(column, Rotation::cur()));
             }
             constraints
+                .into_iter()
+                .zip(keccak_table.table_exprs(meta))
+                .collect()
         });
 
         Config {
-            r,
             minimum_rows: meta.minimum_rows(),
             q_enable,
             q_first,
@@ -393,9 +408,10 @@ impl<F: Field> Config<F> {
 
     pub(crate) fn assign(
         &self,
-        mut layouter: impl Layouter<F>,
+        layouter: &mut impl Layouter<F>,
         size: usize,
         witness: &[UnrolledBytecode<F>],
+        randomness: F,
     ) -> Result<(), Error> {
         let push_rindex_is_zero_chip = IsZeroChip::construct(self.push_rindex_is_zero.clone());
         let length_is_zero_chip = IsZeroChip::construct(self.length_is_zero.clone());
@@ -406,15 +422,42 @@ impl<F: Field> Config<F> {
         layouter.assign_region(
             || "assign bytecode",
             |mut region| {
-                let mut offset = 0;
+                // All-zero row so that disabled lookups into the bytecode table
+                // from other circuits are satisfied
+                self.set_row(
+                    &mut region,
+                    &push_rindex_is_zero_chip,
+                    &length_is_zero_chip,
+                    0,
+                    false,
+                    false,
+                    F::zero(),
+                    F::zero(),
+                    F::zero(),
+                    F::zero(),
+                    F::zero(),
+                    0,
+                    F::zero(),
+                    F::zero(),
+                    F::zero(),
+                    false,
+                    false,
+                    F::zero(),
+                )?;
+
+                let mut offset = 1;
                 let mut push_rindex_prev = 0;
 
                 for bytecode in witness.iter() {
                     // Run over all the bytes
                     let mut push_rindex = 0;
                     let mut byte_push_size = 0;
-                    let mut hash_rlc = F::zero();
                     let hash_length = F::from(bytecode.bytes.len() as u64);
+                    // RLC of the bytes from each position to the end
+                    let mut hash_rlcs = vec![F::zero(); bytecode.bytes.len() + 1];
+                    for (idx, byte) in bytecode.bytes.iter().enumerate().rev() {
+                        hash_rlcs[idx] = F::from(*byte as u64) + randomness * hash_rlcs[idx + 1];
+                    }
                     for (idx, row) in bytecode.rows.iter().enumerate() {
                         // Track which byte is an opcode and which is push
                         // data
@@ -426,8 +469,8 @@ impl<F: Field> Config<F> {
                             } else {
                                 push_rindex - 1
                             };
-                            hash_rlc = hash_rlc * self.r + row.value;
                         }
+                        let hash_rlc = hash_rlcs[idx.saturating_sub(1)];
 
                         // Set the data for this row
                         if offset <= last_row_offset {
@@ -521,7 +564,7 @@ impl<F: Field> Config<F> {
             || format!("assign q_first {}", offset),
             self.q_first,
             offset,
-            || Ok(F::from((offset == 0) as u64)),
+            || Ok(F::from((offset == 1) as u64)),
         )?;
 
         // q_last
@@ -560,11 +603,7 @@ impl<F: Field> Config<F> {
         Ok(())
     }
 
-    pub(crate) fn load(
-        &self,
-        layouter: &mut impl Layouter<F>,
-        bytecodes: &[UnrolledBytecode<F>],
-    ) -> Result<(), Error> {
+    pub(crate) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
         // push table: BYTE -> NUM_PUSHED:
         // [0, OpcodeId::PUSH1[ -> 0
         // [OpcodeId::PUSH1, OpcodeId::PUSH32] -> [1..32]
@@ -588,37 +627,11 @@ impl<F: Field> Config<F> {
                 }
                 Ok(())
             },
-        )?;
-
-        // keccak table
-        layouter.assign_region(
-            || "keccak table",
-            |mut region| {
-                for (offset, bytecode) in bytecodes.iter().map(|v| v.bytes.clone()).enumerate() {
-                    let hash: F = keccak(&bytecode[..], self.r);
-                    let rlc: F = linear_combine(bytecode.clone(), self.r);
-                    let size = F::from(bytecode.len() as u64);
-                    for (name, column, value) in &[
-                        ("rlc", self.keccak_table[0], rlc),
-                        ("size", self.keccak_table[1], size),
-                        ("hash", self.keccak_table[2], hash),
-                    ] {
-                        region.assign_advice(
-                            || format!("Keccak table assign {} {}", name, offset),
-                            *column,
-                            offset,
-                            || Ok(*value),
-                        )?;
-                    }
-                }
-                Ok(())
-            },
-        )?;
-        Ok(())
+        )
     }
 }
 
-fn unroll<F: Field>(bytes: Vec<u8>, r: F) -> UnrolledBytecode<F> {
+pub(crate) fn unroll<F: Field>(bytes: Vec<u8>, r: F) -> UnrolledBytecode<F> {
     let hash = keccak(&bytes[..], r);
     let mut rows = vec![BytecodeRow::<F> {
         hash,
@@ -661,10 +674,16 @@ fn get_push_size(byte: u8) -> u64 {
     }
 }
 
-fn keccak<F: Field>(msg: &[u8], r: F) -> F {
+/// Returns the keccak digest of `msg` as the little-endian bytes of the
+/// word, which is the encoding used for the hash in the EVM circuit.
+fn keccak_le_bytes(msg: &[u8]) -> [u8; 32] {
     let mut keccak = Keccak::default();
     keccak.update(msg);
-    RandomLinearCombination::<F, 32>::random_linear_combine(keccak.digest().try_into().unwrap(), r)
+    Word::from_big_endian(&keccak.digest()).to_le_bytes()
+}
+
+fn keccak<F: Field>(msg: &[u8], r: F) -> F {
+    RandomLinearCombination::<F, 32>::random_linear_combine(keccak_le_bytes(msg), r)
 }
 
 fn into_words(message: &[u8]) -> Vec<u64> {
@@ -680,63 +699,25 @@ fn into_words(message: &[u8]) -> Vec<u64> {
     words
 }
 
-fn linear_combine<F: Field>(bytes: Vec<u8>, r: F) -> F {
-    encode(bytes.into_iter(), r)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use eth_types::{Bytecode, Word};
-    use halo2_proofs::{
-        circuit::{Layouter, SimpleFloorPlanner},
-        dev::MockProver,
-        pairing::bn256::Fr,
-        plonk::{Circuit, ConstraintSystem, Error},
-    };
-
-    #[derive(Default)]
-    struct MyCircuit<F: Field> {
-        bytecodes: Vec<UnrolledBytecode<F>>,
-        size: usize,
-    }
-
-    impl<F: Field> MyCircuit<F> {
-        fn r() -> F {
-            F::from(123456)
-        }
-    }
-
-    impl<F: Field> Circuit<F> for MyCircuit<F> {
-        type Config = Config<F>;
-        type FloorPlanner = SimpleFloorPlanner;
+    use crate::bytecode_circuit::test::TestCircuit;
+    use eth_types::Bytecode;
+    use halo2_proofs::{dev::MockProver, pairing::bn256::Fr};
 
-        fn without_witnesses(&self) -> Self {
-            Self::default()
-        }
-
-        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            Config::configure(meta, MyCircuit::r())
-        }
-
-        fn synthesize(
-            &self,
-            config: Self::Config,
-            mut layouter: impl Layouter<F>,
-        ) -> Result<(), Error> {
-            config.load(&mut layouter, &self.bytecodes)?;
-            config.assign(layouter, self.size, &self.bytecodes)?;
-            Ok(())
-        }
+    fn randomness<F: Field>() -> F {
+        F::from(123456)
     }
 
     fn verify<F: Field>(k: u32, bytecodes: Vec<UnrolledBytecode<F>>, success: bool) {
-        let circuit = MyCircuit::<F> {
-            bytecodes,
-            size: 2usize.pow(k),
-        };
+        let size = 2usize.pow(k);
+        let circuit = TestCircuit::<F>::new(bytecodes, size, randomness());
+
+        // Fill all the usable rows with the powers of randomness
+        let power_of_randomness = TestCircuit::<F>::instance(k, randomness());
 
-        let prover = MockProver::<F>::run(k, &circuit, vec![]).unwrap();
+        let prover = MockProver::<F>::run(k, &circuit, power_of_randomness).unwrap();
         let err = prover.verify();
         let print_failures = true;
         if err.is_err() && print_failures {
@@ -754,7 +735,7 @@ mod tests {
     #[test]
     fn bytecode_unrolling() {
         let k = 10;
-        let r = MyCircuit::r();
+        let r = randomness();
         let mut rows = vec![];
         let mut bytecode = Bytecode::default();
         // First add all non-push bytes, which should all be seen as code
@@ -824,14 +805,14 @@ mod tests {
     #[test]
     fn bytecode_empty() {
         let k = 9;
-        let r = MyCircuit::r();
+        let r = randomness();
         verify::<Fr>(k, vec![unroll(vec![], r)], true);
     }
 
     #[test]
     fn bytecode_simple() {
         let k = 9;
-        let r = MyCircuit::r();
+        let r = randomness();
         let bytecodes = vec![
             unroll(vec![7u8], r),
             unroll(vec![6u8], r),
@@ -844,15 +825,15 @@ mod tests {
     #[test]
     fn bytecode_full() {
         let k = 9;
-        let r = MyCircuit::r();
-        verify::<Fr>(k, vec![unroll(vec![7u8; 2usize.pow(k) - 7], r)], true);
+        let r = randomness();
+        verify::<Fr>(k, vec![unroll(vec![7u8; 2usize.pow(k) - 8], r)], true);
     }
 
     /// Tests a circuit with incomplete bytecode
     #[test]
     fn bytecode_incomplete() {
         let k = 9;
-        let r = MyCircuit::r();
+        let r = randomness();
         verify::<Fr>(k, vec![unroll(vec![7u8; 2usize.pow(k) + 1], r)], false);
     }
 
@@ -860,7 +841,7 @@ mod tests {
     #[test]
     fn bytecode_push() {
         let k = 9;
-        let r = MyCircuit::r();
+        let r = randomness();
         verify::<Fr>(
             k,
             vec![
@@ -885,7 +866,7 @@ mod tests {
     #[test]
     fn bytecode_invalid_hash_data() {
         let k = 9;
-        let r = MyCircuit::r();
+        let r = randomness();
         let bytecode = vec![8u8, 2, 3, 8, 9, 7, 128];
         let unrolled = unroll(bytecode, r);
         verify::<Fr>(k, vec![unrolled.clone()], true);
@@ -916,7 +897,7 @@ mod tests {
     #[ignore]
     fn bytecode_invalid_index() {
         let k = 9;
-        let r = MyCircuit::r();
+        let r = randomness();
         let bytecode = vec![8u8, 2, 3, 8, 9, 7, 128];
         let unrolled = unroll(bytecode, r);
         verify::<Fr>(k, vec![unrolled.clone()], true);
@@ -940,7 +921,7 @@ mod tests {
     #[test]
     fn bytecode_invalid_byte_data() {
         let k = 9;
-        let r = MyCircuit::r();
+        let r = randomness();
         let bytecode = vec![8u8, 2, 3, 8, 9, 7, 128];
         let unrolled = unroll(bytecode, r);
         verify::<Fr>(k, vec![unrolled.clone()], true);
@@ -968,7 +949,7 @@ mod tests {
     #[test]
     fn bytecode_invalid_is_code() {
         let k = 9;
-        let r = MyCircuit::r();
+        let r = randomness();
         let bytecode = vec![
             OpcodeId::ADD.as_u8(),
             OpcodeId::PUSH1.as_u8(),
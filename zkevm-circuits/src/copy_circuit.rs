@@ -2,28 +2,38 @@
 //! copied bytes while execution opcodes such as CALLDATACOPY, CODECOPY, LOGS,
 //! etc.
 
-use bus_mapping::circuit_input_builder::{CopyDataType, CopyEvent, CopyStep, NumberOrHash};
-use eth_types::{Field, ToAddress, ToScalar, U256};
+use bus_mapping::{
+    circuit_input_builder::{CopyDataType, CopyEvent, CopyStep, NumberOrHash},
+    operation::{RWCounter, RW},
+};
+use eth_types::{evm_types::ProgramCounter, Field, ToAddress, ToScalar, U256};
 use gadgets::{
     binary_number::{BinaryNumberChip, BinaryNumberConfig},
     less_than::{LtChip, LtConfig, LtInstruction},
     util::{and, not, or, Expr},
 };
 use halo2_proofs::{
-    circuit::{Layouter, Region},
-    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, Selector, VirtualCells},
+    circuit::{Layouter, Region, SimpleFloorPlanner},
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Selector, VirtualCells,
+    },
     poly::Rotation,
 };
+use itertools::Itertools;
+use std::iter;
 
-use crate::evm_circuit::{
-    table::{BytecodeFieldTag, LookupTable, RwTableTag, TxContextFieldTag, TxLogFieldTag},
-    util::{constraint_builder::BaseConstraintBuilder, RandomLinearCombination},
-    witness::Block,
+use crate::{
+    evm_circuit::{
+        table::{BytecodeFieldTag, LookupTable, RwTableTag, TxContextFieldTag, TxLogFieldTag},
+        util::{constraint_builder::BaseConstraintBuilder, RandomLinearCombination},
+        witness::{Block, Bytecode, RwMap, Transaction},
+    },
+    rw_table::RwTable,
 };
 
 /// The rw table shared between evm circuit and state circuit
 #[derive(Clone, Copy, Debug)]
-pub struct CopyCircuit<F> {
+pub struct CopyCircuitConfig<F> {
     /// Whether the row is enabled or not.
     pub q_enable: Column<Fixed>,
     /// Whether this row denotes a step. A read row is a step and a write row is
@@ -56,9 +66,12 @@ pub struct CopyCircuit<F> {
     pub rw_counter: Column<Advice>,
     /// Decrementing counter denoting reverse read-write counter.
     pub rwc_inc_left: Column<Advice>,
+    /// Random linear combination accumulator of the bytes left to be copied,
+    /// only for CopyDataType::RlcAcc write rows.
+    pub rlc_acc: Column<Advice>,
     /// Binary chip to constrain the copy table conditionally depending on the
-    /// current row's tag, whether it is Bytecode, Memory, TxCalldata or
-    /// TxLog.
+    /// current row's tag, whether it is Bytecode, Memory, TxCalldata, TxLog or
+    /// RlcAcc.
     pub tag: BinaryNumberConfig<CopyDataType, 3>,
     /// Lt chip to check: src_addr < src_addr_end.
     /// Since `src_addr` and `src_addr_end` are u64, 8 bytes are sufficient for
@@ -66,7 +79,7 @@ pub struct CopyCircuit<F> {
     pub addr_lt_addr_end: LtConfig<F, 8>,
 }
 
-impl<F: Field> LookupTable<F> for CopyCircuit<F> {
+impl<F: Field> LookupTable<F> for CopyCircuitConfig<F> {
     fn table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
         vec![
             meta.query_advice(self.is_first, Rotation::cur()),
@@ -80,15 +93,17 @@ impl<F: Field> LookupTable<F> for CopyCircuit<F> {
             meta.query_advice(self.bytes_left, Rotation::cur()), // length
             meta.query_advice(self.rw_counter, Rotation::cur()), // rw_counter
             meta.query_advice(self.rwc_inc_left, Rotation::cur()), // rwc_inc_left
+            meta.query_advice(self.rlc_acc, Rotation::next()), // rlc_acc
         ]
     }
 }
 
-impl<F: Field> CopyCircuit<F> {
+impl<F: Field> CopyCircuitConfig<F> {
     /// Configure the Copy Circuit constraining read-write steps and doing
     /// appropriate lookups to the Tx Table, RW Table and Bytecode Table.
     pub fn configure(
         meta: &mut ConstraintSystem<F>,
+        randomness: Expression<F>,
         tx_table: &dyn LookupTable<F>,
         rw_table: &dyn LookupTable<F>,
         bytecode_table: &dyn LookupTable<F>,
@@ -106,6 +121,7 @@ impl<F: Field> CopyCircuit<F> {
         let is_pad = meta.advice_column();
         let rw_counter = meta.advice_column();
         let rwc_inc_left = meta.advice_column();
+        let rlc_acc = meta.advice_column();
 
         let tag = BinaryNumberChip::configure(meta, q_enable);
 
@@ -243,6 +259,35 @@ impl<F: Field> CopyCircuit<F> {
                 meta.query_advice(is_pad, Rotation::next()),
             );
 
+            let is_rlc_acc = tag.value_equals(CopyDataType::RlcAcc, Rotation::next())(meta);
+            cb.condition(
+                and::expr([
+                    is_rlc_acc.clone(),
+                    meta.query_advice(is_last, Rotation::next()),
+                ]),
+                |cb| {
+                    cb.require_equal(
+                        "rlc_acc == value for last step",
+                        meta.query_advice(rlc_acc, Rotation::next()),
+                        meta.query_advice(value, Rotation::next()),
+                    );
+                },
+            );
+            cb.condition(
+                and::expr([
+                    is_rlc_acc,
+                    not::expr(meta.query_advice(is_last, Rotation::next())),
+                ]),
+                |cb| {
+                    cb.require_equal(
+                        "rlc_acc == value + r * rlc_acc_next for non-last step",
+                        meta.query_advice(rlc_acc, Rotation::next()),
+                        meta.query_advice(value, Rotation::next())
+                            + randomness.clone() * meta.query_advice(rlc_acc, Rotation(3)),
+                    );
+                },
+            );
+
             cb.gate(meta.query_selector(q_step))
         });
 
@@ -338,6 +383,7 @@ impl<F: Field> CopyCircuit<F> {
             is_pad,
             rw_counter,
             rwc_inc_left,
+            rlc_acc,
             tag,
             addr_lt_addr_end,
         }
@@ -352,11 +398,35 @@ impl<F: Field> CopyCircuit<F> {
         let tag_chip = BinaryNumberChip::construct(self.tag);
         let lt_chip = LtChip::construct(self.addr_lt_addr_end);
 
+        let num_rows: usize = block
+            .copy_events
+            .values()
+            .map(|copy_event| copy_event.steps.len())
+            .sum();
+        let pad_to = block.copy_circuit_pad_to;
+        if pad_to > 0 && num_rows > pad_to {
+            log::error!(
+                "copy circuit needs {} rows but only has {}",
+                num_rows,
+                pad_to
+            );
+            return Err(Error::Synthesis);
+        }
+        // The copy events are followed by padding events of 2 rows up to
+        // `pad_to` rows, so that the fixed columns only depend on it.
+        let padding_event = Self::padding_event();
+        let num_padding_events = pad_to.saturating_sub(num_rows) / 2;
+
         layouter.assign_region(
             || "assign copy table",
             |mut region| {
                 let mut offset = 0;
-                for copy_event in block.copy_events.values() {
+                for copy_event in block
+                    .copy_events
+                    .values()
+                    .chain(iter::repeat(&padding_event).take(num_padding_events))
+                {
+                    let rlc_accs = Self::rlc_accs(copy_event, block.randomness);
                     for (step_idx, copy_step) in copy_event.steps.iter().enumerate() {
                         self.assign_step(
                             &mut region,
@@ -365,6 +435,7 @@ impl<F: Field> CopyCircuit<F> {
                             copy_event,
                             step_idx,
                             copy_step,
+                            rlc_accs[step_idx],
                             &tag_chip,
                             &lt_chip,
                         )?;
@@ -381,6 +452,56 @@ impl<F: Field> CopyCircuit<F> {
         )
     }
 
+    /// Returns the event of the padding rows, which copies a padding byte from
+    /// memory to an RLC accumulator, so that it satisfies the constraints
+    /// without any lookup.  Its call id is 0, which no call has.
+    fn padding_event() -> CopyEvent {
+        let step = |tag, rw, is_pad| CopyStep {
+            addr: 0,
+            tag,
+            rw,
+            value: 0,
+            is_code: None,
+            is_pad,
+            rwc: RWCounter(0),
+            rwc_inc_left: 0,
+        };
+        CopyEvent {
+            src_addr: 0,
+            src_addr_end: 0,
+            src_type: CopyDataType::Memory,
+            src_id: NumberOrHash::Number(0),
+            dst_addr: 0,
+            dst_type: CopyDataType::RlcAcc,
+            dst_id: NumberOrHash::Number(0),
+            log_id: None,
+            length: 1,
+            steps: vec![
+                step(CopyDataType::Memory, RW::READ, true),
+                step(CopyDataType::RlcAcc, RW::WRITE, false),
+            ],
+            tx_id: 0,
+            call_id: 0,
+            pc: ProgramCounter(0),
+        }
+    }
+
+    /// Returns the random linear combination accumulator of each step in the
+    /// copy event, which is non-zero only for the RlcAcc write steps.
+    fn rlc_accs(copy_event: &CopyEvent, randomness: F) -> Vec<F> {
+        let mut rlc_accs = vec![F::zero(); copy_event.steps.len()];
+        if copy_event.dst_type == CopyDataType::RlcAcc {
+            let mut acc = F::zero();
+            for (step_idx, copy_step) in copy_event.steps.iter().enumerate().rev() {
+                if copy_step.rw.is_write() {
+                    acc = acc * randomness + F::from(copy_step.value as u64);
+                    rlc_accs[step_idx] = acc;
+                }
+            }
+        }
+        rlc_accs
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn assign_step(
         &self,
@@ -390,6 +511,7 @@ impl<F: Field> CopyCircuit<F> {
         copy_event: &CopyEvent,
         step_idx: usize,
         copy_step: &CopyStep,
+        rlc_acc: F,
         tag_chip: &BinaryNumberChip<F, CopyDataType, 3>,
         lt_chip: &LtChip<F, 8>,
     ) -> Result<(), Error> {
@@ -502,6 +624,13 @@ impl<F: Field> CopyCircuit<F> {
             offset,
             || Ok(F::from(copy_step.rwc_inc_left)),
         )?;
+        // rlc_acc
+        region.assign_advice(
+            || format!("assign rlc_acc {}", offset),
+            self.rlc_acc,
+            offset,
+            || Ok(rlc_acc),
+        )?;
         // tag binary number chip
         tag_chip.assign(region, offset, &copy_step.tag)?;
         // assignment for read steps
@@ -616,212 +745,233 @@ impl<F: Field> CopyCircuit<F> {
             offset,
             || Ok(F::zero()),
         )?;
+        // rlc_acc
+        region.assign_advice(
+            || format!("assign rlc_acc {}", offset),
+            self.rlc_acc,
+            offset,
+            || Ok(F::zero()),
+        )?;
         // tag
         tag_chip.assign(region, offset, &CopyDataType::default())?;
         Ok(())
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use bus_mapping::{
-        circuit_input_builder::{CircuitInputBuilder, CopyDataType},
-        mock::BlockData,
-        operation::RWCounter,
-    };
-    use eth_types::{bytecode, geth_types::GethData, Field, Word};
-    use halo2_proofs::{
-        circuit::{Layouter, SimpleFloorPlanner},
-        dev::{MockProver, VerifyFailure},
-        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
-    };
-    use itertools::Itertools;
-    use mock::TestContext;
-    use rand::{prelude::SliceRandom, Rng};
-
-    use crate::{
-        evm_circuit::witness::{block_convert, Block, Bytecode, RwMap, Transaction},
-        rw_table::RwTable,
-    };
+/// Config of the `CopyCircuit`: the `CopyCircuitConfig` and its lookup tables
+#[derive(Clone)]
+pub struct CopyCircuitTablesConfig<F> {
+    tx_table: [Column<Advice>; 4],
+    rw_table: RwTable,
+    bytecode_table: [Column<Advice>; 5],
+    copy_table: CopyCircuitConfig<F>,
+}
 
-    use super::CopyCircuit;
+impl<F: Field> CopyCircuitTablesConfig<F> {
+    fn load_txs(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        txs: &[Transaction],
+        randomness: F,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "tx table",
+            |mut region| {
+                let mut offset = 0;
+                for column in self.tx_table {
+                    region.assign_advice(
+                        || "tx table all-zero row",
+                        column,
+                        offset,
+                        || Ok(F::zero()),
+                    )?;
+                }
+                offset += 1;
 
-    #[derive(Clone)]
-    struct MyConfig<F> {
-        tx_table: [Column<Advice>; 4],
-        rw_table: RwTable,
-        bytecode_table: [Column<Advice>; 5],
-        copy_table: CopyCircuit<F>,
+                for tx in txs.iter() {
+                    for row in tx.table_assignments(randomness) {
+                        for (column, value) in self.tx_table.iter().zip_eq(row) {
+                            region.assign_advice(
+                                || format!("tx table row {}", offset),
+                                *column,
+                                offset,
+                                || Ok(value),
+                            )?;
+                        }
+                        offset += 1;
+                    }
+                }
+                Ok(())
+            },
+        )
     }
 
-    impl<F: Field> MyConfig<F> {
-        fn load_txs(
-            &self,
-            layouter: &mut impl Layouter<F>,
-            txs: &[Transaction],
-            randomness: F,
-        ) -> Result<(), Error> {
-            layouter.assign_region(
-                || "tx table",
-                |mut region| {
-                    let mut offset = 0;
-                    for column in self.tx_table {
-                        region.assign_advice(
-                            || "tx table all-zero row",
-                            column,
-                            offset,
-                            || Ok(F::zero()),
-                        )?;
-                    }
-                    offset += 1;
+    fn load_rws(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        rws: &RwMap,
+        randomness: F,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "rw table",
+            |mut region| {
+                let mut offset = 0;
+                self.rw_table
+                    .assign(&mut region, offset, &Default::default())?;
+                offset += 1;
 
-                    for tx in txs.iter() {
-                        for row in tx.table_assignments(randomness) {
-                            for (column, value) in self.tx_table.iter().zip_eq(row) {
-                                region.assign_advice(
-                                    || format!("tx table row {}", offset),
-                                    *column,
-                                    offset,
-                                    || Ok(value),
-                                )?;
-                            }
-                            offset += 1;
-                        }
-                    }
-                    Ok(())
-                },
-            )
-        }
+                let mut rows = rws
+                    .0
+                    .values()
+                    .flat_map(|rws| rws.iter())
+                    .collect::<Vec<_>>();
+
+                rows.sort_by_key(|a| a.rw_counter());
+                let mut expected_rw_counter = 1;
+                for rw in rows {
+                    assert!(rw.rw_counter() == expected_rw_counter);
+                    expected_rw_counter += 1;
 
-        fn load_rws(
-            &self,
-            layouter: &mut impl Layouter<F>,
-            rws: &RwMap,
-            randomness: F,
-        ) -> Result<(), Error> {
-            layouter.assign_region(
-                || "rw table",
-                |mut region| {
-                    let mut offset = 0;
                     self.rw_table
-                        .assign(&mut region, offset, &Default::default())?;
+                        .assign(&mut region, offset, &rw.table_assignment(randomness))?;
                     offset += 1;
+                }
+                Ok(())
+            },
+        )
+    }
 
-                    let mut rows = rws
-                        .0
-                        .values()
-                        .flat_map(|rws| rws.iter())
-                        .collect::<Vec<_>>();
-
-                    rows.sort_by_key(|a| a.rw_counter());
-                    let mut expected_rw_counter = 1;
-                    for rw in rows {
-                        assert!(rw.rw_counter() == expected_rw_counter);
-                        expected_rw_counter += 1;
+    fn load_bytecodes<'a>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        bytecodes: impl IntoIterator<Item = &'a Bytecode> + Clone,
+        randomness: F,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "bytecode table",
+            |mut region| {
+                let mut offset = 0;
+                for column in self.bytecode_table {
+                    region.assign_advice(
+                        || "bytecode table all-zero row",
+                        column,
+                        offset,
+                        || Ok(F::zero()),
+                    )?;
+                }
+                offset += 1;
 
-                        self.rw_table.assign(
-                            &mut region,
-                            offset,
-                            &rw.table_assignment(randomness),
-                        )?;
+                for bytecode in bytecodes.clone() {
+                    for row in bytecode.table_assignments(randomness) {
+                        for (column, value) in self.bytecode_table.iter().zip_eq(row) {
+                            region.assign_advice(
+                                || format!("bytecode table row {}", offset),
+                                *column,
+                                offset,
+                                || Ok(value),
+                            )?;
+                        }
                         offset += 1;
                     }
-                    Ok(())
-                },
-            )
-        }
+                }
+                Ok(())
+            },
+        )
+    }
+}
 
-        fn load_bytecodes<'a>(
-            &self,
-            layouter: &mut impl Layouter<F>,
-            bytecodes: impl IntoIterator<Item = &'a Bytecode> + Clone,
-            randomness: F,
-        ) -> Result<(), Error> {
-            layouter.assign_region(
-                || "bytecode table",
-                |mut region| {
-                    let mut offset = 0;
-                    for column in self.bytecode_table {
-                        region.assign_advice(
-                            || "bytecode table all-zero row",
-                            column,
-                            offset,
-                            || Ok(F::zero()),
-                        )?;
-                    }
-                    offset += 1;
+/// Copy circuit with its own tx, rw and bytecode tables, which are loaded
+/// from the witness, to test or prove the circuit on its own.
+#[derive(Default)]
+pub struct CopyCircuit<F> {
+    block: Block<F>,
+}
 
-                    for bytecode in bytecodes.clone() {
-                        for row in bytecode.table_assignments(randomness) {
-                            for (column, value) in self.bytecode_table.iter().zip_eq(row) {
-                                region.assign_advice(
-                                    || format!("bytecode table row {}", offset),
-                                    *column,
-                                    offset,
-                                    || Ok(value),
-                                )?;
-                            }
-                            offset += 1;
-                        }
-                    }
-                    Ok(())
-                },
-            )
-        }
+impl<F> CopyCircuit<F> {
+    /// Build the circuit for the copy events of `block`
+    pub fn new(block: Block<F>) -> Self {
+        Self { block }
     }
+}
 
-    #[derive(Default)]
-    struct MyCircuit<F> {
-        block: Block<F>,
+impl<F: Field> CopyCircuit<F> {
+    /// Returns the instance column for a domain of `2^k` rows, which is
+    /// `randomness` in all the rows.
+    pub fn instance(k: u32, randomness: F) -> Vec<Vec<F>> {
+        vec![vec![randomness; (1 << k) - 64]]
     }
+}
+
+impl<F: Field> Circuit<F> for CopyCircuit<F> {
+    type Config = CopyCircuitTablesConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
 
-    impl<F> MyCircuit<F> {
-        pub fn new(block: Block<F>) -> Self {
-            Self { block }
+    fn without_witnesses(&self) -> Self {
+        Self {
+            block: self.block.without_witnesses(),
         }
     }
 
-    impl<F: Field> Circuit<F> for MyCircuit<F> {
-        type Config = MyConfig<F>;
-        type FloorPlanner = SimpleFloorPlanner;
-
-        fn without_witnesses(&self) -> Self {
-            Self::default()
-        }
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let tx_table = [(); 4].map(|_| meta.advice_column());
+        let rw_table = RwTable::construct(meta);
+        let bytecode_table = [(); 5].map(|_| meta.advice_column());
+        let randomness = {
+            let column = meta.instance_column();
+            let mut randomness = None;
+            meta.create_gate("", |meta| {
+                randomness = Some(meta.query_instance(column, Rotation::cur()));
+                [0.expr()]
+            });
+            randomness.unwrap()
+        };
+        let copy_table =
+            CopyCircuitConfig::configure(meta, randomness, &tx_table, &rw_table, &bytecode_table);
 
-        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            let tx_table = [(); 4].map(|_| meta.advice_column());
-            let rw_table = RwTable::construct(meta);
-            let bytecode_table = [(); 5].map(|_| meta.advice_column());
-            let copy_table = CopyCircuit::configure(meta, &tx_table, &rw_table, &bytecode_table);
-
-            MyConfig {
-                tx_table,
-                rw_table,
-                bytecode_table,
-                copy_table,
-            }
+        CopyCircuitTablesConfig {
+            tx_table,
+            rw_table,
+            bytecode_table,
+            copy_table,
         }
+    }
 
-        fn synthesize(
-            &self,
-            config: Self::Config,
-            mut layouter: impl Layouter<F>,
-        ) -> Result<(), halo2_proofs::plonk::Error> {
-            config.load_txs(&mut layouter, &self.block.txs, self.block.randomness)?;
-            config.load_rws(&mut layouter, &self.block.rws, self.block.randomness)?;
-            config.load_bytecodes(
-                &mut layouter,
-                self.block.bytecodes.values(),
-                self.block.randomness,
-            )?;
-            config.copy_table.assign_block(&mut layouter, &self.block)
-        }
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), halo2_proofs::plonk::Error> {
+        config.load_txs(&mut layouter, &self.block.txs, self.block.randomness)?;
+        config.load_rws(&mut layouter, &self.block.rws, self.block.randomness)?;
+        config.load_bytecodes(
+            &mut layouter,
+            self.block.bytecodes.values(),
+            self.block.randomness,
+        )?;
+        config.copy_table.assign_block(&mut layouter, &self.block)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use bus_mapping::{
+        circuit_input_builder::{CircuitInputBuilder, CopyDataType},
+        mock::BlockData,
+        operation::RWCounter,
+    };
+    use eth_types::{bytecode, geth_types::GethData, Field, Word};
+    use halo2_proofs::dev::{MockProver, VerifyFailure};
+    use mock::TestContext;
+    use rand::{prelude::SliceRandom, Rng};
+
+    use crate::evm_circuit::witness::{block_convert, Block};
+
+    use super::CopyCircuit;
 
     fn run_circuit<F: Field>(k: u32, block: Block<F>) -> Result<(), Vec<VerifyFailure>> {
-        let circuit = MyCircuit::<F>::new(block);
-        let prover = MockProver::<F>::run(k, &circuit, vec![]).unwrap();
+        let instance = CopyCircuit::instance(k, block.randomness);
+        let circuit = CopyCircuit::<F>::new(block);
+        let prover = MockProver::<F>::run(k, &circuit, instance).unwrap();
         prover.verify()
     }
 
@@ -859,6 +1009,22 @@ mod tests {
         builder
     }
 
+    fn gen_sha3_data() -> CircuitInputBuilder {
+        let code = bytecode! {
+            PUSH32(Word::from(0x20))
+            PUSH32(Word::from(0x00))
+            SHA3
+            STOP
+        };
+        let test_ctx = TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap();
+        let block: GethData = test_ctx.into();
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+        builder
+    }
+
     #[test]
     fn copy_circuit_valid_calldatacopy() {
         let builder = gen_calldatacopy_data();
@@ -873,6 +1039,21 @@ mod tests {
         assert!(run_circuit(10, block).is_ok());
     }
 
+    #[test]
+    fn copy_circuit_valid_sha3() {
+        let builder = gen_sha3_data();
+        let block = block_convert(&builder.block, &builder.code_db);
+        assert!(run_circuit(10, block).is_ok());
+    }
+
+    #[test]
+    fn copy_circuit_valid_padded() {
+        let builder = gen_sha3_data();
+        let mut block = block_convert(&builder.block, &builder.code_db);
+        block.copy_circuit_pad_to = 512;
+        assert!(run_circuit(10, block).is_ok());
+    }
+
     fn perturb_tag(block: &mut bus_mapping::circuit_input_builder::Block, tag: CopyDataType) {
         debug_assert!(!block.copy_events.is_empty());
         debug_assert!(!block.copy_events[0].steps.is_empty());
@@ -915,4 +1096,15 @@ mod tests {
         let block = block_convert(&builder.block, &builder.code_db);
         assert!(run_circuit(10, block).is_err());
     }
+
+    #[test]
+    fn copy_circuit_invalid_sha3() {
+        let mut builder = gen_sha3_data();
+        match rand::thread_rng().gen_bool(0.5) {
+            true => perturb_tag(&mut builder.block, CopyDataType::Memory),
+            false => perturb_tag(&mut builder.block, CopyDataType::RlcAcc),
+        }
+        let block = block_convert(&builder.block, &builder.code_db);
+        assert!(run_circuit(10, block).is_err());
+    }
 }
@@ -1,7 +1,11 @@
 //! The EVM circuit implementation.
 
 #![allow(missing_docs)]
-use halo2_proofs::{circuit::Layouter, plonk::*};
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::*,
+    poly::Rotation,
+};
 
 mod execution;
 pub mod param;
@@ -11,22 +15,30 @@ pub(crate) mod util;
 pub mod table;
 pub mod witness;
 
+use crate::{
+    copy_circuit::CopyCircuitConfig,
+    exp_circuit::ExpCircuit,
+    keccak_circuit::{KeccakConfig, KeccakTable},
+    rw_table::RwTable,
+    util::Expr,
+};
 use eth_types::Field;
 use execution::ExecutionConfig;
 use itertools::Itertools;
+use param::MAX_STEP_HEIGHT;
 use table::{FixedTableTag, LookupTable};
-use witness::Block;
+use witness::{Block, BlockContext, Bytecode, RwMap, Transaction};
 
-/// EvmCircuit implements verification of execution trace of a block.
+/// EvmCircuitConfig implements verification of execution trace of a block.
 #[derive(Clone, Debug)]
-pub struct EvmCircuit<F> {
+pub struct EvmCircuitConfig<F> {
     fixed_table: [Column<Fixed>; 4],
     byte_table: [Column<Fixed>; 1],
     execution: Box<ExecutionConfig<F>>,
 }
 
-impl<F: Field> EvmCircuit<F> {
-    /// Configure EvmCircuit
+impl<F: Field> EvmCircuitConfig<F> {
+    /// Configure EvmCircuitConfig
     pub fn configure(
         meta: &mut ConstraintSystem<F>,
         power_of_randomness: [Expression<F>; 31],
@@ -35,6 +47,8 @@ impl<F: Field> EvmCircuit<F> {
         bytecode_table: &dyn LookupTable<F>,
         block_table: &dyn LookupTable<F>,
         copy_table: &dyn LookupTable<F>,
+        exp_table: &dyn LookupTable<F>,
+        keccak_table: &dyn LookupTable<F>,
     ) -> Self {
         let fixed_table = [(); 4].map(|_| meta.fixed_column());
         let byte_table = [(); 1].map(|_| meta.fixed_column());
@@ -48,6 +62,8 @@ impl<F: Field> EvmCircuit<F> {
             bytecode_table,
             block_table,
             copy_table,
+            exp_table,
+            keccak_table,
         ));
 
         Self {
@@ -138,30 +154,338 @@ impl<F: Field> EvmCircuit<F> {
                 num_rows += self.execution.get_step_height(step.execution_state);
             }
         }
+        // The last padding step starts at most `MAX_STEP_HEIGHT` rows after the
+        // padding, and is followed by the rows of its next step
+        if block.evm_circuit_pad_to > 0 {
+            num_rows = num_rows.max(block.evm_circuit_pad_to + 2 * MAX_STEP_HEIGHT);
+        }
         num_rows
     }
 }
 
+/// Config of the `EvmCircuit`: the `EvmCircuitConfig` and its lookup tables
+#[derive(Clone)]
+pub struct EvmCircuitTablesConfig<F> {
+    tx_table: [Column<Advice>; 4],
+    rw_table: RwTable,
+    bytecode_table: [Column<Advice>; 5],
+    block_table: [Column<Advice>; 3],
+    copy_table: CopyCircuitConfig<F>,
+    exp_table: ExpCircuit<F>,
+    keccak_table: KeccakTable,
+    evm_circuit: EvmCircuitConfig<F>,
+}
+
+impl<F: Field> EvmCircuitTablesConfig<F> {
+    /// Configure the `EvmCircuitConfig` and its lookup tables, and return the
+    /// powers of randomness of the instance columns.
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> (Self, [Expression<F>; 31]) {
+        let tx_table = [(); 4].map(|_| meta.advice_column());
+        let rw_table = RwTable::construct(meta);
+        let bytecode_table = [(); 5].map(|_| meta.advice_column());
+        let block_table = [(); 3].map(|_| meta.advice_column());
+
+        // This gate is used just to get the array of expressions from the power of
+        // randomness instance column, so that later on we don't need to query
+        // columns everywhere, and can pass the power of randomness array
+        // expression everywhere.  The gate itself doesn't add any constraints.
+        let power_of_randomness = {
+            let columns = [(); 31].map(|_| meta.instance_column());
+            let mut power_of_randomness = None;
+
+            meta.create_gate("", |meta| {
+                power_of_randomness =
+                    Some(columns.map(|column| meta.query_instance(column, Rotation::cur())));
+
+                [0.expr()]
+            });
+
+            power_of_randomness.unwrap()
+        };
+
+        let copy_table = CopyCircuitConfig::configure(
+            meta,
+            power_of_randomness[0].clone(),
+            &tx_table,
+            &rw_table,
+            &bytecode_table,
+        );
+        let exp_table = ExpCircuit::configure(meta);
+        let keccak_table = KeccakTable::construct(meta);
+
+        let evm_circuit = EvmCircuitConfig::configure(
+            meta,
+            power_of_randomness.clone(),
+            &tx_table,
+            &rw_table,
+            &bytecode_table,
+            &block_table,
+            &copy_table,
+            &exp_table,
+            &keccak_table,
+        );
+
+        (
+            Self {
+                tx_table,
+                rw_table,
+                bytecode_table,
+                block_table,
+                copy_table,
+                exp_table,
+                keccak_table,
+                evm_circuit,
+            },
+            power_of_randomness,
+        )
+    }
+
+    /// Load the fixed and byte tables for `fixed_table_tags` and the lookup
+    /// tables of `block`, except for the keccak table.
+    pub fn load(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        block: &Block<F>,
+        fixed_table_tags: Vec<FixedTableTag>,
+    ) -> Result<(), Error> {
+        self.evm_circuit
+            .load_fixed_table(layouter, fixed_table_tags)?;
+        self.evm_circuit.load_byte_table(layouter)?;
+        self.load_txs(layouter, &block.txs, block.randomness)?;
+        self.load_rws(layouter, &block.rws, block.randomness)?;
+        self.load_bytecodes(layouter, block.bytecodes.values(), block.randomness)?;
+        self.load_block(layouter, &block.context, block.randomness)?;
+        self.copy_table.assign_block(layouter, block)?;
+        self.exp_table.assign_block(layouter, block)
+    }
+
+    fn load_txs(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        txs: &[Transaction],
+        randomness: F,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "tx table",
+            |mut region| {
+                let mut offset = 0;
+                for column in self.tx_table {
+                    region.assign_advice(
+                        || "tx table all-zero row",
+                        column,
+                        offset,
+                        || Ok(F::zero()),
+                    )?;
+                }
+                offset += 1;
+
+                for tx in txs.iter() {
+                    for row in tx.table_assignments(randomness) {
+                        for (column, value) in self.tx_table.iter().zip_eq(row) {
+                            region.assign_advice(
+                                || format!("tx table row {}", offset),
+                                *column,
+                                offset,
+                                || Ok(value),
+                            )?;
+                        }
+                        offset += 1;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn load_rws(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        rws: &RwMap,
+        randomness: F,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "rw table",
+            |mut region| {
+                let mut offset = 0;
+                self.rw_table
+                    .assign(&mut region, offset, &Default::default())?;
+                offset += 1;
+
+                let mut rows = rws
+                    .0
+                    .values()
+                    .flat_map(|rws| rws.iter())
+                    .collect::<Vec<_>>();
+
+                rows.sort_by_key(|a| a.rw_counter());
+                let mut expected_rw_counter = 1;
+                for rw in rows {
+                    assert!(rw.rw_counter() == expected_rw_counter);
+                    expected_rw_counter += 1;
+
+                    self.rw_table
+                        .assign(&mut region, offset, &rw.table_assignment(randomness))?;
+                    offset += 1;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn load_bytecodes<'a>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        bytecodes: impl IntoIterator<Item = &'a Bytecode> + Clone,
+        randomness: F,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "bytecode table",
+            |mut region| {
+                let mut offset = 0;
+                for column in self.bytecode_table {
+                    region.assign_advice(
+                        || "bytecode table all-zero row",
+                        column,
+                        offset,
+                        || Ok(F::zero()),
+                    )?;
+                }
+                offset += 1;
+
+                for bytecode in bytecodes.clone() {
+                    for row in bytecode.table_assignments(randomness) {
+                        for (column, value) in self.bytecode_table.iter().zip_eq(row) {
+                            region.assign_advice(
+                                || format!("bytecode table row {}", offset),
+                                *column,
+                                offset,
+                                || Ok(value),
+                            )?;
+                        }
+                        offset += 1;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn load_block(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        block: &BlockContext,
+        randomness: F,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "block table",
+            |mut region| {
+                let mut offset = 0;
+                for column in self.block_table {
+                    region.assign_advice(
+                        || "block table all-zero row",
+                        column,
+                        offset,
+                        || Ok(F::zero()),
+                    )?;
+                }
+                offset += 1;
+
+                for row in block.table_assignments(randomness) {
+                    for (column, value) in self.block_table.iter().zip_eq(row) {
+                        region.assign_advice(
+                            || format!("block table row {}", offset),
+                            *column,
+                            offset,
+                            || Ok(value),
+                        )?;
+                    }
+                    offset += 1;
+                }
+
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Evm circuit with its own lookup tables, which are loaded from the witness
+/// except for the keccak table that the keccak circuit proves, to prove the
+/// circuit on its own in a domain of `size` rows.
+#[derive(Default)]
+pub struct EvmCircuit<F> {
+    block: Block<F>,
+    fixed_table_tags: Vec<FixedTableTag>,
+    size: usize,
+}
+
+impl<F: Field> EvmCircuit<F> {
+    pub fn new(block: Block<F>, fixed_table_tags: Vec<FixedTableTag>, size: usize) -> Self {
+        Self {
+            block,
+            fixed_table_tags,
+            size,
+        }
+    }
+
+    pub fn get_num_rows_required(block: &Block<F>) -> usize {
+        let mut cs = ConstraintSystem::default();
+        let (config, _) = EvmCircuitTablesConfig::configure(&mut cs);
+        config.evm_circuit.get_num_rows_required(block)
+    }
+
+    pub fn get_active_rows(block: &Block<F>) -> (Vec<usize>, Vec<usize>) {
+        let mut cs = ConstraintSystem::default();
+        let (config, _) = EvmCircuitTablesConfig::configure(&mut cs);
+        config.evm_circuit.get_active_rows(block)
+    }
+}
+
+impl<F: Field> Circuit<F> for EvmCircuit<F> {
+    type Config = (EvmCircuitTablesConfig<F>, KeccakConfig<F>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            block: self.block.without_witnesses(),
+            fixed_table_tags: self.fixed_table_tags.clone(),
+            size: self.size,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let (config, power_of_randomness) = EvmCircuitTablesConfig::configure(meta);
+        let keccak_circuit =
+            KeccakConfig::configure(meta, power_of_randomness, config.keccak_table);
+        (config, keccak_circuit)
+    }
+
+    fn synthesize(
+        &self,
+        (config, keccak_circuit): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load(&mut layouter, &self.block, self.fixed_table_tags.clone())?;
+        keccak_circuit.assign(
+            &mut layouter,
+            self.size,
+            &self.block.sha3_inputs,
+            self.block.randomness,
+        )?;
+        config.evm_circuit.assign_block(&mut layouter, &self.block)
+    }
+}
+
 #[cfg(any(feature = "test", test))]
 pub mod test {
-    use crate::{
-        copy_circuit::CopyCircuit,
-        evm_circuit::{
-            table::FixedTableTag,
-            witness::{Block, BlockContext, Bytecode, RwMap, Transaction},
-            EvmCircuit,
-        },
-        rw_table::RwTable,
-        util::Expr,
+    use crate::evm_circuit::{
+        table::FixedTableTag, witness::Block, EvmCircuit, EvmCircuitTablesConfig,
     };
     use eth_types::{Field, Word};
     use halo2_proofs::{
         circuit::{Layouter, SimpleFloorPlanner},
         dev::{MockProver, VerifyFailure},
-        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
-        poly::Rotation,
+        plonk::{Circuit, ConstraintSystem, Error},
     };
-    use itertools::Itertools;
     use rand::{
         distributions::uniform::{SampleRange, SampleUniform},
         random, thread_rng, Rng,
@@ -188,169 +512,8 @@ pub mod test {
         Word::from_big_endian(&rand_bytes_array::<32>())
     }
 
-    #[derive(Clone)]
-    pub struct TestCircuitConfig<F> {
-        tx_table: [Column<Advice>; 4],
-        rw_table: RwTable,
-        bytecode_table: [Column<Advice>; 5],
-        block_table: [Column<Advice>; 3],
-        copy_table: CopyCircuit<F>,
-        evm_circuit: EvmCircuit<F>,
-    }
-
-    impl<F: Field> TestCircuitConfig<F> {
-        fn load_txs(
-            &self,
-            layouter: &mut impl Layouter<F>,
-            txs: &[Transaction],
-            randomness: F,
-        ) -> Result<(), Error> {
-            layouter.assign_region(
-                || "tx table",
-                |mut region| {
-                    let mut offset = 0;
-                    for column in self.tx_table {
-                        region.assign_advice(
-                            || "tx table all-zero row",
-                            column,
-                            offset,
-                            || Ok(F::zero()),
-                        )?;
-                    }
-                    offset += 1;
-
-                    for tx in txs.iter() {
-                        for row in tx.table_assignments(randomness) {
-                            for (column, value) in self.tx_table.iter().zip_eq(row) {
-                                region.assign_advice(
-                                    || format!("tx table row {}", offset),
-                                    *column,
-                                    offset,
-                                    || Ok(value),
-                                )?;
-                            }
-                            offset += 1;
-                        }
-                    }
-                    Ok(())
-                },
-            )
-        }
-
-        fn load_rws(
-            &self,
-            layouter: &mut impl Layouter<F>,
-            rws: &RwMap,
-            randomness: F,
-        ) -> Result<(), Error> {
-            layouter.assign_region(
-                || "rw table",
-                |mut region| {
-                    let mut offset = 0;
-                    self.rw_table
-                        .assign(&mut region, offset, &Default::default())?;
-                    offset += 1;
-
-                    let mut rows = rws
-                        .0
-                        .values()
-                        .flat_map(|rws| rws.iter())
-                        .collect::<Vec<_>>();
-
-                    rows.sort_by_key(|a| a.rw_counter());
-                    let mut expected_rw_counter = 1;
-                    for rw in rows {
-                        assert!(rw.rw_counter() == expected_rw_counter);
-                        expected_rw_counter += 1;
-
-                        self.rw_table.assign(
-                            &mut region,
-                            offset,
-                            &rw.table_assignment(randomness),
-                        )?;
-                        offset += 1;
-                    }
-                    Ok(())
-                },
-            )
-        }
-
-        fn load_bytecodes<'a>(
-            &self,
-            layouter: &mut impl Layouter<F>,
-            bytecodes: impl IntoIterator<Item = &'a Bytecode> + Clone,
-            randomness: F,
-        ) -> Result<(), Error> {
-            layouter.assign_region(
-                || "bytecode table",
-                |mut region| {
-                    let mut offset = 0;
-                    for column in self.bytecode_table {
-                        region.assign_advice(
-                            || "bytecode table all-zero row",
-                            column,
-                            offset,
-                            || Ok(F::zero()),
-                        )?;
-                    }
-                    offset += 1;
-
-                    for bytecode in bytecodes.clone() {
-                        for row in bytecode.table_assignments(randomness) {
-                            for (column, value) in self.bytecode_table.iter().zip_eq(row) {
-                                region.assign_advice(
-                                    || format!("bytecode table row {}", offset),
-                                    *column,
-                                    offset,
-                                    || Ok(value),
-                                )?;
-                            }
-                            offset += 1;
-                        }
-                    }
-                    Ok(())
-                },
-            )
-        }
-
-        fn load_block(
-            &self,
-            layouter: &mut impl Layouter<F>,
-            block: &BlockContext,
-            randomness: F,
-        ) -> Result<(), Error> {
-            layouter.assign_region(
-                || "block table",
-                |mut region| {
-                    let mut offset = 0;
-                    for column in self.block_table {
-                        region.assign_advice(
-                            || "block table all-zero row",
-                            column,
-                            offset,
-                            || Ok(F::zero()),
-                        )?;
-                    }
-                    offset += 1;
-
-                    for row in block.table_assignments(randomness) {
-                        for (column, value) in self.block_table.iter().zip_eq(row) {
-                            region.assign_advice(
-                                || format!("block table row {}", offset),
-                                *column,
-                                offset,
-                                || Ok(value),
-                            )?;
-                        }
-                        offset += 1;
-                    }
-
-                    Ok(())
-                },
-            )
-        }
-    }
-
+    /// Evm circuit that loads the keccak table from the witness, which avoids
+    /// the columns of the keccak circuit in the unit tests.
     #[derive(Default)]
     pub struct TestCircuit<F> {
         block: Block<F>,
@@ -367,7 +530,7 @@ pub mod test {
     }
 
     impl<F: Field> Circuit<F> for TestCircuit<F> {
-        type Config = TestCircuitConfig<F>;
+        type Config = EvmCircuitTablesConfig<F>;
         type FloorPlanner = SimpleFloorPlanner;
 
         fn without_witnesses(&self) -> Self {
@@ -375,46 +538,7 @@ pub mod test {
         }
 
         fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            let tx_table = [(); 4].map(|_| meta.advice_column());
-            let rw_table = RwTable::construct(meta);
-            let bytecode_table = [(); 5].map(|_| meta.advice_column());
-            let block_table = [(); 3].map(|_| meta.advice_column());
-            let copy_table = CopyCircuit::configure(meta, &tx_table, &rw_table, &bytecode_table);
-
-            // This gate is used just to get the array of expressions from the power of
-            // randomness instance column, so that later on we don't need to query
-            // columns everywhere, and can pass the power of randomness array
-            // expression everywhere.  The gate itself doesn't add any constraints.
-            let power_of_randomness = {
-                let columns = [(); 31].map(|_| meta.instance_column());
-                let mut power_of_randomness = None;
-
-                meta.create_gate("", |meta| {
-                    power_of_randomness =
-                        Some(columns.map(|column| meta.query_instance(column, Rotation::cur())));
-
-                    [0.expr()]
-                });
-
-                power_of_randomness.unwrap()
-            };
-
-            Self::Config {
-                tx_table,
-                rw_table,
-                bytecode_table,
-                block_table,
-                copy_table,
-                evm_circuit: EvmCircuit::configure(
-                    meta,
-                    power_of_randomness,
-                    &tx_table,
-                    &rw_table,
-                    &bytecode_table,
-                    &block_table,
-                    &copy_table,
-                ),
-            }
+            EvmCircuitTablesConfig::configure(meta).0
         }
 
         fn synthesize(
@@ -422,36 +546,13 @@ pub mod test {
             config: Self::Config,
             mut layouter: impl Layouter<F>,
         ) -> Result<(), Error> {
-            config
-                .evm_circuit
-                .load_fixed_table(&mut layouter, self.fixed_table_tags.clone())?;
-            config.evm_circuit.load_byte_table(&mut layouter)?;
-            config.load_txs(&mut layouter, &self.block.txs, self.block.randomness)?;
-            config.load_rws(&mut layouter, &self.block.rws, self.block.randomness)?;
-            config.load_bytecodes(
+            config.load(&mut layouter, &self.block, self.fixed_table_tags.clone())?;
+            config.keccak_table.dev_load(
                 &mut layouter,
-                self.block.bytecodes.values(),
+                &self.block.sha3_inputs,
                 self.block.randomness,
             )?;
-            config.load_block(&mut layouter, &self.block.context, self.block.randomness)?;
-            config.copy_table.assign_block(&mut layouter, &self.block)?;
-            config
-                .evm_circuit
-                .assign_block_exact(&mut layouter, &self.block)
-        }
-    }
-
-    impl<F: Field> TestCircuit<F> {
-        pub fn get_num_rows_required(block: &Block<F>) -> usize {
-            let mut cs = ConstraintSystem::default();
-            let config = TestCircuit::configure(&mut cs);
-            config.evm_circuit.get_num_rows_required(block)
-        }
-
-        pub fn get_active_rows(block: &Block<F>) -> (Vec<usize>, Vec<usize>) {
-            let mut cs = ConstraintSystem::default();
-            let config = TestCircuit::configure(&mut cs);
-            config.evm_circuit.get_active_rows(block)
+            config.evm_circuit.assign_block(&mut layouter, &self.block)
         }
     }
 
@@ -461,7 +562,7 @@ pub mod test {
     ) -> Result<(), Vec<VerifyFailure>> {
         let log2_ceil = |n| u32::BITS - (n as u32).leading_zeros() - (n & (n - 1) == 0) as u32;
 
-        let num_rows_required_for_steps = TestCircuit::get_num_rows_required(&block);
+        let num_rows_required_for_steps = EvmCircuit::get_num_rows_required(&block);
 
         let k = log2_ceil(
             64 + fixed_table_tags
@@ -476,13 +577,27 @@ pub mod test {
                 .map(|bytecode| bytecode.bytes.len())
                 .sum::<usize>(),
         ));
+        let k = k.max(log2_ceil(
+            64 + block
+                .exp_events
+                .iter()
+                .map(|exp_event| exp_event.steps.len())
+                .sum::<usize>(),
+        ));
+        let k = k.max(log2_ceil(
+            64 + block
+                .copy_events
+                .values()
+                .map(|copy_event| copy_event.steps.len())
+                .sum::<usize>(),
+        ));
         let k = k.max(log2_ceil(64 + num_rows_required_for_steps));
         log::debug!("evm circuit uses k = {}", k);
 
         let power_of_randomness = (1..32)
             .map(|exp| vec![block.randomness.pow(&[exp, 0, 0, 0]); (1 << k) - 64])
             .collect();
-        let (active_gate_rows, active_lookup_rows) = TestCircuit::get_active_rows(&block);
+        let (active_gate_rows, active_lookup_rows) = EvmCircuit::get_active_rows(&block);
         let circuit = TestCircuit::<F>::new(block, fixed_table_tags);
         let prover = MockProver::<F>::run(k, &circuit, power_of_randomness).unwrap();
         prover.verify_at_rows(active_gate_rows.into_iter(), active_lookup_rows.into_iter())
@@ -505,6 +620,7 @@ pub mod test {
                 FixedTableTag::SignByte,
                 FixedTableTag::ResponsibleOpcode,
                 FixedTableTag::Pow2,
+                FixedTableTag::ConstantGasCost,
             ],
         )
     }
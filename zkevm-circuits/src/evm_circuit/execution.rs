@@ -24,9 +24,11 @@ use strum::IntoEnumIterator;
 
 mod add_sub;
 mod addmod;
+mod balance;
 mod begin_tx;
 mod bitwise;
 mod block_ctx;
+mod blockhash;
 mod byte;
 mod call;
 mod calldatacopy;
@@ -38,12 +40,38 @@ mod chainid;
 mod codecopy;
 mod codesize;
 mod comparator;
+mod create;
 mod dummy;
 mod dup;
 mod end_block;
 mod end_tx;
+mod error_invalid_creation_code;
+mod error_invalid_jump;
+mod error_invalid_opcode;
+mod error_max_code_size_exceeded;
+mod error_oog_account_access;
+mod error_oog_call;
+mod error_oog_code_store;
+mod error_oog_constant;
+mod error_oog_create2;
+mod error_oog_dynamic_memory;
+mod error_oog_exp;
+mod error_oog_extcodecopy;
+mod error_oog_log;
+mod error_oog_memory_copy;
+mod error_oog_selfdestruct;
+mod error_oog_sha3;
+mod error_oog_sload;
+mod error_oog_sstore;
 mod error_oog_static_memory;
+mod error_precheck;
+mod error_return_data_out_of_bound;
+mod error_stack;
+mod error_write_protection;
+mod exp;
+mod extcodecopy;
 mod extcodehash;
+mod extcodesize;
 mod gas;
 mod gasprice;
 mod is_zero;
@@ -61,8 +89,15 @@ mod pc;
 mod pop;
 mod push;
 mod r#return;
+mod returndatacopy;
+mod returndatasize;
+mod revert;
+mod sar;
 mod sdiv_smod;
 mod selfbalance;
+mod selfdestruct;
+mod sha3;
+mod shl;
 mod shr;
 mod signed_comparator;
 mod signextend;
@@ -73,9 +108,11 @@ mod swap;
 
 use add_sub::AddSubGadget;
 use addmod::AddModGadget;
+use balance::BalanceGadget;
 use begin_tx::BeginTxGadget;
 use bitwise::BitwiseGadget;
 use block_ctx::{BlockCtxU160Gadget, BlockCtxU256Gadget, BlockCtxU64Gadget};
+use blockhash::BlockHashGadget;
 use byte::ByteGadget;
 use call::CallGadget;
 use calldatacopy::CallDataCopyGadget;
@@ -87,12 +124,38 @@ use chainid::ChainIdGadget;
 use codecopy::CodeCopyGadget;
 use codesize::CodesizeGadget;
 use comparator::ComparatorGadget;
+use create::CreateGadget;
 use dummy::DummyGadget;
 use dup::DupGadget;
 use end_block::EndBlockGadget;
 use end_tx::EndTxGadget;
+use error_invalid_creation_code::ErrorInvalidCreationCodeGadget;
+use error_invalid_jump::ErrorInvalidJumpGadget;
+use error_invalid_opcode::ErrorInvalidOpcodeGadget;
+use error_max_code_size_exceeded::ErrorMaxCodeSizeExceededGadget;
+use error_oog_account_access::ErrorOOGAccountAccessGadget;
+use error_oog_call::ErrorOOGCallGadget;
+use error_oog_code_store::ErrorOOGCodeStoreGadget;
+use error_oog_constant::ErrorOOGConstantGadget;
+use error_oog_create2::ErrorOOGCreate2Gadget;
+use error_oog_dynamic_memory::ErrorOOGDynamicMemoryGadget;
+use error_oog_exp::ErrorOOGExpGadget;
+use error_oog_extcodecopy::ErrorOOGExtcodecopyGadget;
+use error_oog_log::ErrorOOGLogGadget;
+use error_oog_memory_copy::ErrorOOGMemoryCopyGadget;
+use error_oog_selfdestruct::ErrorOOGSelfdestructGadget;
+use error_oog_sha3::ErrorOOGSha3Gadget;
+use error_oog_sload::ErrorOOGSloadGadget;
+use error_oog_sstore::ErrorOOGSstoreGadget;
 use error_oog_static_memory::ErrorOOGStaticMemoryGadget;
+use error_precheck::ErrorPrecheckGadget;
+use error_return_data_out_of_bound::ErrorReturnDataOutOfBoundGadget;
+use error_stack::ErrorStackGadget;
+use error_write_protection::ErrorWriteProtectionGadget;
+use exp::ExpGadget;
+use extcodecopy::ExtcodecopyGadget;
 use extcodehash::ExtcodehashGadget;
+use extcodesize::ExtcodesizeGadget;
 use gas::GasGadget;
 use gasprice::GasPriceGadget;
 use is_zero::IsZeroGadget;
@@ -110,8 +173,15 @@ use pc::PcGadget;
 use pop::PopGadget;
 use push::PushGadget;
 use r#return::ReturnGadget;
+use returndatacopy::ReturnDataCopyGadget;
+use returndatasize::ReturnDataSizeGadget;
+use revert::RevertGadget;
+use sar::SarGadget;
 use sdiv_smod::SignedDivModGadget;
 use selfbalance::SelfbalanceGadget;
+use selfdestruct::SelfdestructGadget;
+use sha3::Sha3Gadget;
+use shl::ShlGadget;
 use shr::ShrGadget;
 use signed_comparator::SignedComparatorGadget;
 use signextend::SignextendGadget;
@@ -157,7 +227,9 @@ pub(crate) struct ExecutionConfig<F> {
     // opcode gadgets
     add_sub_gadget: AddSubGadget<F>,
     addmod_gadget: AddModGadget<F>,
+    balance_gadget: BalanceGadget<F>,
     bitwise_gadget: BitwiseGadget<F>,
+    blockhash_gadget: BlockHashGadget<F>,
     byte_gadget: ByteGadget<F>,
     call_gadget: CallGadget<F>,
     call_value_gadget: CallValueGadget<F>,
@@ -169,8 +241,13 @@ pub(crate) struct ExecutionConfig<F> {
     codecopy_gadget: CodeCopyGadget<F>,
     codesize_gadget: CodesizeGadget<F>,
     comparator_gadget: ComparatorGadget<F>,
+    create_gadget: CreateGadget<F, false, { ExecutionState::CREATE }>,
+    create2_gadget: CreateGadget<F, true, { ExecutionState::CREATE2 }>,
     dup_gadget: DupGadget<F>,
+    exp_gadget: ExpGadget<F>,
+    extcodecopy_gadget: ExtcodecopyGadget<F>,
     extcodehash_gadget: ExtcodehashGadget<F>,
+    extcodesize_gadget: ExtcodesizeGadget<F>,
     gas_gadget: GasGadget<F>,
     gasprice_gadget: GasPriceGadget<F>,
     iszero_gadget: IsZeroGadget<F>,
@@ -188,26 +265,17 @@ pub(crate) struct ExecutionConfig<F> {
     pop_gadget: PopGadget<F>,
     push_gadget: PushGadget<F>,
     return_gadget: ReturnGadget<F>,
+    returndatacopy_gadget: ReturnDataCopyGadget<F>,
+    returndatasize_gadget: ReturnDataSizeGadget<F>,
+    revert_gadget: RevertGadget<F>,
     sdiv_smod_gadget: SignedDivModGadget<F>,
     selfbalance_gadget: SelfbalanceGadget<F>,
+    sar_gadget: SarGadget<F>,
+    shl_gadget: ShlGadget<F>,
     shr_gadget: ShrGadget<F>,
-    sha3_gadget: DummyGadget<F, 2, 1, { ExecutionState::SHA3 }>,
+    sha3_gadget: Sha3Gadget<F>,
+    selfdestruct_gadget: SelfdestructGadget<F>,
     address_gadget: DummyGadget<F, 0, 1, { ExecutionState::ADDRESS }>,
-    balance_gadget: DummyGadget<F, 1, 1, { ExecutionState::BALANCE }>,
-    blockhash_gadget: DummyGadget<F, 1, 1, { ExecutionState::BLOCKHASH }>,
-    exp_gadget: DummyGadget<F, 2, 1, { ExecutionState::EXP }>,
-    shl_gadget: DummyGadget<F, 2, 1, { ExecutionState::SHL }>,
-    sar_gadget: DummyGadget<F, 2, 1, { ExecutionState::SAR }>,
-    extcodesize_gadget: DummyGadget<F, 1, 1, { ExecutionState::EXTCODESIZE }>,
-    extcodecopy_gadget: DummyGadget<F, 4, 0, { ExecutionState::EXTCODECOPY }>,
-    returndatasize_gadget: DummyGadget<F, 0, 1, { ExecutionState::RETURNDATASIZE }>,
-    returndatacopy_gadget: DummyGadget<F, 3, 0, { ExecutionState::RETURNDATACOPY }>,
-    create_gadget: DummyGadget<F, 3, 1, { ExecutionState::CREATE }>,
-    callcode_gadget: DummyGadget<F, 7, 1, { ExecutionState::CALLCODE }>,
-    delegatecall_gadget: DummyGadget<F, 6, 1, { ExecutionState::DELEGATECALL }>,
-    create2_gadget: DummyGadget<F, 4, 1, { ExecutionState::CREATE2 }>,
-    staticcall_gadget: DummyGadget<F, 6, 1, { ExecutionState::STATICCALL }>,
-    selfdestruct_gadget: DummyGadget<F, 1, 0, { ExecutionState::SELFDESTRUCT }>,
     signed_comparator_gadget: SignedComparatorGadget<F>,
     signextend_gadget: SignextendGadget<F>,
     sload_gadget: SloadGadget<F>,
@@ -218,7 +286,37 @@ pub(crate) struct ExecutionConfig<F> {
     block_ctx_u160_gadget: BlockCtxU160Gadget<F>,
     block_ctx_u256_gadget: BlockCtxU256Gadget<F>,
     // error gadgets
+    error_depth_gadget: ErrorPrecheckGadget<F, { ExecutionState::ErrorDepth }>,
+    error_insufficient_balance_gadget:
+        ErrorPrecheckGadget<F, { ExecutionState::ErrorInsufficientBalance }>,
+    error_invalid_creation_code_gadget: ErrorInvalidCreationCodeGadget<F>,
+    error_invalid_jump_gadget: ErrorInvalidJumpGadget<F>,
+    error_invalid_opcode_gadget: ErrorInvalidOpcodeGadget<F>,
+    error_max_code_size_exceeded_gadget: ErrorMaxCodeSizeExceededGadget<F>,
+    error_oog_account_access_gadget: ErrorOOGAccountAccessGadget<F>,
+    error_oog_call_gadget: ErrorOOGCallGadget<F, { ExecutionState::ErrorOutOfGasCALL }>,
+    error_oog_callcode_gadget: ErrorOOGCallGadget<F, { ExecutionState::ErrorOutOfGasCALLCODE }>,
+    error_oog_delegatecall_gadget:
+        ErrorOOGCallGadget<F, { ExecutionState::ErrorOutOfGasDELEGATECALL }>,
+    error_oog_staticcall_gadget: ErrorOOGCallGadget<F, { ExecutionState::ErrorOutOfGasSTATICCALL }>,
+    error_oog_code_store_gadget: ErrorOOGCodeStoreGadget<F>,
+    error_oog_constant_gadget: ErrorOOGConstantGadget<F>,
+    error_oog_create2_gadget: ErrorOOGCreate2Gadget<F>,
+    error_oog_dynamic_memory_gadget: ErrorOOGDynamicMemoryGadget<F>,
+    error_oog_exp_gadget: ErrorOOGExpGadget<F>,
+    error_oog_extcodecopy_gadget: ErrorOOGExtcodecopyGadget<F>,
+    error_oog_log_gadget: ErrorOOGLogGadget<F>,
+    error_oog_memory_copy_gadget: ErrorOOGMemoryCopyGadget<F>,
+    error_oog_selfdestruct_gadget: ErrorOOGSelfdestructGadget<F>,
+    error_oog_sha3_gadget: ErrorOOGSha3Gadget<F>,
+    error_oog_sload_gadget: ErrorOOGSloadGadget<F>,
+    error_oog_sstore_gadget: ErrorOOGSstoreGadget<F>,
     error_oog_static_memory_gadget: ErrorOOGStaticMemoryGadget<F>,
+    error_return_data_out_of_bound_gadget: ErrorReturnDataOutOfBoundGadget<F>,
+    error_stack_overflow_gadget: ErrorStackGadget<F, true, { ExecutionState::ErrorStackOverflow }>,
+    error_stack_underflow_gadget:
+        ErrorStackGadget<F, false, { ExecutionState::ErrorStackUnderflow }>,
+    error_write_protection_gadget: ErrorWriteProtectionGadget<F>,
 }
 
 impl<F: Field> ExecutionConfig<F> {
@@ -233,6 +331,8 @@ impl<F: Field> ExecutionConfig<F> {
         bytecode_table: &dyn LookupTable<F>,
         block_table: &dyn LookupTable<F>,
         copy_table: &dyn LookupTable<F>,
+        exp_table: &dyn LookupTable<F>,
+        keccak_table: &dyn LookupTable<F>,
     ) -> Self {
         let q_usable = meta.complex_selector();
         let q_step = meta.advice_column();
@@ -368,7 +468,9 @@ impl<F: Field> ExecutionConfig<F> {
             // opcode gadgets
             add_sub_gadget: configure_gadget!(),
             addmod_gadget: configure_gadget!(),
+            balance_gadget: configure_gadget!(),
             bitwise_gadget: configure_gadget!(),
+            blockhash_gadget: configure_gadget!(),
             byte_gadget: configure_gadget!(),
             call_gadget: configure_gadget!(),
             call_value_gadget: configure_gadget!(),
@@ -380,8 +482,13 @@ impl<F: Field> ExecutionConfig<F> {
             codecopy_gadget: configure_gadget!(),
             codesize_gadget: configure_gadget!(),
             comparator_gadget: configure_gadget!(),
+            create_gadget: configure_gadget!(),
+            create2_gadget: configure_gadget!(),
             dup_gadget: configure_gadget!(),
+            exp_gadget: configure_gadget!(),
+            extcodecopy_gadget: configure_gadget!(),
             extcodehash_gadget: configure_gadget!(),
+            extcodesize_gadget: configure_gadget!(),
             gas_gadget: configure_gadget!(),
             gasprice_gadget: configure_gadget!(),
             iszero_gadget: configure_gadget!(),
@@ -399,25 +506,16 @@ impl<F: Field> ExecutionConfig<F> {
             pop_gadget: configure_gadget!(),
             push_gadget: configure_gadget!(),
             return_gadget: configure_gadget!(),
+            returndatacopy_gadget: configure_gadget!(),
+            returndatasize_gadget: configure_gadget!(),
+            revert_gadget: configure_gadget!(),
             sdiv_smod_gadget: configure_gadget!(),
             selfbalance_gadget: configure_gadget!(),
             sha3_gadget: configure_gadget!(),
+            selfdestruct_gadget: configure_gadget!(),
             address_gadget: configure_gadget!(),
-            balance_gadget: configure_gadget!(),
-            blockhash_gadget: configure_gadget!(),
-            exp_gadget: configure_gadget!(),
-            shl_gadget: configure_gadget!(),
             sar_gadget: configure_gadget!(),
-            extcodesize_gadget: configure_gadget!(),
-            extcodecopy_gadget: configure_gadget!(),
-            returndatasize_gadget: configure_gadget!(),
-            returndatacopy_gadget: configure_gadget!(),
-            create_gadget: configure_gadget!(),
-            callcode_gadget: configure_gadget!(),
-            delegatecall_gadget: configure_gadget!(),
-            create2_gadget: configure_gadget!(),
-            staticcall_gadget: configure_gadget!(),
-            selfdestruct_gadget: configure_gadget!(),
+            shl_gadget: configure_gadget!(),
             shr_gadget: configure_gadget!(),
             signed_comparator_gadget: configure_gadget!(),
             signextend_gadget: configure_gadget!(),
@@ -429,7 +527,34 @@ impl<F: Field> ExecutionConfig<F> {
             block_ctx_u160_gadget: configure_gadget!(),
             block_ctx_u256_gadget: configure_gadget!(),
             // error gadgets
+            error_depth_gadget: configure_gadget!(),
+            error_insufficient_balance_gadget: configure_gadget!(),
+            error_invalid_creation_code_gadget: configure_gadget!(),
+            error_invalid_jump_gadget: configure_gadget!(),
+            error_invalid_opcode_gadget: configure_gadget!(),
+            error_max_code_size_exceeded_gadget: configure_gadget!(),
+            error_oog_account_access_gadget: configure_gadget!(),
+            error_oog_call_gadget: configure_gadget!(),
+            error_oog_callcode_gadget: configure_gadget!(),
+            error_oog_delegatecall_gadget: configure_gadget!(),
+            error_oog_staticcall_gadget: configure_gadget!(),
+            error_oog_code_store_gadget: configure_gadget!(),
+            error_oog_constant_gadget: configure_gadget!(),
+            error_oog_create2_gadget: configure_gadget!(),
+            error_oog_dynamic_memory_gadget: configure_gadget!(),
+            error_oog_exp_gadget: configure_gadget!(),
+            error_oog_extcodecopy_gadget: configure_gadget!(),
+            error_oog_log_gadget: configure_gadget!(),
+            error_oog_memory_copy_gadget: configure_gadget!(),
+            error_oog_selfdestruct_gadget: configure_gadget!(),
+            error_oog_sha3_gadget: configure_gadget!(),
+            error_oog_sload_gadget: configure_gadget!(),
+            error_oog_sstore_gadget: configure_gadget!(),
             error_oog_static_memory_gadget: configure_gadget!(),
+            error_return_data_out_of_bound_gadget: configure_gadget!(),
+            error_stack_overflow_gadget: configure_gadget!(),
+            error_stack_underflow_gadget: configure_gadget!(),
+            error_write_protection_gadget: configure_gadget!(),
             // step and presets
             step: step_curr,
             height_map,
@@ -445,6 +570,8 @@ impl<F: Field> ExecutionConfig<F> {
             bytecode_table,
             block_table,
             copy_table,
+            exp_table,
+            keccak_table,
             &power_of_randomness,
             &cell_manager,
         );
@@ -617,6 +744,8 @@ impl<F: Field> ExecutionConfig<F> {
         bytecode_table: &dyn LookupTable<F>,
         block_table: &dyn LookupTable<F>,
         copy_table: &dyn LookupTable<F>,
+        exp_table: &dyn LookupTable<F>,
+        keccak_table: &dyn LookupTable<F>,
         power_of_randomness: &[Expression<F>; 31],
         cell_manager: &CellManager<F>,
     ) {
@@ -632,6 +761,8 @@ impl<F: Field> ExecutionConfig<F> {
                         Table::Block => block_table,
                         Table::Byte => byte_table,
                         Table::Copy => copy_table,
+                        Table::Exp => exp_table,
+                        Table::Keccak => keccak_table,
                     }
                     .table_exprs(meta);
                     vec![(
@@ -645,12 +776,14 @@ impl<F: Field> ExecutionConfig<F> {
 
     /// Assign block
     /// When exact is enabled, assign exact steps in block without padding for
-    /// unit test purpose
+    /// unit test purpose.  Otherwise the steps are padded with EndBlock steps
+    /// to `block.evm_circuit_pad_to` rows if set, so that the selectors only
+    /// depend on it.
     pub fn assign_block(
         &self,
         layouter: &mut impl Layouter<F>,
         block: &Block<F>,
-        _exact: bool,
+        exact: bool,
     ) -> Result<(), Error> {
         let power_of_randomness = (1..32)
             .map(|exp| block.randomness.pow(&[exp, 0, 0, 0]))
@@ -658,6 +791,45 @@ impl<F: Field> ExecutionConfig<F> {
             .try_into()
             .unwrap();
 
+        let num_rows: usize = block
+            .txs
+            .iter()
+            .flat_map(|tx| tx.steps.iter())
+            .map(|step| self.get_step_height(step.execution_state))
+            .sum();
+        let pad_to = if exact { 0 } else { block.evm_circuit_pad_to };
+        if pad_to > 0 && num_rows > pad_to {
+            log::error!(
+                "evm circuit needs {} rows but only has {}",
+                num_rows,
+                pad_to
+            );
+            return Err(Error::Synthesis);
+        }
+
+        // The padding steps fill the rows up to `pad_to`, and the last of them
+        // starts in the `padding_height` rows after `pad_to`, whatever the
+        // number of rows of the steps of the block.
+        let padding_height = self.get_step_height(ExecutionState::EndBlock);
+        let num_padding_steps = if pad_to > 0 {
+            (pad_to - num_rows + padding_height - 1) / padding_height + 1
+        } else {
+            0
+        };
+        let padding_tx = Transaction {
+            calls: vec![Call::default()],
+            ..Default::default()
+        };
+        let padding_step = ExecStep {
+            execution_state: ExecutionState::EndBlock,
+            ..Default::default()
+        };
+        let num_usable_rows = if pad_to > 0 {
+            pad_to + padding_height
+        } else {
+            num_rows
+        };
+
         layouter.assign_region(
             || "Execution step",
             |mut region| {
@@ -670,6 +842,7 @@ impl<F: Field> ExecutionConfig<F> {
                     .txs
                     .iter()
                     .flat_map(|tx| tx.steps.iter().map(move |step| (tx, step)))
+                    .chain(iter::repeat((&padding_tx, &padding_step)).take(num_padding_steps))
                     .peekable();
 
                 let mut last_height = 0;
@@ -695,7 +868,9 @@ impl<F: Field> ExecutionConfig<F> {
                     // q_step logic
                     for idx in 0..height {
                         let offset = offset + idx;
-                        self.q_usable.enable(&mut region, offset)?;
+                        if offset < num_usable_rows {
+                            self.q_usable.enable(&mut region, offset)?;
+                        }
                         region.assign_advice(
                             || "step selector",
                             self.q_step,
@@ -738,10 +913,16 @@ impl<F: Field> ExecutionConfig<F> {
                     || Ok(F::zero()),
                 )?;
 
-                // If not exact:
-                // TODO: Pad leftover region to the desired capacity
-                // TODO: Enable q_step_last
-                self.q_step_last.enable(&mut region, offset - last_height)?;
+                // The last step of the padding starts in any of the rows after
+                // `pad_to`, which all enable q_step_last so that it only depends
+                // on `pad_to`.
+                if pad_to > 0 {
+                    for offset in pad_to..num_usable_rows {
+                        self.q_step_last.enable(&mut region, offset)?;
+                    }
+                } else {
+                    self.q_step_last.enable(&mut region, offset - last_height)?;
+                }
 
                 Ok(())
             },
@@ -818,9 +999,10 @@ impl<F: Field> ExecutionConfig<F> {
             // opcode
             ExecutionState::ADD_SUB => assign_exec_step!(self.add_sub_gadget),
             ExecutionState::ADDMOD => assign_exec_step!(self.addmod_gadget),
+            ExecutionState::BALANCE => assign_exec_step!(self.balance_gadget),
             ExecutionState::BITWISE => assign_exec_step!(self.bitwise_gadget),
             ExecutionState::BYTE => assign_exec_step!(self.byte_gadget),
-            ExecutionState::CALL => assign_exec_step!(self.call_gadget),
+            ExecutionState::CALL_OP => assign_exec_step!(self.call_gadget),
             ExecutionState::CALLDATACOPY => assign_exec_step!(self.calldatacopy_gadget),
             ExecutionState::CALLDATALOAD => assign_exec_step!(self.calldataload_gadget),
             ExecutionState::CALLDATASIZE => assign_exec_step!(self.calldatasize_gadget),
@@ -830,8 +1012,13 @@ impl<F: Field> ExecutionConfig<F> {
             ExecutionState::CODECOPY => assign_exec_step!(self.codecopy_gadget),
             ExecutionState::CODESIZE => assign_exec_step!(self.codesize_gadget),
             ExecutionState::CMP => assign_exec_step!(self.comparator_gadget),
+            ExecutionState::CREATE => assign_exec_step!(self.create_gadget),
+            ExecutionState::CREATE2 => assign_exec_step!(self.create2_gadget),
             ExecutionState::DUP => assign_exec_step!(self.dup_gadget),
+            ExecutionState::EXP => assign_exec_step!(self.exp_gadget),
+            ExecutionState::EXTCODECOPY => assign_exec_step!(self.extcodecopy_gadget),
             ExecutionState::EXTCODEHASH => assign_exec_step!(self.extcodehash_gadget),
+            ExecutionState::EXTCODESIZE => assign_exec_step!(self.extcodesize_gadget),
             ExecutionState::GAS => assign_exec_step!(self.gas_gadget),
             ExecutionState::GASPRICE => assign_exec_step!(self.gasprice_gadget),
             ExecutionState::ISZERO => assign_exec_step!(self.iszero_gadget),
@@ -849,31 +1036,23 @@ impl<F: Field> ExecutionConfig<F> {
             ExecutionState::POP => assign_exec_step!(self.pop_gadget),
             ExecutionState::PUSH => assign_exec_step!(self.push_gadget),
             ExecutionState::RETURN => assign_exec_step!(self.return_gadget),
+            ExecutionState::RETURNDATACOPY => assign_exec_step!(self.returndatacopy_gadget),
+            ExecutionState::RETURNDATASIZE => assign_exec_step!(self.returndatasize_gadget),
+            ExecutionState::REVERT => assign_exec_step!(self.revert_gadget),
             ExecutionState::SCMP => assign_exec_step!(self.signed_comparator_gadget),
             ExecutionState::SDIV_SMOD => assign_exec_step!(self.sdiv_smod_gadget),
+            ExecutionState::BLOCKHASH => assign_exec_step!(self.blockhash_gadget),
             ExecutionState::BLOCKCTXU64 => assign_exec_step!(self.block_ctx_u64_gadget),
             ExecutionState::BLOCKCTXU160 => assign_exec_step!(self.block_ctx_u160_gadget),
             ExecutionState::BLOCKCTXU256 => assign_exec_step!(self.block_ctx_u256_gadget),
             ExecutionState::SELFBALANCE => assign_exec_step!(self.selfbalance_gadget),
-            // dummy gadgets
             ExecutionState::SHA3 => assign_exec_step!(self.sha3_gadget),
-            ExecutionState::ADDRESS => assign_exec_step!(self.address_gadget),
-            ExecutionState::BALANCE => assign_exec_step!(self.balance_gadget),
-            ExecutionState::BLOCKHASH => assign_exec_step!(self.blockhash_gadget),
-            ExecutionState::EXP => assign_exec_step!(self.exp_gadget),
-            ExecutionState::SHL => assign_exec_step!(self.shl_gadget),
-            ExecutionState::SAR => assign_exec_step!(self.sar_gadget),
-            ExecutionState::EXTCODESIZE => assign_exec_step!(self.extcodesize_gadget),
-            ExecutionState::EXTCODECOPY => assign_exec_step!(self.extcodecopy_gadget),
-            ExecutionState::RETURNDATASIZE => assign_exec_step!(self.returndatasize_gadget),
-            ExecutionState::RETURNDATACOPY => assign_exec_step!(self.returndatacopy_gadget),
-            ExecutionState::CREATE => assign_exec_step!(self.create_gadget),
-            ExecutionState::CALLCODE => assign_exec_step!(self.callcode_gadget),
-            ExecutionState::DELEGATECALL => assign_exec_step!(self.delegatecall_gadget),
-            ExecutionState::CREATE2 => assign_exec_step!(self.create2_gadget),
-            ExecutionState::STATICCALL => assign_exec_step!(self.staticcall_gadget),
             ExecutionState::SELFDESTRUCT => assign_exec_step!(self.selfdestruct_gadget),
+            // dummy gadgets
+            ExecutionState::ADDRESS => assign_exec_step!(self.address_gadget),
             // end of dummy gadgets
+            ExecutionState::SAR => assign_exec_step!(self.sar_gadget),
+            ExecutionState::SHL => assign_exec_step!(self.shl_gadget),
             ExecutionState::SHR => assign_exec_step!(self.shr_gadget),
             ExecutionState::SIGNEXTEND => assign_exec_step!(self.signextend_gadget),
             ExecutionState::SLOAD => assign_exec_step!(self.sload_gadget),
@@ -881,9 +1060,78 @@ impl<F: Field> ExecutionConfig<F> {
             ExecutionState::STOP => assign_exec_step!(self.stop_gadget),
             ExecutionState::SWAP => assign_exec_step!(self.swap_gadget),
             // errors
+            ExecutionState::ErrorDepth => assign_exec_step!(self.error_depth_gadget),
+            ExecutionState::ErrorInsufficientBalance => {
+                assign_exec_step!(self.error_insufficient_balance_gadget)
+            }
+            ExecutionState::ErrorInvalidCreationCode => {
+                assign_exec_step!(self.error_invalid_creation_code_gadget)
+            }
+            ExecutionState::ErrorInvalidJump => {
+                assign_exec_step!(self.error_invalid_jump_gadget)
+            }
+            ExecutionState::ErrorInvalidOpcode => {
+                assign_exec_step!(self.error_invalid_opcode_gadget)
+            }
+            ExecutionState::ErrorMaxCodeSizeExceeded => {
+                assign_exec_step!(self.error_max_code_size_exceeded_gadget)
+            }
+            ExecutionState::ErrorOutOfGasAccountAccess => {
+                assign_exec_step!(self.error_oog_account_access_gadget)
+            }
+            ExecutionState::ErrorOutOfGasCALL => assign_exec_step!(self.error_oog_call_gadget),
+            ExecutionState::ErrorOutOfGasCALLCODE => {
+                assign_exec_step!(self.error_oog_callcode_gadget)
+            }
+            ExecutionState::ErrorOutOfGasDELEGATECALL => {
+                assign_exec_step!(self.error_oog_delegatecall_gadget)
+            }
+            ExecutionState::ErrorOutOfGasSTATICCALL => {
+                assign_exec_step!(self.error_oog_staticcall_gadget)
+            }
+            ExecutionState::ErrorOutOfGasCodeStore => {
+                assign_exec_step!(self.error_oog_code_store_gadget)
+            }
+            ExecutionState::ErrorOutOfGasConstant => {
+                assign_exec_step!(self.error_oog_constant_gadget)
+            }
+            ExecutionState::ErrorOutOfGasCREATE2 => {
+                assign_exec_step!(self.error_oog_create2_gadget)
+            }
+            ExecutionState::ErrorOutOfGasDynamicMemoryExpansion => {
+                assign_exec_step!(self.error_oog_dynamic_memory_gadget)
+            }
+            ExecutionState::ErrorOutOfGasEXP => assign_exec_step!(self.error_oog_exp_gadget),
+            ExecutionState::ErrorOutOfGasEXTCODECOPY => {
+                assign_exec_step!(self.error_oog_extcodecopy_gadget)
+            }
+            ExecutionState::ErrorOutOfGasLOG => assign_exec_step!(self.error_oog_log_gadget),
+            ExecutionState::ErrorOutOfGasMemoryCopy => {
+                assign_exec_step!(self.error_oog_memory_copy_gadget)
+            }
+            ExecutionState::ErrorOutOfGasSELFDESTRUCT => {
+                assign_exec_step!(self.error_oog_selfdestruct_gadget)
+            }
+            ExecutionState::ErrorOutOfGasSHA3 => assign_exec_step!(self.error_oog_sha3_gadget),
+            ExecutionState::ErrorOutOfGasSLOAD => assign_exec_step!(self.error_oog_sload_gadget),
+            ExecutionState::ErrorOutOfGasSSTORE => {
+                assign_exec_step!(self.error_oog_sstore_gadget)
+            }
             ExecutionState::ErrorOutOfGasStaticMemoryExpansion => {
                 assign_exec_step!(self.error_oog_static_memory_gadget)
             }
+            ExecutionState::ErrorReturnDataOutOfBound => {
+                assign_exec_step!(self.error_return_data_out_of_bound_gadget)
+            }
+            ExecutionState::ErrorStackOverflow => {
+                assign_exec_step!(self.error_stack_overflow_gadget)
+            }
+            ExecutionState::ErrorStackUnderflow => {
+                assign_exec_step!(self.error_stack_underflow_gadget)
+            }
+            ExecutionState::ErrorWriteProtection => {
+                assign_exec_step!(self.error_write_protection_gadget)
+            }
             _ => unimplemented!("unimplemented ExecutionState: {:?}", step.execution_state),
         }
 
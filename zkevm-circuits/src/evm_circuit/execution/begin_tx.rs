@@ -5,19 +5,20 @@ use crate::{
         step::ExecutionState,
         table::{AccountFieldTag, CallContextFieldTag, TxContextFieldTag},
         util::{
-            common_gadget::TransferWithGasFeeGadget,
+            common_gadget::{ContractCreateGadget, TransferWithGasFeeGadget},
             constraint_builder::{
                 ConstraintBuilder, ReversionInfo, StepStateTransition,
                 Transition::{Delta, To},
             },
-            math_gadget::{MulWordByU64Gadget, RangeCheckGadget},
-            select, CachedRegion, Cell, RandomLinearCombination, Word,
+            math_gadget::{IsZeroGadget, MulWordByU64Gadget, RangeCheckGadget},
+            rlc, select, CachedRegion, Cell, RandomLinearCombination, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
     util::Expr,
 };
-use eth_types::{evm_types::GasCost, Field, ToLittleEndian, ToScalar};
+use bus_mapping::circuit_input_builder::CopyDataType;
+use eth_types::{evm_types::GasCost, Field, ToLittleEndian, ToScalar, U256};
 use halo2_proofs::plonk::Error;
 
 #[derive(Clone, Debug)]
@@ -37,6 +38,9 @@ pub(crate) struct BeginTxGadget<F> {
     sufficient_gas_left: RangeCheckGadget<F, N_BYTES_GAS>,
     transfer_with_gas_fee: TransferWithGasFeeGadget<F>,
     code_hash: Cell<F>,
+    contract_create: ContractCreateGadget<F, false>,
+    is_empty_init_code: IsZeroGadget<F>,
+    init_code_rlc: Cell<F>,
 }
 
 impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
@@ -100,6 +104,16 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
         let gas_left = tx_gas.expr() - intrinsic_gas_cost;
         let sufficient_gas_left = RangeCheckGadget::construct(cb, gas_left.clone());
 
+        // Derive the address of the contract to be created in a creation
+        // transaction.
+        let contract_create =
+            ContractCreateGadget::construct(cb, tx_caller_address.expr(), tx_nonce.expr());
+        let callee_address = select::expr(
+            tx_is_create.expr(),
+            contract_create.address(),
+            tx_callee_address.expr(),
+        );
+
         // Prepare access list of caller and callee
         cb.account_access_list_write(
             tx_id.expr(),
@@ -110,7 +124,7 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
         );
         cb.account_access_list_write(
             tx_id.expr(),
-            tx_callee_address.expr(),
+            callee_address.clone(),
             1.expr(),
             0.expr(),
             None,
@@ -120,32 +134,86 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
         let transfer_with_gas_fee = TransferWithGasFeeGadget::construct(
             cb,
             tx_caller_address.expr(),
-            tx_callee_address.expr(),
+            callee_address.clone(),
             tx_value.clone(),
             mul_gas_fee_by_gas.product().clone(),
             &mut reversion_info,
         );
 
-        // TODO: Handle creation transaction
         // TODO: Handle precompiled
 
-        // Read code_hash of callee
         let code_hash = cb.query_cell();
-        cb.account_read(
-            tx_callee_address.expr(),
-            AccountFieldTag::CodeHash,
-            code_hash.expr(),
+        let init_code_rlc = cb.query_cell();
+        let is_empty_init_code = IsZeroGadget::construct(cb, tx_call_data_length.expr());
+        cb.condition(tx_is_create.expr(), |cb| {
+            // Increase callee's nonce, which is reverted if the creation
+            // fails.
+            cb.account_write(
+                callee_address.clone(),
+                AccountFieldTag::Nonce,
+                1.expr(),
+                0.expr(),
+                Some(&mut reversion_info),
+            );
+
+            // The code_hash of the init code is the keccak of the tx calldata,
+            // and the contract address is the keccak of the RLP of caller and
+            // nonce.
+            cb.keccak_table_lookup(
+                init_code_rlc.expr(),
+                tx_call_data_length.expr(),
+                code_hash.expr(),
+            );
+            cb.keccak_table_lookup(
+                contract_create.input_rlc(),
+                contract_create.input_length(),
+                contract_create.keccak_output(),
+            );
+        });
+        cb.condition(is_empty_init_code.expr(), |cb| {
+            cb.require_zero(
+                "init_code_rlc == 0 for empty init code",
+                init_code_rlc.expr(),
+            );
+        });
+        cb.condition(
+            tx_is_create.expr() * (1.expr() - is_empty_init_code.expr()),
+            |cb| {
+                cb.copy_table_lookup(
+                    tx_id.expr(),
+                    CopyDataType::TxCalldata.expr(),
+                    call_id.expr(),
+                    CopyDataType::RlcAcc.expr(),
+                    0.expr(),
+                    tx_call_data_length.expr(),
+                    0.expr(), // dst_addr for CopyDataType::RlcAcc is 0.
+                    tx_call_data_length.expr(),
+                    0.expr(), // No rw happens for copy from tx calldata.
+                    0.expr(),
+                    init_code_rlc.expr(),
+                );
+            },
         );
 
+        // Read code_hash of callee
+        cb.condition(1.expr() - tx_is_create.expr(), |cb| {
+            cb.account_read(
+                callee_address.clone(),
+                AccountFieldTag::CodeHash,
+                code_hash.expr(),
+            );
+        });
+
         // Setup next call's context.
         for (field_tag, value) in [
             (CallContextFieldTag::Depth, 1.expr()),
             (CallContextFieldTag::CallerAddress, tx_caller_address.expr()),
-            (CallContextFieldTag::CalleeAddress, tx_callee_address.expr()),
+            (CallContextFieldTag::CalleeAddress, callee_address),
             (CallContextFieldTag::CallDataOffset, 0.expr()),
             (
                 CallContextFieldTag::CallDataLength,
-                tx_call_data_length.expr(),
+                // The calldata of a creation transaction is the init code.
+                (1.expr() - tx_is_create.expr()) * tx_call_data_length.expr(),
             ),
             (CallContextFieldTag::Value, tx_value.expr()),
             (CallContextFieldTag::IsStatic, 0.expr()),
@@ -153,7 +221,7 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             (CallContextFieldTag::LastCalleeReturnDataOffset, 0.expr()),
             (CallContextFieldTag::LastCalleeReturnDataLength, 0.expr()),
             (CallContextFieldTag::IsRoot, 1.expr()),
-            (CallContextFieldTag::IsCreate, 0.expr()),
+            (CallContextFieldTag::IsCreate, tx_is_create.expr()),
             (CallContextFieldTag::CodeHash, code_hash.expr()),
         ] {
             cb.call_context_lookup(false.expr(), Some(call_id.expr()), field_tag, value);
@@ -169,7 +237,7 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             //   - Write TxAccessListAccount
             //   - Write Account Balance
             //   - Write Account Balance
-            //   - Read Account CodeHash
+            //   - Write Account Nonce (creation) or Read Account CodeHash
             //   - Read CallContext Depth
             //   - Read CallContext CallerAddress
             //   - Read CallContext CalleeAddress
@@ -183,10 +251,11 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             rw_counter: Delta(22.expr()),
             call_id: To(call_id.expr()),
             is_root: To(true.expr()),
-            is_create: To(false.expr()),
+            is_create: To(tx_is_create.expr()),
             code_hash: To(code_hash.expr()),
             gas_left: To(gas_left),
-            reversible_write_counter: To(2.expr()),
+            // The balance writes, and the callee nonce write of a creation
+            reversible_write_counter: To(2.expr() + tx_is_create.expr()),
             log_id: To(0.expr()),
             ..StepStateTransition::new_context()
         });
@@ -207,6 +276,9 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             sufficient_gas_left,
             transfer_with_gas_fee,
             code_hash,
+            contract_create,
+            is_empty_init_code,
+            init_code_rlc,
         }
     }
 
@@ -220,9 +292,15 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
         step: &ExecStep,
     ) -> Result<(), Error> {
         let gas_fee = tx.gas_price * tx.gas;
-        let [caller_balance_pair, callee_balance_pair, (callee_code_hash, _)] =
-            [step.rw_indices[6], step.rw_indices[7], step.rw_indices[8]]
-                .map(|idx| block.rws[idx].account_value_pair());
+        let [caller_balance_pair, callee_balance_pair] =
+            [step.rw_indices[6], step.rw_indices[7]].map(|idx| block.rws[idx].account_value_pair());
+        // For a creation transaction the code_hash is of the init code, and
+        // otherwise it's read from the callee account.
+        let callee_code_hash = if tx.is_create {
+            call.code_hash
+        } else {
+            block.rws[step.rw_indices[8]].account_value_pair().0
+        };
 
         self.tx_id
             .assign(region, offset, Some(F::from(tx.id as u64)))?;
@@ -270,6 +348,25 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
                 block.randomness,
             )),
         )?;
+        self.contract_create.assign(
+            region,
+            offset,
+            tx.caller_address,
+            tx.nonce,
+            callee_code_hash,
+            U256::zero(),
+        )?;
+        self.is_empty_init_code
+            .assign(region, offset, F::from(tx.call_data_length as u64))?;
+        self.init_code_rlc.assign(
+            region,
+            offset,
+            Some(if tx.is_create {
+                rlc::value(&tx.call_data, block.randomness)
+            } else {
+                F::zero()
+            }),
+        )?;
         Ok(())
     }
 }
@@ -280,7 +377,7 @@ mod test {
         test::{rand_bytes, run_test_circuit_incomplete_fixed_table},
         witness::block_convert,
     };
-    use bus_mapping::{evm::OpcodeId, mock::BlockData};
+    use bus_mapping::{evm::OpcodeId, mock::BlockData, operation::AccountField};
     use eth_types::{self, bytecode, evm_types::GasCost, geth_types::GethData, Word};
     use mock::{
         eth, gwei, test_ctx::helpers::account_0_code_account_1_no_code, TestContext, MOCK_ACCOUNTS,
@@ -424,4 +521,98 @@ mod test {
         // Transfer nothing with random gas_price, tx reverts
         test_ok(mock_tx(eth(0), random_gas_price, vec![]), false);
     }
+
+    #[test]
+    fn begin_tx_gadget_create() {
+        let init_code = bytecode! {
+            PUSH1(0)
+            PUSH1(0)
+            RETURN
+        };
+
+        for (nonce, init_code) in [
+            (0u64, vec![]),
+            (1, init_code.to_vec()),
+            (0x80, init_code.to_vec()),
+            (0x100, init_code.to_vec()),
+        ] {
+            let block: GethData = TestContext::<1, 1>::new(
+                None,
+                |accs| {
+                    accs[0]
+                        .address(MOCK_ACCOUNTS[0])
+                        .balance(eth(10))
+                        .nonce(nonce.into());
+                },
+                |mut txs, accs| {
+                    txs[0]
+                        .from(accs[0].address)
+                        .nonce(nonce.into())
+                        .value(eth(1))
+                        .input(init_code.into());
+                },
+                |block, _| block,
+            )
+            .unwrap()
+            .into();
+
+            let mut builder =
+                BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+            builder
+                .handle_block(&block.eth_block, &block.geth_traces)
+                .unwrap();
+            let block = block_convert(&builder.block, &builder.code_db);
+
+            assert_eq!(run_test_circuit_incomplete_fixed_table(block), Ok(()));
+        }
+    }
+
+    #[test]
+    fn begin_tx_gadget_create_reverted() {
+        // The init code reverts, so that the creation fails
+        let init_code = bytecode! {
+            PUSH1(0)
+            PUSH1(0)
+            REVERT
+        };
+
+        let block: GethData = TestContext::<1, 1>::new(
+            None,
+            |accs| {
+                accs[0].address(MOCK_ACCOUNTS[0]).balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0]
+                    .from(accs[0].address)
+                    .value(eth(1))
+                    .input(init_code.into());
+            },
+            |block, _| block,
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        // The nonce of the contract is increased and then reverted
+        let callee_nonces: Vec<_> = builder
+            .block
+            .container
+            .account
+            .iter()
+            .map(|op| op.op())
+            .filter(|op| op.address != MOCK_ACCOUNTS[0] && op.field == AccountField::Nonce)
+            .map(|op| (op.value_prev, op.value))
+            .collect();
+        assert_eq!(
+            callee_nonces,
+            vec![(Word::zero(), Word::one()), (Word::one(), Word::zero())]
+        );
+
+        let block = block_convert(&builder.block, &builder.code_db);
+        assert_eq!(run_test_circuit_incomplete_fixed_table(block), Ok(()));
+    }
 }
@@ -0,0 +1,208 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_U64,
+        step::ExecutionState,
+        table::BlockContextFieldTag,
+        util::{
+            and,
+            common_gadget::SameContextGadget,
+            constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
+            from_bytes,
+            math_gadget::{IsZeroGadget, LtGadget},
+            not, sum, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use bus_mapping::evm::OpcodeId;
+use eth_types::{evm_types::NUM_PREV_BLOCK_ALLOWED, Field, ToLittleEndian, ToScalar};
+use halo2_proofs::plonk::Error;
+
+#[derive(Clone, Debug)]
+pub(crate) struct BlockHashGadget<F> {
+    same_context: SameContextGadget<F>,
+    block_number: Word<F>,
+    current_block_number: Cell<F>,
+    block_hash: Cell<F>,
+    // Whether the `block_number` fits into u64.
+    is_block_number_within_u64: IsZeroGadget<F>,
+    // Whether `block_number < current_block_number`.
+    is_before_current: LtGadget<F, N_BYTES_U64>,
+    // Whether `current_block_number - block_number <= 256`.
+    is_within_window: LtGadget<F, { N_BYTES_U64 + 1 }>,
+}
+
+impl<F: Field> ExecutionGadget<F> for BlockHashGadget<F> {
+    const NAME: &'static str = "BLOCKHASH";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::BLOCKHASH;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let block_number = cb.query_word();
+        cb.stack_pop(block_number.expr());
+
+        let current_block_number = cb.query_cell();
+        cb.block_lookup(
+            BlockContextFieldTag::Number.expr(),
+            None,
+            current_block_number.expr(),
+        );
+
+        // Only the most recent 256 blocks excluding the current one are
+        // accessible.
+        let is_block_number_within_u64 =
+            IsZeroGadget::construct(cb, sum::expr(&block_number.cells[N_BYTES_U64..]));
+        let block_number_u64 = from_bytes::expr(&block_number.cells[..N_BYTES_U64]);
+        let is_before_current =
+            LtGadget::construct(cb, block_number_u64.clone(), current_block_number.expr());
+        let is_within_window = LtGadget::construct(
+            cb,
+            current_block_number.expr(),
+            block_number_u64.clone() + (NUM_PREV_BLOCK_ALLOWED + 1).expr(),
+        );
+        let is_valid = and::expr([
+            is_block_number_within_u64.expr(),
+            is_before_current.expr(),
+            is_within_window.expr(),
+        ]);
+
+        // The hash is looked up from the block table when accessible, otherwise
+        // it is zero.
+        let block_hash = cb.query_cell();
+        cb.condition(is_valid.clone(), |cb| {
+            cb.block_lookup(
+                BlockContextFieldTag::BlockHash.expr(),
+                Some(block_number_u64),
+                block_hash.expr(),
+            );
+        });
+        cb.condition(not::expr(is_valid), |cb| {
+            cb.require_zero("Block hash is zero when out of range", block_hash.expr());
+        });
+
+        cb.stack_push(block_hash.expr());
+
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(2.expr()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(0.expr()),
+            gas_left: Delta(-OpcodeId::BLOCKHASH.constant_gas_cost().expr()),
+            ..Default::default()
+        };
+        let opcode = cb.query_cell();
+        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            block_number,
+            current_block_number,
+            block_hash,
+            is_block_number_within_u64,
+            is_before_current,
+            is_within_window,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        _: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let [block_number, block_hash] =
+            [0, 1].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        let block_number_bytes = block_number.to_le_bytes();
+        self.block_number
+            .assign(region, offset, Some(block_number_bytes))?;
+
+        let current_block_number = block.context.number.to_scalar().unwrap();
+        self.current_block_number
+            .assign(region, offset, Some(current_block_number))?;
+
+        self.block_hash.assign(
+            region,
+            offset,
+            Some(Word::random_linear_combine(
+                block_hash.to_le_bytes(),
+                block.randomness,
+            )),
+        )?;
+
+        self.is_block_number_within_u64.assign(
+            region,
+            offset,
+            sum::value(&block_number_bytes[N_BYTES_U64..]),
+        )?;
+        let block_number_u64 = F::from(block_number.low_u64());
+        self.is_before_current
+            .assign(region, offset, block_number_u64, current_block_number)?;
+        self.is_within_window.assign(
+            region,
+            offset,
+            current_block_number,
+            block_number_u64 + F::from(NUM_PREV_BLOCK_ALLOWED + 1),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{bytecode, evm_types::NUM_PREV_BLOCK_ALLOWED, Word};
+    use mock::test_ctx::{helpers::*, TestContext};
+
+    fn test_ok(current_block_number: u64, block_number: Word) {
+        let code = bytecode! {
+            PUSH32(block_number)
+            BLOCKHASH
+            STOP
+        };
+        let history_hashes = (0..NUM_PREV_BLOCK_ALLOWED.min(current_block_number))
+            .map(|idx| Word::from(0x1234_5678u64 * (idx + 1)))
+            .collect();
+
+        assert_eq!(
+            run_test_circuits(
+                TestContext::<2, 1>::new(
+                    Some(history_hashes),
+                    account_0_code_account_1_no_code(code),
+                    tx_from_1_to_0,
+                    |block, _tx| block.number(current_block_number),
+                )
+                .unwrap(),
+                None
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn blockhash_gadget_simple() {
+        test_ok(0xcafe, (0xcafe - 1).into());
+        test_ok(0xcafe, (0xcafe - NUM_PREV_BLOCK_ALLOWED).into());
+        test_ok(3, 0.into());
+    }
+
+    #[test]
+    fn blockhash_gadget_out_of_range() {
+        test_ok(0xcafe, 0xcafe.into());
+        test_ok(0xcafe, (0xcafe + 1).into());
+        test_ok(0xcafe, (0xcafe - NUM_PREV_BLOCK_ALLOWED - 1).into());
+        test_ok(0, 0.into());
+    }
+
+    #[test]
+    fn blockhash_gadget_overflow_u64() {
+        test_ok(0xcafe, (Word::one() << 64) + 0xcafe - 1);
+        test_ok(0xcafe, Word::MAX);
+    }
+}
@@ -16,7 +16,7 @@ use crate::{
                 MinMaxGadget,
             },
             memory_gadget::{MemoryAddressGadget, MemoryExpansionGadget},
-            select, sum, CachedRegion, Cell, Word,
+            or, select, sum, CachedRegion, Cell, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -25,19 +25,37 @@ use crate::{
 use bus_mapping::evm::OpcodeId;
 use eth_types::{
     evm_types::{GasCost, GAS_STIPEND_CALL_WITH_VALUE},
-    Field, ToLittleEndian, ToScalar,
+    Field, ToLittleEndian, ToScalar, U256,
 };
-use halo2_proofs::plonk::Error;
+use halo2_proofs::plonk::{Error, Expression};
 use keccak256::EMPTY_HASH_LE;
 
+/// Gadget for call related opcodes. It supports `OpcodeId::CALL`,
+/// `OpcodeId::CALLCODE`, `OpcodeId::DELEGATECALL` and `OpcodeId::STATICCALL`,
+/// which differ in the caller, callee and value of the new call context:
+///
+/// | Opcode         | Caller           | Callee          | Value         |
+/// |----------------|------------------|-----------------|---------------|
+/// | `CALL`         | current address  | stack address   | stack value   |
+/// | `CALLCODE`     | current address  | current address | stack value   |
+/// | `DELEGATECALL` | current caller   | current address | current value |
+/// | `STATICCALL`   | current address  | stack address   | 0             |
+///
+/// The code to execute is always the one of the account at the stack address.
 #[derive(Clone, Debug)]
 pub(crate) struct CallGadget<F> {
     opcode: Cell<F>,
+    is_call: IsEqualGadget<F>,
+    is_callcode: IsEqualGadget<F>,
+    is_delegatecall: IsEqualGadget<F>,
+    is_staticcall: IsEqualGadget<F>,
     tx_id: Cell<F>,
     reversion_info: ReversionInfo<F>,
     current_address: Cell<F>,
     is_static: Cell<F>,
     depth: Cell<F>,
+    current_caller_address: Cell<F>,
+    current_value: Cell<F>,
     gas: Word<F>,
     callee_address: Word<F>,
     value: Word<F>,
@@ -60,21 +78,30 @@ pub(crate) struct CallGadget<F> {
 }
 
 impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
-    const NAME: &'static str = "CALL";
+    const NAME: &'static str = "CALL_OP";
 
-    const EXECUTION_STATE: ExecutionState = ExecutionState::CALL;
+    const EXECUTION_STATE: ExecutionState = ExecutionState::CALL_OP;
 
     fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
         let opcode = cb.query_cell();
         cb.opcode_lookup(opcode.expr(), 1.expr());
 
         // We do the responsible opcode check explicitly here because we're not using
-        // the `SameContextGadget` for `CALL`.
+        // the `SameContextGadget` for `CALL_OP`.
+        let [is_call, is_callcode, is_delegatecall, is_staticcall] = [
+            OpcodeId::CALL,
+            OpcodeId::CALLCODE,
+            OpcodeId::DELEGATECALL,
+            OpcodeId::STATICCALL,
+        ]
+        .map(|opcode_id| IsEqualGadget::construct(cb, opcode.expr(), opcode_id.expr()));
         cb.require_equal(
-            "Opcode should be CALL",
-            opcode.expr(),
-            OpcodeId::CALL.expr(),
+            "Opcode should be CALL, CALLCODE, DELEGATECALL or STATICCALL",
+            is_call.expr() + is_callcode.expr() + is_delegatecall.expr() + is_staticcall.expr(),
+            1.expr(),
         );
+        // Only `CALL` and `CALLCODE` take the value argument from stack.
+        let has_value_arg = is_call.expr() + is_callcode.expr();
 
         let gas_word = cb.query_word();
         let callee_address_word = cb.query_word();
@@ -99,15 +126,43 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
 
         cb.range_lookup(depth.expr(), 1024);
 
+        // `DELEGATECALL` propagates the caller and the value of current call.
+        let [current_caller_address, current_value] = cb.condition(is_delegatecall.expr(), |cb| {
+            [
+                CallContextFieldTag::CallerAddress,
+                CallContextFieldTag::Value,
+            ]
+            .map(|field_tag| cb.call_context(None, field_tag))
+        });
+
         // Lookup values from stack
         cb.stack_pop(gas_word.expr());
         cb.stack_pop(callee_address_word.expr());
-        cb.stack_pop(value.expr());
-        cb.stack_pop(cd_offset.expr());
-        cb.stack_pop(cd_length.expr());
-        cb.stack_pop(rd_offset.expr());
-        cb.stack_pop(rd_length.expr());
-        cb.stack_push(is_success.expr());
+        cb.condition(has_value_arg.clone(), |cb| {
+            cb.stack_lookup(false.expr(), 2.expr(), value.expr());
+        });
+        cb.condition(is_delegatecall.expr() + is_staticcall.expr(), |cb| {
+            cb.require_zero(
+                "DELEGATECALL and STATICCALL have no value argument",
+                value.expr(),
+            );
+        });
+        for (idx, rlc) in [
+            cd_offset.expr(),
+            cd_length.expr(),
+            rd_offset.expr(),
+            rd_length.expr(),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            cb.stack_lookup(false.expr(), (idx + 2).expr() + has_value_arg.clone(), rlc);
+        }
+        cb.stack_lookup(
+            true.expr(),
+            5.expr() + has_value_arg.clone(),
+            is_success.expr(),
+        );
 
         // Recomposition of random linear combination to integer
         let callee_address =
@@ -148,10 +203,19 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
             );
         });
 
+        // `CALLCODE` and `DELEGATECALL` run the code in the current account's
+        // context.
+        let is_current_context = is_callcode.expr() + is_delegatecall.expr();
+        let next_callee_address = select::expr(
+            is_current_context.clone(),
+            current_address.expr(),
+            callee_address.clone(),
+        );
+
         // Verify transfer
         let value_is_zero = IsZeroGadget::construct(cb, sum::expr(&value.cells));
         let has_value = 1.expr() - value_is_zero.expr();
-        cb.condition(has_value.clone(), |cb| {
+        cb.condition(is_call.expr() * has_value.clone(), |cb| {
             cb.require_zero(
                 "CALL with value must not be in static call stack",
                 is_static.expr(),
@@ -160,7 +224,7 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
         let transfer = TransferGadget::construct(
             cb,
             current_address.expr(),
-            callee_address.clone(),
+            next_callee_address.clone(),
             value.clone(),
             &mut callee_reversion_info,
         );
@@ -187,17 +251,14 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
                 cb.power_of_randomness(),
             ),
         );
-        // Sum up gas cost
-        let gas_cost = select::expr(
+        let gas_cost = call_gas_cost_expr(
             is_warm_prev.expr(),
-            GasCost::WARM_ACCESS.expr(),
-            GasCost::COLD_ACCOUNT_ACCESS.expr(),
-        ) + has_value.clone()
-            * (GasCost::CALL_WITH_VALUE.expr()
-                + is_account_empty.expr()
-                    * is_empty_code_hash.expr()
-                    * GasCost::NEW_ACCOUNT.expr())
-            + memory_expansion.gas_cost();
+            has_value.clone(),
+            is_call.expr(),
+            is_account_empty.expr(),
+            is_empty_code_hash.expr(),
+            memory_expansion.gas_cost(),
+        );
 
         // Apply EIP 150
         let gas_available = cb.curr.state.gas_left.expr() - gas_cost.clone();
@@ -223,9 +284,9 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
             }
 
             cb.require_step_state_transition(StepStateTransition {
-                rw_counter: Delta(24.expr()),
+                rw_counter: Delta(cb.rw_counter_offset()),
                 program_counter: Delta(1.expr()),
-                stack_pointer: Delta(6.expr()),
+                stack_pointer: Delta(5.expr() + has_value_arg.clone()),
                 gas_left: Delta(
                     has_value.clone() * GAS_STIPEND_CALL_WITH_VALUE.expr() - gas_cost.clone(),
                 ),
@@ -244,7 +305,7 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
                 ),
                 (
                     CallContextFieldTag::StackPointer,
-                    cb.curr.state.stack_pointer.expr() + 6.expr(),
+                    cb.curr.state.stack_pointer.expr() + 5.expr() + has_value_arg,
                 ),
                 (
                     CallContextFieldTag::GasLeft,
@@ -267,15 +328,28 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
                 (CallContextFieldTag::CallerId, cb.curr.state.call_id.expr()),
                 (CallContextFieldTag::TxId, tx_id.expr()),
                 (CallContextFieldTag::Depth, depth.expr() + 1.expr()),
-                (CallContextFieldTag::CallerAddress, current_address.expr()),
-                (CallContextFieldTag::CalleeAddress, callee_address),
+                (
+                    CallContextFieldTag::CallerAddress,
+                    select::expr(
+                        is_delegatecall.expr(),
+                        current_caller_address.expr(),
+                        current_address.expr(),
+                    ),
+                ),
+                (CallContextFieldTag::CalleeAddress, next_callee_address),
                 (CallContextFieldTag::CallDataOffset, cd_address.offset()),
                 (CallContextFieldTag::CallDataLength, cd_address.length()),
                 (CallContextFieldTag::ReturnDataOffset, rd_address.offset()),
                 (CallContextFieldTag::ReturnDataLength, rd_address.length()),
-                (CallContextFieldTag::Value, value.expr()),
+                (
+                    CallContextFieldTag::Value,
+                    select::expr(is_delegatecall.expr(), current_value.expr(), value.expr()),
+                ),
                 (CallContextFieldTag::IsSuccess, is_success.expr()),
-                (CallContextFieldTag::IsStatic, is_static.expr()),
+                (
+                    CallContextFieldTag::IsStatic,
+                    or::expr([is_static.expr(), is_staticcall.expr()]),
+                ),
                 (CallContextFieldTag::LastCalleeId, 0.expr()),
                 (CallContextFieldTag::LastCalleeReturnDataOffset, 0.expr()),
                 (CallContextFieldTag::LastCalleeReturnDataLength, 0.expr()),
@@ -290,7 +364,7 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
             let callee_gas_left = callee_gas_left + has_value * GAS_STIPEND_CALL_WITH_VALUE.expr();
 
             cb.require_step_state_transition(StepStateTransition {
-                rw_counter: Delta(44.expr()),
+                rw_counter: Delta(cb.rw_counter_offset()),
                 call_id: To(callee_call_id.expr()),
                 is_root: To(false.expr()),
                 is_create: To(false.expr()),
@@ -303,11 +377,17 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
 
         Self {
             opcode,
+            is_call,
+            is_callcode,
+            is_delegatecall,
+            is_staticcall,
             tx_id,
             reversion_info,
             current_address,
             is_static,
             depth,
+            current_caller_address,
+            current_value,
             gas: gas_word,
             callee_address: callee_address_word,
             value,
@@ -339,41 +419,72 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
         call: &Call,
         step: &ExecStep,
     ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        let is_call = opcode == OpcodeId::CALL;
+        let is_delegatecall = opcode == OpcodeId::DELEGATECALL;
+        let has_value_arg = matches!(opcode, OpcodeId::CALL | OpcodeId::CALLCODE);
+
+        // `DELEGATECALL` has 2 extra call context reads, and `CALL` and
+        // `CALLCODE` have 1 extra stack read, which shift the rw indices.
+        let stack_index = if is_delegatecall { 8 } else { 6 };
+        let rw_offset = stack_index + has_value_arg as usize;
+
         let [tx_id, current_address, is_static, depth, callee_rw_counter_end_of_reversion, callee_is_persistent] =
             [
                 step.rw_indices[0],
                 step.rw_indices[3],
                 step.rw_indices[4],
                 step.rw_indices[5],
-                step.rw_indices[15],
-                step.rw_indices[16],
+                step.rw_indices[rw_offset + 8],
+                step.rw_indices[rw_offset + 9],
             ]
             .map(|idx| block.rws[idx].call_context_value());
-        let [gas, callee_address, value, cd_offset, cd_length, rd_offset, rd_length, is_success] =
-            [
-                step.rw_indices[6],
-                step.rw_indices[7],
-                step.rw_indices[8],
-                step.rw_indices[9],
-                step.rw_indices[10],
-                step.rw_indices[11],
-                step.rw_indices[12],
-                step.rw_indices[13],
-            ]
-            .map(|idx| block.rws[idx].stack_value());
-        let (is_warm, is_warm_prev) = block.rws[step.rw_indices[14]].tx_access_list_value_pair();
+        let [current_caller_address, current_value] = if is_delegatecall {
+            [step.rw_indices[6], step.rw_indices[7]].map(|idx| block.rws[idx].call_context_value())
+        } else {
+            [U256::zero(); 2]
+        };
+        let [gas, callee_address, cd_offset, cd_length, rd_offset, rd_length, is_success] = [
+            step.rw_indices[stack_index],
+            step.rw_indices[stack_index + 1],
+            step.rw_indices[rw_offset + 2],
+            step.rw_indices[rw_offset + 3],
+            step.rw_indices[rw_offset + 4],
+            step.rw_indices[rw_offset + 5],
+            step.rw_indices[rw_offset + 6],
+        ]
+        .map(|idx| block.rws[idx].stack_value());
+        let value = if has_value_arg {
+            block.rws[step.rw_indices[stack_index + 2]].stack_value()
+        } else {
+            U256::zero()
+        };
+        let (is_warm, is_warm_prev) =
+            block.rws[step.rw_indices[rw_offset + 7]].tx_access_list_value_pair();
         let [caller_balance_pair, callee_balance_pair, (callee_nonce, _), (callee_code_hash, _)] =
             [
-                step.rw_indices[17],
-                step.rw_indices[18],
-                step.rw_indices[19],
-                step.rw_indices[20],
+                step.rw_indices[rw_offset + 10],
+                step.rw_indices[rw_offset + 11],
+                step.rw_indices[rw_offset + 12],
+                step.rw_indices[rw_offset + 13],
             ]
             .map(|idx| block.rws[idx].account_value_pair());
 
-        let opcode = step.opcode.unwrap();
         self.opcode
             .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+        for (gadget, opcode_id) in [
+            (&self.is_call, OpcodeId::CALL),
+            (&self.is_callcode, OpcodeId::CALLCODE),
+            (&self.is_delegatecall, OpcodeId::DELEGATECALL),
+            (&self.is_staticcall, OpcodeId::STATICCALL),
+        ] {
+            gadget.assign(
+                region,
+                offset,
+                F::from(opcode.as_u64()),
+                F::from(opcode_id.as_u64()),
+            )?;
+        }
 
         self.tx_id
             .assign(region, offset, Some(F::from(tx_id.low_u64())))?;
@@ -389,6 +500,16 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
             .assign(region, offset, Some(F::from(is_static.low_u64())))?;
         self.depth
             .assign(region, offset, Some(F::from(depth.low_u64())))?;
+        self.current_caller_address
+            .assign(region, offset, current_caller_address.to_scalar())?;
+        self.current_value.assign(
+            region,
+            offset,
+            Some(Word::random_linear_combine(
+                current_value.to_le_bytes(),
+                block.randomness,
+            )),
+        )?;
 
         self.gas.assign(region, offset, Some(gas.to_le_bytes()))?;
         self.callee_address
@@ -451,7 +572,7 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
                 Word::random_linear_combine(callee_balance_pair.1.to_le_bytes(), block.randomness),
             ],
         )?;
-        self.is_empty_code_hash.assign(
+        let is_empty_code_hash = self.is_empty_code_hash.assign(
             region,
             offset,
             Word::random_linear_combine(callee_code_hash.to_le_bytes(), block.randomness),
@@ -464,7 +585,7 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
             GasCost::COLD_ACCOUNT_ACCESS.as_u64()
         } + if has_value {
             GasCost::CALL_WITH_VALUE.as_u64()
-                + if is_account_empty == F::one() {
+                + if is_call && is_account_empty == F::one() && is_empty_code_hash == F::one() {
                     GasCost::NEW_ACCOUNT.as_u64()
                 } else {
                     0
@@ -485,6 +606,26 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
     }
 }
 
+/// Sums up the gas cost of call related opcodes without the gas passed to the
+/// callee, where only `CALL` could create a new account.
+pub(crate) fn call_gas_cost_expr<F: Field>(
+    is_warm_prev: Expression<F>,
+    has_value: Expression<F>,
+    is_call: Expression<F>,
+    is_account_empty: Expression<F>,
+    is_empty_code_hash: Expression<F>,
+    memory_expansion_gas_cost: Expression<F>,
+) -> Expression<F> {
+    select::expr(
+        is_warm_prev,
+        GasCost::WARM_ACCESS.expr(),
+        GasCost::COLD_ACCOUNT_ACCESS.expr(),
+    ) + has_value
+        * (GasCost::CALL_WITH_VALUE.expr()
+            + is_call * is_account_empty * is_empty_code_hash * GasCost::NEW_ACCOUNT.expr())
+        + memory_expansion_gas_cost
+}
+
 #[cfg(test)]
 mod test {
     use crate::evm_circuit::{
@@ -508,7 +649,7 @@ mod test {
         rd_length: u64,
     }
 
-    fn caller(stack: Stack, caller_is_success: bool) -> Account {
+    fn caller(opcode: OpcodeId, stack: Stack, caller_is_success: bool) -> Account {
         let terminator = if caller_is_success {
             OpcodeId::RETURN
         } else {
@@ -516,27 +657,29 @@ mod test {
         };
 
         // Call twice for testing both cold and warm access
-        let bytecode = bytecode! {
-            PUSH32(Word::from(stack.rd_length))
-            PUSH32(Word::from(stack.rd_offset))
-            PUSH32(Word::from(stack.cd_length))
-            PUSH32(Word::from(stack.cd_offset))
-            PUSH32(stack.value)
-            PUSH32(Address::repeat_byte(0xff).to_word())
-            PUSH32(Word::from(stack.gas))
-            CALL
-            PUSH32(Word::from(stack.rd_length))
-            PUSH32(Word::from(stack.rd_offset))
-            PUSH32(Word::from(stack.cd_length))
-            PUSH32(Word::from(stack.cd_offset))
-            PUSH32(stack.value)
-            PUSH32(Address::repeat_byte(0xff).to_word())
-            PUSH32(Word::from(stack.gas))
-            CALL
+        let mut bytecode = Bytecode::default();
+        for _ in 0..2 {
+            bytecode.append(&bytecode! {
+                PUSH32(Word::from(stack.rd_length))
+                PUSH32(Word::from(stack.rd_offset))
+                PUSH32(Word::from(stack.cd_length))
+                PUSH32(Word::from(stack.cd_offset))
+            });
+            // Only CALL and CALLCODE take the value argument
+            if matches!(opcode, OpcodeId::CALL | OpcodeId::CALLCODE) {
+                bytecode.push(32, stack.value);
+            }
+            bytecode.append(&bytecode! {
+                PUSH32(Address::repeat_byte(0xff).to_word())
+                PUSH32(Word::from(stack.gas))
+                .write_op(opcode)
+            });
+        }
+        bytecode.append(&bytecode! {
             PUSH1(0)
             PUSH1(0)
             .write_op(terminator)
-        };
+        });
 
         Account {
             address: Address::repeat_byte(0xfe),
@@ -602,6 +745,13 @@ mod test {
         );
     }
 
+    const CALL_OPCODES: [OpcodeId; 4] = [
+        OpcodeId::CALL,
+        OpcodeId::CALLCODE,
+        OpcodeId::DELEGATECALL,
+        OpcodeId::STATICCALL,
+    ];
+
     #[test]
     fn call_gadget_simple() {
         let stacks = vec![
@@ -645,29 +795,31 @@ mod test {
             },
         ];
         let callees = vec![callee(bytecode! {}), callee(bytecode! { STOP })];
-        for (stack, callee) in stacks.into_iter().cartesian_product(callees.into_iter()) {
-            test_ok(caller(stack, true), callee, false);
+        for ((opcode, stack), callee) in CALL_OPCODES
+            .into_iter()
+            .cartesian_product(stacks.into_iter())
+            .cartesian_product(callees.into_iter())
+        {
+            test_ok(caller(opcode, stack, true), callee, false);
         }
     }
 
     #[test]
     fn call_gadget_nested() {
-        let callers = vec![
-            caller(
-                Stack {
-                    gas: 100000,
-                    ..Default::default()
-                },
-                true,
-            ),
-            caller(
-                Stack {
-                    gas: 100000,
-                    ..Default::default()
-                },
-                false,
-            ),
-        ];
+        let callers = CALL_OPCODES
+            .into_iter()
+            .cartesian_product([true, false])
+            .map(|(opcode, caller_is_success)| {
+                caller(
+                    opcode,
+                    Stack {
+                        gas: 100000,
+                        ..Default::default()
+                    },
+                    caller_is_success,
+                )
+            })
+            .collect_vec();
         let callees = vec![
             // Success
             callee(bytecode! { PUSH1(0) PUSH1(0) RETURN }),
@@ -680,6 +832,36 @@ mod test {
         }
     }
 
+    #[test]
+    fn call_gadget_context() {
+        // The callee reads its caller, value and storage, which all depend on
+        // the call kind.
+        let callee = callee(bytecode! {
+            CALLER
+            CALLVALUE
+            PUSH1(0)
+            SLOAD
+            PUSH1(0)
+            PUSH1(0)
+            RETURN
+        });
+        for opcode in CALL_OPCODES {
+            test_ok(
+                caller(
+                    opcode,
+                    Stack {
+                        gas: 100000,
+                        value: Word::from(10).pow(18.into()),
+                        ..Default::default()
+                    },
+                    true,
+                ),
+                callee.clone(),
+                false,
+            );
+        }
+    }
+
     #[test]
     fn call_gadget_recursive() {
         test_ok(
@@ -1,7 +1,7 @@
 use std::convert::TryInto;
 
 use bus_mapping::{circuit_input_builder::CopyDataType, evm::OpcodeId};
-use eth_types::{Field, ToLittleEndian};
+use eth_types::{evm_types::GasCost, Field, ToLittleEndian};
 use halo2_proofs::plonk::Error;
 
 use crate::{
@@ -37,7 +37,7 @@ pub(crate) struct CodeCopyGadget<F> {
     memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
     /// Opcode CODECOPY needs to copy code bytes into memory. We account for
     /// the copying costs using the memory copier gas gadget.
-    memory_copier_gas: MemoryCopierGasGadget<F>,
+    memory_copier_gas: MemoryCopierGasGadget<F, { GasCost::COPY.as_u64() }>,
     /// RW inverse counter from the copy table at the start of related copy
     /// steps.
     copy_rwc_inc: Cell<F>,
@@ -97,6 +97,7 @@ impl<F: Field> ExecutionGadget<F> for CodeCopyGadget<F> {
                 dst_memory_addr.length(),
                 cb.curr.state.rw_counter.expr() + cb.rw_counter_offset().expr(),
                 copy_rwc_inc.expr(),
+                0.expr(),
             );
         });
         cb.condition(not::expr(dst_memory_addr.has_length()), |cb| {
@@ -0,0 +1,580 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_GAS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        table::{AccountFieldTag, CallContextFieldTag},
+        util::{
+            common_gadget::{ContractCreateGadget, TransferGadget},
+            constraint_builder::{
+                ConstraintBuilder, ReversionInfo, StepStateTransition,
+                Transition::{Delta, To},
+            },
+            math_gadget::ConstantDivisionGadget,
+            memory_gadget::{MemoryAddressGadget, MemoryExpansionGadget, MemoryWordSizeGadget},
+            not, rlc, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use bus_mapping::{circuit_input_builder::CopyDataType, evm::OpcodeId};
+use eth_types::{evm_types::GasCost, Field, ToAddress, ToLittleEndian, ToScalar, U256};
+use halo2_proofs::plonk::Error;
+use keccak256::plain::Keccak;
+
+/// Gadget for `CREATE` and `CREATE2`, which creates a new contract at the
+/// address derived by [`ContractCreateGadget`] and switches to the call
+/// context of its init code, or returns to the caller directly when the init
+/// code is empty.
+#[derive(Clone, Debug)]
+pub(crate) struct CreateGadget<F, const IS_CREATE2: bool, const S: ExecutionState> {
+    opcode: Cell<F>,
+    tx_id: Cell<F>,
+    reversion_info: ReversionInfo<F>,
+    was_static: Cell<F>,
+    depth: Cell<F>,
+    caller_address: Cell<F>,
+    value: Word<F>,
+    init_code: MemoryAddressGadget<F>,
+    init_code_word_size: MemoryWordSizeGadget<F>,
+    init_code_rlc: Cell<F>,
+    copy_rwc_inc: Cell<F>,
+    is_success: Cell<F>,
+    caller_nonce: Cell<F>,
+    is_warm_prev: Cell<F>,
+    callee_reversion_info: ReversionInfo<F>,
+    transfer: TransferGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    one_64th_gas: ConstantDivisionGadget<F, N_BYTES_GAS>,
+    contract_create: ContractCreateGadget<F, IS_CREATE2>,
+}
+
+impl<F: Field, const IS_CREATE2: bool, const S: ExecutionState> ExecutionGadget<F>
+    for CreateGadget<F, IS_CREATE2, S>
+{
+    const NAME: &'static str = if IS_CREATE2 { "CREATE2" } else { "CREATE" };
+
+    const EXECUTION_STATE: ExecutionState = S;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+
+        // We do the responsible opcode check explicitly here because we're not using
+        // the `SameContextGadget` for `CREATE` and `CREATE2`.
+        cb.require_equal(
+            "Opcode should be CREATE or CREATE2",
+            opcode.expr(),
+            if IS_CREATE2 {
+                OpcodeId::CREATE2
+            } else {
+                OpcodeId::CREATE
+            }
+            .expr(),
+        );
+
+        let value = cb.query_word();
+        let init_code_offset = cb.query_cell();
+        let init_code_length = cb.query_rlc();
+        let is_success = cb.query_bool();
+
+        // Use rw_counter of the step which triggers next call as its call_id.
+        let callee_call_id = cb.curr.state.rw_counter.clone();
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let mut reversion_info = cb.reversion_info(None);
+        let [caller_address, was_static, depth] = [
+            CallContextFieldTag::CalleeAddress,
+            CallContextFieldTag::IsStatic,
+            CallContextFieldTag::Depth,
+        ]
+        .map(|field_tag| cb.call_context(None, field_tag));
+
+        cb.require_zero("CREATE must not be in static call stack", was_static.expr());
+        cb.range_lookup(depth.expr(), 1024);
+
+        let caller_nonce = cb.query_cell();
+        let contract_create =
+            ContractCreateGadget::construct(cb, caller_address.expr(), caller_nonce.expr());
+        let callee_address = contract_create.address();
+
+        // Lookup values from stack
+        cb.stack_pop(value.expr());
+        cb.stack_pop(init_code_offset.expr());
+        cb.stack_pop(init_code_length.expr());
+        if IS_CREATE2 {
+            cb.stack_pop(contract_create.salt());
+        }
+        cb.stack_push(is_success.expr() * contract_create.address_rlc());
+
+        // Increase caller's nonce
+        cb.account_write(
+            caller_address.expr(),
+            AccountFieldTag::Nonce,
+            caller_nonce.expr() + 1.expr(),
+            caller_nonce.expr(),
+            Some(&mut reversion_info),
+        );
+
+        // Add callee to access list
+        let is_warm_prev = cb.query_bool();
+        cb.account_access_list_write(
+            tx_id.expr(),
+            callee_address.clone(),
+            1.expr(),
+            is_warm_prev.expr(),
+            Some(&mut reversion_info),
+        );
+
+        // Propagate rw_counter_end_of_reversion and is_persistent
+        let mut callee_reversion_info = cb.reversion_info(Some(callee_call_id.expr()));
+        cb.require_equal(
+            "callee_is_persistent == is_persistent ⋅ is_success",
+            callee_reversion_info.is_persistent(),
+            reversion_info.is_persistent() * is_success.expr(),
+        );
+        cb.condition(is_success.expr() * (1.expr() - reversion_info.is_persistent()), |cb| {
+            cb.require_equal(
+                "callee_rw_counter_end_of_reversion == rw_counter_end_of_reversion - (reversible_write_counter + 2)",
+                callee_reversion_info.rw_counter_end_of_reversion(),
+                reversion_info.rw_counter_of_reversion(),
+            );
+        });
+
+        // Increase callee's nonce
+        cb.account_write(
+            callee_address.clone(),
+            AccountFieldTag::Nonce,
+            1.expr(),
+            0.expr(),
+            Some(&mut callee_reversion_info),
+        );
+
+        let transfer = TransferGadget::construct(
+            cb,
+            caller_address.expr(),
+            callee_address.clone(),
+            value.clone(),
+            &mut callee_reversion_info,
+        );
+
+        // Verify the init code hash and the contract address
+        let init_code = MemoryAddressGadget::construct(cb, init_code_offset, init_code_length);
+        let init_code_rlc = cb.query_cell();
+        cb.keccak_table_lookup(
+            init_code_rlc.expr(),
+            init_code.length(),
+            contract_create.code_hash(),
+        );
+        cb.keccak_table_lookup(
+            contract_create.input_rlc(),
+            contract_create.input_length(),
+            contract_create.keccak_output(),
+        );
+
+        // Sum up gas cost, which includes the cost to hash the init code for
+        // `CREATE2`
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [init_code.address()],
+        );
+        let init_code_word_size = MemoryWordSizeGadget::construct(cb, init_code.length());
+        let gas_cost = GasCost::CREATE.expr()
+            + memory_expansion.gas_cost()
+            + IS_CREATE2.expr() * GasCost::COPY_SHA3.expr() * init_code_word_size.expr();
+
+        // Apply EIP 150
+        let gas_available = cb.curr.state.gas_left.expr() - gas_cost.clone();
+        let one_64th_gas = ConstantDivisionGadget::construct(cb, gas_available.clone(), 64);
+        let callee_gas_left = gas_available - one_64th_gas.quotient();
+
+        let copy_rwc_inc = cb.query_cell();
+        cb.condition(not::expr(init_code.has_length()), |cb| {
+            cb.require_zero("if no init code, init_code_rlc == 0", init_code_rlc.expr());
+            cb.require_zero(
+                "if no init code, copy table rwc inc == 0",
+                copy_rwc_inc.expr(),
+            );
+            cb.require_equal(
+                "if no init code, creation succeeds",
+                is_success.expr(),
+                1.expr(),
+            );
+
+            // Save caller's call state
+            for field_tag in [
+                CallContextFieldTag::LastCalleeId,
+                CallContextFieldTag::LastCalleeReturnDataOffset,
+                CallContextFieldTag::LastCalleeReturnDataLength,
+            ] {
+                cb.call_context_lookup(true.expr(), None, field_tag, 0.expr());
+            }
+
+            cb.require_step_state_transition(StepStateTransition {
+                rw_counter: Delta(cb.rw_counter_offset()),
+                program_counter: Delta(1.expr()),
+                stack_pointer: Delta((2 + IS_CREATE2 as u64).expr()),
+                gas_left: Delta(-gas_cost.clone()),
+                memory_word_size: To(memory_expansion.next_memory_word_size()),
+                // Caller's nonce and access list, callee's nonce and transfer
+                reversible_write_counter: Delta(5.expr()),
+                ..StepStateTransition::default()
+            });
+        });
+
+        cb.condition(init_code.has_length(), |cb| {
+            // Save caller's call state
+            for (field_tag, value) in [
+                (
+                    CallContextFieldTag::ProgramCounter,
+                    cb.curr.state.program_counter.expr() + 1.expr(),
+                ),
+                (
+                    CallContextFieldTag::StackPointer,
+                    cb.curr.state.stack_pointer.expr() + (2 + IS_CREATE2 as u64).expr(),
+                ),
+                (
+                    CallContextFieldTag::GasLeft,
+                    cb.curr.state.gas_left.expr() - gas_cost - callee_gas_left.clone(),
+                ),
+                (
+                    CallContextFieldTag::MemorySize,
+                    memory_expansion.next_memory_word_size(),
+                ),
+                (
+                    CallContextFieldTag::ReversibleWriteCounter,
+                    cb.curr.state.reversible_write_counter.expr() + 2.expr(),
+                ),
+            ] {
+                cb.call_context_lookup(true.expr(), None, field_tag, value);
+            }
+
+            // Setup next call's context.
+            for (field_tag, value) in [
+                (CallContextFieldTag::CallerId, cb.curr.state.call_id.expr()),
+                (CallContextFieldTag::TxId, tx_id.expr()),
+                (CallContextFieldTag::Depth, depth.expr() + 1.expr()),
+                (CallContextFieldTag::CallerAddress, caller_address.expr()),
+                (CallContextFieldTag::CalleeAddress, callee_address.clone()),
+                (CallContextFieldTag::CallDataOffset, 0.expr()),
+                (CallContextFieldTag::CallDataLength, 0.expr()),
+                (CallContextFieldTag::ReturnDataOffset, 0.expr()),
+                (CallContextFieldTag::ReturnDataLength, 0.expr()),
+                (CallContextFieldTag::Value, value.expr()),
+                (CallContextFieldTag::IsSuccess, is_success.expr()),
+                (CallContextFieldTag::IsStatic, 0.expr()),
+                (CallContextFieldTag::LastCalleeId, 0.expr()),
+                (CallContextFieldTag::LastCalleeReturnDataOffset, 0.expr()),
+                (CallContextFieldTag::LastCalleeReturnDataLength, 0.expr()),
+                (CallContextFieldTag::IsRoot, 0.expr()),
+                (CallContextFieldTag::IsCreate, 1.expr()),
+                (CallContextFieldTag::CodeHash, contract_create.code_hash()),
+            ] {
+                cb.call_context_lookup(false.expr(), Some(callee_call_id.expr()), field_tag, value);
+            }
+
+            // Memory reads of the init code, which is accumulated into
+            // init_code_rlc
+            cb.copy_table_lookup(
+                cb.curr.state.call_id.expr(),
+                CopyDataType::Memory.expr(),
+                cb.curr.state.call_id.expr(),
+                CopyDataType::RlcAcc.expr(),
+                init_code.offset(),
+                init_code.address(),
+                0.expr(), // dst_addr for CopyDataType::RlcAcc is 0.
+                init_code.length(),
+                cb.curr.state.rw_counter.expr() + cb.rw_counter_offset(),
+                copy_rwc_inc.expr(),
+                init_code_rlc.expr(),
+            );
+
+            cb.require_step_state_transition(StepStateTransition {
+                rw_counter: Delta(cb.rw_counter_offset() + copy_rwc_inc.expr()),
+                call_id: To(callee_call_id.expr()),
+                is_root: To(false.expr()),
+                is_create: To(true.expr()),
+                code_hash: To(contract_create.code_hash()),
+                gas_left: To(callee_gas_left),
+                // Callee's nonce and transfer
+                reversible_write_counter: To(3.expr()),
+                ..StepStateTransition::new_context()
+            });
+        });
+
+        Self {
+            opcode,
+            tx_id,
+            reversion_info,
+            was_static,
+            depth,
+            caller_address,
+            value,
+            init_code,
+            init_code_word_size,
+            init_code_rlc,
+            copy_rwc_inc,
+            is_success,
+            caller_nonce,
+            is_warm_prev,
+            callee_reversion_info,
+            transfer,
+            memory_expansion,
+            one_64th_gas,
+            contract_create,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        let [tx_id, caller_address, was_static, depth] = [
+            step.rw_indices[0],
+            step.rw_indices[3],
+            step.rw_indices[4],
+            step.rw_indices[5],
+        ]
+        .map(|idx| block.rws[idx].call_context_value());
+        self.tx_id
+            .assign(region, offset, Some(F::from(tx_id.low_u64())))?;
+        self.reversion_info.assign(
+            region,
+            offset,
+            call.rw_counter_end_of_reversion,
+            call.is_persistent,
+        )?;
+        self.was_static
+            .assign(region, offset, Some(F::from(was_static.low_u64())))?;
+        self.depth
+            .assign(region, offset, Some(F::from(depth.low_u64())))?;
+        self.caller_address
+            .assign(region, offset, caller_address.to_scalar())?;
+
+        // Stack pops of value, init code offset, init code length and salt for
+        // `CREATE2`, and the stack push of the contract address.
+        let [value, init_code_offset, init_code_length] =
+            [6, 7, 8].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        let salt = if IS_CREATE2 {
+            block.rws[step.rw_indices[9]].stack_value()
+        } else {
+            U256::zero()
+        };
+        let rw_offset = 9 + IS_CREATE2 as usize;
+        let callee_address = block.rws[step.rw_indices[rw_offset]].stack_value();
+        let is_success = !callee_address.is_zero();
+
+        self.value
+            .assign(region, offset, Some(value.to_le_bytes()))?;
+        let init_code_address = self.init_code.assign(
+            region,
+            offset,
+            init_code_offset,
+            init_code_length,
+            block.randomness,
+        )?;
+        self.is_success
+            .assign(region, offset, Some(F::from(is_success as u64)))?;
+
+        let (_, caller_nonce) = block.rws[step.rw_indices[rw_offset + 1]].account_value_pair();
+        self.caller_nonce
+            .assign(region, offset, Some(F::from(caller_nonce.low_u64())))?;
+        let (_, is_warm_prev) =
+            block.rws[step.rw_indices[rw_offset + 2]].tx_access_list_value_pair();
+        self.is_warm_prev
+            .assign(region, offset, Some(F::from(is_warm_prev as u64)))?;
+
+        let [callee_rw_counter_end_of_reversion, callee_is_persistent] =
+            [rw_offset + 3, rw_offset + 4]
+                .map(|idx| block.rws[step.rw_indices[idx]].call_context_value());
+        self.callee_reversion_info.assign(
+            region,
+            offset,
+            callee_rw_counter_end_of_reversion.low_u64() as usize,
+            callee_is_persistent.low_u64() != 0,
+        )?;
+
+        let [caller_balance_pair, callee_balance_pair] = [rw_offset + 6, rw_offset + 7]
+            .map(|idx| block.rws[step.rw_indices[idx]].account_value_pair());
+        self.transfer.assign(
+            region,
+            offset,
+            caller_balance_pair,
+            callee_balance_pair,
+            value,
+        )?;
+
+        // The memory reads of the init code come after the 5 caller writes and
+        // the 18 callee reads of the call context.
+        let init_code: Vec<u8> = (0..init_code_length.low_u64() as usize)
+            .map(|idx| block.rws[step.rw_indices[rw_offset + 31 + idx]].memory_value())
+            .collect();
+        self.init_code_rlc.assign(
+            region,
+            offset,
+            Some(rlc::value(&init_code, block.randomness)),
+        )?;
+        let copy_rwc_inc = block
+            .copy_events
+            .get(&(tx.id, call.id, step.program_counter as usize))
+            .and_then(|copy_event| copy_event.steps.first())
+            .map_or(F::zero(), |cs| F::from(cs.rwc_inc_left));
+        self.copy_rwc_inc
+            .assign(region, offset, Some(copy_rwc_inc))?;
+
+        let mut keccak = Keccak::default();
+        keccak.update(&init_code);
+        let code_hash = U256::from_big_endian(&keccak.digest());
+        self.contract_create.assign(
+            region,
+            offset,
+            caller_address.to_address(),
+            caller_nonce.low_u64(),
+            code_hash,
+            salt,
+        )?;
+
+        let (_, memory_expansion_gas_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [init_code_address],
+        )?;
+        let init_code_word_size =
+            self.init_code_word_size
+                .assign(region, offset, init_code_length.low_u64())?;
+        let gas_cost = GasCost::CREATE.as_u64()
+            + memory_expansion_gas_cost
+            + if IS_CREATE2 {
+                GasCost::COPY_SHA3.as_u64() * init_code_word_size
+            } else {
+                0
+            };
+        let gas_available = step.gas_left - gas_cost;
+        self.one_64th_gas
+            .assign(region, offset, gas_available as u128)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{
+        address, bytecode, evm_types::OpcodeId, geth_types::Account, Address, Bytecode, Word,
+    };
+    use mock::TestContext;
+
+    // RETURN the deployed code `0x00` (STOP) from the init code.
+    fn init_code() -> Bytecode {
+        bytecode! {
+            PUSH1(0)
+            PUSH1(0)
+            MSTORE8
+            PUSH1(1)
+            PUSH1(0)
+            RETURN
+        }
+    }
+
+    fn creator(init_code: &[u8], is_create2: bool, value: u64) -> Account {
+        let mut code = Bytecode::default();
+        for (idx, byte) in init_code.iter().enumerate() {
+            code.append(&bytecode! {
+                PUSH1(*byte)
+                PUSH32(Word::from(idx))
+                MSTORE8
+            });
+        }
+        if is_create2 {
+            code.append(&bytecode! {PUSH32(Word::from(0xcafe))});
+        }
+        code.append(&bytecode! {
+            PUSH32(Word::from(init_code.len()))
+            PUSH1(0)
+            PUSH32(Word::from(value))
+        });
+        code.write_op(if is_create2 {
+            OpcodeId::CREATE2
+        } else {
+            OpcodeId::CREATE
+        });
+        code.append(&bytecode! {
+            POP
+            STOP
+        });
+
+        Account {
+            address: Address::repeat_byte(0xfe),
+            balance: Word::from(10).pow(20.into()),
+            code: code.to_vec().into(),
+            ..Default::default()
+        }
+    }
+
+    fn test_ok(creator: Account) {
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(Word::from(10u64.pow(19)));
+                accs[1]
+                    .address(creator.address)
+                    .code(creator.code)
+                    .nonce(creator.nonce)
+                    .balance(creator.balance);
+                accs[2]
+                    .address(address!("0x000000000000000000000000000000000000beef"))
+                    .balance(Word::from(10u64.pow(19)));
+            },
+            |mut txs, accs| {
+                txs[0]
+                    .from(accs[0].address)
+                    .to(accs[1].address)
+                    .gas(1000000.into());
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn create_gadget_empty_init_code() {
+        for is_create2 in [false, true] {
+            test_ok(creator(&[], is_create2, 0));
+            test_ok(creator(&[], is_create2, 1000));
+        }
+    }
+
+    #[test]
+    fn create_gadget_non_empty_init_code() {
+        for is_create2 in [false, true] {
+            test_ok(creator(&init_code().to_vec(), is_create2, 0));
+            test_ok(creator(&init_code().to_vec(), is_create2, 1000));
+        }
+    }
+
+    #[test]
+    fn create_gadget_nonce() {
+        for nonce in [1u64, 0x7f, 0x80, 0xff, 0x100, 0xffff, 0x1000000] {
+            let mut creator = creator(&init_code().to_vec(), false, 0);
+            creator.nonce = Word::from(nonce);
+            test_ok(creator);
+        }
+    }
+}
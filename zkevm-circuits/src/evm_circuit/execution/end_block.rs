@@ -43,3 +43,58 @@ impl<F: Field> ExecutionGadget<F> for EndBlockGadget<F> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::evm_circuit::{
+        test::run_test_circuit_incomplete_fixed_table,
+        witness::{block_convert, Block},
+        EvmCircuit,
+    };
+    use bus_mapping::mock::BlockData;
+    use eth_types::{bytecode, geth_types::GethData};
+    use halo2_proofs::pairing::bn256::Fr;
+    use mock::TestContext;
+
+    fn block() -> Block<Fr> {
+        let block: GethData = TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode! { STOP })
+            .unwrap()
+            .into();
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+        block_convert(&builder.block, &builder.code_db)
+    }
+
+    #[test]
+    fn end_block_padding() {
+        let mut block = block();
+
+        // The padding doesn't need to be a multiple of the EndBlock height, and
+        // the last step starts in any of the rows after the padding.
+        for padding in [0, 1, 2, 3, 50] {
+            block.evm_circuit_pad_to = 0;
+            block.evm_circuit_pad_to = EvmCircuit::get_num_rows_required(&block) + padding;
+            assert_eq!(
+                run_test_circuit_incomplete_fixed_table(block.clone()),
+                Ok(())
+            );
+        }
+    }
+
+    #[test]
+    fn end_block_padding_after_end_tx() {
+        let mut block = block();
+        // Without its EndBlock step, the block ends with an EndTx step, which
+        // is the last step if the block isn't padded, and otherwise transits
+        // to the padding with a wrong rw counter.
+        block.txs.last_mut().unwrap().steps.pop();
+        assert_eq!(
+            run_test_circuit_incomplete_fixed_table(block.clone()),
+            Ok(())
+        );
+        block.evm_circuit_pad_to = EvmCircuit::get_num_rows_required(&block);
+        assert!(run_test_circuit_incomplete_fixed_table(block).is_err());
+    }
+}
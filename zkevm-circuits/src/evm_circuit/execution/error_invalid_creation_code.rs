@@ -0,0 +1,103 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget, constraint_builder::ConstraintBuilder,
+            memory_gadget::MemoryAddressGadget, CachedRegion, Cell,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{OpcodeId, INVALID_INIT_CODE_FIRST_BYTE},
+    Field,
+};
+use halo2_proofs::plonk::Error;
+
+/// Gadget to implement the corresponding error for the code deposit of a
+/// creation call, when the code returned by [`OpcodeId::RETURN`] starts with
+/// the byte `0xef` (EIP-3541).
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorInvalidCreationCodeGadget<F> {
+    opcode: Cell<F>,
+    memory_address: MemoryAddressGadget<F>,
+    first_byte: Cell<F>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorInvalidCreationCodeGadget<F> {
+    const NAME: &'static str = "ErrorInvalidCreationCode";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorInvalidCreationCode;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_equal(
+            "ErrorInvalidCreationCode opcode must be RETURN",
+            opcode.expr(),
+            OpcodeId::RETURN.expr(),
+        );
+        cb.require_equal(
+            "ErrorInvalidCreationCode only happens in a creation call",
+            cb.curr.state.is_create.expr(),
+            1.expr(),
+        );
+
+        let offset = cb.query_cell();
+        let length = cb.query_rlc();
+        cb.stack_pop(offset.expr());
+        cb.stack_pop(length.expr());
+        let memory_address = MemoryAddressGadget::construct(cb, offset, length);
+        cb.require_equal(
+            "Code to deposit is not empty",
+            memory_address.has_length(),
+            1.expr(),
+        );
+
+        // Read the first byte of the code and check it's invalid
+        let first_byte = cb.query_cell();
+        cb.memory_lookup(0.expr(), memory_address.offset(), first_byte.expr(), None);
+        cb.require_equal(
+            "First byte of the code is 0xef",
+            first_byte.expr(),
+            INVALID_INIT_CODE_FIRST_BYTE.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            memory_address,
+            first_byte,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        let [memory_offset, length] =
+            [0, 1].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        self.memory_address
+            .assign(region, offset, memory_offset, length, block.randomness)?;
+        let first_byte = block.rws[step.rw_indices[2]].memory_value();
+        self.first_byte
+            .assign(region, offset, Some(F::from(first_byte as u64)))?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 3)
+    }
+}
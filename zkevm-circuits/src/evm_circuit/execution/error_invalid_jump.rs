@@ -0,0 +1,264 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_U64,
+        step::ExecutionState,
+        table::{FixedTableTag, Lookup},
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            from_bytes,
+            math_gadget::{IsEqualGadget, IsZeroGadget, LtGadget},
+            sum, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{evm_types::OpcodeId, Bytecode, Field, ToLittleEndian};
+use halo2_proofs::plonk::Error;
+
+/// Gadget to implement the corresponding invalid jump error, when
+/// [`OpcodeId::JUMP`] or [`OpcodeId::JUMPI`] with a non-zero condition jumps to
+/// a destination which is out of the code range, not a [`OpcodeId::JUMPDEST`],
+/// or inside the data of a PUSH.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorInvalidJumpGadget<F> {
+    opcode: Cell<F>,
+    is_jumpi: IsEqualGadget<F>,
+    destination: Word<F>,
+    condition: Cell<F>,
+    is_condition_zero: IsZeroGadget<F>,
+    code_length: Cell<F>,
+    // Whether the `destination` fits into u64.
+    is_destination_within_u64: IsZeroGadget<F>,
+    // Whether `destination < code_length`.
+    is_destination_within_range: LtGadget<F, N_BYTES_U64>,
+    destination_is_code: Cell<F>,
+    destination_value: Cell<F>,
+    is_jumpdest: IsEqualGadget<F>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorInvalidJumpGadget<F> {
+    const NAME: &'static str = "ErrorInvalidJump";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorInvalidJump;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.add_lookup(
+            "Responsible opcode lookup",
+            Lookup::Fixed {
+                tag: FixedTableTag::ResponsibleOpcode.expr(),
+                values: [
+                    Self::EXECUTION_STATE.as_u64().expr(),
+                    opcode.expr(),
+                    0.expr(),
+                ],
+            },
+        );
+        let is_jumpi = IsEqualGadget::construct(cb, opcode.expr(), OpcodeId::JUMPI.expr());
+
+        // Pop the destination, and the condition for JUMPI, from the stack
+        let destination = cb.query_word();
+        let condition = cb.query_cell();
+        cb.stack_pop(destination.expr());
+        cb.condition(is_jumpi.expr(), |cb| {
+            cb.stack_pop(condition.expr());
+        });
+
+        // JUMPI only jumps when the condition is non-zero
+        let is_condition_zero = IsZeroGadget::construct(cb, condition.expr());
+        cb.condition(is_jumpi.expr(), |cb| {
+            cb.require_zero("JUMPI condition is non-zero", is_condition_zero.expr());
+        });
+
+        // Lookup the byte at the destination when it's within the code range
+        let code_length = cb.bytecode_length(cb.curr.state.code_hash.expr());
+        let is_destination_within_u64 =
+            IsZeroGadget::construct(cb, sum::expr(&destination.cells[N_BYTES_U64..]));
+        let destination_u64 = from_bytes::expr(&destination.cells[..N_BYTES_U64]);
+        let is_destination_within_range =
+            LtGadget::construct(cb, destination_u64.clone(), code_length.expr());
+        let destination_is_code = cb.query_cell();
+        let destination_value = cb.query_cell();
+        let is_jumpdest =
+            IsEqualGadget::construct(cb, destination_value.expr(), OpcodeId::JUMPDEST.expr());
+        cb.condition(
+            is_destination_within_u64.expr() * is_destination_within_range.expr(),
+            |cb| {
+                cb.bytecode_lookup(
+                    cb.curr.state.code_hash.expr(),
+                    destination_u64,
+                    destination_is_code.expr(),
+                    destination_value.expr(),
+                );
+                cb.require_zero(
+                    "Destination within range is not a JUMPDEST or inside PUSH data",
+                    destination_is_code.expr() * is_jumpdest.expr(),
+                );
+            },
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            is_jumpi,
+            destination,
+            condition,
+            is_condition_zero,
+            code_length,
+            is_destination_within_u64,
+            is_destination_within_range,
+            destination_is_code,
+            destination_value,
+            is_jumpdest,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+        let is_jumpi = opcode == OpcodeId::JUMPI;
+        self.is_jumpi.assign(
+            region,
+            offset,
+            F::from(opcode.as_u64()),
+            F::from(OpcodeId::JUMPI.as_u64()),
+        )?;
+
+        let destination = block.rws[step.rw_indices[0]].stack_value();
+        let condition = if is_jumpi {
+            Word::random_linear_combine(
+                block.rws[step.rw_indices[1]].stack_value().to_le_bytes(),
+                block.randomness,
+            )
+        } else {
+            F::zero()
+        };
+        self.destination
+            .assign(region, offset, Some(destination.to_le_bytes()))?;
+        self.condition.assign(region, offset, Some(condition))?;
+        self.is_condition_zero.assign(region, offset, condition)?;
+
+        let code = block
+            .bytecodes
+            .get(&call.code_hash)
+            .expect("could not find current environment's bytecode");
+        let code_length = code.bytes.len() as u64;
+        self.code_length
+            .assign(region, offset, Some(F::from(code_length)))?;
+
+        let destination_bytes = destination.to_le_bytes();
+        self.is_destination_within_u64.assign(
+            region,
+            offset,
+            sum::value(&destination_bytes[N_BYTES_U64..]),
+        )?;
+        self.is_destination_within_range.assign(
+            region,
+            offset,
+            F::from(destination.low_u64()),
+            F::from(code_length),
+        )?;
+
+        let (destination_is_code, destination_value) = if destination < code_length.into() {
+            Bytecode::from(code.bytes.clone())
+                .get(destination.as_usize())
+                .map_or((false, 0), |e| (e.is_code, e.value))
+        } else {
+            (false, 0)
+        };
+        self.destination_is_code.assign(
+            region,
+            offset,
+            Some(F::from(destination_is_code as u64)),
+        )?;
+        self.destination_value
+            .assign(region, offset, Some(F::from(destination_value as u64)))?;
+        self.is_jumpdest.assign(
+            region,
+            offset,
+            F::from(destination_value as u64),
+            F::from(OpcodeId::JUMPDEST.as_u64()),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 1 + is_jumpi as usize)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{bytecode, Bytecode, Word};
+    use mock::test_ctx::{helpers::*, TestContext};
+
+    fn test_ok(code: Bytecode) {
+        assert_eq!(
+            run_test_circuits(
+                TestContext::<2, 1>::new(
+                    None,
+                    account_0_code_account_1_no_code(code),
+                    tx_from_1_to_0,
+                    |block, _tx| block.number(0xcafeu64),
+                )
+                .unwrap(),
+                None
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn error_invalid_jump_out_of_range() {
+        test_ok(bytecode! {
+            PUSH1(0x10)
+            JUMP
+            STOP
+        });
+        test_ok(bytecode! {
+            PUSH32(Word::MAX)
+            JUMP
+            STOP
+        });
+    }
+
+    #[test]
+    fn error_invalid_jump_not_jumpdest() {
+        test_ok(bytecode! {
+            PUSH1(0x3)
+            JUMP
+            STOP
+        });
+        test_ok(bytecode! {
+            PUSH1(0x1)
+            PUSH1(0x5)
+            JUMPI
+            STOP
+        });
+    }
+
+    #[test]
+    fn error_invalid_jump_into_push_data() {
+        test_ok(bytecode! {
+            PUSH1(0x4)
+            JUMP
+            PUSH1(0x5b)
+            STOP
+        });
+    }
+}
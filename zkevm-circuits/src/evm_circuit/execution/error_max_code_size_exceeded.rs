@@ -0,0 +1,102 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_MEMORY_ADDRESS,
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget, constraint_builder::ConstraintBuilder,
+            math_gadget::LtGadget, memory_gadget::MemoryAddressGadget, CachedRegion, Cell,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{OpcodeId, MAX_CODE_SIZE},
+    Field,
+};
+use halo2_proofs::plonk::Error;
+
+/// Gadget to implement the corresponding error for the code deposit of a
+/// creation call, when the code returned by [`OpcodeId::RETURN`] is larger
+/// than [`MAX_CODE_SIZE`].
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorMaxCodeSizeExceededGadget<F> {
+    opcode: Cell<F>,
+    memory_address: MemoryAddressGadget<F>,
+    code_size_exceeded: LtGadget<F, N_BYTES_MEMORY_ADDRESS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorMaxCodeSizeExceededGadget<F> {
+    const NAME: &'static str = "ErrorMaxCodeSizeExceeded";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorMaxCodeSizeExceeded;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_equal(
+            "ErrorMaxCodeSizeExceeded opcode must be RETURN",
+            opcode.expr(),
+            OpcodeId::RETURN.expr(),
+        );
+        cb.require_equal(
+            "ErrorMaxCodeSizeExceeded only happens in a creation call",
+            cb.curr.state.is_create.expr(),
+            1.expr(),
+        );
+
+        let offset = cb.query_cell();
+        let length = cb.query_rlc();
+        cb.stack_pop(offset.expr());
+        cb.stack_pop(length.expr());
+        let memory_address = MemoryAddressGadget::construct(cb, offset, length);
+
+        // Check if the length of the code is greater than MAX_CODE_SIZE
+        let code_size_exceeded =
+            LtGadget::construct(cb, MAX_CODE_SIZE.expr(), memory_address.length());
+        cb.require_equal(
+            "MAX_CODE_SIZE < length",
+            code_size_exceeded.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            memory_address,
+            code_size_exceeded,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        let [memory_offset, length] =
+            [0, 1].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        self.memory_address
+            .assign(region, offset, memory_offset, length, block.randomness)?;
+        self.code_size_exceeded.assign(
+            region,
+            offset,
+            F::from(MAX_CODE_SIZE),
+            F::from(length.low_u64()),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 2)
+    }
+}
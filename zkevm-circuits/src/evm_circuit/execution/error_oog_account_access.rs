@@ -0,0 +1,127 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_GAS},
+        step::ExecutionState,
+        table::CallContextFieldTag,
+        util::{
+            common_gadget::CommonErrorGadget, constraint_builder::ConstraintBuilder, from_bytes,
+            math_gadget::LtGadget, select, CachedRegion, Cell, RandomLinearCombination,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{GasCost, OpcodeId},
+    Field, ToAddress, ToScalar, U256,
+};
+use halo2_proofs::plonk::Error;
+
+/// Gadget to implement the corresponding out of gas error for
+/// [`OpcodeId::BALANCE`], [`OpcodeId::EXTCODESIZE`] and
+/// [`OpcodeId::EXTCODEHASH`].
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGAccountAccessGadget<F> {
+    opcode: Cell<F>,
+    address: RandomLinearCombination<F, N_BYTES_ACCOUNT_ADDRESS>,
+    tx_id: Cell<F>,
+    is_warm: Cell<F>,
+    insufficient_gas_cost: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGAccountAccessGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasAccountAccess";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasAccountAccess;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_in_set(
+            "ErrorOutOfGasAccountAccess opcode must be BALANCE, EXTCODESIZE or EXTCODEHASH",
+            opcode.expr(),
+            vec![
+                OpcodeId::BALANCE.expr(),
+                OpcodeId::EXTCODESIZE.expr(),
+                OpcodeId::EXTCODEHASH.expr(),
+            ],
+        );
+
+        let address = cb.query_rlc();
+        cb.stack_pop(address.expr());
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let is_warm = cb.query_bool();
+        cb.account_access_list_read(
+            tx_id.expr(),
+            from_bytes::expr(&address.cells),
+            is_warm.expr(),
+        );
+
+        // Check if the amount of gas available is less than the amount of gas
+        // required
+        let insufficient_gas_cost = LtGadget::construct(
+            cb,
+            cb.curr.state.gas_left.expr(),
+            select::expr(
+                is_warm.expr(),
+                GasCost::WARM_ACCESS.expr(),
+                GasCost::COLD_ACCOUNT_ACCESS.expr(),
+            ),
+        );
+        cb.require_equal(
+            "gas_left < gas_cost",
+            insufficient_gas_cost.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            address,
+            tx_id,
+            is_warm,
+            insufficient_gas_cost,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        let address = block.rws[step.rw_indices[0]].stack_value().to_address();
+        let mut le_bytes = address.0;
+        le_bytes.reverse();
+        self.address.assign(region, offset, Some(le_bytes))?;
+
+        self.tx_id
+            .assign(region, offset, U256::from(tx.id).to_scalar())?;
+
+        let (is_warm, _) = block.rws[step.rw_indices[2]].tx_access_list_value_pair();
+        self.is_warm
+            .assign(region, offset, Some(F::from(is_warm as u64)))?;
+
+        self.insufficient_gas_cost.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(step.gas_cost),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 3)
+    }
+}
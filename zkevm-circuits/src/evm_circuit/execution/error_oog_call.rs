@@ -0,0 +1,281 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_GAS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        table::{AccountFieldTag, CallContextFieldTag},
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            from_bytes,
+            math_gadget::{BatchedIsZeroGadget, IsEqualGadget, IsZeroGadget, LtGadget},
+            memory_gadget::{MemoryAddressGadget, MemoryExpansionGadget},
+            sum, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian, ToScalar, U256};
+use halo2_proofs::plonk::Error;
+use keccak256::EMPTY_HASH_LE;
+
+use super::call::call_gas_cost_expr;
+
+/// Gadget to implement the corresponding out of gas error for
+/// [`OpcodeId::CALL`], [`OpcodeId::CALLCODE`], [`OpcodeId::DELEGATECALL`] and
+/// [`OpcodeId::STATICCALL`], each of which has its own execution state `S`.
+/// The callee account is read to tell if a `CALL` with value creates a new
+/// account.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGCallGadget<F, const S: ExecutionState> {
+    opcode: Cell<F>,
+    gas: Word<F>,
+    callee_address: Word<F>,
+    value: Word<F>,
+    cd_address: MemoryAddressGadget<F>,
+    rd_address: MemoryAddressGadget<F>,
+    tx_id: Cell<F>,
+    is_warm: Cell<F>,
+    callee_nonce: Cell<F>,
+    callee_balance: Cell<F>,
+    callee_code_hash: Cell<F>,
+    value_is_zero: IsZeroGadget<F>,
+    is_account_empty: BatchedIsZeroGadget<F, 2>,
+    is_empty_code_hash: IsEqualGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, 2, N_BYTES_MEMORY_WORD_SIZE>,
+    insufficient_gas_cost: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field, const S: ExecutionState> ErrorOOGCallGadget<F, S> {
+    const OPCODE: OpcodeId = match S {
+        ExecutionState::ErrorOutOfGasCALL => OpcodeId::CALL,
+        ExecutionState::ErrorOutOfGasCALLCODE => OpcodeId::CALLCODE,
+        ExecutionState::ErrorOutOfGasDELEGATECALL => OpcodeId::DELEGATECALL,
+        ExecutionState::ErrorOutOfGasSTATICCALL => OpcodeId::STATICCALL,
+        _ => unreachable!(),
+    };
+
+    /// Only `CALL` and `CALLCODE` take the value argument from stack.
+    const HAS_VALUE_ARG: bool = matches!(Self::OPCODE, OpcodeId::CALL | OpcodeId::CALLCODE);
+}
+
+impl<F: Field, const S: ExecutionState> ExecutionGadget<F> for ErrorOOGCallGadget<F, S> {
+    const NAME: &'static str = match S {
+        ExecutionState::ErrorOutOfGasCALL => "ErrorOutOfGasCALL",
+        ExecutionState::ErrorOutOfGasCALLCODE => "ErrorOutOfGasCALLCODE",
+        ExecutionState::ErrorOutOfGasDELEGATECALL => "ErrorOutOfGasDELEGATECALL",
+        ExecutionState::ErrorOutOfGasSTATICCALL => "ErrorOutOfGasSTATICCALL",
+        _ => unreachable!(),
+    };
+
+    const EXECUTION_STATE: ExecutionState = S;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_equal(
+            "ErrorOutOfGas opcode must match the call execution state",
+            opcode.expr(),
+            Self::OPCODE.expr(),
+        );
+
+        let gas = cb.query_word();
+        let callee_address = cb.query_word();
+        let value = cb.query_word();
+        let cd_offset = cb.query_cell();
+        let cd_length = cb.query_rlc();
+        let rd_offset = cb.query_cell();
+        let rd_length = cb.query_rlc();
+
+        cb.stack_pop(gas.expr());
+        cb.stack_pop(callee_address.expr());
+        if Self::HAS_VALUE_ARG {
+            cb.stack_pop(value.expr());
+        } else {
+            cb.require_zero(
+                "DELEGATECALL and STATICCALL have no value argument",
+                sum::expr(&value.cells),
+            );
+        }
+        cb.stack_pop(cd_offset.expr());
+        cb.stack_pop(cd_length.expr());
+        cb.stack_pop(rd_offset.expr());
+        cb.stack_pop(rd_length.expr());
+
+        let cd_address = MemoryAddressGadget::construct(cb, cd_offset, cd_length);
+        let rd_address = MemoryAddressGadget::construct(cb, rd_offset, rd_length);
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [cd_address.address(), rd_address.address()],
+        );
+
+        // The access list is only read, since the callee isn't accessed when
+        // the gas is insufficient.
+        let callee_address_expr =
+            from_bytes::expr(&callee_address.cells[..N_BYTES_ACCOUNT_ADDRESS]);
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let is_warm = cb.query_bool();
+        cb.account_access_list_read(tx_id.expr(), callee_address_expr.clone(), is_warm.expr());
+
+        let [callee_nonce, callee_balance, callee_code_hash] = [
+            AccountFieldTag::Nonce,
+            AccountFieldTag::Balance,
+            AccountFieldTag::CodeHash,
+        ]
+        .map(|field_tag| {
+            let value = cb.query_cell();
+            cb.account_read(callee_address_expr.clone(), field_tag, value.expr());
+            value
+        });
+        let is_account_empty =
+            BatchedIsZeroGadget::construct(cb, [callee_nonce.expr(), callee_balance.expr()]);
+        let is_empty_code_hash = IsEqualGadget::construct(
+            cb,
+            callee_code_hash.expr(),
+            Word::random_linear_combine_expr(
+                (*EMPTY_HASH_LE).map(|byte| byte.expr()),
+                cb.power_of_randomness(),
+            ),
+        );
+        let value_is_zero = IsZeroGadget::construct(cb, sum::expr(&value.cells));
+
+        // Check if the amount of gas available is less than the amount of gas
+        // required
+        let gas_cost = call_gas_cost_expr(
+            is_warm.expr(),
+            1.expr() - value_is_zero.expr(),
+            (Self::OPCODE == OpcodeId::CALL).expr(),
+            is_account_empty.expr(),
+            is_empty_code_hash.expr(),
+            memory_expansion.gas_cost(),
+        );
+        let insufficient_gas_cost =
+            LtGadget::construct(cb, cb.curr.state.gas_left.expr(), gas_cost);
+        cb.require_equal(
+            "gas_left < gas_cost",
+            insufficient_gas_cost.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            gas,
+            callee_address,
+            value,
+            cd_address,
+            rd_address,
+            tx_id,
+            is_warm,
+            callee_nonce,
+            callee_balance,
+            callee_code_hash,
+            value_is_zero,
+            is_account_empty,
+            is_empty_code_hash,
+            memory_expansion,
+            insufficient_gas_cost,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        // `CALL` and `CALLCODE` have 1 extra stack read, which shifts the rw
+        // indices.
+        let rw_offset = Self::HAS_VALUE_ARG as usize;
+        let [gas, callee_address, cd_offset, cd_length, rd_offset, rd_length] = [
+            step.rw_indices[0],
+            step.rw_indices[1],
+            step.rw_indices[rw_offset + 2],
+            step.rw_indices[rw_offset + 3],
+            step.rw_indices[rw_offset + 4],
+            step.rw_indices[rw_offset + 5],
+        ]
+        .map(|idx| block.rws[idx].stack_value());
+        let value = if Self::HAS_VALUE_ARG {
+            block.rws[step.rw_indices[2]].stack_value()
+        } else {
+            U256::zero()
+        };
+        self.gas.assign(region, offset, Some(gas.to_le_bytes()))?;
+        self.callee_address
+            .assign(region, offset, Some(callee_address.to_le_bytes()))?;
+        self.value
+            .assign(region, offset, Some(value.to_le_bytes()))?;
+        self.value_is_zero
+            .assign(region, offset, sum::value(&value.to_le_bytes()))?;
+
+        let cd_address =
+            self.cd_address
+                .assign(region, offset, cd_offset, cd_length, block.randomness)?;
+        let rd_address =
+            self.rd_address
+                .assign(region, offset, rd_offset, rd_length, block.randomness)?;
+        self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [cd_address, rd_address],
+        )?;
+
+        self.tx_id
+            .assign(region, offset, Some(F::from(tx.id as u64)))?;
+        let (is_warm, _) = block.rws[step.rw_indices[rw_offset + 7]].tx_access_list_value_pair();
+        self.is_warm
+            .assign(region, offset, Some(F::from(is_warm as u64)))?;
+
+        let [callee_nonce, callee_balance, callee_code_hash] = [
+            step.rw_indices[rw_offset + 8],
+            step.rw_indices[rw_offset + 9],
+            step.rw_indices[rw_offset + 10],
+        ]
+        .map(|idx| block.rws[idx].account_value_pair().0);
+        let callee_balance =
+            Word::random_linear_combine(callee_balance.to_le_bytes(), block.randomness);
+        let callee_code_hash =
+            Word::random_linear_combine(callee_code_hash.to_le_bytes(), block.randomness);
+        self.callee_nonce
+            .assign(region, offset, callee_nonce.to_scalar())?;
+        self.callee_balance
+            .assign(region, offset, Some(callee_balance))?;
+        self.callee_code_hash
+            .assign(region, offset, Some(callee_code_hash))?;
+        self.is_account_empty.assign(
+            region,
+            offset,
+            [F::from(callee_nonce.low_u64()), callee_balance],
+        )?;
+        self.is_empty_code_hash.assign(
+            region,
+            offset,
+            callee_code_hash,
+            Word::random_linear_combine(*EMPTY_HASH_LE, block.randomness),
+        )?;
+
+        self.insufficient_gas_cost.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(step.gas_cost),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, rw_offset + 11)
+    }
+}
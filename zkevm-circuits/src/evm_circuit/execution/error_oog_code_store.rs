@@ -0,0 +1,121 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_GAS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            math_gadget::LtGadget,
+            memory_gadget::{MemoryAddressGadget, MemoryExpansionGadget},
+            CachedRegion, Cell,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{GasCost, OpcodeId},
+    Field,
+};
+use halo2_proofs::plonk::Error;
+
+/// Gadget to implement the corresponding out of gas error for the code deposit
+/// of a creation call, when the gas left after [`OpcodeId::RETURN`] isn't
+/// enough to store the returned code.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGCodeStoreGadget<F> {
+    opcode: Cell<F>,
+    memory_address: MemoryAddressGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    insufficient_gas: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGCodeStoreGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasCodeStore";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasCodeStore;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_equal(
+            "ErrorOutOfGasCodeStore opcode must be RETURN",
+            opcode.expr(),
+            OpcodeId::RETURN.expr(),
+        );
+        cb.require_equal(
+            "ErrorOutOfGasCodeStore only happens in a creation call",
+            cb.curr.state.is_create.expr(),
+            1.expr(),
+        );
+
+        let offset = cb.query_cell();
+        let length = cb.query_rlc();
+        cb.stack_pop(offset.expr());
+        cb.stack_pop(length.expr());
+        let memory_address = MemoryAddressGadget::construct(cb, offset, length);
+
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [memory_address.address()],
+        );
+
+        // Check if the amount of gas left after the memory expansion is less
+        // than the amount of gas required to store the code
+        let insufficient_gas = LtGadget::construct(
+            cb,
+            cb.curr.state.gas_left.expr() - memory_expansion.gas_cost(),
+            GasCost::CODE_DEPOSIT_BYTE_COST.expr() * memory_address.length(),
+        );
+        cb.require_equal(
+            "gas_left - memory_expansion_gas_cost < code_deposit_gas_cost",
+            insufficient_gas.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            memory_address,
+            memory_expansion,
+            insufficient_gas,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        let [memory_offset, length] =
+            [0, 1].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        let address =
+            self.memory_address
+                .assign(region, offset, memory_offset, length, block.randomness)?;
+        let (_, memory_expansion_gas_cost) =
+            self.memory_expansion
+                .assign(region, offset, step.memory_word_size(), [address])?;
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(step.gas_left - memory_expansion_gas_cost),
+            F::from(GasCost::CODE_DEPOSIT_BYTE_COST.as_u64() * length.low_u64()),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 2)
+    }
+}
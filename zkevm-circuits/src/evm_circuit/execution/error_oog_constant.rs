@@ -0,0 +1,134 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_GAS,
+        step::ExecutionState,
+        table::{FixedTableTag, Lookup},
+        util::{
+            common_gadget::CommonErrorGadget, constraint_builder::ConstraintBuilder,
+            math_gadget::LtGadget, CachedRegion, Cell,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::Field;
+use halo2_proofs::plonk::Error;
+
+/// Gadget to implement the corresponding out of gas error for opcodes whose
+/// constant gas cost is already more than the gas left.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGConstantGadget<F> {
+    opcode: Cell<F>,
+    gas_cost: Cell<F>,
+    insufficient_gas_cost: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGConstantGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasConstant";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasConstant;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+
+        let gas_cost = cb.query_cell();
+        cb.add_lookup(
+            "Constant gas cost lookup",
+            Lookup::Fixed {
+                tag: FixedTableTag::ConstantGasCost.expr(),
+                values: [opcode.expr(), gas_cost.expr(), 0.expr()],
+            },
+        );
+
+        // Check if the amount of gas available is less than the amount of gas
+        // required
+        let insufficient_gas_cost =
+            LtGadget::construct(cb, cb.curr.state.gas_left.expr(), gas_cost.expr());
+        cb.require_equal(
+            "gas_left < gas_cost",
+            insufficient_gas_cost.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            gas_cost,
+            insufficient_gas_cost,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        let gas_cost = opcode.constant_gas_cost().as_u64();
+        self.gas_cost
+            .assign(region, offset, Some(F::from(gas_cost)))?;
+        self.insufficient_gas_cost.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(gas_cost),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{bytecode, evm_types::OpcodeId};
+    use mock::test_ctx::{helpers::*, TestContext};
+
+    fn test_ok(gas: u64) {
+        let code = bytecode! {
+            PUSH1(0x01)
+            PUSH1(0x02)
+            MUL
+            STOP
+        };
+        assert_eq!(
+            run_test_circuits(
+                TestContext::<2, 1>::new(
+                    None,
+                    account_0_code_account_1_no_code(code),
+                    |mut txs, accs| {
+                        txs[0]
+                            .from(accs[1].address)
+                            .to(accs[0].address)
+                            .gas(gas.into());
+                    },
+                    |block, _tx| block.number(0xcafeu64),
+                )
+                .unwrap(),
+                None
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn error_oog_constant() {
+        // Enough gas for both PUSH1 but not for MUL
+        let gas = 21_000 + 2 * OpcodeId::PUSH1.constant_gas_cost().as_u64();
+        test_ok(gas);
+        test_ok(gas + OpcodeId::MUL.constant_gas_cost().as_u64() - 1);
+    }
+}
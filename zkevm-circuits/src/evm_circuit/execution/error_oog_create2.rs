@@ -0,0 +1,136 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_GAS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            math_gadget::LtGadget,
+            memory_gadget::{MemoryAddressGadget, MemoryExpansionGadget, MemoryWordSizeGadget},
+            CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{GasCost, OpcodeId},
+    Field, ToLittleEndian,
+};
+use halo2_proofs::plonk::Error;
+
+/// Gadget to implement the corresponding out of gas error for
+/// [`OpcodeId::CREATE2`], whose gas cost includes hashing the init code.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGCreate2Gadget<F> {
+    opcode: Cell<F>,
+    value: Word<F>,
+    init_code: MemoryAddressGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    init_code_word_size: MemoryWordSizeGadget<F>,
+    insufficient_gas_cost: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGCreate2Gadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasCREATE2";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasCREATE2;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_equal(
+            "ErrorOutOfGasCREATE2 opcode must be CREATE2",
+            opcode.expr(),
+            OpcodeId::CREATE2.expr(),
+        );
+
+        let value = cb.query_word();
+        let init_code_offset = cb.query_cell();
+        let init_code_length = cb.query_rlc();
+        cb.stack_pop(value.expr());
+        cb.stack_pop(init_code_offset.expr());
+        cb.stack_pop(init_code_length.expr());
+        let init_code = MemoryAddressGadget::construct(cb, init_code_offset, init_code_length);
+
+        // Sum up gas cost in the same way as `CREATE2`, which includes the
+        // cost to hash the init code
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [init_code.address()],
+        );
+        let init_code_word_size = MemoryWordSizeGadget::construct(cb, init_code.length());
+        let gas_cost = GasCost::CREATE.expr()
+            + memory_expansion.gas_cost()
+            + GasCost::COPY_SHA3.expr() * init_code_word_size.expr();
+
+        // Check if the amount of gas available is less than the amount of gas
+        // required
+        let insufficient_gas_cost =
+            LtGadget::construct(cb, cb.curr.state.gas_left.expr(), gas_cost);
+        cb.require_equal(
+            "gas_left < gas_cost",
+            insufficient_gas_cost.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            value,
+            init_code,
+            memory_expansion,
+            init_code_word_size,
+            insufficient_gas_cost,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        let [value, init_code_offset, init_code_length] =
+            [0, 1, 2].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        self.value
+            .assign(region, offset, Some(value.to_le_bytes()))?;
+        let init_code_address = self.init_code.assign(
+            region,
+            offset,
+            init_code_offset,
+            init_code_length,
+            block.randomness,
+        )?;
+        self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [init_code_address],
+        )?;
+        self.init_code_word_size
+            .assign(region, offset, init_code_length.as_u64())?;
+
+        self.insufficient_gas_cost.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(step.gas_cost),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 3)
+    }
+}
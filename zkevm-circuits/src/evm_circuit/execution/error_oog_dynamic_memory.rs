@@ -0,0 +1,137 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_GAS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            math_gadget::{IsEqualGadget, LtGadget},
+            memory_gadget::{MemoryAddressGadget, MemoryExpansionGadget},
+            CachedRegion, Cell,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{evm_types::OpcodeId, Field};
+use halo2_proofs::plonk::Error;
+
+/// Gadget to implement the corresponding out of gas error for
+/// [`OpcodeId::CREATE`], [`OpcodeId::RETURN`] and [`OpcodeId::REVERT`], whose
+/// gas cost is dominated by the expansion of the memory region specified by
+/// stack.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGDynamicMemoryGadget<F> {
+    opcode: Cell<F>,
+    is_create: IsEqualGadget<F>,
+    memory_address: MemoryAddressGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    insufficient_gas_cost: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGDynamicMemoryGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasDynamicMemoryExpansion";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasDynamicMemoryExpansion;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_in_set(
+            "ErrorOutOfGasDynamicMemoryExpansion opcode must be CREATE, RETURN or REVERT",
+            opcode.expr(),
+            vec![
+                OpcodeId::CREATE.expr(),
+                OpcodeId::RETURN.expr(),
+                OpcodeId::REVERT.expr(),
+            ],
+        );
+
+        // `CREATE` has the value on top of the memory offset and length.
+        let is_create = IsEqualGadget::construct(cb, opcode.expr(), OpcodeId::CREATE.expr());
+        let memory_offset = cb.query_cell();
+        let memory_length = cb.query_rlc();
+        cb.stack_lookup(false.expr(), is_create.expr(), memory_offset.expr());
+        cb.stack_lookup(
+            false.expr(),
+            is_create.expr() + 1.expr(),
+            memory_length.expr(),
+        );
+        let memory_address = MemoryAddressGadget::construct(cb, memory_offset, memory_length);
+
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [memory_address.address()],
+        );
+
+        // Check if the amount of gas available is less than the amount of gas
+        // required
+        let insufficient_gas_cost = LtGadget::construct(
+            cb,
+            cb.curr.state.gas_left.expr(),
+            is_create.expr() * OpcodeId::CREATE.constant_gas_cost().expr()
+                + memory_expansion.gas_cost(),
+        );
+        cb.require_equal(
+            "gas_left < gas_cost",
+            insufficient_gas_cost.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            is_create,
+            memory_address,
+            memory_expansion,
+            insufficient_gas_cost,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+        self.is_create.assign(
+            region,
+            offset,
+            F::from(opcode.as_u64()),
+            F::from(OpcodeId::CREATE.as_u64()),
+        )?;
+
+        let [memory_offset, memory_length] =
+            [0, 1].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        let memory_address = self.memory_address.assign(
+            region,
+            offset,
+            memory_offset,
+            memory_length,
+            block.randomness,
+        )?;
+        self.memory_expansion
+            .assign(region, offset, step.memory_word_size(), [memory_address])?;
+
+        self.insufficient_gas_cost.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(step.gas_cost),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 2)
+    }
+}
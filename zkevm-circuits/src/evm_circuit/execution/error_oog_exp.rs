@@ -0,0 +1,110 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_GAS,
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            math_gadget::{ByteSizeGadget, LtGadget},
+            CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{GasCost, OpcodeId},
+    Field, ToLittleEndian,
+};
+use halo2_proofs::plonk::Error;
+
+/// Gadget to implement the corresponding out of gas error for
+/// [`OpcodeId::EXP`].
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGExpGadget<F> {
+    opcode: Cell<F>,
+    base: Word<F>,
+    exponent: Word<F>,
+    exponent_byte_size: ByteSizeGadget<F>,
+    insufficient_gas_cost: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGExpGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasEXP";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasEXP;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_equal(
+            "ErrorOutOfGasEXP opcode must be EXP",
+            opcode.expr(),
+            OpcodeId::EXP.expr(),
+        );
+
+        let base = cb.query_word();
+        let exponent = cb.query_word();
+        cb.stack_pop(base.expr());
+        cb.stack_pop(exponent.expr());
+
+        let exponent_byte_size = ByteSizeGadget::construct(cb, &exponent);
+
+        // Check if the amount of gas available is less than the amount of gas
+        // required
+        let insufficient_gas_cost = LtGadget::construct(
+            cb,
+            cb.curr.state.gas_left.expr(),
+            OpcodeId::EXP.constant_gas_cost().expr()
+                + GasCost::EXP_BYTE_TIMES.expr() * exponent_byte_size.byte_size(),
+        );
+        cb.require_equal(
+            "gas_left < gas_cost",
+            insufficient_gas_cost.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            base,
+            exponent,
+            exponent_byte_size,
+            insufficient_gas_cost,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        let [base, exponent] = [0, 1].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        self.base.assign(region, offset, Some(base.to_le_bytes()))?;
+        self.exponent
+            .assign(region, offset, Some(exponent.to_le_bytes()))?;
+
+        self.exponent_byte_size.assign(region, offset, exponent)?;
+        self.insufficient_gas_cost.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(step.gas_cost),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 2)
+    }
+}
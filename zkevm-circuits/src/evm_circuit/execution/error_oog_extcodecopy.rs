@@ -0,0 +1,168 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_GAS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        table::CallContextFieldTag,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            from_bytes,
+            math_gadget::LtGadget,
+            memory_gadget::{MemoryAddressGadget, MemoryCopierGasGadget, MemoryExpansionGadget},
+            select, CachedRegion, Cell, RandomLinearCombination, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{GasCost, OpcodeId},
+    Field, ToAddress, ToLittleEndian, ToScalar, U256,
+};
+use halo2_proofs::plonk::Error;
+
+/// Gadget to implement the corresponding out of gas error for
+/// [`OpcodeId::EXTCODECOPY`].
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGExtcodecopyGadget<F> {
+    opcode: Cell<F>,
+    external_address: RandomLinearCombination<F, N_BYTES_ACCOUNT_ADDRESS>,
+    code_offset: Word<F>,
+    dst_memory_addr: MemoryAddressGadget<F>,
+    tx_id: Cell<F>,
+    is_warm: Cell<F>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    memory_copier_gas: MemoryCopierGasGadget<F, { GasCost::COPY.as_u64() }>,
+    insufficient_gas_cost: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGExtcodecopyGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasEXTCODECOPY";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasEXTCODECOPY;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_equal(
+            "ErrorOutOfGasEXTCODECOPY opcode must be EXTCODECOPY",
+            opcode.expr(),
+            OpcodeId::EXTCODECOPY.expr(),
+        );
+
+        let external_address = cb.query_rlc();
+        let dst_memory_offset = cb.query_cell();
+        let code_offset = cb.query_word();
+        let size = cb.query_rlc();
+        cb.stack_pop(external_address.expr());
+        cb.stack_pop(dst_memory_offset.expr());
+        cb.stack_pop(code_offset.expr());
+        cb.stack_pop(size.expr());
+        let dst_memory_addr = MemoryAddressGadget::construct(cb, dst_memory_offset, size);
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let is_warm = cb.query_bool();
+        cb.account_access_list_read(
+            tx_id.expr(),
+            from_bytes::expr(&external_address.cells),
+            is_warm.expr(),
+        );
+
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [dst_memory_addr.address()],
+        );
+        let memory_copier_gas = MemoryCopierGasGadget::construct(
+            cb,
+            dst_memory_addr.length(),
+            memory_expansion.gas_cost(),
+        );
+
+        // Check if the amount of gas available is less than the amount of gas
+        // required
+        let insufficient_gas_cost = LtGadget::construct(
+            cb,
+            cb.curr.state.gas_left.expr(),
+            select::expr(
+                is_warm.expr(),
+                GasCost::WARM_ACCESS.expr(),
+                GasCost::COLD_ACCOUNT_ACCESS.expr(),
+            ) + memory_copier_gas.gas_cost(),
+        );
+        cb.require_equal(
+            "gas_left < gas_cost",
+            insufficient_gas_cost.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            external_address,
+            code_offset,
+            dst_memory_addr,
+            tx_id,
+            is_warm,
+            memory_expansion,
+            memory_copier_gas,
+            insufficient_gas_cost,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        let [external_address, dest_offset, code_offset, size] =
+            [0, 1, 2, 3].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        let mut le_bytes = external_address.to_address().0;
+        le_bytes.reverse();
+        self.external_address
+            .assign(region, offset, Some(le_bytes))?;
+        self.code_offset
+            .assign(region, offset, Some(code_offset.to_le_bytes()))?;
+        let memory_address =
+            self.dst_memory_addr
+                .assign(region, offset, dest_offset, size, block.randomness)?;
+
+        self.tx_id
+            .assign(region, offset, U256::from(tx.id).to_scalar())?;
+
+        let (is_warm, _) = block.rws[step.rw_indices[5]].tx_access_list_value_pair();
+        self.is_warm
+            .assign(region, offset, Some(F::from(is_warm as u64)))?;
+
+        let (_, memory_expansion_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [memory_address],
+        )?;
+        self.memory_copier_gas
+            .assign(region, offset, size.as_u64(), memory_expansion_cost)?;
+
+        self.insufficient_gas_cost.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(step.gas_cost),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 6)
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_GAS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            math_gadget::LtGadget,
+            memory_gadget::{MemoryAddressGadget, MemoryCopierGasGadget, MemoryExpansionGadget},
+            CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{GasCost, OpcodeId},
+    Field, ToLittleEndian,
+};
+use halo2_proofs::plonk::Error;
+
+/// Gadget to implement the corresponding out of gas error for
+/// [`OpcodeId::CALLDATACOPY`], [`OpcodeId::CODECOPY`] and
+/// [`OpcodeId::RETURNDATACOPY`], which copy a specified chunk of data into
+/// memory.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGMemoryCopyGadget<F> {
+    opcode: Cell<F>,
+    src_offset: Word<F>,
+    dst_memory_addr: MemoryAddressGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    memory_copier_gas: MemoryCopierGasGadget<F, { GasCost::COPY.as_u64() }>,
+    insufficient_gas_cost: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGMemoryCopyGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasMemoryCopy";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasMemoryCopy;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_in_set(
+            "ErrorOutOfGasMemoryCopy opcode must be CALLDATACOPY, CODECOPY or RETURNDATACOPY",
+            opcode.expr(),
+            vec![
+                OpcodeId::CALLDATACOPY.expr(),
+                OpcodeId::CODECOPY.expr(),
+                OpcodeId::RETURNDATACOPY.expr(),
+            ],
+        );
+
+        let dst_memory_offset = cb.query_cell();
+        let src_offset = cb.query_word();
+        let size = cb.query_rlc();
+        cb.stack_pop(dst_memory_offset.expr());
+        cb.stack_pop(src_offset.expr());
+        cb.stack_pop(size.expr());
+        let dst_memory_addr = MemoryAddressGadget::construct(cb, dst_memory_offset, size);
+
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [dst_memory_addr.address()],
+        );
+        let memory_copier_gas = MemoryCopierGasGadget::construct(
+            cb,
+            dst_memory_addr.length(),
+            memory_expansion.gas_cost(),
+        );
+
+        // Check if the amount of gas available is less than the amount of gas
+        // required. All of the opcodes have the same constant gas cost.
+        let insufficient_gas_cost = LtGadget::construct(
+            cb,
+            cb.curr.state.gas_left.expr(),
+            OpcodeId::CALLDATACOPY.constant_gas_cost().expr() + memory_copier_gas.gas_cost(),
+        );
+        cb.require_equal(
+            "gas_left < gas_cost",
+            insufficient_gas_cost.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            src_offset,
+            dst_memory_addr,
+            memory_expansion,
+            memory_copier_gas,
+            insufficient_gas_cost,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        let [dst_offset, src_offset, size] =
+            [0, 1, 2].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        self.src_offset
+            .assign(region, offset, Some(src_offset.to_le_bytes()))?;
+        let memory_address =
+            self.dst_memory_addr
+                .assign(region, offset, dst_offset, size, block.randomness)?;
+
+        let (_, memory_expansion_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [memory_address],
+        )?;
+        self.memory_copier_gas
+            .assign(region, offset, size.as_u64(), memory_expansion_cost)?;
+
+        self.insufficient_gas_cost.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(step.gas_cost),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 3)
+    }
+}
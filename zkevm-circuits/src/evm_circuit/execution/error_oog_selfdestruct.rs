@@ -0,0 +1,198 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_GAS},
+        step::ExecutionState,
+        table::{AccountFieldTag, CallContextFieldTag},
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            from_bytes,
+            math_gadget::{BatchedIsZeroGadget, IsEqualGadget, IsZeroGadget, LtGadget},
+            CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian, ToScalar};
+use halo2_proofs::plonk::Error;
+use keccak256::EMPTY_HASH_LE;
+
+use super::selfdestruct::selfdestruct_gas_cost_expr;
+
+/// Gadget to implement the corresponding out of gas error for
+/// [`OpcodeId::SELFDESTRUCT`]. The balance of the current account and the
+/// beneficiary account are read to tell if a new account is created.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGSelfdestructGadget<F> {
+    opcode: Cell<F>,
+    beneficiary: Word<F>,
+    tx_id: Cell<F>,
+    callee_address: Cell<F>,
+    is_warm: Cell<F>,
+    value: Cell<F>,
+    beneficiary_nonce: Cell<F>,
+    beneficiary_balance: Cell<F>,
+    beneficiary_code_hash: Cell<F>,
+    value_is_zero: IsZeroGadget<F>,
+    is_account_empty: BatchedIsZeroGadget<F, 2>,
+    is_empty_code_hash: IsEqualGadget<F>,
+    insufficient_gas_cost: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGSelfdestructGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasSELFDESTRUCT";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasSELFDESTRUCT;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_equal(
+            "ErrorOutOfGasSELFDESTRUCT opcode must be SELFDESTRUCT",
+            opcode.expr(),
+            OpcodeId::SELFDESTRUCT.expr(),
+        );
+
+        let beneficiary = cb.query_word();
+        cb.stack_pop(beneficiary.expr());
+        let beneficiary_address = from_bytes::expr(&beneficiary.cells[..N_BYTES_ACCOUNT_ADDRESS]);
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let callee_address = cb.call_context(None, CallContextFieldTag::CalleeAddress);
+
+        // The access list is only read, since the beneficiary isn't accessed
+        // when the gas is insufficient.
+        let is_warm = cb.query_bool();
+        cb.account_access_list_read(tx_id.expr(), beneficiary_address.clone(), is_warm.expr());
+
+        // The value to send is the whole balance of the current account.
+        let value = cb.query_cell();
+        cb.account_read(
+            callee_address.expr(),
+            AccountFieldTag::Balance,
+            value.expr(),
+        );
+        let [beneficiary_nonce, beneficiary_balance, beneficiary_code_hash] = [
+            AccountFieldTag::Nonce,
+            AccountFieldTag::Balance,
+            AccountFieldTag::CodeHash,
+        ]
+        .map(|field_tag| {
+            let value = cb.query_cell();
+            cb.account_read(beneficiary_address.clone(), field_tag, value.expr());
+            value
+        });
+        let value_is_zero = IsZeroGadget::construct(cb, value.expr());
+        let is_account_empty = BatchedIsZeroGadget::construct(
+            cb,
+            [beneficiary_nonce.expr(), beneficiary_balance.expr()],
+        );
+        let is_empty_code_hash = IsEqualGadget::construct(
+            cb,
+            beneficiary_code_hash.expr(),
+            Word::random_linear_combine_expr(
+                (*EMPTY_HASH_LE).map(|byte| byte.expr()),
+                cb.power_of_randomness(),
+            ),
+        );
+
+        // Check if the amount of gas available is less than the amount of gas
+        // required
+        let gas_cost = selfdestruct_gas_cost_expr(
+            is_warm.expr(),
+            1.expr() - value_is_zero.expr(),
+            is_account_empty.expr(),
+            is_empty_code_hash.expr(),
+        );
+        let insufficient_gas_cost =
+            LtGadget::construct(cb, cb.curr.state.gas_left.expr(), gas_cost);
+        cb.require_equal(
+            "gas_left < gas_cost",
+            insufficient_gas_cost.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            beneficiary,
+            tx_id,
+            callee_address,
+            is_warm,
+            value,
+            beneficiary_nonce,
+            beneficiary_balance,
+            beneficiary_code_hash,
+            value_is_zero,
+            is_account_empty,
+            is_empty_code_hash,
+            insufficient_gas_cost,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        let beneficiary = block.rws[step.rw_indices[0]].stack_value();
+        self.beneficiary
+            .assign(region, offset, Some(beneficiary.to_le_bytes()))?;
+        self.tx_id
+            .assign(region, offset, Some(F::from(tx.id as u64)))?;
+        self.callee_address
+            .assign(region, offset, call.callee_address.to_scalar())?;
+
+        let (is_warm, _) = block.rws[step.rw_indices[3]].tx_access_list_value_pair();
+        self.is_warm
+            .assign(region, offset, Some(F::from(is_warm as u64)))?;
+
+        let [value, beneficiary_nonce, beneficiary_balance, beneficiary_code_hash] =
+            [4, 5, 6, 7].map(|idx| block.rws[step.rw_indices[idx]].account_value_pair().0);
+        let [value, beneficiary_balance, beneficiary_code_hash] =
+            [value, beneficiary_balance, beneficiary_code_hash]
+                .map(|word| Word::random_linear_combine(word.to_le_bytes(), block.randomness));
+        self.value.assign(region, offset, Some(value))?;
+        self.beneficiary_nonce
+            .assign(region, offset, beneficiary_nonce.to_scalar())?;
+        self.beneficiary_balance
+            .assign(region, offset, Some(beneficiary_balance))?;
+        self.beneficiary_code_hash
+            .assign(region, offset, Some(beneficiary_code_hash))?;
+        self.value_is_zero.assign(region, offset, value)?;
+        self.is_account_empty.assign(
+            region,
+            offset,
+            [F::from(beneficiary_nonce.low_u64()), beneficiary_balance],
+        )?;
+        self.is_empty_code_hash.assign(
+            region,
+            offset,
+            beneficiary_code_hash,
+            Word::random_linear_combine(*EMPTY_HASH_LE, block.randomness),
+        )?;
+
+        self.insufficient_gas_cost.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(step.gas_cost),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 8)
+    }
+}
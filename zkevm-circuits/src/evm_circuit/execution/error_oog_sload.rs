@@ -0,0 +1,129 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_GAS,
+        step::ExecutionState,
+        table::CallContextFieldTag,
+        util::{
+            common_gadget::CommonErrorGadget, constraint_builder::ConstraintBuilder,
+            math_gadget::LtGadget, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian, ToScalar};
+use halo2_proofs::plonk::Error;
+
+use super::sload::SloadGasGadget;
+
+/// Gadget to implement the corresponding out of gas error for
+/// [`OpcodeId::SLOAD`].
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGSloadGadget<F> {
+    opcode: Cell<F>,
+    tx_id: Cell<F>,
+    callee_address: Cell<F>,
+    key: Cell<F>,
+    is_warm: Cell<F>,
+    insufficient_gas_cost: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGSloadGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasSLOAD";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasSLOAD;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_equal(
+            "ErrorOutOfGasSLOAD opcode must be SLOAD",
+            opcode.expr(),
+            OpcodeId::SLOAD.expr(),
+        );
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let callee_address = cb.call_context(None, CallContextFieldTag::CalleeAddress);
+
+        let key = cb.query_cell();
+        cb.stack_pop(key.expr());
+
+        // The access list is only read, since the storage slot isn't accessed
+        // when the gas is insufficient.
+        let is_warm = cb.query_bool();
+        cb.account_storage_access_list_read(
+            tx_id.expr(),
+            callee_address.expr(),
+            key.expr(),
+            is_warm.expr(),
+        );
+
+        // Check if the amount of gas available is less than the amount of gas
+        // required
+        let gas_cost = SloadGasGadget::construct(cb, is_warm.expr()).expr();
+        let insufficient_gas_cost =
+            LtGadget::construct(cb, cb.curr.state.gas_left.expr(), gas_cost);
+        cb.require_equal(
+            "gas_left < gas_cost",
+            insufficient_gas_cost.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            tx_id,
+            callee_address,
+            key,
+            is_warm,
+            insufficient_gas_cost,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        self.tx_id
+            .assign(region, offset, Some(F::from(tx.id as u64)))?;
+        self.callee_address
+            .assign(region, offset, call.callee_address.to_scalar())?;
+
+        let key = block.rws[step.rw_indices[2]].stack_value();
+        self.key.assign(
+            region,
+            offset,
+            Some(Word::random_linear_combine(
+                key.to_le_bytes(),
+                block.randomness,
+            )),
+        )?;
+
+        let (is_warm, _) = block.rws[step.rw_indices[3]].tx_access_list_value_pair();
+        self.is_warm
+            .assign(region, offset, Some(F::from(is_warm as u64)))?;
+
+        self.insufficient_gas_cost.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(step.gas_cost),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 4)
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_GAS,
+        step::ExecutionState,
+        table::CallContextFieldTag,
+        util::{
+            common_gadget::CommonErrorGadget, constraint_builder::ConstraintBuilder,
+            math_gadget::LtGadget, or, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{OpcodeId, SSTORE_SENTRY_GAS},
+    Field, ToLittleEndian, ToScalar,
+};
+use halo2_proofs::plonk::Error;
+
+use super::sstore::SstoreGasGadget;
+
+/// Gadget to implement the corresponding out of gas error for
+/// [`OpcodeId::SSTORE`], which happens either when the gas left is less than
+/// the gas cost, or when it's not more than the reentrancy sentry of EIP-2200.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGSstoreGadget<F> {
+    opcode: Cell<F>,
+    tx_id: Cell<F>,
+    callee_address: Cell<F>,
+    key: Cell<F>,
+    gas_cost: SstoreGasGadget<F>,
+    insufficient_gas_cost: LtGadget<F, N_BYTES_GAS>,
+    insufficient_gas_sentry: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGSstoreGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasSSTORE";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasSSTORE;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_equal(
+            "ErrorOutOfGasSSTORE opcode must be SSTORE",
+            opcode.expr(),
+            OpcodeId::SSTORE.expr(),
+        );
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let callee_address = cb.call_context(None, CallContextFieldTag::CalleeAddress);
+
+        let key = cb.query_cell();
+        let value = cb.query_cell();
+        cb.stack_pop(key.expr());
+        cb.stack_pop(value.expr());
+
+        // The storage slot and the access list are only read, since they
+        // aren't written when the gas is insufficient.
+        let value_prev = cb.query_cell();
+        let original_value = cb.query_cell();
+        cb.account_storage_read(
+            callee_address.expr(),
+            key.expr(),
+            value_prev.expr(),
+            tx_id.expr(),
+            original_value.expr(),
+        );
+        let is_warm = cb.query_bool();
+        cb.account_storage_access_list_read(
+            tx_id.expr(),
+            callee_address.expr(),
+            key.expr(),
+            is_warm.expr(),
+        );
+
+        // Check if the amount of gas available is less than the amount of gas
+        // required, or not more than the reentrancy sentry
+        let gas_cost = SstoreGasGadget::construct(cb, value, value_prev, original_value, is_warm);
+        let insufficient_gas_cost =
+            LtGadget::construct(cb, cb.curr.state.gas_left.expr(), gas_cost.expr());
+        let insufficient_gas_sentry = LtGadget::construct(
+            cb,
+            cb.curr.state.gas_left.expr(),
+            (SSTORE_SENTRY_GAS + 1).expr(),
+        );
+        cb.require_equal(
+            "gas_left < gas_cost or gas_left <= SSTORE_SENTRY_GAS",
+            or::expr([insufficient_gas_cost.expr(), insufficient_gas_sentry.expr()]),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            tx_id,
+            callee_address,
+            key,
+            gas_cost,
+            insufficient_gas_cost,
+            insufficient_gas_sentry,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        self.tx_id
+            .assign(region, offset, Some(F::from(tx.id as u64)))?;
+        self.callee_address
+            .assign(region, offset, call.callee_address.to_scalar())?;
+
+        let [key, value] = [2, 3].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        self.key.assign(
+            region,
+            offset,
+            Some(Word::random_linear_combine(
+                key.to_le_bytes(),
+                block.randomness,
+            )),
+        )?;
+
+        let (_, value_prev, _, original_value) = block.rws[step.rw_indices[4]].storage_value_aux();
+        let (is_warm, _) = block.rws[step.rw_indices[5]].tx_access_list_value_pair();
+        self.gas_cost.assign(
+            region,
+            offset,
+            step.gas_cost,
+            value,
+            value_prev,
+            original_value,
+            is_warm,
+            block.randomness,
+        )?;
+
+        self.insufficient_gas_cost.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(step.gas_cost),
+        )?;
+        self.insufficient_gas_sentry.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(SSTORE_SENTRY_GAS + 1),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 6)
+    }
+}
@@ -4,8 +4,9 @@ use crate::{
         param::{N_BYTES_GAS, N_BYTES_MEMORY_WORD_SIZE},
         step::ExecutionState,
         util::{
+            common_gadget::CommonErrorGadget,
             constraint_builder::ConstraintBuilder,
-            math_gadget::{IsEqualGadget, IsZeroGadget, RangeCheckGadget},
+            math_gadget::{IsEqualGadget, IsZeroGadget, LtGadget},
             memory_gadget::{address_high, address_low, MemoryExpansionGadget},
             CachedRegion, Cell, Word,
         },
@@ -16,6 +17,10 @@ use crate::{
 use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian};
 use halo2_proofs::plonk::Error;
 
+/// Gadget to implement the corresponding out of gas error for
+/// [`OpcodeId::MLOAD`], [`OpcodeId::MSTORE`] and [`OpcodeId::MSTORE8`], whose
+/// memory expansion gas cost is more than the gas left, or whose address is
+/// too large to be expanded at all.
 #[derive(Clone, Debug)]
 pub(crate) struct ErrorOOGStaticMemoryGadget<F> {
     opcode: Cell<F>,
@@ -31,8 +36,9 @@ pub(crate) struct ErrorOOGStaticMemoryGadget<F> {
     // Even memory size at most could be 2^35 - 1, the qudratic part of memory
     // expansion gas cost could be at most 2^61 - 2^27, due to the constant
     // division by 512, which still fits in 8 bytes.
-    insufficient_gas: RangeCheckGadget<F, N_BYTES_GAS>,
+    insufficient_gas: LtGadget<F, N_BYTES_GAS>,
     is_mstore8: IsEqualGadget<F>,
+    common_error_gadget: CommonErrorGadget<F>,
 }
 
 impl<F: Field> ExecutionGadget<F> for ErrorOOGStaticMemoryGadget<F> {
@@ -40,9 +46,18 @@ impl<F: Field> ExecutionGadget<F> for ErrorOOGStaticMemoryGadget<F> {
 
     const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasStaticMemoryExpansion;
 
-    // Support other OOG due to pure memory including CREATE, RETURN and REVERT
     fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
         let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_in_set(
+            "ErrorOutOfGasStaticMemoryExpansion opcode must be MLOAD, MSTORE or MSTORE8",
+            opcode.expr(),
+            vec![
+                OpcodeId::MLOAD.expr(),
+                OpcodeId::MSTORE.expr(),
+                OpcodeId::MSTORE8.expr(),
+            ],
+        );
 
         // Query address by a full word
         let address = cb.query_word();
@@ -62,20 +77,24 @@ impl<F: Field> ExecutionGadget<F> for ErrorOOGStaticMemoryGadget<F> {
         let address_in_range = IsZeroGadget::construct(cb, address_high::expr(&address));
         // Check if the amount of gas available is less than the amount of gas
         // required
-        let insufficient_gas = cb.condition(address_in_range.expr(), |cb| {
-            RangeCheckGadget::construct(
-                cb,
-                OpcodeId::MLOAD.constant_gas_cost().expr() + memory_expansion.gas_cost()
-                    - cb.curr.state.gas_left.expr(),
-            )
+        let insufficient_gas = LtGadget::construct(
+            cb,
+            cb.curr.state.gas_left.expr(),
+            OpcodeId::MLOAD.constant_gas_cost().expr() + memory_expansion.gas_cost(),
+        );
+        cb.condition(address_in_range.expr(), |cb| {
+            cb.require_equal(
+                "gas_left < gas_cost when address is in range",
+                insufficient_gas.expr(),
+                1.expr(),
+            );
         });
 
         // Pop the address from the stack
         // We still have to do this to verify the correctness of `address`
         cb.stack_pop(address.expr());
 
-        // TODO: Use ContextSwitchGadget to switch call context to caller's and
-        // consume all gas_left.
+        let common_error_gadget = CommonErrorGadget::construct(cb);
 
         Self {
             opcode,
@@ -84,6 +103,7 @@ impl<F: Field> ExecutionGadget<F> for ErrorOOGStaticMemoryGadget<F> {
             memory_expansion,
             insufficient_gas,
             is_mstore8,
+            common_error_gadget,
         }
     }
 
@@ -93,10 +113,12 @@ impl<F: Field> ExecutionGadget<F> for ErrorOOGStaticMemoryGadget<F> {
         offset: usize,
         block: &Block<F>,
         _: &Transaction,
-        _: &Call,
+        call: &Call,
         step: &ExecStep,
     ) -> Result<(), Error> {
         let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
 
         // Inputs/Outputs
         let address = block.rws[step.rw_indices[0]].stack_value();
@@ -119,7 +141,7 @@ impl<F: Field> ExecutionGadget<F> for ErrorOOGStaticMemoryGadget<F> {
         )?;
 
         // Memory expansion
-        self.memory_expansion.assign(
+        let (_, memory_expansion_gas_cost) = self.memory_expansion.assign(
             region,
             offset,
             step.memory_word_size(),
@@ -128,10 +150,14 @@ impl<F: Field> ExecutionGadget<F> for ErrorOOGStaticMemoryGadget<F> {
         )?;
 
         // Gas insufficient check
-        // Get `gas_available` variable here once it's available
-        self.insufficient_gas
-            .assign(region, offset, F::from(step.gas_cost - step.gas_left))?;
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(OpcodeId::MLOAD.constant_gas_cost().as_u64() + memory_expansion_gas_cost),
+        )?;
 
-        Ok(())
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 1)
     }
 }
@@ -0,0 +1,466 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        table::{AccountFieldTag, CallContextFieldTag},
+        util::{
+            constraint_builder::{
+                ConstraintBuilder, ReversionInfo, StepStateTransition,
+                Transition::{Delta, To},
+            },
+            from_bytes,
+            math_gadget::{BatchedIsZeroGadget, IsEqualGadget, IsZeroGadget, LtWordGadget},
+            memory_gadget::{MemoryAddressGadget, MemoryExpansionGadget, MemoryWordSizeGadget},
+            select, sum, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{GasCost, OpcodeId, GAS_STIPEND_CALL_WITH_VALUE, MAX_CALL_DEPTH},
+    Field, ToLittleEndian, ToScalar, U256,
+};
+use halo2_proofs::plonk::Error;
+use keccak256::EMPTY_HASH_LE;
+
+use super::call::call_gas_cost_expr;
+
+/// Gadget to implement the corresponding call depth error
+/// ([`ExecutionState::ErrorDepth`]) or insufficient balance error
+/// ([`ExecutionState::ErrorInsufficientBalance`]) of `*CALL*` and `CREATE*`,
+/// which fail in the precheck before the callee runs. Unlike the other errors,
+/// the current call isn't halted but continues with `0` pushed on the stack, so
+/// only the gas cost of the opcode is consumed, and the callee's gas (and the
+/// stipend for a call with value) is returned.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorPrecheckGadget<F, const S: ExecutionState> {
+    opcode: Cell<F>,
+    is_call: IsEqualGadget<F>,
+    is_callcode: IsEqualGadget<F>,
+    is_delegatecall: IsEqualGadget<F>,
+    is_staticcall: IsEqualGadget<F>,
+    is_create: IsEqualGadget<F>,
+    is_create2: IsEqualGadget<F>,
+    tx_id: Cell<F>,
+    reversion_info: ReversionInfo<F>,
+    current_address: Cell<F>,
+    depth: Cell<F>,
+    value: Word<F>,
+    gas: Word<F>,
+    callee_address: Word<F>,
+    // Call data for `*CALL*` or init code for `CREATE*`
+    input_address: MemoryAddressGadget<F>,
+    rd_address: MemoryAddressGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, 2, N_BYTES_MEMORY_WORD_SIZE>,
+    init_code_word_size: MemoryWordSizeGadget<F>,
+    is_warm_prev: Cell<F>,
+    callee_nonce: Cell<F>,
+    callee_balance: Cell<F>,
+    callee_code_hash: Cell<F>,
+    value_is_zero: IsZeroGadget<F>,
+    is_account_empty: BatchedIsZeroGadget<F, 2>,
+    is_empty_code_hash: IsEqualGadget<F>,
+    caller_balance: Word<F>,
+    is_insufficient_balance: LtWordGadget<F>,
+}
+
+impl<F: Field, const S: ExecutionState> ExecutionGadget<F> for ErrorPrecheckGadget<F, S> {
+    const NAME: &'static str = match S {
+        ExecutionState::ErrorDepth => "ErrorDepth",
+        ExecutionState::ErrorInsufficientBalance => "ErrorInsufficientBalance",
+        _ => unreachable!(),
+    };
+
+    const EXECUTION_STATE: ExecutionState = S;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+
+        let [is_call, is_callcode, is_delegatecall, is_staticcall, is_create, is_create2] = [
+            OpcodeId::CALL,
+            OpcodeId::CALLCODE,
+            OpcodeId::DELEGATECALL,
+            OpcodeId::STATICCALL,
+            OpcodeId::CREATE,
+            OpcodeId::CREATE2,
+        ]
+        .map(|opcode_id| IsEqualGadget::construct(cb, opcode.expr(), opcode_id.expr()));
+        let is_call_op =
+            is_call.expr() + is_callcode.expr() + is_delegatecall.expr() + is_staticcall.expr();
+        let is_create_op = is_create.expr() + is_create2.expr();
+        cb.require_equal(
+            "Opcode should be *CALL* or CREATE*",
+            is_call_op.clone() + is_create_op.clone(),
+            1.expr(),
+        );
+        if S == ExecutionState::ErrorInsufficientBalance {
+            cb.require_zero(
+                "DELEGATECALL and STATICCALL transfer no value",
+                is_delegatecall.expr() + is_staticcall.expr(),
+            );
+        }
+        // Only `CALL` and `CALLCODE` among `*CALL*` take the value argument.
+        let has_value_arg = is_call.expr() + is_callcode.expr();
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let mut reversion_info = cb.reversion_info(None);
+        let [current_address, depth] = [
+            CallContextFieldTag::CalleeAddress,
+            CallContextFieldTag::Depth,
+        ]
+        .map(|field_tag| cb.call_context(None, field_tag));
+        if S == ExecutionState::ErrorDepth {
+            cb.require_equal(
+                "depth == MAX_CALL_DEPTH + 1",
+                depth.expr(),
+                (MAX_CALL_DEPTH + 1).expr(),
+            );
+        }
+
+        // The value is read first, since it's on top of stack for `CREATE*`
+        // but third for `CALL` and `CALLCODE`.
+        let value = cb.query_word();
+        cb.condition(has_value_arg.clone() + is_create_op.clone(), |cb| {
+            cb.stack_lookup(false.expr(), 2.expr() * has_value_arg.clone(), value.expr());
+        });
+        cb.condition(is_delegatecall.expr() + is_staticcall.expr(), |cb| {
+            cb.require_zero(
+                "DELEGATECALL and STATICCALL have no value argument",
+                sum::expr(&value.cells),
+            );
+        });
+
+        let gas = cb.query_word();
+        let callee_address = cb.query_word();
+        cb.condition(is_call_op.clone(), |cb| {
+            cb.stack_lookup(false.expr(), 0.expr(), gas.expr());
+            cb.stack_lookup(false.expr(), 1.expr(), callee_address.expr());
+        });
+
+        // The call data of `*CALL*` and the init code of `CREATE*` are both
+        // right after the value (if any).
+        let input_offset = cb.query_cell();
+        let input_length = cb.query_rlc();
+        let input_stack_offset =
+            is_call_op.clone() * (2.expr() + has_value_arg.clone()) + is_create_op.clone();
+        cb.stack_lookup(
+            false.expr(),
+            input_stack_offset.clone(),
+            input_offset.expr(),
+        );
+        cb.stack_lookup(
+            false.expr(),
+            input_stack_offset + 1.expr(),
+            input_length.expr(),
+        );
+        let rd_offset = cb.query_cell();
+        let rd_length = cb.query_rlc();
+        cb.condition(is_call_op.clone(), |cb| {
+            cb.stack_lookup(
+                false.expr(),
+                4.expr() + has_value_arg.clone(),
+                rd_offset.expr(),
+            );
+            cb.stack_lookup(
+                false.expr(),
+                5.expr() + has_value_arg.clone(),
+                rd_length.expr(),
+            );
+        });
+
+        // Push `0` as the result since the callee isn't run.
+        let stack_pointer_delta = is_call_op.clone() * (5.expr() + has_value_arg.clone())
+            + is_create_op.clone() * (2.expr() + is_create2.expr());
+        cb.stack_lookup(true.expr(), stack_pointer_delta.clone(), 0.expr());
+
+        let input_address = MemoryAddressGadget::construct(cb, input_offset, input_length);
+        let rd_address = MemoryAddressGadget::construct(cb, rd_offset, rd_length);
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [input_address.address(), rd_address.address()],
+        );
+        let init_code_word_size = MemoryWordSizeGadget::construct(cb, input_address.length());
+
+        // `*CALL*` adds the callee to access list before the precheck, while
+        // `CREATE*` doesn't. To keep the layout the same, `CREATE*` writes the
+        // current address, which is always warm, with its value unchanged.
+        let is_warm_prev = cb.query_bool();
+        cb.account_access_list_write(
+            tx_id.expr(),
+            select::expr(
+                is_call_op.clone(),
+                from_bytes::expr(&callee_address.cells[..N_BYTES_ACCOUNT_ADDRESS]),
+                current_address.expr(),
+            ),
+            is_call_op.clone() + is_create_op.clone() * is_warm_prev.expr(),
+            is_warm_prev.expr(),
+            Some(&mut reversion_info),
+        );
+
+        // The callee account is read to tell if a `CALL` with value creates a
+        // new account.
+        let [callee_nonce, callee_balance, callee_code_hash] = [(); 3].map(|_| cb.query_cell());
+        cb.condition(is_call_op.clone(), |cb| {
+            for (field_tag, value) in [
+                (AccountFieldTag::Nonce, &callee_nonce),
+                (AccountFieldTag::Balance, &callee_balance),
+                (AccountFieldTag::CodeHash, &callee_code_hash),
+            ] {
+                cb.account_read(
+                    from_bytes::expr(&callee_address.cells[..N_BYTES_ACCOUNT_ADDRESS]),
+                    field_tag,
+                    value.expr(),
+                );
+            }
+        });
+        let is_account_empty =
+            BatchedIsZeroGadget::construct(cb, [callee_nonce.expr(), callee_balance.expr()]);
+        let is_empty_code_hash = IsEqualGadget::construct(
+            cb,
+            callee_code_hash.expr(),
+            Word::random_linear_combine_expr(
+                (*EMPTY_HASH_LE).map(|byte| byte.expr()),
+                cb.power_of_randomness(),
+            ),
+        );
+        let value_is_zero = IsZeroGadget::construct(cb, sum::expr(&value.cells));
+        let has_value = 1.expr() - value_is_zero.expr();
+
+        let caller_balance = cb.query_word();
+        let is_insufficient_balance = LtWordGadget::construct(cb, &caller_balance, &value);
+        if S == ExecutionState::ErrorInsufficientBalance {
+            cb.account_read(
+                current_address.expr(),
+                AccountFieldTag::Balance,
+                caller_balance.expr(),
+            );
+            cb.require_equal(
+                "caller_balance < value",
+                is_insufficient_balance.expr(),
+                1.expr(),
+            );
+        }
+
+        // The return data of last callee is cleared.
+        for field_tag in [
+            CallContextFieldTag::LastCalleeId,
+            CallContextFieldTag::LastCalleeReturnDataOffset,
+            CallContextFieldTag::LastCalleeReturnDataLength,
+        ] {
+            cb.call_context_lookup(true.expr(), None, field_tag, 0.expr());
+        }
+
+        // Only the gas cost of the opcode itself is consumed, while a call
+        // with value returns the stipend in addition.
+        let call_gas_cost = call_gas_cost_expr(
+            is_warm_prev.expr(),
+            has_value.clone(),
+            is_call.expr(),
+            is_account_empty.expr(),
+            is_empty_code_hash.expr(),
+            memory_expansion.gas_cost(),
+        );
+        let create_gas_cost = GasCost::CREATE.expr()
+            + memory_expansion.gas_cost()
+            + is_create2.expr() * GasCost::COPY_SHA3.expr() * init_code_word_size.expr();
+        let gas_left_delta = is_call_op
+            * (has_value * GAS_STIPEND_CALL_WITH_VALUE.expr() - call_gas_cost)
+            - is_create_op * create_gas_cost;
+
+        cb.require_step_state_transition(StepStateTransition {
+            rw_counter: Delta(cb.rw_counter_offset()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(stack_pointer_delta),
+            gas_left: Delta(gas_left_delta),
+            memory_word_size: To(memory_expansion.next_memory_word_size()),
+            reversible_write_counter: Delta(1.expr()),
+            ..StepStateTransition::default()
+        });
+
+        Self {
+            opcode,
+            is_call,
+            is_callcode,
+            is_delegatecall,
+            is_staticcall,
+            is_create,
+            is_create2,
+            tx_id,
+            reversion_info,
+            current_address,
+            depth,
+            value,
+            gas,
+            callee_address,
+            input_address,
+            rd_address,
+            memory_expansion,
+            init_code_word_size,
+            is_warm_prev,
+            callee_nonce,
+            callee_balance,
+            callee_code_hash,
+            value_is_zero,
+            is_account_empty,
+            is_empty_code_hash,
+            caller_balance,
+            is_insufficient_balance,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        let is_call_op = matches!(
+            opcode,
+            OpcodeId::CALL | OpcodeId::CALLCODE | OpcodeId::DELEGATECALL | OpcodeId::STATICCALL
+        );
+        let has_value_arg = matches!(
+            opcode,
+            OpcodeId::CALL | OpcodeId::CALLCODE | OpcodeId::CREATE | OpcodeId::CREATE2
+        );
+
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+        for (gadget, opcode_id) in [
+            (&self.is_call, OpcodeId::CALL),
+            (&self.is_callcode, OpcodeId::CALLCODE),
+            (&self.is_delegatecall, OpcodeId::DELEGATECALL),
+            (&self.is_staticcall, OpcodeId::STATICCALL),
+            (&self.is_create, OpcodeId::CREATE),
+            (&self.is_create2, OpcodeId::CREATE2),
+        ] {
+            gadget.assign(
+                region,
+                offset,
+                F::from(opcode.as_u64()),
+                F::from(opcode_id.as_u64()),
+            )?;
+        }
+
+        let [tx_id, current_address, depth] =
+            [0, 3, 4].map(|idx| block.rws[step.rw_indices[idx]].call_context_value());
+        self.tx_id
+            .assign(region, offset, Some(F::from(tx_id.low_u64())))?;
+        self.reversion_info.assign(
+            region,
+            offset,
+            call.rw_counter_end_of_reversion,
+            call.is_persistent,
+        )?;
+        self.current_address
+            .assign(region, offset, current_address.to_scalar())?;
+        self.depth
+            .assign(region, offset, Some(F::from(depth.low_u64())))?;
+
+        // Stack reads start after the 5 call context reads, with the value read
+        // first if any, and end with the stack write of the result.
+        let n_stack_reads = has_value_arg as usize + if is_call_op { 6 } else { 2 };
+        let mut stack_values = step.rw_indices[5..5 + n_stack_reads]
+            .iter()
+            .map(|idx| block.rws[*idx].stack_value());
+        let mut next_stack_value = || stack_values.next().unwrap();
+        let value = if has_value_arg {
+            next_stack_value()
+        } else {
+            U256::zero()
+        };
+        let [gas, callee_address] = if is_call_op {
+            [next_stack_value(), next_stack_value()]
+        } else {
+            [U256::zero(); 2]
+        };
+        let [input_offset, input_length] = [next_stack_value(), next_stack_value()];
+        let [rd_offset, rd_length] = if is_call_op {
+            [next_stack_value(), next_stack_value()]
+        } else {
+            [U256::zero(); 2]
+        };
+        let mut rw_index = 5 + n_stack_reads + 1;
+
+        self.value
+            .assign(region, offset, Some(value.to_le_bytes()))?;
+        self.value_is_zero
+            .assign(region, offset, sum::value(&value.to_le_bytes()))?;
+        self.gas.assign(region, offset, Some(gas.to_le_bytes()))?;
+        self.callee_address
+            .assign(region, offset, Some(callee_address.to_le_bytes()))?;
+
+        let input_address = self.input_address.assign(
+            region,
+            offset,
+            input_offset,
+            input_length,
+            block.randomness,
+        )?;
+        let rd_address =
+            self.rd_address
+                .assign(region, offset, rd_offset, rd_length, block.randomness)?;
+        self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [input_address, rd_address],
+        )?;
+        self.init_code_word_size
+            .assign(region, offset, input_length.as_u64())?;
+
+        let (_, is_warm_prev) = block.rws[step.rw_indices[rw_index]].tx_access_list_value_pair();
+        rw_index += 1;
+        self.is_warm_prev
+            .assign(region, offset, Some(F::from(is_warm_prev as u64)))?;
+
+        let [callee_nonce, callee_balance, callee_code_hash] = if is_call_op {
+            let values = [rw_index, rw_index + 1, rw_index + 2]
+                .map(|idx| block.rws[step.rw_indices[idx]].account_value_pair().0);
+            rw_index += 3;
+            values
+        } else {
+            [U256::zero(); 3]
+        };
+        let callee_balance =
+            Word::random_linear_combine(callee_balance.to_le_bytes(), block.randomness);
+        let callee_code_hash =
+            Word::random_linear_combine(callee_code_hash.to_le_bytes(), block.randomness);
+        self.callee_nonce
+            .assign(region, offset, callee_nonce.to_scalar())?;
+        self.callee_balance
+            .assign(region, offset, Some(callee_balance))?;
+        self.callee_code_hash
+            .assign(region, offset, Some(callee_code_hash))?;
+        self.is_account_empty.assign(
+            region,
+            offset,
+            [F::from(callee_nonce.low_u64()), callee_balance],
+        )?;
+        self.is_empty_code_hash.assign(
+            region,
+            offset,
+            callee_code_hash,
+            Word::random_linear_combine(*EMPTY_HASH_LE, block.randomness),
+        )?;
+
+        let caller_balance = if S == ExecutionState::ErrorInsufficientBalance {
+            block.rws[step.rw_indices[rw_index]].account_value_pair().0
+        } else {
+            U256::zero()
+        };
+        self.caller_balance
+            .assign(region, offset, Some(caller_balance.to_le_bytes()))?;
+        self.is_insufficient_balance
+            .assign(region, offset, caller_balance, value)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_MEMORY_ADDRESS, N_BYTES_U64},
+        step::ExecutionState,
+        table::CallContextFieldTag,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            from_bytes,
+            math_gadget::{IsZeroGadget, LtGadget},
+            not, or, sum, CachedRegion, Cell, MemoryAddress, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian, ToScalar};
+use halo2_proofs::plonk::Error;
+
+use std::convert::TryInto;
+
+/// Gadget to implement the corresponding return data out of bound error, when
+/// [`OpcodeId::RETURNDATACOPY`] copies bytes beyond the end of the return data
+/// of the last callee.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorReturnDataOutOfBoundGadget<F> {
+    opcode: Cell<F>,
+    memory_offset: Cell<F>,
+    data_offset: Word<F>,
+    length: MemoryAddress<F>,
+    return_data_length: Cell<F>,
+    // Whether the `data_offset` fits into u64.
+    is_data_offset_within_u64: IsZeroGadget<F>,
+    // Whether `data_offset + length` is greater than `return_data_length`.
+    is_end_over_return_data: LtGadget<F, { N_BYTES_U64 + 1 }>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorReturnDataOutOfBoundGadget<F> {
+    const NAME: &'static str = "ErrorReturnDataOutOfBound";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorReturnDataOutOfBound;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_equal(
+            "ErrorReturnDataOutOfBound opcode must be RETURNDATACOPY",
+            opcode.expr(),
+            OpcodeId::RETURNDATACOPY.expr(),
+        );
+
+        let memory_offset = cb.query_cell();
+        let data_offset = cb.query_word();
+        let length = cb.query_rlc();
+
+        // Pop memory_offset, data_offset, length from stack
+        cb.stack_pop(memory_offset.expr());
+        cb.stack_pop(data_offset.expr());
+        cb.stack_pop(length.expr());
+
+        let return_data_length = cb.query_cell();
+        cb.call_context_lookup(
+            false.expr(),
+            None,
+            CallContextFieldTag::LastCalleeReturnDataLength,
+            return_data_length.expr(),
+        );
+
+        // The copied range is out of bound when `data_offset` overflows u64, or
+        // when `data_offset + length` exceeds the return data length.
+        let is_data_offset_within_u64 =
+            IsZeroGadget::construct(cb, sum::expr(&data_offset.cells[N_BYTES_U64..]));
+        let is_end_over_return_data = LtGadget::construct(
+            cb,
+            return_data_length.expr(),
+            from_bytes::expr(&data_offset.cells[..N_BYTES_U64]) + from_bytes::expr(&length.cells),
+        );
+        cb.require_equal(
+            "data_offset > u64::MAX or data_offset + length > return_data_length",
+            or::expr([
+                not::expr(is_data_offset_within_u64.expr()),
+                is_end_over_return_data.expr(),
+            ]),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            memory_offset,
+            data_offset,
+            length,
+            return_data_length,
+            is_data_offset_within_u64,
+            is_end_over_return_data,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        let [memory_offset, data_offset, length] =
+            [0, 1, 2].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        let return_data_length = block.rws[step.rw_indices[3]].call_context_value();
+
+        self.memory_offset.assign(
+            region,
+            offset,
+            Some(Word::random_linear_combine(
+                memory_offset.to_le_bytes(),
+                block.randomness,
+            )),
+        )?;
+        self.data_offset
+            .assign(region, offset, Some(data_offset.to_le_bytes()))?;
+        self.length.assign(
+            region,
+            offset,
+            Some(
+                length.to_le_bytes()[..N_BYTES_MEMORY_ADDRESS]
+                    .try_into()
+                    .unwrap(),
+            ),
+        )?;
+        self.return_data_length
+            .assign(region, offset, return_data_length.to_scalar())?;
+
+        let data_offset_bytes = data_offset.to_le_bytes();
+        self.is_data_offset_within_u64.assign(
+            region,
+            offset,
+            sum::value(&data_offset_bytes[N_BYTES_U64..]),
+        )?;
+        self.is_end_over_return_data.assign(
+            region,
+            offset,
+            F::from(return_data_length.as_u64()),
+            F::from(data_offset.low_u64()) + F::from(length.low_u64()),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 4)
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        table::{FixedTableTag, Lookup},
+        util::{
+            common_gadget::CommonErrorGadget, constraint_builder::ConstraintBuilder, CachedRegion,
+            Cell,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::Field;
+use halo2_proofs::plonk::Error;
+
+/// Gadget to implement the corresponding stack overflow or stack underflow
+/// error, when the stack pointer is out of the valid range of the opcode, which
+/// is checked by the fixed table of responsible opcodes.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorStackGadget<F, const IS_OVERFLOW: bool, const S: ExecutionState> {
+    opcode: Cell<F>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field, const IS_OVERFLOW: bool, const S: ExecutionState> ExecutionGadget<F>
+    for ErrorStackGadget<F, IS_OVERFLOW, S>
+{
+    const NAME: &'static str = if IS_OVERFLOW {
+        "ErrorStackOverflow"
+    } else {
+        "ErrorStackUnderflow"
+    };
+
+    const EXECUTION_STATE: ExecutionState = S;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+
+        // The stack pointer is out of the valid range of the opcode.
+        cb.add_lookup(
+            "Responsible opcode with invalid stack pointer lookup",
+            Lookup::Fixed {
+                tag: FixedTableTag::ResponsibleOpcode.expr(),
+                values: [
+                    S.as_u64().expr(),
+                    opcode.expr(),
+                    cb.curr.state.stack_pointer.expr(),
+                ],
+            },
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 0)
+    }
+}
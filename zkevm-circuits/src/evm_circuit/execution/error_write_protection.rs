@@ -0,0 +1,123 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        table::CallContextFieldTag,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            math_gadget::{IsEqualGadget, IsZeroGadget},
+            sum, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian, U256};
+use halo2_proofs::plonk::Error;
+
+/// Gadget to implement the corresponding write protection error, when
+/// a state modifying opcode, or [`OpcodeId::CALL`] with non-zero value, is
+/// executed in a static call.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorWriteProtectionGadget<F> {
+    opcode: Cell<F>,
+    is_call: IsEqualGadget<F>,
+    gas: Word<F>,
+    code_address: Word<F>,
+    value: Word<F>,
+    is_value_zero: IsZeroGadget<F>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorWriteProtectionGadget<F> {
+    const NAME: &'static str = "ErrorWriteProtection";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorWriteProtection;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+        cb.require_in_set(
+            "ErrorWriteProtection opcode must modify the state",
+            opcode.expr(),
+            vec![
+                OpcodeId::SSTORE.expr(),
+                OpcodeId::CREATE.expr(),
+                OpcodeId::CREATE2.expr(),
+                OpcodeId::CALL.expr(),
+                OpcodeId::SELFDESTRUCT.expr(),
+                OpcodeId::LOG0.expr(),
+                OpcodeId::LOG1.expr(),
+                OpcodeId::LOG2.expr(),
+                OpcodeId::LOG3.expr(),
+                OpcodeId::LOG4.expr(),
+            ],
+        );
+
+        // `CALL` only breaks the write protection when it has value.
+        let is_call = IsEqualGadget::construct(cb, opcode.expr(), OpcodeId::CALL.expr());
+        let gas = cb.query_word();
+        let code_address = cb.query_word();
+        let value = cb.query_word();
+        let is_value_zero = IsZeroGadget::construct(cb, sum::expr(&value.cells));
+        cb.condition(is_call.expr(), |cb| {
+            cb.stack_lookup(false.expr(), 0.expr(), gas.expr());
+            cb.stack_lookup(false.expr(), 1.expr(), code_address.expr());
+            cb.stack_lookup(false.expr(), 2.expr(), value.expr());
+            cb.require_zero("CALL with non-zero value", is_value_zero.expr());
+        });
+
+        cb.call_context_lookup(false.expr(), None, CallContextFieldTag::IsStatic, 1.expr());
+
+        let common_error_gadget = CommonErrorGadget::construct(cb);
+
+        Self {
+            opcode,
+            is_call,
+            gas,
+            code_address,
+            value,
+            is_value_zero,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+        self.is_call.assign(
+            region,
+            offset,
+            F::from(opcode.as_u64()),
+            F::from(OpcodeId::CALL.as_u64()),
+        )?;
+
+        let [gas, code_address, value] = if opcode == OpcodeId::CALL {
+            [0, 1, 2].map(|idx| block.rws[step.rw_indices[idx]].stack_value())
+        } else {
+            [U256::zero(); 3]
+        };
+        self.gas.assign(region, offset, Some(gas.to_le_bytes()))?;
+        self.code_address
+            .assign(region, offset, Some(code_address.to_le_bytes()))?;
+        self.value
+            .assign(region, offset, Some(value.to_le_bytes()))?;
+        self.is_value_zero
+            .assign(region, offset, sum::value(&value.to_le_bytes()))?;
+
+        // `CALL` has 3 extra stack reads before the `IsStatic` read.
+        let rw_offset = if opcode == OpcodeId::CALL { 4 } else { 1 };
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, rw_offset)
+    }
+}
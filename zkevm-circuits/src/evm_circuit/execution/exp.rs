@@ -0,0 +1,209 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        util::{
+            common_gadget::SameContextGadget,
+            constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
+            from_bytes,
+            math_gadget::{ByteSizeGadget, IsEqualGadget, IsZeroGadget},
+            split_u256, CachedRegion, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{GasCost, OpcodeId},
+    Field, ToLittleEndian, ToScalar,
+};
+use halo2_proofs::plonk::Error;
+
+/// ExpGadget verifies opcode EXP, where `exponentiation == base ^ exponent (mod
+/// 2^256)`. The trivial cases `exponent == 0` and `exponent == 1` are verified
+/// in place, otherwise the multiplication steps of exponentiation by squaring
+/// are looked up from the exponentiation table.
+#[derive(Clone, Debug)]
+pub(crate) struct ExpGadget<F> {
+    same_context: SameContextGadget<F>,
+    base: Word<F>,
+    exponent: Word<F>,
+    exponentiation: Word<F>,
+    exponent_lo_is_zero: IsZeroGadget<F>,
+    exponent_lo_is_one: IsEqualGadget<F>,
+    exponent_hi_is_zero: IsZeroGadget<F>,
+    exponent_byte_size: ByteSizeGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ExpGadget<F> {
+    const NAME: &'static str = "EXP";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::EXP;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+
+        let base = cb.query_word();
+        let exponent = cb.query_word();
+        let exponentiation = cb.query_word();
+
+        cb.stack_pop(base.expr());
+        cb.stack_pop(exponent.expr());
+        cb.stack_push(exponentiation.expr());
+
+        let exponent_lo = from_bytes::expr(&exponent.cells[..16]);
+        let exponent_hi = from_bytes::expr(&exponent.cells[16..]);
+        let exponentiation_lo = from_bytes::expr(&exponentiation.cells[..16]);
+        let exponentiation_hi = from_bytes::expr(&exponentiation.cells[16..]);
+
+        let exponent_lo_is_zero = IsZeroGadget::construct(cb, exponent_lo.clone());
+        let exponent_lo_is_one = IsEqualGadget::construct(cb, exponent_lo.clone(), 1.expr());
+        let exponent_hi_is_zero = IsZeroGadget::construct(cb, exponent_hi.clone());
+        let exponent_is_zero = exponent_hi_is_zero.expr() * exponent_lo_is_zero.expr();
+        let exponent_is_one = exponent_hi_is_zero.expr() * exponent_lo_is_one.expr();
+
+        // base ^ 0 == 1
+        cb.condition(exponent_is_zero.clone(), |cb| {
+            cb.require_equal(
+                "exponentiation_lo == 1 if exponent == 0",
+                exponentiation_lo.clone(),
+                1.expr(),
+            );
+            cb.require_zero(
+                "exponentiation_hi == 0 if exponent == 0",
+                exponentiation_hi.clone(),
+            );
+        });
+
+        // base ^ 1 == base
+        cb.condition(exponent_is_one.clone(), |cb| {
+            cb.require_equal(
+                "exponentiation == base if exponent == 1",
+                exponentiation.expr(),
+                base.expr(),
+            );
+        });
+
+        // Otherwise the exponentiation is verified by the exponentiation table,
+        // identified by the RW counter at the beginning of this step.
+        cb.condition(1.expr() - exponent_is_zero - exponent_is_one, |cb| {
+            let base_limbs =
+                [0, 1, 2, 3].map(|idx| from_bytes::expr(&base.cells[idx * 8..(idx + 1) * 8]));
+            cb.exp_table_lookup(
+                cb.curr.state.rw_counter.expr(),
+                base_limbs,
+                [exponent_lo, exponent_hi],
+                [exponentiation_lo, exponentiation_hi],
+            );
+        });
+
+        // Dynamic gas cost is proportional to the byte size of the exponent.
+        let exponent_byte_size = ByteSizeGadget::construct(cb, &exponent);
+        let dynamic_gas_cost = GasCost::EXP_BYTE_TIMES.expr() * exponent_byte_size.byte_size();
+
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(3.expr()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(1.expr()),
+            gas_left: Delta(-(OpcodeId::EXP.constant_gas_cost().expr() + dynamic_gas_cost)),
+            ..Default::default()
+        };
+        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            base,
+            exponent,
+            exponentiation,
+            exponent_lo_is_zero,
+            exponent_lo_is_one,
+            exponent_hi_is_zero,
+            exponent_byte_size,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        _: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let [base, exponent, exponentiation] =
+            [0, 1, 2].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        self.base.assign(region, offset, Some(base.to_le_bytes()))?;
+        self.exponent
+            .assign(region, offset, Some(exponent.to_le_bytes()))?;
+        self.exponentiation
+            .assign(region, offset, Some(exponentiation.to_le_bytes()))?;
+
+        let (exponent_lo, exponent_hi) = split_u256(&exponent);
+        let exponent_lo = exponent_lo.to_scalar().unwrap();
+        let exponent_hi = exponent_hi.to_scalar().unwrap();
+        self.exponent_lo_is_zero
+            .assign(region, offset, exponent_lo)?;
+        self.exponent_lo_is_one
+            .assign(region, offset, exponent_lo, F::one())?;
+        self.exponent_hi_is_zero
+            .assign(region, offset, exponent_hi)?;
+        self.exponent_byte_size.assign(region, offset, exponent)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{bytecode, Word};
+    use mock::TestContext;
+
+    fn test_ok(base: Word, exponent: Word) {
+        let code = bytecode! {
+            PUSH32(exponent)
+            PUSH32(base)
+            EXP
+            STOP
+        };
+        assert_eq!(
+            run_test_circuits(
+                TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap(),
+                None
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn exp_gadget_zero() {
+        test_ok(0.into(), 0.into());
+        test_ok(2.into(), 0.into());
+        test_ok(0.into(), 2.into());
+    }
+
+    #[test]
+    fn exp_gadget_one() {
+        test_ok(1.into(), 1.into());
+        test_ok(7.into(), 1.into());
+        test_ok(Word::MAX, 1.into());
+    }
+
+    #[test]
+    fn exp_gadget_simple() {
+        test_ok(2.into(), 5.into());
+        test_ok(3.into(), 101.into());
+        test_ok(5.into(), 259.into());
+        test_ok(7.into(), 1023.into());
+    }
+
+    #[test]
+    fn exp_gadget_overflow() {
+        test_ok(2.into(), 256.into());
+        test_ok(Word::MAX, 3.into());
+        test_ok(Word::MAX, Word::MAX);
+    }
+}
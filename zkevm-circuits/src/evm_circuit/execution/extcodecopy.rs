@@ -0,0 +1,376 @@
+use std::convert::TryInto;
+
+use bus_mapping::circuit_input_builder::CopyDataType;
+use eth_types::{evm_types::GasCost, Field, ToAddress, ToLittleEndian, ToScalar, U256};
+use halo2_proofs::plonk::Error;
+use keccak256::EMPTY_HASH_LE;
+
+use crate::{
+    evm_circuit::{
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_MEMORY_ADDRESS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        table::{AccountFieldTag, CallContextFieldTag},
+        util::{
+            common_gadget::SameContextGadget,
+            constraint_builder::{
+                ConstraintBuilder, ReversionInfo, StepStateTransition, Transition,
+            },
+            from_bytes,
+            math_gadget::IsEqualGadget,
+            memory_gadget::{MemoryAddressGadget, MemoryCopierGasGadget, MemoryExpansionGadget},
+            not, select, CachedRegion, Cell, MemoryAddress, RandomLinearCombination, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+
+use super::ExecutionGadget;
+
+#[derive(Clone, Debug)]
+pub(crate) struct ExtcodecopyGadget<F> {
+    same_context: SameContextGadget<F>,
+    /// Holds the address of the external account whose code is copied.
+    external_address: RandomLinearCombination<F, N_BYTES_ACCOUNT_ADDRESS>,
+    /// Holds the memory address for the offset in code from where we read.
+    code_offset: MemoryAddress<F>,
+    tx_id: Cell<F>,
+    reversion_info: ReversionInfo<F>,
+    is_warm: Cell<F>,
+    /// Holds the code hash of the external account.
+    code_hash: Cell<F>,
+    /// Accounts without code (including non-existing ones) have the empty
+    /// code hash, whose code size is zero.
+    is_empty_code_hash: IsEqualGadget<F>,
+    /// Holds the size of the external account's bytecode.
+    code_size: Cell<F>,
+    /// The code from the external account is copied to memory. To verify this
+    /// copy operation we need the MemoryAddressGadget.
+    dst_memory_addr: MemoryAddressGadget<F>,
+    /// Opcode EXTCODECOPY has a dynamic gas cost:
+    /// gas_code = access_cost + static_gas * minimum_word_size +
+    /// memory_expansion_cost
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    /// Opcode EXTCODECOPY needs to copy code bytes into memory. We account for
+    /// the copying costs using the memory copier gas gadget.
+    memory_copier_gas: MemoryCopierGasGadget<F, { GasCost::COPY.as_u64() }>,
+    /// RW inverse counter from the copy table at the start of related copy
+    /// steps.
+    copy_rwc_inc: Cell<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ExtcodecopyGadget<F> {
+    const NAME: &'static str = "EXTCODECOPY";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::EXTCODECOPY;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+
+        // Query elements to be popped from the stack.
+        let external_address = cb.query_rlc();
+        let dst_memory_offset = cb.query_cell();
+        let code_offset = cb.query_rlc();
+        let size = cb.query_rlc();
+
+        // Pop items from stack.
+        cb.stack_pop(external_address.expr());
+        cb.stack_pop(dst_memory_offset.expr());
+        cb.stack_pop(code_offset.expr());
+        cb.stack_pop(size.expr());
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let mut reversion_info = cb.reversion_info(None);
+
+        let is_warm = cb.query_bool();
+        cb.account_access_list_write(
+            tx_id.expr(),
+            from_bytes::expr(&external_address.cells),
+            1.expr(),
+            is_warm.expr(),
+            Some(&mut reversion_info),
+        );
+
+        // Fetch the hash of the external account's bytecode.
+        let code_hash = cb.query_cell();
+        cb.account_read(
+            from_bytes::expr(&external_address.cells),
+            AccountFieldTag::CodeHash,
+            code_hash.expr(),
+        );
+
+        // Fetch the bytecode length from the bytecode table, unless the code
+        // is empty.
+        let is_empty_code_hash = IsEqualGadget::construct(
+            cb,
+            code_hash.expr(),
+            Word::random_linear_combine_expr(
+                (*EMPTY_HASH_LE).map(|byte| byte.expr()),
+                cb.power_of_randomness(),
+            ),
+        );
+        let code_size = cb.query_cell();
+        cb.condition(not::expr(is_empty_code_hash.expr()), |cb| {
+            let bytecode_length = cb.bytecode_length(code_hash.expr());
+            cb.require_equal(
+                "Code size == bytecode length",
+                code_size.expr(),
+                bytecode_length.expr(),
+            );
+        });
+        cb.condition(is_empty_code_hash.expr(), |cb| {
+            cb.require_zero("Code size == 0", code_size.expr());
+        });
+
+        // Construct memory address in the destionation (memory) to which we copy code.
+        let dst_memory_addr = MemoryAddressGadget::construct(cb, dst_memory_offset, size);
+
+        // Calculate the next memory size and the gas cost for this memory
+        // access. This also accounts for the dynamic gas required to copy bytes to
+        // memory.
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [dst_memory_addr.address()],
+        );
+        let memory_copier_gas = MemoryCopierGasGadget::construct(
+            cb,
+            dst_memory_addr.length(),
+            memory_expansion.gas_cost(),
+        );
+
+        let copy_rwc_inc = cb.query_cell();
+        cb.condition(dst_memory_addr.has_length(), |cb| {
+            cb.copy_table_lookup(
+                code_hash.expr(),
+                CopyDataType::Bytecode.expr(),
+                cb.curr.state.call_id.expr(),
+                CopyDataType::Memory.expr(),
+                from_bytes::expr(&code_offset.cells),
+                code_size.expr(),
+                dst_memory_addr.offset(),
+                dst_memory_addr.length(),
+                cb.curr.state.rw_counter.expr() + cb.rw_counter_offset().expr(),
+                copy_rwc_inc.expr(),
+                0.expr(),
+            );
+        });
+        cb.condition(not::expr(dst_memory_addr.has_length()), |cb| {
+            cb.require_zero(
+                "if no bytes to copy, copy table rwc inc == 0",
+                copy_rwc_inc.expr(),
+            );
+        });
+
+        let gas_cost = select::expr(
+            is_warm.expr(),
+            GasCost::WARM_ACCESS.expr(),
+            GasCost::COLD_ACCOUNT_ACCESS.expr(),
+        ) + memory_copier_gas.gas_cost();
+
+        // Expected state transition.
+        let step_state_transition = StepStateTransition {
+            rw_counter: Transition::Delta(cb.rw_counter_offset() + copy_rwc_inc.expr()),
+            program_counter: Transition::Delta(1.expr()),
+            stack_pointer: Transition::Delta(4.expr()),
+            memory_word_size: Transition::To(memory_expansion.next_memory_word_size()),
+            gas_left: Transition::Delta(-gas_cost),
+            reversible_write_counter: Transition::Delta(1.expr()),
+            ..Default::default()
+        };
+        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            external_address,
+            code_offset,
+            tx_id,
+            reversion_info,
+            is_warm,
+            code_hash,
+            is_empty_code_hash,
+            code_size,
+            dst_memory_addr,
+            memory_expansion,
+            memory_copier_gas,
+            copy_rwc_inc,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let [external_address, dest_offset, code_offset, size] =
+            [0, 1, 2, 3].map(|i| block.rws[step.rw_indices[i]].stack_value());
+
+        let mut le_bytes = external_address.to_address().0;
+        le_bytes.reverse();
+        self.external_address
+            .assign(region, offset, Some(le_bytes))?;
+
+        // assign the code offset memory address.
+        self.code_offset.assign(
+            region,
+            offset,
+            Some(
+                code_offset.to_le_bytes()[..N_BYTES_MEMORY_ADDRESS]
+                    .try_into()
+                    .unwrap(),
+            ),
+        )?;
+
+        self.tx_id
+            .assign(region, offset, U256::from(tx.id).to_scalar())?;
+        self.reversion_info.assign(
+            region,
+            offset,
+            call.rw_counter_end_of_reversion,
+            call.is_persistent,
+        )?;
+
+        let (_, is_warm) = block.rws[step.rw_indices[7]].tx_access_list_value_pair();
+        self.is_warm
+            .assign(region, offset, Some(F::from(is_warm as u64)))?;
+
+        let (code_hash, _) = block.rws[step.rw_indices[8]].account_value_pair();
+        let code_hash_rlc = Word::random_linear_combine(code_hash.to_le_bytes(), block.randomness);
+        self.code_hash.assign(region, offset, Some(code_hash_rlc))?;
+        self.is_empty_code_hash.assign(
+            region,
+            offset,
+            code_hash_rlc,
+            Word::random_linear_combine(*EMPTY_HASH_LE, block.randomness),
+        )?;
+
+        let code_size = block
+            .bytecodes
+            .get(&code_hash)
+            .map_or(0, |bytecode| bytecode.bytes.len() as u64);
+        self.code_size
+            .assign(region, offset, Some(F::from(code_size)))?;
+
+        // assign the destination memory offset.
+        let memory_address =
+            self.dst_memory_addr
+                .assign(region, offset, dest_offset, size, block.randomness)?;
+
+        // assign to gadgets handling memory expansion cost and copying cost.
+        let (_, memory_expansion_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [memory_address],
+        )?;
+        self.memory_copier_gas
+            .assign(region, offset, size.as_u64(), memory_expansion_cost)?;
+
+        let key = (tx.id, call.id, step.program_counter as usize);
+        let copy_rwc_inc = block
+            .copy_events
+            .get(&key)
+            .unwrap()
+            .steps
+            .first()
+            .map_or(F::zero(), |cs| F::from(cs.rwc_inc_left));
+        self.copy_rwc_inc
+            .assign(region, offset, Some(copy_rwc_inc))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eth_types::{address, bytecode, Bytecode, ToWord, Word};
+    use mock::TestContext;
+
+    use crate::test_util::run_test_circuits;
+
+    fn test_ok(
+        external_code: Option<Bytecode>,
+        memory_offset: usize,
+        code_offset: usize,
+        size: usize,
+        is_warm: bool,
+    ) {
+        let external_address = address!("0xaabbccddee000000000000000000000000000000");
+
+        let mut code = Bytecode::default();
+        if is_warm {
+            code.append(&bytecode! {
+                PUSH20(external_address.to_word())
+                EXTCODEHASH
+                POP
+            });
+        }
+        code.append(&bytecode! {
+            PUSH32(Word::from(size))
+            PUSH32(Word::from(code_offset))
+            PUSH32(Word::from(memory_offset))
+            PUSH20(external_address.to_word())
+            #[start]
+            EXTCODECOPY
+            STOP
+        });
+
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(Word::from(1u64 << 20))
+                    .code(code);
+
+                accs[1].address(external_address);
+                if let Some(external_code) = external_code {
+                    accs[1].code(external_code);
+                }
+                accs[2]
+                    .address(address!("0x0000000000000000000000000000000000000010"))
+                    .balance(Word::from(1u64 << 20));
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[0].address).from(accs[2].address);
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    fn external_code(large: bool) -> Bytecode {
+        let mut code = bytecode! {};
+        let n = if large { 0x101 } else { 0x10 };
+        for _ in 0..n {
+            code.push(1, Word::from(123));
+        }
+        code
+    }
+
+    #[test]
+    fn extcodecopy_gadget_simple() {
+        test_ok(Some(external_code(false)), 0x00, 0x00, 0x20, false);
+        test_ok(Some(external_code(false)), 0x20, 0x10, 0x30, true);
+        test_ok(Some(external_code(false)), 0x10, 0x00, 0x00, false);
+    }
+
+    #[test]
+    fn extcodecopy_gadget_empty_account() {
+        test_ok(None, 0x00, 0x00, 0x20, false);
+        test_ok(None, 0x20, 0x10, 0x30, true);
+    }
+
+    #[test]
+    fn extcodecopy_gadget_large() {
+        test_ok(Some(external_code(true)), 0x103, 0x102, 0x101, false);
+    }
+}
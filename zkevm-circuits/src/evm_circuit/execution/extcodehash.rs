@@ -207,7 +207,7 @@ mod test {
         if is_warm {
             code.append(&bytecode! {
                 PUSH20(external_address.to_word())
-                EXTCODEHASH // TODO: Change this to BALANCE once is implemented
+                BALANCE
                 POP
             });
         }
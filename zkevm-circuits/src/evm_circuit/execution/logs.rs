@@ -141,6 +141,7 @@ impl<F: Field> ExecutionGadget<F> for LogGadget<F> {
                 memory_address.length(),
                 cb.curr.state.rw_counter.expr() + cb.rw_counter_offset().expr(),
                 copy_rwc_inc.expr(),
+                0.expr(),
             );
         });
         cb.condition(not::expr(cond), |cb| {
@@ -152,7 +153,7 @@ impl<F: Field> ExecutionGadget<F> for LogGadget<F> {
 
         let gas_cost = GasCost::LOG.as_u64().expr()
             + GasCost::LOG.as_u64().expr() * topic_count.clone()
-            + 8.expr() * memory_address.length()
+            + GasCost::LOG_DATA_GAS.expr() * memory_address.length()
             + memory_expansion.gas_cost();
         // State transition
 
@@ -0,0 +1,365 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_MEMORY_ADDRESS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        table::CallContextFieldTag,
+        util::{
+            common_gadget::RestoreContextGadget,
+            constraint_builder::{
+                ConstraintBuilder, ReversionInfo, StepStateTransition,
+                Transition::{Delta, Same},
+            },
+            math_gadget::{IsZeroGadget, MinMaxGadget},
+            memory_gadget::{MemoryAddressGadget, MemoryExpansionGadget},
+            not, CachedRegion, Cell,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use bus_mapping::{circuit_input_builder::CopyDataType, evm::OpcodeId};
+use eth_types::Field;
+use halo2_proofs::plonk::Error;
+
+/// Gadget for `OpcodeId::REVERT`, which halts the call in failure. The revert
+/// data is copied into the return data buffer of the caller, and all the
+/// reversible writes done in the call are undone by the rw rows right after
+/// this step, which are skipped by the rw_counter transition.
+#[derive(Clone, Debug)]
+pub(crate) struct RevertGadget<F> {
+    opcode: Cell<F>,
+    memory_address: MemoryAddressGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    reversion_info: ReversionInfo<F>,
+    return_data_offset: Cell<F>,
+    return_data_length: Cell<F>,
+    copy_length: MinMaxGadget<F, N_BYTES_MEMORY_ADDRESS>,
+    copy_length_is_zero: IsZeroGadget<F>,
+    copy_rwc_inc: Cell<F>,
+    restore_context: RestoreContextGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for RevertGadget<F> {
+    const NAME: &'static str = "REVERT";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::REVERT;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+
+        // We do the responsible opcode check explicitly here because we're not using
+        // the `SameContextGadget` for `REVERT`.
+        cb.require_equal(
+            "Opcode should be REVERT",
+            opcode.expr(),
+            OpcodeId::REVERT.expr(),
+        );
+
+        let offset = cb.query_cell();
+        let length = cb.query_rlc();
+        cb.stack_pop(offset.expr());
+        cb.stack_pop(length.expr());
+        let memory_address = MemoryAddressGadget::construct(cb, offset, length);
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [memory_address.address()],
+        );
+
+        // Call ends with REVERT must be failed and not persistent
+        cb.call_context_lookup(false.expr(), None, CallContextFieldTag::IsSuccess, 0.expr());
+        let reversion_info = cb.reversion_info(None);
+        cb.require_zero(
+            "Call ends with REVERT is not persistent",
+            reversion_info.is_persistent(),
+        );
+
+        let is_to_end_tx = cb.next.execution_state_selector([ExecutionState::EndTx]);
+        cb.require_equal(
+            "Go to EndTx only when is_root",
+            cb.curr.state.is_root.expr(),
+            is_to_end_tx,
+        );
+
+        // The revert data is copied into the return data buffer of the caller,
+        // up to the buffer's length.
+        let return_data_offset = cb.query_cell();
+        let return_data_length = cb.query_cell();
+        let copy_length =
+            MinMaxGadget::construct(cb, memory_address.length(), return_data_length.expr());
+        let copy_length_is_zero = IsZeroGadget::construct(cb, copy_length.min());
+        let copy_rwc_inc = cb.query_cell();
+
+        // When it's a root call
+        cb.condition(cb.curr.state.is_root.expr(), |cb| {
+            cb.require_zero(
+                "Root call has no return data buffer",
+                return_data_length.expr(),
+            );
+
+            // All reversible writes of this call are reverted right after this
+            // step.
+            cb.require_equal(
+                "rw_counter_end_of_reversion == rw_counter + rw_counter_offset + reversible_write_counter - 1",
+                reversion_info.rw_counter_end_of_reversion(),
+                cb.curr.state.rw_counter.expr()
+                    + cb.rw_counter_offset()
+                    + cb.curr.state.reversible_write_counter.expr()
+                    - 1.expr(),
+            );
+
+            // Do step state transition
+            cb.require_step_state_transition(StepStateTransition {
+                call_id: Same,
+                rw_counter: Delta(
+                    cb.rw_counter_offset() + cb.curr.state.reversible_write_counter.expr(),
+                ),
+                gas_left: Delta(-memory_expansion.gas_cost()),
+                ..StepStateTransition::any()
+            });
+        });
+
+        // When it's an internal call
+        let restore_context = cb.condition(not::expr(cb.curr.state.is_root.expr()), |cb| {
+            for (field_tag, value) in [
+                (CallContextFieldTag::ReturnDataOffset, &return_data_offset),
+                (CallContextFieldTag::ReturnDataLength, &return_data_length),
+            ] {
+                cb.call_context_lookup(false.expr(), None, field_tag, value.expr());
+            }
+
+            let restore_context = RestoreContextGadget::construct(
+                cb,
+                cb.rw_counter_offset()
+                    + copy_rwc_inc.expr()
+                    + cb.curr.state.reversible_write_counter.expr(),
+                memory_address.offset(),
+                memory_address.length(),
+                memory_expansion.gas_cost(),
+                0.expr(),
+            );
+
+            // All reversible writes of this call are reverted right after the
+            // copy of the revert data.
+            cb.require_equal(
+                "rw_counter_end_of_reversion == rw_counter + rw_counter_offset + copy_rwc_inc + reversible_write_counter - 1",
+                reversion_info.rw_counter_end_of_reversion(),
+                cb.curr.state.rw_counter.expr()
+                    + cb.rw_counter_offset()
+                    + copy_rwc_inc.expr()
+                    + cb.curr.state.reversible_write_counter.expr()
+                    - 1.expr(),
+            );
+
+            restore_context
+        });
+
+        cb.condition(
+            not::expr(cb.curr.state.is_root.expr()) * not::expr(copy_length_is_zero.expr()),
+            |cb| {
+                cb.copy_table_lookup(
+                    cb.curr.state.call_id.expr(),
+                    CopyDataType::Memory.expr(),
+                    restore_context.caller_id(),
+                    CopyDataType::Memory.expr(),
+                    memory_address.offset(),
+                    memory_address.offset() + copy_length.min(),
+                    return_data_offset.expr(),
+                    copy_length.min(),
+                    cb.curr.state.rw_counter.expr() + cb.rw_counter_offset(),
+                    copy_rwc_inc.expr(),
+                    0.expr(),
+                );
+            },
+        );
+        cb.condition(copy_length_is_zero.expr(), |cb| {
+            cb.require_zero(
+                "if no bytes to copy, copy table rwc inc == 0",
+                copy_rwc_inc.expr(),
+            );
+        });
+
+        Self {
+            opcode,
+            memory_address,
+            memory_expansion,
+            reversion_info,
+            return_data_offset,
+            return_data_length,
+            copy_length,
+            copy_length_is_zero,
+            copy_rwc_inc,
+            restore_context,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        let [memory_offset, length] =
+            [0, 1].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        let memory_address =
+            self.memory_address
+                .assign(region, offset, memory_offset, length, block.randomness)?;
+        self.memory_expansion
+            .assign(region, offset, step.memory_word_size(), [memory_address])?;
+
+        self.reversion_info.assign(
+            region,
+            offset,
+            call.rw_counter_end_of_reversion,
+            call.is_persistent,
+        )?;
+
+        let (return_data_offset, return_data_length) = if call.is_root {
+            (0, 0)
+        } else {
+            (call.return_data_offset, call.return_data_length)
+        };
+        self.return_data_offset
+            .assign(region, offset, Some(F::from(return_data_offset)))?;
+        self.return_data_length
+            .assign(region, offset, Some(F::from(return_data_length)))?;
+        let (copy_length, _) = self.copy_length.assign(
+            region,
+            offset,
+            F::from(length.low_u64()),
+            F::from(return_data_length),
+        )?;
+        self.copy_length_is_zero
+            .assign(region, offset, copy_length)?;
+
+        let copy_rwc_inc = block
+            .copy_events
+            .get(&(tx.id, call.id, step.program_counter as usize))
+            .and_then(|copy_event| copy_event.steps.first())
+            .map_or(F::zero(), |cs| F::from(cs.rwc_inc_left));
+        self.copy_rwc_inc
+            .assign(region, offset, Some(copy_rwc_inc))?;
+
+        // Stack reads, is_success and reversion info, return data offset and
+        // length are before the caller's context reads.
+        self.restore_context
+            .assign(region, offset, block, call, step, 7)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::evm_circuit::{
+        test::run_test_circuit_incomplete_fixed_table, witness::block_convert,
+    };
+    use eth_types::{address, bytecode, Bytecode, ToWord, Word};
+    use itertools::Itertools;
+    use mock::TestContext;
+
+    fn test_ok(callee_code: Bytecode, return_data_offset: u64, return_data_length: u64) {
+        let callee = address!("0x0000000000000000000000000000000000000020");
+        let block_data = bus_mapping::mock::BlockData::new_from_geth_data(
+            TestContext::<3, 1>::new(
+                None,
+                |accs| {
+                    accs[0]
+                        .address(address!("0x0000000000000000000000000000000000000000"))
+                        .balance(Word::from(1u64 << 30));
+                    accs[1]
+                        .address(address!("0x0000000000000000000000000000000000000010"))
+                        .balance(Word::from(1u64 << 20))
+                        .code(bytecode! {
+                            PUSH32(return_data_length)
+                            PUSH32(return_data_offset)
+                            PUSH1(0)
+                            PUSH1(0)
+                            PUSH1(0)
+                            PUSH32(callee.to_word())
+                            GAS
+                            CALL
+                            STOP
+                        });
+                    accs[2]
+                        .address(callee)
+                        .balance(Word::from(1u64 << 20))
+                        .code(callee_code);
+                },
+                |mut txs, accs| {
+                    txs[0]
+                        .from(accs[0].address)
+                        .to(accs[1].address)
+                        .gas(Word::from(100000));
+                },
+                |block, _tx| block.number(0xcafeu64),
+            )
+            .unwrap()
+            .into(),
+        );
+        let mut builder = block_data.new_circuit_input_builder();
+        builder
+            .handle_block(&block_data.eth_block, &block_data.geth_traces)
+            .unwrap();
+        let block = block_convert(&builder.block, &builder.code_db);
+        assert_eq!(run_test_circuit_incomplete_fixed_table(block), Ok(()));
+    }
+
+    fn revert_code(offset: u64, length: u64) -> Bytecode {
+        // Store a value and write some memory before reverting, the storage
+        // write is reverted while the memory is the revert data.
+        bytecode! {
+            PUSH1(0xff)
+            PUSH1(0)
+            SSTORE
+            PUSH32(Word::from_big_endian(&(1..33).collect::<Vec<u8>>()))
+            PUSH1(0)
+            MSTORE
+            PUSH32(length)
+            PUSH32(offset)
+            REVERT
+        }
+    }
+
+    #[test]
+    fn revert_gadget_root() {
+        for (offset, length) in [(0, 0), (0, 32), (10, 20), (64, 32)] {
+            let block_data = bus_mapping::mock::BlockData::new_from_geth_data(
+                TestContext::<2, 1>::simple_ctx_with_bytecode(revert_code(offset, length))
+                    .unwrap()
+                    .into(),
+            );
+            let mut builder = block_data.new_circuit_input_builder();
+            builder
+                .handle_block(&block_data.eth_block, &block_data.geth_traces)
+                .unwrap();
+            let block = block_convert(&builder.block, &builder.code_db);
+            assert_eq!(run_test_circuit_incomplete_fixed_table(block), Ok(()));
+        }
+    }
+
+    #[test]
+    fn revert_gadget_internal() {
+        let reverts = [(0, 0), (0, 32), (10, 20), (64, 32)];
+        let return_data_buffers = [(0, 0), (0, 32), (32, 16), (0, 64)];
+        for ((offset, length), (return_data_offset, return_data_length)) in reverts
+            .into_iter()
+            .cartesian_product(return_data_buffers.into_iter())
+        {
+            test_ok(
+                revert_code(offset, length),
+                return_data_offset,
+                return_data_length,
+            );
+        }
+    }
+}
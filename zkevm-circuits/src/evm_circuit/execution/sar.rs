@@ -0,0 +1,187 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        util::{
+            common_gadget::SameContextGadget,
+            constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
+            math_gadget::{LtGadget, ShrWordsGadget},
+            select, CachedRegion, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use bus_mapping::evm::OpcodeId;
+use eth_types::{Field, ToLittleEndian, U256};
+use halo2_proofs::plonk::Error;
+
+/// Gadget for `SAR`, which reuses the logical shift right of [`ShrWordsGadget`]
+/// by taking the one's complement of negative values before and after the
+/// shift, i.e. `a >> shift == !(!a >> shift)` for negative `a`, so the sign
+/// bit is filled in from the left.
+#[derive(Clone, Debug)]
+pub(crate) struct SarGadget<F> {
+    same_context: SameContextGadget<F>,
+    a: Word<F>,
+    b: Word<F>,
+    // a < 0
+    is_neg: LtGadget<F, 1>,
+    shr_words: ShrWordsGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for SarGadget<F> {
+    const NAME: &'static str = "SAR";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::SAR;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+
+        let a = cb.query_word();
+        let shift = cb.query_word();
+        let b = cb.query_word();
+
+        cb.stack_pop(shift.expr());
+        cb.stack_pop(a.expr());
+
+        // a is negative if its most significant byte is >= 128
+        let is_neg = LtGadget::construct(cb, 127.expr(), a.cells[31].expr());
+
+        // The logical shift right is done over `!a` when `a` is negative, and
+        // the result is complemented back into `b`.
+        let a_shr = cb.query_word();
+        for (a_shr_cell, a_cell) in a_shr.cells.iter().zip(a.cells.iter()) {
+            cb.require_equal(
+                "a_shr == is_neg ? !a : a",
+                a_shr_cell.expr(),
+                select::expr(is_neg.expr(), 255.expr() - a_cell.expr(), a_cell.expr()),
+            );
+        }
+        let shr_words = ShrWordsGadget::construct(cb, a_shr, shift);
+        for (b_cell, b_shr_cell) in b.cells.iter().zip(shr_words.b().cells.iter()) {
+            cb.require_equal(
+                "b == is_neg ? !b_shr : b_shr",
+                b_cell.expr(),
+                select::expr(
+                    is_neg.expr(),
+                    255.expr() - b_shr_cell.expr(),
+                    b_shr_cell.expr(),
+                ),
+            );
+        }
+        cb.stack_push(b.expr());
+
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(3.expr()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(1.expr()),
+            gas_left: Delta(-OpcodeId::SAR.constant_gas_cost().expr()),
+            ..Default::default()
+        };
+        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            a,
+            b,
+            is_neg,
+            shr_words,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        _: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+        let indices = [step.rw_indices[0], step.rw_indices[1], step.rw_indices[2]];
+        let [shift, a, b] = indices.map(|idx| block.rws[idx].stack_value());
+        self.a.assign(region, offset, Some(a.to_le_bytes()))?;
+        self.b.assign(region, offset, Some(b.to_le_bytes()))?;
+
+        let is_neg = a.bit(255);
+        self.is_neg.assign(
+            region,
+            offset,
+            F::from(127),
+            F::from(a.to_le_bytes()[31] as u64),
+        )?;
+
+        let a_shr = if is_neg { !a } else { a };
+        let b_shr = if shift < U256::from(256) {
+            a_shr >> shift.as_usize()
+        } else {
+            U256::zero()
+        };
+        self.shr_words.assign(region, offset, a_shr, shift, b_shr)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::evm_circuit::test::rand_word;
+    use crate::test_util::run_test_circuits;
+    use eth_types::evm_types::OpcodeId;
+    use eth_types::{bytecode, Word};
+    use mock::TestContext;
+    use rand::Rng;
+
+    fn test_ok(a: Word, shift: Word) {
+        let bytecode = bytecode! {
+            PUSH32(a)
+            PUSH32(shift)
+            #[start]
+            SAR
+            STOP
+        };
+        assert_eq!(
+            run_test_circuits(
+                TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+                None
+            ),
+            Ok(())
+        );
+    }
+
+    fn neg(value: Word) -> Word {
+        (!value).overflowing_add(Word::one()).0
+    }
+
+    #[test]
+    fn sar_gadget_simple() {
+        test_ok(0xABCD.into(), 8.into());
+        test_ok(0x1234.into(), 7.into());
+        test_ok(0x8765.into(), 17.into());
+        test_ok(0x4321.into(), 0.into());
+        test_ok(rand_word(), 127.into());
+        test_ok(rand_word(), 129.into());
+        let rand_shift = rand::thread_rng().gen_range(0..=255);
+        test_ok(rand_word(), rand_shift.into());
+    }
+
+    #[test]
+    fn sar_gadget_negative() {
+        test_ok(neg(0xABCD.into()), 8.into());
+        test_ok(neg(0x1234.into()), 7.into());
+        test_ok(neg(Word::one()), 1.into());
+        test_ok(neg(Word::one()), 0.into());
+        test_ok(Word::one() << 255, 255.into());
+        test_ok(neg(rand_word() >> 1), 64.into());
+        let rand_shift = rand::thread_rng().gen_range(0..=255);
+        test_ok(neg(rand_word() >> 1), rand_shift.into());
+    }
+
+    #[test]
+    fn sar_gadget_rand_overflow_shift() {
+        test_ok(rand_word() >> 1, 256.into());
+        test_ok(neg(rand_word() >> 1), 256.into());
+        test_ok(neg(0x1234.into()), 0x1234.into());
+        test_ok(neg(rand_word() >> 1), Word::from_big_endian(&[255_u8; 32]));
+    }
+}
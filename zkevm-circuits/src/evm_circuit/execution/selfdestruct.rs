@@ -0,0 +1,397 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_ACCOUNT_ADDRESS,
+        step::ExecutionState,
+        table::{AccountFieldTag, CallContextFieldTag},
+        util::{
+            common_gadget::{RestoreContextGadget, TransferGadget},
+            constraint_builder::{
+                ConstraintBuilder, ReversionInfo, StepStateTransition,
+                Transition::{Delta, Same},
+            },
+            from_bytes,
+            math_gadget::{BatchedIsZeroGadget, IsEqualGadget, IsZeroGadget},
+            select, sum, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use bus_mapping::evm::OpcodeId;
+use eth_types::{evm_types::GasCost, Field, ToLittleEndian, ToScalar};
+use halo2_proofs::plonk::{Error, Expression};
+use keccak256::EMPTY_HASH_LE;
+
+/// Gadget for `SELFDESTRUCT`, which transfers all the balance of the current
+/// account to the beneficiary and marks the account as destructed, so it's
+/// deleted at the end of the transaction.
+#[derive(Clone, Debug)]
+pub(crate) struct SelfdestructGadget<F> {
+    opcode: Cell<F>,
+    beneficiary: Word<F>,
+    tx_id: Cell<F>,
+    callee_address: Cell<F>,
+    reversion_info: ReversionInfo<F>,
+    is_warm_prev: Cell<F>,
+    value: Word<F>,
+    transfer: TransferGadget<F>,
+    beneficiary_nonce: Cell<F>,
+    beneficiary_code_hash: Cell<F>,
+    is_account_empty: BatchedIsZeroGadget<F, 2>,
+    is_empty_code_hash: IsEqualGadget<F>,
+    value_is_zero: IsZeroGadget<F>,
+    is_destructed_prev: Cell<F>,
+    restore_context: RestoreContextGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for SelfdestructGadget<F> {
+    const NAME: &'static str = "SELFDESTRUCT";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::SELFDESTRUCT;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+
+        // We do the responsible opcode check explicitly here because we're not using
+        // the `SameContextGadget` for `SELFDESTRUCT`.
+        cb.require_equal(
+            "Opcode should be SELFDESTRUCT",
+            opcode.expr(),
+            OpcodeId::SELFDESTRUCT.expr(),
+        );
+
+        let beneficiary = cb.query_word();
+        cb.stack_pop(beneficiary.expr());
+        let beneficiary_address = from_bytes::expr(&beneficiary.cells[..N_BYTES_ACCOUNT_ADDRESS]);
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        // SELFDESTRUCT modifies the state, so it's not allowed in a static call
+        cb.call_context_lookup(false.expr(), None, CallContextFieldTag::IsStatic, 0.expr());
+        let callee_address = cb.call_context(None, CallContextFieldTag::CalleeAddress);
+        let mut reversion_info = cb.reversion_info(None);
+        // Call ends with SELFDESTRUCT must be successful
+        cb.call_context_lookup(false.expr(), None, CallContextFieldTag::IsSuccess, 1.expr());
+
+        let is_warm_prev = cb.query_bool();
+        cb.account_access_list_write(
+            tx_id.expr(),
+            beneficiary_address.clone(),
+            1.expr(),
+            is_warm_prev.expr(),
+            Some(&mut reversion_info),
+        );
+
+        // Transfer all the balance to the beneficiary, so the balance of the
+        // current account becomes zero.
+        let value = cb.query_word();
+        let transfer = TransferGadget::construct(
+            cb,
+            callee_address.expr(),
+            beneficiary_address.clone(),
+            value.clone(),
+            &mut reversion_info,
+        );
+        cb.require_zero(
+            "Balance of the destructed account becomes zero",
+            sum::expr(&transfer.sender().balance().cells),
+        );
+
+        let [beneficiary_nonce, beneficiary_code_hash] =
+            [AccountFieldTag::Nonce, AccountFieldTag::CodeHash].map(|field_tag| {
+                let value = cb.query_cell();
+                cb.account_read(beneficiary_address.clone(), field_tag, value.expr());
+                value
+            });
+        let is_account_empty = BatchedIsZeroGadget::construct(
+            cb,
+            [
+                beneficiary_nonce.expr(),
+                transfer.receiver().balance_prev().expr(),
+            ],
+        );
+        let is_empty_code_hash = IsEqualGadget::construct(
+            cb,
+            beneficiary_code_hash.expr(),
+            Word::random_linear_combine_expr(
+                (*EMPTY_HASH_LE).map(|byte| byte.expr()),
+                cb.power_of_randomness(),
+            ),
+        );
+        let value_is_zero = IsZeroGadget::construct(cb, sum::expr(&value.cells));
+
+        // Since EIP-3529 there is no refund for SELFDESTRUCT.
+        let is_destructed_prev = cb.query_bool();
+        cb.account_destructed_write(
+            callee_address.expr(),
+            1.expr(),
+            is_destructed_prev.expr(),
+            Some(&mut reversion_info),
+        );
+
+        let gas_cost = selfdestruct_gas_cost_expr(
+            is_warm_prev.expr(),
+            1.expr() - value_is_zero.expr(),
+            is_account_empty.expr(),
+            is_empty_code_hash.expr(),
+        );
+
+        let is_to_end_tx = cb.next.execution_state_selector([ExecutionState::EndTx]);
+        cb.require_equal(
+            "Go to EndTx only when is_root",
+            cb.curr.state.is_root.expr(),
+            is_to_end_tx,
+        );
+
+        // When it's a root call
+        cb.condition(cb.curr.state.is_root.expr(), |cb| {
+            cb.require_step_state_transition(StepStateTransition {
+                call_id: Same,
+                rw_counter: Delta(cb.rw_counter_offset()),
+                gas_left: Delta(-gas_cost.clone()),
+                ..StepStateTransition::any()
+            });
+        });
+
+        // When it's an internal call
+        let restore_context = cb.condition(1.expr() - cb.curr.state.is_root.expr(), |cb| {
+            RestoreContextGadget::construct(
+                cb,
+                cb.rw_counter_offset(),
+                0.expr(),
+                0.expr(),
+                gas_cost,
+                // Access list, balances of sender and receiver and destructed
+                // flag.
+                4.expr(),
+            )
+        });
+
+        Self {
+            opcode,
+            beneficiary,
+            tx_id,
+            callee_address,
+            reversion_info,
+            is_warm_prev,
+            value,
+            transfer,
+            beneficiary_nonce,
+            beneficiary_code_hash,
+            is_account_empty,
+            is_empty_code_hash,
+            value_is_zero,
+            is_destructed_prev,
+            restore_context,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Some(F::from(opcode.as_u64())))?;
+
+        let beneficiary = block.rws[step.rw_indices[0]].stack_value();
+        self.beneficiary
+            .assign(region, offset, Some(beneficiary.to_le_bytes()))?;
+        self.tx_id
+            .assign(region, offset, Some(F::from(tx.id as u64)))?;
+        let callee_address = block.rws[step.rw_indices[3]].call_context_value();
+        self.callee_address
+            .assign(region, offset, callee_address.to_scalar())?;
+        self.reversion_info.assign(
+            region,
+            offset,
+            call.rw_counter_end_of_reversion,
+            call.is_persistent,
+        )?;
+
+        let (_, is_warm_prev) = block.rws[step.rw_indices[7]].tx_access_list_value_pair();
+        self.is_warm_prev
+            .assign(region, offset, Some(F::from(is_warm_prev as u64)))?;
+
+        let [sender_balance_pair, beneficiary_balance_pair] =
+            [step.rw_indices[8], step.rw_indices[9]].map(|idx| block.rws[idx].account_value_pair());
+        let value = sender_balance_pair.1;
+        self.value
+            .assign(region, offset, Some(value.to_le_bytes()))?;
+        self.transfer.assign(
+            region,
+            offset,
+            sender_balance_pair,
+            beneficiary_balance_pair,
+            value,
+        )?;
+
+        let [beneficiary_nonce, beneficiary_code_hash] = [step.rw_indices[10], step.rw_indices[11]]
+            .map(|idx| block.rws[idx].account_value_pair().0);
+        self.beneficiary_nonce
+            .assign(region, offset, beneficiary_nonce.to_scalar())?;
+        self.beneficiary_code_hash.assign(
+            region,
+            offset,
+            Some(Word::random_linear_combine(
+                beneficiary_code_hash.to_le_bytes(),
+                block.randomness,
+            )),
+        )?;
+        self.is_account_empty.assign(
+            region,
+            offset,
+            [
+                F::from(beneficiary_nonce.low_u64()),
+                Word::random_linear_combine(
+                    beneficiary_balance_pair.1.to_le_bytes(),
+                    block.randomness,
+                ),
+            ],
+        )?;
+        self.is_empty_code_hash.assign(
+            region,
+            offset,
+            Word::random_linear_combine(beneficiary_code_hash.to_le_bytes(), block.randomness),
+            Word::random_linear_combine(*EMPTY_HASH_LE, block.randomness),
+        )?;
+        self.value_is_zero
+            .assign(region, offset, sum::value(&value.to_le_bytes()))?;
+
+        let (_, is_destructed_prev) =
+            block.rws[step.rw_indices[12]].account_destructed_value_pair();
+        self.is_destructed_prev
+            .assign(region, offset, Some(F::from(is_destructed_prev as u64)))?;
+
+        self.restore_context
+            .assign(region, offset, block, call, step, 13)?;
+
+        Ok(())
+    }
+}
+
+/// Sums up the gas cost of `SELFDESTRUCT`, where a new account is created only
+/// when some value is sent to an empty beneficiary.
+pub(crate) fn selfdestruct_gas_cost_expr<F: Field>(
+    is_warm_prev: Expression<F>,
+    has_value: Expression<F>,
+    is_account_empty: Expression<F>,
+    is_empty_code_hash: Expression<F>,
+) -> Expression<F> {
+    OpcodeId::SELFDESTRUCT.constant_gas_cost().expr()
+        + select::expr(is_warm_prev, 0.expr(), GasCost::COLD_ACCOUNT_ACCESS.expr())
+        + has_value * is_account_empty * is_empty_code_hash * GasCost::NEW_ACCOUNT.expr()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::evm_circuit::{
+        test::run_test_circuit_incomplete_fixed_table, witness::block_convert,
+    };
+    use eth_types::{address, bytecode, Bytecode, Word};
+    use mock::TestContext;
+
+    fn test_ok(bytecode: Bytecode, is_root: bool) {
+        let block_data = if is_root {
+            bus_mapping::mock::BlockData::new_from_geth_data(
+                TestContext::<2, 1>::new(
+                    None,
+                    |accs| {
+                        accs[0]
+                            .address(address!("0x0000000000000000000000000000000000000000"))
+                            .balance(Word::from(1u64 << 30));
+                        accs[1]
+                            .address(address!("0x0000000000000000000000000000000000000010"))
+                            .balance(Word::from(1u64 << 20))
+                            .code(bytecode);
+                    },
+                    |mut txs, accs| {
+                        txs[0]
+                            .from(accs[0].address)
+                            .to(accs[1].address)
+                            .gas(Word::from(100000));
+                    },
+                    |block, _tx| block.number(0xcafeu64),
+                )
+                .unwrap()
+                .into(),
+            )
+        } else {
+            bus_mapping::mock::BlockData::new_from_geth_data(
+                TestContext::<3, 1>::new(
+                    None,
+                    |accs| {
+                        accs[0]
+                            .address(address!("0x0000000000000000000000000000000000000000"))
+                            .balance(Word::from(1u64 << 30));
+                        accs[1]
+                            .address(address!("0x0000000000000000000000000000000000000010"))
+                            .balance(Word::from(1u64 << 20))
+                            .code(bytecode! {
+                                PUSH1(0)
+                                PUSH1(0)
+                                PUSH1(0)
+                                PUSH1(0)
+                                PUSH1(0)
+                                PUSH1(0x20)
+                                GAS
+                                CALL
+                                STOP
+                            });
+                        accs[2]
+                            .address(address!("0x0000000000000000000000000000000000000020"))
+                            .balance(Word::from(1u64 << 20))
+                            .code(bytecode);
+                    },
+                    |mut txs, accs| {
+                        txs[0]
+                            .from(accs[0].address)
+                            .to(accs[1].address)
+                            .gas(Word::from(100000));
+                    },
+                    |block, _tx| block.number(0xcafeu64),
+                )
+                .unwrap()
+                .into(),
+            )
+        };
+        let mut builder = block_data.new_circuit_input_builder();
+        builder
+            .handle_block(&block_data.eth_block, &block_data.geth_traces)
+            .unwrap();
+        let block = block_convert(&builder.block, &builder.code_db);
+        assert_eq!(run_test_circuit_incomplete_fixed_table(block), Ok(()));
+    }
+
+    #[test]
+    fn selfdestruct_gadget_simple() {
+        for is_root in [true, false] {
+            test_ok(
+                bytecode! {
+                    PUSH20(0xfe)
+                    SELFDESTRUCT
+                },
+                is_root,
+            );
+        }
+    }
+
+    #[test]
+    fn selfdestruct_gadget_to_existing_account() {
+        for is_root in [true, false] {
+            test_ok(
+                bytecode! {
+                    PUSH20(0)
+                    SELFDESTRUCT
+                },
+                is_root,
+            );
+        }
+    }
+}
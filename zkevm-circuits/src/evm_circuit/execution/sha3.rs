@@ -0,0 +1,219 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_MEMORY_WORD_SIZE,
+        step::ExecutionState,
+        util::{
+            common_gadget::SameContextGadget,
+            constraint_builder::{
+                ConstraintBuilder, StepStateTransition,
+                Transition::{Delta, To},
+            },
+            memory_gadget::{MemoryAddressGadget, MemoryCopierGasGadget, MemoryExpansionGadget},
+            not, rlc, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use bus_mapping::{circuit_input_builder::CopyDataType, evm::OpcodeId};
+use eth_types::{evm_types::GasCost, Field, ToLittleEndian};
+use halo2_proofs::plonk::Error;
+
+/// Sha3Gadget verifies opcode SHA3. The bytes to be hashed are copied from
+/// memory into a random linear combination by the copy table, and the hash is
+/// then looked up from the keccak table.
+#[derive(Clone, Debug)]
+pub(crate) struct Sha3Gadget<F> {
+    same_context: SameContextGadget<F>,
+    memory_address: MemoryAddressGadget<F>,
+    sha3_rlc: Word<F>,
+    copy_rwc_inc: Cell<F>,
+    rlc_acc: Cell<F>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    memory_copier_gas: MemoryCopierGasGadget<F, { GasCost::COPY_SHA3.as_u64() }>,
+}
+
+impl<F: Field> ExecutionGadget<F> for Sha3Gadget<F> {
+    const NAME: &'static str = "SHA3";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::SHA3;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+
+        let offset = cb.query_cell();
+        let size = cb.query_rlc();
+        let sha3_rlc = cb.query_word();
+
+        cb.stack_pop(offset.expr());
+        cb.stack_pop(size.expr());
+        cb.stack_push(sha3_rlc.expr());
+
+        let memory_address = MemoryAddressGadget::construct(cb, offset, size);
+
+        let copy_rwc_inc = cb.query_cell();
+        let rlc_acc = cb.query_cell();
+        cb.condition(memory_address.has_length(), |cb| {
+            cb.copy_table_lookup(
+                cb.curr.state.call_id.expr(),
+                CopyDataType::Memory.expr(),
+                cb.curr.state.call_id.expr(),
+                CopyDataType::RlcAcc.expr(),
+                memory_address.offset(),
+                memory_address.address(),
+                0.expr(), // dst_addr for CopyDataType::RlcAcc is 0.
+                memory_address.length(),
+                cb.curr.state.rw_counter.expr() + cb.rw_counter_offset().expr(),
+                copy_rwc_inc.expr(),
+                rlc_acc.expr(),
+            );
+        });
+        cb.condition(not::expr(memory_address.has_length()), |cb| {
+            cb.require_zero("if no bytes to hash, rlc_acc == 0", rlc_acc.expr());
+            cb.require_zero(
+                "if no bytes to hash, copy table rwc inc == 0",
+                copy_rwc_inc.expr(),
+            );
+        });
+
+        cb.keccak_table_lookup(rlc_acc.expr(), memory_address.length(), sha3_rlc.expr());
+
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [memory_address.address()],
+        );
+        let memory_copier_gas = MemoryCopierGasGadget::construct(
+            cb,
+            memory_address.length(),
+            memory_expansion.gas_cost(),
+        );
+
+        let step_state_transition = StepStateTransition {
+            // 2 stack pops + 1 stack push + memory reads of the copy event
+            rw_counter: Delta(cb.rw_counter_offset() + copy_rwc_inc.expr()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(1.expr()),
+            gas_left: Delta(
+                -(OpcodeId::SHA3.constant_gas_cost().expr() + memory_copier_gas.gas_cost()),
+            ),
+            memory_word_size: To(memory_expansion.next_memory_word_size()),
+            ..Default::default()
+        };
+        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            memory_address,
+            sha3_rlc,
+            copy_rwc_inc,
+            rlc_acc,
+            memory_expansion,
+            memory_copier_gas,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let [memory_offset, size, sha3_output] =
+            [0, 1, 2].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        let memory_address =
+            self.memory_address
+                .assign(region, offset, memory_offset, size, block.randomness)?;
+        self.sha3_rlc
+            .assign(region, offset, Some(sha3_output.to_le_bytes()))?;
+
+        let key = (tx.id, call.id, step.program_counter as usize);
+        let copy_rwc_inc = block
+            .copy_events
+            .get(&key)
+            .unwrap()
+            .steps
+            .first()
+            .map_or(F::zero(), |cs| F::from(cs.rwc_inc_left));
+        self.copy_rwc_inc
+            .assign(region, offset, Some(copy_rwc_inc))?;
+
+        let values: Vec<u8> = (3..3 + (size.low_u64() as usize))
+            .map(|idx| block.rws[step.rw_indices[idx]].memory_value())
+            .collect();
+        let rlc_acc = rlc::value(&values, block.randomness);
+        self.rlc_acc.assign(region, offset, Some(rlc_acc))?;
+
+        // Memory expansion and dynamic gas cost
+        let (_, memory_expansion_gas_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [memory_address],
+        )?;
+        self.memory_copier_gas.assign(
+            region,
+            offset,
+            size.as_u64(),
+            memory_expansion_gas_cost as u64,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{evm_circuit::test::rand_bytes, test_util::run_test_circuits};
+    use eth_types::{bytecode, Bytecode, U256};
+    use mock::TestContext;
+
+    fn test_ok(offset: usize, size: usize, mstore_size: usize) {
+        let mut code = Bytecode::default();
+        for (idx, byte) in rand_bytes(mstore_size).into_iter().enumerate() {
+            code.append(&bytecode! {
+                PUSH1(byte)
+                PUSH32(U256::from(idx))
+                MSTORE8
+            });
+        }
+        code.append(&bytecode! {
+            PUSH32(size)
+            PUSH32(offset)
+            SHA3
+            STOP
+        });
+
+        assert_eq!(
+            run_test_circuits(
+                TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap(),
+                None
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn sha3_gadget_zero_length() {
+        test_ok(0x20, 0x00, 0x10);
+    }
+
+    #[test]
+    fn sha3_gadget_simple() {
+        test_ok(0x00, 0x08, 0x10);
+        test_ok(0x10, 0x10, 0x20);
+        test_ok(0x04, 0x1c, 0x20);
+    }
+
+    #[test]
+    fn sha3_gadget_large() {
+        test_ok(0x10, 0x101, 0x20);
+        test_ok(0x101, 0x202, 0x40);
+    }
+}
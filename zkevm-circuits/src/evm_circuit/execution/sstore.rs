@@ -16,7 +16,10 @@ use crate::{
     util::Expr,
 };
 
-use eth_types::{evm_types::GasCost, Field, ToLittleEndian, ToScalar};
+use eth_types::{
+    evm_types::{gas_utils::sstore_gas_cost, GasCost},
+    Field, ToLittleEndian, ToScalar,
+};
 use halo2_proofs::plonk::{Error, Expression};
 
 #[derive(Clone, Debug)]
@@ -340,7 +343,7 @@ impl<F: Field> SstoreGasGadget<F> {
             Word::random_linear_combine(original_value.to_le_bytes(), randomness),
         )?;
         debug_assert_eq!(
-            calc_expected_gas_cost(value, value_prev, original_value, is_warm),
+            sstore_gas_cost(value, value_prev, original_value, is_warm),
             gas_cost
         );
         Ok(())
@@ -508,30 +511,6 @@ impl<F: Field> SstoreTxRefundGadget<F> {
     }
 }
 
-fn calc_expected_gas_cost(
-    value: eth_types::Word,
-    value_prev: eth_types::Word,
-    original_value: eth_types::Word,
-    is_warm: bool,
-) -> u64 {
-    let warm_case_gas = if value_prev == value {
-        GasCost::WARM_ACCESS
-    } else if original_value == value_prev {
-        if original_value == eth_types::Word::from(0) {
-            GasCost::SSTORE_SET
-        } else {
-            GasCost::SSTORE_RESET
-        }
-    } else {
-        GasCost::WARM_ACCESS
-    };
-    if is_warm {
-        warm_case_gas.as_u64()
-    } else {
-        warm_case_gas.as_u64() + GasCost::COLD_SLOAD.as_u64()
-    }
-}
-
 fn calc_expected_tx_refund(
     tx_refund_old: u64,
     value: eth_types::Word,
@@ -82,7 +82,7 @@ impl<F: Field> ExecutionGadget<F> for StopGadget<F> {
 
         // When it's an internal call
         let restore_context = cb.condition(1.expr() - cb.curr.state.is_root.expr(), |cb| {
-            RestoreContextGadget::construct(cb, 1.expr(), 0.expr(), 0.expr())
+            RestoreContextGadget::construct(cb, 1.expr(), 0.expr(), 0.expr(), 0.expr(), 0.expr())
         });
 
         Self {
@@ -120,7 +120,7 @@ impl<F: Field> ExecutionGadget<F> for StopGadget<F> {
             .assign(region, offset, Some(F::from(opcode.as_u64())))?;
 
         self.restore_context
-            .assign(region, offset, block, call, step)?;
+            .assign(region, offset, block, call, step, 1)?;
 
         Ok(())
     }
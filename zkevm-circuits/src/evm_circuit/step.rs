@@ -1,7 +1,7 @@
 use super::util::{CachedRegion, CellManager, CellType};
 use crate::{
     evm_circuit::{
-        param::{MAX_STEP_HEIGHT, STEP_WIDTH},
+        param::{MAX_STEP_HEIGHT, STACK_CAPACITY, STEP_WIDTH},
         util::{Cell, RandomLinearCombination},
         witness::{Block, Call, ExecStep, Transaction},
     },
@@ -79,12 +79,9 @@ pub enum ExecutionState {
     SWAP, // SWAP1, SWAP2, ..., SWAP16
     LOG,  // LOG0, LOG1, ..., LOG4
     CREATE,
-    CALL,
-    CALLCODE,
+    CALL_OP, // CALL, CALLCODE, DELEGATECALL, STATICCALL
     RETURN,
-    DELEGATECALL,
     CREATE2,
-    STATICCALL,
     REVERT,
     SELFDESTRUCT,
     // Error cases
@@ -177,7 +174,25 @@ impl ExecutionState {
         self.halts_in_success() || self.halts_in_exception() || matches!(self, Self::REVERT)
     }
 
-    pub(crate) fn responsible_opcodes(&self) -> Vec<OpcodeId> {
+    pub(crate) fn responsible_opcodes(&self) -> Vec<ResponsibleOp> {
+        // The stack errors are responsible for the opcodes only when the stack
+        // pointer is out of the valid range.
+        if matches!(self, Self::ErrorStackOverflow | Self::ErrorStackUnderflow) {
+            return OpcodeId::valid_opcodes()
+                .into_iter()
+                .flat_map(|opcode| {
+                    let (min_stack_ptr, max_stack_ptr) = opcode.valid_stack_ptr_range();
+                    let invalid_stack_ptrs = if *self == Self::ErrorStackOverflow {
+                        0..min_stack_ptr
+                    } else {
+                        max_stack_ptr + 1..STACK_CAPACITY as u64 + 1
+                    };
+                    invalid_stack_ptrs
+                        .map(move |stack_ptr| ResponsibleOp::InvalidStackPtr(opcode, stack_ptr))
+                })
+                .collect();
+        }
+
         match self {
             Self::STOP => vec![OpcodeId::STOP],
             Self::ADD_SUB => vec![OpcodeId::ADD, OpcodeId::SUB],
@@ -309,16 +324,48 @@ impl ExecutionState {
                 OpcodeId::LOG4,
             ],
             Self::CREATE => vec![OpcodeId::CREATE],
-            Self::CALL => vec![OpcodeId::CALL],
-            Self::CALLCODE => vec![OpcodeId::CALLCODE],
+            Self::CALL_OP => vec![
+                OpcodeId::CALL,
+                OpcodeId::CALLCODE,
+                OpcodeId::DELEGATECALL,
+                OpcodeId::STATICCALL,
+            ],
             Self::RETURN => vec![OpcodeId::RETURN],
-            Self::DELEGATECALL => vec![OpcodeId::DELEGATECALL],
             Self::CREATE2 => vec![OpcodeId::CREATE2],
-            Self::STATICCALL => vec![OpcodeId::STATICCALL],
             Self::REVERT => vec![OpcodeId::REVERT],
             Self::SELFDESTRUCT => vec![OpcodeId::SELFDESTRUCT],
+            Self::ErrorInvalidJump => vec![OpcodeId::JUMP, OpcodeId::JUMPI],
+            Self::ErrorInvalidOpcode => OpcodeId::invalid_opcodes(),
             _ => vec![],
         }
+        .into_iter()
+        .map(ResponsibleOp::Op)
+        .collect()
+    }
+}
+
+/// Opcode which an execution state is responsible for, in which the stack
+/// errors are only responsible for the opcode with an invalid stack pointer.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum ResponsibleOp {
+    Op(OpcodeId),
+    InvalidStackPtr(OpcodeId, u64),
+}
+
+impl ResponsibleOp {
+    pub(crate) fn opcode(&self) -> OpcodeId {
+        match self {
+            Self::Op(opcode) | Self::InvalidStackPtr(opcode, _) => *opcode,
+        }
+    }
+
+    /// Returns the auxiliary value of the responsible opcode lookup, which is
+    /// the invalid stack pointer for the stack errors, or `0` otherwise.
+    pub(crate) fn aux(&self) -> u64 {
+        match self {
+            Self::Op(_) => 0,
+            Self::InvalidStackPtr(_, stack_ptr) => *stack_ptr,
+        }
     }
 }
 
@@ -1,4 +1,5 @@
-use crate::{evm_circuit::step::ExecutionState, impl_expr};
+use crate::{evm_circuit::step::ExecutionState, impl_expr, util::Expr};
+use eth_types::evm_types::OpcodeId;
 use halo2_proofs::{
     arithmetic::FieldExt,
     plonk::{Advice, Column, Expression, Fixed, VirtualCells},
@@ -43,6 +44,7 @@ pub enum FixedTableTag {
     BitwiseXor,
     ResponsibleOpcode,
     Pow2,
+    ConstantGasCost,
 }
 
 impl FixedTableTag {
@@ -93,12 +95,12 @@ impl FixedTableTag {
                     execution_state
                         .responsible_opcodes()
                         .into_iter()
-                        .map(move |opcode| {
+                        .map(move |responsible_op| {
                             [
                                 tag,
                                 F::from(execution_state.as_u64()),
-                                F::from(opcode.as_u64()),
-                                F::zero(),
+                                F::from(responsible_op.opcode().as_u64()),
+                                F::from(responsible_op.aux()),
                             ]
                         })
                 }))
@@ -111,6 +113,16 @@ impl FixedTableTag {
                     F::zero(),
                 ]
             })),
+            Self::ConstantGasCost => {
+                Box::new(OpcodeId::valid_opcodes().into_iter().map(move |opcode| {
+                    [
+                        tag,
+                        F::from(opcode.as_u64()),
+                        F::from(opcode.constant_gas_cost().as_u64()),
+                        F::zero(),
+                    ]
+                }))
+            }
         }
     }
 }
@@ -127,6 +139,8 @@ pub enum TxContextFieldTag {
     CallDataLength,
     CallDataGasCost,
     CallData,
+    TxHash,
+    TxSignHash,
 }
 
 // Keep the sequence consistent with OpcodeId for scalar
@@ -256,6 +270,8 @@ pub(crate) enum Table {
     Block,
     Byte,
     Copy,
+    Exp,
+    Keccak,
 }
 
 #[derive(Clone, Debug)]
@@ -352,6 +368,31 @@ pub(crate) enum Lookup<F> {
         /// The RW counter that is incremented by the time all bytes have been
         /// copied specific to this copy event.
         rwc_inc: Expression<F>,
+        /// The random linear combination accumulator of the copied bytes,
+        /// which is only used when the destination is RlcAcc.
+        rlc_acc: Expression<F>,
+    },
+    /// Lookup to exponentiation table.
+    ExpTable {
+        /// Whether the row is the first row of the exponentiation event.
+        is_first: Expression<F>,
+        /// Identifier of the exponentiation event.
+        identifier: Expression<F>,
+        /// Four 64-bit limbs of the exponentiation base.
+        base_limbs: [Expression<F>; 4],
+        /// Lower and higher 128 bits of the exponent.
+        exponent_lo_hi: [Expression<F>; 2],
+        /// Lower and higher 128 bits of the exponentiation result.
+        exponentiation_lo_hi: [Expression<F>; 2],
+    },
+    /// Lookup to keccak table.
+    KeccakTable {
+        /// Random linear combination of the input bytes.
+        input_rlc: Expression<F>,
+        /// Length of the input bytes.
+        input_len: Expression<F>,
+        /// Random linear combination of the keccak digest.
+        output_rlc: Expression<F>,
     },
     /// Conditional lookup enabled by the first element.
     Conditional(Expression<F>, Box<Lookup<F>>),
@@ -371,6 +412,8 @@ impl<F: FieldExt> Lookup<F> {
             Self::Block { .. } => Table::Block,
             Self::Byte { .. } => Table::Byte,
             Self::CopyTable { .. } => Table::Copy,
+            Self::ExpTable { .. } => Table::Exp,
+            Self::KeccakTable { .. } => Table::Keccak,
             Self::Conditional(_, lookup) => lookup.table(),
         }
     }
@@ -431,6 +474,7 @@ impl<F: FieldExt> Lookup<F> {
                 length,
                 rw_counter,
                 rwc_inc,
+                rlc_acc,
             } => vec![
                 is_first.clone(),
                 src_id.clone(),
@@ -443,6 +487,30 @@ impl<F: FieldExt> Lookup<F> {
                 length.clone(),
                 rw_counter.clone(),
                 rwc_inc.clone(),
+                rlc_acc.clone(),
+            ],
+            Self::ExpTable {
+                is_first,
+                identifier,
+                base_limbs,
+                exponent_lo_hi,
+                exponentiation_lo_hi,
+            } => [
+                vec![is_first.clone(), identifier.clone()],
+                base_limbs.to_vec(),
+                exponent_lo_hi.to_vec(),
+                exponentiation_lo_hi.to_vec(),
+            ]
+            .concat(),
+            Self::KeccakTable {
+                input_rlc,
+                input_len,
+                output_rlc,
+            } => vec![
+                1.expr(), // is_enabled
+                input_rlc.clone(),
+                input_len.clone(),
+                output_rlc.clone(),
             ],
             Self::Conditional(condition, lookup) => lookup
                 .input_exprs()
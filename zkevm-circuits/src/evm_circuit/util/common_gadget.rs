@@ -1,22 +1,26 @@
 use super::CachedRegion;
 use crate::{
     evm_circuit::{
-        param::N_BYTES_GAS,
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_GAS, N_BYTES_U64, N_BYTES_WORD},
+        step::ExecutionState,
         table::{AccountFieldTag, CallContextFieldTag, FixedTableTag, Lookup},
         util::{
             constraint_builder::{
                 ConstraintBuilder, ReversionInfo, StepStateTransition,
                 Transition::{Delta, Same, To},
             },
-            math_gadget::{AddWordsGadget, RangeCheckGadget},
-            Cell, Word,
+            from_bytes,
+            math_gadget::{AddWordsGadget, ByteSizeGadget, LtGadget, RangeCheckGadget},
+            not, rlc, sum, Cell, RandomLinearCombination, Word,
         },
         witness::{Block, Call, ExecStep},
     },
     util::Expr,
 };
-use eth_types::{Field, ToLittleEndian, ToScalar, U256};
+use eth_types::{Address, Field, ToBigEndian, ToLittleEndian, ToScalar, U256};
 use halo2_proofs::plonk::{Error, Expression};
+use keccak256::plain::Keccak;
+use rlp::RlpStream;
 use std::convert::TryInto;
 
 /// Construction of execution state that stays in the same call context, which
@@ -99,6 +103,8 @@ impl<F: Field> RestoreContextGadget<F> {
         rw_counter_delta: Expression<F>,
         return_data_offset: Expression<F>,
         return_data_length: Expression<F>,
+        gas_cost: Expression<F>,
+        reversible_write_counter_increase: Expression<F>,
     ) -> Self {
         // Read caller's context for restore
         let caller_id = cb.call_context(None, CallContextFieldTag::CallerId);
@@ -137,7 +143,7 @@ impl<F: Field> RestoreContextGadget<F> {
         let gas_left = if cb.execution_state().halts_in_exception() {
             caller_gas_left.expr()
         } else {
-            caller_gas_left.expr() + cb.curr.state.gas_left.expr()
+            caller_gas_left.expr() + cb.curr.state.gas_left.expr() - gas_cost
         };
 
         // Accumulate reversible_write_counter in case this call stack reverts in the
@@ -145,7 +151,9 @@ impl<F: Field> RestoreContextGadget<F> {
         // failure, we don't need to accumulate reversible_write_counter because
         // what happened in the sub-call has been reverted.
         let reversible_write_counter = if cb.execution_state().halts_in_success() {
-            caller_reversible_write_counter.expr() + cb.curr.state.reversible_write_counter.expr()
+            caller_reversible_write_counter.expr()
+                + cb.curr.state.reversible_write_counter.expr()
+                + reversible_write_counter_increase
         } else {
             caller_reversible_write_counter.expr()
         };
@@ -178,6 +186,10 @@ impl<F: Field> RestoreContextGadget<F> {
         }
     }
 
+    pub(crate) fn caller_id(&self) -> Expression<F> {
+        self.caller_id.expr()
+    }
+
     pub(crate) fn assign(
         &self,
         region: &mut CachedRegion<'_, '_, F>,
@@ -185,23 +197,14 @@ impl<F: Field> RestoreContextGadget<F> {
         block: &Block<F>,
         call: &Call,
         step: &ExecStep,
+        rw_offset: usize,
     ) -> Result<(), Error> {
         let [caller_id, caller_is_root, caller_is_create, caller_code_hash, caller_program_counter, caller_stack_pointer, caller_gas_left, caller_memory_word_size, caller_reversible_write_counter] =
             if call.is_root {
                 [U256::zero(); 9]
             } else {
-                [
-                    step.rw_indices[1],
-                    step.rw_indices[2],
-                    step.rw_indices[3],
-                    step.rw_indices[4],
-                    step.rw_indices[5],
-                    step.rw_indices[6],
-                    step.rw_indices[7],
-                    step.rw_indices[8],
-                    step.rw_indices[9],
-                ]
-                .map(|idx| block.rws[idx].call_context_value())
+                [0, 1, 2, 3, 4, 5, 6, 7, 8]
+                    .map(|idx| block.rws[step.rw_indices[rw_offset + idx]].call_context_value())
             };
 
         for (cell, value) in [
@@ -233,6 +236,109 @@ impl<F: Field> RestoreContextGadget<F> {
     }
 }
 
+/// Construction of execution state that halts in exception, which marks the
+/// call as failed, reverts all its reversible writes, consumes all gas_left
+/// and then either ends the transaction or restores caller's state.
+#[derive(Clone, Debug)]
+pub(crate) struct CommonErrorGadget<F> {
+    reversion_info: ReversionInfo<F>,
+    restore_context: RestoreContextGadget<F>,
+}
+
+impl<F: Field> CommonErrorGadget<F> {
+    pub(crate) fn construct(cb: &mut ConstraintBuilder<F>) -> Self {
+        // Call ends with exception must be failed and not persistent
+        cb.call_context_lookup(false.expr(), None, CallContextFieldTag::IsSuccess, 0.expr());
+        let reversion_info = cb.reversion_info(None);
+        cb.require_zero(
+            "Call ends with exception is not persistent",
+            reversion_info.is_persistent(),
+        );
+
+        let is_to_end_tx = cb.next.execution_state_selector([ExecutionState::EndTx]);
+        cb.require_equal(
+            "Go to EndTx only when is_root",
+            cb.curr.state.is_root.expr(),
+            is_to_end_tx,
+        );
+
+        // When it's a root call
+        cb.condition(cb.curr.state.is_root.expr(), |cb| {
+            // All reversible writes of this call are reverted right after this
+            // step.
+            cb.require_equal(
+                "rw_counter_end_of_reversion == rw_counter + rw_counter_offset + reversible_write_counter - 1",
+                reversion_info.rw_counter_end_of_reversion(),
+                cb.curr.state.rw_counter.expr()
+                    + cb.rw_counter_offset()
+                    + cb.curr.state.reversible_write_counter.expr()
+                    - 1.expr(),
+            );
+
+            // Do step state transition
+            cb.require_step_state_transition(StepStateTransition {
+                call_id: Same,
+                rw_counter: Delta(
+                    cb.rw_counter_offset() + cb.curr.state.reversible_write_counter.expr(),
+                ),
+                gas_left: To(0.expr()),
+                ..StepStateTransition::any()
+            });
+        });
+
+        // When it's an internal call
+        let restore_context = cb.condition(not::expr(cb.curr.state.is_root.expr()), |cb| {
+            let restore_context = RestoreContextGadget::construct(
+                cb,
+                cb.rw_counter_offset() + cb.curr.state.reversible_write_counter.expr(),
+                0.expr(),
+                0.expr(),
+                0.expr(),
+                0.expr(),
+            );
+
+            // All reversible writes of this call are reverted right after the
+            // restore of caller's context.
+            cb.require_equal(
+                "rw_counter_end_of_reversion == rw_counter + rw_counter_offset + reversible_write_counter - 1",
+                reversion_info.rw_counter_end_of_reversion(),
+                cb.curr.state.rw_counter.expr()
+                    + cb.rw_counter_offset()
+                    + cb.curr.state.reversible_write_counter.expr()
+                    - 1.expr(),
+            );
+
+            restore_context
+        });
+
+        Self {
+            reversion_info,
+            restore_context,
+        }
+    }
+
+    /// Assigns the witness, where `rw_offset` is the index of the
+    /// [`CallContextFieldTag::IsSuccess`] read in the step's rw indices.
+    pub(crate) fn assign(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        call: &Call,
+        step: &ExecStep,
+        rw_offset: usize,
+    ) -> Result<(), Error> {
+        self.reversion_info.assign(
+            region,
+            offset,
+            call.rw_counter_end_of_reversion,
+            call.is_persistent,
+        )?;
+        self.restore_context
+            .assign(region, offset, block, call, step, rw_offset + 3)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct UpdateBalanceGadget<F, const N_ADDENDS: usize, const INCREASE: bool> {
     add_words: AddWordsGadget<F, N_ADDENDS, true>,
@@ -397,6 +503,10 @@ impl<F: Field> TransferGadget<F> {
         Self { sender, receiver }
     }
 
+    pub(crate) fn sender(&self) -> &UpdateBalanceGadget<F, 2, false> {
+        &self.sender
+    }
+
     pub(crate) fn receiver(&self) -> &UpdateBalanceGadget<F, 2, true> {
         &self.receiver
     }
@@ -426,3 +536,215 @@ impl<F: Field> TransferGadget<F> {
         Ok(())
     }
 }
+
+/// Construction of the keccak input which the address of a contract created by
+/// `CREATE` or `CREATE2` is derived from:
+/// - `CREATE`: `rlp([caller_address, caller_nonce])`
+/// - `CREATE2`: `0xff ++ caller_address ++ salt ++ keccak256(init_code)`
+///
+/// The contract address is the lower 20 bytes of the keccak output, which is
+/// expected to be verified by the caller with a keccak table lookup of
+/// `input_rlc`, `input_length` and `keccak_output`.
+#[derive(Clone, Debug)]
+pub(crate) struct ContractCreateGadget<F, const IS_CREATE2: bool> {
+    caller_address: RandomLinearCombination<F, N_BYTES_ACCOUNT_ADDRESS>,
+    nonce: Word<F>,
+    nonce_byte_size: ByteSizeGadget<F>,
+    nonce_is_single_byte: LtGadget<F, N_BYTES_U64>,
+    salt: Word<F>,
+    code_hash: Word<F>,
+    keccak_output: Word<F>,
+    input_rlc: Expression<F>,
+    input_length: Expression<F>,
+    address_rlc: Expression<F>,
+}
+
+impl<F: Field, const IS_CREATE2: bool> ContractCreateGadget<F, IS_CREATE2> {
+    pub(crate) fn construct(
+        cb: &mut ConstraintBuilder<F>,
+        caller_address: Expression<F>,
+        nonce: Expression<F>,
+    ) -> Self {
+        let r = cb.power_of_randomness().to_vec();
+
+        let caller_address_bytes = cb.query_rlc();
+        cb.require_equal(
+            "caller address bytes",
+            from_bytes::expr(&caller_address_bytes.cells),
+            caller_address,
+        );
+
+        let nonce_bytes = cb.query_word();
+        cb.require_equal(
+            "nonce bytes",
+            from_bytes::expr(&nonce_bytes.cells[..N_BYTES_U64]),
+            nonce.clone(),
+        );
+        for byte in nonce_bytes.cells[N_BYTES_U64..].iter() {
+            cb.require_zero("nonce fits in u64", byte.expr());
+        }
+        let nonce_byte_size = ByteSizeGadget::construct(cb, &nonce_bytes);
+        let nonce_is_single_byte = LtGadget::construct(cb, nonce, 0x80.expr());
+
+        let salt = cb.query_word();
+        let code_hash = cb.query_word();
+        let keccak_output = cb.query_word();
+
+        let caller_address_be: Vec<Expression<F>> = caller_address_bytes
+            .cells
+            .iter()
+            .rev()
+            .map(|byte| byte.expr())
+            .collect();
+
+        let (input_rlc, input_length) = if IS_CREATE2 {
+            let rlc_be = |word: &Word<F>| {
+                rlc::expr(
+                    &word
+                        .cells
+                        .iter()
+                        .rev()
+                        .map(|byte| byte.expr())
+                        .collect::<Vec<_>>(),
+                    &r,
+                )
+            };
+            let prefix_and_caller_address: Vec<_> = std::iter::once(0xff.expr())
+                .chain(caller_address_be)
+                .collect();
+            (
+                rlc::expr(&prefix_and_caller_address, &r)
+                    + r[20].clone() * rlc_be(&salt)
+                    + r[30].clone() * r[21].clone() * rlc_be(&code_hash),
+                (1 + N_BYTES_ACCOUNT_ADDRESS + 2 * N_BYTES_WORD).expr(),
+            )
+        } else {
+            // RLP encoding of the nonce, which is 0x80 for 0, the nonce itself
+            // for a single byte below 0x80, and otherwise 0x80 + byte_size
+            // followed by its big-endian bytes.
+            let is_zero = nonce_byte_size.is_byte_size(0);
+            let is_single_byte = nonce_is_single_byte.expr();
+            let nonce_rlp_rlc = is_zero.clone() * 0x80.expr()
+                + (is_single_byte.clone() - is_zero) * nonce_bytes.cells[0].expr()
+                + (1.expr() - is_single_byte.clone())
+                    * sum::expr((1..=N_BYTES_U64).map(|byte_size| {
+                        nonce_byte_size.is_byte_size(byte_size)
+                            * ((0x80 + byte_size).expr()
+                                + sum::expr((0..byte_size).map(|idx| {
+                                    nonce_bytes.cells[byte_size - 1 - idx].expr() * r[idx].clone()
+                                })))
+                    }));
+            let nonce_rlp_length =
+                1.expr() + (1.expr() - is_single_byte) * nonce_byte_size.byte_size();
+
+            let prefix_and_caller_address: Vec<_> = [
+                (0xc0 + 1 + N_BYTES_ACCOUNT_ADDRESS).expr() + nonce_rlp_length.clone(),
+                (0x80 + N_BYTES_ACCOUNT_ADDRESS).expr(),
+            ]
+            .into_iter()
+            .chain(caller_address_be)
+            .collect();
+            (
+                rlc::expr(&prefix_and_caller_address, &r) + r[21].clone() * nonce_rlp_rlc,
+                (2 + N_BYTES_ACCOUNT_ADDRESS).expr() + nonce_rlp_length,
+            )
+        };
+
+        let address_rlc = rlc::expr(
+            &keccak_output.cells[..N_BYTES_ACCOUNT_ADDRESS]
+                .iter()
+                .map(|byte| byte.expr())
+                .collect::<Vec<_>>(),
+            &r,
+        );
+
+        Self {
+            caller_address: caller_address_bytes,
+            nonce: nonce_bytes,
+            nonce_byte_size,
+            nonce_is_single_byte,
+            salt,
+            code_hash,
+            keccak_output,
+            input_rlc,
+            input_length,
+            address_rlc,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn assign(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        caller_address: Address,
+        nonce: u64,
+        code_hash: U256,
+        salt: U256,
+    ) -> Result<(), Error> {
+        let mut caller_address_bytes = caller_address.to_fixed_bytes();
+        caller_address_bytes.reverse();
+        self.caller_address
+            .assign(region, offset, Some(caller_address_bytes))?;
+        self.nonce
+            .assign(region, offset, Some(U256::from(nonce).to_le_bytes()))?;
+        self.nonce_byte_size
+            .assign(region, offset, U256::from(nonce))?;
+        self.nonce_is_single_byte
+            .assign(region, offset, F::from(nonce), F::from(0x80))?;
+        self.salt.assign(region, offset, Some(salt.to_le_bytes()))?;
+        self.code_hash
+            .assign(region, offset, Some(code_hash.to_le_bytes()))?;
+
+        let input = if IS_CREATE2 {
+            std::iter::once(0xff)
+                .chain(caller_address.to_fixed_bytes())
+                .chain(salt.to_be_bytes())
+                .chain(code_hash.to_be_bytes())
+                .collect()
+        } else {
+            let mut stream = RlpStream::new_list(2);
+            stream.append(&caller_address.as_bytes());
+            stream.append(&nonce);
+            stream.out().to_vec()
+        };
+        let mut keccak = Keccak::default();
+        keccak.update(&input);
+        let keccak_output = U256::from_big_endian(&keccak.digest());
+        self.keccak_output
+            .assign(region, offset, Some(keccak_output.to_le_bytes()))?;
+
+        Ok(())
+    }
+
+    pub(crate) fn input_rlc(&self) -> Expression<F> {
+        self.input_rlc.clone()
+    }
+
+    pub(crate) fn input_length(&self) -> Expression<F> {
+        self.input_length.clone()
+    }
+
+    pub(crate) fn keccak_output(&self) -> Expression<F> {
+        self.keccak_output.expr()
+    }
+
+    /// Returns the address of the created contract.
+    pub(crate) fn address(&self) -> Expression<F> {
+        from_bytes::expr(&self.keccak_output.cells[..N_BYTES_ACCOUNT_ADDRESS])
+    }
+
+    /// Returns the random linear combination of the address of the created
+    /// contract, as it's pushed onto the stack.
+    pub(crate) fn address_rlc(&self) -> Expression<F> {
+        self.address_rlc.clone()
+    }
+
+    pub(crate) fn code_hash(&self) -> Expression<F> {
+        self.code_hash.expr()
+    }
+
+    pub(crate) fn salt(&self) -> Expression<F> {
+        self.salt.expr()
+    }
+}
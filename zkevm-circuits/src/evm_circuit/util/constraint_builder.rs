@@ -725,6 +725,53 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         );
     }
 
+    pub(crate) fn account_access_list_read(
+        &mut self,
+        tx_id: Expression<F>,
+        account_address: Expression<F>,
+        value: Expression<F>,
+    ) {
+        self.rw_lookup(
+            "TxAccessListAccount read",
+            false.expr(),
+            RwTableTag::TxAccessListAccount,
+            [
+                tx_id,
+                account_address,
+                0.expr(),
+                0.expr(),
+                value.clone(),
+                value,
+                0.expr(),
+                0.expr(),
+            ],
+        );
+    }
+
+    pub(crate) fn account_storage_access_list_read(
+        &mut self,
+        tx_id: Expression<F>,
+        account_address: Expression<F>,
+        storage_key: Expression<F>,
+        value: Expression<F>,
+    ) {
+        self.rw_lookup(
+            "TxAccessListAccountStorage read",
+            false.expr(),
+            RwTableTag::TxAccessListAccountStorage,
+            [
+                tx_id,
+                account_address,
+                0.expr(),
+                storage_key,
+                value.clone(),
+                value,
+                0.expr(),
+                0.expr(),
+            ],
+        );
+    }
+
     pub(crate) fn account_storage_access_list_write(
         &mut self,
         tx_id: Expression<F>,
@@ -845,6 +892,30 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         );
     }
 
+    pub(crate) fn account_destructed_write(
+        &mut self,
+        account_address: Expression<F>,
+        value: Expression<F>,
+        value_prev: Expression<F>,
+        reversion_info: Option<&mut ReversionInfo<F>>,
+    ) {
+        self.reversible_write(
+            "AccountDestructed write with reversion",
+            RwTableTag::AccountDestructed,
+            [
+                0.expr(),
+                account_address,
+                0.expr(),
+                0.expr(),
+                value,
+                value_prev,
+                0.expr(),
+                0.expr(),
+            ],
+            reversion_info,
+        );
+    }
+
     // Account Storage
 
     pub(crate) fn account_storage_read(
@@ -1080,6 +1151,7 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
         length: Expression<F>,
         rw_counter: Expression<F>,
         rwc_inc: Expression<F>,
+        rlc_acc: Expression<F>,
     ) {
         self.add_lookup(
             "copy lookup",
@@ -1095,6 +1167,46 @@ impl<'a, F: FieldExt> ConstraintBuilder<'a, F> {
                 length,
                 rw_counter,
                 rwc_inc,
+                rlc_acc,
+            },
+        );
+    }
+
+    // Exponentiation Table
+
+    pub(crate) fn exp_table_lookup(
+        &mut self,
+        identifier: Expression<F>,
+        base_limbs: [Expression<F>; 4],
+        exponent_lo_hi: [Expression<F>; 2],
+        exponentiation_lo_hi: [Expression<F>; 2],
+    ) {
+        self.add_lookup(
+            "exponentiation lookup",
+            Lookup::ExpTable {
+                is_first: 1.expr(), // is_first
+                identifier,
+                base_limbs,
+                exponent_lo_hi,
+                exponentiation_lo_hi,
+            },
+        );
+    }
+
+    // Keccak Table
+
+    pub(crate) fn keccak_table_lookup(
+        &mut self,
+        input_rlc: Expression<F>,
+        input_len: Expression<F>,
+        output_rlc: Expression<F>,
+    ) {
+        self.add_lookup(
+            "keccak lookup",
+            Lookup::KeccakTable {
+                input_rlc,
+                input_len,
+                output_rlc,
             },
         );
     }
@@ -1141,6 +1141,258 @@ impl<F: Field> ShrWordsGadget<F> {
     }
 }
 
+/// Construction of word shift left for `a << shift == b`.
+#[derive(Clone, Debug)]
+pub(crate) struct ShlWordsGadget<F> {
+    a: util::Word<F>,
+    shift: util::Word<F>,
+    b: util::Word<F>,
+    // four 64-bit limbs of word `a`
+    a64s: [Cell<F>; 4],
+    // four 64-bit limbs of word `b`
+    b64s: [Cell<F>; 4],
+    // Each of the four `a64s` limbs is split into two parts (`a64s_lo` and `a64s_hi`) at
+    // position `64 - shf_mod64`. `a64s_lo` is the lower `64 - shf_mod64` bits.
+    a64s_lo: [Cell<F>; 4],
+    // `a64s_hi` is the higher `shf_mod64` bits.
+    a64s_hi: [Cell<F>; 4],
+    // shift[0] / 64
+    shf_div64: Cell<F>,
+    // shift[0] % 64
+    shf_mod64: Cell<F>,
+    // 1 << shf_mod64
+    p_lo: Cell<F>,
+    // 1 << (64 - shf_mod64)
+    p_hi: Cell<F>,
+    // shift < 256
+    shf_lt256: IsZeroGadget<F>,
+    // shf_div64 == 0
+    shf_div64_eq0: IsZeroGadget<F>,
+    // shf_div64 == 1
+    shf_div64_eq1: IsEqualGadget<F>,
+    // shf_div64 == 2
+    shf_div64_eq2: IsEqualGadget<F>,
+    // a64s_lo[idx] < p_hi
+    a64s_lo_lt_p_hi: [LtGadget<F, 16>; 4],
+}
+
+impl<F: Field> ShlWordsGadget<F> {
+    pub(crate) fn construct(
+        cb: &mut ConstraintBuilder<F>,
+        a: util::Word<F>,
+        shift: util::Word<F>,
+    ) -> Self {
+        let b = cb.query_word();
+        let a64s = array_init(|_| cb.query_cell());
+        let b64s = array_init(|_| cb.query_cell());
+        let a64s_lo = array_init(|_| cb.query_cell());
+        let a64s_hi = array_init(|_| cb.query_cell());
+        let shf_div64 = cb.query_cell();
+        let shf_mod64 = cb.query_cell();
+        let p_lo = cb.query_cell();
+        let p_hi = cb.query_cell();
+        let shf_lt256 = IsZeroGadget::construct(cb, sum::expr(&shift.cells[1..32]));
+        for idx in 0..4 {
+            let offset = idx * N_BYTES_U64;
+
+            // a64s constraint
+            cb.require_equal(
+                "a64s[idx] == from_bytes(a[8 * idx..8 * (idx + 1)])",
+                a64s[idx].expr(),
+                from_bytes::expr(&a.cells[offset..offset + N_BYTES_U64]),
+            );
+
+            // b64s constraint
+            cb.require_equal(
+                "b64s[idx] * shf_lt256 == from_bytes(b[8 * idx..8 * (idx + 1)])",
+                b64s[idx].expr() * shf_lt256.expr(),
+                from_bytes::expr(&b.cells[offset..offset + N_BYTES_U64]),
+            );
+
+            cb.require_equal(
+                "a64s[idx] == a64s_lo[idx] + a64s_hi[idx] * p_hi",
+                a64s[idx].expr(),
+                a64s_lo[idx].expr() + a64s_hi[idx].expr() * p_hi.expr(),
+            );
+        }
+
+        // a64s_lo[idx] < p_hi
+        let a64s_lo_lt_p_hi = array_init(|idx| {
+            let lt = LtGadget::construct(cb, a64s_lo[idx].expr(), p_hi.expr());
+            cb.require_equal("a64s_lo[idx] < p_hi", lt.expr(), 1.expr());
+            lt
+        });
+
+        // merge contraints
+        let shf_div64_eq0 = IsZeroGadget::construct(cb, shf_div64.expr());
+        let shf_div64_eq1 = IsEqualGadget::construct(cb, shf_div64.expr(), 1.expr());
+        let shf_div64_eq2 = IsEqualGadget::construct(cb, shf_div64.expr(), 2.expr());
+        let shf_div64_eq3 =
+            1.expr() - shf_div64_eq0.expr() - shf_div64_eq1.expr() - shf_div64_eq2.expr();
+        cb.require_equal(
+            "Constrain b64s[0]",
+            b64s[0].expr(),
+            a64s_lo[0].expr() * p_lo.expr() * shf_div64_eq0.expr(),
+        );
+        cb.require_equal(
+            "Constrain b64s[1]",
+            b64s[1].expr(),
+            (a64s_lo[1].expr() * p_lo.expr() + a64s_hi[0].expr()) * shf_div64_eq0.expr()
+                + a64s_lo[0].expr() * p_lo.expr() * shf_div64_eq1.expr(),
+        );
+        cb.require_equal(
+            "Constrain b64s[2]",
+            b64s[2].expr(),
+            (a64s_lo[2].expr() * p_lo.expr() + a64s_hi[1].expr()) * shf_div64_eq0.expr()
+                + (a64s_lo[1].expr() * p_lo.expr() + a64s_hi[0].expr()) * shf_div64_eq1.expr()
+                + a64s_lo[0].expr() * p_lo.expr() * shf_div64_eq2.expr(),
+        );
+        cb.require_equal(
+            "Constrain b64s[3]",
+            b64s[3].expr(),
+            (a64s_lo[3].expr() * p_lo.expr() + a64s_hi[2].expr()) * shf_div64_eq0.expr()
+                + (a64s_lo[2].expr() * p_lo.expr() + a64s_hi[1].expr()) * shf_div64_eq1.expr()
+                + (a64s_lo[1].expr() * p_lo.expr() + a64s_hi[0].expr()) * shf_div64_eq2.expr()
+                + a64s_lo[0].expr() * p_lo.expr() * shf_div64_eq3,
+        );
+
+        // shift constraint
+        cb.require_equal(
+            "shift[0] == shf_mod64 + shf_div64 * 64",
+            shift.cells[0].expr(),
+            shf_mod64.expr() + shf_div64.expr() * 64.expr(),
+        );
+
+        // p_lo == pow(2, shf_mod64)
+        cb.add_lookup(
+            "Pow2 lookup",
+            Lookup::Fixed {
+                tag: FixedTableTag::Pow2.expr(),
+                values: [shf_mod64.expr(), p_lo.expr(), 0.expr()],
+            },
+        );
+
+        // p_hi == pow(2, 64 - shf_mod64)
+        cb.add_lookup(
+            "Pow2 lookup",
+            Lookup::Fixed {
+                tag: FixedTableTag::Pow2.expr(),
+                values: [64.expr() - shf_mod64.expr(), p_hi.expr(), 0.expr()],
+            },
+        );
+
+        Self {
+            a,
+            shift,
+            b,
+            a64s,
+            b64s,
+            a64s_lo,
+            a64s_hi,
+            shf_div64,
+            shf_mod64,
+            p_lo,
+            p_hi,
+            shf_lt256,
+            shf_div64_eq0,
+            shf_div64_eq1,
+            shf_div64_eq2,
+            a64s_lo_lt_p_hi,
+        }
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        a: Word,
+        shift: Word,
+        b: Word,
+    ) -> Result<(), Error> {
+        self.assign_witness(region, offset, &a, &shift)?;
+        self.a.assign(region, offset, Some(a.to_le_bytes()))?;
+        self.shift
+            .assign(region, offset, Some(shift.to_le_bytes()))?;
+        self.b.assign(region, offset, Some(b.to_le_bytes()))?;
+        Ok(())
+    }
+
+    pub(crate) fn b(&self) -> &util::Word<F> {
+        &self.b
+    }
+
+    fn assign_witness(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        a: &Word,
+        shift: &Word,
+    ) -> Result<(), Error> {
+        let shf0 = shift.to_le_bytes()[0] as usize;
+        let shf_div64 = shf0 / 64;
+        let shf_mod64 = shf0 % 64;
+        let p_lo: u128 = 1 << shf_mod64;
+        let p_hi: u128 = 1 << (64 - shf_mod64);
+        let shf_lt256 = shift
+            .to_le_bytes()
+            .iter()
+            .fold(0, |acc, val| acc + *val as u128)
+            - shf0 as u128;
+        let a64s = a.0;
+        let mut a64s_lo = [0_u128; 4];
+        let mut a64s_hi = [0_u128; 4];
+        for idx in 0..4 {
+            a64s_lo[idx] = u128::from(a64s[idx]) % p_hi;
+            a64s_hi[idx] = u128::from(a64s[idx]) / p_hi;
+        }
+        let mut b64s = [0_u128; 4];
+        b64s[shf_div64] = a64s_lo[0] * p_lo;
+        for k in shf_div64 + 1..4 {
+            b64s[k] = a64s_lo[k - shf_div64] * p_lo + a64s_hi[k - shf_div64 - 1];
+        }
+        self.a64s
+            .iter()
+            .zip(a64s.iter())
+            .map(|(cell, val)| cell.assign(region, offset, Some(F::from(*val))))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.b64s
+            .iter()
+            .zip(b64s.iter())
+            .map(|(cell, val)| cell.assign(region, offset, Some(F::from_u128(*val))))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.a64s_lo
+            .iter()
+            .zip(a64s_lo.iter())
+            .map(|(cell, val)| cell.assign(region, offset, Some(F::from_u128(*val))))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.a64s_hi
+            .iter()
+            .zip(a64s_hi.iter())
+            .map(|(cell, val)| cell.assign(region, offset, Some(F::from_u128(*val))))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.shf_div64
+            .assign(region, offset, Some(F::from(shf_div64 as u64)))?;
+        self.shf_mod64
+            .assign(region, offset, Some(F::from(shf_mod64 as u64)))?;
+        self.p_lo.assign(region, offset, Some(F::from_u128(p_lo)))?;
+        self.p_hi.assign(region, offset, Some(F::from_u128(p_hi)))?;
+        self.shf_lt256
+            .assign(region, offset, F::from_u128(shf_lt256))?;
+        self.shf_div64_eq0
+            .assign(region, offset, F::from(shf_div64 as u64))?;
+        self.shf_div64_eq1
+            .assign(region, offset, F::from(shf_div64 as u64), F::from(1))?;
+        self.shf_div64_eq2
+            .assign(region, offset, F::from(shf_div64 as u64), F::from(2))?;
+        self.a64s_lo_lt_p_hi
+            .iter()
+            .zip(a64s_lo.iter())
+            .map(|(lt, val)| lt.assign(region, offset, F::from_u128(*val), F::from_u128(p_hi)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug)]
 /// CmpWordsGadget compares two words, exposing `eq`  and `lt`
 pub(crate) struct CmpWordsGadget<F> {
@@ -1571,3 +1823,92 @@ impl<F: Field> AbsWordGadget<F> {
         &self.is_neg
     }
 }
+
+/// Returns the number of bytes needed to represent a 256-bit word, i.e. the
+/// index of its most significant non-zero byte plus one, or zero for a zero
+/// word.
+#[derive(Clone, Debug)]
+pub(crate) struct ByteSizeGadget<F> {
+    /// Array of indices from which only one will be turned on. The turned on
+    /// index is the byte size of the value.
+    most_significant_nonzero_byte_index: [Cell<F>; 33],
+    /// Inverse of the most significant non-zero byte, to verify that it is
+    /// indeed non-zero.
+    most_significant_nonzero_byte_inverse: Cell<F>,
+}
+
+impl<F: Field> ByteSizeGadget<F> {
+    pub(crate) fn construct(cb: &mut ConstraintBuilder<F>, value: &util::Word<F>) -> Self {
+        let most_significant_nonzero_byte_index = array_init(|_| cb.query_bool());
+        cb.require_equal(
+            "exactly one byte size index is turned on",
+            sum::expr(&most_significant_nonzero_byte_index),
+            1.expr(),
+        );
+
+        let most_significant_nonzero_byte_inverse = cb.query_cell();
+        for (index, is_byte_size) in most_significant_nonzero_byte_index.iter().enumerate() {
+            cb.condition(is_byte_size.expr(), |cb| {
+                for byte in value.cells[index..].iter() {
+                    cb.require_zero("more significant bytes are 0", byte.expr());
+                }
+                if index > 0 {
+                    cb.require_equal(
+                        "most significant byte is non-zero",
+                        value.cells[index - 1].expr()
+                            * most_significant_nonzero_byte_inverse.expr(),
+                        1.expr(),
+                    );
+                }
+            });
+        }
+
+        Self {
+            most_significant_nonzero_byte_index,
+            most_significant_nonzero_byte_inverse,
+        }
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        value: Word,
+    ) -> Result<(), Error> {
+        let byte_size = (value.bits() + 7) / 8;
+        for (index, is_byte_size) in self.most_significant_nonzero_byte_index.iter().enumerate() {
+            is_byte_size.assign(region, offset, Some(F::from((index == byte_size) as u64)))?;
+        }
+        if byte_size > 0 {
+            let most_significant_nonzero_byte = value.to_le_bytes()[byte_size - 1];
+            self.most_significant_nonzero_byte_inverse.assign(
+                region,
+                offset,
+                Some(
+                    F::from(most_significant_nonzero_byte as u64)
+                        .invert()
+                        .unwrap(),
+                ),
+            )?;
+        } else {
+            self.most_significant_nonzero_byte_inverse
+                .assign(region, offset, Some(F::zero()))?;
+        }
+        Ok(())
+    }
+
+    /// Returns `1` when the byte size of the value is `byte_size`, `0`
+    /// otherwise.
+    pub(crate) fn is_byte_size(&self, byte_size: usize) -> Expression<F> {
+        self.most_significant_nonzero_byte_index[byte_size].expr()
+    }
+
+    pub(crate) fn byte_size(&self) -> Expression<F> {
+        sum::expr(
+            self.most_significant_nonzero_byte_index
+                .iter()
+                .enumerate()
+                .map(|(index, is_byte_size)| is_byte_size.expr() * index.expr()),
+        )
+    }
+}
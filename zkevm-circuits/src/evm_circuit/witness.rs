@@ -10,19 +10,19 @@ use crate::evm_circuit::{
 };
 
 use bus_mapping::{
-    circuit_input_builder::{self, CopyEvent},
+    circuit_input_builder::{self, CopyEvent, ExpEvent},
     error::{ExecError, OogError},
     operation::{self, AccountField, CallContextField, TxLogField, TxReceiptField},
 };
 
 use eth_types::{evm_types::OpcodeId, ToWord};
-use eth_types::{Address, Field, ToLittleEndian, ToScalar, Word};
+use eth_types::{Address, Field, ToBigEndian, ToLittleEndian, ToScalar, Word};
 use eth_types::{ToAddress, U256};
-use halo2_proofs::arithmetic::{BaseExt, FieldExt};
+use halo2_proofs::arithmetic::FieldExt;
 use halo2_proofs::pairing::bn256::Fr;
-use itertools::Itertools;
 use sha3::{Digest, Keccak256};
 use std::{collections::HashMap, convert::TryInto, iter};
+use strum::IntoEnumIterator;
 
 #[derive(Debug, Default, Clone)]
 pub struct Block<F> {
@@ -39,6 +39,36 @@ pub struct Block<F> {
     /// Copy events for the EVM circuit's Copy Table, a mapping from (tx_id ||
     /// call_id || pc) to the corresponding copy event.
     pub copy_events: HashMap<(usize, usize, usize), CopyEvent>,
+    /// Exponentiation events for the EVM circuit's Exponentiation Table.
+    pub exp_events: Vec<ExpEvent>,
+    /// Inputs to the SHA3 opcode for the EVM circuit's Keccak Table.
+    pub sha3_inputs: Vec<Vec<u8>>,
+    /// RLP encoded nodes of the state and storage tries before the block
+    /// which are known, for the MPT circuit to walk the paths of the updated
+    /// accounts and storage slots.
+    pub trie_nodes: Vec<Vec<u8>>,
+    /// Number of rows the EVM circuit is padded to with EndBlock steps, so
+    /// that its fixed columns only depend on it.  No padding if 0.
+    pub evm_circuit_pad_to: usize,
+    /// Number of rows the Copy Circuit is padded to, which has to be even.  No
+    /// padding if 0.
+    pub copy_circuit_pad_to: usize,
+    /// Number of rows the Exponentiation Circuit is padded to.  No padding if
+    /// 0.
+    pub exp_circuit_pad_to: usize,
+}
+
+impl<F: Field> Block<F> {
+    /// Returns an empty block with the padding of the circuits of this block,
+    /// which assigns the same fixed columns.
+    pub fn without_witnesses(&self) -> Self {
+        Self {
+            evm_circuit_pad_to: self.evm_circuit_pad_to,
+            copy_circuit_pad_to: self.copy_circuit_pad_to,
+            exp_circuit_pad_to: self.exp_circuit_pad_to,
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -59,6 +89,16 @@ pub struct BlockContext {
     pub history_hashes: Vec<Word>,
     /// The chain id
     pub chain_id: Word,
+    /// The hash of the block
+    pub hash: Word,
+    /// The RLP encoding of the block header, whose keccak hash is `hash`
+    pub header_rlp: Vec<u8>,
+    /// The hash of the parent block
+    pub parent_hash: Word,
+    /// The state root after the block
+    pub state_root: Word,
+    /// The state root before the block
+    pub prev_state_root: Word,
 }
 
 impl BlockContext {
@@ -110,13 +150,17 @@ impl BlockContext {
                     ),
                 ],
             ],
+            // The latest hash is at the end of `history_hashes`, so the first
+            // one belongs to block `number - history_hashes.len()`.
             self.history_hashes
                 .iter()
                 .enumerate()
                 .map(|(idx, hash)| {
                     [
                         F::from(BlockContextFieldTag::BlockHash as u64),
-                        (self.number - idx - 1).to_scalar().unwrap(),
+                        (self.number - self.history_hashes.len() + idx)
+                            .to_scalar()
+                            .unwrap(),
                         RandomLinearCombination::random_linear_combine(
                             hash.to_le_bytes(),
                             randomness,
@@ -133,6 +177,8 @@ impl BlockContext {
 pub struct Transaction {
     /// The transaction identifier in the block
     pub id: usize,
+    /// The hash of the transaction
+    pub hash: Word,
     /// The sender account nonce of the transaction
     pub nonce: u64,
     /// The gas limit of the transaction
@@ -153,6 +199,12 @@ pub struct Transaction {
     pub call_data_length: usize,
     /// The gas cost for transaction call data
     pub call_data_gas_cost: u64,
+    /// The signature v value
+    pub v: u64,
+    /// The signature r value
+    pub r: Word,
+    /// The signature s value
+    pub s: Word,
     /// The calls made in the transaction
     pub calls: Vec<Call>,
     /// The steps executioned in the transaction
@@ -223,6 +275,15 @@ impl Transaction {
                     F::zero(),
                     F::from(self.call_data_gas_cost),
                 ],
+                [
+                    F::from(self.id as u64),
+                    F::from(TxContextFieldTag::TxHash as u64),
+                    F::zero(),
+                    RandomLinearCombination::random_linear_combine(
+                        self.hash.to_le_bytes(),
+                        randomness,
+                    ),
+                ],
             ],
             self.call_data
                 .iter()
@@ -543,6 +604,17 @@ impl Rw {
         }
     }
 
+    pub fn account_destructed_value_pair(&self) -> (bool, bool) {
+        match self {
+            Self::AccountDestructed {
+                is_destructed,
+                is_destructed_prev,
+                ..
+            } => (*is_destructed, *is_destructed_prev),
+            _ => unreachable!(),
+        }
+    }
+
     pub fn aux_pair(&self) -> (usize, Word) {
         match self {
             Self::AccountStorage {
@@ -602,6 +674,54 @@ impl Rw {
         }
     }
 
+    /// Returns the value, the previous value and the committed value of the
+    /// row, before their RLC encodings.
+    fn raw_values(&self) -> [Word; 3] {
+        match *self {
+            Self::Start { .. } => [Word::zero(); 3],
+            Self::TxAccessListAccount {
+                is_warm,
+                is_warm_prev,
+                ..
+            }
+            | Self::TxAccessListAccountStorage {
+                is_warm,
+                is_warm_prev,
+                ..
+            } => [
+                Word::from(is_warm as u64),
+                Word::from(is_warm_prev as u64),
+                Word::zero(),
+            ],
+            Self::TxRefund {
+                value, value_prev, ..
+            } => [Word::from(value), Word::from(value_prev), Word::zero()],
+            Self::Account {
+                value, value_prev, ..
+            } => [value, value_prev, Word::zero()],
+            Self::AccountStorage {
+                value,
+                value_prev,
+                committed_value,
+                ..
+            } => [value, value_prev, committed_value],
+            Self::AccountDestructed {
+                is_destructed,
+                is_destructed_prev,
+                ..
+            } => [
+                Word::from(is_destructed as u64),
+                Word::from(is_destructed_prev as u64),
+                Word::zero(),
+            ],
+            Self::CallContext { value, .. }
+            | Self::Stack { value, .. }
+            | Self::TxLog { value, .. } => [value, Word::zero(), Word::zero()],
+            Self::Memory { byte, .. } => [Word::from(byte), Word::zero(), Word::zero()],
+            Self::TxReceipt { value, .. } => [Word::from(value), Word::zero(), Word::zero()],
+        }
+    }
+
     pub fn table_assignment<F: Field>(&self, randomness: F) -> RwRow<F> {
         RwRow {
             rw_counter: F::from(self.rw_counter() as u64),
@@ -874,6 +994,11 @@ impl From<&circuit_input_builder::Block> for BlockContext {
             base_fee: block.base_fee,
             history_hashes: block.history_hashes.clone(),
             chain_id: block.chain_id,
+            hash: block.hash,
+            header_rlp: block.header_rlp.clone(),
+            parent_hash: block.parent_hash,
+            state_root: block.state_root,
+            prev_state_root: block.prev_state_root,
         }
     }
 }
@@ -1197,7 +1322,11 @@ impl From<&circuit_input_builder::ExecStep> for ExecutionState {
                     OpcodeId::MSIZE => ExecutionState::MSIZE,
                     OpcodeId::CALLER => ExecutionState::CALLER,
                     OpcodeId::CALLVALUE => ExecutionState::CALLVALUE,
+                    OpcodeId::BALANCE => ExecutionState::BALANCE,
+                    OpcodeId::EXTCODESIZE => ExecutionState::EXTCODESIZE,
+                    OpcodeId::EXTCODECOPY => ExecutionState::EXTCODECOPY,
                     OpcodeId::EXTCODEHASH => ExecutionState::EXTCODEHASH,
+                    OpcodeId::BLOCKHASH => ExecutionState::BLOCKHASH,
                     OpcodeId::TIMESTAMP | OpcodeId::NUMBER | OpcodeId::GASLIMIT => {
                         ExecutionState::BLOCKCTXU64
                     }
@@ -1205,37 +1334,34 @@ impl From<&circuit_input_builder::ExecStep> for ExecutionState {
                     OpcodeId::DIFFICULTY | OpcodeId::BASEFEE => ExecutionState::BLOCKCTXU256,
                     OpcodeId::GAS => ExecutionState::GAS,
                     OpcodeId::SELFBALANCE => ExecutionState::SELFBALANCE,
+                    OpcodeId::SHL => ExecutionState::SHL,
                     OpcodeId::SHR => ExecutionState::SHR,
+                    OpcodeId::SAR => ExecutionState::SAR,
                     OpcodeId::SLOAD => ExecutionState::SLOAD,
                     OpcodeId::SSTORE => ExecutionState::SSTORE,
                     OpcodeId::CALLDATASIZE => ExecutionState::CALLDATASIZE,
                     OpcodeId::CALLDATACOPY => ExecutionState::CALLDATACOPY,
                     OpcodeId::CHAINID => ExecutionState::CHAINID,
                     OpcodeId::ISZERO => ExecutionState::ISZERO,
-                    OpcodeId::CALL => ExecutionState::CALL,
+                    OpcodeId::CALL
+                    | OpcodeId::CALLCODE
+                    | OpcodeId::DELEGATECALL
+                    | OpcodeId::STATICCALL => ExecutionState::CALL_OP,
                     OpcodeId::ORIGIN => ExecutionState::ORIGIN,
                     OpcodeId::CODECOPY => ExecutionState::CODECOPY,
                     OpcodeId::CALLDATALOAD => ExecutionState::CALLDATALOAD,
                     OpcodeId::CODESIZE => ExecutionState::CODESIZE,
-                    OpcodeId::RETURN | OpcodeId::REVERT => ExecutionState::RETURN,
+                    OpcodeId::RETURN => ExecutionState::RETURN,
+                    OpcodeId::RETURNDATACOPY => ExecutionState::RETURNDATACOPY,
+                    OpcodeId::RETURNDATASIZE => ExecutionState::RETURNDATASIZE,
+                    OpcodeId::REVERT => ExecutionState::REVERT,
+                    OpcodeId::EXP => ExecutionState::EXP,
+                    OpcodeId::SHA3 => ExecutionState::SHA3,
+                    OpcodeId::CREATE => ExecutionState::CREATE,
+                    OpcodeId::CREATE2 => ExecutionState::CREATE2,
+                    OpcodeId::SELFDESTRUCT => ExecutionState::SELFDESTRUCT,
                     // dummy ops
-                    OpcodeId::SHA3 => dummy!(ExecutionState::SHA3),
                     OpcodeId::ADDRESS => dummy!(ExecutionState::ADDRESS),
-                    OpcodeId::BALANCE => dummy!(ExecutionState::BALANCE),
-                    OpcodeId::BLOCKHASH => dummy!(ExecutionState::BLOCKHASH),
-                    OpcodeId::EXP => dummy!(ExecutionState::EXP),
-                    OpcodeId::SHL => dummy!(ExecutionState::SHL),
-                    OpcodeId::SAR => dummy!(ExecutionState::SAR),
-                    OpcodeId::EXTCODESIZE => dummy!(ExecutionState::EXTCODESIZE),
-                    OpcodeId::EXTCODECOPY => dummy!(ExecutionState::EXTCODECOPY),
-                    OpcodeId::RETURNDATASIZE => dummy!(ExecutionState::RETURNDATASIZE),
-                    OpcodeId::RETURNDATACOPY => dummy!(ExecutionState::RETURNDATACOPY),
-                    OpcodeId::CREATE => dummy!(ExecutionState::CREATE),
-                    OpcodeId::CALLCODE => dummy!(ExecutionState::CALLCODE),
-                    OpcodeId::DELEGATECALL => dummy!(ExecutionState::DELEGATECALL),
-                    OpcodeId::CREATE2 => dummy!(ExecutionState::CREATE2),
-                    OpcodeId::STATICCALL => dummy!(ExecutionState::STATICCALL),
-                    OpcodeId::SELFDESTRUCT => dummy!(ExecutionState::SELFDESTRUCT),
                     _ => unimplemented!("unimplemented opcode {:?}", op),
                 }
             }
@@ -1295,6 +1421,7 @@ fn step_convert(step: &circuit_input_builder::ExecStep) -> ExecStep {
 fn tx_convert(tx: &circuit_input_builder::Transaction, id: usize, is_last_tx: bool) -> Transaction {
     Transaction {
         id,
+        hash: tx.hash,
         nonce: tx.nonce,
         gas: tx.gas,
         gas_price: tx.gas_price,
@@ -1308,6 +1435,9 @@ fn tx_convert(tx: &circuit_input_builder::Transaction, id: usize, is_last_tx: bo
             .input
             .iter()
             .fold(0, |acc, byte| acc + if *byte == 0 { 4 } else { 16 }),
+        v: tx.v,
+        r: tx.r,
+        s: tx.s,
         calls: tx
             .calls()
             .iter()
@@ -1353,13 +1483,70 @@ fn tx_convert(tx: &circuit_input_builder::Transaction, id: usize, is_last_tx: bo
     }
 }
 
+/// Returns the commitment of the prover to the witnesses of `block` that the
+/// RLC encodings compress: the rows of the rw table, the bytecodes, the inputs
+/// of the SHA3 opcode and the trie nodes.  The other witnesses are looked up
+/// from these, or are bound by the public data of the block.
+pub fn witness_commitment<F>(block: &Block<F>) -> Word {
+    let mut hasher = Keccak256::new();
+    for tag in RwTableTag::iter() {
+        let rws = block.rws.0.get(&tag).map(Vec::as_slice).unwrap_or_default();
+        hasher.update((rws.len() as u64).to_be_bytes());
+        for rw in rws {
+            hasher.update((rw.rw_counter() as u64).to_be_bytes());
+            hasher.update([rw.is_write() as u8, rw.tag() as u8]);
+            hasher.update((rw.id().unwrap_or_default() as u64).to_be_bytes());
+            hasher.update(rw.address().unwrap_or_default());
+            hasher.update(rw.field_tag().unwrap_or_default().to_be_bytes());
+            hasher.update(rw.storage_key().unwrap_or_default().to_be_bytes());
+            for value in rw.raw_values() {
+                hasher.update(value.to_be_bytes());
+            }
+        }
+    }
+    // The bytecodes are sorted by their hashes, for an order that doesn't
+    // depend on the map.
+    let mut bytecodes: Vec<_> = block.bytecodes.iter().collect();
+    bytecodes.sort_by_key(|(hash, _)| *hash);
+    for inputs in [
+        bytecodes
+            .into_iter()
+            .map(|(_, bytecode)| bytecode.bytes.as_slice())
+            .collect::<Vec<_>>(),
+        block.sha3_inputs.iter().map(Vec::as_slice).collect(),
+        block.trie_nodes.iter().map(Vec::as_slice).collect(),
+    ] {
+        hasher.update((inputs.len() as u64).to_be_bytes());
+        for input in inputs {
+            hasher.update((input.len() as u64).to_be_bytes());
+            hasher.update(input);
+        }
+    }
+    Word::from_big_endian(&hasher.finalize())
+}
+
+/// Returns the randomness of the RLC encodings for the block with `hash` and
+/// the `witness_commitment` of the prover.  As the prover commits to its
+/// witnesses before the randomness is known, it can't choose them for the
+/// randomness, and a verifier derives it from the commitment in the proofs.
+// TODO: Check the commitment against the advice commitments of the proofs
+// once halo2 supports challenges.
+pub fn block_randomness(hash: Word, witness_commitment: Word) -> Fr {
+    let mut hasher = Keccak256::new();
+    hasher.update(hash.to_be_bytes());
+    hasher.update(witness_commitment.to_be_bytes());
+    let mut bytes = [0; 64];
+    bytes[..32].copy_from_slice(&hasher.finalize());
+    Fr::from_bytes_wide(&bytes)
+}
+
 pub fn block_convert(
     block: &circuit_input_builder::Block,
     code_db: &bus_mapping::state_db::CodeDB,
 ) -> Block<Fr> {
-    Block {
-        randomness: Fr::rand(),
-        context: block.into(),
+    let context = BlockContext::from(block);
+    let mut block = Block {
+        context,
         rws: RwMap::from(&block.container),
         txs: block
             .txs()
@@ -1367,19 +1554,15 @@ pub fn block_convert(
             .enumerate()
             .map(|(idx, tx)| tx_convert(tx, idx + 1, idx + 1 == block.txs().len()))
             .collect(),
-        bytecodes: block
-            .txs()
-            .iter()
-            .flat_map(|tx| {
-                tx.calls()
-                    .iter()
-                    .map(|call| call.code_hash)
-                    .unique()
-                    .into_iter()
-                    .map(|code_hash| {
-                        let bytecode = Bytecode::new(code_db.0.get(&code_hash).unwrap().to_vec());
-                        (bytecode.hash, bytecode)
-                    })
+        // All the codes in the code db are included, since besides the codes
+        // of the calls, external codes are also accessed by `EXTCODESIZE` and
+        // `EXTCODECOPY`.
+        bytecodes: code_db
+            .0
+            .values()
+            .map(|code| {
+                let bytecode = Bytecode::new(code.to_vec());
+                (bytecode.hash, bytecode)
             })
             .collect(),
         copy_events: block
@@ -1392,5 +1575,11 @@ pub fn block_convert(
                 )
             })
             .collect(),
-    }
+        exp_events: block.exp_events.clone(),
+        sha3_inputs: block.sha3_inputs.clone(),
+        trie_nodes: block.trie_nodes.clone(),
+        ..Default::default()
+    };
+    block.randomness = block_randomness(block.context.hash, witness_commitment(&block));
+    block
 }
@@ -0,0 +1,716 @@
+//! The Exponentiation circuit implements the exponentiation by squaring trace
+//! of the EXP opcode, where every row verifies one intermediate multiplication
+//! `a * b == d (mod 2^256)`.
+
+use bus_mapping::circuit_input_builder::{ExpEvent, ExpStep};
+use eth_types::{Field, ToLittleEndian, ToScalar, U256};
+use gadgets::util::{and, not, Expr};
+use halo2_proofs::{
+    circuit::{Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+
+use crate::evm_circuit::{
+    param::N_BYTES_WORD,
+    table::LookupTable,
+    util::{constraint_builder::BaseConstraintBuilder, from_bytes, pow_of_two_expr, split_u256},
+    witness::Block,
+};
+
+/// Number of bytes used to range check the carries of the multiplication.
+const N_BYTES_CARRY: usize = 9;
+
+/// Number of bytes of each 128-bit half of the exponent.
+const N_BYTES_HALF_WORD: usize = 16;
+
+/// The exponentiation table, verifying each multiplication step performed
+/// while computing `base ^ exponent (mod 2^256)` by squaring.
+///
+/// The steps of an exponentiation are laid out from the multiplication that
+/// produces the final result (first row) down to the squaring of the base
+/// (last row). With `e` being the exponent at the current row:
+/// - if `e` is odd, `a = d_next`, `b = base` and `e_next = e - 1`
+/// - if `e` is even, `a = b = d_next` and `e_next = e / 2`
+/// - in the last row, `a = b = base` and `e == 2`
+///
+/// The exponent is range checked to 256 bits at every row and `is_odd` is its
+/// least significant bit, so that halving and decrementing follow the integer
+/// exponent and not just its value modulo the field.
+#[derive(Clone, Copy, Debug)]
+pub struct ExpCircuit<F> {
+    /// Whether the row is enabled or not.
+    pub q_enable: Column<Fixed>,
+    /// Whether the row is the first step of an exponentiation event.
+    pub is_first: Column<Advice>,
+    /// Whether the row is the last step of an exponentiation event.
+    pub is_last: Column<Advice>,
+    /// Identifier of the exponentiation event, which is the RW counter of the
+    /// EXP step in the EVM circuit.
+    pub identifier: Column<Advice>,
+    /// Whether the exponent at the current row is odd.
+    pub is_odd: Column<Advice>,
+    /// Four 64-bit limbs of the exponentiation base.
+    pub base_limbs: [Column<Advice>; 4],
+    /// Lower 128 bits of the exponent at the current row.
+    pub exponent_lo: Column<Advice>,
+    /// Higher 128 bits of the exponent at the current row.
+    pub exponent_hi: Column<Advice>,
+    /// Least significant bit of `exponent_hi`, carried to the lower 128 bits
+    /// when the exponent is halved.
+    pub exponent_carry: Column<Advice>,
+    /// Little-endian bytes of `exponent_lo`.
+    pub exponent_lo_bytes: [Column<Advice>; N_BYTES_HALF_WORD],
+    /// Little-endian bytes of `exponent_hi`.
+    pub exponent_hi_bytes: [Column<Advice>; N_BYTES_HALF_WORD],
+    /// The least significant byte of the exponent divided by two, so that
+    /// `exponent_lo_bytes[0] == 2 * exponent_byte0_half + is_odd`.
+    pub exponent_byte0_half: Column<Advice>,
+    /// Four 64-bit limbs of the first multiplicand.
+    pub a_limbs: [Column<Advice>; 4],
+    /// Four 64-bit limbs of the second multiplicand.
+    pub b_limbs: [Column<Advice>; 4],
+    /// Little-endian bytes of the multiplication result.
+    pub d_bytes: [Column<Advice>; N_BYTES_WORD],
+    /// Little-endian bytes of the carry of the lower 128 bits.
+    pub carry_lo: [Column<Advice>; N_BYTES_CARRY],
+    /// Little-endian bytes of the carry of the higher 128 bits.
+    pub carry_hi: [Column<Advice>; N_BYTES_CARRY],
+    /// Fixed table with all the byte values, used for range checks.
+    pub u8_table: Column<Fixed>,
+    _marker: std::marker::PhantomData<F>,
+}
+
+fn limbs_expr<F: Field>(bytes: &[Expression<F>]) -> [Expression<F>; 4] {
+    [0, 1, 2, 3].map(|idx| from_bytes::expr(&bytes[idx * 8..(idx + 1) * 8]))
+}
+
+impl<F: Field> LookupTable<F> for ExpCircuit<F> {
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        let d_bytes = self
+            .d_bytes
+            .map(|column| meta.query_advice(column, Rotation::cur()));
+        vec![
+            // is_first is only meaningful on enabled rows
+            meta.query_fixed(self.q_enable, Rotation::cur())
+                * meta.query_advice(self.is_first, Rotation::cur()),
+            meta.query_advice(self.identifier, Rotation::cur()),
+            meta.query_advice(self.base_limbs[0], Rotation::cur()),
+            meta.query_advice(self.base_limbs[1], Rotation::cur()),
+            meta.query_advice(self.base_limbs[2], Rotation::cur()),
+            meta.query_advice(self.base_limbs[3], Rotation::cur()),
+            meta.query_advice(self.exponent_lo, Rotation::cur()),
+            meta.query_advice(self.exponent_hi, Rotation::cur()),
+            from_bytes::expr(&d_bytes[..16]), // exponentiation_lo
+            from_bytes::expr(&d_bytes[16..]), // exponentiation_hi
+        ]
+    }
+}
+
+impl<F: Field> ExpCircuit<F> {
+    /// Configure the Exponentiation Circuit.
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let q_enable = meta.fixed_column();
+        let is_first = meta.advice_column();
+        let is_last = meta.advice_column();
+        let identifier = meta.advice_column();
+        let is_odd = meta.advice_column();
+        let base_limbs = [(); 4].map(|_| meta.advice_column());
+        let exponent_lo = meta.advice_column();
+        let exponent_hi = meta.advice_column();
+        let exponent_carry = meta.advice_column();
+        let exponent_lo_bytes = [(); N_BYTES_HALF_WORD].map(|_| meta.advice_column());
+        let exponent_hi_bytes = [(); N_BYTES_HALF_WORD].map(|_| meta.advice_column());
+        let exponent_byte0_half = meta.advice_column();
+        let a_limbs = [(); 4].map(|_| meta.advice_column());
+        let b_limbs = [(); 4].map(|_| meta.advice_column());
+        let d_bytes = [(); N_BYTES_WORD].map(|_| meta.advice_column());
+        let carry_lo = [(); N_BYTES_CARRY].map(|_| meta.advice_column());
+        let carry_hi = [(); N_BYTES_CARRY].map(|_| meta.advice_column());
+        let u8_table = meta.fixed_column();
+
+        meta.create_gate("verify multiplication", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let a = a_limbs.map(|column| meta.query_advice(column, Rotation::cur()));
+            let b = b_limbs.map(|column| meta.query_advice(column, Rotation::cur()));
+            let d = d_bytes.map(|column| meta.query_advice(column, Rotation::cur()));
+            let carry_lo = carry_lo.map(|column| meta.query_advice(column, Rotation::cur()));
+            let carry_hi = carry_hi.map(|column| meta.query_advice(column, Rotation::cur()));
+
+            let t0 = a[0].clone() * b[0].clone();
+            let t1 = a[0].clone() * b[1].clone() + a[1].clone() * b[0].clone();
+            let t2 = a[0].clone() * b[2].clone()
+                + a[1].clone() * b[1].clone()
+                + a[2].clone() * b[0].clone();
+            let t3 = a[0].clone() * b[3].clone()
+                + a[1].clone() * b[2].clone()
+                + a[2].clone() * b[1].clone()
+                + a[3].clone() * b[0].clone();
+            let carry_lo = from_bytes::expr(&carry_lo);
+            let carry_hi = from_bytes::expr(&carry_hi);
+
+            cb.require_equal(
+                "(a * b)_lo == d_lo + carry_lo * 2^128",
+                t0 + t1 * pow_of_two_expr(64),
+                from_bytes::expr(&d[..16]) + carry_lo.clone() * pow_of_two_expr(128),
+            );
+            cb.require_equal(
+                "(a * b)_hi + carry_lo == d_hi + carry_hi * 2^128",
+                t2 + t3 * pow_of_two_expr(64) + carry_lo,
+                from_bytes::expr(&d[16..]) + carry_hi * pow_of_two_expr(128),
+            );
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.create_gate("verify exponentiation step", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let is_first_next = meta.query_advice(is_first, Rotation::next());
+            let is_last = meta.query_advice(is_last, Rotation::cur());
+            let is_odd_cur = meta.query_advice(is_odd, Rotation::cur());
+            let exponent_carry = meta.query_advice(exponent_carry, Rotation::cur());
+            let base = base_limbs.map(|column| meta.query_advice(column, Rotation::cur()));
+            let a = a_limbs.map(|column| meta.query_advice(column, Rotation::cur()));
+            let b = b_limbs.map(|column| meta.query_advice(column, Rotation::cur()));
+            let d_next =
+                limbs_expr(&d_bytes.map(|column| meta.query_advice(column, Rotation::next())));
+            let exponent_lo_cur = meta.query_advice(exponent_lo, Rotation::cur());
+            let exponent_hi_cur = meta.query_advice(exponent_hi, Rotation::cur());
+            let exponent_lo_next = meta.query_advice(exponent_lo, Rotation::next());
+            let exponent_hi_next = meta.query_advice(exponent_hi, Rotation::next());
+
+            cb.require_boolean(
+                "is_first is boolean",
+                meta.query_advice(is_first, Rotation::cur()),
+            );
+            cb.require_boolean("is_last is boolean", is_last.clone());
+            cb.require_boolean("is_odd is boolean", is_odd_cur.clone());
+            cb.require_boolean("exponent_carry is boolean", exponent_carry.clone());
+
+            let exponent_lo_bytes =
+                exponent_lo_bytes.map(|column| meta.query_advice(column, Rotation::cur()));
+            let exponent_hi_bytes =
+                exponent_hi_bytes.map(|column| meta.query_advice(column, Rotation::cur()));
+            cb.require_equal(
+                "exponent_lo == from_bytes(exponent_lo_bytes)",
+                exponent_lo_cur.clone(),
+                from_bytes::expr(&exponent_lo_bytes),
+            );
+            cb.require_equal(
+                "exponent_hi == from_bytes(exponent_hi_bytes)",
+                exponent_hi_cur.clone(),
+                from_bytes::expr(&exponent_hi_bytes),
+            );
+            // Both sides are less than 2^9, so the equality holds over the
+            // integers and is_odd is the parity of the exponent.
+            cb.require_equal(
+                "exponent_lo_bytes[0] == 2 * exponent_byte0_half + is_odd",
+                exponent_lo_bytes[0].clone(),
+                2.expr() * meta.query_advice(exponent_byte0_half, Rotation::cur())
+                    + is_odd_cur.clone(),
+            );
+
+            cb.condition(not::expr(is_last.clone()), |cb| {
+                cb.require_equal(
+                    "next row is enabled for non-last step",
+                    meta.query_fixed(q_enable, Rotation::next()),
+                    1.expr(),
+                );
+                cb.require_zero(
+                    "is_first_next == 0 for non-last step",
+                    is_first_next.clone(),
+                );
+                cb.require_equal(
+                    "identifier_next == identifier",
+                    meta.query_advice(identifier, Rotation::next()),
+                    meta.query_advice(identifier, Rotation::cur()),
+                );
+                for (idx, base_limb) in base.iter().enumerate() {
+                    cb.require_equal(
+                        "base_next == base",
+                        meta.query_advice(base_limbs[idx], Rotation::next()),
+                        base_limb.clone(),
+                    );
+                }
+                for (a_limb, d_next_limb) in a.iter().zip(d_next.iter()) {
+                    cb.require_equal(
+                        "a == d_next for non-last step",
+                        a_limb.clone(),
+                        d_next_limb.clone(),
+                    );
+                }
+            });
+
+            cb.condition(
+                and::expr([not::expr(is_last.clone()), is_odd_cur.clone()]),
+                |cb| {
+                    for (b_limb, base_limb) in b.iter().zip(base.iter()) {
+                        cb.require_equal(
+                            "b == base for odd exponent",
+                            b_limb.clone(),
+                            base_limb.clone(),
+                        );
+                    }
+                    cb.require_equal(
+                        "exponent_lo_next == exponent_lo - 1 for odd exponent",
+                        exponent_lo_next.clone(),
+                        exponent_lo_cur.clone() - 1.expr(),
+                    );
+                    cb.require_equal(
+                        "exponent_hi_next == exponent_hi for odd exponent",
+                        exponent_hi_next.clone(),
+                        exponent_hi_cur.clone(),
+                    );
+                    cb.require_zero(
+                        "exponent_next is even for odd exponent",
+                        meta.query_advice(is_odd, Rotation::next()),
+                    );
+                },
+            );
+
+            cb.condition(
+                and::expr([not::expr(is_last.clone()), not::expr(is_odd_cur.clone())]),
+                |cb| {
+                    for (b_limb, d_next_limb) in b.iter().zip(d_next.iter()) {
+                        cb.require_equal(
+                            "b == d_next for even exponent",
+                            b_limb.clone(),
+                            d_next_limb.clone(),
+                        );
+                    }
+                    cb.require_equal(
+                        "exponent_lo == 2 * exponent_lo_next - carry * 2^128 for even exponent",
+                        exponent_lo_cur.clone(),
+                        2.expr() * exponent_lo_next.clone()
+                            - exponent_carry.clone() * pow_of_two_expr(128),
+                    );
+                    cb.require_equal(
+                        "exponent_hi == 2 * exponent_hi_next + carry for even exponent",
+                        exponent_hi_cur.clone(),
+                        2.expr() * exponent_hi_next.clone() + exponent_carry.clone(),
+                    );
+                },
+            );
+
+            cb.condition(is_last.clone(), |cb| {
+                for ((a_limb, b_limb), base_limb) in a.iter().zip(b.iter()).zip(base.iter()) {
+                    cb.require_equal("a == base for last step", a_limb.clone(), base_limb.clone());
+                    cb.require_equal("b == base for last step", b_limb.clone(), base_limb.clone());
+                }
+                cb.require_equal(
+                    "exponent_lo == 2 for last step",
+                    exponent_lo_cur.clone(),
+                    2.expr(),
+                );
+                cb.require_zero("exponent_hi == 0 for last step", exponent_hi_cur.clone());
+                cb.require_zero("is_odd == 0 for last step", is_odd_cur.clone());
+                cb.require_zero(
+                    "next enabled row after last step is the first step of an event",
+                    meta.query_fixed(q_enable, Rotation::next()) * not::expr(is_first_next),
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        for column in d_bytes
+            .iter()
+            .chain(carry_lo.iter())
+            .chain(carry_hi.iter())
+            .chain(exponent_lo_bytes.iter())
+            .chain(exponent_hi_bytes.iter())
+            .chain(std::iter::once(&exponent_byte0_half))
+        {
+            meta.lookup_any("Range check byte", |meta| {
+                let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+                vec![(
+                    q_enable * meta.query_advice(*column, Rotation::cur()),
+                    meta.query_fixed(u8_table, Rotation::cur()),
+                )]
+            });
+        }
+
+        Self {
+            q_enable,
+            is_first,
+            is_last,
+            identifier,
+            is_odd,
+            base_limbs,
+            exponent_lo,
+            exponent_hi,
+            exponent_carry,
+            exponent_lo_bytes,
+            exponent_hi_bytes,
+            exponent_byte0_half,
+            a_limbs,
+            b_limbs,
+            d_bytes,
+            carry_lo,
+            carry_hi,
+            u8_table,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Assign a witness block to the Exponentiation Circuit.
+    pub fn assign_block(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        block: &Block<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "u8 table",
+            |mut region| {
+                for value in 0..256 {
+                    region.assign_fixed(
+                        || format!("u8 table row {}", value),
+                        self.u8_table,
+                        value,
+                        || Ok(F::from(value as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+
+        let num_rows: usize = block
+            .exp_events
+            .iter()
+            .map(|exp_event| exp_event.steps.len())
+            .sum();
+        let pad_to = block.exp_circuit_pad_to;
+        if pad_to > 0 && num_rows > pad_to {
+            log::error!(
+                "exp circuit needs {} rows but only has {}",
+                num_rows,
+                pad_to
+            );
+            return Err(Error::Synthesis);
+        }
+        // The exponentiation events are followed by padding events of 1 row up
+        // to `pad_to` rows, so that the fixed columns only depend on it.
+        let padding_event = Self::padding_event();
+        let num_padding_events = pad_to.saturating_sub(num_rows);
+
+        layouter.assign_region(
+            || "assign exp table",
+            |mut region| {
+                let mut offset = 0;
+                for exp_event in block
+                    .exp_events
+                    .iter()
+                    .chain(std::iter::repeat(&padding_event).take(num_padding_events))
+                {
+                    let mut exponent = exp_event.exponent;
+                    for (step_idx, exp_step) in exp_event.steps.iter().enumerate() {
+                        self.assign_step(
+                            &mut region,
+                            offset,
+                            exp_event,
+                            step_idx,
+                            exp_step,
+                            exponent,
+                        )?;
+                        exponent = if exponent.bit(0) {
+                            exponent - 1
+                        } else {
+                            exponent >> 1
+                        };
+                        offset += 1;
+                    }
+                }
+                // pad a row in the end to satisfy Halo2 cell assignment check
+                self.assign_padding_row(&mut region, offset)
+            },
+        )
+    }
+
+    /// Returns the event of the padding rows, which squares 0 in a single
+    /// step.  Its identifier is 0, which no EXP step has.
+    fn padding_event() -> ExpEvent {
+        ExpEvent {
+            identifier: 0,
+            base: U256::zero(),
+            exponent: U256::from(2),
+            exponentiation: U256::zero(),
+            steps: vec![ExpStep::from((U256::zero(), U256::zero(), U256::zero()))],
+        }
+    }
+
+    fn assign_step(
+        &self,
+        region: &mut Region<F>,
+        offset: usize,
+        exp_event: &ExpEvent,
+        step_idx: usize,
+        exp_step: &ExpStep,
+        exponent: U256,
+    ) -> Result<(), Error> {
+        let (exponent_lo, exponent_hi) = split_u256(&exponent);
+        let (ab_lo, ab_hi) = {
+            let a = exp_step.a.0;
+            let b = exp_step.b.0;
+            // (a * b)_lo and (a * b)_hi computed in 64-bit limbs, which could
+            // exceed 128 bits.
+            let t0 = U256::from(a[0]) * U256::from(b[0]);
+            let t1 = U256::from(a[0]) * U256::from(b[1]) + U256::from(a[1]) * U256::from(b[0]);
+            let t2 = U256::from(a[0]) * U256::from(b[2])
+                + U256::from(a[1]) * U256::from(b[1])
+                + U256::from(a[2]) * U256::from(b[0]);
+            let t3 = U256::from(a[0]) * U256::from(b[3])
+                + U256::from(a[1]) * U256::from(b[2])
+                + U256::from(a[2]) * U256::from(b[1])
+                + U256::from(a[3]) * U256::from(b[0]);
+            (t0 + (t1 << 64), t2 + (t3 << 64))
+        };
+        let (d_lo, _) = split_u256(&exp_step.d);
+        let carry_lo = (ab_lo - d_lo) >> 128;
+        let carry_hi = (ab_hi + carry_lo - (exp_step.d >> 128)) >> 128;
+
+        region.assign_fixed(|| "q_enable", self.q_enable, offset, || Ok(F::one()))?;
+        for (name, column, value) in [
+            ("is_first", self.is_first, F::from((step_idx == 0) as u64)),
+            (
+                "is_last",
+                self.is_last,
+                F::from((step_idx == exp_event.steps.len() - 1) as u64),
+            ),
+            (
+                "identifier",
+                self.identifier,
+                F::from(exp_event.identifier as u64),
+            ),
+            ("is_odd", self.is_odd, F::from(exponent.bit(0) as u64)),
+            (
+                "exponent_lo",
+                self.exponent_lo,
+                exponent_lo.to_scalar().unwrap(),
+            ),
+            (
+                "exponent_hi",
+                self.exponent_hi,
+                exponent_hi.to_scalar().unwrap(),
+            ),
+            (
+                "exponent_carry",
+                self.exponent_carry,
+                F::from(exponent.bit(128) as u64),
+            ),
+            (
+                "exponent_byte0_half",
+                self.exponent_byte0_half,
+                F::from((exponent.low_u64() & 0xff) >> 1),
+            ),
+        ] {
+            region.assign_advice(
+                || format!("assign {} {}", name, offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        for (name, columns, limbs) in [
+            ("base", self.base_limbs, exp_event.base.0),
+            ("a", self.a_limbs, exp_step.a.0),
+            ("b", self.b_limbs, exp_step.b.0),
+        ] {
+            for (column, limb) in columns.iter().zip(limbs) {
+                region.assign_advice(
+                    || format!("assign {} {}", name, offset),
+                    *column,
+                    offset,
+                    || Ok(F::from(limb)),
+                )?;
+            }
+        }
+        let exponent_bytes = exponent.to_le_bytes();
+        for (name, columns, bytes) in [
+            ("d", &self.d_bytes[..], exp_step.d.to_le_bytes().to_vec()),
+            (
+                "exponent_lo_bytes",
+                &self.exponent_lo_bytes[..],
+                exponent_bytes[..N_BYTES_HALF_WORD].to_vec(),
+            ),
+            (
+                "exponent_hi_bytes",
+                &self.exponent_hi_bytes[..],
+                exponent_bytes[N_BYTES_HALF_WORD..].to_vec(),
+            ),
+            (
+                "carry_lo",
+                &self.carry_lo[..],
+                carry_lo.to_le_bytes().to_vec(),
+            ),
+            (
+                "carry_hi",
+                &self.carry_hi[..],
+                carry_hi.to_le_bytes().to_vec(),
+            ),
+        ] {
+            for (column, byte) in columns.iter().zip(bytes) {
+                region.assign_advice(
+                    || format!("assign {} {}", name, offset),
+                    *column,
+                    offset,
+                    || Ok(F::from(byte as u64)),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn assign_padding_row(&self, region: &mut Region<F>, offset: usize) -> Result<(), Error> {
+        region.assign_fixed(|| "q_enable", self.q_enable, offset, || Ok(F::zero()))?;
+        for column in [
+            self.is_first,
+            self.is_last,
+            self.identifier,
+            self.is_odd,
+            self.exponent_lo,
+            self.exponent_hi,
+            self.exponent_carry,
+            self.exponent_byte0_half,
+        ]
+        .iter()
+        .chain(self.exponent_lo_bytes.iter())
+        .chain(self.exponent_hi_bytes.iter())
+        .chain(self.base_limbs.iter())
+        .chain(self.a_limbs.iter())
+        .chain(self.b_limbs.iter())
+        .chain(self.d_bytes.iter())
+        .chain(self.carry_lo.iter())
+        .chain(self.carry_hi.iter())
+        {
+            region.assign_advice(
+                || format!("assign padding row {}", offset),
+                *column,
+                offset,
+                || Ok(F::zero()),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bus_mapping::{
+        circuit_input_builder::{self, CircuitInputBuilder},
+        mock::BlockData,
+    };
+    use eth_types::{bytecode, geth_types::GethData, Field, Word};
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        dev::{MockProver, VerifyFailure},
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use mock::TestContext;
+
+    use crate::evm_circuit::witness::{block_convert, Block};
+
+    use super::ExpCircuit;
+
+    #[derive(Default)]
+    struct MyCircuit<F> {
+        block: Block<F>,
+    }
+
+    impl<F: Field> Circuit<F> for MyCircuit<F> {
+        type Config = ExpCircuit<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            ExpCircuit::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.assign_block(&mut layouter, &self.block)
+        }
+    }
+
+    fn run_circuit<F: Field>(k: u32, block: Block<F>) -> Result<(), Vec<VerifyFailure>> {
+        let circuit = MyCircuit::<F> { block };
+        let prover = MockProver::<F>::run(k, &circuit, vec![]).unwrap();
+        prover.verify()
+    }
+
+    fn gen_data(base: Word, exponent: Word) -> CircuitInputBuilder {
+        let code = bytecode! {
+            PUSH32(exponent)
+            PUSH32(base)
+            EXP
+            STOP
+        };
+        let test_ctx = TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap();
+        let block: GethData = test_ctx.into();
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+        builder
+    }
+
+    fn test_ok(base: Word, exponent: Word) {
+        let builder = gen_data(base, exponent);
+        let block = block_convert(&builder.block, &builder.code_db);
+        assert_eq!(run_circuit(10, block), Ok(()));
+    }
+
+    fn test_ko(base: Word, exponent: Word, perturb: impl Fn(&mut circuit_input_builder::Block)) {
+        let mut builder = gen_data(base, exponent);
+        perturb(&mut builder.block);
+        let block = block_convert(&builder.block, &builder.code_db);
+        assert!(run_circuit(10, block).is_err());
+    }
+
+    #[test]
+    fn exp_circuit_valid() {
+        test_ok(2.into(), 2.into());
+        test_ok(3.into(), 7.into());
+        test_ok(5.into(), 11.into());
+        test_ok(7.into(), 255.into());
+        test_ok(Word::MAX, Word::MAX);
+    }
+
+    #[test]
+    fn exp_circuit_valid_padded() {
+        let builder = gen_data(3.into(), 7.into());
+        let mut block = block_convert(&builder.block, &builder.code_db);
+        block.exp_circuit_pad_to = 512;
+        assert_eq!(run_circuit(10, block), Ok(()));
+    }
+
+    #[test]
+    fn exp_circuit_invalid_result() {
+        test_ko(3.into(), 7.into(), |block| {
+            block.exp_events[0].steps[0].d = block.exp_events[0].steps[0].d + 1;
+        });
+    }
+
+    #[test]
+    fn exp_circuit_invalid_exponent() {
+        test_ko(3.into(), 7.into(), |block| {
+            block.exp_events[0].exponent = 6.into();
+        });
+    }
+
+    #[test]
+    fn exp_circuit_invalid_step() {
+        test_ko(5.into(), 11.into(), |block| {
+            let steps = &mut block.exp_events[0].steps;
+            steps.swap(1, 2);
+        });
+    }
+}
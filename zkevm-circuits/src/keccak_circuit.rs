@@ -0,0 +1,1022 @@
+//! The keccak table, shared by all the circuits that look up keccak hashes:
+//! the bytecode circuit (code hash), the tx circuit (public key hash in the
+//! signature verification) and the EVM circuit (SHA3 opcode).
+//!
+//! Each enabled row of the table contains `(is_enabled, input_rlc, input_len,
+//! output_rlc)` for one variable-length input, where:
+//! - `input_rlc` is the random linear combination of the input bytes, with the
+//!   first byte multiplied by the lowest power of randomness.
+//! - `output_rlc` is the random linear combination of the little-endian bytes
+//!   of the hash as a word, which is the same encoding used for any other word
+//!   in the EVM circuit.
+//!
+//! The table is assigned and constrained by the [`KeccakConfig`] circuit,
+//! which computes the keccak-f permutation bit by bit: each 64-bit lane of the
+//! state is laid out over 64 boolean columns, with one row per lane and one
+//! round of the permutation every 25 rows.  The variable-length inputs are
+//! padded and absorbed 136 bytes at a time, and the output of the permutation
+//! of the last block of an input is exposed in the table, together with the
+//! RLC and length of the input.
+
+#![allow(missing_docs)]
+use crate::{
+    evm_circuit::{
+        table::LookupTable,
+        util::{constraint_builder::BaseConstraintBuilder, not, rlc, sum, RandomLinearCombination},
+    },
+    util::Expr,
+};
+use eth_types::{Field, ToLittleEndian, Word};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use keccak256::{
+    common::{NEXT_INPUTS_LANES, PERMUTATION, ROTATION_CONSTANTS, ROUND_CONSTANTS},
+    plain::{Keccak, KeccakF},
+};
+use log::error;
+use std::{convert::TryInto, iter, marker::PhantomData};
+
+/// Number of rows of a round of the permutation, one per lane of the state.
+const ROUND_ROWS: usize = 25;
+/// Number of rows of the permutation of a block.
+pub const BLOCK_ROWS: usize = PERMUTATION * ROUND_ROWS;
+/// Number of input bytes absorbed per block.
+const RATE: usize = NEXT_INPUTS_LANES * 8;
+/// Row of a block with the first lanes of the output of its permutation.
+const OUTPUT_ROW: usize = BLOCK_ROWS - ROUND_ROWS;
+/// The only bits that are set in the round constants.
+const ROUND_CONSTANT_BITS: [usize; 7] = [0, 1, 3, 7, 15, 31, 63];
+
+/// The keccak table shared between the circuits that look up keccak hashes
+#[derive(Clone, Copy, Debug)]
+pub struct KeccakTable {
+    pub is_enabled: Column<Advice>,
+    pub input_rlc: Column<Advice>,
+    pub input_len: Column<Advice>,
+    pub output_rlc: Column<Advice>,
+}
+
+impl<F: FieldExt> LookupTable<F> for KeccakTable {
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        vec![
+            meta.query_advice(self.is_enabled, Rotation::cur()),
+            meta.query_advice(self.input_rlc, Rotation::cur()),
+            meta.query_advice(self.input_len, Rotation::cur()),
+            meta.query_advice(self.output_rlc, Rotation::cur()),
+        ]
+    }
+}
+
+impl KeccakTable {
+    pub fn construct<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            is_enabled: meta.advice_column(),
+            input_rlc: meta.advice_column(),
+            input_len: meta.advice_column(),
+            output_rlc: meta.advice_column(),
+        }
+    }
+
+    /// Returns the table row of the keccak hash of `input`.
+    pub fn assignment<F: FieldExt>(input: &[u8], randomness: F) -> [F; 4] {
+        let mut keccak = Keccak::default();
+        keccak.update(input);
+        let output = Word::from_big_endian(&keccak.digest());
+        [
+            F::one(),
+            rlc::value(input, randomness),
+            F::from(input.len() as u64),
+            RandomLinearCombination::random_linear_combine(output.to_le_bytes(), randomness),
+        ]
+    }
+
+    fn assign_row<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: [F; 4],
+    ) -> Result<(), Error> {
+        for (column, value) in [
+            self.is_enabled,
+            self.input_rlc,
+            self.input_len,
+            self.output_rlc,
+        ]
+        .iter()
+        .zip(row)
+        {
+            region.assign_advice(
+                || format!("keccak table row {}", offset),
+                *column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Assign the hashes of `inputs` into the table, after an all-zero row that
+    /// allows disabled lookups.  The rows are not constrained, so this is
+    /// only meant for the unit tests of the circuits that look up the table:
+    /// any other circuit must assign the table with [`KeccakConfig`].
+    #[cfg(any(feature = "test", test))]
+    pub fn dev_load<'a, F: FieldExt>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        inputs: impl IntoIterator<Item = &'a Vec<u8>> + Clone,
+        randomness: F,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "keccak table",
+            |mut region| {
+                self.assign_row(&mut region, 0, [F::zero(); 4])?;
+                for (offset, input) in inputs.clone().into_iter().enumerate() {
+                    self.assign_row(&mut region, offset + 1, Self::assignment(input, randomness))?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Returns `a XOR b` for the boolean expressions `a` and `b`.
+fn xor<F: FieldExt>(a: Expression<F>, b: Expression<F>) -> Expression<F> {
+    a.clone() + b.clone() - 2.expr() * a * b
+}
+
+/// Returns the byte given by the little-endian `bits`.
+fn from_bits<F: FieldExt>(bits: &[Expression<F>]) -> Expression<F> {
+    bits.iter()
+        .rev()
+        .fold(0.expr(), |acc, bit| acc * 2.expr() + bit.clone())
+}
+
+/// Witness of a row of the keccak circuit
+#[derive(Clone, Debug, Default)]
+struct KeccakRow<F> {
+    // Lane of the state before the round
+    state: u64,
+    // Parity of a column of the state, on the first 5 rows of a round
+    theta_c: u64,
+    // Lane of the state after theta
+    theta: u64,
+    // Lane of the state after the round
+    chi: u64,
+    // Lane of the padded input, on the first 17 rows of a block
+    input: u64,
+    is_padding: [bool; 8],
+    is_first: bool,
+    is_final: bool,
+    data_rlc_prev: F,
+    data_rlc: F,
+    data_len_prev: u64,
+    data_len: u64,
+    power_prev: F,
+    power: F,
+    table: [F; 4],
+}
+
+/// Appends the rows of the permutations of the padded `input` to `rows`.
+fn keccak_input_rows<F: Field>(rows: &mut Vec<KeccakRow<F>>, input: &[u8], randomness: F) {
+    let num_blocks = input.len() / RATE + 1;
+    let mut padded = input.to_vec();
+    padded.resize(num_blocks * RATE, 0);
+    padded[input.len()] = 1;
+    padded[num_blocks * RATE - 1] |= 0x80;
+
+    let mut state = [[0u64; 5]; 5];
+    let mut data_rlc = F::zero();
+    let mut data_len = 0;
+    let mut power = F::one();
+    for (idx, block) in padded.chunks(RATE).enumerate() {
+        let mut block_rows = vec![KeccakRow::<F>::default(); BLOCK_ROWS];
+        for row in block_rows.iter_mut().take(ROUND_ROWS) {
+            row.is_first = idx == 0;
+            row.is_final = idx == num_blocks - 1;
+        }
+
+        // Absorb the block
+        for (lane, bytes) in block.chunks(8).enumerate() {
+            let row = &mut block_rows[lane];
+            row.data_rlc_prev = data_rlc;
+            row.data_len_prev = data_len;
+            row.power_prev = power;
+            for (k, byte) in bytes.iter().enumerate() {
+                row.is_padding[k] = idx * RATE + lane * 8 + k >= input.len();
+                if !row.is_padding[k] {
+                    data_rlc += F::from(*byte as u64) * power;
+                    data_len += 1;
+                    power *= randomness;
+                }
+            }
+            row.data_rlc = data_rlc;
+            row.data_len = data_len;
+            row.power = power;
+            row.input = u64::from_le_bytes(bytes.try_into().unwrap());
+            state[lane % 5][lane / 5] ^= row.input;
+        }
+
+        // Permute the state
+        for (round, round_constant) in ROUND_CONSTANTS.iter().enumerate() {
+            let theta = KeccakF::theta(state);
+            let chi = KeccakF::iota(
+                KeccakF::xi(KeccakF::pi(KeccakF::rho(theta))),
+                *round_constant,
+            );
+            for (lane, row) in block_rows[round * ROUND_ROWS..(round + 1) * ROUND_ROWS]
+                .iter_mut()
+                .enumerate()
+            {
+                let (x, y) = (lane % 5, lane / 5);
+                row.state = state[x][y];
+                if lane < 5 {
+                    row.theta_c = state[x].iter().fold(0, |acc, lane| acc ^ lane);
+                }
+                row.theta = theta[x][y];
+                row.chi = chi[x][y];
+            }
+            state = chi;
+        }
+
+        if idx == num_blocks - 1 {
+            block_rows[OUTPUT_ROW].table = KeccakTable::assignment(input, randomness);
+        }
+        rows.extend(block_rows);
+    }
+}
+
+/// Returns the rows of the permutations of `inputs`, followed by the
+/// permutations of the empty input that fill a domain of `size` rows.
+fn keccak_rows<'a, F: Field>(
+    size: usize,
+    inputs: impl IntoIterator<Item = &'a Vec<u8>>,
+    randomness: F,
+) -> Result<Vec<KeccakRow<F>>, Error> {
+    // The powers of randomness are only given in the first `size - 64` rows of
+    // the instance columns.
+    let capacity = (size - 64) / BLOCK_ROWS;
+
+    let mut rows = Vec::new();
+    for input in inputs {
+        keccak_input_rows(&mut rows, input, randomness);
+    }
+    if rows.len() > capacity * BLOCK_ROWS {
+        error!(
+            "keccak circuit needs {} blocks but only has {}",
+            rows.len() / BLOCK_ROWS,
+            capacity
+        );
+        return Err(Error::Synthesis);
+    }
+    while rows.len() < capacity * BLOCK_ROWS {
+        keccak_input_rows(&mut rows, &[], randomness);
+    }
+    Ok(rows)
+}
+
+/// Config of the keccak circuit, which assigns the rows of the keccak table.
+#[derive(Clone, Debug)]
+pub struct KeccakConfig<F> {
+    minimum_rows: usize,
+    q_enable: Column<Fixed>,
+    q_first: Column<Fixed>,
+    q_block_start: Column<Fixed>,
+    q_absorb: Column<Fixed>,
+    q_round: Column<Fixed>,
+    q_next: Column<Fixed>,
+    q_input: Column<Fixed>,
+    q_input_last: Column<Fixed>,
+    q_output: Column<Fixed>,
+    round_constant: [Column<Fixed>; 7],
+    state: [Column<Advice>; 64],
+    theta_c: [Column<Advice>; 64],
+    theta: [Column<Advice>; 64],
+    chi: [Column<Advice>; 64],
+    input: [Column<Advice>; 64],
+    is_padding: [Column<Advice>; 8],
+    is_first: Column<Advice>,
+    is_final: Column<Advice>,
+    data_rlc_prev: Column<Advice>,
+    data_rlc: Column<Advice>,
+    data_len_prev: Column<Advice>,
+    data_len: Column<Advice>,
+    power_prev: Column<Advice>,
+    power: Column<Advice>,
+    keccak_table: KeccakTable,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> KeccakConfig<F> {
+    /// Configure the keccak circuit so that it assigns the rows of the shared
+    /// `keccak_table`.  As it records the minimum number of rows of the
+    /// circuit to place its last row, it must be configured after the other
+    /// circuits, except for the bytecode circuit.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        power_of_randomness: [Expression<F>; 31],
+        keccak_table: KeccakTable,
+    ) -> Self {
+        let q_enable = meta.fixed_column();
+        let q_first = meta.fixed_column();
+        let q_block_start = meta.fixed_column();
+        let q_absorb = meta.fixed_column();
+        let q_round = meta.fixed_column();
+        let q_next = meta.fixed_column();
+        let q_input = meta.fixed_column();
+        let q_input_last = meta.fixed_column();
+        let q_output = meta.fixed_column();
+        let round_constant = array_init::array_init(|_| meta.fixed_column());
+        let state = array_init::array_init(|_| meta.advice_column());
+        let theta_c = array_init::array_init(|_| meta.advice_column());
+        let theta = array_init::array_init(|_| meta.advice_column());
+        let chi = array_init::array_init(|_| meta.advice_column());
+        let input = array_init::array_init(|_| meta.advice_column());
+        let is_padding = array_init::array_init(|_| meta.advice_column());
+        let is_first = meta.advice_column();
+        let is_final = meta.advice_column();
+        let data_rlc_prev = meta.advice_column();
+        let data_rlc = meta.advice_column();
+        let data_len_prev = meta.advice_column();
+        let data_len = meta.advice_column();
+        let power_prev = meta.advice_column();
+        let power = meta.advice_column();
+
+        // Powers of randomness, starting from r^0
+        let r: Vec<Expression<F>> = iter::once(1.expr())
+            .chain(power_of_randomness.iter().cloned())
+            .collect();
+        let last_input_row = (NEXT_INPUTS_LANES - 1) as i32;
+
+        // A round of the permutation, on the 25 rows of its lanes, where the
+        // lane (x, y) of the state is on row x + 5y.
+        meta.create_gate("keccak-f round", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let lanes = |meta: &mut VirtualCells<F>, columns: &[Column<Advice>; 64]| {
+                (0..25)
+                    .map(|lane| {
+                        columns
+                            .iter()
+                            .map(|column| meta.query_advice(*column, Rotation(lane)))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let state = lanes(meta, &state);
+            let theta = lanes(meta, &theta);
+            let chi = lanes(meta, &chi);
+            let c = (0..5)
+                .map(|x| {
+                    theta_c
+                        .iter()
+                        .map(|column| meta.query_advice(*column, Rotation(x)))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+
+            // theta: c[x] is the parity of the column x of the state, which
+            // is xored to the columns x + 1 and x - 1 (rotated) of each lane.
+            for (x, c_x) in c.iter().enumerate() {
+                for (z, c_xz) in c_x.iter().enumerate() {
+                    cb.require_boolean("theta c is boolean", c_xz.clone());
+                    let sum = sum::expr((0..5).map(|y| state[x + 5 * y][z].clone()));
+                    let diff = sum - c_xz.clone();
+                    cb.require_zero(
+                        "theta c is the parity of the column",
+                        diff.clone() * (diff.clone() - 2.expr()) * (diff - 4.expr()),
+                    );
+                }
+            }
+            for (lane, (theta, state)) in theta.iter().zip(state.iter()).enumerate() {
+                let x = lane % 5;
+                for (z, (theta, state)) in theta.iter().zip(state.iter()).enumerate() {
+                    cb.require_equal(
+                        "theta",
+                        theta.clone(),
+                        xor(
+                            xor(state.clone(), c[(x + 4) % 5][z].clone()),
+                            c[(x + 1) % 5][(z + 63) % 64].clone(),
+                        ),
+                    );
+                }
+            }
+
+            // rho and pi move the bit z of the lane (x, y) to the bit z + ROT[x][y]
+            // of the lane (y, 2x + 3y), so the lane (x, y) of their output
+            // comes from the lane (x + 3y, x).
+            let b = |x: usize, y: usize, z: usize| {
+                let (x_in, y_in) = ((x + 3 * y) % 5, x);
+                let rotation = ROTATION_CONSTANTS[x_in][y_in] as usize;
+                theta[x_in + 5 * y_in][(z + 64 - rotation) % 64].clone()
+            };
+            // chi and iota
+            for y in 0..5 {
+                for x in 0..5 {
+                    for z in 0..64 {
+                        let b0 = b(x, y, z);
+                        let b1 = not::expr(b((x + 1) % 5, y, z));
+                        let b2 = b((x + 2) % 5, y, z);
+                        let mut value = xor(b0, b1 * b2);
+                        if x == 0 && y == 0 {
+                            if let Some(idx) = ROUND_CONSTANT_BITS.iter().position(|bit| *bit == z)
+                            {
+                                value = xor(
+                                    value,
+                                    meta.query_fixed(round_constant[idx], Rotation::cur()),
+                                );
+                            }
+                        }
+                        cb.require_equal("chi", chi[x + 5 * y][z].clone(), value);
+                    }
+                }
+            }
+
+            cb.gate(meta.query_fixed(q_round, Rotation::cur()))
+        });
+
+        // Every round after the first starts from the output of the previous
+        // one.
+        meta.create_gate("keccak-f next round", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+            for z in 0..64 {
+                cb.require_equal(
+                    "state is the output of the previous round",
+                    meta.query_advice(state[z], Rotation::cur()),
+                    meta.query_advice(chi[z], Rotation(-(ROUND_ROWS as i32))),
+                );
+            }
+            cb.gate(meta.query_fixed(q_next, Rotation::cur()))
+        });
+
+        // The first round of a block starts from the input lanes xored to the
+        // output of the previous block, or to zero for the first block of an
+        // input.
+        meta.create_gate("keccak absorb", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+            let is_first_expr = meta.query_advice(is_first, Rotation::cur());
+            let q_input = meta.query_fixed(q_input, Rotation::cur());
+            for z in 0..64 {
+                cb.require_equal(
+                    "state is the input absorbed into the previous state",
+                    meta.query_advice(state[z], Rotation::cur()),
+                    xor(
+                        not::expr(is_first_expr.clone())
+                            * meta.query_advice(chi[z], Rotation(-(ROUND_ROWS as i32))),
+                        q_input.clone() * meta.query_advice(input[z], Rotation::cur()),
+                    ),
+                );
+            }
+            cb.condition(
+                not::expr(meta.query_fixed(q_block_start, Rotation::cur())),
+                |cb| {
+                    cb.require_equal(
+                        "is_first is the same for the whole round",
+                        is_first_expr,
+                        meta.query_advice(is_first, Rotation::prev()),
+                    );
+                    cb.require_equal(
+                        "is_final is the same for the whole round",
+                        meta.query_advice(is_final, Rotation::cur()),
+                        meta.query_advice(is_final, Rotation::prev()),
+                    );
+                },
+            );
+            cb.gate(meta.query_fixed(q_absorb, Rotation::cur()))
+        });
+
+        // A block is the first of an input if it's the first block of the
+        // circuit or follows the final block of the previous input, else it
+        // continues the RLC of the previous block.
+        meta.create_gate("keccak block start", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+            let q_first = meta.query_fixed(q_first, Rotation::cur());
+            let is_first = meta.query_advice(is_first, Rotation::cur());
+            let prev_block = -(BLOCK_ROWS as i32);
+            cb.require_equal(
+                "is_first = q_first or the previous block is final",
+                is_first.clone(),
+                q_first.clone()
+                    + not::expr(q_first) * meta.query_advice(is_final, Rotation(prev_block)),
+            );
+            cb.require_boolean(
+                "is_final is boolean",
+                meta.query_advice(is_final, Rotation::cur()),
+            );
+            cb.require_equal(
+                "data_rlc_prev is the RLC of the previous block",
+                meta.query_advice(data_rlc_prev, Rotation::cur()),
+                not::expr(is_first.clone())
+                    * meta.query_advice(data_rlc, Rotation(prev_block + last_input_row)),
+            );
+            cb.require_equal(
+                "data_len_prev is the length of the previous block",
+                meta.query_advice(data_len_prev, Rotation::cur()),
+                not::expr(is_first.clone())
+                    * meta.query_advice(data_len, Rotation(prev_block + last_input_row)),
+            );
+            cb.require_equal(
+                "power_prev is the power of the previous block",
+                meta.query_advice(power_prev, Rotation::cur()),
+                is_first.clone()
+                    + not::expr(is_first)
+                        * meta.query_advice(power, Rotation(prev_block + last_input_row)),
+            );
+            cb.gate(meta.query_fixed(q_block_start, Rotation::cur()))
+        });
+
+        // The input lanes: the padding of the final block and the RLC and
+        // length of the input bytes.
+        meta.create_gate("keccak input", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+            let is_final = meta.query_advice(is_final, Rotation::cur());
+            let q_input_last = meta.query_fixed(q_input_last, Rotation::cur());
+            let bits: Vec<_> = input
+                .iter()
+                .map(|column| meta.query_advice(*column, Rotation::cur()))
+                .collect();
+            let bytes: Vec<_> = bits.chunks(8).map(from_bits).collect();
+            let pads: Vec<_> = is_padding
+                .iter()
+                .map(|column| meta.query_advice(*column, Rotation::cur()))
+                .collect();
+            // The byte before the first byte of the lane is padding
+            let pad_prev = not::expr(meta.query_fixed(q_block_start, Rotation::cur()))
+                * meta.query_advice(is_padding[7], Rotation::prev());
+
+            for bit in bits.iter() {
+                cb.require_boolean("input bit is boolean", bit.clone());
+            }
+            for (k, (pad, byte)) in pads.iter().zip(bytes.iter()).enumerate() {
+                let pad = pad.clone();
+                cb.require_boolean("is_padding is boolean", pad.clone());
+                cb.require_zero(
+                    "only the final block is padded",
+                    not::expr(is_final.clone()) * pad.clone(),
+                );
+                if k < 7 {
+                    cb.require_zero(
+                        "padding continues until the end of the lane",
+                        pad.clone() * not::expr(pads[k + 1].clone()),
+                    );
+                }
+                // The first padding byte has the bit 0 set, the last byte of
+                // the block has the bit 7 set, and all the others are zero.
+                let prev = if k == 0 {
+                    pad_prev.clone()
+                } else {
+                    pads[k - 1].clone()
+                };
+                let last = if k == 7 {
+                    q_input_last.clone() * 128.expr()
+                } else {
+                    0.expr()
+                };
+                cb.require_zero(
+                    "padding bytes",
+                    pad.clone() * (byte.clone() - (pad - prev) - last),
+                );
+            }
+
+            let power_prev_expr = meta.query_advice(power_prev, Rotation::cur());
+            cb.require_equal(
+                "data_rlc accumulates the input bytes",
+                meta.query_advice(data_rlc, Rotation::cur()),
+                meta.query_advice(data_rlc_prev, Rotation::cur())
+                    + sum::expr((0..8).map(|k| {
+                        not::expr(pads[k].clone())
+                            * bytes[k].clone()
+                            * power_prev_expr.clone()
+                            * r[k].clone()
+                    })),
+            );
+            cb.require_equal(
+                "data_len accumulates the input length",
+                meta.query_advice(data_len, Rotation::cur()),
+                meta.query_advice(data_len_prev, Rotation::cur())
+                    + sum::expr(pads.iter().map(|pad| not::expr(pad.clone()))),
+            );
+            // Exactly one of the terms is enabled: the number of input bytes
+            // in the lane.
+            let num_bytes_terms = iter::once(pads[0].clone())
+                .chain((1..8).map(|k| pads[k].clone() * not::expr(pads[k - 1].clone())))
+                .chain(iter::once(not::expr(pads[7].clone())));
+            cb.require_equal(
+                "power is multiplied by r per input byte",
+                meta.query_advice(power, Rotation::cur()),
+                power_prev_expr
+                    * sum::expr(
+                        num_bytes_terms
+                            .zip(r.iter())
+                            .map(|(term, r)| term * r.clone()),
+                    ),
+            );
+
+            cb.condition(q_input_last.clone(), |cb| {
+                cb.require_equal(
+                    "the last byte of the block is padding in the final block",
+                    pads[7].clone(),
+                    is_final.clone(),
+                );
+            });
+            cb.condition(not::expr(q_input_last), |cb| {
+                cb.require_zero(
+                    "padding continues on the next lane",
+                    pads[7].clone() * not::expr(meta.query_advice(is_padding[0], Rotation::next())),
+                );
+                for (prev, cur) in [
+                    (data_rlc_prev, data_rlc),
+                    (data_len_prev, data_len),
+                    (power_prev, power),
+                ] {
+                    cb.require_equal(
+                        "accumulators continue on the next lane",
+                        meta.query_advice(prev, Rotation::next()),
+                        meta.query_advice(cur, Rotation::cur()),
+                    );
+                }
+            });
+
+            cb.gate(meta.query_fixed(q_input, Rotation::cur()))
+        });
+
+        // The table row of the final block of an input, with its output on the
+        // first 4 lanes of the state after the last round.
+        meta.create_gate("keccak output", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+            let output_row = -(OUTPUT_ROW as i32);
+            let is_final = meta.query_advice(is_final, Rotation(output_row));
+            let output_bytes: Vec<_> = (0..32)
+                .map(|idx| {
+                    let bits: Vec<_> = (0..8)
+                        .map(|bit| {
+                            meta.query_advice(chi[8 * (idx % 8) + bit], Rotation((idx / 8) as i32))
+                        })
+                        .collect();
+                    from_bits(&bits)
+                })
+                .collect();
+            let table = keccak_table.table_exprs(meta);
+            cb.require_equal("is_enabled = is_final", table[0].clone(), is_final.clone());
+            cb.require_equal(
+                "input_rlc = data_rlc",
+                table[1].clone(),
+                is_final.clone()
+                    * meta.query_advice(data_rlc, Rotation(output_row + last_input_row)),
+            );
+            cb.require_equal(
+                "input_len = data_len",
+                table[2].clone(),
+                is_final.clone()
+                    * meta.query_advice(data_len, Rotation(output_row + last_input_row)),
+            );
+            // The output is the RLC of the little-endian bytes of the hash as
+            // a word, so the first byte of the hash gets the highest power.
+            cb.require_equal(
+                "output_rlc is the RLC of the output",
+                table[3].clone(),
+                is_final
+                    * sum::expr(
+                        output_bytes
+                            .into_iter()
+                            .zip(r.iter().rev())
+                            .map(|(byte, r)| byte * r.clone()),
+                    ),
+            );
+            cb.gate(meta.query_fixed(q_output, Rotation::cur()))
+        });
+
+        // Only the output rows are enabled in the keccak table.
+        meta.create_gate("keccak table", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+            cb.require_zero(
+                "is_enabled is only set on the output rows",
+                (meta.query_fixed(q_enable, Rotation::cur())
+                    - meta.query_fixed(q_output, Rotation::cur()))
+                    * meta.query_advice(keccak_table.is_enabled, Rotation::cur()),
+            );
+            cb.gate(1.expr())
+        });
+
+        Self {
+            minimum_rows: meta.minimum_rows(),
+            q_enable,
+            q_first,
+            q_block_start,
+            q_absorb,
+            q_round,
+            q_next,
+            q_input,
+            q_input_last,
+            q_output,
+            round_constant,
+            state,
+            theta_c,
+            theta,
+            chi,
+            input,
+            is_padding,
+            is_first,
+            is_final,
+            data_rlc_prev,
+            data_rlc,
+            data_len_prev,
+            data_len,
+            power_prev,
+            power,
+            keccak_table,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the number of rows required to hash `inputs`, including the
+    /// rows without powers of randomness.
+    pub fn get_num_rows_required<'a>(inputs: impl IntoIterator<Item = &'a Vec<u8>>) -> usize {
+        let num_blocks: usize = inputs.into_iter().map(|input| input.len() / RATE + 1).sum();
+        num_blocks * BLOCK_ROWS + 64
+    }
+
+    /// Assign the keccak circuit in a domain of `size` rows, filling it with
+    /// the permutations of `inputs` followed by the permutations of the empty
+    /// input.
+    pub fn assign<'a>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        size: usize,
+        inputs: impl IntoIterator<Item = &'a Vec<u8>>,
+        randomness: F,
+    ) -> Result<(), Error> {
+        let rows = keccak_rows(size, inputs, randomness)?;
+        self.assign_rows(layouter, size, &rows)
+    }
+
+    fn assign_rows(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        size: usize,
+        rows: &[KeccakRow<F>],
+    ) -> Result<(), Error> {
+        // Subtract the unusable rows from the size
+        let last_row_offset = size - self.minimum_rows + 1;
+
+        layouter.assign_region(
+            || "keccak circuit",
+            |mut region| {
+                for offset in 0..=last_row_offset {
+                    self.assign_fixed_row(&mut region, offset, offset < rows.len())?;
+                }
+                for (offset, row) in rows.iter().enumerate() {
+                    self.assign_row(&mut region, offset, row)?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn assign_fixed_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        is_permutation: bool,
+    ) -> Result<(), Error> {
+        let row = offset % BLOCK_ROWS;
+        let is_round = is_permutation && row % ROUND_ROWS == 0;
+        for (name, column, value) in [
+            ("q_enable", self.q_enable, true),
+            ("q_first", self.q_first, is_permutation && offset == 0),
+            (
+                "q_block_start",
+                self.q_block_start,
+                is_permutation && row == 0,
+            ),
+            (
+                "q_absorb",
+                self.q_absorb,
+                is_permutation && row < ROUND_ROWS,
+            ),
+            ("q_round", self.q_round, is_round),
+            ("q_next", self.q_next, is_permutation && row >= ROUND_ROWS),
+            (
+                "q_input",
+                self.q_input,
+                is_permutation && row < NEXT_INPUTS_LANES,
+            ),
+            (
+                "q_input_last",
+                self.q_input_last,
+                is_permutation && row == NEXT_INPUTS_LANES - 1,
+            ),
+            (
+                "q_output",
+                self.q_output,
+                is_permutation && row == OUTPUT_ROW,
+            ),
+        ] {
+            region.assign_fixed(
+                || format!("{} {}", name, offset),
+                column,
+                offset,
+                || Ok(F::from(value as u64)),
+            )?;
+        }
+        let round_constant = if is_round {
+            ROUND_CONSTANTS[row / ROUND_ROWS]
+        } else {
+            0
+        };
+        for (column, bit) in self.round_constant.iter().zip(ROUND_CONSTANT_BITS) {
+            region.assign_fixed(
+                || format!("round constant {}", offset),
+                *column,
+                offset,
+                || Ok(F::from((round_constant >> bit) & 1)),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn assign_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: &KeccakRow<F>,
+    ) -> Result<(), Error> {
+        for (name, columns, lane) in [
+            ("state", &self.state, row.state),
+            ("theta_c", &self.theta_c, row.theta_c),
+            ("theta", &self.theta, row.theta),
+            ("chi", &self.chi, row.chi),
+            ("input", &self.input, row.input),
+        ] {
+            for (z, column) in columns.iter().enumerate() {
+                region.assign_advice(
+                    || format!("{} {} {}", name, z, offset),
+                    *column,
+                    offset,
+                    || Ok(F::from((lane >> z) & 1)),
+                )?;
+            }
+        }
+        for (column, is_padding) in self.is_padding.iter().zip(row.is_padding) {
+            region.assign_advice(
+                || format!("is_padding {}", offset),
+                *column,
+                offset,
+                || Ok(F::from(is_padding as u64)),
+            )?;
+        }
+        for (name, column, value) in [
+            ("is_first", self.is_first, F::from(row.is_first as u64)),
+            ("is_final", self.is_final, F::from(row.is_final as u64)),
+            ("data_rlc_prev", self.data_rlc_prev, row.data_rlc_prev),
+            ("data_rlc", self.data_rlc, row.data_rlc),
+            (
+                "data_len_prev",
+                self.data_len_prev,
+                F::from(row.data_len_prev),
+            ),
+            ("data_len", self.data_len, F::from(row.data_len)),
+            ("power_prev", self.power_prev, row.power_prev),
+            ("power", self.power, row.power),
+        ] {
+            region.assign_advice(
+                || format!("{} {}", name, offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        self.keccak_table.assign_row(region, offset, row.table)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::{MockProver, VerifyFailure},
+        pairing::bn256::Fr,
+        plonk::Circuit,
+    };
+
+    #[derive(Default)]
+    struct TestCircuit<F> {
+        rows: Vec<KeccakRow<F>>,
+        size: usize,
+    }
+
+    impl<F: Field> Circuit<F> for TestCircuit<F> {
+        type Config = KeccakConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let keccak_table = KeccakTable::construct(meta);
+            let power_of_randomness = {
+                let columns = [(); 31].map(|_| meta.instance_column());
+                let mut power_of_randomness = None;
+
+                meta.create_gate("power of randomness", |meta| {
+                    power_of_randomness =
+                        Some(columns.map(|column| meta.query_instance(column, Rotation::cur())));
+
+                    [0.expr()]
+                });
+
+                power_of_randomness.unwrap()
+            };
+            KeccakConfig::configure(meta, power_of_randomness, keccak_table)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.assign_rows(&mut layouter, self.size, &self.rows)
+        }
+    }
+
+    fn run<F: Field>(
+        k: u32,
+        inputs: Vec<Vec<u8>>,
+        randomness: F,
+        instance_randomness: F,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        run_tampered(k, inputs, randomness, instance_randomness, |_| {})
+    }
+
+    /// Runs the circuit on the rows of `inputs` modified by `tamper`.
+    fn run_tampered<F: Field>(
+        k: u32,
+        inputs: Vec<Vec<u8>>,
+        randomness: F,
+        instance_randomness: F,
+        tamper: impl FnOnce(&mut [KeccakRow<F>]),
+    ) -> Result<(), Vec<VerifyFailure>> {
+        let size = 1 << k;
+        let mut rows = keccak_rows(size, &inputs, randomness).unwrap();
+        tamper(&mut rows);
+        let circuit = TestCircuit { rows, size };
+        let instance = (1..32)
+            .map(|exp| vec![instance_randomness.pow(&[exp, 0, 0, 0]); size - 64])
+            .collect();
+        let prover = MockProver::<F>::run(k, &circuit, instance).unwrap();
+        prover.verify()
+    }
+
+    #[test]
+    fn keccak_circuit_ok() {
+        // "abc" takes one block and the 200 bytes take two, which fills the 3
+        // blocks that fit in 2^11 rows.
+        let inputs = vec![b"abc".to_vec(), (0..200).map(|i| i as u8).collect()];
+        let randomness = Fr::from(0x1234);
+        assert_eq!(run(11, inputs, randomness, randomness), Ok(()));
+    }
+
+    #[test]
+    fn keccak_circuit_padding_ok() {
+        // 135 bytes leave a single padding byte 0x81 in the block.
+        let inputs = vec![vec![0xff; 135], vec![]];
+        let randomness = Fr::from(0x1234);
+        assert_eq!(run(11, inputs, randomness, randomness), Ok(()));
+    }
+
+    #[test]
+    fn keccak_circuit_wrong_randomness() {
+        let inputs = vec![b"abc".to_vec()];
+        assert!(run(11, inputs, Fr::from(0x1234), Fr::from(0x1235)).is_err());
+    }
+
+    #[test]
+    fn keccak_circuit_flipped_state_bit() {
+        let inputs = vec![b"abc".to_vec()];
+        let randomness = Fr::from(0x1234);
+        // Flip a bit of a lane of the state in the middle of the permutation
+        let tamper = |rows: &mut [KeccakRow<Fr>]| rows[5 * ROUND_ROWS + 7].state ^= 1 << 13;
+        assert!(run_tampered(11, inputs, randomness, randomness, tamper).is_err());
+    }
+
+    #[test]
+    fn keccak_circuit_wrong_output_rlc() {
+        let inputs = vec![b"abc".to_vec()];
+        let randomness = Fr::from(0x1234);
+        let tamper = |rows: &mut [KeccakRow<Fr>]| rows[OUTPUT_ROW].table[3] += Fr::one();
+        assert!(run_tampered(11, inputs, randomness, randomness, tamper).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn keccak_circuit_capacity_exceeded() {
+        let inputs = vec![vec![0; 4 * RATE]];
+        let randomness = Fr::from(0x1234);
+        let _ = run(11, inputs, randomness, randomness);
+    }
+}
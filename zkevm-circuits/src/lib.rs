@@ -19,8 +19,14 @@
 pub mod bytecode_circuit;
 pub mod copy_circuit;
 pub mod evm_circuit;
+pub mod exp_circuit;
+pub mod keccak_circuit;
+pub mod mpt_circuit;
+pub mod pi_circuit;
+pub mod rlp_circuit;
 pub mod rw_table;
 pub mod state_circuit;
+pub mod super_circuit;
 #[cfg(test)]
 pub mod test_util;
 pub mod tx_circuit;
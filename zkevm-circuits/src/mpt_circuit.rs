@@ -0,0 +1,1869 @@
+//! The MPT circuit implementation, which proves the sequence of updates to
+//! the state trie made by the account and storage accesses of a block, from
+//! the state root before the block to the one after it.
+//!
+//! Each update is a walk down a trie along the hashed key of the update, over
+//! 64 rows with one nibble of the key per row.  The rows of a walk belong to
+//! the nodes of the path of the key, which are the same before and after the
+//! update except for their child on the path, and end with:
+//! - the leaf of the key, before and/or after the update,
+//! - an empty slot or the leaf of another key, to prove that the key is absent
+//!   before and after the update, or
+//! - the leaf of another key which is split into a branch node (with an
+//!   extension node above it if both keys share nibbles) with both leaves as
+//!   children, to insert the key, or the other way around to delete it.
+//!
+//! The nodes are decoded by the RLP circuit, whose streams are hashed into
+//! the keccak table, and each row references the RLP streams of its nodes by
+//! their ids, so that the hash of each node is the RLC encoded child of the
+//! node above it, and the first row of a walk has the roots of the trie
+//! before and after the update.
+//!
+//! A storage update walks down the storage trie of the account, and is
+//! followed by a walk of the state trie which updates the storage root of the
+//! account.  An account update walks down the state trie, and the state roots
+//! are chained from one walk to the next one.  The first row of each walk
+//! other than the storage root updates is a row of the MPT table, which the
+//! state circuit looks up both ways for the initial and final values of the
+//! accessed account fields and storage slots.
+//!
+//! The embedded nodes, the insertion of a key which diverges from the path
+//! of an extension node, and the deletion of a key which leaves a branch node
+//! with a single child which is not a leaf are not supported.
+
+#[cfg(test)]
+mod test;
+mod witness;
+
+pub use witness::MptWitness;
+pub(crate) use witness::WALK_ROWS;
+
+use crate::{
+    evm_circuit::{
+        table::{LookupTable, RwTableTag},
+        util::{constraint_builder::BaseConstraintBuilder, not, rlc, RandomLinearCombination},
+        witness::Rw,
+    },
+    keccak_circuit::KeccakTable,
+    rlp_circuit::RlpConfig,
+    state_circuit::{access_key, AccessKey},
+    util::Expr,
+};
+use bus_mapping::mpt::EMPTY_ROOT;
+use eth_types::{Field, ToLittleEndian, ToScalar, ToWord, Word};
+use gadgets::is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction};
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use keccak256::EMPTY_HASH;
+use log::error;
+use std::collections::BTreeMap;
+use witness::{AccountField, LeafValues, NodeKind, Walk, N_NODE_KINDS};
+
+/// An update to the state trie made by the accesses to an account field or a
+/// storage slot, from the value before the first access to the value after
+/// the last access.
+#[derive(Clone, Copy, Debug)]
+pub struct MptUpdate {
+    first: Rw,
+    last: Rw,
+}
+
+impl MptUpdate {
+    /// Return the value before the update, which is the initial value of the
+    /// accesses in state circuit.
+    pub fn old_value_assignment<F: Field>(&self, randomness: F) -> F {
+        self.first
+            .value_prev_assignment(randomness)
+            .unwrap_or_default()
+    }
+
+    /// Return the value after the update.
+    pub fn new_value_assignment<F: Field>(&self, randomness: F) -> F {
+        self.last.value_assignment(randomness)
+    }
+
+    fn table_assignment<F: Field>(&self, randomness: F) -> [F; 6] {
+        [
+            F::one(),
+            self.first
+                .address()
+                .and_then(|address| address.to_scalar())
+                .unwrap(),
+            RandomLinearCombination::random_linear_combine(
+                self.first.storage_key().unwrap_or_default().to_le_bytes(),
+                randomness,
+            ),
+            F::from(self.first.field_tag().unwrap_or_default()),
+            self.old_value_assignment(randomness),
+            self.new_value_assignment(randomness),
+        ]
+    }
+}
+
+/// The updates to the state trie of a block, sorted in the same order as the
+/// rows in state circuit.
+#[derive(Clone, Debug, Default)]
+pub struct MptUpdates(BTreeMap<AccessKey, MptUpdate>);
+
+impl MptUpdates {
+    /// Collect an update for each account field and storage slot accessed in
+    /// `rows`.
+    pub fn new(rows: &[Rw]) -> Self {
+        let mut updates = BTreeMap::<_, MptUpdate>::new();
+        for row in rows
+            .iter()
+            .filter(|row| matches!(row.tag(), RwTableTag::Account | RwTableTag::AccountStorage))
+        {
+            updates
+                .entry(access_key(row))
+                .and_modify(|update| {
+                    if row.rw_counter() < update.first.rw_counter() {
+                        update.first = *row;
+                    }
+                    if row.rw_counter() > update.last.rw_counter() {
+                        update.last = *row;
+                    }
+                })
+                .or_insert(MptUpdate {
+                    first: *row,
+                    last: *row,
+                });
+        }
+        Self(updates)
+    }
+
+    /// Return the update made by the accesses that `row` belongs to.
+    pub fn get(&self, row: &Rw) -> Option<&MptUpdate> {
+        self.0.get(&access_key(row))
+    }
+
+    /// Return the updates in the order of the rows in state circuit, which
+    /// is also the order of the walks in MPT circuit.
+    pub fn iter(&self) -> impl Iterator<Item = &MptUpdate> {
+        self.0.values()
+    }
+
+    /// Return the number of updates.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Return whether there is no update.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Return the rows of [`MptTable`] for the updates.
+    pub fn table_assignments<F: Field>(&self, randomness: F) -> Vec<[F; 6]> {
+        self.0
+            .values()
+            .map(|update| update.table_assignment(randomness))
+            .collect()
+    }
+}
+
+/// The MPT table shared between state circuit and MPT circuit, where each
+/// row with `is_update` set is an update to the state trie.
+#[derive(Clone, Copy, Debug)]
+pub struct MptTable {
+    /// Whether the row is assigned
+    pub q_enable: Column<Fixed>,
+    /// Whether the row is an update
+    pub is_update: Column<Advice>,
+    /// The account address
+    pub address: Column<Advice>,
+    /// The RLC encoded storage key, which is 0 for account fields
+    pub storage_key: Column<Advice>,
+    /// The account field tag, which is 0 for storage slots
+    pub field_tag: Column<Advice>,
+    /// The value before the update
+    pub old_value: Column<Advice>,
+    /// The value after the update
+    pub new_value: Column<Advice>,
+}
+
+impl<F: FieldExt> LookupTable<F> for MptTable {
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        let q_enable = meta.query_fixed(self.q_enable, Rotation::cur());
+        self.columns()
+            .iter()
+            .map(|column| q_enable.clone() * meta.query_advice(*column, Rotation::cur()))
+            .collect()
+    }
+}
+
+impl MptTable {
+    /// Construct the columns of the table.
+    pub fn construct<F: FieldExt>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            q_enable: meta.fixed_column(),
+            is_update: meta.advice_column(),
+            address: meta.advice_column(),
+            storage_key: meta.advice_column(),
+            field_tag: meta.advice_column(),
+            old_value: meta.advice_column(),
+            new_value: meta.advice_column(),
+        }
+    }
+
+    fn columns(&self) -> [Column<Advice>; 6] {
+        [
+            self.is_update,
+            self.address,
+            self.storage_key,
+            self.field_tag,
+            self.old_value,
+            self.new_value,
+        ]
+    }
+
+    /// Assign a row of the table at `offset`.
+    pub fn assign<F: FieldExt>(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: [F; 6],
+    ) -> Result<(), Error> {
+        region.assign_fixed(
+            || "assign mpt table q_enable",
+            self.q_enable,
+            offset,
+            || Ok(F::one()),
+        )?;
+        for (column, value) in self.columns().into_iter().zip(row) {
+            region.assign_advice(|| "assign mpt table row", column, offset, || Ok(value))?;
+        }
+        Ok(())
+    }
+
+    /// Load the table with an all-zero row followed by `updates`, for the
+    /// circuits which look up the table without proving it.
+    pub fn load<F: Field>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        updates: &MptUpdates,
+        randomness: F,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "mpt table",
+            |mut region| {
+                self.assign(&mut region, 0, [F::zero(); 6])?;
+                for (offset, row) in updates
+                    .table_assignments(randomness)
+                    .into_iter()
+                    .enumerate()
+                {
+                    self.assign(&mut region, offset + 1, row)?;
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+/// Columns of the leaf of the key of a walk, before or after the update
+#[derive(Clone, Debug)]
+struct LeafColumns<F> {
+    present: Column<Advice>,
+    probe: Column<Advice>,
+    probe_is_zero: IsZeroConfig<F>,
+    vrlc: Column<Advice>,
+    vlen: Column<Advice>,
+    vlen_is_one: IsZeroConfig<F>,
+    pow: Column<Advice>,
+    nonce: Column<Advice>,
+    balance: Column<Advice>,
+    storage_root: Column<Advice>,
+    code_hash: Column<Advice>,
+    code_hash_is_zero: IsZeroConfig<F>,
+    stored_code_hash: Column<Advice>,
+    account_id: Column<Advice>,
+}
+
+/// Queries of [`LeafColumns`] on the current row
+struct LeafQueries<F> {
+    present: Expression<F>,
+    probe: Expression<F>,
+    probe_is_zero: Expression<F>,
+    vrlc: Expression<F>,
+    vlen: Expression<F>,
+    vlen_is_one: Expression<F>,
+    pow: Expression<F>,
+    nonce: Expression<F>,
+    balance: Expression<F>,
+    storage_root: Expression<F>,
+    code_hash: Expression<F>,
+    code_hash_is_zero: Expression<F>,
+    stored_code_hash: Expression<F>,
+    account_id: Expression<F>,
+}
+
+impl<F: Field> LeafColumns<F> {
+    fn configure(meta: &mut ConstraintSystem<F>, q_enable: Column<Fixed>) -> Self {
+        let [present, probe, vrlc, vlen, pow] = [(); 5].map(|_| meta.advice_column());
+        let [nonce, balance, storage_root, code_hash, stored_code_hash, account_id] =
+            [(); 6].map(|_| meta.advice_column());
+        let [probe_inv, vlen_inv, code_hash_inv] = [(); 3].map(|_| meta.advice_column());
+        let probe_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_enable, Rotation::cur()),
+            |meta| meta.query_advice(probe, Rotation::cur()),
+            probe_inv,
+        );
+        let vlen_is_one = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_enable, Rotation::cur()),
+            |meta| meta.query_advice(vlen, Rotation::cur()) - 1.expr(),
+            vlen_inv,
+        );
+        let code_hash_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_enable, Rotation::cur()),
+            |meta| meta.query_advice(code_hash, Rotation::cur()),
+            code_hash_inv,
+        );
+        Self {
+            present,
+            probe,
+            probe_is_zero,
+            vrlc,
+            vlen,
+            vlen_is_one,
+            pow,
+            nonce,
+            balance,
+            storage_root,
+            code_hash,
+            code_hash_is_zero,
+            stored_code_hash,
+            account_id,
+        }
+    }
+
+    fn columns(&self) -> [Column<Advice>; 11] {
+        [
+            self.present,
+            self.probe,
+            self.vrlc,
+            self.vlen,
+            self.pow,
+            self.nonce,
+            self.balance,
+            self.storage_root,
+            self.code_hash,
+            self.stored_code_hash,
+            self.account_id,
+        ]
+    }
+
+    fn queries(&self, meta: &mut VirtualCells<F>) -> LeafQueries<F> {
+        let mut cur = |column| meta.query_advice(column, Rotation::cur());
+        LeafQueries {
+            present: cur(self.present),
+            probe: cur(self.probe),
+            probe_is_zero: self.probe_is_zero.expr(),
+            vrlc: cur(self.vrlc),
+            vlen: cur(self.vlen),
+            vlen_is_one: self.vlen_is_one.expr(),
+            pow: cur(self.pow),
+            nonce: cur(self.nonce),
+            balance: cur(self.balance),
+            storage_root: cur(self.storage_root),
+            code_hash: cur(self.code_hash),
+            code_hash_is_zero: self.code_hash_is_zero.expr(),
+            stored_code_hash: cur(self.stored_code_hash),
+            account_id: cur(self.account_id),
+        }
+    }
+
+    fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        leaf: &LeafValues<F>,
+    ) -> Result<(), Error> {
+        for (column, value) in self.columns().into_iter().zip([
+            F::from(leaf.present as u64),
+            leaf.probe,
+            leaf.vrlc,
+            F::from(leaf.vlen),
+            leaf.pow,
+            leaf.nonce,
+            leaf.balance,
+            leaf.storage_root,
+            leaf.code_hash,
+            leaf.stored_code_hash,
+            F::from(leaf.account_id),
+        ]) {
+            region.assign_advice(
+                || format!("mpt leaf {}", offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        for (config, value) in [
+            (&self.probe_is_zero, leaf.probe),
+            (&self.vlen_is_one, F::from(leaf.vlen) - F::one()),
+            (&self.code_hash_is_zero, leaf.code_hash),
+        ] {
+            IsZeroChip::construct(config.clone()).assign(region, offset, Some(value))?;
+        }
+        Ok(())
+    }
+}
+
+/// Queries of the kinds of the node of a row
+struct KindQueries<F> {
+    branch: Expression<F>,
+    ext: Expression<F>,
+    leaf: Expression<F>,
+    empty: Expression<F>,
+    other: Expression<F>,
+    split_ext: Expression<F>,
+    split_branch: Expression<F>,
+    split_leaf: Expression<F>,
+}
+
+impl<F: Field> KindQueries<F> {
+    fn new(
+        meta: &mut VirtualCells<F>,
+        columns: [Column<Advice>; N_NODE_KINDS],
+        rotation: Rotation,
+    ) -> Self {
+        let [branch, ext, leaf, empty, other, split_ext, split_branch, split_leaf] =
+            columns.map(|column| meta.query_advice(column, rotation));
+        Self {
+            branch,
+            ext,
+            leaf,
+            empty,
+            other,
+            split_ext,
+            split_branch,
+            split_leaf,
+        }
+    }
+
+    /// Whether the row is in a split
+    fn split(&self) -> Expression<F> {
+        self.split_ext.clone() + self.split_branch.clone() + self.split_leaf.clone()
+    }
+
+    /// Whether the nibble of the row is in the path of the node
+    fn has_path(&self) -> Expression<F> {
+        self.ext.clone()
+            + self.leaf.clone()
+            + self.other.clone()
+            + self.split_ext.clone()
+            + self.split_leaf.clone()
+    }
+
+    /// Whether the path of the node is the path of a leaf
+    fn leaf_path(&self) -> Expression<F> {
+        self.leaf.clone() + self.other.clone() + self.split_leaf.clone()
+    }
+}
+
+/// Return the sum of `tuples` multiplied by their mutually exclusive
+/// conditions, to look up any of them in a single lookup.
+fn select<F: Field, const N: usize>(
+    tuples: Vec<(Expression<F>, [Expression<F>; N])>,
+) -> Vec<Expression<F>> {
+    (0..N)
+        .map(|idx| {
+            tuples.iter().fold(0.expr(), |acc, (condition, tuple)| {
+                acc + condition.clone() * tuple[idx].clone()
+            })
+        })
+        .collect()
+}
+
+/// Config for the MPT circuit
+#[derive(Clone, Debug)]
+pub struct MptConfig<F> {
+    mpt_table: MptTable,
+    q_first: Column<Fixed>,
+    q_second: Column<Fixed>,
+    q_last: Column<Fixed>,
+    q_next: Column<Fixed>,
+    q_odd: Column<Fixed>,
+    q_pre20: Column<Fixed>,
+    q_end20: Column<Fixed>,
+    q_pre32: Column<Fixed>,
+    q_end32: Column<Fixed>,
+    q_first_block: Column<Fixed>,
+    q_last_block: Column<Fixed>,
+    u8_table: Column<Fixed>,
+    nibble_table: Column<Fixed>,
+    q_pow: Column<Fixed>,
+    q_pow_first: Column<Fixed>,
+    q_pow_next: Column<Fixed>,
+    pow: Column<Advice>,
+    // Values of a walk, which are the same on all its rows
+    q_update: Column<Advice>,
+    is_storage: Column<Advice>,
+    address: Column<Advice>,
+    storage_key: Column<Advice>,
+    old_value: Column<Advice>,
+    new_value: Column<Advice>,
+    field_selectors: [Column<Advice>; 4],
+    old_root: Column<Advice>,
+    new_root: Column<Advice>,
+    old_state_root: Column<Advice>,
+    new_state_root: Column<Advice>,
+    key_input: Column<Advice>,
+    other_vrlc: Column<Advice>,
+    other_vlen: Column<Advice>,
+    old: LeafColumns<F>,
+    new: LeafColumns<F>,
+    // Values of a row
+    nibble_bits: [Column<Advice>; 16],
+    other_nibble: Column<Advice>,
+    pre_byte: Column<Advice>,
+    pre_rlc: Column<Advice>,
+    pre_value: Column<Advice>,
+    key_rlc: Column<Advice>,
+    kinds: [Column<Advice>; N_NODE_KINDS],
+    is_high: Column<Advice>,
+    n_rlc: Column<Advice>,
+    n_len: Column<Advice>,
+    o_rlc: Column<Advice>,
+    o_len: Column<Advice>,
+    o2_rlc: Column<Advice>,
+    o2_len: Column<Advice>,
+    other_inv: Column<Advice>,
+    ids: [Column<Advice>; 3],
+    hashes: [Column<Advice>; 3],
+    old_ref: Column<Advice>,
+    new_ref: Column<Advice>,
+    old_empty: Column<Advice>,
+    new_empty: Column<Advice>,
+    children: [Column<Advice>; 16],
+    flags: [Column<Advice>; 16],
+    rlp: RlpConfig<F>,
+}
+
+impl<F: Field> MptConfig<F> {
+    /// Configure the MPT circuit, which assigns `mpt_table`, decodes its
+    /// trie nodes with an RLP circuit, and looks up the hashes of the nodes
+    /// and of the keys in `keccak_table`.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        power_of_randomness: [Expression<F>; 31],
+        keccak_table: KeccakTable,
+        mpt_table: MptTable,
+    ) -> Self {
+        let q_enable = mpt_table.q_enable;
+        let [q_first, q_second, q_last, q_next, q_odd] = [(); 5].map(|_| meta.fixed_column());
+        let [q_pre20, q_end20, q_pre32, q_end32] = [(); 4].map(|_| meta.fixed_column());
+        let [q_first_block, q_last_block] = [(); 2].map(|_| meta.fixed_column());
+        let [u8_table, nibble_table, q_pow, q_pow_first, q_pow_next] =
+            [(); 5].map(|_| meta.fixed_column());
+        let pow = meta.advice_column();
+        let [q_update, is_storage, address, storage_key, old_value, new_value] =
+            [(); 6].map(|_| meta.advice_column());
+        let field_selectors = [(); 4].map(|_| meta.advice_column());
+        let [old_root, new_root, old_state_root, new_state_root] =
+            [(); 4].map(|_| meta.advice_column());
+        let [key_input, other_vrlc, other_vlen] = [(); 3].map(|_| meta.advice_column());
+        let old = LeafColumns::configure(meta, q_enable);
+        let new = LeafColumns::configure(meta, q_enable);
+        let nibble_bits = [(); 16].map(|_| meta.advice_column());
+        let [other_nibble, pre_byte, pre_rlc, pre_value, key_rlc] =
+            [(); 5].map(|_| meta.advice_column());
+        let kinds = [(); N_NODE_KINDS].map(|_| meta.advice_column());
+        let [is_high, n_rlc, n_len, o_rlc, o_len, o2_rlc, o2_len, other_inv] =
+            [(); 8].map(|_| meta.advice_column());
+        let ids = [(); 3].map(|_| meta.advice_column());
+        let hashes = [(); 3].map(|_| meta.advice_column());
+        let [old_ref, new_ref, old_empty, new_empty] = [(); 4].map(|_| meta.advice_column());
+        let children = [(); 16].map(|_| meta.advice_column());
+        let flags = [(); 16].map(|_| meta.advice_column());
+        let rlp = RlpConfig::configure(meta, power_of_randomness[0].clone(), keccak_table);
+        meta.enable_equality(old_state_root);
+        meta.enable_equality(new_state_root);
+
+        let config = Self {
+            mpt_table,
+            q_first,
+            q_second,
+            q_last,
+            q_next,
+            q_odd,
+            q_pre20,
+            q_end20,
+            q_pre32,
+            q_end32,
+            q_first_block,
+            q_last_block,
+            u8_table,
+            nibble_table,
+            q_pow,
+            q_pow_first,
+            q_pow_next,
+            pow,
+            q_update,
+            is_storage,
+            address,
+            storage_key,
+            old_value,
+            new_value,
+            field_selectors,
+            old_root,
+            new_root,
+            old_state_root,
+            new_state_root,
+            key_input,
+            other_vrlc,
+            other_vlen,
+            old,
+            new,
+            nibble_bits,
+            other_nibble,
+            pre_byte,
+            pre_rlc,
+            pre_value,
+            key_rlc,
+            kinds,
+            is_high,
+            n_rlc,
+            n_len,
+            o_rlc,
+            o_len,
+            o2_rlc,
+            o2_len,
+            other_inv,
+            ids,
+            hashes,
+            old_ref,
+            new_ref,
+            old_empty,
+            new_empty,
+            children,
+            flags,
+            rlp,
+        };
+        let c = &config;
+
+        let r = power_of_randomness[0].clone();
+        let word_expr = |word: Word| {
+            rlc::expr(
+                &word
+                    .to_le_bytes()
+                    .map(|byte| Expression::Constant(F::from(byte as u64))),
+                &power_of_randomness,
+            )
+        };
+        // RLC encoded root of the empty trie and hash of the empty code
+        let empty_root = word_expr(EMPTY_ROOT.to_word());
+        let empty_hash = word_expr(Word::from_big_endian(&*EMPTY_HASH));
+
+        let cur = |meta: &mut VirtualCells<F>, column| meta.query_advice(column, Rotation::cur());
+        let prev = |meta: &mut VirtualCells<F>, column| meta.query_advice(column, Rotation::prev());
+        let next = |meta: &mut VirtualCells<F>, column| meta.query_advice(column, Rotation::next());
+        let fixed = |meta: &mut VirtualCells<F>, column| meta.query_fixed(column, Rotation::cur());
+        let nibble = |meta: &mut VirtualCells<F>, rotation| {
+            c.nibble_bits
+                .iter()
+                .enumerate()
+                .fold(0.expr(), |acc, (idx, bit)| {
+                    acc + meta.query_advice(*bit, rotation) * idx.expr()
+                })
+        };
+
+        meta.create_gate("mpt walk values", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let columns = [
+                c.q_update,
+                c.is_storage,
+                c.address,
+                c.storage_key,
+                c.old_value,
+                c.new_value,
+                c.old_root,
+                c.new_root,
+                c.old_state_root,
+                c.new_state_root,
+                c.key_input,
+                c.other_vrlc,
+                c.other_vlen,
+            ]
+            .into_iter()
+            .chain(c.field_selectors)
+            .chain(c.old.columns())
+            .chain(c.new.columns());
+            cb.condition(not::expr(fixed(meta, c.q_first)), |cb| {
+                for column in columns {
+                    cb.require_equal(
+                        "walk values are the same on all the rows of a walk",
+                        cur(meta, column),
+                        prev(meta, column),
+                    );
+                }
+            });
+
+            cb.gate(fixed(meta, q_enable))
+        });
+
+        meta.create_gate("mpt row", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let q_first = fixed(meta, c.q_first);
+            let q_odd = fixed(meta, c.q_odd);
+            let q_update = cur(meta, c.q_update);
+            let is_storage = cur(meta, c.is_storage);
+            let k = KindQueries::new(meta, c.kinds, Rotation::cur());
+            let k_prev = KindQueries::new(meta, c.kinds, Rotation::prev());
+            let k_next = KindQueries::new(meta, c.kinds, Rotation::next());
+            let n = nibble(meta, Rotation::cur());
+            let n_prev = nibble(meta, Rotation::prev());
+            let o = cur(meta, c.other_nibble);
+            let is_high = cur(meta, c.is_high);
+            let [h1, h2, _] = c.hashes.map(|column| cur(meta, column));
+            let old_present = cur(meta, c.old.present);
+            let new_present = cur(meta, c.new.present);
+
+            cb.require_boolean("q_update is boolean", q_update.clone());
+            cb.require_boolean("is_storage is boolean", is_storage.clone());
+            cb.require_zero(
+                "padding walks are not in a storage trie",
+                is_storage.clone() * not::expr(q_update.clone()),
+            );
+            let bits = c.nibble_bits.map(|column| cur(meta, column));
+            for bit in bits.iter() {
+                cb.require_boolean("nibble bits are boolean", bit.clone());
+            }
+            cb.require_equal(
+                "one nibble bit is set in the walks of updates",
+                bits.iter().fold(0.expr(), |acc, bit| acc + bit.clone()),
+                q_update.clone(),
+            );
+            let kinds = c.kinds.map(|column| cur(meta, column));
+            for kind in kinds.iter() {
+                cb.require_boolean("node kinds are boolean", kind.clone());
+            }
+            cb.require_equal(
+                "one node kind is set in the walks of updates",
+                kinds.iter().fold(0.expr(), |acc, kind| acc + kind.clone()),
+                q_update.clone(),
+            );
+            cb.require_boolean("is_high is boolean", is_high.clone());
+            let children = c.children.map(|column| cur(meta, column));
+            let flags = c.flags.map(|column| cur(meta, column));
+            for (child, flag) in children.iter().zip(flags.iter()) {
+                cb.require_boolean("child flags are boolean", flag.clone());
+                cb.require_zero(
+                    "children without flag are empty",
+                    not::expr(flag.clone()) * child.clone(),
+                );
+            }
+
+            // The references to the node of the row from the node above it
+            // before and after the update, which are the hashes of the nodes
+            // or 0 for empty slots.
+            cb.require_equal(
+                "old_ref is the hash of the node before the update",
+                cur(meta, c.old_ref),
+                (k.branch.clone() + k.ext.clone() + k.other.clone()) * h1.clone()
+                    + k.leaf.clone() * old_present.clone() * h1.clone()
+                    + k.split() * (h1.clone() + old_present.clone() * (h2.clone() - h1.clone())),
+            );
+            cb.require_equal(
+                "new_ref is the hash of the node after the update",
+                cur(meta, c.new_ref),
+                (k.branch.clone() + k.ext.clone()) * h2.clone()
+                    + k.other.clone() * h1.clone()
+                    + k.leaf.clone() * new_present.clone() * h2.clone()
+                    + k.split() * (h2.clone() + old_present.clone() * (h1 - h2)),
+            );
+            cb.require_equal(
+                "old_empty := the key ends at an empty slot before the update",
+                cur(meta, c.old_empty),
+                k.empty.clone() + k.leaf.clone() * not::expr(old_present),
+            );
+            cb.require_equal(
+                "new_empty := the key ends at an empty slot after the update",
+                cur(meta, c.new_empty),
+                k.empty.clone() + k.leaf.clone() * not::expr(new_present),
+            );
+
+            // The paths of the nodes start after a branch node or on the
+            // first row, and the path of the leaf of a split starts after its
+            // branch node.
+            let not_first = not::expr(q_first.clone());
+            let after_branch = q_first.clone() + not_first.clone() * k_prev.branch.clone();
+            let seg_start = after_branch.clone() + not_first.clone() * k_prev.split_branch.clone();
+            let ext_end = k.ext.clone() * k_next.branch.clone();
+            let split_ext_end = k.split_ext.clone() * k_next.split_branch.clone();
+
+            // The nibbles of a path are paired from its last nibble, which is
+            // on the last row of leaves and the row before the branch node of
+            // extension nodes.
+            cb.require_zero(
+                "is_high is set on the even rows of the paths of leaves",
+                k.leaf_path() * (is_high.clone() - not::expr(q_odd.clone())),
+            );
+            cb.require_zero(
+                "is_high alternates in the paths of extension nodes",
+                (k.ext.clone() + k.split_ext.clone())
+                    * not::expr(seg_start.clone())
+                    * (is_high.clone() + prev(meta, c.is_high) - 1.expr()),
+            );
+            cb.require_zero(
+                "the last nibble of the path of an extension node is low",
+                (ext_end + split_ext_end) * is_high.clone(),
+            );
+
+            // `n_rlc` and `n_len` accumulate the hex prefix encoding of the
+            // path of the node from the nibbles of the key.
+            let flag = 0x20.expr() * k.leaf_path();
+            cb.condition(k.has_path() * seg_start.clone(), |cb| {
+                cb.require_equal(
+                    "n_len := 1 on the first row of a path",
+                    cur(meta, c.n_len),
+                    1.expr(),
+                );
+                cb.require_equal(
+                    "n_rlc := the flag byte on the first row of a path",
+                    cur(meta, c.n_rlc),
+                    is_high.clone() * flag.clone()
+                        + not::expr(is_high.clone()) * (flag + 0x10.expr() + n.clone()),
+                );
+            });
+            cb.condition(k.has_path() * not::expr(seg_start), |cb| {
+                cb.require_equal(
+                    "n_len := n_len_prev + 1 on low nibbles",
+                    cur(meta, c.n_len),
+                    prev(meta, c.n_len) + not::expr(is_high.clone()),
+                );
+                cb.require_equal(
+                    "n_rlc := n_rlc_prev * r + 16 * n_prev + n on low nibbles",
+                    cur(meta, c.n_rlc),
+                    is_high.clone() * prev(meta, c.n_rlc)
+                        + not::expr(is_high.clone())
+                            * (prev(meta, c.n_rlc) * r.clone()
+                                + 16.expr() * n_prev.clone()
+                                + n.clone()),
+                );
+            });
+
+            // `o_rlc` and `o_len` accumulate the hex prefix encoding of the
+            // path of the leaf of another key from its nibbles, and `o2_rlc`
+            // and `o2_len` the path of the same leaf after a split.
+            let o_prev = prev(meta, c.other_nibble);
+            let leaf_path_rows = [
+                (
+                    k.other.clone() + k.split(),
+                    after_branch,
+                    c.o_rlc,
+                    c.o_len,
+                ),
+                (
+                    k.split_leaf.clone(),
+                    k_prev.split_branch.clone(),
+                    c.o2_rlc,
+                    c.o2_len,
+                ),
+            ];
+            for (condition, is_start, path_rlc, path_len) in leaf_path_rows {
+                cb.condition(condition.clone() * is_start.clone(), |cb| {
+                    cb.require_equal(
+                        "path_len := 1 on the first row of the path of a leaf",
+                        cur(meta, path_len),
+                        1.expr(),
+                    );
+                    cb.require_equal(
+                        "path_rlc := the flag byte on the first row of the path of a leaf",
+                        cur(meta, path_rlc),
+                        0x20.expr() + q_odd.clone() * (0x10.expr() + o.clone()),
+                    );
+                });
+                cb.condition(condition * not::expr(is_start), |cb| {
+                    cb.require_equal(
+                        "path_len := path_len_prev + 1 on odd rows",
+                        cur(meta, path_len),
+                        prev(meta, path_len) + q_odd.clone(),
+                    );
+                    cb.require_equal(
+                        "path_rlc := path_rlc_prev * r + 16 * o_prev + o on odd rows",
+                        cur(meta, path_rlc),
+                        q_odd.clone()
+                            * (prev(meta, path_rlc) * r.clone()
+                                + 16.expr() * o_prev.clone()
+                                + o.clone())
+                            + not::expr(q_odd.clone()) * prev(meta, path_rlc),
+                    );
+                });
+            }
+            cb.require_zero(
+                "the other key has the same nibbles in the extension of a split",
+                k.split_ext.clone() * (o.clone() - n.clone()),
+            );
+
+            // `key_rlc` accumulates the bytes of the hashed key, which is
+            // looked up in the keccak table on the last row.
+            cb.condition(q_odd.clone(), |cb| {
+                cb.require_equal(
+                    "key_rlc := key_rlc_prev * r + 16 * n_prev + n on odd rows",
+                    cur(meta, c.key_rlc),
+                    prev(meta, c.key_rlc) * r.clone() + 16.expr() * n_prev.clone() + n.clone(),
+                );
+            });
+            cb.condition(not::expr(q_odd) * not_first.clone(), |cb| {
+                cb.require_equal(
+                    "key_rlc := key_rlc_prev on even rows",
+                    cur(meta, c.key_rlc),
+                    prev(meta, c.key_rlc),
+                );
+            });
+
+            // The preimage of the hashed key is the 20 bytes of the address
+            // in the state trie, and the 32 bytes of the storage key in the
+            // storage tries, on the first rows of the walk.
+            let is_account = q_update.clone() - is_storage.clone();
+            let q_pre20 = fixed(meta, c.q_pre20);
+            let q_end20 = fixed(meta, c.q_end20);
+            let q_pre32 = fixed(meta, c.q_pre32);
+            let q_end32 = fixed(meta, c.q_end32);
+            let pre_byte = cur(meta, c.pre_byte);
+            let pre_rlc = cur(meta, c.pre_rlc);
+            let pre_value = cur(meta, c.pre_value);
+            let pre_end = is_account.clone() * q_end20.clone() + is_storage.clone() * q_end32.clone();
+            cb.require_zero(
+                "pre_rlc := pre_byte + r * pre_rlc_next in the preimage",
+                (is_account.clone() * (q_pre20.clone() - q_end20)
+                    + is_storage.clone() * (q_pre32.clone() - q_end32))
+                    * (pre_rlc.clone() - pre_byte.clone() - r.clone() * next(meta, c.pre_rlc)),
+            );
+            cb.require_zero(
+                "pre_rlc := pre_byte on the last byte of the preimage",
+                pre_end.clone() * (pre_rlc - pre_byte.clone()),
+            );
+            cb.require_zero(
+                "pre_value is the address or the storage key on the last byte of the preimage",
+                pre_end
+                    * (pre_value.clone()
+                        - is_account.clone() * cur(meta, c.address)
+                        - is_storage.clone() * cur(meta, c.storage_key)),
+            );
+            cb.require_zero(
+                "pre_value := pre_value_prev * base + pre_byte in the preimage",
+                not_first
+                    * (is_account.clone() * q_pre20 + is_storage.clone() * q_pre32)
+                    * (pre_value
+                        - prev(meta, c.pre_value)
+                            * (is_account * 256.expr() + is_storage * r.clone())
+                        - pre_byte),
+            );
+
+            // The child of a branch node on the path of the key is the node
+            // of the next row, and the children of the branch node of a split
+            // are the leaves of both keys.
+            let child = bits
+                .iter()
+                .zip(children.iter())
+                .fold(0.expr(), |acc, (bit, child)| acc + bit.clone() * child.clone());
+            let child_flag = bits
+                .iter()
+                .zip(flags.iter())
+                .fold(0.expr(), |acc, (bit, flag)| acc + bit.clone() * flag.clone());
+            cb.condition(k.branch.clone(), |cb| {
+                cb.require_equal(
+                    "the child of a branch node is the new node of the next row",
+                    child.clone(),
+                    next(meta, c.new_ref),
+                );
+                cb.require_equal(
+                    "the child of a branch node is not empty unless the next row is",
+                    child_flag.clone(),
+                    not::expr(next(meta, c.new_empty)),
+                );
+            });
+            cb.condition(k.split_branch.clone(), |cb| {
+                cb.require_equal(
+                    "the branch node of a split has 2 children",
+                    flags.iter().fold(0.expr(), |acc, flag| acc + flag.clone()),
+                    2.expr(),
+                );
+                cb.require_equal(
+                    "the branch node of a split has a child on the path",
+                    child_flag,
+                    1.expr(),
+                );
+                cb.require_equal(
+                    "the child of the branch node of a split is the leaf of the key",
+                    child,
+                    next(meta, c.hashes[1]),
+                );
+                cb.require_equal(
+                    "the other child of the branch node of a split is the leaf of the other key",
+                    flags
+                        .iter()
+                        .zip(children.iter())
+                        .fold(0.expr(), |acc, (flag, child)| acc + flag.clone() * child.clone()),
+                    next(meta, c.hashes[1]) + next(meta, c.hashes[2]),
+                );
+                cb.require_equal(
+                    "the other child of the branch node of a split is at the nibble of the other key",
+                    flags
+                        .iter()
+                        .enumerate()
+                        .fold(0.expr(), |acc, (idx, flag)| acc + flag.clone() * idx.expr()),
+                    n + o,
+                );
+            });
+
+            // The first row of a walk is a row of the MPT table, except for
+            // the updates of the storage roots after the storage updates.
+            let sels = c.field_selectors.map(|column| cur(meta, column));
+            let is_update = q_first * q_update * not::expr(sels[3].clone());
+            cb.require_equal(
+                "is_update := the first row of the walk of an update",
+                cur(meta, c.mpt_table.is_update),
+                is_update.clone(),
+            );
+            for (column, value) in [
+                (c.mpt_table.address, cur(meta, c.address)),
+                (c.mpt_table.storage_key, cur(meta, c.storage_key)),
+                (
+                    c.mpt_table.field_tag,
+                    sels[0].clone() + 2.expr() * sels[1].clone() + 3.expr() * sels[2].clone(),
+                ),
+                (c.mpt_table.old_value, cur(meta, c.old_value)),
+                (c.mpt_table.new_value, cur(meta, c.new_value)),
+            ] {
+                cb.require_equal(
+                    "the MPT table has the values of the update",
+                    cur(meta, column),
+                    is_update.clone() * value,
+                );
+            }
+
+            cb.gate(fixed(meta, q_enable))
+        });
+
+        meta.create_gate("mpt walk transitions", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let k = KindQueries::new(meta, c.kinds, Rotation::cur());
+            let k_next = KindQueries::new(meta, c.kinds, Rotation::next());
+            cb.require_zero(
+                "a branch node is not followed by the leaf of a split",
+                k.branch.clone() * k_next.split_leaf.clone(),
+            );
+            cb.require_zero(
+                "an extension node is followed by a branch node",
+                k.ext.clone() * not::expr(k_next.ext.clone() + k_next.branch.clone()),
+            );
+            cb.require_zero(
+                "the extension node of a split is followed by its branch node",
+                k.split_ext.clone()
+                    * not::expr(k_next.split_ext.clone() + k_next.split_branch.clone()),
+            );
+            cb.require_zero(
+                "the branch node of a split is followed by the leaves",
+                k.split_branch.clone() * not::expr(k_next.split_leaf.clone()),
+            );
+            for (kind, kind_next) in [
+                (k.leaf.clone(), k_next.leaf.clone()),
+                (k.empty.clone(), k_next.empty.clone()),
+                (k.other.clone(), k_next.other.clone()),
+                (k.split_leaf.clone(), k_next.split_leaf.clone()),
+            ] {
+                cb.require_zero(
+                    "the end of a walk spans the last rows",
+                    kind * not::expr(kind_next),
+                );
+            }
+
+            // The nodes span the rows of their path.
+            let ext_cont = k.ext.clone() * k_next.ext.clone();
+            let keeps = [
+                ext_cont.clone() + k.leaf.clone() + k.other.clone() + k.split(),
+                ext_cont
+                    + k.leaf.clone()
+                    + k.split_ext.clone() * k_next.split_ext.clone()
+                    + k.split_leaf.clone(),
+                k.split_leaf,
+            ];
+            for ((keep, id), hash) in keeps.into_iter().zip(c.ids).zip(c.hashes) {
+                cb.require_equal(
+                    "the node is the same on the rows of its path",
+                    keep.clone() * next(meta, id),
+                    keep.clone() * cur(meta, id),
+                );
+                cb.require_equal(
+                    "the node hash is the same on the rows of its path",
+                    keep.clone() * next(meta, hash),
+                    keep * cur(meta, hash),
+                );
+            }
+
+            cb.gate(fixed(meta, c.q_next))
+        });
+
+        meta.create_gate("mpt first row", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let k = KindQueries::new(meta, c.kinds, Rotation::cur());
+            let q_update = cur(meta, c.q_update);
+            let is_storage = cur(meta, c.is_storage);
+            let is_account = q_update.clone() - is_storage.clone();
+            let sels = c.field_selectors.map(|column| cur(meta, column));
+            let sel_storage_root = sels[3].clone();
+            let q_first_block = fixed(meta, c.q_first_block);
+
+            cb.require_zero("the first row is not in a split leaf", k.split_leaf);
+            cb.require_zero("key_rlc := 0 on the first row", cur(meta, c.key_rlc));
+            cb.require_equal(
+                "pre_value := pre_byte on the first row",
+                cur(meta, c.pre_value),
+                cur(meta, c.pre_byte),
+            );
+            cb.require_equal(
+                "key_input is the RLC of the preimage",
+                cur(meta, c.key_input),
+                cur(meta, c.pre_rlc),
+            );
+            cb.require_equal(
+                "old_root is the reference to the first node before the update",
+                cur(meta, c.old_root),
+                cur(meta, c.old_ref) + cur(meta, c.old_empty) * empty_root.clone(),
+            );
+            cb.require_equal(
+                "new_root is the reference to the first node after the update",
+                cur(meta, c.new_root),
+                cur(meta, c.new_ref) + cur(meta, c.new_empty) * empty_root.clone(),
+            );
+
+            // The walks of the state trie chain the state roots, and the
+            // walk after a storage update updates the storage root of its
+            // account.
+            cb.condition(not::expr(q_first_block.clone()), |cb| {
+                cb.require_equal(
+                    "old_state_root is the previous new_state_root",
+                    cur(meta, c.old_state_root),
+                    prev(meta, c.new_state_root),
+                );
+                cb.require_equal(
+                    "the storage root is updated after a storage update",
+                    sel_storage_root.clone(),
+                    prev(meta, c.is_storage),
+                );
+            });
+            cb.require_zero(
+                "the first walk doesn't update a storage root",
+                q_first_block * sel_storage_root.clone(),
+            );
+            cb.condition(sel_storage_root, |cb| {
+                cb.require_equal(
+                    "the storage root is updated in the account of the storage update",
+                    cur(meta, c.address),
+                    prev(meta, c.address),
+                );
+                cb.require_equal(
+                    "the old storage root is the old root of the storage update",
+                    cur(meta, c.old.storage_root),
+                    prev(meta, c.old_root),
+                );
+                cb.require_equal(
+                    "the new storage root is the new root of the storage update",
+                    cur(meta, c.new.storage_root),
+                    prev(meta, c.new_root),
+                );
+            });
+            cb.condition(is_account.clone(), |cb| {
+                cb.require_equal(
+                    "the walks of the state trie start at the state root",
+                    cur(meta, c.old_root),
+                    cur(meta, c.old_state_root),
+                );
+                cb.require_equal(
+                    "the walks of the state trie end at the state root",
+                    cur(meta, c.new_root),
+                    cur(meta, c.new_state_root),
+                );
+            });
+            cb.require_zero(
+                "the state root is the same in the other walks",
+                not::expr(is_account.clone())
+                    * (cur(meta, c.new_state_root) - cur(meta, c.old_state_root)),
+            );
+
+            // The walks of the state trie update one field of the account.
+            for sel in sels.iter() {
+                cb.require_boolean("field selectors are boolean", sel.clone());
+            }
+            cb.require_equal(
+                "one field is selected in the walks of the state trie",
+                sels.iter().fold(0.expr(), |acc, sel| acc + sel.clone()),
+                is_account.clone(),
+            );
+            cb.require_zero(
+                "storage_key := 0 in the walks of the state trie",
+                not::expr(is_storage.clone()) * cur(meta, c.storage_key),
+            );
+            let old = c.old.queries(meta);
+            let new = c.new.queries(meta);
+            let fields = |leaf: &LeafQueries<F>| {
+                [
+                    leaf.nonce.clone(),
+                    leaf.balance.clone(),
+                    leaf.code_hash.clone(),
+                    leaf.storage_root.clone(),
+                ]
+            };
+            for (sel, (old_field, new_field)) in
+                sels.iter().zip(fields(&old).into_iter().zip(fields(&new)))
+            {
+                cb.require_zero(
+                    "the other fields of the account are unchanged",
+                    is_account.clone() * not::expr(sel.clone()) * (new_field - old_field),
+                );
+            }
+            for (leaf, value) in [(&old, c.old_value), (&new, c.new_value)] {
+                let value = cur(meta, value);
+                cb.require_zero(
+                    "the value is the selected field of the account",
+                    not::expr(is_storage.clone())
+                        * (value.clone()
+                            - sels
+                                .iter()
+                                .zip(fields(leaf))
+                                .fold(0.expr(), |acc, (sel, field)| acc + sel.clone() * field)),
+                );
+
+                // The probe is 0 if and only if the leaf is absent, which is
+                // when the storage value is 0, and when the account is empty,
+                // and in the padding walks.
+                cb.require_equal(
+                    "probe := the storage value or the difference to the empty account",
+                    leaf.probe.clone(),
+                    is_storage.clone() * value.clone()
+                        + is_account.clone()
+                            * (leaf.nonce.clone()
+                                + r.clone() * leaf.balance.clone()
+                                + power_of_randomness[1].clone()
+                                    * (leaf.storage_root.clone() - empty_root.clone())
+                                + power_of_randomness[2].clone()
+                                    * (leaf.stored_code_hash.clone() - empty_hash.clone())),
+                );
+                cb.require_equal(
+                    "present := probe != 0",
+                    leaf.present.clone(),
+                    q_update.clone() * not::expr(leaf.probe_is_zero.clone()),
+                );
+                let absent_account = is_account.clone() * not::expr(leaf.present.clone());
+                for (name, field) in [
+                    ("nonce of absent accounts is 0", leaf.nonce.clone()),
+                    ("balance of absent accounts is 0", leaf.balance.clone()),
+                    (
+                        "storage root of absent accounts is the empty root",
+                        leaf.storage_root.clone() - empty_root.clone(),
+                    ),
+                    (
+                        "code hash of absent accounts is the empty hash",
+                        leaf.stored_code_hash.clone() - empty_hash.clone(),
+                    ),
+                ] {
+                    cb.require_zero(name, absent_account.clone() * field);
+                }
+                cb.require_zero(
+                    "the stored code hash is the code hash, or the empty hash for 0",
+                    is_account.clone()
+                        * (leaf.stored_code_hash.clone()
+                            - leaf.code_hash.clone()
+                            - leaf.code_hash_is_zero.clone() * empty_hash.clone()),
+                );
+                cb.require_zero(
+                    "the leaf value is the RLP encoding of the storage value",
+                    is_storage.clone()
+                        * leaf.present.clone()
+                        * (leaf.vrlc.clone()
+                            - value
+                            - not::expr(leaf.vlen_is_one.clone())
+                                * (0x7f.expr() + leaf.vlen.clone())
+                                * leaf.pow.clone()),
+                );
+            }
+
+            cb.gate(fixed(meta, c.q_first))
+        });
+
+        meta.create_gate("mpt last row", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let k = KindQueries::new(meta, c.kinds, Rotation::cur());
+            let present = cur(meta, c.old.present) + cur(meta, c.new.present);
+            cb.require_equal(
+                "the walks of updates end with a leaf, an empty slot or a split",
+                cur(meta, c.q_update),
+                k.leaf_path() + k.empty.clone(),
+            );
+            cb.require_zero(
+                "the key is absent at an empty slot or the leaf of another key",
+                (k.empty + k.other.clone()) * present.clone(),
+            );
+            cb.require_zero(
+                "the key is present on one side of a split",
+                k.split_leaf * (present - 1.expr()),
+            );
+            cb.require_zero(
+                "the leaf of another key has a different path",
+                k.other
+                    * ((cur(meta, c.o_rlc) - cur(meta, c.n_rlc)) * cur(meta, c.other_inv)
+                        - 1.expr()),
+            );
+            cb.require_zero(
+                "the last walk doesn't update a storage trie",
+                fixed(meta, c.q_last_block) * cur(meta, c.is_storage),
+            );
+
+            cb.gate(fixed(meta, c.q_last))
+        });
+
+        meta.create_gate("mpt powers of randomness", |meta| {
+            let pow = cur(meta, c.pow);
+            vec![
+                (
+                    "pow := 1 on the first row",
+                    fixed(meta, c.q_pow_first) * (pow.clone() - 1.expr()),
+                ),
+                (
+                    "pow_next := pow * r",
+                    fixed(meta, c.q_pow_next) * (next(meta, c.pow) - pow * r.clone()),
+                ),
+            ]
+        });
+
+        meta.lookup_any("mpt preimage byte", |meta| {
+            vec![(
+                fixed(meta, q_enable) * cur(meta, c.pre_byte),
+                fixed(meta, c.u8_table),
+            )]
+        });
+        meta.lookup_any("mpt other nibble", |meta| {
+            vec![(
+                fixed(meta, q_enable) * cur(meta, c.other_nibble),
+                fixed(meta, c.nibble_table),
+            )]
+        });
+        meta.lookup_any("mpt key keccak", |meta| {
+            let enable = fixed(meta, c.q_last) * cur(meta, c.q_update);
+            let len = 20.expr() + 12.expr() * cur(meta, c.is_storage);
+            [
+                enable.clone(),
+                enable.clone() * cur(meta, c.key_input),
+                enable.clone() * len,
+                enable * cur(meta, c.key_rlc),
+            ]
+            .into_iter()
+            .zip(keccak_table.table_exprs(meta))
+            .collect()
+        });
+        meta.lookup_any("mpt storage value header power", |meta| {
+            let is_storage = cur(meta, c.is_storage);
+            let old = fixed(meta, c.q_first) * is_storage.clone() * cur(meta, c.old.present);
+            let new = fixed(meta, c.q_second) * is_storage * cur(meta, c.new.present);
+            let q_pow = fixed(meta, c.q_pow);
+            vec![
+                (
+                    old.clone() * (cur(meta, c.old.vlen) - 1.expr())
+                        + new.clone() * (cur(meta, c.new.vlen) - 1.expr()),
+                    q_pow.clone() * fixed(meta, c.u8_table),
+                ),
+                (
+                    old * cur(meta, c.old.pow) + new * cur(meta, c.new.pow),
+                    q_pow * cur(meta, c.pow),
+                ),
+            ]
+        });
+
+        // The children of the branch nodes before and after the update are
+        // the same, except for the child on the path of the key.
+        for idx in 0..16 {
+            meta.lookup_any("mpt old branch child", |meta| {
+                let enable = fixed(meta, q_enable) * cur(meta, c.kinds[NodeKind::Branch as usize]);
+                let bit = cur(meta, c.nibble_bits[idx]);
+                let child = cur(meta, c.children[idx]);
+                let flag = cur(meta, c.flags[idx]);
+                [
+                    cur(meta, c.ids[0]),
+                    (idx + 1).expr(),
+                    child.clone() + bit.clone() * (next(meta, c.old_ref) - child),
+                    32.expr() * (flag.clone() + bit * (not::expr(next(meta, c.old_empty)) - flag)),
+                ]
+                .into_iter()
+                .map(|expr| enable.clone() * expr)
+                .zip(c.rlp.item_rlc_len_table_exprs(meta))
+                .collect()
+            });
+            meta.lookup_any("mpt new branch child", |meta| {
+                let enable = fixed(meta, q_enable)
+                    * (cur(meta, c.kinds[NodeKind::Branch as usize])
+                        + cur(meta, c.kinds[NodeKind::SplitBranch as usize]));
+                [
+                    cur(meta, c.ids[1]),
+                    (idx + 1).expr(),
+                    cur(meta, c.children[idx]),
+                    32.expr() * cur(meta, c.flags[idx]),
+                ]
+                .into_iter()
+                .map(|expr| enable.clone() * expr)
+                .zip(c.rlp.item_rlc_len_table_exprs(meta))
+                .collect()
+            });
+        }
+
+        // The paths and the children of the extension nodes and the leaves
+        // are their items 1 and 2, and the branch nodes have an empty item 17.
+        let node_items = |meta: &mut VirtualCells<F>| {
+            let q_enable = fixed(meta, q_enable);
+            let q_last = fixed(meta, c.q_last);
+            let k = KindQueries::new(meta, c.kinds, Rotation::cur());
+            let k_next = KindQueries::new(meta, c.kinds, Rotation::next());
+            let ext_end = q_enable.clone() * k.ext.clone() * k_next.branch.clone();
+            let split_ext_end = q_enable.clone() * k.split_ext.clone() * k_next.split_branch;
+            let [id1, id2, id3] = c.ids.map(|column| cur(meta, column));
+            let old_present = cur(meta, c.old.present);
+            let new_present = cur(meta, c.new.present);
+            let old_leaf = q_last.clone() * k.leaf.clone() * old_present.clone();
+            let new_leaf = q_last.clone() * k.leaf * new_present;
+            let other_leaf = q_last.clone() * (k.other + k.split_leaf.clone());
+            let split_leaf = q_last * k.split_leaf;
+            let n_path = [cur(meta, c.n_rlc), cur(meta, c.n_len)];
+            let o_path = [cur(meta, c.o_rlc), cur(meta, c.o_len)];
+            let o2_path = [cur(meta, c.o2_rlc), cur(meta, c.o2_len)];
+            let old_value = [cur(meta, c.old.vrlc), cur(meta, c.old.vlen)];
+            let new_value = [cur(meta, c.new.vrlc), cur(meta, c.new.vlen)];
+            let other_value = [cur(meta, c.other_vrlc), cur(meta, c.other_vlen)];
+            let item = |id: &Expression<F>, item: usize, [rlc, len]: [Expression<F>; 2]| {
+                [id.clone(), item.expr(), rlc, len]
+            };
+            let empty_item = [0.expr(), 0.expr()];
+            let child_item = |child: Expression<F>| [child, 32.expr()];
+            vec![
+                select(vec![
+                    (ext_end.clone(), item(&id1, 1, n_path.clone())),
+                    (old_leaf.clone(), item(&id1, 1, n_path.clone())),
+                    (other_leaf.clone(), item(&id1, 1, o_path)),
+                ]),
+                select(vec![
+                    (
+                        q_enable.clone() * k.branch.clone(),
+                        item(&id1, 17, empty_item.clone()),
+                    ),
+                    (
+                        ext_end.clone(),
+                        item(&id1, 2, child_item(next(meta, c.old_ref))),
+                    ),
+                    (old_leaf, item(&id1, 2, old_value.clone())),
+                    (other_leaf, item(&id1, 2, other_value.clone())),
+                ]),
+                select(vec![
+                    (
+                        ext_end.clone() + split_ext_end.clone(),
+                        item(&id2, 1, n_path.clone()),
+                    ),
+                    (new_leaf.clone() + split_leaf.clone(), item(&id2, 1, n_path)),
+                ]),
+                select(vec![
+                    (
+                        q_enable * (k.branch + k.split_branch),
+                        item(&id2, 17, empty_item),
+                    ),
+                    (ext_end, item(&id2, 2, child_item(next(meta, c.new_ref)))),
+                    (
+                        split_ext_end,
+                        item(&id2, 2, child_item(next(meta, c.hashes[1]))),
+                    ),
+                    (new_leaf, item(&id2, 2, new_value.clone())),
+                    (
+                        split_leaf.clone(),
+                        item(
+                            &id2,
+                            2,
+                            [
+                                new_value[0].clone()
+                                    + old_present.clone()
+                                        * (old_value[0].clone() - new_value[0].clone()),
+                                new_value[1].clone()
+                                    + old_present * (old_value[1].clone() - new_value[1].clone()),
+                            ],
+                        ),
+                    ),
+                ]),
+                select(vec![(split_leaf.clone(), item(&id3, 1, o2_path))]),
+                select(vec![(split_leaf, item(&id3, 2, other_value))]),
+            ]
+        };
+        for idx in 0..6 {
+            meta.lookup_any("mpt node item", |meta| {
+                node_items(meta)
+                    .swap_remove(idx)
+                    .into_iter()
+                    .zip(c.rlp.item_rlc_len_table_exprs(meta))
+                    .collect()
+            });
+        }
+
+        // The nodes are the RLP streams of their ids, with 17 items for
+        // branch nodes and 2 items otherwise.
+        let node_streams = |meta: &mut VirtualCells<F>| {
+            let k = KindQueries::new(meta, c.kinds, Rotation::cur());
+            let old_leaf = k.leaf.clone() * cur(meta, c.old.present);
+            let new_leaf = k.leaf * cur(meta, c.new.present);
+            let nodes = k.branch.clone() + k.ext + k.split();
+            [
+                (
+                    nodes.clone() + old_leaf + k.other,
+                    2.expr() + 15.expr() * k.branch.clone(),
+                ),
+                (
+                    nodes + new_leaf,
+                    2.expr() + 15.expr() * (k.branch + k.split_branch),
+                ),
+                (k.split_leaf, 2.expr()),
+            ]
+        };
+        for idx in 0..3 {
+            meta.lookup_any("mpt node hash", |meta| {
+                let q_enable = fixed(meta, q_enable);
+                let (active, _) = node_streams(meta)[idx].clone();
+                let enable = q_enable * active;
+                [cur(meta, c.ids[idx]), cur(meta, c.hashes[idx])]
+                    .into_iter()
+                    .map(|expr| enable.clone() * expr)
+                    .zip(c.rlp.hash_table_exprs(meta))
+                    .collect()
+            });
+            meta.lookup_any("mpt node end", |meta| {
+                let q_enable = fixed(meta, q_enable);
+                let (active, n_items) = node_streams(meta)[idx].clone();
+                let enable = q_enable * active;
+                [cur(meta, c.ids[idx]), n_items]
+                    .into_iter()
+                    .map(|expr| enable.clone() * expr)
+                    .zip(c.rlp.end_table_exprs(meta))
+                    .collect()
+            });
+        }
+
+        // The values of the leaves of the state trie are the RLP streams of
+        // the accounts, which are looked up on the first row of the walk
+        // before the update, and on the second row after the update.
+        let account_fields = |meta: &mut VirtualCells<F>| {
+            let is_account = cur(meta, c.q_update) - cur(meta, c.is_storage);
+            let old = fixed(meta, c.q_first) * is_account.clone() * cur(meta, c.old.present);
+            let new = fixed(meta, c.q_second) * is_account * cur(meta, c.new.present);
+            let mut field = |old_column, new_column| {
+                old.clone() * meta.query_advice(old_column, Rotation::cur())
+                    + new.clone() * meta.query_advice(new_column, Rotation::cur())
+            };
+            let id = field(c.old.account_id, c.new.account_id);
+            let rlc = field(c.old.vrlc, c.new.vrlc);
+            let len = field(c.old.vlen, c.new.vlen);
+            let nonce = field(c.old.nonce, c.new.nonce);
+            let balance = field(c.old.balance, c.new.balance);
+            let storage_root = field(c.old.storage_root, c.new.storage_root);
+            let code_hash = field(c.old.stored_code_hash, c.new.stored_code_hash);
+            let enable = old + new;
+            [
+                id,
+                rlc,
+                len,
+                nonce,
+                balance,
+                storage_root,
+                code_hash,
+                enable,
+            ]
+        };
+        meta.lookup_any("mpt account stream rlc", |meta| {
+            let [id, rlc, ..] = account_fields(meta);
+            vec![id, rlc]
+                .into_iter()
+                .zip(c.rlp.stream_rlc_table_exprs(meta))
+                .collect()
+        });
+        meta.lookup_any("mpt account stream length", |meta| {
+            let [id, _, len, ..] = account_fields(meta);
+            vec![id, len]
+                .into_iter()
+                .zip(c.rlp.stream_len_table_exprs(meta))
+                .collect()
+        });
+        meta.lookup_any("mpt account items", |meta| {
+            let [id, .., enable] = account_fields(meta);
+            vec![id, 4.expr() * enable]
+                .into_iter()
+                .zip(c.rlp.end_table_exprs(meta))
+                .collect()
+        });
+        meta.lookup_any("mpt account nonce", |meta| {
+            let [id, _, _, nonce, .., enable] = account_fields(meta);
+            vec![id, enable, nonce]
+                .into_iter()
+                .zip(c.rlp.value_table_exprs(meta))
+                .collect()
+        });
+        for (item, name) in [
+            (2, "mpt account balance"),
+            (3, "mpt account storage root"),
+            (4, "mpt account code hash"),
+        ] {
+            meta.lookup_any(name, |meta| {
+                let [id, _, _, _, balance, storage_root, code_hash, enable] = account_fields(meta);
+                let field = [balance, storage_root, code_hash][item - 2].clone();
+                vec![id, item.expr() * enable, field]
+                    .into_iter()
+                    .zip(c.rlp.value_rlc_table_exprs(meta))
+                    .collect()
+            });
+        }
+
+        config
+    }
+
+    /// Assign the walks of `witness` into `max_walks` walks followed by
+    /// padding, and their trie nodes into `max_rlp_rows` rows of the RLP
+    /// circuit, and return the cells of the state roots before and after the
+    /// walks.
+    pub fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        witness: &MptWitness<F>,
+        max_walks: usize,
+        max_rlp_rows: usize,
+        randomness: F,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        if max_walks == 0 || witness.walks.len() > max_walks {
+            error!(
+                "MPT circuit needs {} walks but only has {}",
+                witness.walks.len(),
+                max_walks
+            );
+            return Err(Error::Synthesis);
+        }
+        self.rlp.load(layouter)?;
+        self.rlp
+            .assign(layouter, max_rlp_rows, &witness.streams, randomness)?;
+
+        layouter.assign_region(
+            || "mpt tables",
+            |mut region| {
+                let mut pow = F::one();
+                for offset in 0..256 {
+                    for (column, value) in [
+                        (self.u8_table, offset as u64),
+                        (self.nibble_table, offset as u64 % 16),
+                        (self.q_pow, (offset <= 32) as u64),
+                        (self.q_pow_first, (offset == 0) as u64),
+                        (self.q_pow_next, (offset < 32) as u64),
+                    ] {
+                        region.assign_fixed(
+                            || format!("mpt tables {}", offset),
+                            column,
+                            offset,
+                            || Ok(F::from(value)),
+                        )?;
+                    }
+                    if offset <= 32 {
+                        region.assign_advice(
+                            || format!("mpt pow {}", offset),
+                            self.pow,
+                            offset,
+                            || Ok(pow),
+                        )?;
+                        pow *= randomness;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+
+        let padding = Walk::padding(witness.state_root_rlc);
+        layouter.assign_region(
+            || "mpt walks",
+            |mut region| {
+                let mut state_roots = Vec::with_capacity(max_walks);
+                for idx in 0..max_walks {
+                    let walk = witness.walks.get(idx).unwrap_or(&padding);
+                    state_roots.push(self.assign_walk(
+                        &mut region,
+                        idx * WALK_ROWS,
+                        idx == 0,
+                        idx + 1 == max_walks,
+                        walk,
+                    )?);
+                }
+                let (prev_state_root, _) = state_roots[0].clone();
+                let (_, state_root) = state_roots[max_walks - 1].clone();
+                Ok((prev_state_root, state_root))
+            },
+        )
+    }
+
+    fn assign_walk(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        is_first_walk: bool,
+        is_last_walk: bool,
+        walk: &Walk<F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let values = &walk.values;
+        let field_tag = match values.field {
+            Some(AccountField::Nonce) => 1,
+            Some(AccountField::Balance) => 2,
+            Some(AccountField::CodeHash) => 3,
+            _ => 0,
+        };
+        let is_update = values.q_update && values.field != Some(AccountField::StorageRoot);
+        let mut prev_state_root = None;
+        let mut state_root = None;
+
+        for (idx, row) in walk.rows.iter().enumerate() {
+            let offset = offset + idx;
+            for (column, value) in [
+                (self.q_first, idx == 0),
+                (self.q_second, idx == 1),
+                (self.q_last, idx + 1 == WALK_ROWS),
+                (self.q_next, idx + 1 < WALK_ROWS),
+                (self.q_odd, idx % 2 == 1),
+                (self.q_pre20, idx < 20),
+                (self.q_end20, idx == 19),
+                (self.q_pre32, idx < 32),
+                (self.q_end32, idx == 31),
+                (self.q_first_block, is_first_walk && idx == 0),
+                (self.q_last_block, is_last_walk && idx + 1 == WALK_ROWS),
+            ] {
+                region.assign_fixed(
+                    || format!("mpt selector {}", offset),
+                    column,
+                    offset,
+                    || Ok(F::from(value as u64)),
+                )?;
+            }
+
+            let old_state_root = region.assign_advice(
+                || format!("mpt old_state_root {}", offset),
+                self.old_state_root,
+                offset,
+                || Ok(values.old_state_root),
+            )?;
+            let new_state_root = region.assign_advice(
+                || format!("mpt new_state_root {}", offset),
+                self.new_state_root,
+                offset,
+                || Ok(values.new_state_root),
+            )?;
+            if idx == 0 {
+                prev_state_root = Some(old_state_root);
+            }
+            state_root = Some(new_state_root);
+
+            let bool_value = |value: bool| F::from(value as u64);
+            let field_selectors = [
+                AccountField::Nonce,
+                AccountField::Balance,
+                AccountField::CodeHash,
+                AccountField::StorageRoot,
+            ]
+            .map(|field| (values.field == Some(field)) as u64);
+            let kinds = [
+                NodeKind::Branch,
+                NodeKind::Extension,
+                NodeKind::Leaf,
+                NodeKind::Empty,
+                NodeKind::Other,
+                NodeKind::SplitExtension,
+                NodeKind::SplitBranch,
+                NodeKind::SplitLeaf,
+            ]
+            .map(|kind| row.kind == Some(kind));
+            let mut nibble_bits = [false; 16];
+            if values.q_update {
+                nibble_bits[row.nibble as usize] = true;
+            }
+            let advices = [
+                (self.q_update, bool_value(values.q_update)),
+                (self.is_storage, bool_value(values.is_storage)),
+                (self.address, values.address),
+                (self.storage_key, values.storage_key),
+                (self.old_value, values.old_value),
+                (self.new_value, values.new_value),
+                (self.old_root, values.old_root),
+                (self.new_root, values.new_root),
+                (self.key_input, values.key_input),
+                (self.other_vrlc, values.other_vrlc),
+                (self.other_vlen, F::from(values.other_vlen)),
+                (self.other_nibble, F::from(row.other_nibble as u64)),
+                (self.pre_byte, F::from(row.pre_byte as u64)),
+                (self.pre_rlc, row.pre_rlc),
+                (self.pre_value, row.pre_value),
+                (self.key_rlc, row.key_rlc),
+                (self.is_high, bool_value(row.is_high)),
+                (self.n_rlc, row.n_rlc),
+                (self.n_len, F::from(row.n_len)),
+                (self.o_rlc, row.o_rlc),
+                (self.o_len, F::from(row.o_len)),
+                (self.o2_rlc, row.o2_rlc),
+                (self.o2_len, F::from(row.o2_len)),
+                (self.other_inv, row.other_inv),
+                (self.old_ref, row.old_ref),
+                (self.new_ref, row.new_ref),
+                (self.old_empty, bool_value(row.old_empty)),
+                (self.new_empty, bool_value(row.new_empty)),
+            ]
+            .into_iter()
+            .chain(
+                self.field_selectors
+                    .into_iter()
+                    .zip(field_selectors.map(F::from)),
+            )
+            .chain(
+                self.nibble_bits
+                    .into_iter()
+                    .zip(nibble_bits.map(bool_value)),
+            )
+            .chain(self.kinds.into_iter().zip(kinds.map(bool_value)))
+            .chain(self.ids.into_iter().zip(row.ids.map(F::from)))
+            .chain(self.hashes.into_iter().zip(row.hashes))
+            .chain(self.children.into_iter().zip(row.children))
+            .chain(self.flags.into_iter().zip(row.flags.map(bool_value)));
+            for (column, value) in advices {
+                region.assign_advice(
+                    || format!("mpt walk {}", offset),
+                    column,
+                    offset,
+                    || Ok(value),
+                )?;
+            }
+            self.old.assign(region, offset, &values.old)?;
+            self.new.assign(region, offset, &values.new)?;
+
+            let table_row = if is_update && idx == 0 {
+                [
+                    F::one(),
+                    values.address,
+                    values.storage_key,
+                    F::from(field_tag),
+                    values.old_value,
+                    values.new_value,
+                ]
+            } else {
+                [F::zero(); 6]
+            };
+            self.mpt_table.assign(region, offset, table_row)?;
+        }
+
+        Ok((prev_state_root.unwrap(), state_root.unwrap()))
+    }
+}
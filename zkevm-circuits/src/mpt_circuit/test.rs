@@ -0,0 +1,396 @@
+use super::{MptConfig, MptTable, MptUpdates, MptWitness};
+use crate::{
+    evm_circuit::{
+        table::{AccountFieldTag, RwTableTag},
+        util::RandomLinearCombination,
+        witness::{Rw, RwMap},
+    },
+    keccak_circuit::KeccakTable,
+    util::Expr,
+};
+use bus_mapping::mpt::{encode_account, encode_storage_value, trie::Trie, EMPTY_ROOT};
+use eth_types::{address, Address, Field, ToBigEndian, ToLittleEndian, ToWord, Word, H256, U256};
+use ethers_core::utils::keccak256;
+use halo2_proofs::{
+    arithmetic::BaseExt,
+    circuit::{Layouter, SimpleFloorPlanner},
+    dev::{MockProver, VerifyFailure},
+    pairing::bn256::Fr,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+    poly::Rotation,
+};
+use keccak256::EMPTY_HASH;
+use pretty_assertions::assert_eq;
+use std::collections::HashMap;
+
+const K: u32 = 12;
+const MAX_WALKS: usize = 16;
+const MAX_RLP_ROWS: usize = 2000;
+
+#[derive(Default)]
+struct MptTestCircuit<F> {
+    witness: MptWitness<F>,
+    randomness: F,
+}
+
+impl<F: Field> Circuit<F> for MptTestCircuit<F> {
+    type Config = (MptConfig<F>, KeccakTable, Column<Instance>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let keccak_table = KeccakTable::construct(meta);
+        let mpt_table = MptTable::construct(meta);
+        let power_of_randomness = {
+            let columns = [(); 31].map(|_| meta.instance_column());
+            let mut power_of_randomness = None;
+            meta.create_gate("power of randomness", |meta| {
+                power_of_randomness =
+                    Some(columns.map(|column| meta.query_instance(column, Rotation::cur())));
+                [0.expr()]
+            });
+            power_of_randomness.unwrap()
+        };
+        let state_roots = meta.instance_column();
+        meta.enable_equality(state_roots);
+
+        (
+            MptConfig::configure(meta, power_of_randomness, keccak_table, mpt_table),
+            keccak_table,
+            state_roots,
+        )
+    }
+
+    fn synthesize(
+        &self,
+        (config, keccak_table, state_roots): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        keccak_table.dev_load(
+            &mut layouter,
+            &self.witness.keccak_inputs(),
+            self.randomness,
+        )?;
+        let (prev_state_root, state_root) = config.assign(
+            &mut layouter,
+            &self.witness,
+            MAX_WALKS,
+            MAX_RLP_ROWS,
+            self.randomness,
+        )?;
+        layouter.constrain_instance(prev_state_root.cell(), state_roots, 0)?;
+        layouter.constrain_instance(state_root.cell(), state_roots, 1)
+    }
+}
+
+fn word_rlc(word: Word, randomness: Fr) -> Fr {
+    RandomLinearCombination::random_linear_combine(word.to_le_bytes(), randomness)
+}
+
+fn verify(
+    witness: MptWitness<Fr>,
+    randomness: Fr,
+    prev_state_root: Word,
+    state_root: Word,
+) -> Result<(), Vec<VerifyFailure>> {
+    let n_rows = (1 << K) - 64;
+    let mut instance: Vec<Vec<Fr>> = (1..32)
+        .map(|exp| vec![randomness.pow(&[exp, 0, 0, 0]); n_rows])
+        .collect();
+    instance.push(vec![
+        word_rlc(prev_state_root, randomness),
+        word_rlc(state_root, randomness),
+    ]);
+    let circuit = MptTestCircuit {
+        witness,
+        randomness,
+    };
+    MockProver::<Fr>::run(K, &circuit, instance)
+        .unwrap()
+        .verify()
+}
+
+/// An account with its storage, as in the state trie
+#[derive(Default)]
+struct Account {
+    address: Address,
+    nonce: u64,
+    balance: u64,
+    storage: Vec<(u64, u64)>,
+}
+
+/// Build the state trie of `accounts`, and return its root and its nodes.
+fn state_trie(accounts: &[Account]) -> (Word, Vec<Vec<u8>>) {
+    let mut trie = Trie::default();
+    let mut state_root = *EMPTY_ROOT;
+    for account in accounts {
+        let mut storage_root = *EMPTY_ROOT;
+        for (key, value) in account.storage.iter() {
+            storage_root = trie
+                .insert(
+                    storage_root,
+                    &H256(keccak256(Word::from(*key).to_be_bytes())),
+                    encode_storage_value(Word::from(*value)),
+                )
+                .unwrap();
+        }
+        state_root = trie
+            .insert(
+                state_root,
+                &H256(keccak256(account.address.as_bytes())),
+                encode_account(
+                    Word::from(account.nonce),
+                    Word::from(account.balance),
+                    storage_root,
+                    H256(*EMPTY_HASH),
+                ),
+            )
+            .unwrap();
+    }
+    (
+        state_root.to_word(),
+        trie.nodes().map(<[u8]>::to_vec).collect(),
+    )
+}
+
+fn alice() -> Address {
+    address!("0x00000000000000000000000000000000000cafe0")
+}
+
+fn bob() -> Address {
+    address!("0x00000000000000000000000000000000000b0b00")
+}
+
+fn carol() -> Address {
+    address!("0x00000000000000000000000000000000000ca201")
+}
+
+fn dave() -> Address {
+    address!("0x00000000000000000000000000000000000da7e0")
+}
+
+fn balance_write(rw_counter: usize, address: Address, value_prev: u64, value: u64) -> Rw {
+    Rw::Account {
+        rw_counter,
+        is_write: true,
+        account_address: address,
+        field_tag: AccountFieldTag::Balance,
+        value: U256::from(value),
+        value_prev: U256::from(value_prev),
+    }
+}
+
+fn storage_write(rw_counter: usize, key: u64, value_prev: u64, value: u64) -> Rw {
+    Rw::AccountStorage {
+        rw_counter,
+        is_write: true,
+        account_address: alice(),
+        storage_key: U256::from(key),
+        value: U256::from(value),
+        value_prev: U256::from(value_prev),
+        tx_id: 1,
+        committed_value: U256::from(value_prev),
+    }
+}
+
+/// Return the state trie before and after the updates of `rows`, which
+/// modify and insert accounts and storage slots, and read an absent account.
+fn updates() -> (Vec<Rw>, [Account; 2], [Account; 3]) {
+    let rows = vec![
+        balance_write(1, alice(), 100, 90),
+        Rw::Account {
+            rw_counter: 2,
+            is_write: false,
+            account_address: alice(),
+            field_tag: AccountFieldTag::Nonce,
+            value: U256::one(),
+            value_prev: U256::one(),
+        },
+        storage_write(3, 1, 5, 6),
+        storage_write(4, 3, 0, 7),
+        Rw::Account {
+            rw_counter: 5,
+            is_write: false,
+            account_address: carol(),
+            field_tag: AccountFieldTag::Balance,
+            value: U256::zero(),
+            value_prev: U256::zero(),
+        },
+        balance_write(6, dave(), 0, 10),
+        balance_write(7, bob(), 7, 8),
+    ];
+    let prev_accounts = [
+        Account {
+            address: alice(),
+            nonce: 1,
+            balance: 100,
+            storage: vec![(1, 5)],
+        },
+        Account {
+            address: bob(),
+            balance: 7,
+            ..Default::default()
+        },
+    ];
+    let accounts = [
+        Account {
+            address: alice(),
+            nonce: 1,
+            balance: 90,
+            storage: vec![(1, 6), (3, 7)],
+        },
+        Account {
+            address: bob(),
+            balance: 8,
+            ..Default::default()
+        },
+        Account {
+            address: dave(),
+            balance: 10,
+            ..Default::default()
+        },
+    ];
+    (rows, prev_accounts, accounts)
+}
+
+#[test]
+fn mpt_updates_of_first_and_last_accesses() {
+    let address = alice();
+    let rw_map = RwMap(HashMap::from([
+        (
+            RwTableTag::Account,
+            vec![
+                balance_write(1, address, 100, 90),
+                balance_write(5, address, 90, 80),
+                Rw::Account {
+                    rw_counter: 3,
+                    is_write: false,
+                    account_address: address,
+                    field_tag: AccountFieldTag::Nonce,
+                    value: U256::from(1),
+                    value_prev: U256::from(1),
+                },
+            ],
+        ),
+        (RwTableTag::AccountStorage, vec![storage_write(2, 3, 0, 7)]),
+    ]));
+    let updates = MptUpdates::new(&rw_map.0.values().flatten().cloned().collect::<Vec<_>>());
+    assert_eq!(updates.len(), 3);
+
+    let randomness = Fr::rand();
+    let update = updates.get(&balance_write(0, address, 0, 0)).unwrap();
+    let rlc = |value: u64| word_rlc(U256::from(value), randomness);
+    assert_eq!(update.old_value_assignment(randomness), rlc(100));
+    assert_eq!(update.new_value_assignment(randomness), rlc(80));
+}
+
+#[test]
+fn mpt_circuit_valid() {
+    let (rows, prev_accounts, accounts) = updates();
+    let (prev_state_root, trie_nodes) = state_trie(&prev_accounts);
+    let (state_root, _) = state_trie(&accounts);
+
+    let randomness = Fr::rand();
+    let witness = MptWitness::new(
+        &MptUpdates::new(&rows),
+        &trie_nodes,
+        prev_state_root,
+        randomness,
+    )
+    .unwrap();
+    assert_eq!(witness.state_root(), state_root);
+    assert_eq!(
+        verify(witness, randomness, prev_state_root, state_root),
+        Ok(())
+    );
+}
+
+#[test]
+fn mpt_circuit_no_updates() {
+    let (_, prev_accounts, _) = updates();
+    let (prev_state_root, trie_nodes) = state_trie(&prev_accounts);
+
+    let randomness = Fr::rand();
+    let witness = MptWitness::new(
+        &MptUpdates::default(),
+        &trie_nodes,
+        prev_state_root,
+        randomness,
+    )
+    .unwrap();
+    assert_eq!(
+        verify(witness, randomness, prev_state_root, prev_state_root),
+        Ok(())
+    );
+}
+
+#[test]
+fn mpt_circuit_wrong_state_root() {
+    let (rows, prev_accounts, accounts) = updates();
+    let (prev_state_root, trie_nodes) = state_trie(&prev_accounts);
+    let (state_root, _) = state_trie(&accounts);
+
+    let randomness = Fr::rand();
+    let witness = MptWitness::new(
+        &MptUpdates::new(&rows),
+        &trie_nodes,
+        prev_state_root,
+        randomness,
+    )
+    .unwrap();
+    assert!(verify(
+        witness.clone(),
+        randomness,
+        prev_state_root,
+        prev_state_root
+    )
+    .is_err());
+    assert!(verify(witness, randomness, state_root, state_root).is_err());
+}
+
+#[test]
+fn mpt_circuit_wrong_value() {
+    let (rows, prev_accounts, accounts) = updates();
+    let (prev_state_root, trie_nodes) = state_trie(&prev_accounts);
+    let (state_root, _) = state_trie(&accounts);
+
+    let randomness = Fr::rand();
+    let witness = MptWitness::new(
+        &MptUpdates::new(&rows),
+        &trie_nodes,
+        prev_state_root,
+        randomness,
+    )
+    .unwrap();
+    for idx in 0..witness.walks.len() {
+        let mut witness = witness.clone();
+        let values = &mut witness.walks[idx].values;
+        values.new_value += Fr::one();
+        assert!(verify(witness, randomness, prev_state_root, state_root).is_err());
+    }
+}
+
+#[test]
+fn mpt_circuit_missing_trie_node() {
+    let (rows, prev_accounts, _) = updates();
+    let (prev_state_root, mut trie_nodes) = state_trie(&prev_accounts);
+    trie_nodes.retain(|node| H256(keccak256(node)).to_word() != prev_state_root);
+
+    assert!(MptWitness::new(
+        &MptUpdates::new(&rows),
+        &trie_nodes,
+        prev_state_root,
+        Fr::rand(),
+    )
+    .is_err());
+}
+
+#[test]
+fn degree() {
+    let mut meta = ConstraintSystem::<Fr>::default();
+    MptTestCircuit::<Fr>::configure(&mut meta);
+    assert!(meta.degree() <= 9);
+}
@@ -0,0 +1,867 @@
+//! The witness of the MPT circuit, which walks down the state trie and the
+//! storage tries along the hashed keys of the updates, with one row per
+//! nibble of the key.
+
+use super::{MptUpdate, MptUpdates};
+use crate::evm_circuit::{table::AccountFieldTag, util::RandomLinearCombination, witness::Rw};
+use bus_mapping::mpt::{
+    encode_account, encode_storage_value,
+    trie::{key_nibbles, Node, PathEnd, Trie, TrieUpdate},
+    EMPTY_ROOT,
+};
+use eth_types::{Address, Field, ToBigEndian, ToLittleEndian, ToScalar, ToWord, Word, H256};
+use ethers_core::utils::keccak256;
+use halo2_proofs::plonk::Error;
+use keccak256::EMPTY_HASH;
+use log::error;
+use rlp::Rlp;
+use std::collections::HashMap;
+
+/// Number of rows of a walk, one per nibble of the hashed keys
+pub(crate) const WALK_ROWS: usize = 64;
+
+/// Number of kinds of nodes a row of a walk can belong to
+pub(crate) const N_NODE_KINDS: usize = 8;
+
+/// Kind of the node that a row of a walk belongs to, which is also the index
+/// of its column in the MPT circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum NodeKind {
+    /// Branch node of the path, on the row of the nibble of its child
+    Branch,
+    /// Extension node of the path, on the rows of the nibbles of its path
+    Extension,
+    /// Leaf of the key before and/or after the update
+    Leaf,
+    /// Empty slot, where the key is absent before and after the update
+    Empty,
+    /// Leaf of another key, where the key is absent before and after the
+    /// update
+    Other,
+    /// Extension node above the branch node of a split
+    SplitExtension,
+    /// Branch node of a split, with the leaf of the key and the leaf of the
+    /// other key as children
+    SplitBranch,
+    /// Leaf of the key and the leaf of the other key after a split
+    SplitLeaf,
+}
+
+impl NodeKind {
+    /// Return whether the nibbles of the rows are in the path of the node.
+    pub(crate) fn has_path(&self) -> bool {
+        matches!(
+            self,
+            Self::Extension | Self::Leaf | Self::Other | Self::SplitExtension | Self::SplitLeaf
+        )
+    }
+
+    /// Return whether the path of the node is the path of a leaf.
+    pub(crate) fn is_leaf(&self) -> bool {
+        matches!(self, Self::Leaf | Self::Other | Self::SplitLeaf)
+    }
+}
+
+/// Account field updated by a walk of the state trie, which is also the index
+/// of its selector column in the MPT circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum AccountField {
+    Nonce,
+    Balance,
+    CodeHash,
+    StorageRoot,
+}
+
+/// Fields of an account as stored in the leaves of the state trie
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Account {
+    nonce: Word,
+    balance: Word,
+    storage_root: H256,
+    code_hash: H256,
+}
+
+impl Default for Account {
+    fn default() -> Self {
+        Self {
+            nonce: Word::zero(),
+            balance: Word::zero(),
+            storage_root: *EMPTY_ROOT,
+            code_hash: H256(*EMPTY_HASH),
+        }
+    }
+}
+
+impl Account {
+    fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        let rlp = Rlp::new(bytes);
+        let item = |idx| {
+            rlp.at(idx)
+                .and_then(|item| item.data().map(<[u8]>::to_vec))
+                .map_err(|err| {
+                    error!("invalid account in the state trie: {:?}", err);
+                    Error::Synthesis
+                })
+        };
+        let hash = |bytes: Vec<u8>| {
+            if bytes.len() == 32 {
+                Ok(H256::from_slice(&bytes))
+            } else {
+                error!("invalid hash of an account in the state trie");
+                Err(Error::Synthesis)
+            }
+        };
+        Ok(Self {
+            nonce: Word::from_big_endian(&item(0)?),
+            balance: Word::from_big_endian(&item(1)?),
+            storage_root: hash(item(2)?)?,
+            code_hash: hash(item(3)?)?,
+        })
+    }
+
+    /// Return the RLP encoding of the account, or `None` if it is empty and
+    /// so absent from the state trie.
+    fn encode(&self) -> Option<Vec<u8>> {
+        (*self != Self::default())
+            .then(|| encode_account(self.nonce, self.balance, self.storage_root, self.code_hash))
+    }
+}
+
+/// Values of the leaf of the key of a walk before or after the update
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct LeafValues<F> {
+    /// Whether the key is in the trie
+    pub present: bool,
+    /// Value which is zero if and only if the key is absent
+    pub probe: F,
+    /// RLC of the value of the leaf
+    pub vrlc: F,
+    /// Length of the value of the leaf
+    pub vlen: u64,
+    /// Power of randomness of the header byte of a storage value
+    pub pow: F,
+    /// Nonce of the account
+    pub nonce: F,
+    /// RLC encoded balance of the account
+    pub balance: F,
+    /// RLC encoded storage root of the account
+    pub storage_root: F,
+    /// RLC encoded code hash of the account as in the rw table, which is 0
+    /// for the empty code hash of absent accounts
+    pub code_hash: F,
+    /// RLC encoded code hash of the account as stored in the state trie
+    pub stored_code_hash: F,
+    /// Id of the RLP stream of the account
+    pub account_id: u64,
+}
+
+impl<F: Field> LeafValues<F> {
+    /// Return the value of `field` of the account.
+    pub(crate) fn field(&self, field: AccountField) -> F {
+        match field {
+            AccountField::Nonce => self.nonce,
+            AccountField::Balance => self.balance,
+            AccountField::CodeHash => self.code_hash,
+            AccountField::StorageRoot => self.storage_root,
+        }
+    }
+}
+
+/// Values of a walk, which are the same on all its rows
+#[derive(Clone, Debug, Default)]
+pub(crate) struct WalkValues<F> {
+    /// Whether the walk is an update, or padding otherwise
+    pub q_update: bool,
+    /// Whether the walk is in a storage trie
+    pub is_storage: bool,
+    /// Account address
+    pub address: F,
+    /// RLC encoded storage key
+    pub storage_key: F,
+    /// Value before the update, as in the MPT table
+    pub old_value: F,
+    /// Value after the update, as in the MPT table
+    pub new_value: F,
+    /// Updated account field of a walk of the state trie
+    pub field: Option<AccountField>,
+    /// RLC encoded root of the walked trie before the update
+    pub old_root: F,
+    /// RLC encoded root of the walked trie after the update
+    pub new_root: F,
+    /// RLC encoded state root before the update
+    pub old_state_root: F,
+    /// RLC encoded state root after the update
+    pub new_state_root: F,
+    /// Leaf of the key before the update
+    pub old: LeafValues<F>,
+    /// Leaf of the key after the update
+    pub new: LeafValues<F>,
+    /// RLC of the preimage of the hashed key
+    pub key_input: F,
+    /// RLC of the value of the leaf of another key
+    pub other_vrlc: F,
+    /// Length of the value of the leaf of another key
+    pub other_vlen: u64,
+}
+
+/// A row of a walk
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MptRow<F> {
+    pub kind: Option<NodeKind>,
+    pub nibble: u8,
+    pub other_nibble: u8,
+    pub pre_byte: u8,
+    pub pre_rlc: F,
+    pub pre_value: F,
+    pub key_rlc: F,
+    pub is_high: bool,
+    pub n_rlc: F,
+    pub n_len: u64,
+    pub o_rlc: F,
+    pub o_len: u64,
+    pub o2_rlc: F,
+    pub o2_len: u64,
+    pub other_inv: F,
+    pub ids: [u64; 3],
+    pub hashes: [F; 3],
+    pub old_ref: F,
+    pub new_ref: F,
+    pub old_empty: bool,
+    pub new_empty: bool,
+    pub children: [F; 16],
+    pub flags: [bool; 16],
+}
+
+/// A walk down a trie along the hashed key of an update
+#[derive(Clone, Debug)]
+pub(crate) struct Walk<F> {
+    pub values: WalkValues<F>,
+    pub rows: Vec<MptRow<F>>,
+}
+
+impl<F: Field> Walk<F> {
+    /// Return a padding walk, which keeps the state root.
+    pub(crate) fn padding(state_root: F) -> Self {
+        Self {
+            values: WalkValues {
+                old_state_root: state_root,
+                new_state_root: state_root,
+                ..Default::default()
+            },
+            rows: vec![MptRow::default(); WALK_ROWS],
+        }
+    }
+}
+
+/// Witness of the MPT circuit, made of a walk for each update, followed by a
+/// walk of the state trie to update the storage root of the account after
+/// each storage update.
+#[derive(Clone, Debug, Default)]
+pub struct MptWitness<F> {
+    pub(crate) walks: Vec<Walk<F>>,
+    /// RLP streams of the nodes and the accounts, with ids 1, 2, ...
+    pub(crate) streams: Vec<Vec<u8>>,
+    key_preimages: Vec<Vec<u8>>,
+    state_root: Word,
+    pub(crate) state_root_rlc: F,
+}
+
+impl<F: Field> MptWitness<F> {
+    /// Compute the walks of `updates` from `prev_state_root`, with the trie
+    /// nodes of their paths in `trie_nodes`.
+    pub fn new(
+        updates: &MptUpdates,
+        trie_nodes: &[Vec<u8>],
+        prev_state_root: Word,
+        randomness: F,
+    ) -> Result<Self, Error> {
+        let mut builder = WitnessBuilder {
+            trie: Trie::new(trie_nodes.iter().map(Vec::as_slice)),
+            state_root: H256(prev_state_root.to_be_bytes()),
+            randomness,
+            stream_ids: HashMap::new(),
+            witness: Self::default(),
+        };
+        for update in updates.iter() {
+            builder.update(update)?;
+        }
+        let mut witness = builder.witness;
+        witness.state_root = builder.state_root.to_word();
+        witness.state_root_rlc = word_rlc(witness.state_root, randomness);
+        Ok(witness)
+    }
+
+    /// Return the state root after the updates.
+    pub fn state_root(&self) -> Word {
+        self.state_root
+    }
+
+    /// Return the inputs of the keccak hashes looked up by the MPT circuit,
+    /// which are the RLP streams and the preimages of the keys.
+    pub fn keccak_inputs(&self) -> Vec<Vec<u8>> {
+        self.streams
+            .iter()
+            .chain(&self.key_preimages)
+            .cloned()
+            .collect()
+    }
+}
+
+fn word_rlc<F: Field>(word: Word, randomness: F) -> F {
+    RandomLinearCombination::random_linear_combine(word.to_le_bytes(), randomness)
+}
+
+/// Return the RLC of `bytes` with the last byte multiplied by the lowest power
+/// of randomness, as the `value_rlc` of the RLP circuit.
+fn bytes_rlc<F: Field>(bytes: &[u8], randomness: F) -> F {
+    bytes.iter().fold(F::zero(), |acc, byte| {
+        acc * randomness + F::from(*byte as u64)
+    })
+}
+
+fn trie_error(err: bus_mapping::Error) -> Error {
+    error!("failed to update the trie: {:?}", err);
+    Error::Synthesis
+}
+
+/// Set the rows of a segment of a walk which belongs to a node, where the
+/// nibbles of the paths are paired from the last one, so that the last
+/// nibble of a segment is a low nibble.
+fn set_segment<F: Field>(rows: &mut [MptRow<F>], kind: NodeKind, ids: [u64; 3], hashes: [F; 3]) {
+    let len = rows.len();
+    for (idx, row) in rows.iter_mut().enumerate() {
+        row.kind = Some(kind);
+        row.ids = ids;
+        row.hashes = hashes;
+        row.is_high = (len - 1 - idx) % 2 == 1;
+    }
+}
+
+/// Return the RLC and the length of the hex prefix encoded path of a leaf
+/// after the nibble of a row, which is a high nibble on even rows, from the
+/// RLC, the length and the nibble of the previous row, or `None` on the first
+/// row of the path.
+fn leaf_path_step<F: Field>(
+    prev: Option<(F, u64, u8)>,
+    nibble: u8,
+    is_odd: bool,
+    randomness: F,
+) -> (F, u64) {
+    match prev {
+        None if is_odd => (F::from(0x30 + nibble as u64), 1),
+        None => (F::from(0x20), 1),
+        Some((rlc, len, prev_nibble)) if is_odd => (
+            rlc * randomness + F::from(16 * prev_nibble as u64 + nibble as u64),
+            len + 1,
+        ),
+        Some((rlc, len, _)) => (rlc, len),
+    }
+}
+
+struct WitnessBuilder<F> {
+    trie: Trie,
+    state_root: H256,
+    randomness: F,
+    stream_ids: HashMap<Vec<u8>, u64>,
+    witness: MptWitness<F>,
+}
+
+impl<F: Field> WitnessBuilder<F> {
+    fn hash_rlc(&self, hash: H256) -> F {
+        word_rlc(hash.to_word(), self.randomness)
+    }
+
+    /// Return the id of the RLP stream `bytes`, which is added to the streams
+    /// if it is new.
+    fn stream_id(&mut self, bytes: Vec<u8>) -> u64 {
+        let streams = &mut self.witness.streams;
+        *self.stream_ids.entry(bytes).or_insert_with_key(|bytes| {
+            streams.push(bytes.clone());
+            streams.len() as u64
+        })
+    }
+
+    /// Return the stream id and the RLC encoded hash of `node`.
+    fn node(&mut self, node: &Node) -> (u64, F) {
+        let bytes = node.encode();
+        let hash = self.hash_rlc(H256(keccak256(&bytes)));
+        (self.stream_id(bytes), hash)
+    }
+
+    fn account(&self, address: Address) -> Result<Account, Error> {
+        let key = H256(keccak256(address.as_bytes()));
+        match self.trie.get(self.state_root, &key).map_err(trie_error)? {
+            Some(bytes) => Account::decode(&bytes),
+            None => Ok(Account::default()),
+        }
+    }
+
+    fn update(&mut self, update: &MptUpdate) -> Result<(), Error> {
+        match (update.first, update.last) {
+            (
+                Rw::AccountStorage {
+                    account_address,
+                    storage_key,
+                    value_prev,
+                    ..
+                },
+                Rw::AccountStorage { value, .. },
+            ) => self.storage_update(update, account_address, storage_key, value_prev, value),
+            (
+                Rw::Account {
+                    account_address,
+                    field_tag,
+                    value_prev,
+                    ..
+                },
+                Rw::Account { value, .. },
+            ) => self.account_update(account_address, field_tag, value_prev, value),
+            _ => unreachable!("MPT updates are made of account and storage accesses"),
+        }
+    }
+
+    fn storage_update(
+        &mut self,
+        update: &MptUpdate,
+        address: Address,
+        storage_key: Word,
+        old: Word,
+        new: Word,
+    ) -> Result<(), Error> {
+        let randomness = self.randomness;
+        let account = self.account(address)?;
+        let key = H256(keccak256(storage_key.to_be_bytes()));
+        let encode = |value: Word| (!value.is_zero()).then(|| encode_storage_value(value));
+        let trie_update = self
+            .trie
+            .update(account.storage_root, &key, encode(new))
+            .map_err(trie_error)?;
+        if trie_update.old_value() != encode(old).as_deref() {
+            error!(
+                "initial value of storage key {:?} of account {:?} is {:?} in the rw table but not in the storage trie",
+                storage_key, address, old
+            );
+            return Err(Error::Synthesis);
+        }
+
+        let state_root = self.hash_rlc(self.state_root);
+        let mut values = WalkValues {
+            q_update: true,
+            is_storage: true,
+            address: address.to_scalar().unwrap(),
+            storage_key: word_rlc(storage_key, randomness),
+            old_value: update.old_value_assignment(randomness),
+            new_value: update.new_value_assignment(randomness),
+            field: None,
+            old_root: self.hash_rlc(trie_update.old_root),
+            new_root: self.hash_rlc(trie_update.new_root),
+            old_state_root: state_root,
+            new_state_root: state_root,
+            old: self.storage_leaf(old),
+            new: self.storage_leaf(new),
+            ..Default::default()
+        };
+        self.push_walk(&mut values, &storage_key.to_be_bytes(), &key, &trie_update)?;
+
+        // Update the storage root of the account in the state trie.
+        let new_account = Account {
+            storage_root: trie_update.new_root,
+            ..account
+        };
+        self.account_walk(
+            address,
+            AccountField::StorageRoot,
+            account,
+            new_account,
+            None,
+        )
+    }
+
+    fn account_update(
+        &mut self,
+        address: Address,
+        field_tag: AccountFieldTag,
+        old: Word,
+        new: Word,
+    ) -> Result<(), Error> {
+        let account = self.account(address)?;
+        let (field, stored, new_account) = match field_tag {
+            AccountFieldTag::Nonce => (
+                AccountField::Nonce,
+                account.nonce,
+                Account {
+                    nonce: new,
+                    ..account
+                },
+            ),
+            AccountFieldTag::Balance => (
+                AccountField::Balance,
+                account.balance,
+                Account {
+                    balance: new,
+                    ..account
+                },
+            ),
+            AccountFieldTag::CodeHash => (
+                AccountField::CodeHash,
+                account.code_hash.to_word(),
+                Account {
+                    code_hash: if new.is_zero() {
+                        H256(*EMPTY_HASH)
+                    } else {
+                        H256(new.to_be_bytes())
+                    },
+                    ..account
+                },
+            ),
+        };
+        // The code hash of absent accounts is 0 in the rw table.
+        let is_empty_code_hash =
+            field == AccountField::CodeHash && old.is_zero() && account.code_hash.0 == *EMPTY_HASH;
+        if old != stored && !is_empty_code_hash {
+            error!(
+                "initial value of {:?} of account {:?} is {:?} in the rw table but {:?} in the state trie",
+                field_tag, address, old, stored
+            );
+            return Err(Error::Synthesis);
+        }
+        let code_hashes = (field == AccountField::CodeHash).then(|| (old, new));
+        self.account_walk(address, field, account, new_account, code_hashes)
+    }
+
+    /// Push the walk of the state trie which updates `field` of the account
+    /// of `address`, where `code_hashes` are the code hashes before and
+    /// after the update in the rw table if they are updated.
+    fn account_walk(
+        &mut self,
+        address: Address,
+        field: AccountField,
+        old_account: Account,
+        new_account: Account,
+        code_hashes: Option<(Word, Word)>,
+    ) -> Result<(), Error> {
+        let key = H256(keccak256(address.as_bytes()));
+        let trie_update = self
+            .trie
+            .update(self.state_root, &key, new_account.encode())
+            .map_err(trie_error)?;
+        let (old_code_hash, new_code_hash) = code_hashes.unwrap_or((
+            old_account.code_hash.to_word(),
+            new_account.code_hash.to_word(),
+        ));
+        let old = self.account_leaf(&old_account, old_code_hash);
+        let new = self.account_leaf(&new_account, new_code_hash);
+        let old_root = self.hash_rlc(trie_update.old_root);
+        let new_root = self.hash_rlc(trie_update.new_root);
+        let mut values = WalkValues {
+            q_update: true,
+            is_storage: false,
+            address: address.to_scalar().unwrap(),
+            storage_key: F::zero(),
+            old_value: old.field(field),
+            new_value: new.field(field),
+            field: Some(field),
+            old_root,
+            new_root,
+            old_state_root: old_root,
+            new_state_root: new_root,
+            old,
+            new,
+            ..Default::default()
+        };
+        self.push_walk(&mut values, address.as_bytes(), &key, &trie_update)?;
+        self.state_root = trie_update.new_root;
+        Ok(())
+    }
+
+    fn storage_leaf(&self, value: Word) -> LeafValues<F> {
+        if value.is_zero() {
+            return LeafValues::default();
+        }
+        let bytes = encode_storage_value(value);
+        LeafValues {
+            present: true,
+            probe: word_rlc(value, self.randomness),
+            vrlc: bytes_rlc(&bytes, self.randomness),
+            vlen: bytes.len() as u64,
+            pow: self.randomness.pow(&[bytes.len() as u64 - 1, 0, 0, 0]),
+            ..Default::default()
+        }
+    }
+
+    fn account_leaf(&mut self, account: &Account, code_hash: Word) -> LeafValues<F> {
+        let randomness = self.randomness;
+        let nonce = account.nonce.to_scalar().unwrap();
+        let balance = word_rlc(account.balance, randomness);
+        let storage_root = self.hash_rlc(account.storage_root);
+        let stored_code_hash = self.hash_rlc(account.code_hash);
+        let mut leaf = LeafValues {
+            probe: nonce
+                + randomness * balance
+                + randomness.square() * (storage_root - self.hash_rlc(*EMPTY_ROOT))
+                + randomness.square()
+                    * randomness
+                    * (stored_code_hash - self.hash_rlc(H256(*EMPTY_HASH))),
+            nonce,
+            balance,
+            storage_root,
+            code_hash: word_rlc(code_hash, randomness),
+            stored_code_hash,
+            ..Default::default()
+        };
+        if let Some(bytes) = account.encode() {
+            leaf.present = true;
+            leaf.vrlc = bytes_rlc(&bytes, randomness);
+            leaf.vlen = bytes.len() as u64;
+            leaf.account_id = self.stream_id(bytes);
+        }
+        leaf
+    }
+
+    /// Push the walk of `update` along `key`, whose preimage is `preimage`.
+    fn push_walk(
+        &mut self,
+        values: &mut WalkValues<F>,
+        preimage: &[u8],
+        key: &H256,
+        update: &TrieUpdate,
+    ) -> Result<(), Error> {
+        let randomness = self.randomness;
+        let nibbles = key_nibbles(key);
+        let mut rows = vec![MptRow::<F>::default(); WALK_ROWS];
+        for (row, nibble) in rows.iter_mut().zip(&nibbles) {
+            row.nibble = *nibble;
+        }
+        let too_deep = |depth: usize| {
+            if depth >= WALK_ROWS {
+                error!("leaves at depth {} are not supported", depth);
+                Err(Error::Synthesis)
+            } else {
+                Ok(())
+            }
+        };
+
+        for node in update.path.iter() {
+            let (old_id, old_hash) = self.node(&node.old);
+            let (new_id, new_hash) = self.node(&node.new);
+            let ids = [old_id, new_id, 0];
+            let hashes = [old_hash, new_hash, F::zero()];
+            match &node.new {
+                Node::Branch(children) => {
+                    let segment = &mut rows[node.depth..node.depth + 1];
+                    set_segment(segment, NodeKind::Branch, ids, hashes);
+                    self.set_children(&mut segment[0], children);
+                }
+                Node::Extension(path, _) => {
+                    let segment = &mut rows[node.depth..node.depth + path.len()];
+                    set_segment(segment, NodeKind::Extension, ids, hashes);
+                }
+                Node::Leaf(..) => unreachable!("leaves are at the end of the path"),
+            }
+        }
+
+        let other = match &update.end {
+            PathEnd::Leaf { depth, old, new } => {
+                too_deep(*depth)?;
+                let (old_id, old_hash) = old.as_ref().map_or((0, F::zero()), |n| self.node(n));
+                let (new_id, new_hash) = new.as_ref().map_or((0, F::zero()), |n| self.node(n));
+                set_segment(
+                    &mut rows[*depth..],
+                    NodeKind::Leaf,
+                    [old_id, new_id, 0],
+                    [old_hash, new_hash, F::zero()],
+                );
+                None
+            }
+            PathEnd::Empty { depth } => {
+                set_segment(&mut rows[*depth..], NodeKind::Empty, [0; 3], [F::zero(); 3]);
+                None
+            }
+            PathEnd::Other { depth, leaf } => {
+                too_deep(*depth)?;
+                let (id, hash) = self.node(leaf);
+                set_segment(
+                    &mut rows[*depth..],
+                    NodeKind::Other,
+                    [id, 0, 0],
+                    [hash, F::zero(), F::zero()],
+                );
+                Some((*depth, leaf))
+            }
+            PathEnd::Split {
+                depth,
+                other,
+                extension,
+                branch,
+                leaf,
+                sibling,
+                ..
+            } => {
+                let branch_depth = depth
+                    + match extension {
+                        Some(Node::Extension(path, _)) => path.len(),
+                        _ => 0,
+                    };
+                too_deep(branch_depth + 1)?;
+                let (other_id, other_hash) = self.node(other);
+                if let Some(extension) = extension {
+                    let (id, hash) = self.node(extension);
+                    set_segment(
+                        &mut rows[*depth..branch_depth],
+                        NodeKind::SplitExtension,
+                        [other_id, id, 0],
+                        [other_hash, hash, F::zero()],
+                    );
+                }
+                let (branch_id, branch_hash) = self.node(branch);
+                let segment = &mut rows[branch_depth..branch_depth + 1];
+                set_segment(
+                    segment,
+                    NodeKind::SplitBranch,
+                    [other_id, branch_id, 0],
+                    [other_hash, branch_hash, F::zero()],
+                );
+                if let Node::Branch(children) = branch {
+                    self.set_children(&mut segment[0], children);
+                }
+                let (leaf_id, leaf_hash) = self.node(leaf);
+                let (sibling_id, sibling_hash) = self.node(sibling);
+                set_segment(
+                    &mut rows[branch_depth + 1..],
+                    NodeKind::SplitLeaf,
+                    [other_id, leaf_id, sibling_id],
+                    [other_hash, leaf_hash, sibling_hash],
+                );
+                Some((*depth, other))
+            }
+        };
+        if let Some((depth, Node::Leaf(path, value))) = other {
+            for (row, nibble) in rows[depth..].iter_mut().zip(path) {
+                row.other_nibble = *nibble;
+            }
+            values.other_vrlc = bytes_rlc(value, randomness);
+            values.other_vlen = value.len() as u64;
+        }
+
+        for (idx, byte) in preimage.iter().enumerate() {
+            rows[idx].pre_byte = *byte;
+        }
+        let mut pre_rlc = F::zero();
+        for idx in (0..preimage.len()).rev() {
+            pre_rlc = pre_rlc * randomness + F::from(preimage[idx] as u64);
+            rows[idx].pre_rlc = pre_rlc;
+        }
+        let base = if values.is_storage {
+            randomness
+        } else {
+            F::from(256)
+        };
+        let mut pre_value = F::zero();
+        for (idx, byte) in preimage.iter().enumerate() {
+            pre_value = pre_value * base + F::from(*byte as u64);
+            rows[idx].pre_value = pre_value;
+        }
+        values.key_input = rows[0].pre_rlc;
+
+        for idx in 0..WALK_ROWS {
+            let (before, after) = rows.split_at_mut(idx);
+            let prev = before.last();
+            let row = &mut after[0];
+            let kind = row.kind.expect("the walk covers all the nibbles");
+            let prev_kind = prev.and_then(|prev| prev.kind);
+            let after_branch = prev.is_none() || prev_kind == Some(NodeKind::Branch);
+            let seg_start = after_branch || prev_kind == Some(NodeKind::SplitBranch);
+            let is_odd = idx % 2 == 1;
+            let nibble = F::from(row.nibble as u64);
+
+            if kind.has_path() {
+                let flag = if kind.is_leaf() { 0x20 } else { 0 };
+                match prev {
+                    Some(prev) if !seg_start => {
+                        row.n_len = prev.n_len + (!row.is_high) as u64;
+                        row.n_rlc = if row.is_high {
+                            prev.n_rlc
+                        } else {
+                            prev.n_rlc * randomness + F::from(16 * prev.nibble as u64) + nibble
+                        };
+                    }
+                    _ => {
+                        row.n_len = 1;
+                        row.n_rlc = if row.is_high {
+                            F::from(flag)
+                        } else {
+                            F::from(flag + 0x10) + nibble
+                        };
+                    }
+                }
+            }
+            if matches!(
+                kind,
+                NodeKind::Other
+                    | NodeKind::SplitExtension
+                    | NodeKind::SplitBranch
+                    | NodeKind::SplitLeaf
+            ) {
+                let prev_o = prev
+                    .filter(|_| !after_branch)
+                    .map(|prev| (prev.o_rlc, prev.o_len, prev.other_nibble));
+                let (o_rlc, o_len) = leaf_path_step(prev_o, row.other_nibble, is_odd, randomness);
+                row.o_rlc = o_rlc;
+                row.o_len = o_len;
+            }
+            if kind == NodeKind::SplitLeaf {
+                let prev_o2 = prev
+                    .filter(|prev| prev.kind != Some(NodeKind::SplitBranch))
+                    .map(|prev| (prev.o2_rlc, prev.o2_len, prev.other_nibble));
+                let (o2_rlc, o2_len) =
+                    leaf_path_step(prev_o2, row.other_nibble, is_odd, randomness);
+                row.o2_rlc = o2_rlc;
+                row.o2_len = o2_len;
+            }
+            if kind == NodeKind::Other && idx + 1 == WALK_ROWS {
+                row.other_inv = (row.o_rlc - row.n_rlc).invert().unwrap_or(F::zero());
+            }
+            row.key_rlc = match prev {
+                Some(prev) if is_odd => {
+                    prev.key_rlc * randomness + F::from(16 * prev.nibble as u64) + nibble
+                }
+                Some(prev) => prev.key_rlc,
+                None => F::zero(),
+            };
+
+            let (old_present, new_present) = (values.old.present, values.new.present);
+            let [h1, h2, _] = row.hashes;
+            let (old_ref, new_ref) = match kind {
+                NodeKind::Branch | NodeKind::Extension => (h1, h2),
+                NodeKind::Leaf => (
+                    if old_present { h1 } else { F::zero() },
+                    if new_present { h2 } else { F::zero() },
+                ),
+                NodeKind::Empty => (F::zero(), F::zero()),
+                NodeKind::Other => (h1, h1),
+                _ if old_present => (h2, h1),
+                _ => (h1, h2),
+            };
+            row.old_ref = old_ref;
+            row.new_ref = new_ref;
+            row.old_empty = kind == NodeKind::Empty || (kind == NodeKind::Leaf && !old_present);
+            row.new_empty = kind == NodeKind::Empty || (kind == NodeKind::Leaf && !new_present);
+        }
+
+        self.witness.key_preimages.push(preimage.to_vec());
+        self.witness.walks.push(Walk {
+            values: values.clone(),
+            rows,
+        });
+        Ok(())
+    }
+
+    fn set_children(&self, row: &mut MptRow<F>, children: &[Option<H256>; 16]) {
+        for (idx, child) in children.iter().enumerate() {
+            if let Some(hash) = child {
+                row.children[idx] = self.hash_rlc(*hash);
+                row.flags[idx] = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,856 @@
+//! The public-input circuit implementation, which exposes the hash, the state
+//! roots and the chain id of a block, the hashes of its transactions and its
+//! history hashes as instances, and assigns the block table from them.
+//!
+//! The fields of the block table are the items of the RLP of the block header,
+//! whose keccak hash is the public block hash, and the parent hash in the
+//! header is the latest public history hash.  The tx table is assigned by the
+//! TxCircuit, which checks the TxHash of each tx against the RLP of its fields
+//! and calldata, so looking up the public tx hashes in it binds the whole tx
+//! table to them.
+
+use crate::evm_circuit::{
+    table::{BlockContextFieldTag, LookupTable, TxContextFieldTag},
+    util::{constraint_builder::BaseConstraintBuilder, RandomLinearCombination},
+    witness::{Block, BlockContext},
+};
+use crate::keccak_circuit::{KeccakConfig, KeccakTable};
+use crate::rlp_circuit::RlpConfig;
+use crate::tx_circuit::{tx_from_witness, tx_hash};
+use crate::util::Expr;
+use eth_types::{Field, ToLittleEndian, Word};
+use halo2_proofs::{
+    circuit::{AssignedCell, Cell, Layouter, Region, SimpleFloorPlanner},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance},
+    poly::Rotation,
+};
+use serde::{Deserialize, Serialize};
+
+/// Number of rows of the block table, besides the all-zero row: one for each
+/// block context field and one for each of the 256 history hashes.
+pub const BLOCK_TABLE_LEN: usize = 7 + 256;
+/// Number of rows of the block table before the history hashes: the all-zero
+/// row and the block context fields.
+const BLOCK_FIELDS_LEN: usize = 8;
+/// Number of public values besides the tx hashes and the history hashes, which
+/// are the block hash, the state root, the previous state root and the chain
+/// id.
+pub const N_EXTRA: usize = 4;
+/// Maximum length of the RLP of a block header, reached when the words and
+/// the extra data take 32 bytes.
+const HEADER_RLP_MAX_LEN: usize = 700;
+/// Id of the RLP stream of the block header
+const HEADER_RLP_ID: u64 = 1;
+/// Item of the parent hash in the RLP of the block header
+const HEADER_ITEM_PARENT_HASH: u64 = 1;
+/// Item of the state root in the RLP of the block header
+const HEADER_ITEM_STATE_ROOT: u64 = 4;
+/// Number of items in the RLP of a block header since London, the last of
+/// which is the base fee.
+const HEADER_ITEMS: u64 = 16;
+
+/// Tags of the field rows of the block table, in the order of
+/// `BlockContext::table_assignments`.
+const BLOCK_FIELD_TAGS: [BlockContextFieldTag; BLOCK_FIELDS_LEN - 1] = [
+    BlockContextFieldTag::Coinbase,
+    BlockContextFieldTag::Timestamp,
+    BlockContextFieldTag::Number,
+    BlockContextFieldTag::Difficulty,
+    BlockContextFieldTag::GasLimit,
+    BlockContextFieldTag::BaseFee,
+    BlockContextFieldTag::ChainId,
+];
+
+/// Returns whether the block field with `tag` is the RLC of its item in the
+/// RLP of the block header, or its big-endian value, and the item.
+fn header_item(tag: BlockContextFieldTag) -> Option<(bool, u64)> {
+    match tag {
+        BlockContextFieldTag::Coinbase => Some((false, 3)),
+        BlockContextFieldTag::Difficulty => Some((true, 8)),
+        BlockContextFieldTag::Number => Some((false, 9)),
+        BlockContextFieldTag::GasLimit => Some((false, 10)),
+        BlockContextFieldTag::Timestamp => Some((false, 12)),
+        BlockContextFieldTag::BaseFee => Some((true, HEADER_ITEMS)),
+        _ => None,
+    }
+}
+
+/// Config for PiCircuit
+#[derive(Clone, Debug)]
+pub struct PiCircuitConfig<F: Field> {
+    /// Block table, laid out as (tag, index, value)
+    pub block_table: [Column<Advice>; 3],
+    /// Tx table, laid out as (tx_id, tag, index, value)
+    pub tx_table: [Column<Advice>; 4],
+    /// Whether the row is the all-zero row or a field of the block table
+    q_field: Column<Fixed>,
+    /// Whether the row is the all-zero row of the block table
+    q_zero: Column<Fixed>,
+    /// Tag of the field rows of the block table, fixed by their offset
+    fixed_tag: Column<Fixed>,
+    /// Whether the row is the latest history hash of the block table
+    q_parent: Column<Fixed>,
+    q_header_value: Column<Fixed>,
+    q_header_rlc: Column<Fixed>,
+    /// Item of the RLP of the header looked up by the row
+    header_item: Column<Fixed>,
+    /// Public values besides the history hashes
+    value: Column<Advice>,
+    q_block_hash: Column<Fixed>,
+    q_state_root: Column<Fixed>,
+    q_tx_hash: Column<Fixed>,
+    /// Tx id of the tx hash rows, fixed by their offset
+    fixed_tx_id: Column<Fixed>,
+    rlp: RlpConfig<F>,
+    /// Public inputs
+    pi: Column<Instance>,
+}
+
+impl<F: Field> PiCircuitConfig<F> {
+    /// Configure the public-input circuit so that it assigns the block table,
+    /// which is laid out as `[tag, index, value]` so that it can be shared
+    /// with the EVM circuit, and looks up the public tx hashes in the shared
+    /// `tx_table` and the hash of the block header in the shared
+    /// `keccak_table`.
+    pub fn new(
+        meta: &mut ConstraintSystem<F>,
+        randomness: Expression<F>,
+        block_table: [Column<Advice>; 3],
+        tx_table: [Column<Advice>; 4],
+        keccak_table: KeccakTable,
+    ) -> Self {
+        let [tag, index, block_value] = block_table;
+        let q_field = meta.fixed_column();
+        let q_zero = meta.fixed_column();
+        let fixed_tag = meta.fixed_column();
+        let q_parent = meta.fixed_column();
+        let q_header_value = meta.fixed_column();
+        let q_header_rlc = meta.fixed_column();
+        let header_item = meta.fixed_column();
+        let value = meta.advice_column();
+        let q_block_hash = meta.fixed_column();
+        let q_state_root = meta.fixed_column();
+        let q_tx_hash = meta.fixed_column();
+        let fixed_tx_id = meta.fixed_column();
+        let pi = meta.instance_column();
+
+        for column in block_table.into_iter().chain([value]) {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(pi);
+
+        let rlp = RlpConfig::configure(meta, randomness, keccak_table);
+
+        meta.create_gate("pi block table field", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            cb.require_equal(
+                "tag is fixed by the offset",
+                meta.query_advice(tag, Rotation::cur()),
+                meta.query_fixed(fixed_tag, Rotation::cur()),
+            );
+            cb.require_zero("index is 0", meta.query_advice(index, Rotation::cur()));
+            cb.condition(meta.query_fixed(q_zero, Rotation::cur()), |cb| {
+                cb.require_zero(
+                    "value is 0 in the all-zero row",
+                    meta.query_advice(block_value, Rotation::cur()),
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_field, Rotation::cur()))
+        });
+
+        // The block fields are the items of the header, which has id
+        // `HEADER_RLP_ID` in the RLP table.
+        meta.lookup_any("pi header value", |meta| {
+            let enable = meta.query_fixed(q_header_value, Rotation::cur());
+            vec![
+                enable.clone() * HEADER_RLP_ID.expr(),
+                enable.clone() * meta.query_fixed(header_item, Rotation::cur()),
+                enable * meta.query_advice(block_value, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(rlp.value_table_exprs(meta))
+            .collect()
+        });
+        meta.lookup_any("pi header value_rlc", |meta| {
+            let enable = meta.query_fixed(q_header_rlc, Rotation::cur());
+            vec![
+                enable.clone() * HEADER_RLP_ID.expr(),
+                enable.clone() * meta.query_fixed(header_item, Rotation::cur()),
+                enable * meta.query_advice(block_value, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(rlp.value_rlc_table_exprs(meta))
+            .collect()
+        });
+        // The parent hash of the header is the latest history hash, unless the
+        // history is empty.  The tag of the latest history row is public, so
+        // it is either BlockHash or 0.
+        meta.lookup_any("pi header parent hash", |meta| {
+            let has_history = meta.query_fixed(q_parent, Rotation::cur())
+                * meta.query_advice(tag, Rotation::cur())
+                * Expression::Constant(
+                    F::from(BlockContextFieldTag::BlockHash as u64)
+                        .invert()
+                        .unwrap(),
+                );
+            vec![
+                has_history.clone() * HEADER_RLP_ID.expr(),
+                has_history.clone() * HEADER_ITEM_PARENT_HASH.expr(),
+                has_history * meta.query_advice(block_value, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(rlp.value_rlc_table_exprs(meta))
+            .collect()
+        });
+        meta.lookup_any("pi header state root", |meta| {
+            let enable = meta.query_fixed(q_state_root, Rotation::cur());
+            vec![
+                enable.clone() * HEADER_RLP_ID.expr(),
+                enable.clone() * HEADER_ITEM_STATE_ROOT.expr(),
+                enable * meta.query_advice(value, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(rlp.value_rlc_table_exprs(meta))
+            .collect()
+        });
+        meta.lookup_any("pi header hash", |meta| {
+            let enable = meta.query_fixed(q_block_hash, Rotation::cur());
+            vec![
+                enable.clone() * HEADER_RLP_ID.expr(),
+                enable * meta.query_advice(value, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(rlp.hash_table_exprs(meta))
+            .collect()
+        });
+        meta.lookup_any("pi header items", |meta| {
+            let enable = meta.query_fixed(q_block_hash, Rotation::cur());
+            vec![
+                enable.clone() * HEADER_RLP_ID.expr(),
+                enable * HEADER_ITEMS.expr(),
+            ]
+            .into_iter()
+            .zip(rlp.end_table_exprs(meta))
+            .collect()
+        });
+        // The public hash of each tx is its TxHash, which is 0 for the padding
+        // txs.
+        meta.lookup_any("pi tx hash", |meta| {
+            let enable = meta.query_fixed(q_tx_hash, Rotation::cur());
+            vec![
+                enable.clone() * meta.query_fixed(fixed_tx_id, Rotation::cur()),
+                enable.clone() * TxContextFieldTag::TxHash.expr(),
+                0.expr(),
+                enable * meta.query_advice(value, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(tx_table.table_exprs(meta))
+            .collect()
+        });
+
+        Self {
+            block_table,
+            tx_table,
+            q_field,
+            q_zero,
+            fixed_tag,
+            q_parent,
+            q_header_value,
+            q_header_rlc,
+            header_item,
+            value,
+            q_block_hash,
+            q_state_root,
+            q_tx_hash,
+            fixed_tx_id,
+            rlp,
+            pi,
+        }
+    }
+
+    /// Constrain the cells of the RLC encoded state roots before and after
+    /// the block, which the MPT circuit assigns, to the public values.
+    pub fn constrain_state_roots(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        prev_state_root: Cell,
+        state_root: Cell,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(state_root, self.pi, 1)?;
+        layouter.constrain_instance(prev_state_root, self.pi, 2)
+    }
+
+    /// Assigns the fixed columns of a row from their names and values.
+    fn assign_fixed_row(
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: &[(&str, Column<Fixed>, F)],
+    ) -> Result<(), Error> {
+        for (name, column, value) in row {
+            region.assign_fixed(
+                || format!("{} {}", name, offset),
+                *column,
+                offset,
+                || Ok(*value),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Assigns a row of the block table, with its fixed columns determined by
+    /// `tag` for the all-zero row and the field rows, and returns its cells.
+    fn assign_block_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        tag: Option<BlockContextFieldTag>,
+        row: &[F; 3],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        let is_field = offset < BLOCK_FIELDS_LEN;
+        let item = tag.and_then(header_item);
+        Self::assign_fixed_row(
+            region,
+            offset,
+            &[
+                ("q_field", self.q_field, F::from(is_field as u64)),
+                ("q_zero", self.q_zero, F::from((offset == 0) as u64)),
+                (
+                    "fixed_tag",
+                    self.fixed_tag,
+                    F::from(tag.map_or(0, |tag| tag as u64)),
+                ),
+                (
+                    "q_parent",
+                    self.q_parent,
+                    F::from((offset == BLOCK_TABLE_LEN) as u64),
+                ),
+                (
+                    "q_header_value",
+                    self.q_header_value,
+                    F::from(matches!(item, Some((false, _))) as u64),
+                ),
+                (
+                    "q_header_rlc",
+                    self.q_header_rlc,
+                    F::from(matches!(item, Some((true, _))) as u64),
+                ),
+                (
+                    "header_item",
+                    self.header_item,
+                    F::from(item.map_or(0, |(_, item)| item)),
+                ),
+            ],
+        )?;
+        self.block_table
+            .iter()
+            .zip(row)
+            .map(|(column, value)| {
+                region.assign_advice(
+                    || format!("block table row {}", offset),
+                    *column,
+                    offset,
+                    || Ok(*value),
+                )
+            })
+            .collect()
+    }
+}
+
+/// The public data of a block that the PiCircuit exposes, from which a
+/// verifier computes the public inputs to check a proof against the block.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicData {
+    /// The number of the block
+    pub number: Word,
+    /// The hash of the block
+    pub hash: Word,
+    /// The state root after the block
+    pub state_root: Word,
+    /// The state root before the block
+    pub prev_state_root: Word,
+    /// The chain id
+    pub chain_id: Word,
+    /// The hashes of the previous blocks, the last of which is the parent
+    /// hash
+    pub history_hashes: Vec<Word>,
+    /// The hashes of the txs in the block
+    pub tx_hashes: Vec<Word>,
+}
+
+impl PublicData {
+    /// Returns the public data of `block`
+    pub fn new<F: Field>(block: &Block<F>) -> Self {
+        Self {
+            number: block.context.number,
+            hash: block.context.hash,
+            state_root: block.context.state_root,
+            prev_state_root: block.context.prev_state_root,
+            chain_id: block.context.chain_id,
+            history_hashes: block.context.history_hashes.clone(),
+            tx_hashes: block
+                .txs
+                .iter()
+                .map(|tx| tx_hash(&tx_from_witness(tx)))
+                .collect(),
+        }
+    }
+
+    /// Returns the public inputs of a PiCircuit with `MAX_TXS` txs for this
+    /// data and `randomness`, see `PiCircuit::public_inputs`.
+    pub fn public_inputs<F: Field, const MAX_TXS: usize>(&self, randomness: F) -> Vec<F> {
+        PiCircuit::<F, MAX_TXS> {
+            randomness,
+            context: BlockContext {
+                number: self.number,
+                hash: self.hash,
+                state_root: self.state_root,
+                prev_state_root: self.prev_state_root,
+                chain_id: self.chain_id,
+                history_hashes: self.history_hashes.clone(),
+                ..Default::default()
+            },
+            tx_hashes: self.tx_hashes.clone(),
+            size: 0,
+        }
+        .public_inputs()
+    }
+}
+
+/// Public-input circuit, which exposes the block hash, the state roots, the
+/// chain id, the tx hashes and the history hashes of a block as instances,
+/// and assigns the block table from them.  A verifier can then compute the
+/// instances from the Ethereum block to check a proof against it.
+#[derive(Default)]
+pub struct PiCircuit<F: Field, const MAX_TXS: usize> {
+    /// Randomness for RLC encoding
+    pub randomness: F,
+    /// The block context
+    pub context: BlockContext,
+    /// Hashes of the transactions in the block
+    pub tx_hashes: Vec<Word>,
+    /// Number of rows of the circuit, which the Keccak Circuit fills
+    pub size: usize,
+}
+
+impl<F: Field, const MAX_TXS: usize> PiCircuit<F, MAX_TXS> {
+    /// Make a new public-input circuit of `2^k` rows from a block
+    pub fn new(block: &Block<F>, k: u32) -> Self {
+        Self {
+            randomness: block.randomness,
+            context: block.context.clone(),
+            tx_hashes: PublicData::new(block).tx_hashes,
+            size: 1 << k,
+        }
+    }
+
+    fn rlc(&self, word: Word) -> F {
+        RandomLinearCombination::random_linear_combine(word.to_le_bytes(), self.randomness)
+    }
+
+    /// Rows of the block table, starting with an all-zero row. The history
+    /// hashes are right aligned, so that the parent hash is always in the last
+    /// row.
+    fn block_table_assignments(&self) -> Vec<[F; 3]> {
+        let history_len = self.context.history_hashes.len();
+        assert!(history_len <= 256, "too many history hashes");
+
+        let rows = self.context.table_assignments(self.randomness);
+        let (fields, history_hashes) = rows.split_at(rows.len() - history_len);
+        assert_eq!(fields.len() + 256, BLOCK_TABLE_LEN);
+
+        std::iter::once([F::zero(); 3])
+            .chain(fields.iter().copied())
+            .chain(std::iter::repeat([F::zero(); 3]).take(256 - history_len))
+            .chain(history_hashes.iter().copied())
+            .collect()
+    }
+
+    /// The block hash, the state root, the previous state root, the chain id,
+    /// and the hashes of the `MAX_TXS` txs, which are 0 for the padding txs.
+    fn public_values(&self) -> Vec<F> {
+        assert!(self.tx_hashes.len() <= MAX_TXS, "too many txs");
+
+        [
+            self.context.hash,
+            self.context.state_root,
+            self.context.prev_state_root,
+            self.context.chain_id,
+        ]
+        .into_iter()
+        .map(|word| self.rlc(word))
+        .chain(self.tx_hashes.iter().map(|hash| self.rlc(*hash)))
+        .chain(std::iter::repeat(F::zero()).take(MAX_TXS - self.tx_hashes.len()))
+        .collect()
+    }
+
+    /// The public inputs, which are the public values followed by the rows of
+    /// the history hashes of the block table, flattened.
+    pub fn public_inputs(&self) -> Vec<F> {
+        self.public_values()
+            .into_iter()
+            .chain(
+                self.block_table_assignments()
+                    .into_iter()
+                    .skip(BLOCK_FIELDS_LEN)
+                    .flatten(),
+            )
+            .collect()
+    }
+
+    /// The instance columns of the circuit: the powers of the randomness for
+    /// the keccak circuit, followed by the public inputs.
+    pub fn instance(&self) -> Vec<Vec<F>> {
+        let mut instance: Vec<Vec<F>> = (1..32)
+            .map(|exp| vec![self.randomness.pow(&[exp, 0, 0, 0]); self.size - 64])
+            .collect();
+        instance.push(self.public_inputs());
+        instance
+    }
+
+    /// Return the inputs of the keccak hashes that the PiCircuit looks up in
+    /// the keccak table.
+    pub fn keccak_inputs(&self) -> Vec<Vec<u8>> {
+        vec![self.context.header_rlp.clone()]
+    }
+
+    /// Assign the block table and the public values, and constrain them to be
+    /// equal to the public inputs.
+    pub fn synthesize_sub(
+        &self,
+        config: &PiCircuitConfig<F>,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.rlp.load(layouter)?;
+        config.rlp.assign(
+            layouter,
+            HEADER_RLP_MAX_LEN,
+            &[self.context.header_rlp.clone()],
+            self.randomness,
+        )?;
+
+        let (chain_id_in_block_table, history_cells) = layouter.assign_region(
+            || "block table",
+            |mut region| {
+                let mut chain_id = None;
+                let mut history_cells = Vec::new();
+                for (offset, row) in self.block_table_assignments().iter().enumerate() {
+                    // The tags of the field rows are fixed, as opposed to
+                    // the tags of the history rows which are public.
+                    let tag = (1..BLOCK_FIELDS_LEN)
+                        .contains(&offset)
+                        .then(|| BLOCK_FIELD_TAGS[offset - 1]);
+                    let cells = config.assign_block_row(&mut region, offset, tag, row)?;
+                    if tag == Some(BlockContextFieldTag::ChainId) {
+                        chain_id = Some(cells[2].cell());
+                    }
+                    if offset >= BLOCK_FIELDS_LEN {
+                        history_cells.extend(cells.into_iter().map(|cell| cell.cell()));
+                    }
+                }
+                Ok((
+                    chain_id.expect("no chain id in the block table"),
+                    history_cells,
+                ))
+            },
+        )?;
+
+        let value_cells = layouter.assign_region(
+            || "public values",
+            |mut region| {
+                self.public_values()
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, value)| {
+                        let tx_id = offset.checked_sub(N_EXTRA).map(|idx| idx + 1);
+                        PiCircuitConfig::assign_fixed_row(
+                            &mut region,
+                            offset,
+                            &[
+                                (
+                                    "q_block_hash",
+                                    config.q_block_hash,
+                                    F::from((offset == 0) as u64),
+                                ),
+                                (
+                                    "q_state_root",
+                                    config.q_state_root,
+                                    F::from((offset == 1) as u64),
+                                ),
+                                (
+                                    "q_tx_hash",
+                                    config.q_tx_hash,
+                                    F::from(tx_id.is_some() as u64),
+                                ),
+                                (
+                                    "fixed_tx_id",
+                                    config.fixed_tx_id,
+                                    F::from(tx_id.unwrap_or_default() as u64),
+                                ),
+                            ],
+                        )?;
+                        let cell = region.assign_advice(
+                            || format!("public value {}", offset),
+                            config.value,
+                            offset,
+                            || Ok(*value),
+                        )?;
+                        // The chain id of the block table is public.
+                        if offset == N_EXTRA - 1 {
+                            region.constrain_equal(cell.cell(), chain_id_in_block_table)?;
+                        }
+                        Ok(cell.cell())
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            },
+        )?;
+
+        for (row, cell) in value_cells.into_iter().chain(history_cells).enumerate() {
+            layouter.constrain_instance(cell, config.pi, row)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<F: Field, const MAX_TXS: usize> Circuit<F> for PiCircuit<F, MAX_TXS> {
+    type Config = (PiCircuitConfig<F>, KeccakConfig<F>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            size: self.size,
+            ..Default::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let block_table = [(); 3].map(|_| meta.advice_column());
+        let tx_table = [(); 4].map(|_| meta.advice_column());
+        let keccak_table = KeccakTable::construct(meta);
+
+        // This gate is used just to get the array of expressions from the power of
+        // randomness instance column, so that later on we don't need to query
+        // columns everywhere, and can pass the power of randomness array
+        // expression everywhere.  The gate itself doesn't add any constraints.
+        let power_of_randomness = {
+            let columns = [(); 31].map(|_| meta.instance_column());
+            let mut power_of_randomness = None;
+
+            meta.create_gate("power of randomness", |meta| {
+                power_of_randomness =
+                    Some(columns.map(|column| meta.query_instance(column, Rotation::cur())));
+
+                [0.expr()]
+            });
+
+            power_of_randomness.unwrap()
+        };
+
+        (
+            PiCircuitConfig::new(
+                meta,
+                power_of_randomness[0].clone(),
+                block_table,
+                tx_table,
+                keccak_table,
+            ),
+            KeccakConfig::configure(meta, power_of_randomness, keccak_table),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        (config, keccak_circuit): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        // The tx table only contains the tx hashes, after an all-zero row that
+        // allows disabled lookups.
+        layouter.assign_region(
+            || "tx table",
+            |mut region| {
+                let tx_hashes = self.tx_hashes.iter().enumerate().map(|(idx, hash)| {
+                    [
+                        F::from(idx as u64 + 1),
+                        F::from(TxContextFieldTag::TxHash as u64),
+                        F::zero(),
+                        self.rlc(*hash),
+                    ]
+                });
+                for (offset, row) in std::iter::once([F::zero(); 4]).chain(tx_hashes).enumerate() {
+                    for (column, value) in config.tx_table.iter().zip(row) {
+                        region.assign_advice(
+                            || format!("tx table row {}", offset),
+                            *column,
+                            offset,
+                            || Ok(value),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+        keccak_circuit.assign(
+            &mut layouter,
+            self.size,
+            &self.keccak_inputs(),
+            self.randomness,
+        )?;
+        self.synthesize_sub(&config, &mut layouter)
+    }
+}
+
+#[cfg(test)]
+mod pi_circuit_tests {
+    use super::*;
+    use crate::evm_circuit::witness::Transaction as WitnessTransaction;
+    use bus_mapping::circuit_input_builder::header_rlp;
+    use eth_types::{address, Address, ToBigEndian, H256, U64};
+    use halo2_proofs::{
+        arithmetic::BaseExt,
+        dev::{MockProver, VerifyFailure},
+        pairing::bn256::Fr,
+    };
+    use sha3::{Digest, Keccak256};
+
+    const MAX_TXS: usize = 4;
+    const K: u32 = 12;
+
+    /// Returns a block whose header has the fields of the block context, and
+    /// whose parent hash is `parent_hash`.
+    fn block(history_hashes: Vec<Word>, parent_hash: Word) -> Block<Fr> {
+        let mut context = BlockContext {
+            coinbase: address!("0x00000000000000000000000000000000c014ba5e"),
+            gas_limit: 15_000_000,
+            number: Word::from(0xcafe),
+            timestamp: Word::from(1_600_000_000),
+            difficulty: Word::from(0x200000),
+            base_fee: Word::from(7),
+            history_hashes,
+            chain_id: Word::from(1),
+            parent_hash,
+            state_root: Word::from(0x5a7e),
+            prev_state_root: Word::from(0x5a7d),
+            ..Default::default()
+        };
+        let header = eth_types::Block::<()> {
+            parent_hash: H256(context.parent_hash.to_be_bytes()),
+            author: context.coinbase,
+            state_root: H256(context.state_root.to_be_bytes()),
+            number: Some(U64::from(context.number.as_u64())),
+            gas_limit: Word::from(context.gas_limit),
+            timestamp: context.timestamp,
+            difficulty: context.difficulty,
+            base_fee_per_gas: Some(context.base_fee),
+            logs_bloom: Some(Default::default()),
+            mix_hash: Some(H256::zero()),
+            nonce: Some(U64::zero()),
+            ..Default::default()
+        };
+        context.header_rlp = header_rlp(&header).unwrap();
+        context.hash = Word::from_big_endian(Keccak256::digest(&context.header_rlp).as_slice());
+
+        let txs = (1..=2)
+            .map(|id| WitnessTransaction {
+                id,
+                nonce: id as u64,
+                gas: 21_000,
+                gas_price: Word::from(10),
+                caller_address: Address::repeat_byte(id as u8),
+                callee_address: Address::repeat_byte(0xff),
+                value: Word::from(100),
+                call_data: vec![id as u8; 8],
+                call_data_length: 8,
+                call_data_gas_cost: 8 * 16,
+                v: 37,
+                r: Word::from(0x1234),
+                s: Word::from(0x5678),
+                ..Default::default()
+            })
+            .collect();
+
+        Block {
+            randomness: Fr::rand(),
+            context,
+            txs,
+            ..Default::default()
+        }
+    }
+
+    fn default_block() -> Block<Fr> {
+        let parent_hash = Word::from(0xbeef);
+        block(vec![Word::from(0xdead), parent_hash], parent_hash)
+    }
+
+    fn verify(
+        circuit: PiCircuit<Fr, MAX_TXS>,
+        instance: Vec<Vec<Fr>>,
+    ) -> Result<(), Vec<VerifyFailure>> {
+        MockProver::<Fr>::run(K, &circuit, instance)
+            .unwrap()
+            .verify()
+    }
+
+    #[test]
+    fn pi_circuit_ok() {
+        let circuit = PiCircuit::<Fr, MAX_TXS>::new(&default_block(), K);
+        let instance = circuit.instance();
+        assert_eq!(instance[31].len(), N_EXTRA + MAX_TXS + 256 * 3);
+        assert_eq!(verify(circuit, instance), Ok(()));
+    }
+
+    #[test]
+    fn pi_circuit_public_data() {
+        let block = default_block();
+        let circuit = PiCircuit::<Fr, MAX_TXS>::new(&block, K);
+        assert_eq!(
+            PublicData::new(&block).public_inputs::<Fr, MAX_TXS>(block.randomness),
+            circuit.public_inputs()
+        );
+    }
+
+    #[test]
+    fn pi_circuit_no_history() {
+        let circuit = PiCircuit::<Fr, MAX_TXS>::new(&block(vec![], Word::from(0xbeef)), K);
+        let instance = circuit.instance();
+        assert_eq!(verify(circuit, instance), Ok(()));
+    }
+
+    #[test]
+    fn pi_circuit_wrong_instance() {
+        let block = default_block();
+        let circuit = PiCircuit::<Fr, MAX_TXS>::new(&block, K);
+
+        let mut other_block = block.clone();
+        other_block.context.state_root = Word::from(0x5a7f);
+        let instance = PiCircuit::<Fr, MAX_TXS>::new(&other_block, K).instance();
+        assert!(verify(circuit, instance).is_err());
+
+        let circuit = PiCircuit::<Fr, MAX_TXS>::new(&block, K);
+        let mut other_block = block;
+        other_block.txs[1].value = Word::from(101);
+        let instance = PiCircuit::<Fr, MAX_TXS>::new(&other_block, K).instance();
+        assert!(verify(circuit, instance).is_err());
+    }
+
+    #[test]
+    fn pi_circuit_header_mismatch() {
+        let mut block = default_block();
+        block.context.number = Word::from(0xcaff);
+        let circuit = PiCircuit::<Fr, MAX_TXS>::new(&block, K);
+        let instance = circuit.instance();
+        assert!(verify(circuit, instance).is_err());
+    }
+
+    #[test]
+    fn pi_circuit_parent_hash_mismatch() {
+        let block = block(
+            vec![Word::from(0xdead), Word::from(0xbeef)],
+            Word::from(0xbeee),
+        );
+        let circuit = PiCircuit::<Fr, MAX_TXS>::new(&block, K);
+        let instance = circuit.instance();
+        assert!(verify(circuit, instance).is_err());
+    }
+}
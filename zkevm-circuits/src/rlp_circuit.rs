@@ -0,0 +1,1022 @@
+//! The RLP decoding circuit, which splits RLP encoded lists of strings into
+//! their items, and binds the encoding to its keccak hash via the keccak
+//! table.
+//!
+//! Each RLP stream is laid out over one row per byte, and every stream is
+//! identified by an `id` that starts at 1 and increases by one per stream.
+//! The first byte of a stream is the header of the list (item 0), which is
+//! followed by the headers and contents of the strings (items 1, 2, ...).
+//! Each byte is either:
+//! - a header, which defines the kind of the item via a lookup into a fixed
+//!   table of the 256 header bytes,
+//! - a byte of the length of a long string or list, or
+//! - a byte of the content of a string.
+//!
+//! The last row of each item accumulates its big-endian `value`, the RLC of
+//! its bytes `value_rlc`, with the last byte multiplied by the lowest power
+//! of randomness (the encoding used for words in the EVM circuit), and its
+//! length `len`, which other circuits can look up together with the id and
+//! the item index.  The first row of each stream contains the keccak hash of
+//! the whole stream, which is looked up in the keccak table, and its length,
+//! and the last row contains the RLC of the whole stream with the last byte
+//! multiplied by the lowest power of randomness, so that a stream can be
+//! matched with the content of an item of another stream.
+//!
+//! The lengths of the lists are not checked, as the decoding is
+//! deterministic from the bytes, which are fixed by the hash.
+
+use crate::{
+    evm_circuit::{
+        table::LookupTable,
+        util::{constraint_builder::BaseConstraintBuilder, not},
+    },
+    keccak_circuit::KeccakTable,
+    util::Expr,
+};
+use eth_types::Field;
+use gadgets::is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction};
+use halo2_proofs::{
+    circuit::{Layouter, Region},
+    plonk::{Advice, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
+    poly::Rotation,
+};
+use log::error;
+use std::{iter, marker::PhantomData};
+
+/// Returns whether the header `byte` encodes a single byte string, a long
+/// string or list, and a list.
+fn header_kind(byte: u8) -> (bool, bool, bool) {
+    match byte {
+        0x00..=0x7f => (true, false, false),
+        0x80..=0xb7 => (false, false, false),
+        0xb8..=0xbf => (false, true, false),
+        0xc0..=0xf7 => (false, false, true),
+        0xf8..=0xff => (false, true, true),
+    }
+}
+
+/// Returns the value subtracted from a header byte to get the length of the
+/// item, or of the length of the item for long items.
+fn header_base(is_long: bool, is_list: bool) -> u64 {
+    0x80 + if is_list { 0x40 } else { 0 } + if is_long { 0x37 } else { 0 }
+}
+
+/// A row of the RLP decoding circuit
+#[derive(Clone, Debug, Default)]
+struct RlpRow<F> {
+    id: usize,
+    is_start: bool,
+    is_end: bool,
+    is_header: bool,
+    is_length: bool,
+    is_content: bool,
+    is_single: bool,
+    is_long: bool,
+    is_list: bool,
+    is_seg_end: bool,
+    is_item_end: bool,
+    is_byte: bool,
+    byte: u8,
+    item: usize,
+    remaining: u64,
+    length: u64,
+    value: F,
+    value_rlc: F,
+    len: usize,
+    rlc: F,
+    frlc: F,
+    rindex: usize,
+    hash_rlc: F,
+}
+
+/// Appends the rows of the RLP stream `bytes` with `id` to `rows`.  Returns an
+/// error if `bytes` is not a list of strings.
+fn rlp_stream_rows<F: Field>(
+    rows: &mut Vec<RlpRow<F>>,
+    id: usize,
+    bytes: &[u8],
+    randomness: F,
+) -> Result<(), Error> {
+    let byte_at = |pos: usize| {
+        bytes.get(pos).copied().ok_or_else(|| {
+            error!("RLP stream {} is truncated", id);
+            Error::Synthesis
+        })
+    };
+    let start = rows.len();
+
+    let mut pos = 0;
+    let mut item = 0;
+    while pos < bytes.len() {
+        let header = byte_at(pos)?;
+        let (is_single, is_long, is_list) = header_kind(header);
+        if (item == 0) != is_list {
+            error!("RLP stream {} is not a list of strings", id);
+            return Err(Error::Synthesis);
+        }
+        let size = if is_single {
+            0
+        } else {
+            header as u64 - header_base(is_long, is_list)
+        };
+        let remaining = if is_list && !is_long { 0 } else { size };
+        rows.push(RlpRow {
+            id,
+            is_start: item == 0,
+            is_header: true,
+            is_single,
+            is_long,
+            is_list,
+            is_seg_end: remaining == 0,
+            is_item_end: remaining == 0 && !is_list,
+            is_byte: is_single,
+            byte: header,
+            item,
+            remaining,
+            value: F::from(is_single as u64 * header as u64),
+            value_rlc: F::from(is_single as u64 * header as u64),
+            len: is_single as usize,
+            ..Default::default()
+        });
+        pos += 1;
+
+        let mut length = if is_long || is_list { 0 } else { size };
+        if is_long {
+            for remaining in (0..size).rev() {
+                let byte = byte_at(pos)?;
+                length = length * 256 + byte as u64;
+                rows.push(RlpRow {
+                    id,
+                    is_length: true,
+                    is_list,
+                    is_seg_end: remaining == 0,
+                    byte,
+                    item,
+                    remaining,
+                    length,
+                    ..Default::default()
+                });
+                pos += 1;
+            }
+        }
+
+        if !is_list {
+            let mut value = F::zero();
+            let mut value_rlc = F::zero();
+            for (len, remaining) in (0..length).rev().enumerate() {
+                let byte = byte_at(pos)?;
+                value = value * F::from(256) + F::from(byte as u64);
+                value_rlc = value_rlc * randomness + F::from(byte as u64);
+                rows.push(RlpRow {
+                    id,
+                    is_content: true,
+                    is_seg_end: remaining == 0,
+                    is_item_end: remaining == 0,
+                    is_byte: true,
+                    byte,
+                    item,
+                    remaining,
+                    value,
+                    value_rlc,
+                    len: len + 1,
+                    ..Default::default()
+                });
+                pos += 1;
+            }
+        }
+        item += 1;
+    }
+
+    let stream = &mut rows[start..];
+    match stream.last_mut() {
+        Some(row) if row.is_item_end => row.is_end = true,
+        _ => {
+            error!("RLP stream {} doesn't end with a string", id);
+            return Err(Error::Synthesis);
+        }
+    }
+    let hash_rlc = KeccakTable::assignment(bytes, randomness)[3];
+    let mut frlc = F::zero();
+    for row in stream.iter_mut() {
+        frlc = frlc * randomness + F::from(row.byte as u64);
+        row.frlc = frlc;
+    }
+    let mut rlc = F::zero();
+    for (rindex, row) in stream.iter_mut().rev().enumerate() {
+        rlc = rlc * randomness + F::from(row.byte as u64);
+        row.rlc = rlc;
+        row.rindex = rindex + 1;
+        row.hash_rlc = hash_rlc;
+    }
+    Ok(())
+}
+
+/// Config of the RLP decoding circuit
+#[derive(Clone, Debug)]
+pub struct RlpConfig<F> {
+    q_enable: Column<Fixed>,
+    q_first: Column<Fixed>,
+    q_last: Column<Fixed>,
+    q_next: Column<Fixed>,
+    /// Header table: `[is_enabled, byte, is_single, is_long, is_list]`
+    header_table: [Column<Fixed>; 5],
+    id: Column<Advice>,
+    is_start: Column<Advice>,
+    is_end: Column<Advice>,
+    is_header: Column<Advice>,
+    is_length: Column<Advice>,
+    is_content: Column<Advice>,
+    is_single: Column<Advice>,
+    is_long: Column<Advice>,
+    is_list: Column<Advice>,
+    is_seg_end: Column<Advice>,
+    is_item_end: Column<Advice>,
+    is_byte: Column<Advice>,
+    byte: Column<Advice>,
+    item: Column<Advice>,
+    remaining: Column<Advice>,
+    remaining_is_zero: IsZeroConfig<F>,
+    length: Column<Advice>,
+    value: Column<Advice>,
+    value_rlc: Column<Advice>,
+    len: Column<Advice>,
+    rlc: Column<Advice>,
+    frlc: Column<Advice>,
+    rindex: Column<Advice>,
+    hash_rlc: Column<Advice>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> RlpConfig<F> {
+    /// Configure the RLP decoding circuit, which computes the RLC of the
+    /// streams with `randomness` and looks up their hashes in
+    /// `keccak_table`.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        randomness: Expression<F>,
+        keccak_table: KeccakTable,
+    ) -> Self {
+        let q_enable = meta.fixed_column();
+        let q_first = meta.fixed_column();
+        let q_last = meta.fixed_column();
+        let q_next = meta.fixed_column();
+        let header_table = [(); 5].map(|_| meta.fixed_column());
+        let id = meta.advice_column();
+        let is_start = meta.advice_column();
+        let is_end = meta.advice_column();
+        let is_header = meta.advice_column();
+        let is_length = meta.advice_column();
+        let is_content = meta.advice_column();
+        let is_single = meta.advice_column();
+        let is_long = meta.advice_column();
+        let is_list = meta.advice_column();
+        let is_seg_end = meta.advice_column();
+        let is_item_end = meta.advice_column();
+        let is_byte = meta.advice_column();
+        let byte = meta.advice_column();
+        let item = meta.advice_column();
+        let remaining = meta.advice_column();
+        let length = meta.advice_column();
+        let value = meta.advice_column();
+        let value_rlc = meta.advice_column();
+        let len = meta.advice_column();
+        let rlc = meta.advice_column();
+        let frlc = meta.advice_column();
+        let rindex = meta.advice_column();
+        let hash_rlc = meta.advice_column();
+
+        let remaining_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_enable, Rotation::cur()),
+            |meta| meta.query_advice(remaining, Rotation::cur()),
+            meta.advice_column(),
+        );
+
+        meta.create_gate("rlp row", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let cur =
+                |meta: &mut VirtualCells<F>, column| meta.query_advice(column, Rotation::cur());
+            let prev =
+                |meta: &mut VirtualCells<F>, column| meta.query_advice(column, Rotation::prev());
+            let header = cur(meta, is_header);
+            let length_byte = cur(meta, is_length);
+            let content = cur(meta, is_content);
+            let is_row = header.clone() + length_byte.clone() + content.clone();
+
+            for (name, flag) in [
+                ("is_start is boolean", cur(meta, is_start)),
+                ("is_end is boolean", cur(meta, is_end)),
+                ("is_header is boolean", header.clone()),
+                ("is_length is boolean", length_byte.clone()),
+                ("is_content is boolean", content.clone()),
+                ("only one of is_header, is_length and is_content", is_row),
+            ] {
+                cb.require_boolean(name, flag);
+            }
+            cb.require_equal(
+                "is_seg_end := remaining == 0",
+                cur(meta, is_seg_end),
+                remaining_is_zero.expr(),
+            );
+            cb.require_equal(
+                "is_item_end := is_seg_end && (is_content || is_header && !is_list)",
+                cur(meta, is_item_end),
+                cur(meta, is_seg_end)
+                    * (content.clone() + header.clone() * not::expr(cur(meta, is_list))),
+            );
+            cb.require_equal(
+                "is_byte := is_content || is_header && is_single",
+                cur(meta, is_byte),
+                content.clone() + header.clone() * cur(meta, is_single),
+            );
+            cb.require_zero(
+                "is_start implies is_header",
+                cur(meta, is_start) * not::expr(header.clone()),
+            );
+            cb.require_zero(
+                "is_start implies item == 0",
+                cur(meta, is_start) * cur(meta, item),
+            );
+            cb.require_zero(
+                "is_start implies is_list",
+                cur(meta, is_start) * not::expr(cur(meta, is_list)),
+            );
+            cb.require_zero(
+                "is_end implies is_item_end",
+                cur(meta, is_end) * not::expr(cur(meta, is_item_end)),
+            );
+            // `rlc` and `rindex` accumulate the bytes from the last one to the
+            // first one, so that they end up on the first row of the stream
+            // as its RLC and length, as they are encoded in the keccak table.
+            cb.condition(cur(meta, is_end), |cb| {
+                cb.require_equal(
+                    "rlc := byte on the last row",
+                    cur(meta, rlc),
+                    cur(meta, byte),
+                );
+                cb.require_equal("rindex := 1 on the last row", cur(meta, rindex), 1.expr());
+            });
+            // `frlc` accumulates the bytes from the first one to the last one.
+            cb.condition(cur(meta, is_start), |cb| {
+                cb.require_equal(
+                    "frlc := byte on the first row",
+                    cur(meta, frlc),
+                    cur(meta, byte),
+                );
+            });
+
+            cb.condition(header, |cb| {
+                let single = cur(meta, is_single);
+                let long = cur(meta, is_long);
+                let list = cur(meta, is_list);
+                cb.require_zero(
+                    "only the first header is a list",
+                    not::expr(cur(meta, is_start)) * list.clone(),
+                );
+                // The segment of a header spans the bytes of the length of
+                // long items, and the bytes of the content of short strings.
+                // The items of a short list follow its header.
+                let base = 0x80.expr() + 0x40.expr() * list.clone() + 0x37.expr() * long.clone();
+                cb.require_equal(
+                    "remaining := byte - base, or 0 for single bytes and short lists",
+                    cur(meta, remaining),
+                    not::expr(single.clone())
+                        * (long.clone() + not::expr(long) * not::expr(list))
+                        * (cur(meta, byte) - base),
+                );
+                cb.require_equal(
+                    "value := single ? byte : 0",
+                    cur(meta, value),
+                    single.clone() * cur(meta, byte),
+                );
+                cb.require_equal(
+                    "value_rlc := single ? byte : 0",
+                    cur(meta, value_rlc),
+                    single.clone() * cur(meta, byte),
+                );
+                cb.require_equal("len := single ? 1 : 0", cur(meta, len), single);
+                cb.require_zero("length := 0", cur(meta, length));
+            });
+            cb.condition(length_byte, |cb| {
+                cb.require_equal(
+                    "length := length_prev * 256 + byte",
+                    cur(meta, length),
+                    prev(meta, length) * 256.expr() + cur(meta, byte),
+                );
+                cb.require_equal(
+                    "is_list := is_list_prev",
+                    cur(meta, is_list),
+                    prev(meta, is_list),
+                );
+                cb.require_zero("value := 0", cur(meta, value));
+                cb.require_zero("value_rlc := 0", cur(meta, value_rlc));
+                cb.require_zero("len := 0", cur(meta, len));
+            });
+            cb.condition(content, |cb| {
+                cb.require_equal(
+                    "value := value_prev * 256 + byte",
+                    cur(meta, value),
+                    prev(meta, value) * 256.expr() + cur(meta, byte),
+                );
+                cb.require_equal(
+                    "value_rlc := value_rlc_prev * r + byte",
+                    cur(meta, value_rlc),
+                    prev(meta, value_rlc) * randomness.clone() + cur(meta, byte),
+                );
+                cb.require_equal(
+                    "len := len_prev + 1",
+                    cur(meta, len),
+                    prev(meta, len) + 1.expr(),
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.create_gate("rlp first row", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let is_start = meta.query_advice(is_start, Rotation::cur());
+            let is_row = meta.query_advice(is_header, Rotation::cur())
+                + meta.query_advice(is_length, Rotation::cur())
+                + meta.query_advice(is_content, Rotation::cur());
+            cb.require_equal(
+                "the first row starts a stream or is padding",
+                is_start.clone(),
+                is_row,
+            );
+            cb.require_zero(
+                "the first stream has id 1",
+                is_start * (meta.query_advice(id, Rotation::cur()) - 1.expr()),
+            );
+
+            cb.gate(meta.query_fixed(q_first, Rotation::cur()))
+        });
+
+        meta.create_gate("rlp last row", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let is_row = meta.query_advice(is_header, Rotation::cur())
+                + meta.query_advice(is_length, Rotation::cur())
+                + meta.query_advice(is_content, Rotation::cur());
+            cb.require_zero(
+                "the last row ends a stream or is padding",
+                is_row * not::expr(meta.query_advice(is_end, Rotation::cur())),
+            );
+
+            cb.gate(meta.query_fixed(q_last, Rotation::cur()))
+        });
+
+        meta.create_gate("rlp transition", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let cur =
+                |meta: &mut VirtualCells<F>, column| meta.query_advice(column, Rotation::cur());
+            let next =
+                |meta: &mut VirtualCells<F>, column| meta.query_advice(column, Rotation::next());
+            let header = cur(meta, is_header);
+            let length_byte = cur(meta, is_length);
+            let content = cur(meta, is_content);
+            let is_row = header.clone() + length_byte.clone() + content.clone();
+            let is_row_next =
+                next(meta, is_header) + next(meta, is_length) + next(meta, is_content);
+            let is_end = cur(meta, is_end);
+            let is_seg_end = cur(meta, is_seg_end);
+
+            cb.require_zero(
+                "padding is followed by padding",
+                not::expr(is_row.clone()) * is_row_next.clone(),
+            );
+            cb.require_zero(
+                "the end of a stream is followed by a new stream or padding",
+                is_end.clone() * (next(meta, is_start) - is_row_next.clone()),
+            );
+            cb.require_zero(
+                "the id of a new stream is the previous id + 1",
+                next(meta, is_start) * (next(meta, id) - cur(meta, id) - 1.expr()),
+            );
+
+            let in_stream = is_row * not::expr(is_end);
+            cb.condition(in_stream.clone(), |cb| {
+                cb.require_equal("the stream continues", is_row_next, 1.expr());
+                cb.require_zero("the stream doesn't restart", next(meta, is_start));
+                cb.require_equal("id remains the same", next(meta, id), cur(meta, id));
+                cb.require_equal(
+                    "rlc := byte + r * rlc_next",
+                    cur(meta, rlc),
+                    cur(meta, byte) + randomness.clone() * next(meta, rlc),
+                );
+                cb.require_equal(
+                    "rindex := rindex_next + 1",
+                    cur(meta, rindex),
+                    next(meta, rindex) + 1.expr(),
+                );
+                cb.require_equal(
+                    "frlc_next := frlc * r + byte_next",
+                    next(meta, frlc),
+                    cur(meta, frlc) * randomness.clone() + next(meta, byte),
+                );
+            });
+            cb.condition(in_stream.clone() * not::expr(is_seg_end.clone()), |cb| {
+                cb.require_equal(
+                    "remaining_next := remaining - 1 within a segment",
+                    next(meta, remaining),
+                    cur(meta, remaining) - 1.expr(),
+                );
+                cb.require_equal(
+                    "item remains the same within a segment",
+                    next(meta, item),
+                    cur(meta, item),
+                );
+                cb.require_zero(
+                    "a long header is followed by its length",
+                    header.clone() * cur(meta, is_long) * not::expr(next(meta, is_length)),
+                );
+                cb.require_zero(
+                    "a short header is followed by its content",
+                    header.clone()
+                        * not::expr(cur(meta, is_long))
+                        * not::expr(next(meta, is_content)),
+                );
+                cb.require_zero(
+                    "a length byte is followed by a length byte within a segment",
+                    length_byte.clone() * not::expr(next(meta, is_length)),
+                );
+                cb.require_zero(
+                    "a content byte is followed by a content byte within a segment",
+                    content.clone() * not::expr(next(meta, is_content)),
+                );
+            });
+            cb.condition(in_stream * is_seg_end, |cb| {
+                // The length of a long string is followed by its content
+                let is_string_length = length_byte.clone() * not::expr(cur(meta, is_list));
+                cb.require_zero(
+                    "the length of a long string is followed by its content",
+                    is_string_length.clone() * not::expr(next(meta, is_content)),
+                );
+                cb.require_zero(
+                    "remaining_next := length - 1 after the length of a long string",
+                    is_string_length.clone()
+                        * (next(meta, remaining) - cur(meta, length) + 1.expr()),
+                );
+                cb.require_zero(
+                    "item remains the same after the length of a long string",
+                    is_string_length * (next(meta, item) - cur(meta, item)),
+                );
+                // Otherwise the segment ends the list header or an item, and
+                // it's followed by the header of the next item.
+                let is_next_item = header + content + length_byte * cur(meta, is_list);
+                cb.require_zero(
+                    "the end of an item is followed by a header",
+                    is_next_item.clone() * not::expr(next(meta, is_header)),
+                );
+                cb.require_zero(
+                    "item_next := item + 1 after the end of an item",
+                    is_next_item * (next(meta, item) - cur(meta, item) - 1.expr()),
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_next, Rotation::cur()))
+        });
+
+        meta.lookup_any("rlp header", |meta| {
+            let enable = meta.query_fixed(q_enable, Rotation::cur())
+                * meta.query_advice(is_header, Rotation::cur());
+            iter::once(enable.clone())
+                .chain(
+                    [byte, is_single, is_long, is_list]
+                        .iter()
+                        .map(|column| enable.clone() * meta.query_advice(*column, Rotation::cur())),
+                )
+                .zip(header_table.table_exprs(meta))
+                .collect()
+        });
+
+        meta.lookup_any("rlp keccak", |meta| {
+            let enable = meta.query_fixed(q_enable, Rotation::cur())
+                * meta.query_advice(is_start, Rotation::cur());
+            iter::once(enable.clone())
+                .chain(
+                    [rlc, rindex, hash_rlc]
+                        .iter()
+                        .map(|column| enable.clone() * meta.query_advice(*column, Rotation::cur())),
+                )
+                .zip(keccak_table.table_exprs(meta))
+                .collect()
+        });
+
+        Self {
+            q_enable,
+            q_first,
+            q_last,
+            q_next,
+            header_table,
+            id,
+            is_start,
+            is_end,
+            is_header,
+            is_length,
+            is_content,
+            is_single,
+            is_long,
+            is_list,
+            is_seg_end,
+            is_item_end,
+            is_byte,
+            byte,
+            item,
+            remaining,
+            remaining_is_zero,
+            length,
+            value,
+            value_rlc,
+            len,
+            rlc,
+            frlc,
+            rindex,
+            hash_rlc,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns the table expressions `columns` on the rows where `flag` is
+    /// set, and zero otherwise.
+    fn flagged_table_exprs(
+        &self,
+        meta: &mut VirtualCells<F>,
+        flag: Column<Advice>,
+        columns: Vec<Expression<F>>,
+    ) -> Vec<Expression<F>> {
+        let enable = meta.query_fixed(self.q_enable, Rotation::cur())
+            * meta.query_advice(flag, Rotation::cur());
+        columns
+            .into_iter()
+            .map(|column| enable.clone() * column)
+            .collect()
+    }
+
+    /// Returns the table `[id, hash_rlc]` of the keccak hashes of the
+    /// streams.
+    pub fn hash_table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        let columns = vec![
+            meta.query_advice(self.id, Rotation::cur()),
+            meta.query_advice(self.hash_rlc, Rotation::cur()),
+        ];
+        self.flagged_table_exprs(meta, self.is_start, columns)
+    }
+
+    /// Returns the table `[id, frlc]` of the RLC of the streams, with the last
+    /// byte multiplied by the lowest power of randomness, which is the
+    /// `value_rlc` of a string whose content is the stream.
+    pub fn stream_rlc_table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        let columns = vec![
+            meta.query_advice(self.id, Rotation::cur()),
+            meta.query_advice(self.frlc, Rotation::cur()),
+        ];
+        self.flagged_table_exprs(meta, self.is_end, columns)
+    }
+
+    /// Returns the table `[id, len]` of the lengths of the streams.
+    pub fn stream_len_table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        let columns = vec![
+            meta.query_advice(self.id, Rotation::cur()),
+            meta.query_advice(self.rindex, Rotation::cur()),
+        ];
+        self.flagged_table_exprs(meta, self.is_start, columns)
+    }
+
+    /// Returns the table `[id, item]` of the last item of each stream.
+    pub fn end_table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        let columns = vec![
+            meta.query_advice(self.id, Rotation::cur()),
+            meta.query_advice(self.item, Rotation::cur()),
+        ];
+        self.flagged_table_exprs(meta, self.is_end, columns)
+    }
+
+    /// Returns the table of the items `[id, item, field]` where `field` is
+    /// the big-endian value, the RLC or the length of the item.
+    fn item_table_exprs(
+        &self,
+        meta: &mut VirtualCells<F>,
+        field: Column<Advice>,
+    ) -> Vec<Expression<F>> {
+        let columns = vec![
+            meta.query_advice(self.id, Rotation::cur()),
+            meta.query_advice(self.item, Rotation::cur()),
+            meta.query_advice(field, Rotation::cur()),
+        ];
+        self.flagged_table_exprs(meta, self.is_item_end, columns)
+    }
+
+    /// Returns the table `[id, item, value]` of the big-endian values of the
+    /// items.  The values wrap around the field for items longer than 31
+    /// bytes.
+    pub fn value_table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        self.item_table_exprs(meta, self.value)
+    }
+
+    /// Returns the table `[id, item, value_rlc]` of the RLC of the items, with
+    /// the last byte multiplied by the lowest power of randomness.
+    pub fn value_rlc_table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        self.item_table_exprs(meta, self.value_rlc)
+    }
+
+    /// Returns the table `[id, item, len]` of the lengths of the items.
+    pub fn len_table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        self.item_table_exprs(meta, self.len)
+    }
+
+    /// Returns the table `[id, item, value_rlc, len]` of the RLC and the
+    /// length of the items, to look up both with a single lookup.
+    pub(crate) fn item_rlc_len_table_exprs(
+        &self,
+        meta: &mut VirtualCells<F>,
+    ) -> Vec<Expression<F>> {
+        let columns = vec![
+            meta.query_advice(self.id, Rotation::cur()),
+            meta.query_advice(self.item, Rotation::cur()),
+            meta.query_advice(self.value_rlc, Rotation::cur()),
+            meta.query_advice(self.len, Rotation::cur()),
+        ];
+        self.flagged_table_exprs(meta, self.is_item_end, columns)
+    }
+
+    /// Returns the table `[id, item, index, byte]` of the bytes of the
+    /// content of the items.
+    pub fn byte_table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        let columns = vec![
+            meta.query_advice(self.id, Rotation::cur()),
+            meta.query_advice(self.item, Rotation::cur()),
+            meta.query_advice(self.len, Rotation::cur()) - 1.expr(),
+            meta.query_advice(self.byte, Rotation::cur()),
+        ];
+        self.flagged_table_exprs(meta, self.is_byte, columns)
+    }
+
+    /// Load the fixed table of the 256 header bytes.
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_region(
+            || "rlp header table",
+            |mut region| {
+                for byte in 0..=u8::MAX {
+                    let (is_single, is_long, is_list) = header_kind(byte);
+                    for (column, value) in self.header_table.iter().zip([
+                        1,
+                        byte as u64,
+                        is_single as u64,
+                        is_long as u64,
+                        is_list as u64,
+                    ]) {
+                        region.assign_fixed(
+                            || format!("rlp header table row {}", byte),
+                            *column,
+                            byte as usize,
+                            || Ok(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Assign the RLP `streams`, with ids 1, 2, ..., into `max_rows` rows,
+    /// followed by padding.
+    pub fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        max_rows: usize,
+        streams: &[Vec<u8>],
+        randomness: F,
+    ) -> Result<(), Error> {
+        let mut rows = Vec::new();
+        for (idx, stream) in streams.iter().enumerate() {
+            rlp_stream_rows(&mut rows, idx + 1, stream, randomness)?;
+        }
+        if rows.len() > max_rows {
+            error!(
+                "RLP circuit needs {} rows but only has {}",
+                rows.len(),
+                max_rows
+            );
+            return Err(Error::Synthesis);
+        }
+        let padding = RlpRow {
+            is_seg_end: true,
+            ..Default::default()
+        };
+
+        let remaining_is_zero_chip = IsZeroChip::construct(self.remaining_is_zero.clone());
+        layouter.assign_region(
+            || "rlp circuit",
+            |mut region| {
+                for offset in 0..max_rows {
+                    let row = rows.get(offset).unwrap_or(&padding);
+                    self.assign_row(&mut region, &remaining_is_zero_chip, offset, max_rows, row)?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn assign_row(
+        &self,
+        region: &mut Region<'_, F>,
+        remaining_is_zero_chip: &IsZeroChip<F>,
+        offset: usize,
+        max_rows: usize,
+        row: &RlpRow<F>,
+    ) -> Result<(), Error> {
+        for (name, column, value) in [
+            ("q_enable", self.q_enable, true),
+            ("q_first", self.q_first, offset == 0),
+            ("q_last", self.q_last, offset == max_rows - 1),
+            ("q_next", self.q_next, offset < max_rows - 1),
+        ] {
+            region.assign_fixed(
+                || format!("{} {}", name, offset),
+                column,
+                offset,
+                || Ok(F::from(value as u64)),
+            )?;
+        }
+        for (name, column, value) in [
+            ("id", self.id, F::from(row.id as u64)),
+            ("is_start", self.is_start, F::from(row.is_start as u64)),
+            ("is_end", self.is_end, F::from(row.is_end as u64)),
+            ("is_header", self.is_header, F::from(row.is_header as u64)),
+            ("is_length", self.is_length, F::from(row.is_length as u64)),
+            (
+                "is_content",
+                self.is_content,
+                F::from(row.is_content as u64),
+            ),
+            ("is_single", self.is_single, F::from(row.is_single as u64)),
+            ("is_long", self.is_long, F::from(row.is_long as u64)),
+            ("is_list", self.is_list, F::from(row.is_list as u64)),
+            (
+                "is_seg_end",
+                self.is_seg_end,
+                F::from(row.is_seg_end as u64),
+            ),
+            (
+                "is_item_end",
+                self.is_item_end,
+                F::from(row.is_item_end as u64),
+            ),
+            ("is_byte", self.is_byte, F::from(row.is_byte as u64)),
+            ("byte", self.byte, F::from(row.byte as u64)),
+            ("item", self.item, F::from(row.item as u64)),
+            ("remaining", self.remaining, F::from(row.remaining)),
+            ("length", self.length, F::from(row.length)),
+            ("value", self.value, row.value),
+            ("value_rlc", self.value_rlc, row.value_rlc),
+            ("len", self.len, F::from(row.len as u64)),
+            ("rlc", self.rlc, row.rlc),
+            ("frlc", self.frlc, row.frlc),
+            ("rindex", self.rindex, F::from(row.rindex as u64)),
+            ("hash_rlc", self.hash_rlc, row.hash_rlc),
+        ] {
+            region.assign_advice(
+                || format!("{} {}", name, offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        remaining_is_zero_chip.assign(region, offset, Some(F::from(row.remaining)))
+    }
+}
+
+#[cfg(test)]
+mod rlp_circuit_tests {
+    use super::*;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::{MockProver, VerifyFailure},
+        pairing::bn256::Fr,
+        plonk::Circuit,
+    };
+    use pretty_assertions::assert_eq;
+    use rlp::RlpStream;
+
+    #[derive(Default)]
+    struct RlpTestCircuit<F> {
+        streams: Vec<Vec<u8>>,
+        keccak_inputs: Vec<Vec<u8>>,
+        randomness: F,
+        max_rows: usize,
+    }
+
+    impl<F: Field> Circuit<F> for RlpTestCircuit<F> {
+        type Config = (RlpConfig<F>, KeccakTable);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                max_rows: self.max_rows,
+                ..Default::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let keccak_table = KeccakTable::construct(meta);
+            let randomness = {
+                let column = meta.instance_column();
+                let mut randomness = None;
+                meta.create_gate("randomness", |meta| {
+                    randomness = Some(meta.query_instance(column, Rotation::cur()));
+                    [0.expr()]
+                });
+                randomness.unwrap()
+            };
+
+            (
+                RlpConfig::configure(meta, randomness, keccak_table),
+                keccak_table,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, keccak_table): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            keccak_table.dev_load(&mut layouter, &self.keccak_inputs, self.randomness)?;
+            config.load(&mut layouter)?;
+            config.assign(&mut layouter, self.max_rows, &self.streams, self.randomness)
+        }
+    }
+
+    fn run(streams: Vec<Vec<u8>>, keccak_inputs: Vec<Vec<u8>>) -> Result<(), Vec<VerifyFailure>> {
+        let k = 10;
+        let randomness = Fr::from(0x100);
+        let circuit = RlpTestCircuit {
+            streams,
+            keccak_inputs,
+            randomness,
+            max_rows: 300,
+        };
+        let instance = vec![vec![randomness; (1 << k) - 64]];
+        let prover = MockProver::<Fr>::run(k, &circuit, instance).unwrap();
+        prover.verify()
+    }
+
+    fn streams() -> Vec<Vec<u8>> {
+        // Single byte, empty, long and short strings in a short list
+        let mut short_list = RlpStream::new_list(4);
+        short_list
+            .append(&0x7fu8)
+            .append(&0u8)
+            .append(&vec![0xabu8; 60])
+            .append(&0x1234u64);
+        // Short strings in a long list
+        let mut long_list = RlpStream::new_list(3);
+        for byte in 1..=3u8 {
+            long_list.append(&vec![byte; 30]);
+        }
+        vec![short_list.out().to_vec(), long_list.out().to_vec()]
+    }
+
+    #[test]
+    fn rlp_circuit_valid() {
+        assert_eq!(run(streams(), streams()), Ok(()));
+    }
+
+    #[test]
+    fn rlp_circuit_no_streams() {
+        assert_eq!(run(vec![], vec![]), Ok(()));
+    }
+
+    #[test]
+    fn rlp_circuit_unknown_hash() {
+        let streams = streams();
+        let keccak_inputs = vec![streams[0].clone()];
+        assert!(run(streams, keccak_inputs).is_err());
+    }
+
+    #[test]
+    fn rlp_stream_rows_items() {
+        let randomness = Fr::from(0x100);
+        let mut rows = Vec::new();
+        rlp_stream_rows(&mut rows, 1, &streams()[0], randomness).unwrap();
+        let items: Vec<(usize, Fr, usize)> = rows
+            .iter()
+            .filter(|row| row.is_item_end)
+            .map(|row| (row.item, row.value, row.len))
+            .collect();
+        assert_eq!(items[0], (1, Fr::from(0x7f), 1));
+        assert_eq!(items[1], (2, Fr::zero(), 0));
+        assert_eq!(items[2].0, 3);
+        assert_eq!(items[2].2, 60);
+        assert_eq!(items[3], (4, Fr::from(0x1234), 2));
+        assert!(rows.last().unwrap().is_end);
+        assert_eq!(rows[0].rindex, rows.len());
+        let frlc = streams()[0].iter().fold(Fr::zero(), |acc, byte| {
+            acc * randomness + Fr::from(*byte as u64)
+        });
+        assert_eq!(rows.last().unwrap().frlc, frlc);
+    }
+}
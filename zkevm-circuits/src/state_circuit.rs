@@ -7,13 +7,17 @@ mod random_linear_combination;
 #[cfg(test)]
 mod test;
 
-use crate::evm_circuit::{
-    param::N_BYTES_WORD,
-    table::RwTableTag,
-    witness::{Rw, RwMap},
+use crate::{
+    evm_circuit::{
+        param::N_BYTES_WORD,
+        table::{LookupTable, RwTableTag},
+        witness::{Rw, RwMap},
+    },
+    mpt_circuit::{MptTable, MptUpdates},
+    rw_table::RwTable,
 };
 use constraint_builder::{ConstraintBuilder, Queries};
-use eth_types::{Address, Field};
+use eth_types::{Address, Field, Word};
 use gadgets::{
     binary_number::{BinaryNumberChip, BinaryNumberConfig},
     util::Expr,
@@ -31,7 +35,7 @@ use multiple_precision_integer::{Chip as MpiChip, Config as MpiConfig, Queries a
 use random_linear_combination::{Chip as RlcChip, Config as RlcConfig, Queries as RlcQueries};
 #[cfg(test)]
 use std::collections::HashMap;
-use std::iter::once;
+use std::iter::{self, once};
 
 const N_LIMBS_RW_COUNTER: usize = 2;
 const N_LIMBS_ACCOUNT_ADDRESS: usize = 10;
@@ -48,84 +52,45 @@ pub struct StateConfig {
     initial_value: Column<Advice>, /* Assigned value at the start of the block. For Rw::Account
                                     * and Rw::AccountStorage rows this is the committed value in
                                     * the MPT, for others, it is 0. */
+    // Whether the row is the last access of an account field or a storage slot, which updates
+    // the MPT from initial_value to value.
+    is_mpt_update: Column<Advice>,
     lexicographic_ordering: LexicographicOrderingConfig,
     lookups: LookupsConfig,
+    mpt_table: MptTable,
     power_of_randomness: [Column<Instance>; N_BYTES_WORD - 1],
+    rw_table: RwTable,
 }
 
-/// Keys for sorting the rows of the state circuit
-#[derive(Clone, Copy)]
-pub struct SortKeysConfig {
-    tag: BinaryNumberConfig<RwTableTag, 4>,
-    id: MpiConfig<u32, N_LIMBS_ID>,
-    address: MpiConfig<Address, N_LIMBS_ACCOUNT_ADDRESS>,
-    field_tag: Column<Advice>,
-    storage_key: RlcConfig<N_BYTES_WORD>,
-    rw_counter: MpiConfig<u32, N_LIMBS_RW_COUNTER>,
-}
-
-type Lookup<F> = (&'static str, Expression<F>, Expression<F>);
-
-/// State Circuit for proving RwTable is valid
-#[derive(Default)]
-pub struct StateCircuit<F: Field, const N_ROWS: usize> {
-    pub(crate) randomness: F,
-    pub(crate) rows: Vec<Rw>,
-    #[cfg(test)]
-    overrides: HashMap<(test::AdviceColumn, isize), F>,
-}
-
-impl<F: Field, const N_ROWS: usize> StateCircuit<F, N_ROWS> {
-    /// make a new state circuit from an RwMap
-    pub fn new(randomness: F, rw_map: RwMap) -> Self {
-        let mut rows: Vec<_> = rw_map.0.into_values().flatten().collect();
-        rows.sort_by_key(|row| {
-            (
-                row.tag() as u64,
-                row.id().unwrap_or_default(),
-                row.address().unwrap_or_default(),
-                row.field_tag().unwrap_or_default(),
-                row.storage_key().unwrap_or_default(),
-                row.rw_counter(),
-            )
-        });
-        Self {
-            randomness,
-            rows,
-            #[cfg(test)]
-            overrides: HashMap::new(),
-        }
-    }
-
-    /// powers of randomness for instance columns
-    pub fn instance(&self) -> Vec<Vec<F>> {
-        (1..32)
-            .map(|exp| vec![self.randomness.pow(&[exp, 0, 0, 0]); N_ROWS])
-            .collect()
-    }
-}
-
-impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
-    type Config = StateConfig;
-    type FloorPlanner = SimpleFloorPlanner;
-
-    fn without_witnesses(&self) -> Self {
-        Self::default()
-    }
-
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+impl StateConfig {
+    /// Configure the state circuit so that its sorted rows are assigned into
+    /// `rw_table`, which can then be shared with the EVM circuit, and so that
+    /// the last access of each account field and storage slot is an update of
+    /// `mpt_table`, which can then be shared with the MPT circuit.
+    pub fn configure<F: Field>(
+        meta: &mut ConstraintSystem<F>,
+        power_of_randomness: [Column<Instance>; N_BYTES_WORD - 1],
+        rw_table: RwTable,
+        mpt_table: MptTable,
+    ) -> Self {
         let selector = meta.fixed_column();
         let lookups = LookupsChip::configure(meta);
-        let power_of_randomness = [0; N_BYTES_WORD - 1].map(|_| meta.instance_column());
 
-        let [is_write, field_tag, value, initial_value] = [0; 4].map(|_| meta.advice_column());
+        let [initial_value, is_mpt_update] = [0; 2].map(|_| meta.advice_column());
+        let (is_write, field_tag, value) = (rw_table.is_write, rw_table.key3, rw_table.value);
 
         let tag = BinaryNumberChip::configure(meta, selector);
 
-        let id = MpiChip::configure(meta, selector, lookups.u16);
-        let address = MpiChip::configure(meta, selector, lookups.u16);
-        let storage_key = RlcChip::configure(meta, selector, lookups.u8, power_of_randomness);
-        let rw_counter = MpiChip::configure(meta, selector, lookups.u16);
+        let id = MpiChip::configure(meta, selector, rw_table.key1, lookups.u16);
+        let address = MpiChip::configure(meta, selector, rw_table.key2, lookups.u16);
+        let storage_key = RlcChip::configure(
+            meta,
+            selector,
+            rw_table.key4,
+            lookups.u8,
+            power_of_randomness,
+        );
+        let rw_counter = MpiChip::configure(meta, selector, rw_table.rw_counter, lookups.u16);
 
         let sort_keys = SortKeysConfig {
             tag,
@@ -143,15 +108,18 @@ impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
             power_of_randomness,
         );
 
-        let config = Self::Config {
+        let config = Self {
             selector,
             sort_keys,
             is_write,
             value,
             initial_value,
+            is_mpt_update,
             lexicographic_ordering,
             lookups,
+            mpt_table,
             power_of_randomness,
+            rw_table,
         };
 
         let mut constraint_builder = ConstraintBuilder::new();
@@ -163,16 +131,119 @@ impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
         for (name, expressions) in constraint_builder.lookups() {
             meta.lookup_any(name, |_| vec![expressions]);
         }
+        // The last accesses of the account fields and storage slots are the
+        // updates of the MPT table, both ways, so that the MPT circuit proves
+        // exactly the updates from their initial values to their final
+        // values.
+        let update_exprs = |meta: &mut VirtualCells<F>| {
+            let is_mpt_update = meta.query_advice(is_mpt_update, Rotation::cur());
+            iter::once(is_mpt_update.clone())
+                .chain(
+                    [
+                        address.value,
+                        storage_key.encoded,
+                        field_tag,
+                        initial_value,
+                        value,
+                    ]
+                    .map(|column| {
+                        is_mpt_update.clone() * meta.query_advice(column, Rotation::cur())
+                    }),
+                )
+                .collect::<Vec<_>>()
+        };
+        meta.lookup_any("mpt update lookup", |meta| {
+            update_exprs(meta)
+                .into_iter()
+                .zip(mpt_table.table_exprs(meta))
+                .collect()
+        });
+        meta.lookup_any("mpt update reverse lookup", |meta| {
+            let selector = meta.query_fixed(selector, Rotation::cur());
+            mpt_table
+                .table_exprs(meta)
+                .into_iter()
+                .zip(
+                    update_exprs(meta)
+                        .into_iter()
+                        .map(|expr| selector.clone() * expr),
+                )
+                .collect()
+        });
 
         config
     }
+}
 
-    fn synthesize(
+/// Keys for sorting the rows of the state circuit
+#[derive(Clone, Copy)]
+pub struct SortKeysConfig {
+    tag: BinaryNumberConfig<RwTableTag, 4>,
+    id: MpiConfig<u32, N_LIMBS_ID>,
+    address: MpiConfig<Address, N_LIMBS_ACCOUNT_ADDRESS>,
+    field_tag: Column<Advice>,
+    storage_key: RlcConfig<N_BYTES_WORD>,
+    rw_counter: MpiConfig<u32, N_LIMBS_RW_COUNTER>,
+}
+
+type Lookup<F> = (&'static str, Expression<F>, Expression<F>);
+
+/// Keys of a row in the state circuit, where the rows of the same keys are
+/// accesses to the same value.
+pub(crate) type AccessKey = (u64, usize, Address, u64, Word);
+
+/// Return the keys of `row` in the state circuit, by which (and then the rw
+/// counter) the rows are sorted.
+pub(crate) fn access_key(row: &Rw) -> AccessKey {
+    (
+        row.tag() as u64,
+        row.id().unwrap_or_default(),
+        row.address().unwrap_or_default(),
+        row.field_tag().unwrap_or_default(),
+        row.storage_key().unwrap_or_default(),
+    )
+}
+
+/// State Circuit for proving RwTable is valid
+#[derive(Default)]
+pub struct StateCircuit<F: Field, const N_ROWS: usize> {
+    pub(crate) randomness: F,
+    pub(crate) rows: Vec<Rw>,
+    pub(crate) updates: MptUpdates,
+    #[cfg(test)]
+    overrides: HashMap<(test::AdviceColumn, isize), F>,
+}
+
+impl<F: Field, const N_ROWS: usize> StateCircuit<F, N_ROWS> {
+    /// make a new state circuit from an RwMap
+    pub fn new(randomness: F, rw_map: RwMap) -> Self {
+        let mut rows: Vec<_> = rw_map.0.into_values().flatten().collect();
+        rows.sort_by_key(|row| (access_key(row), row.rw_counter()));
+        let updates = MptUpdates::new(&rows);
+        Self {
+            randomness,
+            rows,
+            updates,
+            #[cfg(test)]
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// powers of randomness for instance columns
+    pub fn instance(&self) -> Vec<Vec<F>> {
+        (1..32)
+            .map(|exp| vec![self.randomness.pow(&[exp, 0, 0, 0]); N_ROWS])
+            .collect()
+    }
+
+    /// Make the assignments to the StateCircuit, including the rows of its
+    /// rw table.
+    pub fn synthesize_sub(
         &self,
-        config: Self::Config,
-        mut layouter: impl Layouter<F>,
+        config: &StateConfig,
+        layouter: &mut impl Layouter<F>,
     ) -> Result<(), Error> {
-        LookupsChip::construct(config.lookups).load(&mut layouter)?;
+        LookupsChip::construct(config.lookups).load(layouter)?;
 
         let tag_chip = BinaryNumberChip::construct(config.sort_keys.tag);
 
@@ -184,10 +255,13 @@ impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
 
                 let rows = padding.chain(self.rows.iter().cloned());
                 let prev_rows = once(None).chain(rows.clone().map(Some));
+                let next_rows = rows.clone().skip(1).map(Some).chain(once(None));
 
                 let mut initial_value = F::zero();
 
-                for (offset, (row, prev_row)) in rows.zip(prev_rows).enumerate() {
+                for (offset, ((row, prev_row), next_row)) in
+                    rows.zip(prev_rows).zip(next_rows).enumerate()
+                {
                     region.assign_fixed(|| "selector", config.selector, offset, || Ok(F::one()))?;
                     config.sort_keys.rw_counter.assign(
                         &mut region,
@@ -232,6 +306,19 @@ impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
                         offset,
                         || Ok(row.value_assignment(self.randomness)),
                     )?;
+                    let table_row = row.table_assignment(self.randomness);
+                    for (name, column, value) in [
+                        ("tag", config.rw_table.tag, table_row.tag),
+                        (
+                            "value_prev",
+                            config.rw_table.value_prev,
+                            table_row.value_prev,
+                        ),
+                        ("aux1", config.rw_table.aux1, table_row.aux1),
+                        ("aux2", config.rw_table.aux2, table_row.aux2),
+                    ] {
+                        region.assign_advice(|| name, column, offset, || Ok(value))?;
+                    }
 
                     if let Some(prev_row) = prev_row {
                         let is_first_access = config.lexicographic_ordering.assign(
@@ -241,15 +328,19 @@ impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
                             &prev_row,
                         )?;
 
-                        // TODO: Get initial_values from MPT updates instead.
                         if is_first_access {
                             // TODO: Set initial values for Rw::CallContext to be 0 instead of
                             // special casing it.
-                            initial_value = if matches!(row.tag(), RwTableTag::CallContext) {
-                                row.value_assignment(self.randomness)
-                            } else {
-                                row.value_prev_assignment(self.randomness)
-                                    .unwrap_or_default()
+                            initial_value = match row.tag() {
+                                RwTableTag::CallContext => row.value_assignment(self.randomness),
+                                RwTableTag::Account | RwTableTag::AccountStorage => self
+                                    .updates
+                                    .get(&row)
+                                    .unwrap()
+                                    .old_value_assignment(self.randomness),
+                                _ => row
+                                    .value_prev_assignment(self.randomness)
+                                    .unwrap_or_default(),
                             };
                         }
                     }
@@ -260,8 +351,25 @@ impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
                         offset,
                         || Ok(initial_value),
                     )?;
+
+                    let is_mpt_update =
+                        matches!(row.tag(), RwTableTag::Account | RwTableTag::AccountStorage)
+                            && next_row
+                                .map_or(true, |next_row| access_key(&next_row) != access_key(&row));
+                    region.assign_advice(
+                        || "is_mpt_update",
+                        config.is_mpt_update,
+                        offset,
+                        || Ok(F::from(is_mpt_update as u64)),
+                    )?;
                 }
 
+                // All-zero row so that disabled lookups into the rw table from other
+                // circuits are satisfied
+                config
+                    .rw_table
+                    .assign(&mut region, N_ROWS, &Default::default())?;
+
                 #[cfg(test)]
                 for ((column, row_offset), &f) in &self.overrides {
                     let advice_column = column.value(&config);
@@ -277,6 +385,33 @@ impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
     }
 }
 
+impl<F: Field, const N_ROWS: usize> Circuit<F> for StateCircuit<F, N_ROWS> {
+    type Config = StateConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let power_of_randomness = [0; N_BYTES_WORD - 1].map(|_| meta.instance_column());
+        let rw_table = RwTable::construct(meta);
+        let mpt_table = MptTable::construct(meta);
+        StateConfig::configure(meta, power_of_randomness, rw_table, mpt_table)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config
+            .mpt_table
+            .load(&mut layouter, &self.updates, self.randomness)?;
+        self.synthesize_sub(&config, &mut layouter)
+    }
+}
+
 fn queries<F: Field>(meta: &mut VirtualCells<'_, F>, c: &StateConfig) -> Queries<F> {
     let first_different_limb = c.lexicographic_ordering.first_different_limb;
     let final_bits_sum = meta.query_advice(first_different_limb.bits[3], Rotation::cur())
@@ -294,6 +429,7 @@ fn queries<F: Field>(meta: &mut VirtualCells<'_, F>, c: &StateConfig) -> Queries
             .tag
             .bits
             .map(|bit| meta.query_advice(bit, Rotation::cur())),
+        rw_table_tag: meta.query_advice(c.rw_table.tag, Rotation::cur()),
         id: MpiQueries::new(meta, c.sort_keys.id),
         // this isn't binary! only 0 if most significant 3 bits are all 0 and at most 1 of the two
         // least significant bits is 1.
@@ -311,6 +447,9 @@ fn queries<F: Field>(meta: &mut VirtualCells<'_, F>, c: &StateConfig) -> Queries
         value_prev: meta.query_advice(c.value, Rotation::prev()),
         initial_value: meta.query_advice(c.initial_value, Rotation::cur()),
         initial_value_prev: meta.query_advice(c.initial_value, Rotation::prev()),
+        rw_table_value_prev: meta.query_advice(c.rw_table.value_prev, Rotation::cur()),
+        aux2: meta.query_advice(c.rw_table.aux2, Rotation::cur()),
+        is_mpt_update: meta.query_advice(c.is_mpt_update, Rotation::cur()),
         lookups: LookupsQueries::new(meta, c.lookups),
         power_of_randomness: c
             .power_of_randomness
@@ -327,5 +466,9 @@ fn queries<F: Field>(meta: &mut VirtualCells<'_, F>, c: &StateConfig) -> Queries
             * meta.query_advice(first_different_limb.bits[1], Rotation::cur())
             * meta.query_advice(first_different_limb.bits[2], Rotation::cur())
             * meta.query_advice(first_different_limb.bits[3], Rotation::cur()),
+        not_first_access_next: meta.query_advice(first_different_limb.bits[0], Rotation::next())
+            * meta.query_advice(first_different_limb.bits[1], Rotation::next())
+            * meta.query_advice(first_different_limb.bits[2], Rotation::next())
+            * meta.query_advice(first_different_limb.bits[3], Rotation::next()),
     }
 }
@@ -22,6 +22,7 @@ pub struct Queries<F: Field> {
     pub is_write: Expression<F>,
     pub tag: Expression<F>,
     pub tag_bits: [Expression<F>; 4],
+    pub rw_table_tag: Expression<F>,
     pub id: MpiQueries<F, N_LIMBS_ID>,
     pub is_tag_and_id_unchanged: Expression<F>,
     pub address: MpiQueries<F, N_LIMBS_ACCOUNT_ADDRESS>,
@@ -31,10 +32,14 @@ pub struct Queries<F: Field> {
     pub value_prev: Expression<F>,
     pub initial_value: Expression<F>,
     pub initial_value_prev: Expression<F>,
+    pub rw_table_value_prev: Expression<F>,
+    pub aux2: Expression<F>,
+    pub is_mpt_update: Expression<F>,
     pub lookups: LookupsQueries<F>,
     pub power_of_randomness: [Expression<F>; N_BYTES_WORD - 1],
     pub first_access: Expression<F>,
     pub not_first_access: Expression<F>,
+    pub not_first_access_next: Expression<F>,
 }
 
 type Constraint<F> = (&'static str, Expression<F>);
@@ -108,6 +113,11 @@ impl<F: Field> ConstraintBuilder<F> {
     fn build_general_constraints(&mut self, q: &Queries<F>) {
         // tag value in RwTableTag range is enforced in BinaryNumberChip
         self.require_boolean("is_write is boolean", q.is_write());
+        self.require_equal(
+            "tag matches rw table tag",
+            q.tag.clone(),
+            q.rw_table_tag.clone(),
+        );
 
         // When at least one of the keys (tag, id, address, field_tag, or storage_key)
         // in the current row differs from the previous row.
@@ -129,6 +139,52 @@ impl<F: Field> ConstraintBuilder<F> {
                 q.initial_value.clone() - q.initial_value_prev(),
             );
         });
+
+        // value_prev in the rw table is the value before this access for the tags that
+        // have one, i.e. initial_value at the start of an access group and the
+        // value of the previous row otherwise.
+        let has_value_prev = [
+            RwTableTag::AccountStorage,
+            RwTableTag::TxAccessListAccount,
+            RwTableTag::TxAccessListAccountStorage,
+            RwTableTag::TxRefund,
+            RwTableTag::Account,
+            RwTableTag::AccountDestructed,
+        ]
+        .into_iter()
+        .fold(0.expr(), |acc, tag| acc + q.tag_matches(tag));
+        self.condition(has_value_prev.clone(), |cb| {
+            cb.condition(q.first_access(), |cb| {
+                cb.require_equal(
+                    "first access value_prev is initial_value",
+                    q.rw_table_value_prev(),
+                    q.initial_value(),
+                );
+            });
+            cb.condition(q.not_first_access.clone(), |cb| {
+                cb.require_equal(
+                    "non-first access value_prev is previous value",
+                    q.rw_table_value_prev(),
+                    q.value_prev(),
+                );
+            });
+        });
+        self.condition(not::expr(has_value_prev), |cb| {
+            cb.require_zero("value_prev is 0 when unused", q.rw_table_value_prev());
+        });
+
+        // aux2 is only used for the committed value of AccountStorage
+        self.condition(not::expr(q.tag_matches(RwTableTag::AccountStorage)), |cb| {
+            cb.require_zero("aux2 is 0 for non-AccountStorage", q.aux2());
+        });
+        // The last access of an account field or a storage slot is looked up in
+        // the MPT table, as an update from the initial value to the value.
+        self.require_equal(
+            "is_mpt_update is 1 only for the last access of Account and AccountStorage",
+            q.is_mpt_update.clone(),
+            (1.expr() - q.not_first_access_next.clone())
+                * (q.tag_matches(RwTableTag::Account) + q.tag_matches(RwTableTag::AccountStorage)),
+        );
     }
 
     fn build_start_constraints(&mut self, q: &Queries<F>) {
@@ -183,9 +239,13 @@ impl<F: Field> ConstraintBuilder<F> {
     fn build_account_storage_constraints(&mut self, q: &Queries<F>) {
         // TODO: cold VS warm
         self.require_zero("field_tag is 0 for AccountStorage", q.field_tag());
-
-        // TODO: add mpt lookup for committed value and final value in an access
-        // group.
+        // Accesses are grouped by tx id, so the value at the start of the group is the
+        // value committed before the tx.
+        self.require_equal(
+            "AccountStorage committed value is initial_value",
+            q.aux2(),
+            q.initial_value(),
+        );
     }
     fn build_tx_access_list_account_constraints(&mut self, q: &Queries<F>) {
         self.require_zero("field_tag is 0 for TxAccessListAccount", q.field_tag());
@@ -233,9 +293,6 @@ impl<F: Field> ConstraintBuilder<F> {
             q.field_tag(),
             set::<F, AccountFieldTag>(),
         );
-
-        // TODO: add mpt lookup for committed value and final value in an access
-        // group.
     }
 
     fn build_account_destructed_constraints(&mut self, q: &Queries<F>) {
@@ -420,6 +477,14 @@ impl<F: Field> Queries<F> {
         self.initial_value_prev.clone()
     }
 
+    fn rw_table_value_prev(&self) -> Expression<F> {
+        self.rw_table_value_prev.clone()
+    }
+
+    fn aux2(&self) -> Expression<F> {
+        self.aux2.clone()
+    }
+
     fn tag_matches(&self, tag: RwTableTag) -> Expression<F> {
         BinaryNumberConfig::<RwTableTag, 4>::value_equals_expr(tag, self.tag_bits.clone())
     }
@@ -1,7 +1,12 @@
 use super::{StateCircuit, StateConfig};
-use crate::evm_circuit::{
-    table::{AccountFieldTag, CallContextFieldTag, RwTableTag, TxLogFieldTag, TxReceiptFieldTag},
-    witness::{Rw, RwMap},
+use crate::{
+    evm_circuit::{
+        table::{
+            AccountFieldTag, CallContextFieldTag, RwTableTag, TxLogFieldTag, TxReceiptFieldTag,
+        },
+        witness::{Rw, RwMap},
+    },
+    mpt_circuit::MptUpdates,
 };
 use bus_mapping::operation::{
     MemoryOp, Operation, OperationContainer, RWCounter, StackOp, StorageOp, RW,
@@ -47,6 +52,9 @@ pub enum AdviceColumn {
     LimbIndexBit3,
     LimbIndexBit4, // least significant bit
     InitialValue,
+    Tag,
+    ValuePrev,
+    Aux2,
 }
 
 impl AdviceColumn {
@@ -73,6 +81,9 @@ impl AdviceColumn {
             Self::LimbIndexBit3 => config.lexicographic_ordering.first_different_limb.bits[3],
             Self::LimbIndexBit4 => config.lexicographic_ordering.first_different_limb.bits[4],
             Self::InitialValue => config.initial_value,
+            Self::Tag => config.rw_table.tag,
+            Self::ValuePrev => config.rw_table.value_prev,
+            Self::Aux2 => config.rw_table.aux2,
         }
     }
 }
@@ -427,7 +438,14 @@ fn address_limb_mismatch() {
 
     let result = verify_with_overrides(rows, overrides);
 
-    assert_error_matches(result, "mpi value matches claimed limbs");
+    assert_errors_match(
+        result,
+        &[
+            "mpi value matches claimed limbs",
+            "mpt update lookup",
+            "mpt update reverse lookup",
+        ],
+    );
 }
 
 #[test]
@@ -466,7 +484,14 @@ fn storage_key_mismatch() {
 
     let result = verify_with_overrides(rows, overrides);
 
-    assert_error_matches(result, "rlc encoded value matches bytes");
+    assert_errors_match(
+        result,
+        &[
+            "rlc encoded value matches bytes",
+            "mpt update lookup",
+            "mpt update reverse lookup",
+        ],
+    );
 }
 
 #[test]
@@ -489,7 +514,14 @@ fn storage_key_byte_out_of_range() {
 
     let result = verify_with_overrides(rows, overrides);
 
-    assert_error_matches(result, "rlc bytes fit into u8");
+    assert_errors_match(
+        result,
+        &[
+            "rlc bytes fit into u8",
+            "mpt update lookup",
+            "mpt update reverse lookup",
+        ],
+    );
 }
 
 #[test]
@@ -867,6 +899,7 @@ fn invalid_tags() {
             ((AdviceColumn::TagBit1, first_row_offset), bits[1]),
             ((AdviceColumn::TagBit2, first_row_offset), bits[2]),
             ((AdviceColumn::TagBit3, first_row_offset), bits[3]),
+            ((AdviceColumn::Tag, first_row_offset), Fr::from(i as u64)),
         ]);
 
         let result = prover(vec![], overrides).verify_at_rows(0..1, 0..1);
@@ -904,7 +937,10 @@ fn bad_initial_tx_access_list_account_value() {
         is_warm_prev: false,
     }];
 
-    let overrides = HashMap::from([((AdviceColumn::InitialValue, 0), Fr::from(1))]);
+    let overrides = HashMap::from([
+        ((AdviceColumn::InitialValue, 0), Fr::from(1)),
+        ((AdviceColumn::ValuePrev, 0), Fr::from(1)),
+    ]);
 
     assert_error_matches(
         verify_with_overrides(rows, overrides),
@@ -926,6 +962,7 @@ fn bad_initial_tx_refund_value() {
         ((AdviceColumn::IsWrite, 0), Fr::from(1)),
         ((AdviceColumn::Value, 0), Fr::from(10)),
         ((AdviceColumn::InitialValue, 0), Fr::from(10)),
+        ((AdviceColumn::ValuePrev, 0), Fr::from(10)),
     ]);
 
     assert_error_matches(
@@ -976,11 +1013,118 @@ fn bad_initial_tx_receipt_value() {
     );
 }
 
+#[test]
+fn bad_first_access_value_prev() {
+    let rows = vec![Rw::TxRefund {
+        rw_counter: 1,
+        is_write: true,
+        tx_id: 1,
+        value: 20,
+        value_prev: 0,
+    }];
+
+    let overrides = HashMap::from([((AdviceColumn::ValuePrev, 0), Fr::from(7))]);
+
+    assert_error_matches(
+        verify_with_overrides(rows, overrides),
+        "first access value_prev is initial_value",
+    );
+}
+
+#[test]
+fn bad_non_first_access_value_prev() {
+    let rows = vec![
+        Rw::Account {
+            rw_counter: 1,
+            is_write: true,
+            account_address: Address::default(),
+            field_tag: AccountFieldTag::Nonce,
+            value: U256::from(5),
+            value_prev: U256::zero(),
+        },
+        Rw::Account {
+            rw_counter: 2,
+            is_write: true,
+            account_address: Address::default(),
+            field_tag: AccountFieldTag::Nonce,
+            value: U256::from(6),
+            value_prev: U256::from(5),
+        },
+    ];
+
+    let overrides = HashMap::from([((AdviceColumn::ValuePrev, 1), Fr::from(4))]);
+
+    assert_error_matches(
+        verify_with_overrides(rows, overrides),
+        "non-first access value_prev is previous value",
+    );
+}
+
+#[test]
+fn nonzero_unused_value_prev() {
+    let rows = vec![Rw::Stack {
+        rw_counter: 1,
+        is_write: true,
+        call_id: 1,
+        stack_pointer: 1023,
+        value: U256::from(10),
+    }];
+
+    let overrides = HashMap::from([((AdviceColumn::ValuePrev, 0), Fr::from(10))]);
+
+    assert_error_matches(
+        verify_with_overrides(rows, overrides),
+        "value_prev is 0 when unused",
+    );
+}
+
+#[test]
+fn bad_committed_value() {
+    let rows = vec![Rw::AccountStorage {
+        rw_counter: 1,
+        is_write: true,
+        account_address: Address::default(),
+        storage_key: U256::from(6),
+        value: U256::from(34),
+        value_prev: U256::from(12),
+        tx_id: 4,
+        committed_value: U256::from(12),
+    }];
+
+    let overrides = HashMap::from([((AdviceColumn::Aux2, 0), Fr::from(34))]);
+
+    assert_error_matches(
+        verify_with_overrides(rows, overrides),
+        "AccountStorage committed value is initial_value",
+    );
+}
+
+#[test]
+fn nonzero_aux2() {
+    let rows = vec![Rw::Account {
+        rw_counter: 1,
+        is_write: false,
+        account_address: Address::default(),
+        field_tag: AccountFieldTag::Balance,
+        value: U256::zero(),
+        value_prev: U256::zero(),
+    }];
+
+    let overrides = HashMap::from([((AdviceColumn::Aux2, 0), Fr::from(1))]);
+
+    assert_error_matches(
+        verify_with_overrides(rows, overrides),
+        "aux2 is 0 for non-AccountStorage",
+    );
+}
+
 fn prover(rows: Vec<Rw>, overrides: HashMap<(AdviceColumn, isize), Fr>) -> MockProver<Fr> {
     let randomness = Fr::rand();
+    let updates = MptUpdates::new(&rows);
     let circuit = StateCircuit::<Fr, N_ROWS> {
         randomness,
         rows,
+        updates,
         overrides,
     };
     let power_of_randomness = circuit.instance();
@@ -1009,9 +1153,19 @@ fn verify_with_overrides(
 }
 
 fn assert_error_matches(result: Result<(), Vec<VerifyFailure>>, name: &str) {
+    assert_errors_match(result, &[name]);
+}
+
+fn assert_errors_match(result: Result<(), Vec<VerifyFailure>>, names: &[&str]) {
     let errors = result.err().expect("result is not an error");
-    assert_eq!(errors.len(), 1, "{:?}", errors);
-    match &errors[0] {
+    assert_eq!(errors.len(), names.len(), "{:?}", errors);
+    for (error, name) in errors.iter().zip(names) {
+        assert_failure_matches(error, name);
+    }
+}
+
+fn assert_failure_matches(error: &VerifyFailure, name: &str) {
+    match error {
         VerifyFailure::ConstraintNotSatisfied { constraint, .. } => {
             // fields of halo2_proofs::dev::metadata::Constraint aren't public, so we have
             // to match off of its format string.
@@ -0,0 +1,480 @@
+//! The super circuit is a circuit that contains all the circuits of the
+//! zkEVM in order to achieve two things:
+//! - Check the correct integration between circuits via the shared lookup
+//!   tables, to verify that the table layouts match.
+//! - Allow having a single circuit setup for which a proof can be generated
+//!   that proves the whole block, with the cross-circuit lookups enforced.
+//!
+//! The circuits share the following tables:
+//! - Tx table: assigned by the TxCircuit, looked up by the EVM and Copy
+//!   circuits.
+//! - Rw table: assigned by the StateCircuit, looked up by the EVM and Copy
+//!   circuits.
+//! - Bytecode table: assigned by the bytecode circuit, looked up by the EVM and
+//!   Copy circuits.
+//! - Copy table and Exp table: assigned by the Copy and Exp circuits, looked up
+//!   by the EVM circuit.
+//! - Keccak table: assigned by the Keccak circuit, looked up by the EVM,
+//!   bytecode, Tx, public-input and MPT circuits.
+//! - Block table: assigned by the public-input circuit, looked up by the EVM
+//!   circuit, and by the Tx circuit for the chain id.
+//! - MPT table: assigned by the MPT circuit, looked up by the State circuit for
+//!   the initial and final values of the accounts and storage slots.
+//!
+//! The public-input circuit also looks up the public tx hashes in the tx
+//! table, so that the instances bind the block table and the tx table, and
+//! its public state roots are the first and last state roots of the MPT
+//! circuit.
+
+use crate::bytecode_circuit::bytecode_unroller::{
+    unroll, Config as BytecodeConfig, UnrolledBytecode,
+};
+use crate::copy_circuit::CopyCircuitConfig;
+use crate::evm_circuit::{table::FixedTableTag, witness::Block, EvmCircuitConfig};
+use crate::exp_circuit::ExpCircuit;
+use crate::keccak_circuit::{KeccakConfig, KeccakTable};
+use crate::mpt_circuit::{MptConfig, MptTable, MptWitness, WALK_ROWS};
+use crate::pi_circuit::{PiCircuit, PiCircuitConfig};
+use crate::rw_table::RwTable;
+use crate::state_circuit::{StateCircuit, StateConfig};
+use crate::tx_circuit::{TxCircuit, TxCircuitConfig, POW_RAND_SIZE};
+use crate::util::Expr;
+use eth_types::Field;
+use halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner},
+    plonk::{Circuit, ConstraintSystem, Error},
+    poly::Rotation,
+};
+use rand::RngCore;
+use strum::IntoEnumIterator;
+
+/// Configuration of the Super Circuit
+#[derive(Clone)]
+pub struct SuperCircuitConfig<F: Field> {
+    evm_circuit: EvmCircuitConfig<F>,
+    state_circuit: StateConfig,
+    tx_circuit: TxCircuitConfig<F>,
+    pi_circuit: PiCircuitConfig<F>,
+    bytecode_circuit: BytecodeConfig<F>,
+    keccak_circuit: KeccakConfig<F>,
+    copy_circuit: CopyCircuitConfig<F>,
+    exp_circuit: ExpCircuit<F>,
+    mpt_circuit: MptConfig<F>,
+}
+
+/// The Super Circuit contains all the zkEVM circuits
+#[derive(Default)]
+pub struct SuperCircuit<
+    F: Field,
+    const MAX_TXS: usize,
+    const MAX_CALLDATA: usize,
+    const MAX_RWS: usize,
+> {
+    /// EVM Circuit
+    pub block: Block<F>,
+    /// Fixed table tags loaded in the EVM Circuit
+    pub fixed_table_tags: Vec<FixedTableTag>,
+    /// Tx Circuit
+    pub tx_circuit: TxCircuit<F, MAX_TXS, MAX_CALLDATA>,
+    /// Public Input Circuit
+    pub pi_circuit: PiCircuit<F, MAX_TXS>,
+    /// Number of rows of the circuit, which the Bytecode and Keccak Circuits
+    /// fill
+    pub bytecode_size: usize,
+}
+
+impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize, const MAX_RWS: usize>
+    SuperCircuit<F, MAX_TXS, MAX_CALLDATA, MAX_RWS>
+{
+    /// Build a SuperCircuit of `2^k` rows from a witness block.  `rng` is only
+    /// used to sample the auxiliary generator of the signature verification.
+    pub fn build_from_witness_block(block: Block<F>, k: u32, rng: impl RngCore) -> Self {
+        let tx_circuit = TxCircuit::new_from_block(&block, k, rng);
+        let pi_circuit = PiCircuit::new(&block, k);
+
+        Self {
+            block,
+            fixed_table_tags: FixedTableTag::iter().collect(),
+            tx_circuit,
+            pi_circuit,
+            bytecode_size: 1 << k,
+        }
+    }
+
+    /// Returns the instance columns of the circuit: the powers of randomness,
+    /// followed by the (empty) instance column of the signature verification
+    /// and the public inputs of the public-input circuit.
+    pub fn instance(&self) -> Vec<Vec<F>> {
+        let mut instance: Vec<Vec<F>> = (1..POW_RAND_SIZE + 1)
+            .map(|exp| {
+                vec![self.block.randomness.pow(&[exp as u64, 0, 0, 0]); self.bytecode_size - 64]
+            })
+            .collect();
+        // SignVerifyChip -> ECDSAChip -> MainGate instance column
+        instance.push(vec![]);
+        instance.push(self.pi_circuit.public_inputs());
+        instance
+    }
+}
+
+impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize, const MAX_RWS: usize> Circuit<F>
+    for SuperCircuit<F, MAX_TXS, MAX_CALLDATA, MAX_RWS>
+{
+    type Config = SuperCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            block: self.block.without_witnesses(),
+            fixed_table_tags: self.fixed_table_tags.clone(),
+            tx_circuit: self.tx_circuit.without_witnesses(),
+            pi_circuit: self.pi_circuit.without_witnesses(),
+            bytecode_size: self.bytecode_size,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let tx_table = [(); 4].map(|_| meta.advice_column());
+        let rw_table = RwTable::construct(meta);
+        let bytecode_table = [(); 5].map(|_| meta.advice_column());
+        let block_table = [(); 3].map(|_| meta.advice_column());
+        let keccak_table = KeccakTable::construct(meta);
+        let mpt_table = MptTable::construct(meta);
+
+        // This gate is used just to get the array of expressions from the power of
+        // randomness instance column, so that later on we don't need to query
+        // columns everywhere, and can pass the power of randomness array
+        // expression everywhere.  The gate itself doesn't add any constraints.
+        let power_of_randomness_columns = [(); POW_RAND_SIZE].map(|_| meta.instance_column());
+        let power_of_randomness = {
+            let mut power_of_randomness = None;
+
+            meta.create_gate("power of randomness", |meta| {
+                power_of_randomness = Some(
+                    power_of_randomness_columns
+                        .map(|column| meta.query_instance(column, Rotation::cur())),
+                );
+
+                [0.expr()]
+            });
+
+            power_of_randomness.unwrap()
+        };
+        let power_of_randomness_31: [_; 31] =
+            array_init::array_init(|i| power_of_randomness[i].clone());
+
+        let copy_circuit = CopyCircuitConfig::configure(
+            meta,
+            power_of_randomness[0].clone(),
+            &tx_table,
+            &rw_table,
+            &bytecode_table,
+        );
+        let exp_circuit = ExpCircuit::configure(meta);
+        let evm_circuit = EvmCircuitConfig::configure(
+            meta,
+            power_of_randomness_31.clone(),
+            &tx_table,
+            &rw_table,
+            &bytecode_table,
+            &block_table,
+            &copy_circuit,
+            &exp_circuit,
+            &keccak_table,
+        );
+        let state_circuit = StateConfig::configure(
+            meta,
+            array_init::array_init(|i| power_of_randomness_columns[i]),
+            rw_table,
+            mpt_table,
+        );
+        let tx_circuit = TxCircuitConfig::new(
+            meta,
+            power_of_randomness,
+            tx_table,
+            block_table,
+            keccak_table,
+        );
+        let pi_circuit = PiCircuitConfig::new(
+            meta,
+            power_of_randomness_31[0].clone(),
+            block_table,
+            tx_table,
+            keccak_table,
+        );
+        let mpt_circuit = MptConfig::configure(
+            meta,
+            power_of_randomness_31.clone(),
+            keccak_table,
+            mpt_table,
+        );
+        // The keccak and bytecode circuits are configured last because they
+        // record the minimum number of rows of the whole circuit to place their
+        // last row.
+        let keccak_circuit =
+            KeccakConfig::configure(meta, power_of_randomness_31.clone(), keccak_table);
+        let bytecode_circuit =
+            BytecodeConfig::configure(meta, power_of_randomness_31, bytecode_table, keccak_table);
+
+        Self::Config {
+            evm_circuit,
+            state_circuit,
+            tx_circuit,
+            pi_circuit,
+            bytecode_circuit,
+            keccak_circuit,
+            copy_circuit,
+            exp_circuit,
+            mpt_circuit,
+        }
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let randomness = self.block.randomness;
+
+        // --- EVM Circuit ---
+        config
+            .evm_circuit
+            .load_fixed_table(&mut layouter, self.fixed_table_tags.clone())?;
+        config.evm_circuit.load_byte_table(&mut layouter)?;
+        config
+            .evm_circuit
+            .assign_block(&mut layouter, &self.block)?;
+        // --- State Circuit ---
+        let state_circuit = StateCircuit::<F, MAX_RWS>::new(randomness, self.block.rws.clone());
+        state_circuit.synthesize_sub(&config.state_circuit, &mut layouter)?;
+        // --- MPT Circuit ---
+        // The walks of the updates take a sixteenth of the rows, and the RLP
+        // streams of their trie nodes an eighth.
+        let mpt_witness = MptWitness::new(
+            &state_circuit.updates,
+            &self.block.trie_nodes,
+            self.block.context.prev_state_root,
+            randomness,
+        )?;
+        let (prev_state_root, state_root) = config.mpt_circuit.assign(
+            &mut layouter,
+            &mpt_witness,
+            self.bytecode_size / (WALK_ROWS * 16),
+            self.bytecode_size / 8,
+            randomness,
+        )?;
+        // --- Tx Circuit ---
+        self.tx_circuit
+            .synthesize_sub(&config.tx_circuit, &mut layouter)?;
+        // --- Public Input Circuit ---
+        self.pi_circuit
+            .synthesize_sub(&config.pi_circuit, &mut layouter)?;
+        config.pi_circuit.constrain_state_roots(
+            &mut layouter,
+            prev_state_root.cell(),
+            state_root.cell(),
+        )?;
+        // --- Bytecode Circuit ---
+        let bytecodes: Vec<UnrolledBytecode<F>> = self
+            .block
+            .bytecodes
+            .values()
+            .map(|bytecode| unroll(bytecode.bytes.clone(), randomness))
+            .collect();
+        config.bytecode_circuit.load(&mut layouter)?;
+        config.bytecode_circuit.assign(
+            &mut layouter,
+            self.bytecode_size,
+            &bytecodes,
+            randomness,
+        )?;
+        // --- Copy Circuit ---
+        config
+            .copy_circuit
+            .assign_block(&mut layouter, &self.block)?;
+        // --- Exponentiation Circuit ---
+        config
+            .exp_circuit
+            .assign_block(&mut layouter, &self.block)?;
+        // --- Keccak Circuit ---
+        let keccak_inputs: Vec<Vec<u8>> = self
+            .block
+            .sha3_inputs
+            .iter()
+            .cloned()
+            .chain(
+                self.block
+                    .bytecodes
+                    .values()
+                    .map(|bytecode| bytecode.bytes.clone()),
+            )
+            .chain(self.tx_circuit.keccak_inputs()?)
+            .chain(self.pi_circuit.keccak_inputs())
+            .chain(mpt_witness.keccak_inputs())
+            .collect();
+        config.keccak_circuit.assign(
+            &mut layouter,
+            self.bytecode_size,
+            &keccak_inputs,
+            randomness,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod super_circuit_tests {
+    use super::*;
+    use crate::evm_circuit::witness::block_convert;
+    use crate::mpt_circuit::MptUpdates;
+    use bus_mapping::mock::BlockData;
+    use eth_types::{bytecode, geth_types::GethData, Bytes, ToBigEndian, Word, H256};
+    use ethers_core::{types::TransactionRequest, utils::keccak256};
+    use ethers_signers::{LocalWallet, Signer};
+    use halo2_proofs::{
+        dev::MockProver,
+        pairing::bn256::{Bn256, Fr, G1Affine},
+        plonk::{create_proof, keygen_pk, keygen_vk, verify_proof, SingleVerifier},
+        poly::commitment::{Params, ParamsVerifier},
+        transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+    };
+    use mock::{eth, TestContext, MOCK_CHAIN_ID};
+    use pretty_assertions::assert_eq;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    /// Return the state root after `block`, as computed by the MPT circuit
+    /// from the state trie of the mock accounts.
+    fn state_root_after(block: &GethData) -> Word {
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+        let block = block_convert(&builder.block, &builder.code_db);
+        let rows: Vec<_> = block.rws.0.values().flatten().cloned().collect();
+        MptWitness::new(
+            &MptUpdates::new(&rows),
+            &block.trie_nodes,
+            block.context.prev_state_root,
+            block.randomness,
+        )
+        .unwrap()
+        .state_root()
+    }
+
+    const MAX_TXS: usize = 1;
+    const MAX_CALLDATA: usize = 32;
+    const MAX_RWS: usize = 1 << 16;
+    // The smallest degree which fits the range tables of the signature
+    // verification.
+    const K: u32 = 19;
+
+    fn build_circuit(rng: &mut ChaCha20Rng) -> SuperCircuit<Fr, MAX_TXS, MAX_CALLDATA, MAX_RWS> {
+        let chain_id = MOCK_CHAIN_ID.as_u64();
+
+        let wallet = LocalWallet::new(rng).with_chain_id(chain_id);
+        let callee = LocalWallet::new(rng).address();
+        let code = bytecode! {
+            PUSH1(0x20)
+            PUSH1(0x00)
+            PUSH1(0x00)
+            CALLDATACOPY
+            STOP
+        };
+        let call_data = Bytes::from(b"hello");
+
+        // Sign the transaction with the same fields as the mock transaction
+        let tx = TransactionRequest::new()
+            .from(wallet.address())
+            .to(callee)
+            .nonce(0)
+            .value(1000)
+            .data(call_data.clone())
+            .gas(500_000)
+            .gas_price(1234);
+        let sighash = keccak256(tx.rlp(chain_id).as_ref()).into();
+        let sig = wallet.sign_hash(sighash, true);
+
+        let mut block: GethData = TestContext::<2, 1>::new(
+            None,
+            |accs| {
+                accs[0].address(callee).balance(eth(10)).code(code);
+                accs[1].address(wallet.address()).balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0]
+                    .from(accs[1].address)
+                    .to(accs[0].address)
+                    .nonce(Word::from(0))
+                    .value(Word::from(1000))
+                    .input(call_data)
+                    .gas(Word::from(500_000))
+                    .gas_price(Word::from(1234))
+                    .sig_data((sig.v, sig.r, sig.s));
+            },
+            |block, _tx| block,
+        )
+        .unwrap()
+        .into();
+        // The state root of the header is the one after the updates of the
+        // block to the state trie of the mock accounts.
+        block.eth_block.state_root = H256(state_root_after(&block).to_be_bytes());
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+        let block = block_convert(&builder.block, &builder.code_db);
+
+        SuperCircuit::build_from_witness_block(block, K, rng)
+    }
+
+    // High memory usage test.  Run in serial with:
+    // `cargo test [...] serial_ -- --ignored --test-threads 1`
+    #[ignore]
+    #[test]
+    fn serial_test_super_circuit() {
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        let circuit = build_circuit(&mut rng);
+        let instance = circuit.instance();
+        let prover = MockProver::<Fr>::run(K, &circuit, instance).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    /// The keys generated from the circuit without witnesses, as the prover
+    /// does, must prove and verify the circuit with its witnesses.
+    #[test]
+    fn test_super_circuit_keygen_without_witnesses() {
+        let mut rng = ChaCha20Rng::seed_from_u64(2);
+        let circuit = build_circuit(&mut rng);
+        let instance = circuit.instance();
+        let instance_slices: Vec<&[Fr]> = instance.iter().map(Vec::as_slice).collect();
+
+        let params = Params::<G1Affine>::unsafe_setup::<Bn256>(K);
+        let vk = keygen_vk(&params, &circuit.without_witnesses()).unwrap();
+        let pk = keygen_pk(&params, vk, &circuit.without_witnesses()).unwrap();
+
+        let mut transcript = Blake2bWrite::<_, G1Affine, Challenge255<_>>::init(vec![]);
+        create_proof(
+            &params,
+            &pk,
+            &[circuit],
+            &[&instance_slices[..]],
+            &mut rng,
+            &mut transcript,
+        )
+        .unwrap();
+        let proof = transcript.finalize();
+
+        let verifier_params: ParamsVerifier<Bn256> = params.verifier((1 << K) - 64).unwrap();
+        let mut transcript = Blake2bRead::<_, G1Affine, Challenge255<_>>::init(&proof[..]);
+        assert!(verify_proof(
+            &verifier_params,
+            pk.get_vk(),
+            SingleVerifier::new(&verifier_params),
+            &[&instance_slices[..]],
+            &mut transcript,
+        )
+        .is_ok());
+    }
+}
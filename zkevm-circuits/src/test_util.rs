@@ -35,6 +35,7 @@ pub fn get_fixed_table(conf: FixedTableConfig) -> Vec<FixedTableTag> {
                 FixedTableTag::SignByte,
                 FixedTableTag::ResponsibleOpcode,
                 FixedTableTag::Pow2,
+                FixedTableTag::ConstantGasCost,
             ]
         }
         FixedTableConfig::Complete => FixedTableTag::iter().collect(),
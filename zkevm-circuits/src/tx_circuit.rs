@@ -6,15 +6,24 @@
 
 pub mod sign_verify;
 
+use crate::evm_circuit::{
+    table::{BlockContextFieldTag, LookupTable, TxContextFieldTag},
+    util::{constraint_builder::BaseConstraintBuilder, not},
+    witness::{self, Block},
+};
+use crate::keccak_circuit::{KeccakConfig, KeccakTable};
+use crate::rlp_circuit::RlpConfig;
 use crate::util::{random_linear_combine_word as rlc, Expr};
 use eth_types::{
     geth_types::Transaction, Address, Field, ToBigEndian, ToLittleEndian, ToScalar, Word,
 };
 use ff::PrimeField;
-use group::GroupEncoding;
+use gadgets::is_zero::{IsZeroChip, IsZeroConfig, IsZeroInstruction};
+use group::{Curve, Group, GroupEncoding};
 use halo2_proofs::{
+    arithmetic::CurveAffine,
     circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner},
-    plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, VirtualCells},
     poly::Rotation,
 };
 use itertools::Itertools;
@@ -23,10 +32,13 @@ use libsecp256k1;
 use log::error;
 use num::Integer;
 use num_bigint::BigUint;
+use rand::RngCore;
 use rlp::RlpStream;
 use secp256k1::Secp256k1Affine;
 use sha3::{Digest, Keccak256};
-use sign_verify::{pk_bytes_swap_endianness, SignData, SignVerifyChip, SignVerifyConfig};
+use sign_verify::{
+    keccak_inputs_sign_verify, pk_bytes_swap_endianness, SignData, SignVerifyChip, SignVerifyConfig,
+};
 pub use sign_verify::{POW_RAND_SIZE, VERIF_HEIGHT};
 use std::convert::TryInto;
 use std::marker::PhantomData;
@@ -85,6 +97,146 @@ fn ct_option_ok_or<T, E>(v: CtOption<T>, err: E) -> Result<T, E> {
     Option::<T>::from(v).ok_or(err)
 }
 
+/// Maximum number of bytes of the RLP of a signed transaction besides its
+/// calldata: the headers of the list and of the calldata take at most 5 bytes
+/// each, the nonce, gas and chain id at most 9 bytes each, the gas price and
+/// value at most 33 bytes each, the callee 21 bytes, and the two zeros 1 byte
+/// each.
+const SIGN_RLP_MAX_OVERHEAD: usize = 128;
+
+/// Maximum number of bytes of the RLP of a transaction besides its calldata:
+/// the same as `SIGN_RLP_MAX_OVERHEAD` with `v` instead of the chain id, and
+/// the `r` and `s` words of the signature instead of the two zeros.
+const TX_RLP_MAX_OVERHEAD: usize = 192;
+
+/// Item of the calldata in the RLP of a signed transaction
+const SIGN_RLP_ITEM_DATA: u64 = 6;
+
+/// Number of items in the RLP of a signed transaction, and of a transaction
+const SIGN_RLP_ITEMS: u64 = 9;
+
+/// How a field of the tx table is checked against its item in the RLP of the
+/// signed transaction.  The fields checked with `Value`, `Rlc` and `Len` are
+/// also checked against the same item of the RLP of the transaction.
+#[derive(Clone, Copy, Debug)]
+enum SignRlpLookup {
+    /// The field is the big-endian value of the item
+    Value(u64),
+    /// The item is zero, and the field isn't checked
+    Zero(u64),
+    /// The field is the RLC of the item
+    Rlc(u64),
+    /// The chain id of the block is the RLC of the item
+    ChainId(u64),
+    /// The field is the length of the item
+    Len(u64),
+    /// The field is the hash of the RLP, which has `SIGN_RLP_ITEMS` items
+    Hash,
+    /// The field is the hash of the RLP of the transaction, which has
+    /// `SIGN_RLP_ITEMS` items
+    TxHash,
+}
+
+impl SignRlpLookup {
+    /// Returns the lookup of the tx table field with `tag`, in the order of
+    /// the signed items `[nonce, gas_price, gas, to, value, data, chain_id,
+    /// 0, 0]`.
+    fn from_tag(tag: TxContextFieldTag) -> Option<Self> {
+        match tag {
+            TxContextFieldTag::Nonce => Some(Self::Value(1)),
+            TxContextFieldTag::GasPrice => Some(Self::Rlc(2)),
+            TxContextFieldTag::Gas => Some(Self::Value(3)),
+            TxContextFieldTag::CalleeAddress => Some(Self::Value(4)),
+            TxContextFieldTag::Value => Some(Self::Rlc(5)),
+            TxContextFieldTag::CallDataLength => Some(Self::Len(SIGN_RLP_ITEM_DATA)),
+            // The caller address is recovered from the signature, so its row
+            // is used to check the chain id.
+            TxContextFieldTag::CallerAddress => Some(Self::ChainId(7)),
+            // The rows of the fields that are not part of the RLP check the
+            // zeros at the end of the list.
+            TxContextFieldTag::IsCreate => Some(Self::Zero(8)),
+            TxContextFieldTag::CallDataGasCost => Some(Self::Zero(9)),
+            TxContextFieldTag::TxSignHash => Some(Self::Hash),
+            TxContextFieldTag::TxHash => Some(Self::TxHash),
+            _ => None,
+        }
+    }
+
+    /// Returns the item of the lookup
+    fn item(&self) -> u64 {
+        match self {
+            Self::Value(item)
+            | Self::Zero(item)
+            | Self::Rlc(item)
+            | Self::ChainId(item)
+            | Self::Len(item) => *item,
+            Self::Hash | Self::TxHash => SIGN_RLP_ITEMS,
+        }
+    }
+}
+
+/// Returns the RLP of `tx` that is hashed and signed:
+/// `rlp([nonce, gas_price, gas, to, value, data, chain_id, 0, 0])`
+fn tx_sign_rlp(tx: &Transaction, chain_id: u64) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(SIGN_RLP_ITEMS as usize);
+    stream
+        .append(&tx.nonce)
+        .append(&tx.gas_price)
+        .append(&tx.gas_limit)
+        .append(&tx.to.unwrap_or_else(Address::zero))
+        .append(&tx.value)
+        .append(&tx.call_data.0)
+        .append(&chain_id)
+        .append(&0u32)
+        .append(&0u32);
+    stream.out().to_vec()
+}
+
+/// Returns the RLP of `tx` whose keccak hash is the tx hash:
+/// `rlp([nonce, gas_price, gas, to, value, data, v, r, s])`, where `to` is
+/// empty for contract creations.
+fn tx_rlp(tx: &Transaction) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(SIGN_RLP_ITEMS as usize);
+    stream
+        .append(&tx.nonce)
+        .append(&tx.gas_price)
+        .append(&tx.gas_limit);
+    match tx.to {
+        Some(to) => stream.append(&to),
+        None => stream.append_empty_data(),
+    };
+    stream
+        .append(&tx.value)
+        .append(&tx.call_data.0)
+        .append(&tx.v)
+        .append(&tx.r)
+        .append(&tx.s);
+    stream.out().to_vec()
+}
+
+/// Returns the hash of `tx`, which is the keccak hash of its RLP.
+pub(crate) fn tx_hash(tx: &Transaction) -> Word {
+    Word::from_big_endian(Keccak256::digest(&tx_rlp(tx)).as_slice())
+}
+
+/// Returns the transaction of the witness `tx`, with the fields that the
+/// TxCircuit checks.
+pub(crate) fn tx_from_witness(tx: &witness::Transaction) -> Transaction {
+    Transaction {
+        from: tx.caller_address,
+        to: (!tx.is_create).then(|| tx.callee_address),
+        nonce: Word::from(tx.nonce),
+        gas_limit: Word::from(tx.gas),
+        value: tx.value,
+        gas_price: tx.gas_price,
+        call_data: tx.call_data.clone().into(),
+        v: tx.v,
+        r: tx.r,
+        s: tx.s,
+        ..Default::default()
+    }
+}
+
 fn tx_to_sign_data(tx: &Transaction, chain_id: u64) -> Result<SignData, Error> {
     let sig_r_le = tx.r.to_le_bytes();
     let sig_s_le = tx.s.to_le_bytes();
@@ -98,19 +250,7 @@ fn tx_to_sign_data(tx: &Transaction, chain_id: u64) -> Result<SignData, Error> {
             error!("Invalid 's' signature value");
             e
         })?;
-    // msg = rlp([nonce, gasPrice, gas, to, value, data, sig_v, r, s])
-    let mut stream = RlpStream::new_list(9);
-    stream
-        .append(&tx.nonce)
-        .append(&tx.gas_price)
-        .append(&tx.gas_limit)
-        .append(&tx.to.unwrap_or_else(Address::zero))
-        .append(&tx.value)
-        .append(&tx.call_data.0)
-        .append(&chain_id)
-        .append(&0u32)
-        .append(&0u32);
-    let msg = stream.out();
+    let msg = tx_sign_rlp(tx, chain_id);
     let msg_hash: [u8; 32] = Keccak256::digest(&msg)
         .as_slice()
         .to_vec()
@@ -134,37 +274,6 @@ fn tx_to_sign_data(tx: &Transaction, chain_id: u64) -> Result<SignData, Error> {
     })
 }
 
-// TODO: Deduplicate with
-// `zkevm-circuits/src/evm_circuit/table.rs::TxContextFieldTag`.
-/// Tag used to identify each field in the transaction in a row of the
-/// transaction table.
-#[derive(Clone, Copy, Debug)]
-pub enum TxFieldTag {
-    /// Unused tag
-    Null = 0,
-    /// Nonce
-    Nonce,
-    /// Gas
-    Gas,
-    /// GasPrice
-    GasPrice,
-    /// CallerAddress
-    CallerAddress,
-    /// CalleeAddress
-    CalleeAddress,
-    /// IsCreate
-    IsCreate,
-    /// Value
-    Value,
-    /// CallDataLength
-    CallDataLength,
-    /// TxSignHash: Hash of the transaction without the signature, used for
-    /// signing.
-    TxSignHash,
-    /// CallData
-    CallData,
-}
-
 /// Config for TxCircuit
 #[derive(Clone, Debug)]
 pub struct TxCircuitConfig<F: Field> {
@@ -172,47 +281,376 @@ pub struct TxCircuitConfig<F: Field> {
     tag: Column<Advice>,
     index: Column<Advice>,
     value: Column<Advice>,
+    /// Whether the row is a field of a tx
+    q_field: Column<Fixed>,
+    /// Whether the row is a byte of calldata
+    q_calldata: Column<Fixed>,
+    /// Tag of the row, fixed by its offset
+    fixed_tag: Column<Fixed>,
+    /// Tx id of the field rows, fixed by their offset
+    fixed_tx_id: Column<Fixed>,
+    q_caller: Column<Fixed>,
+    q_sign_hash: Column<Fixed>,
+    q_tx_hash: Column<Fixed>,
+    q_rlp_value: Column<Fixed>,
+    q_rlp_zero: Column<Fixed>,
+    q_rlp_rlc: Column<Fixed>,
+    q_rlp_len: Column<Fixed>,
+    /// Item of the RLP looked up by the row
+    rlp_item: Column<Fixed>,
+    /// Whether the tx of the row is not padding, that is, its caller is not
+    /// zero
+    is_enabled: Column<Advice>,
+    caller_is_zero: IsZeroConfig<F>,
+    calldata_tx_id_is_zero: IsZeroConfig<F>,
+    /// RLC of the chain id, on the rows of the tx fields
+    chain_id: Column<Advice>,
     sign_verify: SignVerifyConfig<F>,
+    rlp: RlpConfig<F>,
     _marker: PhantomData<F>,
 }
 
 impl<F: Field> TxCircuitConfig<F> {
-    fn new(meta: &mut ConstraintSystem<F>) -> Self {
-        let tx_id = meta.advice_column();
-        let tag = meta.advice_column();
-        let index = meta.advice_column();
-        let value = meta.advice_column();
+    /// Return a new TxCircuitConfig that assigns its rows into `tx_table`,
+    /// which is laid out as `[tx_id, tag, index, value]` so that it can be
+    /// shared with the EVM circuit, and looks up the chain id in the shared
+    /// `block_table`, and the public key hashes and the hashes of the RLP of
+    /// the txs and of the signed txs in the shared `keccak_table`.
+    pub fn new(
+        meta: &mut ConstraintSystem<F>,
+        power_of_randomness: [Expression<F>; POW_RAND_SIZE],
+        tx_table: [Column<Advice>; 4],
+        block_table: [Column<Advice>; 3],
+        keccak_table: KeccakTable,
+    ) -> Self {
+        let [tx_id, tag, index, value] = tx_table;
         meta.enable_equality(value);
 
-        // This gate is used just to get the array of expressions from the power of
-        // randomness instance column, so that later on we don't need to query
-        // columns everywhere, and can pass the power of randomness array
-        // expression everywhere.  The gate itself doesn't add any constraints.
-        let power_of_randomness = {
-            let columns = [(); sign_verify::POW_RAND_SIZE].map(|_| meta.instance_column());
-            let mut power_of_randomness = None;
+        let q_field = meta.fixed_column();
+        let q_calldata = meta.fixed_column();
+        let fixed_tag = meta.fixed_column();
+        let fixed_tx_id = meta.fixed_column();
+        let q_caller = meta.fixed_column();
+        let q_sign_hash = meta.fixed_column();
+        let q_tx_hash = meta.fixed_column();
+        let q_rlp_value = meta.fixed_column();
+        let q_rlp_zero = meta.fixed_column();
+        let q_rlp_rlc = meta.fixed_column();
+        let q_rlp_len = meta.fixed_column();
+        let rlp_item = meta.fixed_column();
+        let is_enabled = meta.advice_column();
+        let chain_id = meta.advice_column();
 
-            meta.create_gate("power of randomness", |meta| {
-                power_of_randomness =
-                    Some(columns.map(|column| meta.query_instance(column, Rotation::cur())));
+        let caller_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_caller, Rotation::cur()),
+            |meta| meta.query_advice(value, Rotation::cur()),
+            meta.advice_column(),
+        );
+        let calldata_tx_id_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_fixed(q_calldata, Rotation::cur()),
+            |meta| meta.query_advice(tx_id, Rotation::cur()),
+            meta.advice_column(),
+        );
 
-                [0.expr()]
+        let rlp = RlpConfig::configure(meta, power_of_randomness[0].clone(), keccak_table);
+        let sign_verify = SignVerifyConfig::new(meta, power_of_randomness, keccak_table);
+
+        meta.create_gate("tx field", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            cb.require_equal(
+                "tag is fixed by the offset",
+                meta.query_advice(tag, Rotation::cur()),
+                meta.query_fixed(fixed_tag, Rotation::cur()),
+            );
+            cb.require_equal(
+                "tx_id is fixed by the offset",
+                meta.query_advice(tx_id, Rotation::cur()),
+                meta.query_fixed(fixed_tx_id, Rotation::cur()),
+            );
+            cb.require_zero("index is 0", meta.query_advice(index, Rotation::cur()));
+            cb.require_zero(
+                "is_enabled is the same in all the fields of a tx",
+                not::expr(meta.query_fixed(q_tx_hash, Rotation::cur()))
+                    * (meta.query_advice(is_enabled, Rotation::next())
+                        - meta.query_advice(is_enabled, Rotation::cur())),
+            );
+            cb.condition(meta.query_fixed(q_caller, Rotation::cur()), |cb| {
+                cb.require_equal(
+                    "is_enabled := caller != 0",
+                    meta.query_advice(is_enabled, Rotation::cur()),
+                    not::expr(caller_is_zero.expr()),
+                );
             });
 
-            power_of_randomness.unwrap()
+            cb.gate(meta.query_fixed(q_field, Rotation::cur()))
+        });
+
+        meta.create_gate("tx calldata", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            cb.require_equal(
+                "tag is CallData",
+                meta.query_advice(tag, Rotation::cur()),
+                meta.query_fixed(fixed_tag, Rotation::cur()),
+            );
+            // The padding rows are all zero, so that they match the disabled
+            // rows of the RLP table.
+            cb.condition(calldata_tx_id_is_zero.expr(), |cb| {
+                cb.require_zero(
+                    "index is 0 in padding",
+                    meta.query_advice(index, Rotation::cur()),
+                );
+                cb.require_zero(
+                    "value is 0 in padding",
+                    meta.query_advice(value, Rotation::cur()),
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_calldata, Rotation::cur()))
+        });
+
+        // Ref. spec 1. The fields of the enabled txs are items of the RLP of
+        // the signed tx, which has id `2 * tx_id - 1`, and of the RLP of the
+        // tx, which has id `2 * tx_id`.
+        let sign_rlp_id = |meta: &mut VirtualCells<F>| {
+            2.expr() * meta.query_advice(tx_id, Rotation::cur()) - 1.expr()
+        };
+        let tx_rlp_id =
+            |meta: &mut VirtualCells<F>| 2.expr() * meta.query_advice(tx_id, Rotation::cur());
+        meta.lookup_any("tx sign rlp value", |meta| {
+            let is_enabled = meta.query_advice(is_enabled, Rotation::cur());
+            let q_rlp_value = meta.query_fixed(q_rlp_value, Rotation::cur());
+            let enable = (q_rlp_value.clone() + meta.query_fixed(q_rlp_zero, Rotation::cur()))
+                * is_enabled.clone();
+            vec![
+                enable.clone() * sign_rlp_id(meta),
+                enable * meta.query_fixed(rlp_item, Rotation::cur()),
+                q_rlp_value * is_enabled * meta.query_advice(value, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(rlp.value_table_exprs(meta))
+            .collect()
+        });
+        meta.lookup_any("tx sign rlp value_rlc", |meta| {
+            let is_enabled = meta.query_advice(is_enabled, Rotation::cur());
+            let q_rlp_rlc = meta.query_fixed(q_rlp_rlc, Rotation::cur());
+            let q_caller = meta.query_fixed(q_caller, Rotation::cur());
+            let enable = (q_rlp_rlc.clone() + q_caller.clone()) * is_enabled.clone();
+            vec![
+                enable.clone() * sign_rlp_id(meta),
+                enable * meta.query_fixed(rlp_item, Rotation::cur()),
+                is_enabled
+                    * (q_rlp_rlc * meta.query_advice(value, Rotation::cur())
+                        + q_caller * meta.query_advice(chain_id, Rotation::cur())),
+            ]
+            .into_iter()
+            .zip(rlp.value_rlc_table_exprs(meta))
+            .collect()
+        });
+        let len_table = RlpConfig::len_table_exprs
+            as fn(&RlpConfig<F>, &mut VirtualCells<F>) -> Vec<Expression<F>>;
+        for (name, q_rlp, is_tx_rlp, table) in [
+            ("tx sign rlp len", q_rlp_len, false, len_table),
+            (
+                "tx rlp value",
+                q_rlp_value,
+                true,
+                RlpConfig::value_table_exprs,
+            ),
+            (
+                "tx rlp value_rlc",
+                q_rlp_rlc,
+                true,
+                RlpConfig::value_rlc_table_exprs,
+            ),
+            ("tx rlp len", q_rlp_len, true, len_table),
+        ] {
+            meta.lookup_any(name, |meta| {
+                let enable = meta.query_fixed(q_rlp, Rotation::cur())
+                    * meta.query_advice(is_enabled, Rotation::cur());
+                let id = if is_tx_rlp {
+                    tx_rlp_id(meta)
+                } else {
+                    sign_rlp_id(meta)
+                };
+                vec![
+                    enable.clone() * id,
+                    enable.clone() * meta.query_fixed(rlp_item, Rotation::cur()),
+                    enable * meta.query_advice(value, Rotation::cur()),
+                ]
+                .into_iter()
+                .zip(table(&rlp, meta))
+                .collect()
+            });
+        }
+        // The TxSignHash and the TxHash are the keccak hashes of the RLPs,
+        // which end after their last items.  The SignVerifyChip checks the
+        // TxSignHash against the signature modulo the order of secp256k1, so
+        // this lookup fails for the negligible fraction of hashes that are not
+        // reduced.
+        let hash_rlp_id = |meta: &mut VirtualCells<F>| {
+            sign_rlp_id(meta) + meta.query_fixed(q_tx_hash, Rotation::cur())
         };
-        let sign_verify = SignVerifyConfig::new(meta, power_of_randomness);
+        meta.lookup_any("tx rlp hash", |meta| {
+            let enable = (meta.query_fixed(q_sign_hash, Rotation::cur())
+                + meta.query_fixed(q_tx_hash, Rotation::cur()))
+                * meta.query_advice(is_enabled, Rotation::cur());
+            vec![
+                enable.clone() * hash_rlp_id(meta),
+                enable * meta.query_advice(value, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(rlp.hash_table_exprs(meta))
+            .collect()
+        });
+        meta.lookup_any("tx rlp items", |meta| {
+            let enable = (meta.query_fixed(q_sign_hash, Rotation::cur())
+                + meta.query_fixed(q_tx_hash, Rotation::cur()))
+                * meta.query_advice(is_enabled, Rotation::cur());
+            vec![
+                enable.clone() * hash_rlp_id(meta),
+                enable * meta.query_fixed(rlp_item, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(rlp.end_table_exprs(meta))
+            .collect()
+        });
+        // The calldata of both RLPs is the calldata of the tx, and the padding
+        // rows match the disabled rows of the RLP table.
+        for (name, is_tx_rlp) in [("tx sign rlp calldata", false), ("tx rlp calldata", true)] {
+            meta.lookup_any(name, |meta| {
+                let enable = meta.query_fixed(q_calldata, Rotation::cur());
+                let is_not_padding = not::expr(calldata_tx_id_is_zero.expr());
+                vec![
+                    enable.clone()
+                        * (2.expr() * meta.query_advice(tx_id, Rotation::cur())
+                            - (!is_tx_rlp).expr() * is_not_padding.clone()),
+                    enable.clone() * is_not_padding * SIGN_RLP_ITEM_DATA.expr(),
+                    enable.clone() * meta.query_advice(index, Rotation::cur()),
+                    enable * meta.query_advice(value, Rotation::cur()),
+                ]
+                .into_iter()
+                .zip(rlp.byte_table_exprs(meta))
+                .collect()
+            });
+        }
+        meta.lookup_any("tx chain id", |meta| {
+            let enable = meta.query_fixed(q_caller, Rotation::cur());
+            vec![
+                enable.clone() * BlockContextFieldTag::ChainId.expr(),
+                0.expr(),
+                enable * meta.query_advice(chain_id, Rotation::cur()),
+            ]
+            .into_iter()
+            .zip(block_table.table_exprs(meta))
+            .collect()
+        });
 
         Self {
             tx_id,
             tag,
             index,
             value,
+            q_field,
+            q_calldata,
+            fixed_tag,
+            fixed_tx_id,
+            q_caller,
+            q_sign_hash,
+            q_tx_hash,
+            q_rlp_value,
+            q_rlp_zero,
+            q_rlp_rlc,
+            q_rlp_len,
+            rlp_item,
+            is_enabled,
+            caller_is_zero,
+            calldata_tx_id_is_zero,
+            chain_id,
             sign_verify,
+            rlp,
             _marker: PhantomData,
         }
     }
 
+    /// Assigns the fixed columns of a tx circuit row: `field` is the tag and
+    /// tx id of a field row, and `is_calldata` marks the calldata rows.
+    fn assign_fixed_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        field: Option<(TxContextFieldTag, usize)>,
+        is_calldata: bool,
+    ) -> Result<(), Error> {
+        let lookup = field.and_then(|(tag, _)| SignRlpLookup::from_tag(tag));
+        let tag = match field {
+            Some((tag, _)) => F::from(tag as u64),
+            None if is_calldata => F::from(TxContextFieldTag::CallData as u64),
+            None => F::zero(),
+        };
+        for (name, column, value) in [
+            ("q_field", self.q_field, F::from(field.is_some() as u64)),
+            ("q_calldata", self.q_calldata, F::from(is_calldata as u64)),
+            ("fixed_tag", self.fixed_tag, tag),
+            (
+                "fixed_tx_id",
+                self.fixed_tx_id,
+                F::from(field.map_or(0, |(_, tx_id)| tx_id) as u64),
+            ),
+            (
+                "q_caller",
+                self.q_caller,
+                F::from(matches!(lookup, Some(SignRlpLookup::ChainId(_))) as u64),
+            ),
+            (
+                "q_sign_hash",
+                self.q_sign_hash,
+                F::from(matches!(lookup, Some(SignRlpLookup::Hash)) as u64),
+            ),
+            (
+                "q_tx_hash",
+                self.q_tx_hash,
+                F::from(matches!(lookup, Some(SignRlpLookup::TxHash)) as u64),
+            ),
+            (
+                "q_rlp_value",
+                self.q_rlp_value,
+                F::from(matches!(lookup, Some(SignRlpLookup::Value(_))) as u64),
+            ),
+            (
+                "q_rlp_zero",
+                self.q_rlp_zero,
+                F::from(matches!(lookup, Some(SignRlpLookup::Zero(_))) as u64),
+            ),
+            (
+                "q_rlp_rlc",
+                self.q_rlp_rlc,
+                F::from(matches!(lookup, Some(SignRlpLookup::Rlc(_))) as u64),
+            ),
+            (
+                "q_rlp_len",
+                self.q_rlp_len,
+                F::from(matches!(lookup, Some(SignRlpLookup::Len(_))) as u64),
+            ),
+            (
+                "rlp_item",
+                self.rlp_item,
+                F::from(lookup.map_or(0, |lookup| lookup.item())),
+            ),
+        ] {
+            region.assign_fixed(
+                || format!("{} {}", name, offset),
+                column,
+                offset,
+                || Ok(value),
+            )?;
+        }
+        Ok(())
+    }
+
     /// Assigns a tx circuit row and returns the assigned cell of the value in
     /// the row.
     fn assign_row(
@@ -220,12 +658,12 @@ impl<F: Field> TxCircuitConfig<F> {
         region: &mut Region<'_, F>,
         offset: usize,
         tx_id: usize,
-        tag: TxFieldTag,
+        tag: F,
         index: usize,
         value: F,
     ) -> Result<AssignedCell<F, F>, Error> {
         region.assign_advice(|| "tx_id", self.tx_id, offset, || Ok(F::from(tx_id as u64)))?;
-        region.assign_advice(|| "tag", self.tag, offset, || Ok(F::from(tag as u64)))?;
+        region.assign_advice(|| "tag", self.tag, offset, || Ok(tag))?;
         region.assign_advice(|| "index", self.index, offset, || Ok(F::from(index as u64)))?;
         region.assign_advice(|| "value", self.value, offset, || Ok(value))
     }
@@ -242,30 +680,48 @@ pub struct TxCircuit<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize>
     pub txs: Vec<Transaction>,
     /// Chain ID
     pub chain_id: u64,
+    /// Number of rows of the circuit, which the Keccak Circuit fills
+    pub size: usize,
 }
 
-impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
-    for TxCircuit<F, MAX_TXS, MAX_CALLDATA>
+impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize>
+    TxCircuit<F, MAX_TXS, MAX_CALLDATA>
 {
-    type Config = TxCircuitConfig<F>;
-    type FloorPlanner = SimpleFloorPlanner;
+    /// Build a TxCircuit of `2^k` rows from the transactions of a witness
+    /// block.  `rng` is only used to sample the auxiliary generator of the
+    /// signature verification.
+    pub fn new_from_block(block: &Block<F>, k: u32, mut rng: impl RngCore) -> Self {
+        let aux_generator =
+            <Secp256k1Affine as CurveAffine>::CurveExt::random(&mut rng).to_affine();
+        let txs = block.txs.iter().map(tx_from_witness).collect();
 
-    fn without_witnesses(&self) -> Self {
-        Self::default()
+        Self {
+            sign_verify: SignVerifyChip {
+                aux_generator,
+                window_size: 2,
+                _marker: PhantomData,
+            },
+            randomness: block.randomness,
+            txs,
+            chain_id: block.context.chain_id.as_u64(),
+            size: 1 << k,
+        }
     }
 
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        TxCircuitConfig::new(meta)
+    /// Returns the instance columns for a domain of `2^k` rows: the powers of
+    /// the randomness, followed by the (empty) instance column of the
+    /// signature verification.
+    pub fn instance(k: u32, randomness: F) -> Vec<Vec<F>> {
+        let mut instance: Vec<Vec<F>> = (1..POW_RAND_SIZE + 1)
+            .map(|exp| vec![randomness.pow(&[exp as u64, 0, 0, 0]); (1 << k) - 64])
+            .collect();
+        // SignVerifyChip -> ECDSAChip -> MainGate instance column
+        instance.push(vec![]);
+        instance
     }
 
-    fn synthesize(
-        &self,
-        config: Self::Config,
-        mut layouter: impl Layouter<F>,
-    ) -> Result<(), Error> {
-        assert!(self.txs.len() <= MAX_TXS);
-        let sign_datas: Vec<SignData> = self
-            .txs
+    fn sign_datas(&self) -> Result<Vec<SignData>, Error> {
+        self.txs
             .iter()
             .map(|tx| {
                 tx_to_sign_data(tx, self.chain_id).map_err(|e| {
@@ -273,20 +729,58 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
                     e
                 })
             })
-            .try_collect()?;
-        let assigned_sig_verifs = self.sign_verify.assign(
-            &config.sign_verify,
-            &mut layouter,
+            .try_collect()
+    }
+
+    /// Return the inputs of the keccak hashes that the TxCircuit looks up in
+    /// the keccak table.
+    pub fn keccak_inputs(&self) -> Result<Vec<Vec<u8>>, Error> {
+        let mut inputs = keccak_inputs_sign_verify(&self.sign_datas()?);
+        inputs.extend(self.rlps());
+        Ok(inputs)
+    }
+
+    /// Returns the RLP of each tx that is hashed and signed, followed by the
+    /// RLP of the tx, so that the ids of the RLP streams of the tx with id
+    /// `tx_id` are `2 * tx_id - 1` and `2 * tx_id`.
+    fn rlps(&self) -> Vec<Vec<u8>> {
+        self.txs
+            .iter()
+            .flat_map(|tx| [tx_sign_rlp(tx, self.chain_id), tx_rlp(tx)])
+            .collect()
+    }
+
+    /// Make the assignments to the TxCircuit
+    pub fn synthesize_sub(
+        &self,
+        config: &TxCircuitConfig<F>,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<(), Error> {
+        assert!(self.txs.len() <= MAX_TXS);
+        let sign_datas = self.sign_datas()?;
+        let assigned_sig_verifs =
+            self.sign_verify
+                .assign(&config.sign_verify, layouter, self.randomness, &sign_datas)?;
+        config.rlp.load(layouter)?;
+        config.rlp.assign(
+            layouter,
+            MAX_TXS * (SIGN_RLP_MAX_OVERHEAD + TX_RLP_MAX_OVERHEAD) + 2 * MAX_CALLDATA,
+            &self.rlps(),
             self.randomness,
-            &sign_datas,
         )?;
 
+        let caller_is_zero_chip = IsZeroChip::construct(config.caller_is_zero.clone());
+        let calldata_tx_id_is_zero_chip =
+            IsZeroChip::construct(config.calldata_tx_id_is_zero.clone());
+        let chain_id = rlc(Word::from(self.chain_id).to_le_bytes(), self.randomness);
+
         layouter.assign_region(
             || "tx table",
             |mut region| {
                 let mut offset = 0;
                 // Empty entry
-                config.assign_row(&mut region, offset, 0, TxFieldTag::Null, 0, F::zero())?;
+                config.assign_fixed_row(&mut region, offset, None, false)?;
+                config.assign_row(&mut region, offset, 0, F::zero(), 0, F::zero())?;
                 offset += 1;
                 // Assign al Tx fields except for call data
                 let tx_default = Transaction::default();
@@ -300,59 +794,85 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
                     let address_cell = assigned_sig_verif.address.cell();
                     let msg_hash_rlc_cell = assigned_sig_verif.msg_hash_rlc.cell();
                     let msg_hash_rlc_value = assigned_sig_verif.msg_hash_rlc.value();
+                    let is_enabled = F::from((tx.from != Address::zero()) as u64);
                     for (tag, value) in &[
+                        (TxContextFieldTag::Nonce, F::from(tx.nonce.as_u64())),
+                        (TxContextFieldTag::Gas, F::from(tx.gas_limit.as_u64())),
                         (
-                            TxFieldTag::Nonce,
-                            rlc(tx.nonce.to_le_bytes(), self.randomness),
-                        ),
-                        (
-                            TxFieldTag::Gas,
-                            rlc(tx.gas_limit.to_le_bytes(), self.randomness),
-                        ),
-                        (
-                            TxFieldTag::GasPrice,
+                            TxContextFieldTag::GasPrice,
                             rlc(tx.gas_price.to_le_bytes(), self.randomness),
                         ),
                         (
-                            TxFieldTag::CallerAddress,
+                            TxContextFieldTag::CallerAddress,
                             tx.from.to_scalar().expect("tx.from too big"),
                         ),
                         (
-                            TxFieldTag::CalleeAddress,
+                            TxContextFieldTag::CalleeAddress,
                             tx.to
                                 .unwrap_or_else(Address::zero)
                                 .to_scalar()
                                 .expect("tx.to too big"),
                         ),
-                        (TxFieldTag::IsCreate, F::from(tx.to.is_none() as u64)),
+                        (TxContextFieldTag::IsCreate, F::from(tx.to.is_none() as u64)),
                         (
-                            TxFieldTag::Value,
+                            TxContextFieldTag::Value,
                             rlc(tx.value.to_le_bytes(), self.randomness),
                         ),
                         (
-                            TxFieldTag::CallDataLength,
+                            TxContextFieldTag::CallDataLength,
                             F::from(tx.call_data.0.len() as u64),
                         ),
                         (
-                            TxFieldTag::TxSignHash,
+                            TxContextFieldTag::CallDataGasCost,
+                            F::from(
+                                tx.call_data
+                                    .0
+                                    .iter()
+                                    .fold(0, |acc, byte| acc + if *byte == 0 { 4 } else { 16 }),
+                            ),
+                        ),
+                        (
+                            TxContextFieldTag::TxSignHash,
                             *msg_hash_rlc_value.unwrap_or(&F::zero()),
                         ),
+                        (
+                            TxContextFieldTag::TxHash,
+                            if i < self.txs.len() {
+                                rlc(tx_hash(tx).to_le_bytes(), self.randomness)
+                            } else {
+                                F::zero()
+                            },
+                        ),
                     ] {
-                        let assigned_cell =
-                            config.assign_row(&mut region, offset, i + 1, *tag, 0, *value)?;
-                        offset += 1;
+                        config.assign_fixed_row(&mut region, offset, Some((*tag, i + 1)), false)?;
+                        let assigned_cell = config.assign_row(
+                            &mut region,
+                            offset,
+                            i + 1,
+                            F::from(*tag as u64),
+                            0,
+                            *value,
+                        )?;
+                        for (name, column, value) in [
+                            ("is_enabled", config.is_enabled, is_enabled),
+                            ("chain_id", config.chain_id, chain_id),
+                        ] {
+                            region.assign_advice(|| name, column, offset, || Ok(value))?;
+                        }
 
                         // Ref. spec 0. Copy constraints using fixed offsets between the tx rows and
                         // the SignVerifyChip
                         match tag {
-                            TxFieldTag::CallerAddress => {
+                            TxContextFieldTag::CallerAddress => {
+                                caller_is_zero_chip.assign(&mut region, offset, Some(*value))?;
                                 region.constrain_equal(assigned_cell.cell(), address_cell)?
                             }
-                            TxFieldTag::TxSignHash => {
+                            TxContextFieldTag::TxSignHash => {
                                 region.constrain_equal(assigned_cell.cell(), msg_hash_rlc_cell)?
                             }
                             _ => (),
                         }
+                        offset += 1;
                     }
                 }
 
@@ -361,11 +881,17 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
                 for (i, tx) in self.txs.iter().enumerate() {
                     for (index, byte) in tx.call_data.0.iter().enumerate() {
                         assert!(calldata_count < MAX_CALLDATA);
+                        config.assign_fixed_row(&mut region, offset, None, true)?;
+                        calldata_tx_id_is_zero_chip.assign(
+                            &mut region,
+                            offset,
+                            Some(F::from((i + 1) as u64)),
+                        )?;
                         config.assign_row(
                             &mut region,
                             offset,
                             i + 1, // tx_id
-                            TxFieldTag::CallData,
+                            F::from(TxContextFieldTag::CallData as u64),
                             index,
                             F::from(*byte as u64),
                         )?;
@@ -374,11 +900,13 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
                     }
                 }
                 for _ in calldata_count..MAX_CALLDATA {
+                    config.assign_fixed_row(&mut region, offset, None, true)?;
+                    calldata_tx_id_is_zero_chip.assign(&mut region, offset, Some(F::zero()))?;
                     config.assign_row(
                         &mut region,
                         offset,
                         0, // tx_id
-                        TxFieldTag::CallData,
+                        F::from(TxContextFieldTag::CallData as u64),
                         0,
                         F::zero(),
                     )?;
@@ -391,6 +919,99 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
     }
 }
 
+impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
+    for TxCircuit<F, MAX_TXS, MAX_CALLDATA>
+{
+    type Config = (TxCircuitConfig<F>, KeccakConfig<F>, [Column<Advice>; 3]);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            sign_verify: SignVerifyChip {
+                aux_generator: self.sign_verify.aux_generator,
+                window_size: self.sign_verify.window_size,
+                _marker: PhantomData,
+            },
+            size: self.size,
+            ..Default::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let tx_table = [(); 4].map(|_| meta.advice_column());
+        let block_table = [(); 3].map(|_| meta.advice_column());
+        let keccak_table = KeccakTable::construct(meta);
+
+        // This gate is used just to get the array of expressions from the power of
+        // randomness instance column, so that later on we don't need to query
+        // columns everywhere, and can pass the power of randomness array
+        // expression everywhere.  The gate itself doesn't add any constraints.
+        let power_of_randomness = {
+            let columns = [(); POW_RAND_SIZE].map(|_| meta.instance_column());
+            let mut power_of_randomness = None;
+
+            meta.create_gate("power of randomness", |meta| {
+                power_of_randomness =
+                    Some(columns.map(|column| meta.query_instance(column, Rotation::cur())));
+
+                [0.expr()]
+            });
+
+            power_of_randomness.unwrap()
+        };
+
+        let power_of_randomness_31 = array_init::array_init(|i| power_of_randomness[i].clone());
+        (
+            TxCircuitConfig::new(
+                meta,
+                power_of_randomness,
+                tx_table,
+                block_table,
+                keccak_table,
+            ),
+            KeccakConfig::configure(meta, power_of_randomness_31, keccak_table),
+            block_table,
+        )
+    }
+
+    fn synthesize(
+        &self,
+        (config, keccak_circuit, block_table): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        // The block table only contains the chain id, after an all-zero row
+        // that allows disabled lookups.
+        layouter.assign_region(
+            || "block table",
+            |mut region| {
+                let chain_id = [
+                    F::from(BlockContextFieldTag::ChainId as u64),
+                    F::zero(),
+                    rlc(Word::from(self.chain_id).to_le_bytes(), self.randomness),
+                ];
+                for (offset, row) in [[F::zero(); 3], chain_id].iter().enumerate() {
+                    for (column, value) in block_table.iter().zip(row) {
+                        region.assign_advice(
+                            || format!("block table row {}", offset),
+                            *column,
+                            offset,
+                            || Ok(*value),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+        keccak_circuit.assign(
+            &mut layouter,
+            self.size,
+            &self.keccak_inputs()?,
+            self.randomness,
+        )?;
+        self.synthesize_sub(&config, &mut layouter)
+    }
+}
+
 #[cfg(test)]
 mod tx_circuit_tests {
     use super::*;
@@ -420,11 +1041,7 @@ mod tx_circuit_tests {
             <Secp256k1Affine as CurveAffine>::CurveExt::random(&mut rng).to_affine();
 
         let randomness = F::random(&mut rng);
-        let mut instance: Vec<Vec<F>> = (1..POW_RAND_SIZE + 1)
-            .map(|exp| vec![randomness.pow(&[exp as u64, 0, 0, 0]); txs.len() * VERIF_HEIGHT])
-            .collect();
-        // SignVerifyChip -> ECDSAChip -> MainGate instance column
-        instance.push(vec![]);
+        let instance = TxCircuit::<F, MAX_TXS, MAX_CALLDATA>::instance(k, randomness);
         let circuit = TxCircuit::<F, MAX_TXS, MAX_CALLDATA> {
             sign_verify: SignVerifyChip {
                 aux_generator,
@@ -434,6 +1051,7 @@ mod tx_circuit_tests {
             randomness,
             txs,
             chain_id,
+            size: 1 << k,
         };
 
         let prover = match MockProver::run(k, &circuit, instance) {
@@ -6,6 +6,7 @@
 
 use crate::{
     evm_circuit::util::{not, RandomLinearCombination, Word},
+    keccak_circuit::KeccakTable,
     util::Expr,
 };
 use ecc::{EccConfig, GeneralEccChip};
@@ -56,11 +57,6 @@ pub struct SignVerifyChip<F: FieldExt, const MAX_VERIF: usize> {
     pub _marker: PhantomData<F>,
 }
 
-const KECCAK_IS_ENABLED: usize = 0;
-const KECCAK_INPUT_RLC: usize = 1;
-const KECCAK_INPUT_LEN: usize = 2;
-const KECCAK_OUTPUT_RLC: usize = 3;
-
 const NUMBER_OF_LIMBS: usize = 4;
 const BIT_LEN_LIMB: usize = 72;
 
@@ -127,15 +123,13 @@ pub(crate) struct SignVerifyConfig<F: FieldExt> {
     pk: [[Column<Advice>; 32]; 2],
     msg_hash: [Column<Advice>; 32],
     power_of_randomness: [Expression<F>; POW_RAND_SIZE],
-
-    // [is_enabled, input_rlc, input_len, output_rlc]
-    keccak_table: [Column<Advice>; 4],
 }
 
 impl<F: FieldExt> SignVerifyConfig<F> {
     pub(crate) fn new(
         meta: &mut ConstraintSystem<F>,
         power_of_randomness: [Expression<F>; POW_RAND_SIZE],
+        keccak_table: KeccakTable,
     ) -> Self {
         let q_enable = meta.complex_selector();
 
@@ -164,9 +158,6 @@ impl<F: FieldExt> SignVerifyConfig<F> {
         // is_not_padding == address != 0
         let is_not_padding = not::expr(address_is_zero.is_zero_expression.clone());
 
-        // lookup keccak table
-        let keccak_table = [(); 4].map(|_| meta.advice_column());
-
         // Ref. spec SignVerifyChip 1. Verify that keccak(pub_key_bytes) = pub_key_hash
         // by keccak table lookup, where pub_key_bytes is built from the pub_key
         // in the ecdsa_chip
@@ -177,13 +168,11 @@ impl<F: FieldExt> SignVerifyConfig<F> {
             let mut table_map = Vec::new();
 
             // Column 0: is_enabled
-            let keccak_is_enabled =
-                meta.query_advice(keccak_table[KECCAK_IS_ENABLED], Rotation::cur());
+            let keccak_is_enabled = meta.query_advice(keccak_table.is_enabled, Rotation::cur());
             table_map.push((selector.clone(), keccak_is_enabled));
 
             // Column 1: input_rlc (pk_rlc)
-            let keccak_input_rlc =
-                meta.query_advice(keccak_table[KECCAK_INPUT_RLC], Rotation::cur());
+            let keccak_input_rlc = meta.query_advice(keccak_table.input_rlc, Rotation::cur());
             let pk_le: [Expression<F>; 64] = pk
                 .map(|coord| coord.map(|c| meta.query_advice(c, Rotation::cur())))
                 .iter()
@@ -198,14 +187,15 @@ impl<F: FieldExt> SignVerifyConfig<F> {
             table_map.push((selector.clone() * pk_rlc, keccak_input_rlc));
 
             // Column 2: input_len (64)
-            let keccak_input_len =
-                meta.query_advice(keccak_table[KECCAK_INPUT_LEN], Rotation::cur());
+            let keccak_input_len = meta.query_advice(keccak_table.input_len, Rotation::cur());
             table_map.push((selector.clone() * 64usize.expr(), keccak_input_len));
 
             // Column 3: output_rlc (pk_hash_rlc)
-            let keccak_output_rlc =
-                meta.query_advice(keccak_table[KECCAK_OUTPUT_RLC], Rotation::cur());
-            let pk_hash = pk_hash.map(|c| meta.query_advice(c, Rotation::cur()));
+            // The keccak table encodes the hash as a word, so its RLC is over
+            // the little endian bytes.
+            let keccak_output_rlc = meta.query_advice(keccak_table.output_rlc, Rotation::cur());
+            let mut pk_hash = pk_hash.map(|c| meta.query_advice(c, Rotation::cur()));
+            pk_hash.reverse();
             let pk_hash_rlc =
                 RandomLinearCombination::random_linear_combine_expr(pk_hash, &power_of_randomness);
             table_map.push((selector * pk_hash_rlc, keccak_output_rlc));
@@ -260,16 +250,10 @@ impl<F: FieldExt> SignVerifyConfig<F> {
             pk,
             msg_hash,
             power_of_randomness,
-            keccak_table,
         }
     }
 }
 
-pub(crate) struct KeccakAux {
-    input: [u8; 64],
-    output: [u8; 32],
-}
-
 impl<F: FieldExt> SignVerifyConfig<F> {
     pub(crate) fn load_range(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
         let bit_len_lookup = BIT_LEN_LIMB / NUMBER_OF_LOOKUP_LIMBS;
@@ -280,67 +264,6 @@ impl<F: FieldExt> SignVerifyConfig<F> {
         Ok(())
     }
 
-    fn keccak_assign_row(
-        &self,
-        region: &mut Region<'_, F>,
-        offset: usize,
-        is_enabled: F,
-        input_rlc: F,
-        input_len: usize,
-        output_rlc: F,
-    ) -> Result<(), Error> {
-        for (name, column, value) in &[
-            ("is_enabled", self.keccak_table[0], is_enabled),
-            ("input_rlc", self.keccak_table[1], input_rlc),
-            ("input_len", self.keccak_table[2], F::from(input_len as u64)),
-            ("output_rlc", self.keccak_table[3], output_rlc),
-        ] {
-            region.assign_advice(
-                || format!("Keccak table assign {} {}", name, offset),
-                *column,
-                offset,
-                || Ok(*value),
-            )?;
-        }
-        Ok(())
-    }
-
-    pub(crate) fn load_keccak(
-        &self,
-        layouter: &mut impl Layouter<F>,
-        auxs: Vec<KeccakAux>,
-        randomness: F,
-    ) -> Result<(), Error> {
-        layouter.assign_region(
-            || "keccak table",
-            |mut region| {
-                let mut offset = 0;
-
-                // All zero row to allow simulating a disabled lookup.
-                self.keccak_assign_row(&mut region, offset, F::zero(), F::zero(), 0, F::zero())?;
-                offset += 1;
-
-                for aux in &auxs {
-                    let KeccakAux { input, output } = aux;
-                    let input_rlc =
-                        RandomLinearCombination::random_linear_combine(*input, randomness);
-                    let output_rlc = Word::random_linear_combine(*output, randomness);
-                    self.keccak_assign_row(
-                        &mut region,
-                        offset,
-                        F::one(),
-                        input_rlc,
-                        input.len(),
-                        output_rlc,
-                    )?;
-                    offset += 1;
-                }
-                Ok(())
-            },
-        )?;
-        Ok(())
-    }
-
     pub(crate) fn ecc_chip_config(&self) -> EccConfig {
         EccConfig::new(self.range_config.clone(), self.main_gate_config.clone())
     }
@@ -504,7 +427,7 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
         address_is_zero_chip: &IsZeroChip<F>,
         sign_data: Option<&SignData>,
         assigned_ecdsa: &AssignedECDSA<F>,
-    ) -> Result<(AssignedSignatureVerify<F>, KeccakAux), Error> {
+    ) -> Result<AssignedSignatureVerify<F>, Error> {
         let (padding, sign_data) = match sign_data {
             Some(sign_data) => (false, sign_data.clone()),
             None => (true, SignData::default()),
@@ -556,19 +479,8 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
         )?;
 
         // Assign pk
-        let pk_coord =
-            Option::<Coordinates<_>>::from(pk.coordinates()).expect("point is the identity");
-        let mut pk_x_le = [0u8; 32];
-        let mut pk_y_le = [0u8; 32];
-        pk_coord
-            .x()
-            .write(&mut Cursor::new(&mut pk_x_le[..]))
-            .expect("cannot write bytes to array");
-        pk_coord
-            .y()
-            .write(&mut Cursor::new(&mut pk_y_le[..]))
-            .expect("cannot write bytes to array");
-        for (i, byte) in pk_x_le.iter().enumerate() {
+        let pk_le = pk_bytes_le(&pk);
+        for (i, byte) in pk_le[..32].iter().enumerate() {
             region.assign_advice(
                 || format!("pk x byte {}", i),
                 config.pk[0][i],
@@ -576,7 +488,7 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
                 || Ok(F::from(*byte as u64)),
             )?;
         }
-        for (i, byte) in pk_y_le.iter().enumerate() {
+        for (i, byte) in pk_le[32..].iter().enumerate() {
             region.assign_advice(
                 || format!("pk y byte {}", i),
                 config.pk[1][i],
@@ -585,9 +497,6 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
             )?;
         }
 
-        let mut pk_le = [0u8; 64];
-        pk_le[..32].copy_from_slice(&pk_x_le);
-        pk_le[32..].copy_from_slice(&pk_y_le);
         let pk_be = pk_bytes_swap_endianness(&pk_le);
         let mut keccak = Keccak::default();
         keccak.update(&pk_be);
@@ -621,16 +530,10 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
             )?;
         }
 
-        Ok((
-            AssignedSignatureVerify {
-                address: address_assigned,
-                msg_hash_rlc: msg_hash_rlc_assigned,
-            },
-            KeccakAux {
-                input: pk_be,
-                output: pk_hash.try_into().expect("vec to array of size 32"),
-            },
-        ))
+        Ok(AssignedSignatureVerify {
+            address: address_assigned,
+            msg_hash_rlc: msg_hash_rlc_assigned,
+        })
     }
 
     pub(crate) fn assign(
@@ -666,7 +569,6 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
         let address_is_zero_chip = IsZeroChip::construct(config.address_is_zero.clone());
 
         let mut assigned_ecdsas = Vec::new();
-        let mut keccak_auxs = Vec::new();
 
         let chips = ChipsRef {
             main_gate: &main_gate,
@@ -680,7 +582,6 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
             || "ecdsa chip verification",
             |mut region| {
                 assigned_ecdsas.clear();
-                keccak_auxs.clear();
                 let offset = &mut 0;
                 let mut ctx = RegionCtx::new(&mut region, offset);
                 for i in 0..MAX_VERIF {
@@ -705,7 +606,7 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
                 // for i in 0..MAX_VERIF
                 for (i, assigned_ecdsa) in assigned_ecdsas.iter().enumerate() {
                     let sign_data = signatures.get(i); // None when padding (enabled when address == 0)
-                    let (assigned_sig_verif, keccak_aux) = self.assign_signature_verify(
+                    let assigned_sig_verif = self.assign_signature_verify(
                         config,
                         &mut region,
                         i, // offset
@@ -714,9 +615,6 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
                         sign_data,
                         assigned_ecdsa,
                     )?;
-                    if i < signatures.len() {
-                        keccak_auxs.push(keccak_aux);
-                    }
                     assigned_sig_verifs.push(assigned_sig_verif);
                 }
 
@@ -724,7 +622,6 @@ impl<F: FieldExt, const MAX_VERIF: usize> SignVerifyChip<F, MAX_VERIF> {
             },
         )?;
 
-        config.load_keccak(layouter, keccak_auxs, randomness)?;
         config.load_range(layouter)?;
 
         Ok(assigned_sig_verifs)
@@ -792,6 +689,31 @@ impl Default for SignData {
     }
 }
 
+/// Return the serialized public key as the little endian bytes of the x
+/// coordinate followed by the little endian bytes of the y coordinate.
+fn pk_bytes_le(pk: &Secp256k1Affine) -> [u8; 64] {
+    let pk_coord = Option::<Coordinates<_>>::from(pk.coordinates()).expect("point is the identity");
+    let mut pk_le = [0u8; 64];
+    pk_coord
+        .x()
+        .write(&mut Cursor::new(&mut pk_le[..32]))
+        .expect("cannot write bytes to array");
+    pk_coord
+        .y()
+        .write(&mut Cursor::new(&mut pk_le[32..]))
+        .expect("cannot write bytes to array");
+    pk_le
+}
+
+/// Return the inputs to the keccak table required to verify `signatures`,
+/// which are the serialized public keys in big endian.
+pub(crate) fn keccak_inputs_sign_verify(signatures: &[SignData]) -> Vec<Vec<u8>> {
+    signatures
+        .iter()
+        .map(|sign_data| pk_bytes_swap_endianness(&pk_bytes_le(&sign_data.pk)).to_vec())
+        .collect()
+}
+
 fn pub_key_hash_to_address<F: FieldExt>(pk_hash: &[u8]) -> F {
     pk_hash[32 - 20..]
         .iter()
@@ -812,6 +734,7 @@ mod sign_verify_tests {
     #[derive(Clone, Debug)]
     struct TestCircuitSignVerifyConfig<F: FieldExt> {
         sign_verify: SignVerifyConfig<F>,
+        keccak_table: KeccakTable,
     }
 
     impl<F: FieldExt> TestCircuitSignVerifyConfig<F> {
@@ -834,8 +757,12 @@ mod sign_verify_tests {
                 power_of_randomness.unwrap()
             };
 
-            let sign_verify = SignVerifyConfig::new(meta, power_of_randomness);
-            TestCircuitSignVerifyConfig { sign_verify }
+            let keccak_table = KeccakTable::construct(meta);
+            let sign_verify = SignVerifyConfig::new(meta, power_of_randomness, keccak_table);
+            TestCircuitSignVerifyConfig {
+                sign_verify,
+                keccak_table,
+            }
         }
     }
 
@@ -869,6 +796,11 @@ mod sign_verify_tests {
                 self.randomness,
                 &self.signatures,
             )?;
+            config.keccak_table.dev_load(
+                &mut layouter,
+                &keccak_inputs_sign_verify(&self.signatures),
+                self.randomness,
+            )?;
             Ok(())
         }
     }